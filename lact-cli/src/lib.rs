@@ -1,6 +1,28 @@
-use anyhow::{Context, Result};
+use amdgpu_sysfs::gpu_handle::{PerformanceLevel, PowerLevelKind};
+use anyhow::{anyhow, Context, Result};
 use lact_client::DaemonClient;
-use lact_schema::args::{CliArgs, CliCommand};
+use lact_schema::{
+    args::{
+        AlertCommand, ApuLimitArg, CliArgs, CliCommand, CompareProfilesCommand, CurveInputArg,
+        MemoryStateCommand, PmfwCommand, PowerProfileCommand, PowerStateCommand, PowerStateKind,
+        ProfileCommand, RuntimePmArg, UndervoltCommand,
+    },
+    request::{ConfirmCommand, ProfileBase, SetClocksCommand},
+    Alert, AlertAction, ApuLimitKind, FanControlMode, FanCurveInput, FanCurveMap, FanOptions,
+    FanSpeedUnit, PmfwInfo, PmfwOptions, PowerState, ProfileComparisonOptions,
+    ProfileComparisonState, ProfileRule, ProfileSchedule, ProfileStatsSummary, UndervoltOptions,
+    UndervoltState, FAN_STALL_SENSOR,
+};
+use std::{
+    fs,
+    io::{self, IsTerminal, Write},
+    path::Path,
+    sync::mpsc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const PROCESS_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 pub fn run(args: CliArgs) -> Result<()> {
     let rt = tokio::runtime::Builder::new_current_thread()
@@ -8,12 +30,70 @@ pub fn run(args: CliArgs) -> Result<()> {
         .build()
         .unwrap();
     rt.block_on(async move {
-        let client = DaemonClient::connect().await?;
+        let client = match &args.socket_path {
+            Some(path) => DaemonClient::connect_path(path).await?,
+            None => DaemonClient::connect().await?,
+        };
+        client.check_protocol_version().await?;
 
         match args.subcommand {
             CliCommand::ListGpus => list_gpus(&args, &client).await,
             CliCommand::Info => info(&args, &client).await,
+            CliCommand::Stats => stats(&args, &client).await,
+            CliCommand::Processes => processes(&args, &client).await,
+            CliCommand::Sensors => sensors(&client).await,
+            CliCommand::PowerCap { watts } => power_cap(&args, &client, watts).await,
+            CliCommand::AppPowerCap { ref process, watts } => {
+                app_power_cap(&args, &client, process, watts).await
+            }
+            CliCommand::PcieMinState { state, reset } => {
+                pcie_min_state(&args, &client, state, reset).await
+            }
+            CliCommand::VramMinState { state, reset } => {
+                vram_min_state(&args, &client, state, reset).await
+            }
+            CliCommand::ApuLimit { kind, value } => apu_limit(&args, &client, kind, value).await,
+            CliCommand::ThermalTarget { temperature } => {
+                thermal_target(&args, &client, temperature).await
+            }
+            CliCommand::RuntimePm { mode } => runtime_pm(&args, &client, mode).await,
+            CliCommand::FanCurve {
+                ref path,
+                fan,
+                preview,
+                rpm,
+                input,
+                power_weight,
+            } => {
+                let input = match input {
+                    CurveInputArg::Temperature => FanCurveInput::Temperature,
+                    CurveInputArg::Power => FanCurveInput::Power,
+                    CurveInputArg::Composite => FanCurveInput::Composite {
+                        power_weight: power_weight.unwrap_or_default(),
+                    },
+                };
+                fan_curve(&args, &client, path, fan, preview, rpm, input).await
+            }
+            CliCommand::ResetFan => reset_fan(&args, &client).await,
+            CliCommand::ResetClocks => reset_clocks(&args, &client).await,
+            CliCommand::ResetPowerCap => reset_power_cap(&args, &client).await,
+            CliCommand::Undo => undo(&args, &client, false).await,
+            CliCommand::Redo => undo(&args, &client, true).await,
             CliCommand::Snapshot => snapshot(&client).await,
+            CliCommand::Profile(ref command) => profile(command, &client).await,
+            CliCommand::ExportState => export_state(&client).await,
+            CliCommand::ImportState { ref path } => import_state(path, &client).await,
+            CliCommand::DumpVbios { ref path } => dump_vbios(&args, &client, path).await,
+            CliCommand::ResetGpu => reset_gpu(&args, &client).await,
+            CliCommand::PowerProfile(ref command) => power_profile(&args, &client, command).await,
+            CliCommand::MemoryState(ref command) => memory_state(&args, &client, command).await,
+            CliCommand::PowerState(ref command) => power_state(&args, &client, command).await,
+            CliCommand::Pmfw(ref command) => pmfw(&args, &client, command).await,
+            CliCommand::Alert(ref command) => alert(&args, &client, command).await,
+            CliCommand::Undervolt(ref command) => undervolt(&args, &client, command).await,
+            CliCommand::CompareProfiles(ref command) => {
+                compare_profiles(&args, &client, command).await
+            }
         }
     })
 }
@@ -21,9 +101,9 @@ pub fn run(args: CliArgs) -> Result<()> {
 async fn list_gpus(_: &CliArgs, client: &DaemonClient) -> Result<()> {
     let buffer = client.list_devices().await?;
     for entry in buffer.inner()? {
-        let id = entry.id;
-        if let Some(name) = entry.name {
-            println!("{id} ({name})");
+        let id = &entry.id;
+        if entry.name.is_some() || entry.label.is_some() {
+            println!("{id} ({entry})");
         } else {
             println!("{id}");
         }
@@ -52,6 +132,1133 @@ async fn info(args: &CliArgs, client: &DaemonClient) -> Result<()> {
     Ok(())
 }
 
+async fn stats(args: &CliArgs, client: &DaemonClient) -> Result<()> {
+    for id in extract_gpu_ids(args, client).await {
+        let stats = client.get_device_stats(&id).await?.inner()?;
+        println!("GPU {id}:");
+
+        let mut temps: Vec<_> = stats.temps.iter().collect();
+        temps.sort_by_key(|(name, _)| name.as_str());
+        for (name, temp) in temps {
+            if let Some(current) = temp.current {
+                println!("  Temperature ({name}): {current}°C");
+            }
+        }
+        if let Some(rpm) = stats.fan.speed_current {
+            println!("  Fan speed: {rpm} RPM");
+        }
+        if let Some(pwm) = stats.fan.pwm_current {
+            println!(
+                "  Fan PWM: {:.0}%",
+                f64::from(pwm) / f64::from(u8::MAX) * 100.0
+            );
+        }
+        if let Some(power) = stats.power.current.or(stats.power.average) {
+            match stats.power.cap_current {
+                Some(cap) => println!("  Power usage: {power:.1}/{cap:.0} W"),
+                None => println!("  Power usage: {power:.1} W"),
+            }
+            if let Some(cap) = stats.power.app_power_cap {
+                println!("  Power cap of a running application: {cap:.0} W");
+            }
+        }
+        if let Some(clock) = stats.clockspeed.gpu_clockspeed {
+            println!("  GPU clock: {clock} MHz");
+        }
+        if let Some(clock) = stats.clockspeed.vram_clockspeed {
+            println!("  VRAM clock: {clock} MHz");
+        }
+        if let Some(voltage) = stats.voltage.gpu {
+            println!("  GPU voltage: {voltage} mV");
+        }
+        if let Some(voltage) = stats.voltage.northbridge {
+            println!("  Northbridge voltage: {voltage} mV");
+        }
+        if let (Some(used), Some(total)) = (stats.vram.used, stats.vram.total) {
+            println!(
+                "  VRAM usage: {}/{} MiB",
+                used / 1024 / 1024,
+                total / 1024 / 1024
+            );
+        }
+        if let (Some(used), Some(total)) = (stats.vram.visible_used, stats.vram.visible_total) {
+            println!(
+                "  CPU visible VRAM usage: {}/{} MiB",
+                used / 1024 / 1024,
+                total / 1024 / 1024
+            );
+        }
+        if let (Some(used), Some(total)) = (stats.vram.gtt_used, stats.vram.gtt_total) {
+            println!(
+                "  GTT usage: {}/{} MiB",
+                used / 1024 / 1024,
+                total / 1024 / 1024
+            );
+        }
+        if let Some(busy) = stats.busy_percent {
+            println!("  GPU usage: {busy}%");
+        }
+        for (engine, usage) in &stats.engine_usage {
+            println!("  Engine usage ({engine}): {usage:.0}%");
+        }
+        if let Some(status) = stats.throttle_status {
+            if status.is_active() {
+                println!("  Throttling: {}", status.active_kinds().join(", "));
+            } else {
+                println!("  Throttling: no");
+            }
+        }
+        if let Some(events) = stats.throttle_events {
+            println!(
+                "  Throttling events: power limit {}, thermal {}, current limit {}, other {}",
+                events.power, events.thermal, events.current, events.other
+            );
+        }
+        if let Some(link) = &stats.link_info {
+            if let (Some(gen), Some(width)) = (link.current_generation(), &link.current_width) {
+                print!("  PCIe link: gen {gen} x{width}");
+                if let (Some(max_gen), Some(max_width)) = (link.max_generation(), &link.max_width) {
+                    print!(" (max gen {max_gen} x{max_width})");
+                }
+                println!();
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn processes(args: &CliArgs, client: &DaemonClient) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+
+    // Engine usage is calculated between two samples, so the first one only serves as a reference
+    client.get_processes(&id).await?.inner()?;
+    tokio::time::sleep(PROCESS_SAMPLE_INTERVAL).await;
+    let processes = client.get_processes(&id).await?.inner()?;
+
+    if processes.is_empty() {
+        println!("No processes are using this GPU");
+        return Ok(());
+    }
+
+    println!(
+        "{:>8}  {:<20}  {:>10}  {:>10}  Engines",
+        "PID", "Name", "VRAM", "GTT"
+    );
+    for process in processes {
+        let engines: Vec<String> = process
+            .engine_usage
+            .iter()
+            .filter(|(_, usage)| **usage >= 0.1)
+            .map(|(engine, usage)| format!("{engine} {usage:.1}%"))
+            .collect();
+        println!(
+            "{:>8}  {:<20}  {:>10}  {:>10}  {}",
+            process.pid,
+            process.name,
+            format_mib(process.vram),
+            format_mib(process.gtt),
+            engines.join(", ")
+        );
+    }
+    Ok(())
+}
+
+async fn sensors(client: &DaemonClient) -> Result<()> {
+    let chips = client.get_sensor_chips().await?.inner()?;
+    if chips.is_empty() {
+        println!("No sensor chips found, add their names to `sensor_chips` in the daemon config");
+        return Ok(());
+    }
+
+    for chip in chips {
+        println!("{} ({})", chip.name, chip.hwmon);
+        for (label, temperature) in &chip.temperatures {
+            println!("  {label}: {temperature:.1}°C");
+        }
+        for (label, rpm) in &chip.fans {
+            println!("  {label}: {rpm} RPM");
+        }
+    }
+    Ok(())
+}
+
+fn format_mib(bytes: Option<u64>) -> String {
+    bytes.map_or_else(
+        || "-".to_owned(),
+        |bytes| format!("{} MiB", bytes / 1024 / 1024),
+    )
+}
+
+async fn power_cap(args: &CliArgs, client: &DaemonClient, watts: Option<f64>) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+    ensure_confirmable(args.yes)?;
+    let delay = client.set_power_cap(&id, watts).await?;
+    keep_change(args, client, delay).await?;
+
+    match watts {
+        Some(watts) => println!("Set power cap to {watts} W"),
+        None => println!("Reset power cap to default"),
+    }
+    Ok(())
+}
+
+async fn pcie_min_state(
+    args: &CliArgs,
+    client: &DaemonClient,
+    state: Option<u8>,
+    reset: bool,
+) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+
+    if state.is_none() && !reset {
+        let states = client.get_pcie_power_states(&id).await?.inner()?;
+        if states.is_empty() {
+            println!("This GPU does not support configuring PCIe power states");
+        }
+        for (index, speed) in states.iter().enumerate() {
+            println!("{index}: {speed}");
+        }
+        return Ok(());
+    }
+
+    ensure_confirmable(args.yes)?;
+    let delay = client.set_min_pcie_state(&id, state).await?;
+    keep_change(args, client, delay).await?;
+
+    match state {
+        Some(state) => println!("Set minimum PCIe power state to {state}"),
+        None => println!("Enabled all PCIe power states"),
+    }
+    Ok(())
+}
+
+async fn vram_min_state(
+    args: &CliArgs,
+    client: &DaemonClient,
+    state: Option<u8>,
+    reset: bool,
+) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+
+    if state.is_none() && !reset {
+        let states = client.get_power_states(&id).await?.inner()?.vram;
+        if states.is_empty() {
+            println!("This GPU does not support configuring VRAM power states");
+        }
+        print_power_states(&states);
+        return Ok(());
+    }
+
+    ensure_confirmable(args.yes)?;
+    let delay = client.set_min_vram_state(&id, state).await?;
+    keep_change(args, client, delay).await?;
+
+    match state {
+        Some(state) => println!("Set minimum VRAM power state to {state}"),
+        None => println!("Enabled all VRAM power states"),
+    }
+    Ok(())
+}
+
+async fn apu_limit(
+    args: &CliArgs,
+    client: &DaemonClient,
+    kind: Option<ApuLimitArg>,
+    value: Option<f64>,
+) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+
+    let Some(kind) = kind else {
+        let limits = client
+            .get_device_capabilities(&id)
+            .await?
+            .inner()?
+            .apu_limits;
+        if limits.is_empty() {
+            println!("This GPU does not have APU limits");
+        }
+        for kind in limits {
+            println!("{kind:?} ({})", kind.unit());
+        }
+        return Ok(());
+    };
+    let kind = match kind {
+        ApuLimitArg::Stapm => ApuLimitKind::Stapm,
+        ApuLimitArg::Fast => ApuLimitKind::Fast,
+        ApuLimitArg::Slow => ApuLimitKind::Slow,
+        ApuLimitArg::Temperature => ApuLimitKind::Temperature,
+    };
+
+    ensure_confirmable(args.yes)?;
+    let delay = client.set_apu_limit(&id, kind, value).await?;
+    keep_change(args, client, delay).await?;
+
+    match value {
+        Some(value) => println!("Set the {kind:?} limit to {value} {}", kind.unit()),
+        None => println!("Reset the {kind:?} limit to default"),
+    }
+    Ok(())
+}
+
+async fn app_power_cap(
+    args: &CliArgs,
+    client: &DaemonClient,
+    process: &str,
+    watts: Option<f64>,
+) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+    ensure_confirmable(args.yes)?;
+    let delay = client
+        .set_app_power_cap(&id, process.to_owned(), watts)
+        .await?;
+    keep_change(args, client, delay).await?;
+
+    match watts {
+        Some(watts) => println!("Set power cap to {watts} W while {process} is running"),
+        None => println!("Removed power cap override of {process}"),
+    }
+    Ok(())
+}
+
+async fn thermal_target(
+    args: &CliArgs,
+    client: &DaemonClient,
+    temperature: Option<f32>,
+) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+    ensure_confirmable(args.yes)?;
+    let delay = client.set_thermal_target(&id, temperature).await?;
+    keep_change(args, client, delay).await?;
+
+    match temperature {
+        Some(temperature) => println!("Set thermal target to {temperature}°C"),
+        None => println!("Disabled thermal target"),
+    }
+    Ok(())
+}
+
+async fn runtime_pm(
+    args: &CliArgs,
+    client: &DaemonClient,
+    mode: Option<RuntimePmArg>,
+) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+
+    let Some(mode) = mode else {
+        let stats = client.get_device_stats(&id).await?.inner()?;
+        let Some(runtime_pm) = stats.runtime_pm else {
+            println!("This GPU does not support runtime power management");
+            return Ok(());
+        };
+
+        println!("Status: {}", runtime_pm.status);
+        println!(
+            "Powering off while unused: {}",
+            if runtime_pm.auto {
+                "allowed"
+            } else {
+                "disabled"
+            }
+        );
+        if let (Some(suspended), Some(active)) =
+            (runtime_pm.suspended_time_ms, runtime_pm.active_time_ms)
+        {
+            let total = (suspended + active).max(1);
+            println!(
+                "Time powered off since boot: {:.0}%",
+                suspended as f64 / total as f64 * 100.0
+            );
+        }
+        if runtime_pm.processes.is_empty() {
+            println!("Used by: no processes");
+        } else {
+            println!("Used by: {}", runtime_pm.processes.join(", "));
+        }
+        if let Some(wake) = runtime_pm.last_wake {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_millis() as i64);
+            let seconds = (now - wake.timestamp).max(0) / 1000;
+            if wake.processes.is_empty() {
+                println!("Last woke up {seconds}s ago, without any process using it");
+            } else {
+                println!(
+                    "Last woke up {seconds}s ago, used by {}",
+                    wake.processes.join(", ")
+                );
+            }
+        }
+        return Ok(());
+    };
+
+    let auto = match mode {
+        RuntimePmArg::Auto => Some(true),
+        RuntimePmArg::On => Some(false),
+        RuntimePmArg::System => None,
+    };
+    ensure_confirmable(args.yes)?;
+    let delay = client.set_runtime_pm(&id, auto).await?;
+    keep_change(args, client, delay).await?;
+
+    match auto {
+        Some(true) => println!("The GPU is powered off while unused"),
+        Some(false) => println!("The GPU is kept powered on"),
+        None => println!("Runtime power management is left to the system"),
+    }
+    Ok(())
+}
+
+async fn memory_state(
+    args: &CliArgs,
+    client: &DaemonClient,
+    command: &MemoryStateCommand,
+) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+
+    let delay = match *command {
+        MemoryStateCommand::List => {
+            let states = client.get_memory_states(&id).await?.inner()?;
+            if states.is_empty() {
+                println!("This GPU does not support configuring memory states");
+            }
+            for state in states {
+                println!(
+                    "{}: {} MHz, {} mV",
+                    state.index, state.clockspeed, state.voltage
+                );
+            }
+            return Ok(());
+        }
+        MemoryStateCommand::Set {
+            index,
+            clockspeed,
+            voltage,
+        } => {
+            ensure_confirmable(args.yes)?;
+            client
+                .set_clocks_value(
+                    &id,
+                    SetClocksCommand::MemoryState {
+                        index,
+                        clockspeed,
+                        voltage,
+                    },
+                )
+                .await?
+        }
+        MemoryStateCommand::Lock { index } => {
+            ensure_confirmable(args.yes)?;
+            client.lock_memory_state(&id, Some(index)).await?
+        }
+        MemoryStateCommand::Unlock => {
+            ensure_confirmable(args.yes)?;
+            client.lock_memory_state(&id, None).await?
+        }
+    };
+    keep_change(args, client, delay).await?;
+
+    match *command {
+        MemoryStateCommand::Set {
+            index,
+            clockspeed,
+            voltage,
+        } => println!("Set memory state {index} to {clockspeed} MHz at {voltage} mV"),
+        MemoryStateCommand::Lock { index } => println!("Locked memory to state {index}"),
+        _ => println!("Enabled all memory states"),
+    }
+    Ok(())
+}
+
+async fn power_state(
+    args: &CliArgs,
+    client: &DaemonClient,
+    command: &PowerStateCommand,
+) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+
+    let delay = match command {
+        PowerStateCommand::List => {
+            let states = client.get_power_states(&id).await?.inner()?;
+            if states.is_empty() {
+                println!("This GPU does not report any power states");
+            }
+            for (name, states) in [("GPU clock", &states.core), ("VRAM clock", &states.vram)] {
+                if !states.is_empty() {
+                    println!("{name}:");
+                    print_power_states(states);
+                }
+            }
+            return Ok(());
+        }
+        PowerStateCommand::Enable { kind, states } => {
+            let kind = match kind {
+                PowerStateKind::Core => PowerLevelKind::CoreClock,
+                PowerStateKind::Memory => PowerLevelKind::MemoryClock,
+            };
+            ensure_confirmable(args.yes)?;
+            client
+                .set_enabled_power_states(&id, kind, states.clone())
+                .await?
+        }
+        PowerStateCommand::Reset => {
+            ensure_confirmable(args.yes)?;
+            client
+                .set_performance_level(&id, PerformanceLevel::Auto)
+                .await?
+        }
+    };
+    keep_change(args, client, delay).await?;
+
+    match command {
+        PowerStateCommand::Enable { states, .. } => {
+            let states: Vec<String> = states.iter().map(u8::to_string).collect();
+            println!("Enabled power states {}", states.join(", "));
+        }
+        _ => println!("Enabled all power states"),
+    }
+    Ok(())
+}
+
+fn print_power_states(states: &[PowerState]) {
+    for (i, state) in states.iter().enumerate() {
+        let marker = if state.enabled { "*" } else { " " };
+        let index = state.index.map_or(i, usize::from);
+        match state.min_value {
+            Some(min_value) => {
+                println!("{marker} {index}: {min_value}-{} MHz", state.value);
+            }
+            None => println!("{marker} {index}: {} MHz", state.value),
+        }
+    }
+}
+
+async fn power_profile(
+    args: &CliArgs,
+    client: &DaemonClient,
+    command: &PowerProfileCommand,
+) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+    let table = client.get_device_power_profile_modes(&id).await?.inner()?;
+
+    let (index, custom_heuristics) = match command {
+        PowerProfileCommand::List => {
+            for (index, mode) in &table.modes {
+                let marker = if *index == table.active { "*" } else { " " };
+                println!("{marker} {index}: {}", mode.name);
+
+                for component in &mode.components {
+                    let values = table
+                        .value_names
+                        .iter()
+                        .zip(&component.values)
+                        .map(|(name, value)| match value {
+                            Some(value) => format!("{name}={value}"),
+                            None => format!("{name}=-"),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    match &component.clock_type {
+                        Some(clock_type) => println!("      {clock_type}: {values}"),
+                        None => println!("      {values}"),
+                    }
+                }
+            }
+            return Ok(());
+        }
+        PowerProfileCommand::Set { index } => (Some(*index), vec![]),
+        PowerProfileCommand::Custom { path } => {
+            let raw_heuristics =
+                fs::read_to_string(path).with_context(|| format!("Could not read {path:?}"))?;
+            let heuristics: Vec<Vec<Option<i32>>> =
+                serde_json::from_str(&raw_heuristics).context("Could not parse heuristics")?;
+            let custom_index = table
+                .modes
+                .iter()
+                .find(|(_, mode)| mode.is_custom())
+                .map(|(index, _)| *index)
+                .context("The GPU has no custom power profile mode")?;
+            (Some(custom_index), heuristics)
+        }
+        PowerProfileCommand::Reset => (None, vec![]),
+    };
+
+    ensure_confirmable(args.yes)?;
+    let delay = client
+        .set_power_profile_mode(&id, index, custom_heuristics)
+        .await?;
+    keep_change(args, client, delay).await?;
+
+    match index.and_then(|index| table.modes.get(&index)) {
+        Some(mode) => println!(
+            "Set power profile mode to {} (this switches the performance level to manual)",
+            mode.name
+        ),
+        None => println!("Reset power profile mode"),
+    }
+    Ok(())
+}
+
+async fn fan_curve(
+    args: &CliArgs,
+    client: &DaemonClient,
+    path: &Path,
+    fan: Option<u32>,
+    preview: bool,
+    rpm: bool,
+    input: FanCurveInput,
+) -> Result<()> {
+    if preview && input != FanCurveInput::Temperature {
+        return Err(anyhow!(
+            "Only temperature curves can be previewed, as the stats history has no power samples"
+        ));
+    }
+
+    let id = single_gpu_id(args, client).await?;
+    let raw_curve = fs::read_to_string(path).with_context(|| format!("Could not read {path:?}"))?;
+    let mut curve: FanCurveMap =
+        serde_json::from_str(&raw_curve).context("Could not parse fan curve")?;
+
+    // Curves are always sent as ratios, RPM values are converted with the speed range of the fan
+    let rpm_range = if rpm {
+        let stats = client.get_device_stats(&id).await?.inner()?;
+        let (min, max) = stats
+            .fan
+            .speed_min
+            .zip(stats.fan.speed_max)
+            .filter(|(min, max)| max > min)
+            .context("The fan speed range of this GPU is not known")?;
+        for (temperature, speed) in &mut curve {
+            let rpm = f64::from(*speed);
+            if !(f64::from(min)..=f64::from(max)).contains(&rpm) {
+                return Err(anyhow!(
+                    "{rpm} RPM at {temperature}{} is outside of the fan speed range of {min}-{max} RPM",
+                    input.unit()
+                ));
+            }
+            *speed = ((rpm - f64::from(min)) / f64::from(max - min)) as f32;
+        }
+        Some((min, max))
+    } else {
+        None
+    };
+
+    if preview {
+        let points = client.preview_fan_curve(&id, curve, None).await?;
+        if points.is_empty() {
+            println!("The stats history has no temperature samples yet");
+        }
+        // Times are shown relative to the latest sample
+        let latest = points.last().map_or(0, |point| point.timestamp);
+        for point in points {
+            let speed = match rpm_range {
+                Some((min, max)) => format!(
+                    "{:.0} RPM",
+                    f64::from(min) + f64::from(max - min) * f64::from(point.speed)
+                ),
+                None => format!("{:.0}%", point.speed * 100.0),
+            };
+            println!(
+                "{:>6}s  {:.0}°C  {speed}",
+                (point.timestamp - latest) / 1000,
+                point.temperature,
+            );
+        }
+        return Ok(());
+    }
+
+    let (curve, fan_curves) = match fan {
+        Some(channel) => (None, Some([(channel, curve)].into())),
+        None => (Some(curve), None),
+    };
+
+    ensure_confirmable(args.yes)?;
+    let delay = client
+        .set_fan_control(FanOptions {
+            id: &id,
+            enabled: true,
+            mode: Some(FanControlMode::Curve),
+            static_speed: None,
+            curve,
+            pmfw: PmfwOptions::default(),
+            spindown_delay_ms: None,
+            change_threshold: None,
+            max_ramp_rate: None,
+            temperature_sensors: None,
+            fan_curves,
+            speed_unit: Some(if rpm {
+                FanSpeedUnit::Rpm
+            } else {
+                FanSpeedUnit::Pwm
+            }),
+            curve_input: Some(input),
+        })
+        .await?;
+    keep_change(args, client, delay).await?;
+
+    println!("Applied fan curve from {}", path.display());
+    Ok(())
+}
+
+async fn reset_fan(args: &CliArgs, client: &DaemonClient) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+    ensure_confirmable(args.yes)?;
+    let delay = client.reset_fan_control(&id).await?.inner()?;
+    keep_change(args, client, delay).await?;
+
+    println!("Fan control reset to automatic");
+    Ok(())
+}
+
+async fn reset_clocks(args: &CliArgs, client: &DaemonClient) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+    ensure_confirmable(args.yes)?;
+    let delay = client.reset_clocks(&id).await?.inner()?;
+    keep_change(args, client, delay).await?;
+
+    println!("Clocks reset to stock");
+    Ok(())
+}
+
+async fn reset_power_cap(args: &CliArgs, client: &DaemonClient) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+    ensure_confirmable(args.yes)?;
+    let delay = client.reset_power_cap(&id).await?.inner()?;
+    keep_change(args, client, delay).await?;
+
+    println!("Power cap reset to default");
+    Ok(())
+}
+
+async fn undo(args: &CliArgs, client: &DaemonClient, redo: bool) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+    ensure_confirmable(args.yes)?;
+    let delay = if redo {
+        client.redo_last_change(&id).await?.inner()?
+    } else {
+        client.undo_last_change(&id).await?.inner()?
+    };
+    keep_change(args, client, delay).await?;
+
+    let history = client.get_config_history(&id).await?.inner()?;
+    if redo {
+        println!("Redid the last undone change");
+    } else {
+        println!("Undid the last change");
+    }
+    println!(
+        "{} more changes can be undone, {} can be redone",
+        history.undo_steps, history.redo_steps
+    );
+    Ok(())
+}
+
+async fn pmfw(args: &CliArgs, client: &DaemonClient, command: &PmfwCommand) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+
+    let delay = match *command {
+        PmfwCommand::Show => {
+            let info = client.get_device_stats(&id).await?.inner()?.fan.pmfw_info;
+            if info == PmfwInfo::default() {
+                println!("This GPU does not have firmware fan settings");
+            }
+
+            let settings = [
+                ("Acoustic limit (RPM)", info.acoustic_limit),
+                ("Acoustic target (RPM)", info.acoustic_target),
+                ("Target temperature (°C)", info.target_temp),
+                ("Minimum PWM (%)", info.minimum_pwm),
+                ("Zero RPM temperature (°C)", info.zero_rpm_temperature),
+            ];
+            for (name, setting) in settings {
+                if let Some(setting) = setting {
+                    match setting.allowed_range {
+                        Some((min, max)) => {
+                            println!("{name}: {} (allowed {min}-{max})", setting.current);
+                        }
+                        None => println!("{name}: {}", setting.current),
+                    }
+                }
+            }
+            if let Some(enabled) = info.zero_rpm_enable {
+                println!("Zero RPM: {}", if enabled { "enabled" } else { "disabled" });
+            }
+            return Ok(());
+        }
+        PmfwCommand::Set {
+            acoustic_limit,
+            acoustic_target,
+            target_temperature,
+            minimum_pwm,
+            zero_rpm,
+            zero_rpm_threshold,
+        } => {
+            let options = PmfwOptions {
+                acoustic_limit,
+                acoustic_target,
+                minimum_pwm,
+                target_temperature,
+                zero_rpm,
+                zero_rpm_threshold,
+            };
+            if options.is_empty() {
+                return Err(anyhow!("No settings to change were given"));
+            }
+            ensure_confirmable(args.yes)?;
+            client.set_pmfw_options(&id, options).await?
+        }
+        PmfwCommand::Reset => {
+            ensure_confirmable(args.yes)?;
+            client.reset_pmfw(&id).await?.inner()?
+        }
+    };
+    keep_change(args, client, delay).await?;
+    println!("Applied firmware fan settings");
+    Ok(())
+}
+
+async fn alert(args: &CliArgs, client: &DaemonClient, command: &AlertCommand) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+    let mut alerts = client.get_alerts(&id).await?.inner()?;
+
+    match command {
+        AlertCommand::List => {
+            if alerts.is_empty() {
+                println!("No alerts are set");
+            }
+            for (index, alert) in alerts.iter().enumerate() {
+                let actions: Vec<String> = alert.actions.iter().map(ToString::to_string).collect();
+                let unit = if alert.sensor == FAN_STALL_SENSOR {
+                    "%"
+                } else {
+                    "°C"
+                };
+                println!(
+                    "{index}: {} above {}{unit} for {}s: {}",
+                    alert.sensor,
+                    alert.threshold,
+                    alert.duration,
+                    actions.join(", ")
+                );
+            }
+            return Ok(());
+        }
+        AlertCommand::Add {
+            sensor,
+            threshold,
+            duration,
+            notify,
+            max_fans,
+            power_cap,
+        } => {
+            let mut actions = Vec::new();
+            if *notify {
+                actions.push(AlertAction::Notify);
+            }
+            if *max_fans {
+                actions.push(AlertAction::MaxFans);
+            }
+            if let Some(watts) = power_cap {
+                actions.push(AlertAction::PowerCap { watts: *watts });
+            }
+            if actions.is_empty() {
+                return Err(anyhow!(
+                    "No actions were given, use --notify, --max-fans or --power-cap"
+                ));
+            }
+
+            alerts.push(Alert {
+                sensor: sensor.clone(),
+                threshold: *threshold,
+                duration: *duration,
+                actions,
+            });
+        }
+        AlertCommand::Remove { index } => {
+            if *index >= alerts.len() {
+                return Err(anyhow!("Alert {index} does not exist"));
+            }
+            alerts.remove(*index);
+        }
+        AlertCommand::Clear => alerts.clear(),
+    }
+
+    ensure_confirmable(args.yes)?;
+    let delay = client.set_alerts(&id, alerts).await?;
+    keep_change(args, client, delay).await?;
+    println!("Updated alerts");
+    Ok(())
+}
+
+/// Changes are only kept after they were confirmed interactively,
+/// so a change that makes the system unusable gets reverted
+fn ensure_confirmable(yes: bool) -> Result<()> {
+    if !yes && !io::stdin().is_terminal() {
+        return Err(anyhow!(
+            "Changes need to be confirmed, pass --yes to keep them without asking"
+        ));
+    }
+    Ok(())
+}
+
+/// Keeps the pending change right away with `--yes`, otherwise asks whether to keep it.
+/// Fails when the change was reverted.
+async fn keep_change(args: &CliArgs, client: &DaemonClient, delay: u64) -> Result<()> {
+    if args.yes {
+        client
+            .confirm_pending_config(ConfirmCommand::Confirm)
+            .await?;
+        Ok(())
+    } else {
+        confirm_or_revert(client, delay).await
+    }
+}
+
+/// Asks whether to keep the pending change, which the daemon reverts by itself after `delay` seconds
+async fn confirm_or_revert(client: &DaemonClient, delay: u64) -> Result<()> {
+    print!("Keep the new settings? [y/N] (reverting in {delay} seconds) ");
+    io::stdout().flush()?;
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer).is_ok() {
+            let _ = tx.send(answer);
+        }
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(delay);
+    while Instant::now() < deadline {
+        if let Ok(answer) = rx.try_recv() {
+            let answer = answer.trim().to_lowercase();
+            if answer == "y" || answer == "yes" {
+                client
+                    .confirm_pending_config(ConfirmCommand::Confirm)
+                    .await?;
+                println!("Kept the new settings");
+            } else {
+                client
+                    .confirm_pending_config(ConfirmCommand::Revert)
+                    .await?;
+                return Err(anyhow!("The settings have been reverted"));
+            }
+            return Ok(());
+        }
+        tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+    }
+
+    println!();
+    Err(anyhow!(
+        "No confirmation received, the settings have been reverted"
+    ))
+}
+
+/// Settings are only changed on an explicitly selected GPU, unless there is just one
+async fn single_gpu_id(args: &CliArgs, client: &DaemonClient) -> Result<String> {
+    let mut ids = extract_gpu_ids(args, client).await;
+    match ids.len() {
+        0 => Err(anyhow!("No GPUs found")),
+        1 => Ok(ids.remove(0)),
+        _ => Err(anyhow!(
+            "Multiple GPUs are available, select one with --gpu-id"
+        )),
+    }
+}
+
+async fn undervolt(
+    args: &CliArgs,
+    client: &DaemonClient,
+    command: &UndervoltCommand,
+) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+
+    match command {
+        UndervoltCommand::Start {
+            command,
+            step,
+            min_offset,
+            duration,
+        } => {
+            let options = UndervoltOptions {
+                stress_command: command.clone(),
+                step: *step,
+                min_offset: *min_offset,
+                step_duration: *duration,
+            };
+            client.start_undervolt(&id, options).await?;
+            println!("Started searching, check the progress with `undervolt status`");
+        }
+        UndervoltCommand::Status => {
+            let status = client.get_undervolt_status(&id).await?.inner()?;
+            for line in &status.log {
+                println!("{line}");
+            }
+            match status.state {
+                UndervoltState::Idle => println!("Not started"),
+                UndervoltState::Running { offset } => println!("Testing {offset} mV"),
+                UndervoltState::Finished {
+                    stable_offset: Some(offset),
+                } => println!("Finished, applied the lowest stable offset of {offset} mV"),
+                UndervoltState::Finished {
+                    stable_offset: None,
+                } => {
+                    println!("Finished, no lower offset was stable");
+                }
+                UndervoltState::Cancelled => println!("Cancelled"),
+                UndervoltState::Failed { error } => println!("Failed: {error}"),
+            }
+        }
+        UndervoltCommand::Cancel => {
+            client.cancel_undervolt(&id).await?;
+            println!("Cancelling, the previous offset is restored once the stress test stops");
+        }
+    }
+
+    Ok(())
+}
+
+async fn compare_profiles(
+    args: &CliArgs,
+    client: &DaemonClient,
+    command: &CompareProfilesCommand,
+) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+
+    match command {
+        CompareProfilesCommand::Start {
+            first,
+            second,
+            duration,
+            settle_time,
+        } => {
+            let options = ProfileComparisonOptions {
+                profiles: [Some(first.clone()), second.clone()],
+                duration: *duration,
+                settle_time: *settle_time,
+            };
+            client.start_profile_comparison(&id, options).await?;
+            println!("Started recording, check the progress with `compare-profiles status`");
+        }
+        CompareProfilesCommand::Status => {
+            let status = client.get_profile_comparison_status(&id).await?.inner()?;
+            match status.state {
+                ProfileComparisonState::Idle => println!("Not started"),
+                ProfileComparisonState::Running { profile, recording } => {
+                    let action = if recording { "Recording" } else { "Settling" };
+                    let name = status
+                        .profiles
+                        .get(profile)
+                        .and_then(Option::as_deref)
+                        .unwrap_or("Default");
+                    println!("{action} with {name}");
+                }
+                ProfileComparisonState::Finished => println!("Finished"),
+                ProfileComparisonState::Cancelled => println!("Cancelled"),
+                ProfileComparisonState::Failed { error } => println!("Failed: {error}"),
+            }
+            print_profile_comparison(&status.results);
+        }
+        CompareProfilesCommand::Cancel => {
+            client.cancel_profile_comparison(&id).await?;
+            println!("Cancelling, the previous settings are restored shortly");
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the averages of every profile, and the difference of the second profile to the first
+fn print_profile_comparison(results: &[ProfileStatsSummary]) {
+    if results.is_empty() {
+        return;
+    }
+
+    let format_value = |value: Option<f64>, unit: &str| {
+        value.map_or_else(|| "-".to_owned(), |value| format!("{value:.1}{unit}"))
+    };
+    let format_diff = |values: [Option<f64>; 2], unit: &str| match values {
+        [Some(first), Some(second)] => format!("{:+.1}{unit}", second - first),
+        _ => String::new(),
+    };
+
+    let mut rows: Vec<(String, Vec<Option<f64>>, &str)> = vec![
+        (
+            "Power".to_owned(),
+            results.iter().map(|summary| summary.power).collect(),
+            " W",
+        ),
+        (
+            "GPU clock".to_owned(),
+            results
+                .iter()
+                .map(|summary| summary.gpu_clockspeed)
+                .collect(),
+            " MHz",
+        ),
+        (
+            "VRAM clock".to_owned(),
+            results
+                .iter()
+                .map(|summary| summary.vram_clockspeed)
+                .collect(),
+            " MHz",
+        ),
+        (
+            "Fan speed".to_owned(),
+            results.iter().map(|summary| summary.fan_speed).collect(),
+            " RPM",
+        ),
+        (
+            "GPU usage".to_owned(),
+            results.iter().map(|summary| summary.busy_percent).collect(),
+            "%",
+        ),
+    ];
+    let mut sensors: Vec<&String> = results
+        .iter()
+        .flat_map(|summary| summary.temperatures.keys())
+        .collect();
+    sensors.sort_unstable();
+    sensors.dedup();
+    for sensor in sensors {
+        rows.push((
+            format!("Temperature ({sensor})"),
+            results
+                .iter()
+                .map(|summary| summary.temperatures.get(sensor).copied())
+                .collect(),
+            "°C",
+        ));
+    }
+
+    let header: Vec<String> = results
+        .iter()
+        .map(|summary| {
+            summary
+                .profile
+                .clone()
+                .unwrap_or_else(|| "Default".to_owned())
+        })
+        .collect();
+    println!(
+        "{:<24}{}",
+        "",
+        header
+            .iter()
+            .map(|name| format!("{name:>16}"))
+            .collect::<String>()
+    );
+    for (name, values, unit) in rows {
+        let mut line = format!("{name:<24}");
+        for value in &values {
+            line.push_str(&format!("{:>16}", format_value(*value, unit)));
+        }
+        if let [first, second] = values[..] {
+            line.push_str(&format!("{:>16}", format_diff([first, second], unit)));
+        }
+        println!("{line}");
+    }
+}
+
 async fn extract_gpu_ids(args: &CliArgs, client: &DaemonClient) -> Vec<String> {
     match args.gpu_id {
         Some(ref id) => vec![id.clone()],
@@ -73,3 +1280,192 @@ async fn snapshot(client: &DaemonClient) -> Result<()> {
     println!("Generated debug snapshot in {path}");
     Ok(())
 }
+
+fn format_schedule(schedule: &ProfileSchedule) -> String {
+    const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+    let mut text = format!("from {} to {}", schedule.start, schedule.end);
+    if !schedule.days.is_empty() {
+        let days: Vec<&str> = schedule
+            .days
+            .iter()
+            .filter_map(|day| DAYS.get(usize::from(*day).checked_sub(1)?).copied())
+            .collect();
+        text.push_str(&format!(" starting on {}", days.join(", ")));
+    }
+    text
+}
+
+async fn profile(command: &ProfileCommand, client: &DaemonClient) -> Result<()> {
+    match command {
+        ProfileCommand::List => {
+            let info = client.list_profiles().await?.inner()?;
+            let marker = |active: bool| if active { "*" } else { " " };
+
+            println!("{} Default", marker(info.current_profile.is_none()));
+            for name in &info.profiles {
+                let active = info.current_profile.as_ref() == Some(name);
+                match info.rules.get(name) {
+                    Some(rule) => match &rule.args {
+                        Some(args) => println!(
+                            "{} {name} (when running: {} {args})",
+                            marker(active),
+                            rule.process
+                        ),
+                        None => {
+                            println!("{} {name} (when running: {})", marker(active), rule.process);
+                        }
+                    },
+                    None => println!("{} {name}", marker(active)),
+                }
+                if let Some(schedule) = info.schedules.get(name) {
+                    println!("    scheduled {}", format_schedule(schedule));
+                }
+            }
+            if info.auto_switch {
+                println!("Automatic profile switching is enabled");
+                if let Some(fallback) = &info.fallback_profile {
+                    println!("Profile {fallback} is used when no rule or schedule matches");
+                }
+            }
+        }
+        ProfileCommand::Set { name } => {
+            client.set_profile(name.clone()).await?;
+            println!(
+                "Switched to profile {}",
+                name.as_deref().unwrap_or("Default")
+            );
+        }
+        ProfileCommand::Next => {
+            let info = client.list_profiles().await?.inner()?;
+            let next = match &info.current_profile {
+                None => info.profiles.first().cloned(),
+                Some(current) => info
+                    .profiles
+                    .iter()
+                    .position(|name| name == current)
+                    .and_then(|index| info.profiles.get(index + 1))
+                    .cloned(),
+            };
+
+            client.set_profile(next.clone()).await?;
+            println!(
+                "Switched to profile {}",
+                next.as_deref().unwrap_or("Default")
+            );
+        }
+        ProfileCommand::Save { name } => {
+            let info = client.list_profiles().await?.inner()?;
+            let base = match info.current_profile {
+                Some(current) => ProfileBase::Profile(current),
+                None => ProfileBase::Default,
+            };
+            client.create_profile(name.clone(), base).await?;
+            println!("Saved current settings as profile {name}");
+        }
+        ProfileCommand::Delete { name } => {
+            client.delete_profile(name.clone()).await?;
+            println!("Deleted profile {name}");
+        }
+        ProfileCommand::Rule {
+            name,
+            process,
+            args,
+        } => {
+            let rule = process.clone().map(|process| ProfileRule {
+                process,
+                args: args.clone(),
+            });
+            client.set_profile_rule(name.clone(), rule).await?;
+            match process {
+                Some(process) => println!("Profile {name} will be activated when {process} runs"),
+                None => println!("Removed the rule of profile {name}"),
+            }
+        }
+        ProfileCommand::Schedule {
+            name,
+            start,
+            end,
+            days,
+        } => {
+            let schedule = start
+                .clone()
+                .zip(end.clone())
+                .map(|(start, end)| ProfileSchedule {
+                    start,
+                    end,
+                    days: days.clone(),
+                });
+            client
+                .set_profile_schedule(name.clone(), schedule.clone())
+                .await?;
+            match schedule {
+                Some(schedule) => println!(
+                    "Profile {name} will be activated {}",
+                    format_schedule(&schedule)
+                ),
+                None => println!("Removed the schedule of profile {name}"),
+            }
+        }
+        ProfileCommand::Fallback { name } => {
+            client.set_fallback_profile(name.clone()).await?;
+            println!(
+                "Profile {} will be used when no rule or schedule matches",
+                name.as_deref().unwrap_or("Default")
+            );
+        }
+        ProfileCommand::AutoSwitch { enabled } => {
+            client.set_auto_switch_profiles(*enabled).await?;
+            if *enabled {
+                println!("Enabled automatic profile switching");
+            } else {
+                println!("Disabled automatic profile switching");
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn export_state(client: &DaemonClient) -> Result<()> {
+    let state = client.export_state().await?.inner()?;
+    println!("{state}");
+    Ok(())
+}
+
+async fn import_state(path: &Path, client: &DaemonClient) -> Result<()> {
+    let state = fs::read_to_string(path).with_context(|| format!("Could not read {path:?}"))?;
+    let result = client.import_state(state).await?.inner()?;
+    for (imported_id, id) in &result.matched_gpus {
+        println!("Imported settings for GPU {imported_id} to {id}, which is the same model");
+    }
+    for id in &result.skipped_gpus {
+        println!("Skipped settings for GPU {id}, as it is not present on this system");
+    }
+    println!("Imported state from {}", path.display());
+    Ok(())
+}
+
+async fn reset_gpu(args: &CliArgs, client: &DaemonClient) -> Result<()> {
+    if !args.yes {
+        return Err(anyhow!(
+            "Resetting the GPU interrupts all applications using it, pass --yes to confirm"
+        ));
+    }
+
+    let id = single_gpu_id(args, client).await?;
+    client.reset_gpu(&id, true).await?;
+    println!("GPU {id} was reset");
+    Ok(())
+}
+
+async fn dump_vbios(args: &CliArgs, client: &DaemonClient, path: &Path) -> Result<()> {
+    let id = single_gpu_id(args, client).await?;
+    let vbios = client.dump_vbios(&id).await?.inner()?;
+    fs::write(path, &vbios).with_context(|| format!("Could not write {path:?}"))?;
+    println!(
+        "Saved {} KiB VBIOS to {}",
+        vbios.len() / 1024,
+        path.display()
+    );
+    Ok(())
+}
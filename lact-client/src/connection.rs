@@ -1,13 +1,16 @@
 pub mod tcp;
 pub mod unix;
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use futures::future::BoxFuture;
 use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
 
 pub trait DaemonConnection {
     fn request<'a>(&'a mut self, payload: &'a str) -> BoxFuture<'a, anyhow::Result<String>>;
 
+    /// Reads the next message pushed by the service, used for subscriptions
+    fn read_message(&mut self) -> BoxFuture<'_, anyhow::Result<String>>;
+
     /// Establish a new connection to the same service
     fn new_connection(&self) -> BoxFuture<'_, anyhow::Result<Box<dyn DaemonConnection>>>;
 }
@@ -28,3 +31,11 @@ async fn request(
 
     Ok(response_payload)
 }
+
+async fn read_message(socket: &mut BufReader<impl AsyncRead + Unpin>) -> anyhow::Result<String> {
+    let mut message = String::new();
+    if socket.read_line(&mut message).await? == 0 {
+        bail!("Connection closed by the daemon");
+    }
+    Ok(message)
+}
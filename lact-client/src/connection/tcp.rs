@@ -1,4 +1,5 @@
-use super::{request, DaemonConnection};
+use super::{read_message, request, DaemonConnection};
+use crate::schema::{Request, Response};
 use anyhow::Context;
 use futures::future::BoxFuture;
 use tokio::{
@@ -9,15 +10,35 @@ use tracing::info;
 
 pub struct TcpConnection {
     inner: BufReader<TcpStream>,
+    auth_token: Option<String>,
 }
 
 impl TcpConnection {
-    pub async fn connect(addr: impl ToSocketAddrs) -> anyhow::Result<Box<Self>> {
+    pub async fn connect(
+        addr: impl ToSocketAddrs,
+        auth_token: Option<String>,
+    ) -> anyhow::Result<Box<Self>> {
         info!("connecting to remote TCP service");
         let inner = TcpStream::connect(addr).await?;
-        Ok(Box::new(Self {
+        let mut connection = Self {
             inner: BufReader::new(inner),
-        }))
+            auth_token,
+        };
+
+        if let Some(token) = connection.auth_token.clone() {
+            let payload = serde_json::to_string(&Request::Authenticate { token })?;
+            let response = request(&mut connection.inner, &payload).await?;
+            match serde_json::from_str::<Response<()>>(&response)
+                .context("Could not deserialize authentication response")?
+            {
+                Response::Ok(()) => info!("authenticated to remote TCP service"),
+                Response::Error(err) => {
+                    return Err(anyhow::Error::new(err).context("Could not authenticate"));
+                }
+            }
+        }
+
+        Ok(Box::new(connection))
     }
 }
 
@@ -26,6 +47,10 @@ impl DaemonConnection for TcpConnection {
         Box::pin(async { request(&mut self.inner, payload).await })
     }
 
+    fn read_message(&mut self) -> BoxFuture<'_, anyhow::Result<String>> {
+        Box::pin(async { read_message(&mut self.inner).await })
+    }
+
     fn new_connection(&self) -> BoxFuture<'_, anyhow::Result<Box<dyn DaemonConnection>>> {
         Box::pin(async {
             let peer_addr = self
@@ -34,7 +59,8 @@ impl DaemonConnection for TcpConnection {
                 .peer_addr()
                 .context("Could not read peer address")?;
 
-            Ok(Self::connect(peer_addr).await? as Box<dyn DaemonConnection>)
+            Ok(Self::connect(peer_addr, self.auth_token.clone()).await?
+                as Box<dyn DaemonConnection>)
         })
     }
 }
@@ -1,3 +1,30 @@
+//! Async client for the LACT daemon.
+//!
+//! [`DaemonClient`] connects to the daemon's unix socket (or a TCP listener) and has a typed method for every request.
+//! The socket is taken from the `LACT_SOCKET_PATH` environment variable when it is set, otherwise the default locations are used.
+//! When the connection is lost, requests wait for the daemon to come back, retrying with an increasing delay.
+//! Stats and device events can be pushed by the daemon through a [`Subscription`], which can also be used as a [`Stream`].
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use futures::StreamExt;
+//! use std::time::Duration;
+//!
+//! let client = lact_client::DaemonClient::connect().await?;
+//! client.check_protocol_version().await?;
+//!
+//! let devices = client.list_devices().await?.inner()?;
+//! let stats = client
+//!     .subscribe_stats(&devices[0].id, Duration::from_secs(1))
+//!     .await?
+//!     .into_stream();
+//! let mut stats = std::pin::pin!(stats);
+//! while let Some(stats) = stats.next().await {
+//!     println!("{:?}", stats?.power.average);
+//! }
+//! # Ok(())
+//! # }
+//! ```
 mod connection;
 #[macro_use]
 mod macros;
@@ -9,16 +36,28 @@ use amdgpu_sysfs::gpu_handle::{
 };
 use anyhow::Context;
 use connection::{tcp::TcpConnection, unix::UnixConnection, DaemonConnection};
+use futures::{stream, Stream};
 use nix::unistd::getuid;
 use schema::{
     request::{ConfirmCommand, ProfileBase, SetClocksCommand},
-    ClocksInfo, DeviceInfo, DeviceListEntry, DeviceStats, FanOptions, PowerStates, ProfilesInfo,
-    Request, Response, SystemInfo,
+    Alert, ApuLimitKind, ClocksInfo, ConfigHistoryInfo, DeviceCapabilities, DeviceEvent,
+    DeviceInfo, DeviceListEntry, DeviceStats, EnergyPrice, EnergyStats, EventLogEntry, FanCurveMap,
+    FanCurvePreviewPoint, FanOptions, GpuProcess, ImportStateResult, MemoryState, PmfwOptions,
+    Pong, PowerStates, ProfileComparisonOptions, ProfileComparisonStatus, ProfileRule,
+    ProfileSchedule, ProfilesInfo, Request, ResetResult, Response, SensorChip, StatsHistorySample,
+    SystemInfo, UndervoltOptions, UndervoltStatus, DEFAULT_SOCKET_PATH, PROTOCOL_VERSION,
+    SOCKET_PATH_ENV,
 };
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 use std::{
-    future::Future, marker::PhantomData, os::unix::net::UnixStream, path::PathBuf, pin::Pin,
-    rc::Rc, time::Duration,
+    env,
+    future::Future,
+    marker::PhantomData,
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    pin::Pin,
+    rc::Rc,
+    time::Duration,
 };
 use tokio::{
     net::ToSocketAddrs,
@@ -28,6 +67,7 @@ use tracing::{error, info};
 
 const STATUS_MSG_CHANNEL_SIZE: usize = 16;
 const RECONNECT_INTERVAL_MS: u64 = 250;
+const MAX_RECONNECT_INTERVAL_MS: u64 = 5000;
 
 #[derive(Clone)]
 pub struct DaemonClient {
@@ -40,7 +80,12 @@ impl DaemonClient {
     pub async fn connect() -> anyhow::Result<Self> {
         let path =
             get_socket_path().context("Could not connect to daemon: socket file not found")?;
-        let stream = UnixConnection::connect(&path).await?;
+        Self::connect_path(&path).await
+    }
+
+    /// Connects to the daemon listening on the given socket
+    pub async fn connect_path(path: &Path) -> anyhow::Result<Self> {
+        let stream = UnixConnection::connect(path).await?;
 
         Ok(Self {
             stream: Rc::new(Mutex::new(stream)),
@@ -49,8 +94,11 @@ impl DaemonClient {
         })
     }
 
-    pub async fn connect_tcp(addr: impl ToSocketAddrs) -> anyhow::Result<Self> {
-        let stream = TcpConnection::connect(addr).await?;
+    pub async fn connect_tcp(
+        addr: impl ToSocketAddrs,
+        auth_token: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let stream = TcpConnection::connect(addr, auth_token).await?;
 
         Ok(Self {
             stream: Rc::new(Mutex::new(stream)),
@@ -68,6 +116,20 @@ impl DaemonClient {
         })
     }
 
+    /// Checks that the daemon speaks the same protocol version as this client
+    pub async fn check_protocol_version(&self) -> anyhow::Result<()> {
+        let system_info = self.get_system_info().await?.inner()?;
+        match system_info.protocol_version {
+            Some(PROTOCOL_VERSION) => Ok(()),
+            Some(version) => Err(anyhow::anyhow!(
+                "Protocol version mismatch: the client uses version {PROTOCOL_VERSION}, but the daemon uses version {version}"
+            )),
+            None => Err(anyhow::anyhow!(
+                "Protocol version mismatch: the daemon is too old to report its protocol version"
+            )),
+        }
+    }
+
     pub fn status_receiver(&self) -> broadcast::Receiver<ConnectionStatusMsg> {
         self.status_tx.subscribe()
     }
@@ -89,6 +151,7 @@ impl DaemonClient {
                     error!("Could not make request: {err}, reconnecting to socket");
                     let _ = self.status_tx.send(ConnectionStatusMsg::Disconnected);
 
+                    let mut delay = Duration::from_millis(RECONNECT_INTERVAL_MS);
                     loop {
                         match stream.new_connection().await {
                             Ok(new_connection) => {
@@ -101,9 +164,12 @@ impl DaemonClient {
                                 return self.make_request(request).await;
                             }
                             Err(err) => {
-                                error!("Could not reconnect: {err:#}, retrying in {RECONNECT_INTERVAL_MS}ms");
-                                tokio::time::sleep(Duration::from_millis(RECONNECT_INTERVAL_MS))
-                                    .await;
+                                error!(
+                                    "Could not reconnect: {err:#}, retrying in {}ms",
+                                    delay.as_millis()
+                                );
+                                tokio::time::sleep(delay).await;
+                                delay = next_reconnect_delay(delay);
                             }
                         }
                     }
@@ -112,6 +178,41 @@ impl DaemonClient {
         })
     }
 
+    /// Opens a separate connection on which the daemon pushes stats of the GPU at the given interval
+    pub async fn subscribe_stats(
+        &self,
+        id: &str,
+        interval: Duration,
+    ) -> anyhow::Result<StatsSubscription> {
+        let mut connection = self.stream.lock().await.new_connection().await?;
+
+        let payload = serde_json::to_string(&Request::SubscribeStats {
+            id,
+            interval_ms: interval.as_millis().try_into()?,
+        })?;
+        let confirmation: ResponseBuffer<()> = ResponseBuffer {
+            buf: connection.request(&payload).await?,
+            _phantom: PhantomData,
+        };
+        confirmation.inner()?;
+
+        Ok(Subscription::new(connection))
+    }
+
+    /// Opens a separate connection on which the daemon pushes events about GPUs being added or removed
+    pub async fn subscribe_device_events(&self) -> anyhow::Result<DeviceEventSubscription> {
+        let mut connection = self.stream.lock().await.new_connection().await?;
+
+        let payload = serde_json::to_string(&Request::SubscribeDeviceEvents)?;
+        let confirmation: ResponseBuffer<()> = ResponseBuffer {
+            buf: connection.request(&payload).await?,
+            _phantom: PhantomData,
+        };
+        confirmation.inner()?;
+
+        Ok(Subscription::new(connection))
+    }
+
     pub async fn list_devices(&self) -> anyhow::Result<ResponseBuffer<Vec<DeviceListEntry>>> {
         self.make_request(Request::ListDevices).await
     }
@@ -122,18 +223,28 @@ impl DaemonClient {
             .inner()
     }
 
+    pub async fn set_gpu_label(&self, id: &str, label: Option<String>) -> anyhow::Result<()> {
+        self.make_request(Request::SetGpuLabel { id, label })
+            .await?
+            .inner()
+    }
+
     pub async fn set_power_cap(&self, id: &str, cap: Option<f64>) -> anyhow::Result<u64> {
         self.make_request(Request::SetPowerCap { id, cap })
             .await?
             .inner()
     }
 
+    request_plain!(ping, Ping, Pong);
     request_plain!(get_system_info, SystemInfo, SystemInfo);
     request_plain!(enable_overdrive, EnableOverdrive, String);
     request_plain!(disable_overdrive, DisableOverdrive, String);
     request_plain!(generate_debug_snapshot, GenerateSnapshot, String);
-    request_plain!(reset_config, RestConfig, ());
+    request_plain!(reset_config, RestConfig, ResetResult);
     request_plain!(list_profiles, ListProfiles, ProfilesInfo);
+    request_plain!(get_event_log, GetEventLog, Vec<EventLogEntry>);
+    request_plain!(export_state, ExportState, String);
+    request_plain!(get_sensor_chips, GetSensorChips, Vec<SensorChip>);
     request_with_id!(get_device_info, DeviceInfo, DeviceInfo);
     request_with_id!(get_device_stats, DeviceStats, DeviceStats);
     request_with_id!(get_device_clocks_info, DeviceClocksInfo, ClocksInfo);
@@ -142,9 +253,39 @@ impl DaemonClient {
         DevicePowerProfileModes,
         PowerProfileModesTable
     );
+    request_with_id!(
+        get_device_capabilities,
+        DeviceCapabilities,
+        DeviceCapabilities
+    );
     request_with_id!(get_power_states, GetPowerStates, PowerStates);
     request_with_id!(reset_pmfw, ResetPmfw, u64);
+    request_with_id!(get_alerts, GetAlerts, Vec<Alert>);
+    request_with_id!(reset_fan_control, ResetFanControl, u64);
+    request_with_id!(reset_clocks, ResetClocks, u64);
+    request_with_id!(reset_power_cap, ResetPowerCap, u64);
     request_with_id!(dump_vbios, VbiosDump, Vec<u8>);
+    request_with_id!(get_pcie_power_states, PciePowerStates, Vec<String>);
+    request_with_id!(get_memory_states, MemoryStates, Vec<MemoryState>);
+    request_with_id!(get_processes, GetProcesses, Vec<GpuProcess>);
+    request_with_id!(undo_last_change, UndoLastChange, u64);
+    request_with_id!(redo_last_change, RedoLastChange, u64);
+    request_with_id!(get_config_history, ConfigHistory, ConfigHistoryInfo);
+    request_with_id!(apply_idle_optimization, ApplyIdleOptimization, u64);
+    request_with_id!(get_energy_stats, GetEnergyStats, EnergyStats);
+    request_with_id!(get_undervolt_status, GetUndervoltStatus, UndervoltStatus);
+    request_with_id!(
+        get_profile_comparison_status,
+        GetProfileComparisonStatus,
+        ProfileComparisonStatus
+    );
+
+    pub async fn import_state(
+        &self,
+        state: String,
+    ) -> anyhow::Result<ResponseBuffer<ImportStateResult>> {
+        self.make_request(Request::ImportState { state }).await
+    }
 
     pub async fn set_profile(&self, name: Option<String>) -> anyhow::Result<()> {
         self.make_request(Request::SetProfile { name })
@@ -164,6 +305,165 @@ impl DaemonClient {
             .inner()
     }
 
+    pub async fn set_profile_rule(
+        &self,
+        name: String,
+        rule: Option<ProfileRule>,
+    ) -> anyhow::Result<()> {
+        self.make_request(Request::SetProfileRule { name, rule })
+            .await?
+            .inner()
+    }
+
+    pub async fn start_undervolt(&self, id: &str, options: UndervoltOptions) -> anyhow::Result<()> {
+        self.make_request(Request::StartUndervolt { id, options })
+            .await?
+            .inner()
+    }
+
+    pub async fn cancel_undervolt(&self, id: &str) -> anyhow::Result<()> {
+        self.make_request(Request::CancelUndervolt { id })
+            .await?
+            .inner()
+    }
+
+    pub async fn start_profile_comparison(
+        &self,
+        id: &str,
+        options: ProfileComparisonOptions,
+    ) -> anyhow::Result<()> {
+        self.make_request(Request::StartProfileComparison { id, options })
+            .await?
+            .inner()
+    }
+
+    pub async fn cancel_profile_comparison(&self, id: &str) -> anyhow::Result<()> {
+        self.make_request(Request::CancelProfileComparison { id })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_energy_price(&self, price: Option<EnergyPrice>) -> anyhow::Result<()> {
+        self.make_request(Request::SetEnergyPrice { price })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_profile_schedule(
+        &self,
+        name: String,
+        schedule: Option<ProfileSchedule>,
+    ) -> anyhow::Result<()> {
+        self.make_request(Request::SetProfileSchedule { name, schedule })
+            .await?
+            .inner()
+    }
+
+    pub async fn preview_fan_curve(
+        &self,
+        id: &str,
+        curve: FanCurveMap,
+        since: Option<i64>,
+    ) -> anyhow::Result<Vec<FanCurvePreviewPoint>> {
+        self.make_request(Request::PreviewFanCurve { id, curve, since })
+            .await?
+            .inner()
+    }
+
+    pub async fn get_stats_history(
+        &self,
+        id: &str,
+        since: Option<i64>,
+    ) -> anyhow::Result<Vec<StatsHistorySample>> {
+        self.make_request(Request::GetStatsHistory { id, since })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_pmfw_options(&self, id: &str, options: PmfwOptions) -> anyhow::Result<u64> {
+        self.make_request(Request::SetPmfwOptions { id, options })
+            .await?
+            .inner()
+    }
+
+    pub async fn lock_memory_state(&self, id: &str, state: Option<u8>) -> anyhow::Result<u64> {
+        self.make_request(Request::LockMemoryState { id, state })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_min_pcie_state(&self, id: &str, state: Option<u8>) -> anyhow::Result<u64> {
+        self.make_request(Request::SetMinPcieState { id, state })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_min_vram_state(&self, id: &str, state: Option<u8>) -> anyhow::Result<u64> {
+        self.make_request(Request::SetMinVramState { id, state })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_apu_limit(
+        &self,
+        id: &str,
+        kind: ApuLimitKind,
+        value: Option<f64>,
+    ) -> anyhow::Result<u64> {
+        self.make_request(Request::SetApuLimit { id, kind, value })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_thermal_target(
+        &self,
+        id: &str,
+        temperature: Option<f32>,
+    ) -> anyhow::Result<u64> {
+        self.make_request(Request::SetThermalTarget { id, temperature })
+            .await?
+            .inner()
+    }
+
+    pub async fn reset_gpu(&self, id: &str, confirm: bool) -> anyhow::Result<()> {
+        self.make_request(Request::ResetGpu { id, confirm })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_alerts(&self, id: &str, alerts: Vec<Alert>) -> anyhow::Result<u64> {
+        self.make_request(Request::SetAlerts { id, alerts })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_app_power_cap(
+        &self,
+        id: &str,
+        process: String,
+        power_cap: Option<f64>,
+    ) -> anyhow::Result<u64> {
+        self.make_request(Request::SetAppPowerCap {
+            id,
+            process,
+            power_cap,
+        })
+        .await?
+        .inner()
+    }
+
+    pub async fn set_auto_switch_profiles(&self, enabled: bool) -> anyhow::Result<()> {
+        self.make_request(Request::SetAutoSwitchProfiles { enabled })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_fallback_profile(&self, name: Option<String>) -> anyhow::Result<()> {
+        self.make_request(Request::SetFallbackProfile { name })
+            .await?
+            .inner()
+    }
+
     pub async fn set_performance_level(
         &self,
         id: &str,
@@ -177,6 +477,24 @@ impl DaemonClient {
         .inner()
     }
 
+    pub async fn set_benchmark_lock(&self, id: &str, enabled: bool) -> anyhow::Result<()> {
+        self.make_request(Request::SetBenchmarkLock { id, enabled })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_low_latency_mode(&self, id: &str, enabled: bool) -> anyhow::Result<u64> {
+        self.make_request(Request::SetLowLatencyMode { id, enabled })
+            .await?
+            .inner()
+    }
+
+    pub async fn set_runtime_pm(&self, id: &str, auto: Option<bool>) -> anyhow::Result<u64> {
+        self.make_request(Request::SetRuntimePm { id, auto })
+            .await?
+            .inner()
+    }
+
     pub async fn set_clocks_value(
         &self,
         id: &str,
@@ -197,6 +515,13 @@ impl DaemonClient {
             .inner()
     }
 
+    /// Applies the changes of all the requests at once, or none of them if any fails
+    pub async fn apply_batch(&self, id: &str, requests: Vec<Request<'_>>) -> anyhow::Result<u64> {
+        self.make_request(Request::ApplyBatch { id, requests })
+            .await?
+            .inner()
+    }
+
     pub async fn set_enabled_power_states(
         &self,
         id: &str,
@@ -230,15 +555,25 @@ impl DaemonClient {
     }
 }
 
+/// Doubles the delay between reconnection attempts, up to [`MAX_RECONNECT_INTERVAL_MS`]
+fn next_reconnect_delay(delay: Duration) -> Duration {
+    (delay * 2).min(Duration::from_millis(MAX_RECONNECT_INTERVAL_MS))
+}
+
+/// The socket from [`SOCKET_PATH_ENV`] is used even if it doesn't exist, so that connecting to it gives a meaningful error
 fn get_socket_path() -> Option<PathBuf> {
-    let root_path = PathBuf::from("/var/run/lactd.sock");
+    if let Some(path) = env::var_os(SOCKET_PATH_ENV).filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+
+    let root_path = PathBuf::from(DEFAULT_SOCKET_PATH);
 
     if root_path.exists() {
         return Some(root_path);
     }
 
     let uid = getuid();
-    let user_path = PathBuf::from(format!("/var/run/user/{}/lactd.sock", uid));
+    let user_path = PathBuf::from(format!("/run/user/{}/lactd.sock", uid));
 
     if user_path.exists() {
         Some(user_path)
@@ -254,8 +589,9 @@ pub struct ResponseBuffer<T> {
 
 impl<'a, T: Deserialize<'a>> ResponseBuffer<T> {
     pub fn inner(&'a self) -> anyhow::Result<T> {
-        let response: Response<T> = serde_json::from_str(&self.buf)
-            .context("Could not deserialize response from daemon")?;
+        let response: Response<T> = serde_json::from_str(&self.buf).context(
+            "Could not deserialize response from daemon (is the daemon running the same version?)",
+        )?;
         match response {
             Response::Ok(data) => Ok(data),
             Response::Error(err) => {
@@ -266,8 +602,70 @@ impl<'a, T: Deserialize<'a>> ResponseBuffer<T> {
     }
 }
 
+/// Messages pushed by the daemon on a dedicated connection, the subscription ends when this is dropped
+pub struct Subscription<T> {
+    connection: Box<dyn DaemonConnection>,
+    _phantom: PhantomData<T>,
+}
+
+/// Stats updates of a GPU at the requested interval
+pub type StatsSubscription = Subscription<DeviceStats>;
+/// GPUs being added or removed
+pub type DeviceEventSubscription = Subscription<DeviceEvent>;
+
+impl<T: DeserializeOwned> Subscription<T> {
+    fn new(connection: Box<dyn DaemonConnection>) -> Self {
+        Self {
+            connection,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub async fn next(&mut self) -> anyhow::Result<T> {
+        let buffer: ResponseBuffer<T> = ResponseBuffer {
+            buf: self.connection.read_message().await?,
+            _phantom: PhantomData,
+        };
+        buffer.inner()
+    }
+
+    /// Turns the subscription into a stream, which ends after the first error.
+    /// Subscriptions are not resumed when the daemon restarts, a new one has to be created.
+    pub fn into_stream(self) -> impl Stream<Item = anyhow::Result<T>> {
+        stream::unfold(Some(self), |subscription| async move {
+            let mut subscription = subscription?;
+            match subscription.next().await {
+                Ok(item) => Some((Ok(item), Some(subscription))),
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum ConnectionStatusMsg {
     Disconnected,
     Reconnected,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::next_reconnect_delay;
+    use std::time::Duration;
+
+    #[test]
+    fn reconnect_backoff() {
+        assert_eq!(
+            next_reconnect_delay(Duration::from_millis(250)),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            next_reconnect_delay(Duration::from_millis(4000)),
+            Duration::from_millis(5000)
+        );
+        assert_eq!(
+            next_reconnect_delay(Duration::from_millis(5000)),
+            Duration::from_millis(5000)
+        );
+    }
+}
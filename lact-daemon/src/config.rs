@@ -1,29 +1,48 @@
-use crate::server::gpu_controller::fan_control::FanCurve;
+use crate::server::{
+    gpu_controller::fan_control::{CurveTiming, FanCurve},
+    hooks::HookEventKind,
+};
 use amdgpu_sysfs::gpu_handle::{PerformanceLevel, PowerLevelKind};
-use anyhow::Context;
+use anyhow::{bail, Context};
 use indexmap::IndexMap;
-use lact_schema::{default_fan_curve, request::SetClocksCommand, FanControlMode, PmfwOptions};
+use lact_schema::{
+    default_fan_curve, request::SetClocksCommand, Alert, ApuLimitKind, EnergyPrice, ExtraClock,
+    FanControlMode, FanCurveInput, FanSpeedUnit, PmfwOptions, ProfileRule, ProfileSchedule,
+};
 use nix::unistd::getuid;
 use notify::{RecommendedWatcher, Watcher};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::{
-    collections::HashMap,
-    env, fs,
-    path::PathBuf,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    env, fmt,
+    fs::{self, Permissions},
+    io::{self, Write},
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 use tokio::{sync::mpsc, time};
-use tracing::{debug, error};
+use tracing::{debug, error, info, warn};
 
-const FILE_NAME: &str = "config.yaml";
-const DEFAULT_ADMIN_GROUPS: [&str; 2] = ["wheel", "sudo"];
+const FILE_NAME: &str = "config.toml";
+/// Unversioned YAML config used before the TOML format, which is migrated when no TOML config exists yet
+const LEGACY_FILE_NAME: &str = "config.yaml";
+/// Version of the config format, should be bumped along with a migration on incompatible changes.
+/// Version 1 is the legacy YAML config.
+const CONFIG_SCHEMA_VERSION: u32 = 2;
+/// The dedicated `lact` group is preferred, the others are common admin groups on distros where it was not created
+const DEFAULT_ADMIN_GROUPS: [&str; 3] = ["lact", "wheel", "sudo"];
 /// Minimum amount of time between separate config reloads
 const CONFIG_RELOAD_INTERVAL_MILLIS: u64 = 50;
+/// Version of the exported state format, should be bumped on incompatible changes
+pub const EXPORTED_STATE_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Config {
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
     pub daemon: Daemon,
     #[serde(default = "default_apply_settings_timer")]
     pub apply_settings_timer: u64,
@@ -33,16 +52,34 @@ pub struct Config {
     pub profiles: IndexMap<String, Profile>,
     #[serde(default)]
     pub current_profile: Option<String>,
+    /// Activate profiles automatically based on their rules
+    #[serde(default)]
+    pub auto_switch_profiles: bool,
+    /// Profile to switch back to when an automatically activated profile stops matching,
+    /// the default settings are used when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_profile: Option<String>,
+    /// Custom GPU names, independent of profiles
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub gpu_labels: HashMap<String, String>,
+    /// Electricity price for estimating the cost of the used energy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub energy_price: Option<EnergyPrice>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            schema_version: CONFIG_SCHEMA_VERSION,
             daemon: Daemon::default(),
             apply_settings_timer: default_apply_settings_timer(),
             gpus: HashMap::new(),
             profiles: IndexMap::new(),
             current_profile: None,
+            auto_switch_profiles: false,
+            fallback_profile: None,
+            gpu_labels: HashMap::new(),
+            energy_price: None,
         }
     }
 }
@@ -55,6 +92,63 @@ pub struct Daemon {
     #[serde(default)]
     pub disable_clocks_cleanup: bool,
     pub tcp_listen_address: Option<String>,
+    /// Token that TCP clients have to authenticate with
+    pub tcp_auth_token: Option<String>,
+    /// Address to serve Prometheus metrics on, e.g. `0.0.0.0:9090`
+    pub metrics_listen_address: Option<String>,
+    /// Address to serve the web interface on, e.g. `0.0.0.0:12854`. Requires `web_auth_token` to be set.
+    pub web_listen_address: Option<String>,
+    /// Token that the web interface has to be unlocked with
+    pub web_auth_token: Option<String>,
+    /// Expose a read-only interface on the system D-Bus
+    #[serde(default)]
+    pub dbus_interface: bool,
+    /// Allow fan curves that don't reach the minimum safe speed at high temperatures
+    #[serde(default)]
+    pub allow_unsafe_fan_curves: bool,
+    /// Amount of samples to average clockspeed and power stats over
+    pub stats_smoothing_window: Option<usize>,
+    /// Seconds between the samples of the stats history. The history is disabled when not set.
+    pub stats_history_interval: Option<u64>,
+    /// How many hours of stats history to keep
+    #[serde(default = "default_stats_history_hours")]
+    pub stats_history_hours: u64,
+    /// Reset all GPUs to stock settings when the daemon exits.
+    /// When disabled, only fan control is handed back to the GPU.
+    #[serde(default = "default_reset_on_exit")]
+    pub reset_on_exit: bool,
+    /// Sample GPU sensors in the background less often while no clients are connected,
+    /// except for GPUs with alerts
+    #[serde(default = "default_adaptive_stats_polling")]
+    pub adaptive_stats_polling: bool,
+    /// Seconds without connected clients after which the daemon exits when it was started through systemd socket activation.
+    /// 0 keeps it running.
+    #[serde(default = "default_idle_timeout")]
+    pub idle_timeout: u64,
+    /// Let every local user connect to the socket, and authorize changes from users outside of the admin groups through polkit.
+    /// Users that are not authorized can only read information.
+    #[serde(default)]
+    pub polkit: bool,
+    /// Socket to listen on instead of the default one, e.g. to run a separate daemon in a container.
+    /// Overridden by `--socket-path` and the `LACT_SOCKET_PATH` environment variable, and only used after a restart.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket_path: Option<PathBuf>,
+    /// Names of non-GPU hwmon chips to show in the sensors page, e.g. `k10temp`, `nct6798` or `nvme`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sensor_chips: Vec<String>,
+    /// Commands to run on events such as profile switches or temperature alerts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<Hook>,
+}
+
+/// Shell command that is run as root on the given events, with their details in `LACT_*`
+/// environment variables and as JSON on stdin
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Hook {
+    /// The command runs on all events when none are given
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<HookEventKind>,
+    pub command: String,
 }
 
 impl Default for Daemon {
@@ -64,6 +158,22 @@ impl Default for Daemon {
             admin_groups: DEFAULT_ADMIN_GROUPS.map(str::to_owned).to_vec(),
             disable_clocks_cleanup: false,
             tcp_listen_address: None,
+            tcp_auth_token: None,
+            metrics_listen_address: None,
+            web_listen_address: None,
+            web_auth_token: None,
+            dbus_interface: false,
+            allow_unsafe_fan_curves: false,
+            stats_smoothing_window: None,
+            stats_history_interval: None,
+            stats_history_hours: default_stats_history_hours(),
+            reset_on_exit: true,
+            adaptive_stats_polling: true,
+            idle_timeout: default_idle_timeout(),
+            polkit: false,
+            socket_path: None,
+            sensor_chips: vec![],
+            hooks: vec![],
         }
     }
 }
@@ -72,6 +182,10 @@ impl Default for Daemon {
 pub struct Profile {
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub gpus: HashMap<String, Gpu>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<ProfileRule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<ProfileSchedule>,
 }
 
 #[skip_serializing_none]
@@ -82,19 +196,43 @@ pub struct Gpu {
     #[serde(default, skip_serializing_if = "PmfwOptions::is_empty")]
     pub pmfw_options: PmfwOptions,
     pub power_cap: Option<f64>,
+    /// Temperature to keep the GPU at or below by lowering the power cap, `power_cap` becomes the upper limit
+    pub thermal_target: Option<f32>,
+    /// Power caps to use while a process with the given name is running, overriding `power_cap`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub app_power_caps: HashMap<String, f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<Alert>,
+    /// Power (in watts) and temperature (in °C) limits of integrated GPUs
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub apu_limits: BTreeMap<ApuLimitKind, f64>,
     pub performance_level: Option<PerformanceLevel>,
     #[serde(default, flatten)]
     pub clocks_configuration: ClocksConfiguration,
     pub power_profile_mode_index: Option<u16>,
     /// Outer vector is for power profile components, inner vector is for the heuristics within a component
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "optional_heuristics"
+    )]
     pub custom_power_profile_mode_hueristics: Vec<Vec<Option<i32>>>,
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub power_states: HashMap<PowerLevelKind, Vec<u8>>,
+    /// Hwmon directory of an external fan controller (e.g. a motherboard chip) to use for fan control instead of the GPU's own
+    pub fan_control_hwmon_path: Option<PathBuf>,
+    /// Milliseconds between background samples of the GPU's sensors (energy usage, throttling), between 250 and 10000
+    pub stats_interval_ms: Option<u64>,
+    /// Holds the GPU at its highest clocks without deep sleep, overriding `performance_level` and `power_states`
+    #[serde(default)]
+    pub low_latency: bool,
+    /// Sets `power/control` of the GPU to `auto` (true), so that it can power off while unused, or `on` (false).
+    /// The setting is left as it is when this is not set, as it's often managed by the system (e.g. by udev rules or TLP).
+    pub runtime_pm: Option<bool>,
 }
 
 #[skip_serializing_none]
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 pub struct ClocksConfiguration {
     pub min_core_clock: Option<i32>,
     pub min_memory_clock: Option<i32>,
@@ -102,7 +240,29 @@ pub struct ClocksConfiguration {
     pub max_core_clock: Option<i32>,
     pub max_memory_clock: Option<i32>,
     pub max_voltage: Option<i32>,
+    pub boost_core_clock: Option<i32>,
     pub voltage_offset: Option<i32>,
+    pub core_clock_offset: Option<i32>,
+    /// Data fabric clock (FCLK) range, on GPUs that allow overclocking it
+    pub min_fabric_clock: Option<i32>,
+    pub max_fabric_clock: Option<i32>,
+    /// SoC clock (SOCCLK) range, on GPUs that allow overclocking it
+    pub min_soc_clock: Option<i32>,
+    pub max_soc_clock: Option<i32>,
+    /// Custom points of the GPU voltage/frequency curve, sorted by index
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gpu_curve: Vec<ClocksPoint>,
+    /// Custom clocks and voltages of memory power states, sorted by index (pre-Vega20)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub memory_states: Vec<ClocksPoint>,
+}
+
+/// Clockspeed and voltage at an index of the GPU curve or the memory power states
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClocksPoint {
+    pub index: usize,
+    pub clockspeed: i32,
+    pub voltage: i32,
 }
 
 impl Gpu {
@@ -119,7 +279,27 @@ impl Gpu {
             SetClocksCommand::MinCoreClock(clock) => clocks.min_core_clock = Some(*clock),
             SetClocksCommand::MinMemoryClock(clock) => clocks.min_memory_clock = Some(*clock),
             SetClocksCommand::MinVoltage(voltage) => clocks.min_voltage = Some(*voltage),
+            SetClocksCommand::BoostCoreClock(clock) => clocks.boost_core_clock = Some(*clock),
             SetClocksCommand::VoltageOffset(offset) => clocks.voltage_offset = Some(*offset),
+            SetClocksCommand::CoreClockOffset(offset) => {
+                clocks.core_clock_offset = Some(*offset);
+            }
+            SetClocksCommand::MinExtraClock(clock, value) => {
+                *clocks.extra_clock_mut(*clock).0 = Some(*value);
+            }
+            SetClocksCommand::MaxExtraClock(clock, value) => {
+                *clocks.extra_clock_mut(*clock).1 = Some(*value);
+            }
+            SetClocksCommand::GpuCurvePoint {
+                index,
+                clockspeed,
+                voltage,
+            } => set_clocks_point(&mut clocks.gpu_curve, *index, *clockspeed, *voltage),
+            SetClocksCommand::MemoryState {
+                index,
+                clockspeed,
+                voltage,
+            } => set_clocks_point(&mut clocks.memory_states, *index, *clockspeed, *voltage),
             SetClocksCommand::Reset => {
                 *clocks = ClocksConfiguration::default();
                 assert!(!self.is_core_clocks_used());
@@ -128,6 +308,36 @@ impl Gpu {
     }
 }
 
+impl ClocksConfiguration {
+    /// Configured min and max level of an extra clock
+    pub fn extra_clock(&self, clock: ExtraClock) -> (Option<i32>, Option<i32>) {
+        match clock {
+            ExtraClock::Fabric => (self.min_fabric_clock, self.max_fabric_clock),
+            ExtraClock::Soc => (self.min_soc_clock, self.max_soc_clock),
+        }
+    }
+
+    fn extra_clock_mut(&mut self, clock: ExtraClock) -> (&mut Option<i32>, &mut Option<i32>) {
+        match clock {
+            ExtraClock::Fabric => (&mut self.min_fabric_clock, &mut self.max_fabric_clock),
+            ExtraClock::Soc => (&mut self.min_soc_clock, &mut self.max_soc_clock),
+        }
+    }
+}
+
+/// Inserts or replaces the point at the given index, keeping the points sorted
+fn set_clocks_point(points: &mut Vec<ClocksPoint>, index: usize, clockspeed: i32, voltage: i32) {
+    let point = ClocksPoint {
+        index,
+        clockspeed,
+        voltage,
+    };
+    match points.binary_search_by_key(&index, |point| point.index) {
+        Ok(pos) => points[pos] = point,
+        Err(pos) => points.insert(pos, point),
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct FanControlSettings {
@@ -136,10 +346,28 @@ pub struct FanControlSettings {
     #[serde(default = "default_fan_static_speed")]
     pub static_speed: f64,
     pub temperature_key: String,
+    /// Additional sensors to follow, the curve uses the hottest of these and `temperature_key`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_temperature_keys: Vec<String>,
     pub interval_ms: u64,
     pub curve: FanCurve,
     pub spindown_delay_ms: Option<u64>,
     pub change_threshold: Option<u64>,
+    /// Maximum fan speed change in percent per second
+    pub max_ramp_rate: Option<u64>,
+    /// Curves for individual fan channels, fans without one use `curve`
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        with = "string_keys"
+    )]
+    pub fan_curves: BTreeMap<u32, FanCurve>,
+    /// Whether the curve and static speed are applied as PWM or as an RPM target
+    #[serde(default)]
+    pub speed_unit: FanSpeedUnit,
+    /// What the curve points are placed at, e.g. the power draw instead of the temperature
+    #[serde(default, skip_serializing_if = "FanCurveInput::is_temperature")]
+    pub curve_input: FanCurveInput,
 }
 
 impl Default for FanControlSettings {
@@ -148,12 +376,50 @@ impl Default for FanControlSettings {
             mode: FanControlMode::default(),
             static_speed: default_fan_static_speed(),
             temperature_key: "edge".to_owned(),
+            extra_temperature_keys: vec![],
             interval_ms: 500,
             curve: FanCurve(default_fan_curve()),
             spindown_delay_ms: None,
             change_threshold: None,
+            max_ramp_rate: None,
+            fan_curves: BTreeMap::new(),
+            speed_unit: FanSpeedUnit::default(),
+            curve_input: FanCurveInput::default(),
+        }
+    }
+}
+
+impl FanControlSettings {
+    /// Curve followed by the given fan channel
+    pub fn curve_for_channel(&self, channel: u32) -> &FanCurve {
+        self.fan_curves.get(&channel).unwrap_or(&self.curve)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub fn curve_timing(&self) -> CurveTiming {
+        CurveTiming {
+            spindown_delay: Duration::from_millis(self.spindown_delay_ms.unwrap_or(0)),
+            change_threshold: self.change_threshold.unwrap_or(0) as f32,
+            max_ramp_rate: self.max_ramp_rate.filter(|rate| *rate > 0),
         }
     }
+
+    /// All sensors the fan curve follows
+    pub fn temperature_keys(&self) -> Vec<&str> {
+        std::iter::once(self.temperature_key.as_str())
+            .chain(self.extra_temperature_keys.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Selects the sensors the curve follows, the first one becomes the primary key
+    pub fn set_temperature_keys(&mut self, keys: &[String]) -> anyhow::Result<()> {
+        let (first, rest) = keys
+            .split_first()
+            .context("At least one temperature sensor has to be selected")?;
+        self.temperature_key.clone_from(first);
+        self.extra_temperature_keys = rest.to_vec();
+        Ok(())
+    }
 }
 
 pub fn default_fan_static_speed() -> f64 {
@@ -164,22 +430,102 @@ impl Config {
     pub fn load() -> anyhow::Result<Option<Self>> {
         let path = get_path();
         if path.exists() {
-            let raw_config = fs::read_to_string(path).context("Could not open config file")?;
-            let config =
-                serde_yaml::from_str(&raw_config).context("Could not deserialize config")?;
+            let raw_config = fs::read_to_string(&path).context("Could not open config file")?;
+            let config = Self::parse(&raw_config)
+                .with_context(|| format!("Invalid config in {}", path.display()))?;
+            if config.daemon.tcp_auth_token.is_some() || config.daemon.web_auth_token.is_some() {
+                if let Err(err) = restrict_permissions(&path) {
+                    warn!(
+                        "could not restrict the permissions of the config with auth tokens: {err}"
+                    );
+                }
+            }
             Ok(Some(config))
         } else {
+            let legacy_path = path.with_file_name(LEGACY_FILE_NAME);
+            if legacy_path.exists() {
+                return migrate_legacy_config(&legacy_path).map(Some);
+            }
+
             let parent = path.parent().unwrap();
             fs::create_dir_all(parent)?;
             Ok(None)
         }
     }
 
+    /// Parses a TOML config. Errors point to the line and key of the invalid setting.
+    fn parse(raw_config: &str) -> anyhow::Result<Self> {
+        #[derive(Deserialize)]
+        struct SchemaVersion {
+            #[serde(default = "default_schema_version")]
+            schema_version: u32,
+        }
+
+        let SchemaVersion { schema_version } = toml::from_str(raw_config)?;
+        if schema_version > CONFIG_SCHEMA_VERSION {
+            bail!(
+                "Config schema version {schema_version} is not supported, the latest known version is {CONFIG_SCHEMA_VERSION}"
+            );
+        }
+
+        let mut config: Self = toml::from_str(raw_config)?;
+        // Older versions only differ in the file format
+        config.schema_version = CONFIG_SCHEMA_VERSION;
+        Ok(config)
+    }
+
     pub fn save(&self) -> anyhow::Result<()> {
         let path = get_path();
         debug!("saving config to {path:?}");
-        let raw_config = serde_yaml::to_string(self)?;
-        fs::write(path, raw_config).context("Could not write config")
+        let raw_config = toml::to_string(self)?;
+        write_private_file(&path, raw_config.as_bytes()).context("Could not write config")
+    }
+
+    /// Checks a config that was edited by hand before it gets applied
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let all_gpus = std::iter::once(("gpus".to_owned(), &self.gpus)).chain(
+            self.profiles
+                .iter()
+                .map(|(name, profile)| (format!("profiles.\"{name}\".gpus"), &profile.gpus)),
+        );
+        for (gpus_key, gpus) in all_gpus {
+            for (id, gpu) in gpus {
+                if let Some(settings) = &gpu.fan_control_settings {
+                    let key = format!("{gpus_key}.\"{id}\".fan_control_settings");
+                    settings
+                        .curve
+                        .validate(settings.curve_input)
+                        .with_context(|| format!("Invalid {key}.curve"))?;
+                    for (channel, curve) in &settings.fan_curves {
+                        curve
+                            .validate(settings.curve_input)
+                            .with_context(|| format!("Invalid {key}.fan_curves.{channel}"))?;
+                    }
+                }
+            }
+        }
+
+        if let Some(profile) = &self.current_profile {
+            if !self.profiles.contains_key(profile) {
+                bail!("Invalid current_profile: there is no profile named \"{profile}\"");
+            }
+        }
+        if let Some(profile) = &self.fallback_profile {
+            if !self.profiles.contains_key(profile) {
+                bail!("Invalid fallback_profile: there is no profile named \"{profile}\"");
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists the settings that differ from the previous config, e.g. `gpus.<id>.power_cap changed`
+    pub fn changes_from(&self, old: &Config) -> Vec<String> {
+        let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(self)) else {
+            return vec![];
+        };
+        let mut changes = vec![];
+        diff_values("", 0, &old, &new, &mut changes);
+        changes
     }
 
     pub fn load_or_create() -> anyhow::Result<Self> {
@@ -220,6 +566,21 @@ impl Config {
         }
     }
 
+    /// Shell commands of the alerts of every GPU, including the ones in profiles
+    pub fn alert_commands(&self) -> BTreeSet<String> {
+        self.gpus
+            .values()
+            .chain(
+                self.profiles
+                    .values()
+                    .flat_map(|profile| profile.gpus.values()),
+            )
+            .flat_map(|gpu| &gpu.alerts)
+            .flat_map(Alert::commands)
+            .map(str::to_owned)
+            .collect()
+    }
+
     /// Get a specific profile
     pub fn profile(&self, profile: &str) -> anyhow::Result<&Profile> {
         self.profiles
@@ -231,6 +592,8 @@ impl Config {
     pub fn default_profile(&self) -> Profile {
         Profile {
             gpus: self.gpus.clone(),
+            rule: None,
+            schedule: None,
         }
     }
 
@@ -238,6 +601,181 @@ impl Config {
         self.gpus.clear();
         self.profiles.clear();
         self.current_profile = None;
+        self.fallback_profile = None;
+    }
+
+    /// Removes the settings of GPUs that don't match the predicate from all profiles.
+    /// Returns the ids of the removed GPUs.
+    pub fn retain_gpus(&mut self, f: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut removed = Vec::new();
+
+        let all_gpus = std::iter::once(&mut self.gpus)
+            .chain(self.profiles.values_mut().map(|profile| &mut profile.gpus));
+        for gpus in all_gpus {
+            gpus.retain(|id, _| {
+                let keep = f(id);
+                if !keep && !removed.contains(id) {
+                    removed.push(id.clone());
+                }
+                keep
+            });
+        }
+        self.gpu_labels.retain(|id, _| f(id));
+
+        removed.sort();
+        removed
+    }
+
+    /// Ids of all GPUs that have settings or a label, in any profile
+    pub fn gpu_ids(&self) -> BTreeSet<String> {
+        std::iter::once(&self.gpus)
+            .chain(self.profiles.values().map(|profile| &profile.gpus))
+            .flat_map(HashMap::keys)
+            .chain(self.gpu_labels.keys())
+            .cloned()
+            .collect()
+    }
+
+    /// Moves the settings of GPUs to other ids in all profiles
+    pub fn rename_gpus(&mut self, renames: &BTreeMap<String, String>) {
+        let rename = |id: String| renames.get(&id).cloned().unwrap_or(id);
+
+        let all_gpus = std::iter::once(&mut self.gpus)
+            .chain(self.profiles.values_mut().map(|profile| &mut profile.gpus));
+        for gpus in all_gpus {
+            *gpus = gpus.drain().map(|(id, gpu)| (rename(id), gpu)).collect();
+        }
+        self.gpu_labels = self
+            .gpu_labels
+            .drain()
+            .map(|(id, label)| (rename(id), label))
+            .collect();
+    }
+
+    /// Clears the auth tokens, for copies of the config that other users can see, such as debug snapshots
+    pub fn without_secrets(&self) -> Self {
+        let mut config = self.clone();
+        config.daemon.tcp_auth_token = None;
+        config.daemon.web_auth_token = None;
+        config
+    }
+
+    /// Removes settings that are specific to this system, such as the auth tokens, so that the config can be shared
+    pub fn sanitized(&self) -> Self {
+        Self {
+            daemon: Daemon::default(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Pairs GPU ids from another system with the GPUs of this one, as the PCI slot in the id usually differs between systems.
+/// An identical id is preferred, then a GPU of the same model from the same board vendor, then any GPU of the same model.
+/// Every local GPU is only used once. Returns the ids that changed, from the imported id to the local one.
+pub fn match_gpu_ids<'a>(
+    imported: impl IntoIterator<Item = &'a String>,
+    local: impl IntoIterator<Item = &'a String>,
+) -> BTreeMap<String, String> {
+    // Ids have the format `vendor:device-subsystem_vendor:subsystem_device-pci_slot`
+    fn board(id: &str) -> Option<&str> {
+        id.rsplit_once('-').map(|(board, _)| board)
+    }
+    fn model(id: &str) -> Option<&str> {
+        id.split_once('-').map(|(model, _)| model)
+    }
+
+    let mut unmatched: Vec<&String> = imported.into_iter().collect();
+    let mut available: Vec<&String> = local.into_iter().collect();
+    unmatched.retain(|id| match available.iter().position(|local| local == id) {
+        Some(index) => {
+            available.remove(index);
+            false
+        }
+        None => true,
+    });
+
+    let mut renames = BTreeMap::new();
+    for key in [board as fn(&str) -> Option<&str>, model] {
+        unmatched.retain(|id| {
+            let Some(imported_key) = key(id) else {
+                return true;
+            };
+            match available
+                .iter()
+                .position(|local| key(local) == Some(imported_key))
+            {
+                Some(index) => {
+                    renames.insert((*id).clone(), available.remove(index).clone());
+                    false
+                }
+                None => true,
+            }
+        });
+    }
+    renames
+}
+
+/// The full daemon configuration in a portable format, used for backups
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExportedState {
+    pub version: u32,
+    pub config: Config,
+}
+
+/// Clamps a configured value to the range supported by the hardware.
+/// Configs can come from backups or other systems, so out of range values are expected and should not fail the whole apply.
+pub fn clamp_setting<T: PartialOrd + Copy + fmt::Display>(
+    name: &str,
+    value: T,
+    min: Option<T>,
+    max: Option<T>,
+) -> T {
+    let clamped = match (min, max) {
+        (Some(min), _) if value < min => min,
+        (_, Some(max)) if value > max => max,
+        _ => value,
+    };
+
+    if clamped != value {
+        warn!("configured {name} {value} is outside of the supported range, using {clamped}");
+    }
+    clamped
+}
+
+/// How many levels of nesting are described separately in config changes
+const MAX_DIFF_DEPTH: usize = 3;
+
+fn diff_values(
+    path: &str,
+    depth: usize,
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    changes: &mut Vec<String>,
+) {
+    use serde_json::Value;
+
+    if old == new {
+        return;
+    }
+
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) if depth < MAX_DIFF_DEPTH => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                let key_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_values(&key_path, depth + 1, old, new, changes),
+                    (None, Some(_)) => changes.push(format!("{key_path} added")),
+                    (Some(_), None) => changes.push(format!("{key_path} removed")),
+                    (None, None) => (),
+                }
+            }
+        }
+        _ => changes.push(format!("{path} changed")),
     }
 }
 
@@ -328,7 +866,69 @@ impl notify::EventHandler for SenderEventHandler {
     }
 }
 
-fn get_path() -> PathBuf {
+/// Converts the legacy YAML config to the current format, keeping the old file as a backup
+fn migrate_legacy_config(legacy_path: &Path) -> anyhow::Result<Config> {
+    let raw_config =
+        fs::read_to_string(legacy_path).context("Could not open legacy config file")?;
+    let mut config: Config = serde_yaml::from_str(&raw_config)
+        .with_context(|| format!("Invalid config in {}", legacy_path.display()))?;
+    config.schema_version = CONFIG_SCHEMA_VERSION;
+    config.save()?;
+
+    let backup_path = legacy_path.with_extension("yaml.bak");
+    fs::rename(legacy_path, &backup_path).with_context(|| {
+        format!(
+            "Could not move the legacy config to {}",
+            backup_path.display()
+        )
+    })?;
+    info!(
+        "migrated the config to {}, the old one was kept at {}",
+        get_path().display(),
+        backup_path.display()
+    );
+    Ok(config)
+}
+
+/// Writes a file that only its owner can read, as the config holds the auth tokens.
+/// The contents go to a temporary file first, which replaces the file at once so it's never left half written.
+fn write_private_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&temp_path)?;
+    // The mode is only used for new files, a leftover temporary file may have other permissions
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)
+}
+
+/// Makes a file that was written by hand, or by an older version, readable only by its owner
+fn restrict_permissions(path: &Path) -> io::Result<()> {
+    let mode = fs::metadata(path)?.permissions().mode();
+    if mode & 0o077 != 0 {
+        info!("restricting config permissions to the owner, as it contains auth tokens");
+        fs::set_permissions(path, Permissions::from_mode(mode & 0o700))?;
+    }
+    Ok(())
+}
+
+/// Path of the config file, which is in the user's config directory when not running as root
+pub fn get_path() -> PathBuf {
+    // Tests that save the config must not replace the one of the machine running them
+    if cfg!(test) {
+        return env::temp_dir()
+            .join(format!("lact-test-config-{}", std::process::id()))
+            .join(FILE_NAME);
+    }
+
     let uid = getuid();
     if uid.is_root() {
         PathBuf::from("/etc/lact").join(FILE_NAME)
@@ -345,12 +945,125 @@ fn default_apply_settings_timer() -> u64 {
     5
 }
 
+fn default_reset_on_exit() -> bool {
+    true
+}
+
+fn default_adaptive_stats_polling() -> bool {
+    true
+}
+
+fn default_stats_history_hours() -> u64 {
+    24
+}
+
+fn default_idle_timeout() -> u64 {
+    300
+}
+
+fn default_schema_version() -> u32 {
+    CONFIG_SCHEMA_VERSION
+}
+
+/// Maps with non-string keys, which TOML doesn't support
+mod string_keys {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+    use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+    pub fn serialize<K: Display, V: Serialize, S: Serializer>(
+        map: &BTreeMap<K, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(key, value)| (key.to_string(), value)))
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<BTreeMap<K, V>, D::Error>
+    where
+        K: FromStr + Ord,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        BTreeMap::<String, V>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(key, value)| {
+                let parsed = key
+                    .parse()
+                    .map_err(|_| de::Error::custom(format!("invalid key `{key}`")))?;
+                Ok((parsed, value))
+            })
+            .collect()
+    }
+}
+
+/// TOML has no null values, so unset heuristics are written as empty strings
+mod optional_heuristics {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Heuristic {
+        Set(i32),
+        /// Null in the legacy YAML config
+        Unset(Option<String>),
+    }
+
+    pub fn serialize<S: Serializer>(
+        components: &[Vec<Option<i32>>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(components.iter().map(|heuristics| {
+            heuristics
+                .iter()
+                .map(|value| match value {
+                    Some(value) => Heuristic::Set(*value),
+                    None => Heuristic::Unset(Some(String::new())),
+                })
+                .collect::<Vec<_>>()
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<Option<i32>>>, D::Error> {
+        let components = Vec::<Vec<Heuristic>>::deserialize(deserializer)?;
+        Ok(components
+            .into_iter()
+            .map(|heuristics| {
+                heuristics
+                    .into_iter()
+                    .map(|value| match value {
+                        Heuristic::Set(value) => Some(value),
+                        Heuristic::Unset(_) => None,
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{ClocksConfiguration, Config, Daemon, FanControlSettings, Gpu};
-    use crate::server::gpu_controller::fan_control::FanCurve;
-    use lact_schema::{FanControlMode, PmfwOptions};
-    use std::collections::HashMap;
+    use super::{
+        clamp_setting, match_gpu_ids, restrict_permissions, write_private_file,
+        ClocksConfiguration, Config, Daemon, ExportedState, FanControlSettings, Gpu, Profile,
+        EXPORTED_STATE_VERSION,
+    };
+    use crate::{
+        server::{
+            gpu_controller::fan_control::{CurveTiming, FanCurve},
+            hooks::HookEventKind,
+        },
+        tests::TempDir,
+    };
+    use amdgpu_sysfs::gpu_handle::PowerLevelKind;
+    use lact_schema::{
+        request::SetClocksCommand, FanControlMode, FanCurveInput, FanSpeedUnit, PmfwOptions,
+    };
+    use std::{
+        collections::{BTreeMap, HashMap},
+        fs::{self, Permissions},
+        os::unix::fs::PermissionsExt,
+    };
 
     #[test]
     fn serde_de_full() {
@@ -363,11 +1076,16 @@ mod tests {
                     fan_control_settings: Some(FanControlSettings {
                         curve: FanCurve::default(),
                         temperature_key: "edge".to_owned(),
+                        extra_temperature_keys: vec!["junction".to_owned()],
                         interval_ms: 500,
                         mode: FanControlMode::Curve,
                         static_speed: 0.5,
                         spindown_delay_ms: Some(5000),
                         change_threshold: Some(3),
+                        max_ramp_rate: Some(10),
+                        fan_curves: [(2, FanCurve::default())].into(),
+                        speed_unit: FanSpeedUnit::Rpm,
+                        curve_input: FanCurveInput::Composite { power_weight: 0.5 },
                     }),
                     ..Default::default()
                 },
@@ -380,6 +1098,292 @@ mod tests {
         assert_eq!(config, deserialized_config);
     }
 
+    #[test]
+    fn toml_roundtrip() {
+        let config = Config {
+            gpus: [(
+                "1002:73BF-1458:2318-0000:03:00.0".to_owned(),
+                Gpu {
+                    fan_control_enabled: true,
+                    fan_control_settings: Some(FanControlSettings {
+                        curve: FanCurve([(40, 0.35), (80, 1.0)].into()),
+                        fan_curves: [(2, FanCurve::default())].into(),
+                        curve_input: FanCurveInput::Power,
+                        ..Default::default()
+                    }),
+                    power_cap: Some(200.0),
+                    custom_power_profile_mode_hueristics: vec![vec![Some(1), None, Some(3)]],
+                    power_states: [(PowerLevelKind::CoreClock, vec![0, 2])].into(),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let data = toml::to_string(&config).unwrap();
+        assert!(data.starts_with("schema_version = 2"));
+        assert!(data.contains("40 = 0.35"));
+        assert_eq!(Config::parse(&data).unwrap(), config);
+    }
+
+    #[test]
+    fn copies_without_secrets() {
+        let mut config = Config::default();
+        config.daemon.tcp_auth_token = Some("tcp-secret".to_owned());
+        config.daemon.web_auth_token = Some("web-secret".to_owned());
+        config.daemon.admin_groups = vec!["gpu-admins".to_owned()];
+
+        let data = toml::to_string(&config.without_secrets()).unwrap();
+        assert!(!data.contains("tcp-secret"));
+        assert!(!data.contains("web-secret"));
+        // Other daemon settings are kept, as they help with debugging
+        assert!(data.contains("gpu-admins"));
+        assert_eq!(config.daemon.tcp_auth_token.as_deref(), Some("tcp-secret"));
+    }
+
+    #[test]
+    fn saved_file_is_private() {
+        let dir = TempDir::new("config-save");
+        let path = dir.join("config.toml");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, Permissions::from_mode(0o644)).unwrap();
+
+        write_private_file(&path, b"new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!dir.join("config.toml.tmp").exists());
+    }
+
+    #[test]
+    fn restricts_readable_file() {
+        let dir = TempDir::new("config-permissions");
+        let path = dir.join("config.toml");
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, Permissions::from_mode(0o644)).unwrap();
+
+        restrict_permissions(&path).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn parse_config_versions() {
+        let legacy = "
+daemon:
+  log_level: info
+  admin_groups:
+  - wheel
+gpus:
+  my-gpu:
+    fan_control_enabled: true
+    fan_control_settings:
+      temperature_key: edge
+      interval_ms: 500
+      curve:
+        40: 0.2
+        80: 1.0
+      fan_curves:
+        2:
+          60: 0.5
+    custom_power_profile_mode_hueristics:
+    - - 1
+      - null
+";
+        let config: Config = serde_yaml::from_str(legacy).unwrap();
+        let gpu = &config.gpus().unwrap()["my-gpu"];
+        let settings = gpu.fan_control_settings.as_ref().unwrap();
+        assert_eq!(settings.curve.0, [(40, 0.2), (80, 1.0)].into());
+        assert_eq!(settings.curve_for_channel(2).0, [(60, 0.5)].into());
+        assert_eq!(
+            gpu.custom_power_profile_mode_hueristics,
+            vec![vec![Some(1), None]]
+        );
+
+        let newer = "schema_version = 3\n[daemon]\nlog_level = \"info\"\nadmin_groups = []\n";
+        assert!(Config::parse(newer).is_err());
+
+        let invalid = "[daemon]\nlog_level = 5\nadmin_groups = []\n";
+        let err = Config::parse(invalid).unwrap_err().to_string();
+        assert!(err.contains("line 2"), "{err}");
+    }
+
+    #[test]
+    fn validation_names_key() {
+        let mut config = Config::default();
+        config.profiles.insert(
+            "Silent".to_owned(),
+            Profile {
+                gpus: [(
+                    "gpu".to_owned(),
+                    Gpu {
+                        fan_control_settings: Some(FanControlSettings {
+                            curve: FanCurve([(40, 1.5)].into()),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    },
+                )]
+                .into(),
+                ..Default::default()
+            },
+        );
+        let err = config.validate().unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "Invalid profiles.\"Silent\".gpus.\"gpu\".fan_control_settings.curve"
+        );
+    }
+
+    #[test]
+    fn describe_config_changes() {
+        let old = Config::default();
+        assert!(old.changes_from(&old).is_empty());
+
+        let mut new = old.clone();
+        new.daemon.log_level = "debug".to_owned();
+        new.gpus_mut().unwrap().insert(
+            "1002:687F-1043:0555-0000:0b:00.0".to_owned(),
+            Gpu {
+                power_cap: Some(200.0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            new.changes_from(&old),
+            vec!["daemon.log_level changed", "gpus added",]
+        );
+
+        let mut newer = new.clone();
+        newer
+            .gpus_mut()
+            .unwrap()
+            .get_mut("1002:687F-1043:0555-0000:0b:00.0")
+            .unwrap()
+            .power_cap = Some(180.0);
+        assert_eq!(
+            newer.changes_from(&new),
+            vec!["gpus.1002:687F-1043:0555-0000:0b:00.0.power_cap changed"]
+        );
+    }
+
+    #[test]
+    fn exported_state_roundtrip() {
+        let config = Config {
+            gpus: [("gpu-a".to_owned(), Gpu::default())].into(),
+            profiles: [(
+                "Silent".to_owned(),
+                Profile {
+                    gpus: [(
+                        "gpu-b".to_owned(),
+                        Gpu {
+                            power_cap: Some(150.0),
+                            ..Default::default()
+                        },
+                    )]
+                    .into(),
+                    rule: None,
+                    schedule: None,
+                },
+            )]
+            .into(),
+            current_profile: Some("Silent".to_owned()),
+            ..Default::default()
+        };
+        let state = ExportedState {
+            version: EXPORTED_STATE_VERSION,
+            config,
+        };
+
+        let data = serde_json::to_string(&state).unwrap();
+        let deserialized_state: ExportedState = serde_json::from_str(&data).unwrap();
+        assert_eq!(state, deserialized_state);
+    }
+
+    #[test]
+    fn retain_gpus_in_all_profiles() {
+        let mut config = Config {
+            gpus: [
+                ("present".to_owned(), Gpu::default()),
+                ("missing".to_owned(), Gpu::default()),
+            ]
+            .into(),
+            profiles: [(
+                "Max".to_owned(),
+                Profile {
+                    gpus: [("missing".to_owned(), Gpu::default())].into(),
+                    rule: None,
+                    schedule: None,
+                },
+            )]
+            .into(),
+            gpu_labels: [("missing".to_owned(), "Old card".to_owned())].into(),
+            ..Default::default()
+        };
+
+        let removed = config.retain_gpus(|id| id == "present");
+        assert_eq!(removed, vec!["missing".to_owned()]);
+        assert!(config.gpus.contains_key("present"));
+        assert!(config.profile("Max").unwrap().gpus.is_empty());
+        assert!(config.gpu_labels.is_empty());
+    }
+
+    #[test]
+    fn match_gpus_from_other_system() {
+        let ids = |ids: &[&str]| ids.iter().map(|id| (*id).to_owned()).collect::<Vec<_>>();
+        let imported = ids(&[
+            "1002:687F-1043:0555-0000:0b:00.0",
+            "1002:73BF-1DA2:E438-0000:03:00.0",
+            "1002:744C-1002:0E3B-0000:0a:00.0",
+            "10DE:2684-10DE:16F3-0000:01:00.0",
+        ]);
+        let local = ids(&[
+            "1002:687F-1043:0555-0000:0b:00.0",
+            "1002:73BF-1043:04F2-0000:0c:00.0",
+            "1002:744C-1002:0E3B-0000:2d:00.0",
+        ]);
+
+        let renames = match_gpu_ids(&imported, &local);
+        assert_eq!(renames.len(), 2);
+        assert_eq!(renames[&imported[1]], local[1]);
+        assert_eq!(renames[&imported[2]], local[2]);
+
+        let mut config = Config {
+            gpus: [(imported[2].clone(), Gpu::default())].into(),
+            gpu_labels: [(imported[2].clone(), "Main".to_owned())].into(),
+            ..Default::default()
+        };
+        config.rename_gpus(&renames);
+        assert!(config.gpus.contains_key(&local[2]));
+        assert_eq!(config.gpu_labels[&local[2]], "Main");
+    }
+
+    #[test]
+    fn clamp_power_cap_above_max() {
+        let cap = clamp_setting("power cap", 400.0, Some(50.0), Some(300.0));
+        assert!((cap - 300.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn clamp_offset_below_min() {
+        assert_eq!(
+            clamp_setting("voltage offset", -500, Some(-200), Some(0)),
+            -200
+        );
+    }
+
+    #[test]
+    fn clamp_in_range_unchanged() {
+        assert_eq!(
+            clamp_setting("max core clock", 2000, Some(500), Some(3000)),
+            2000
+        );
+        assert_eq!(clamp_setting("max core clock", 3500, None, None), 3500);
+        assert_eq!(clamp_setting("max core clock", 3500, Some(500), None), 3500);
+    }
+
     #[test]
     fn clocks_configuration_applied() {
         let mut gpu = Gpu {
@@ -392,10 +1396,70 @@ mod tests {
             power_profile_mode_index: None,
             custom_power_profile_mode_hueristics: vec![],
             power_states: HashMap::new(),
+            fan_control_hwmon_path: None,
+            thermal_target: None,
+            app_power_caps: HashMap::new(),
+            alerts: vec![],
+            apu_limits: BTreeMap::new(),
+            stats_interval_ms: None,
+            low_latency: false,
+            runtime_pm: None,
         };
 
         assert!(!gpu.is_core_clocks_used());
         gpu.clocks_configuration.voltage_offset = Some(10);
         assert!(gpu.is_core_clocks_used());
     }
+
+    #[test]
+    fn gpu_curve_points_sorted_and_replaced() {
+        let mut gpu = Gpu::default();
+
+        for (index, clockspeed) in [(2, 1800), (0, 800), (2, 1900)] {
+            gpu.apply_clocks_command(&SetClocksCommand::GpuCurvePoint {
+                index,
+                clockspeed,
+                voltage: 1000,
+            });
+        }
+
+        let points: Vec<_> = gpu
+            .clocks_configuration
+            .gpu_curve
+            .iter()
+            .map(|point| (point.index, point.clockspeed))
+            .collect();
+        assert_eq!(points, vec![(0, 800), (2, 1900)]);
+
+        gpu.apply_clocks_command(&SetClocksCommand::Reset);
+        assert!(gpu.clocks_configuration.gpu_curve.is_empty());
+    }
+
+    #[test]
+    fn memory_states_kept_separate_from_curve() {
+        let mut gpu = Gpu::default();
+
+        gpu.apply_clocks_command(&SetClocksCommand::MemoryState {
+            index: 2,
+            clockspeed: 2050,
+            voltage: 950,
+        });
+        gpu.apply_clocks_command(&SetClocksCommand::MemoryState {
+            index: 1,
+            clockspeed: 1000,
+            voltage: 900,
+        });
+
+        let clocks = &gpu.clocks_configuration;
+        assert!(clocks.gpu_curve.is_empty());
+        assert_eq!(
+            clocks
+                .memory_states
+                .iter()
+                .map(|point| (point.index, point.clockspeed, point.voltage))
+                .collect::<Vec<_>>(),
+            vec![(1, 1000, 900), (2, 2050, 950)]
+        );
+        assert!(gpu.is_core_clocks_used());
+    }
 }
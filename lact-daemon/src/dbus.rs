@@ -0,0 +1,195 @@
+use crate::server::handler::Handler;
+use anyhow::Context;
+use std::{collections::HashMap, time::Duration};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::interval,
+};
+use tracing::{error, info};
+use zbus::{connection, fdo, interface, Connection, SignalContext};
+
+pub const BUS_NAME: &str = "org.lact.Daemon";
+const OBJECT_PATH: &str = "/org/lact/Daemon";
+const SIGNAL_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Minimum temperature change (in degrees) that triggers a new signal
+const TEMPERATURE_SIGNAL_THRESHOLD: f32 = 1.0;
+
+enum Query {
+    ListDevices,
+    DeviceInfo(String),
+    DeviceStats(String),
+    Config,
+}
+
+type QueryReply = oneshot::Sender<anyhow::Result<String>>;
+
+/// Read-only view of the daemon's API. Changing settings is only possible through the socket,
+/// which is restricted to admin users.
+///
+/// The handler is not thread-safe, so the queries are forwarded to a local task that owns it.
+struct DaemonInterface {
+    sender: mpsc::Sender<(Query, QueryReply)>,
+}
+
+impl DaemonInterface {
+    async fn query(&self, query: Query) -> fdo::Result<String> {
+        let (tx, rx) = oneshot::channel();
+        self.sender
+            .send((query, tx))
+            .await
+            .map_err(|_| fdo::Error::Failed("Daemon is shutting down".to_owned()))?;
+
+        rx.await
+            .map_err(|_| fdo::Error::Failed("Daemon is shutting down".to_owned()))?
+            .map_err(|err| fdo::Error::Failed(format!("{err:#}")))
+    }
+}
+
+#[interface(name = "org.lact.Daemon")]
+impl DaemonInterface {
+    /// JSON list of the available GPUs
+    async fn list_devices(&self) -> fdo::Result<String> {
+        self.query(Query::ListDevices).await
+    }
+
+    /// JSON-encoded information about the given GPU
+    async fn device_info(&self, id: String) -> fdo::Result<String> {
+        self.query(Query::DeviceInfo(id)).await
+    }
+
+    /// JSON-encoded current stats of the given GPU
+    async fn device_stats(&self, id: String) -> fdo::Result<String> {
+        self.query(Query::DeviceStats(id)).await
+    }
+
+    /// JSON-encoded current configuration
+    async fn config(&self) -> fdo::Result<String> {
+        self.query(Query::Config).await
+    }
+
+    #[zbus(signal)]
+    async fn temperature_changed(
+        ctxt: &SignalContext<'_>,
+        id: &str,
+        sensor: &str,
+        temperature: f64,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn throttling_changed(
+        ctxt: &SignalContext<'_>,
+        id: &str,
+        reasons: Vec<String>,
+    ) -> zbus::Result<()>;
+}
+
+pub async fn serve(handler: Handler) {
+    let (sender, receiver) = mpsc::channel(16);
+
+    match connect(sender).await {
+        Ok(connection) => {
+            info!("D-Bus interface available as {BUS_NAME}");
+            tokio::task::spawn_local(emit_signals(connection, handler.clone()));
+            answer_queries(receiver, handler).await;
+        }
+        Err(err) => error!("could not register D-Bus interface: {err:#}"),
+    }
+}
+
+async fn connect(sender: mpsc::Sender<(Query, QueryReply)>) -> anyhow::Result<Connection> {
+    Box::pin(
+        connection::Builder::system()?
+            .name(BUS_NAME)?
+            .serve_at(OBJECT_PATH, DaemonInterface { sender })?
+            .build(),
+    )
+    .await
+    .context("Could not connect to the system bus")
+}
+
+async fn answer_queries(mut receiver: mpsc::Receiver<(Query, QueryReply)>, handler: Handler) {
+    while let Some((query, reply)) = receiver.recv().await {
+        let result = match query {
+            Query::ListDevices => to_json(&handler.list_devices()),
+            Query::DeviceInfo(id) => handler.get_device_info(&id).and_then(|info| to_json(&info)),
+            Query::DeviceStats(id) => handler.get_gpu_stats(&id).and_then(|stats| to_json(&stats)),
+            Query::Config => {
+                // The bus is readable by any user, so the auth tokens must not be exposed
+                to_json(&handler.config.borrow().without_secrets())
+            }
+        };
+        let _ = reply.send(result);
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> anyhow::Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+async fn emit_signals(connection: Connection, handler: Handler) {
+    let ctxt = match SignalContext::new(&connection, OBJECT_PATH) {
+        Ok(ctxt) => ctxt,
+        Err(err) => {
+            error!("could not create D-Bus signal context: {err}");
+            return;
+        }
+    };
+
+    let mut last_temperatures: HashMap<(String, String), f32> = HashMap::new();
+    let mut last_throttling: HashMap<String, Vec<String>> = HashMap::new();
+
+    let mut interval = interval(SIGNAL_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        // The controllers are queried directly so that the signals don't feed the stats smoother
+        let stats: Vec<_> = {
+            let config = handler.config.borrow();
+            let gpus = config.gpus().ok();
+            handler
+                .controllers()
+                .iter()
+                .map(|(id, controller)| {
+                    let gpu_config = gpus.and_then(|gpus| gpus.get(id));
+                    (id.clone(), controller.get_stats(gpu_config))
+                })
+                .collect()
+        };
+
+        for (id, stats) in stats {
+            for (sensor, temperature) in &stats.temps {
+                let Some(current) = temperature.current else {
+                    continue;
+                };
+
+                let key = (id.clone(), sensor.clone());
+                let changed = match last_temperatures.get(&key) {
+                    Some(last) => (current - last).abs() >= TEMPERATURE_SIGNAL_THRESHOLD,
+                    None => true,
+                };
+                if changed {
+                    last_temperatures.insert(key, current);
+                    if let Err(err) =
+                        DaemonInterface::temperature_changed(&ctxt, &id, sensor, f64::from(current))
+                            .await
+                    {
+                        error!("could not emit D-Bus signal: {err}");
+                    }
+                }
+            }
+
+            let reasons: Vec<String> = stats
+                .throttle_info
+                .map(|info| info.into_keys().collect())
+                .unwrap_or_default();
+            if last_throttling.get(&id) != Some(&reasons) {
+                if let Err(err) =
+                    DaemonInterface::throttling_changed(&ctxt, &id, reasons.clone()).await
+                {
+                    error!("could not emit D-Bus signal: {err}");
+                }
+                last_throttling.insert(id, reasons);
+            }
+        }
+    }
+}
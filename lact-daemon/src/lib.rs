@@ -2,16 +2,19 @@
 #![allow(clippy::missing_panics_doc)]
 
 mod config;
+mod dbus;
 mod server;
 mod socket;
 mod suspend;
+#[cfg(test)]
+mod tests;
 
 use anyhow::Context;
 use config::Config;
 use futures::future::select_all;
-use server::{handle_stream, handler::Handler, Server};
-use std::str::FromStr;
-use std::{os::unix::net::UnixStream as StdUnixStream, time::Duration};
+use lact_schema::args::DaemonArgs;
+use server::{gpu_controller::fan_watchdog, handle_stream, handler::Handler, ClientAccess, Server};
+use std::{os::unix::net::UnixStream as StdUnixStream, str::FromStr, time::Duration};
 use tokio::net::UnixStream;
 use tokio::{
     runtime,
@@ -26,6 +29,8 @@ pub const AMDGPU_FAMILY_GC_11_0_0: u32 = 145;
 pub use server::system::MODULE_CONF_PATH;
 
 const MIN_SYSTEM_UPTIME_SECS: f32 = 15.0;
+/// How often to check whether the daemon can exit when it was started through socket activation
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 const SHUTDOWN_SIGNALS: [SignalKind; 4] = [
     SignalKind::terminate(),
     SignalKind::interrupt(),
@@ -33,11 +38,11 @@ const SHUTDOWN_SIGNALS: [SignalKind; 4] = [
     SignalKind::hangup(),
 ];
 
-/// Run the daemon, binding to the default socket.
+/// Run the daemon, binding to the socket from the arguments or the configured one.
 ///
 /// # Errors
 /// Returns an error when the daemon cannot initialize.
-pub fn run() -> anyhow::Result<()> {
+pub fn run(args: &DaemonArgs) -> anyhow::Result<()> {
     let rt = runtime::Builder::new_current_thread()
         .enable_all()
         .build()
@@ -49,14 +54,26 @@ pub fn run() -> anyhow::Result<()> {
         tracing_subscriber::fmt().with_max_level(max_level).init();
 
         ensure_sufficient_uptime().await;
+        fan_watchdog::install();
 
         LocalSet::new()
             .run_until(async move {
-                let server = Server::new(config).await?;
+                let server = Server::new(config, args).await?;
                 let handler = server.handler.clone();
 
+                let idle_timeout = handler.config.borrow().daemon.idle_timeout;
+                if server.socket_activated && idle_timeout > 0 {
+                    tokio::task::spawn_local(exit_when_idle(
+                        handler.clone(),
+                        Duration::from_secs(idle_timeout),
+                    ));
+                }
+
                 tokio::task::spawn_local(listen_config_changes(handler.clone()));
                 tokio::task::spawn_local(listen_exit_signals(handler.clone()));
+                if handler.config.borrow().daemon.dbus_interface {
+                    tokio::task::spawn_local(dbus::serve(handler.clone()));
+                }
                 tokio::task::spawn_local(suspend::listen_events(handler));
                 server.run().await;
                 Ok(())
@@ -65,6 +82,45 @@ pub fn run() -> anyhow::Result<()> {
     })
 }
 
+/// Apply the configured settings and exit, without listening for clients.
+/// Used for applying the settings at boot when the daemon itself is only started through socket activation.
+///
+/// # Errors
+/// Returns an error when the daemon cannot initialize.
+pub fn apply_once() -> anyhow::Result<()> {
+    let rt = runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Could not initialize tokio runtime");
+    rt.block_on(async {
+        let config = Config::load_or_create()?;
+
+        let max_level = Level::from_str(&config.daemon.log_level).context("Invalid log level")?;
+        tracing_subscriber::fmt().with_max_level(max_level).init();
+
+        ensure_sufficient_uptime().await;
+
+        LocalSet::new()
+            .run_until(async move {
+                let handler = Handler::new(config, None).await?;
+
+                // Fan curves that are not stored in the firmware stop with the daemon, so the fans can't be left in manual mode
+                if handler
+                    .controllers()
+                    .iter()
+                    .any(|(_, controller)| controller.has_fan_control_task())
+                {
+                    warn!("fan curves that are not handled by the firmware need the daemon to keep running, handing fan control back to the GPU");
+                    handler.release_fan_control_tasks().await;
+                }
+
+                info!("settings applied");
+                Ok(())
+            })
+            .await
+    })
+}
+
 /// Run the daemon with a given `UnixStream`.
 /// This will NOT bind to a socket by itself, and the daemon will only be accessible via the given stream.
 ///
@@ -78,11 +134,12 @@ pub fn run_embedded(stream: StdUnixStream) -> anyhow::Result<()> {
     rt.block_on(async {
         LocalSet::new()
             .run_until(async move {
+                fan_watchdog::install();
                 let config = Config::default();
-                let handler = Handler::new(config).await?;
+                let handler = Handler::new(config, None).await?;
                 let stream = UnixStream::try_from(stream)?;
 
-                handle_stream(stream, handler).await
+                handle_stream(stream, handler, ClientAccess::Trusted).await
             })
             .await
     })
@@ -96,7 +153,28 @@ async fn listen_exit_signals(handler: Handler) {
 
     info!("cleaning up and shutting down...");
     async {
-        handler.cleanup().await;
+        let reset_on_exit = handler.config.borrow().daemon.reset_on_exit;
+        if reset_on_exit {
+            let result = handler.cleanup().await;
+            if result.is_ok() {
+                info!("all GPUs were reset");
+            } else {
+                for (id, errors) in result.errors {
+                    error!("GPU {id} could not be fully reset: {}", errors.join(", "));
+                }
+            }
+        } else {
+            warn!("resetting on exit is disabled, custom clocks, voltages and power limits will stay applied without the daemon running");
+            // Settings waiting for confirmation are not saved, so the confirmed ones are restored instead of leaving them applied
+            if handler.is_config_pending() {
+                info!("reverting settings that were not confirmed");
+                if let Err(err) = handler.apply_current_config().await {
+                    error!("could not revert unconfirmed settings: {err:#}");
+                }
+            }
+            handler.release_fan_control().await;
+        }
+        handler.flush_state();
         socket::cleanup();
     }
     .instrument(debug_span!("shutdown_cleanup"))
@@ -104,17 +182,58 @@ async fn listen_exit_signals(handler: Handler) {
     std::process::exit(0);
 }
 
+/// Exits once no clients have been connected for the given time, as systemd starts the daemon again on the next connection.
+/// The settings are left applied instead of being reset like on a regular shutdown.
+async fn exit_when_idle(handler: Handler, idle_timeout: Duration) {
+    let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if !handler.event_log.is_idle_for(idle_timeout) {
+            continue;
+        }
+        if let Some(reason) = handler.idle_exit_blocker() {
+            debug!("not exiting while idle, as {reason}");
+            continue;
+        }
+
+        info!(
+            "no clients were connected for {}s, exiting",
+            idle_timeout.as_secs()
+        );
+        handler.flush_state();
+        socket::cleanup();
+        std::process::exit(0);
+    }
+}
+
 async fn listen_config_changes(handler: Handler) {
     let mut rx = config::start_watcher(handler.config_last_saved.clone());
     while let Some(new_config) = rx.recv().await {
-        info!("config file was changed, reloading");
-        handler.config.replace(new_config);
+        let changes = new_config.changes_from(&handler.config.borrow());
+        if changes.is_empty() {
+            debug!("config file was changed, but the settings are the same");
+            continue;
+        }
+        info!("config file was changed: {}", changes.join(", "));
+
+        if let Err(err) = new_config.validate() {
+            error!("not applying the new config: {err:#}");
+            continue;
+        }
+
+        let old_config = handler.config.replace(new_config);
         match handler.apply_current_config().await {
             Ok(()) => {
                 info!("configuration reloaded");
             }
             Err(err) => {
-                error!("could not apply new config: {err:#}");
+                error!("could not apply new config, restoring the previous one: {err:#}");
+                handler.config.replace(old_config);
+                if let Err(err) = handler.apply_current_config().await {
+                    error!("could not reapply the previous config: {err:#}");
+                }
             }
         }
     }
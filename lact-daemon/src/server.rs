@@ -1,62 +1,188 @@
+mod alerts;
+mod app_power_caps;
+mod config_history;
+mod energy;
+mod event_log;
 pub mod gpu_controller;
+mod gpu_processes;
 pub mod handler;
+pub(crate) mod hooks;
+mod hotplug;
+mod http;
+mod metrics;
+mod polkit;
+mod profile_comparison;
+mod profile_watcher;
+pub(crate) mod rate_limit;
+mod runtime_pm;
+mod sampling;
+mod stats_history;
+mod stats_smoothing;
 pub(crate) mod system;
+mod system_sensors;
+mod thermal_target;
+mod throttle_tracker;
+mod undervolt;
 mod vulkan;
+mod web;
 
 use self::handler::Handler;
 use crate::{config::Config, socket};
-use anyhow::Context;
+use anyhow::{anyhow, bail, Context};
 use futures::future::join_all;
-use lact_schema::{Pong, Request, Response};
+use lact_schema::{
+    args::DaemonArgs, ConnectionEvent, DeviceEvent, Pong, Request, Response, PROTOCOL_VERSION,
+};
+use nix::unistd::Gid;
 use serde::Serialize;
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 use tokio::{
     io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
     net::{TcpListener, UnixListener},
+    select,
+    sync::broadcast::error::RecvError,
+    time::{interval, timeout, MissedTickBehavior},
 };
-use tracing::{error, info, instrument, trace};
+use tracing::{error, info, instrument, trace, warn};
+
+/// How long a client has to finish sending a request after it started sending it
+const REQUEST_READ_TIMEOUT_SECS: u64 = 5;
+/// Shortest interval stats subscriptions can be updated at
+const MIN_STATS_INTERVAL_MS: u64 = 100;
 
 pub struct Server {
     pub handler: Handler,
+    /// Whether the socket was passed by systemd instead of being created by the daemon
+    pub socket_activated: bool,
     unix_listener: UnixListener,
     tcp_listener: Option<TcpListener>,
+    /// Group whose members don't need polkit authorization, set when polkit is enabled
+    admin_gid: Option<Gid>,
+}
+
+/// What a client can do without further authorization
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientAccess {
+    /// Local clients with access to the socket
+    Trusted,
+    /// Remote clients, which have to authenticate with the TCP auth token when one is configured
+    Tcp,
+    /// Local clients outside of the admin group, which need polkit authorization to change settings
+    Polkit { pid: i32, uid: u32 },
+    /// Local clients that could not be identified, which can only read information
+    ReadOnly,
 }
 
 impl Server {
-    pub async fn new(config: Config) -> anyhow::Result<Self> {
-        let unix_listener = socket::listen(&config.daemon.admin_groups)?;
+    pub async fn new(config: Config, args: &DaemonArgs) -> anyhow::Result<Self> {
+        let polkit = config.daemon.polkit;
+        let (unix_listener, socket_activated) = match socket::listen_activated(polkit)? {
+            Some(listener) => (listener, true),
+            None => {
+                let socket_path = socket::get_socket_path(
+                    args.socket_path.as_deref(),
+                    config.daemon.socket_path.as_deref(),
+                );
+                (
+                    socket::listen(socket_path, &config.daemon.admin_groups, polkit)?,
+                    false,
+                )
+            }
+        };
+
+        let admin_gid = if polkit {
+            info!("changes from users outside of the socket group are authorized through polkit");
+            Some(socket::socket_group(&unix_listener)?)
+        } else {
+            None
+        };
 
         let tcp_listener = if let Some(address) = &config.daemon.tcp_listen_address {
             let listener = TcpListener::bind(address)
                 .await
                 .with_context(|| format!("Could not bind to TCP address {address}"))?;
             info!("TCP listening on {}", listener.local_addr()?);
+            if config.daemon.tcp_auth_token.is_none() {
+                warn!("no TCP auth token is configured, anyone with network access can control the GPUs");
+            }
             Some(listener)
         } else {
             info!("TCP listener disabled");
             None
         };
 
-        let handler = Handler::new(config).await?;
+        let handler = Handler::new(config, args.test_sysfs.clone()).await?;
 
         Ok(Self {
             handler,
+            socket_activated,
             unix_listener,
             tcp_listener,
+            admin_gid,
         })
     }
 
     pub async fn run(self) {
         let mut tasks = vec![];
 
+        let metrics_address = self
+            .handler
+            .config
+            .borrow()
+            .daemon
+            .metrics_listen_address
+            .clone();
+        if let Some(address) = metrics_address {
+            if let Err(err) = metrics::listen(&address, self.handler.clone()).await {
+                error!("{err:#}");
+            }
+        }
+
+        let web_address = self
+            .handler
+            .config
+            .borrow()
+            .daemon
+            .web_listen_address
+            .clone();
+        if let Some(address) = web_address {
+            if let Err(err) = web::listen(&address, self.handler.clone()).await {
+                error!("{err:#}");
+            }
+        }
+
+        tokio::task::spawn_local(profile_watcher::run(self.handler.clone()));
+        tokio::task::spawn_local(thermal_target::run(self.handler.clone()));
+        tokio::task::spawn_local(alerts::run(self.handler.clone()));
+        tokio::task::spawn_local(app_power_caps::run(self.handler.clone()));
+        tokio::task::spawn_local(throttle_tracker::run(self.handler.clone()));
+        tokio::task::spawn_local(runtime_pm::run(self.handler.clone()));
+        tokio::task::spawn_local(energy::run(self.handler.clone()));
+        tokio::task::spawn_local(hotplug::run(self.handler.clone()));
+
+        let stats_history_interval = self.handler.config.borrow().daemon.stats_history_interval;
+        if let (Some(interval), Some(history)) =
+            (stats_history_interval, self.handler.stats_history.clone())
+        {
+            tokio::task::spawn_local(stats_history::run(
+                self.handler.clone(),
+                history,
+                Duration::from_secs(interval.max(1)),
+            ));
+        }
+
         let unix_handler = self.handler.clone();
         let unix_task = tokio::task::spawn_local(async move {
             loop {
                 match self.unix_listener.accept().await {
                     Ok((stream, _)) => {
                         let handler = unix_handler.clone();
+                        let access = match self.admin_gid {
+                            Some(admin_gid) => polkit::client_access(&stream, admin_gid),
+                            None => ClientAccess::Trusted,
+                        };
                         tokio::task::spawn_local(async move {
-                            if let Err(error) = handle_stream(stream, handler).await {
+                            if let Err(error) = handle_stream(stream, handler, access).await {
                                 error!("{error}");
                             }
                         });
@@ -76,7 +202,9 @@ impl Server {
                         Ok((stream, _)) => {
                             let handler = self.handler.clone();
                             tokio::task::spawn_local(async move {
-                                if let Err(error) = handle_stream(stream, handler).await {
+                                if let Err(error) =
+                                    handle_stream(stream, handler, ClientAccess::Tcp).await
+                                {
                                     error!("{error}");
                                 }
                             });
@@ -98,22 +226,117 @@ impl Server {
 pub async fn handle_stream<T: AsyncRead + AsyncWrite + Unpin>(
     stream: T,
     handler: Handler,
+    access: ClientAccess,
+) -> anyhow::Result<()> {
+    let connection_id = handler.event_log.connect();
+
+    let result = handle_stream_requests(stream, &handler, connection_id, access).await;
+
+    handler.event_log.disconnect(
+        connection_id,
+        result.as_ref().err().map(|err| format!("{err:#}")),
+    );
+    result
+}
+
+async fn handle_stream_requests<T: AsyncRead + AsyncWrite + Unpin>(
+    stream: T,
+    handler: &Handler,
+    connection_id: u64,
+    access: ClientAccess,
 ) -> anyhow::Result<()> {
     let mut stream = BufReader::new(stream);
+    let mut authenticated =
+        access != ClientAccess::Tcp || handler.config.borrow().daemon.tcp_auth_token.is_none();
+    // Polkit authorization is kept for the rest of the connection
+    let mut authorized = false;
 
     let mut buf = String::new();
-    while stream.read_line(&mut buf).await? != 0 {
+    loop {
+        // Waiting for the next request is not limited, as clients keep their connections open between requests
+        if stream.fill_buf().await?.is_empty() {
+            break;
+        }
+
+        timeout(
+            Duration::from_secs(REQUEST_READ_TIMEOUT_SECS),
+            stream.read_line(&mut buf),
+        )
+        .await
+        .with_context(|| {
+            format!("Client did not finish sending a request within {REQUEST_READ_TIMEOUT_SECS}s, dropping connection")
+        })??;
+
         trace!("handling request: {}", buf.trim_end());
 
-        let maybe_request = serde_json::from_str(&buf);
+        let maybe_request = serde_json::from_str::<Request>(&buf);
+
+        if let (true, Ok(Request::SubscribeStats { id, interval_ms })) =
+            (authenticated, &maybe_request)
+        {
+            handler.event_log.push(
+                connection_id,
+                ConnectionEvent::Request {
+                    command: "subscribe_stats".to_owned(),
+                },
+            );
+            return stream_stats(&mut stream, handler, id, *interval_ms).await;
+        }
+        if let (true, Ok(Request::SubscribeDeviceEvents)) = (authenticated, &maybe_request) {
+            handler.event_log.push(
+                connection_id,
+                ConnectionEvent::Request {
+                    command: "subscribe_device_events".to_owned(),
+                },
+            );
+            return stream_device_events(&mut stream, handler).await;
+        }
+
         let response = match maybe_request {
-            Ok(request) => match handle_request(request, &handler).await {
-                Ok(response) => response,
-                Err(error) => serde_json::to_vec(&Response::<()>::from(error))?,
-            },
-            Err(error) => serde_json::to_vec(&Response::<()>::from(
-                anyhow::Error::new(error).context("Failed to deserialize"),
-            ))?,
+            Ok(request) => {
+                let command = serde_json::to_value(&request)
+                    .ok()
+                    .and_then(|value| value.get("command")?.as_str().map(str::to_owned))
+                    .unwrap_or_default();
+                handler
+                    .event_log
+                    .push(connection_id, ConnectionEvent::Request { command });
+
+                let result = match request {
+                    Request::Authenticate { ref token } => check_auth_token(handler, token)
+                        .and_then(|()| {
+                            authenticated = true;
+                            ok_response(())
+                        }),
+                    _ if !authenticated => Err(anyhow!("Authentication required")),
+                    request => match authorize(&request, access, &mut authorized).await {
+                        Ok(()) => handle_request(request, handler).await,
+                        Err(err) => Err(err),
+                    },
+                };
+
+                match result {
+                    Ok(response) => {
+                        handler.event_log.push(connection_id, ConnectionEvent::Ok);
+                        response
+                    }
+                    Err(error) => {
+                        handler
+                            .event_log
+                            .push(connection_id, ConnectionEvent::Error(format!("{error:#}")));
+                        serde_json::to_vec(&Response::<()>::from(error))?
+                    }
+                }
+            }
+            Err(error) => {
+                let error = anyhow::Error::new(error).context(format!(
+                    "Failed to deserialize request, make sure the client uses protocol version {PROTOCOL_VERSION}"
+                ));
+                handler
+                    .event_log
+                    .push(connection_id, ConnectionEvent::Error(format!("{error:#}")));
+                serde_json::to_vec(&Response::<()>::from(error))?
+            }
         };
 
         stream.write_all(&response).await?;
@@ -125,25 +348,173 @@ pub async fn handle_stream<T: AsyncRead + AsyncWrite + Unpin>(
     Ok(())
 }
 
+/// Sends stats updates until the client disconnects
+async fn stream_stats<T: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut BufReader<T>,
+    handler: &Handler,
+    id: &str,
+    interval_ms: u64,
+) -> anyhow::Result<()> {
+    if let Err(error) = handler.get_gpu_stats(id) {
+        stream
+            .write_all(&serde_json::to_vec(&Response::<()>::from(error))?)
+            .await?;
+        stream.write_all(b"\n").await?;
+        return Ok(());
+    }
+    stream.write_all(&ok_response(())?).await?;
+    stream.write_all(b"\n").await?;
+
+    let mut interval = interval(Duration::from_millis(
+        interval_ms.max(MIN_STATS_INTERVAL_MS),
+    ));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        select! {
+            _ = interval.tick() => (),
+            read = stream.fill_buf() => {
+                match read {
+                    Ok(data) if !data.is_empty() => {
+                        return Err(anyhow!("Client sent a request on a stats subscription"));
+                    }
+                    // The client closed the connection
+                    _ => return Ok(()),
+                }
+            }
+        }
+
+        let response = match handler.get_gpu_stats(id) {
+            Ok(stats) => ok_response(stats)?,
+            Err(error) => serde_json::to_vec(&Response::<()>::from(error))?,
+        };
+        stream.write_all(&response).await?;
+        stream.write_all(b"\n").await?;
+    }
+}
+
+/// Checks that the client may send the request, asking polkit the first time a polkit client changes settings
+async fn authorize(
+    request: &Request<'_>,
+    access: ClientAccess,
+    authorized: &mut bool,
+) -> anyhow::Result<()> {
+    if *authorized || request.is_read_only() {
+        return Ok(());
+    }
+
+    match access {
+        ClientAccess::Trusted | ClientAccess::Tcp => Ok(()),
+        ClientAccess::Polkit { pid, uid } => {
+            polkit::check_authorization(pid, uid).await?;
+            *authorized = true;
+            Ok(())
+        }
+        ClientAccess::ReadOnly => Err(anyhow!(
+            "Could not identify the client process, only reading information is allowed"
+        )),
+    }
+}
+
+/// Sends events about added and removed GPUs until the client disconnects
+async fn stream_device_events<T: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut BufReader<T>,
+    handler: &Handler,
+) -> anyhow::Result<()> {
+    let mut events = handler.subscribe_device_events();
+    stream.write_all(&ok_response(())?).await?;
+    stream.write_all(b"\n").await?;
+
+    loop {
+        let event: DeviceEvent = select! {
+            event = events.recv() => match event {
+                Ok(event) => event,
+                Err(RecvError::Lagged(count)) => {
+                    warn!("device event subscriber missed {count} events");
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            read = stream.fill_buf() => {
+                match read {
+                    Ok(data) if !data.is_empty() => {
+                        return Err(anyhow!("Client sent a request on a device events subscription"));
+                    }
+                    // The client closed the connection
+                    _ => return Ok(()),
+                }
+            }
+        };
+
+        stream.write_all(&ok_response(event)?).await?;
+        stream.write_all(b"\n").await?;
+    }
+}
+
+fn check_auth_token(handler: &Handler, token: &str) -> anyhow::Result<()> {
+    match &handler.config.borrow().daemon.tcp_auth_token {
+        Some(expected) if constant_time_eq(expected.as_bytes(), token.as_bytes()) => Ok(()),
+        Some(_) => Err(anyhow!("Invalid authentication token")),
+        None => Ok(()),
+    }
+}
+
+/// Compares the values without returning early, to not leak the token through response timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 #[instrument(level = "debug", skip(handler))]
 async fn handle_request<'a>(request: Request<'a>, handler: &'a Handler) -> anyhow::Result<Vec<u8>> {
     match request {
         Request::Ping => ok_response(ping()),
+        Request::Authenticate { .. } => ok_response(()),
         Request::SystemInfo => ok_response(system::info().await?),
         Request::ListDevices => ok_response(handler.list_devices()),
         Request::DeviceInfo { id } => ok_response(handler.get_device_info(id)?),
+        Request::SetGpuLabel { id, label } => ok_response(handler.set_gpu_label(id, label)?),
         Request::DeviceStats { id } => ok_response(handler.get_gpu_stats(id)?),
+        Request::SubscribeStats { .. } | Request::SubscribeDeviceEvents => Err(anyhow!(
+            "Subscriptions are only available on authenticated connections"
+        )),
         Request::DeviceClocksInfo { id } => ok_response(handler.get_clocks_info(id)?),
         Request::DevicePowerProfileModes { id } => {
             ok_response(handler.get_power_profile_modes(id)?)
         }
+        Request::DeviceCapabilities { id } => ok_response(handler.get_capabilities(id)?),
         Request::SetFanControl(opts) => ok_response(handler.set_fan_control(opts).await?),
+        Request::SetPmfwOptions { id, options } => {
+            ok_response(handler.set_pmfw_options(id, options).await?)
+        }
         Request::ResetPmfw { id } => ok_response(handler.reset_pmfw(id).await?),
+        Request::ResetFanControl { id } => ok_response(handler.reset_fan_control(id).await?),
+        Request::ResetClocks { id } => ok_response(handler.reset_clocks(id).await?),
+        Request::ResetPowerCap { id } => ok_response(handler.reset_power_cap(id).await?),
         Request::SetPowerCap { id, cap } => ok_response(handler.set_power_cap(id, cap).await?),
+        Request::SetThermalTarget { id, temperature } => {
+            ok_response(handler.set_thermal_target(id, temperature).await?)
+        }
+        Request::GetAlerts { id } => ok_response(handler.get_alerts(id)?),
+        Request::SetAlerts { id, alerts } => ok_response(handler.set_alerts(id, alerts).await?),
+        Request::SetAppPowerCap {
+            id,
+            process,
+            power_cap,
+        } => ok_response(handler.set_app_power_cap(id, process, power_cap).await?),
         Request::SetPerformanceLevel {
             id,
             performance_level,
         } => ok_response(handler.set_performance_level(id, performance_level).await?),
+        Request::SetBenchmarkLock { id, enabled } => {
+            ok_response(handler.set_benchmark_lock(id, enabled)?)
+        }
+        Request::SetLowLatencyMode { id, enabled } => {
+            ok_response(handler.set_low_latency_mode(id, enabled).await?)
+        }
+        Request::SetRuntimePm { id, auto } => ok_response(handler.set_runtime_pm(id, auto).await?),
+        Request::ApplyIdleOptimization { id } => {
+            ok_response(handler.apply_idle_optimization(id).await?)
+        }
         Request::SetClocksValue { id, command } => {
             ok_response(handler.set_clocks_value(id, command).await?)
         }
@@ -163,22 +534,100 @@ async fn handle_request<'a>(request: Request<'a>, handler: &'a Handler) -> anyho
         Request::SetEnabledPowerStates { id, kind, states } => {
             ok_response(handler.set_enabled_power_states(id, kind, states).await?)
         }
+        Request::PciePowerStates { id } => ok_response(handler.get_pcie_power_states(id)?),
+        Request::MemoryStates { id } => ok_response(handler.get_memory_states(id)?),
+        Request::LockMemoryState { id, state } => {
+            ok_response(handler.lock_memory_state(id, state).await?)
+        }
+        Request::SetMinPcieState { id, state } => {
+            ok_response(handler.set_min_pcie_state(id, state).await?)
+        }
+        Request::SetMinVramState { id, state } => {
+            ok_response(handler.set_min_vram_state(id, state).await?)
+        }
+        Request::SetApuLimit { id, kind, value } => {
+            ok_response(handler.set_apu_limit(id, kind, value).await?)
+        }
+        Request::UndoLastChange { id } => ok_response(handler.undo_last_change(id).await?),
+        Request::RedoLastChange { id } => ok_response(handler.redo_last_change(id).await?),
+        Request::ConfigHistory { id } => ok_response(handler.config_history.info(id)),
+        Request::GetProcesses { id } => ok_response(handler.get_processes(id)?),
+        Request::GetSensorChips => ok_response(handler.get_sensor_chips()),
         Request::VbiosDump { id } => ok_response(handler.vbios_dump(id)?),
+        Request::ResetGpu { id, confirm } => ok_response(handler.reset_gpu(id, confirm).await?),
         Request::ListProfiles => ok_response(handler.list_profiles()),
         Request::SetProfile { name } => ok_response(handler.set_profile(name).await?),
         Request::CreateProfile { name, base } => ok_response(handler.create_profile(name, base)?),
         Request::DeleteProfile { name } => ok_response(handler.delete_profile(name).await?),
+        Request::SetProfileRule { name, rule } => {
+            ok_response(handler.set_profile_rule(&name, rule)?)
+        }
+        Request::SetProfileSchedule { name, schedule } => {
+            ok_response(handler.set_profile_schedule(&name, schedule)?)
+        }
+        Request::SetAutoSwitchProfiles { enabled } => {
+            ok_response(handler.set_auto_switch_profiles(enabled)?)
+        }
+        Request::SetFallbackProfile { name } => ok_response(handler.set_fallback_profile(name)?),
         Request::EnableOverdrive => ok_response(system::enable_overdrive().await?),
         Request::DisableOverdrive => ok_response(system::disable_overdrive().await?),
         Request::GenerateSnapshot => ok_response(handler.generate_snapshot().await?),
+        Request::GetEventLog => ok_response(handler.event_log.entries()),
+        Request::GetEnergyStats { id } => ok_response(handler.get_energy_stats(id)?),
+        Request::SetEnergyPrice { price } => ok_response(handler.set_energy_price(price)?),
+        Request::StartUndervolt { id, options } => {
+            ok_response(handler.start_undervolt(id, options)?)
+        }
+        Request::GetUndervoltStatus { id } => ok_response(handler.get_undervolt_status(id)?),
+        Request::CancelUndervolt { id } => ok_response(handler.cancel_undervolt(id)?),
+        Request::StartProfileComparison { id, options } => {
+            ok_response(handler.start_profile_comparison(id, options)?)
+        }
+        Request::GetProfileComparisonStatus { id } => {
+            ok_response(handler.get_profile_comparison_status(id)?)
+        }
+        Request::CancelProfileComparison { id } => {
+            ok_response(handler.cancel_profile_comparison(id)?)
+        }
+        Request::GetStatsHistory { id, since } => {
+            ok_response(handler.get_stats_history(id, since)?)
+        }
+        Request::PreviewFanCurve { id, curve, since } => {
+            ok_response(handler.preview_fan_curve(id, curve, since)?)
+        }
+        Request::ExportState => ok_response(handler.export_state()?),
+        Request::ImportState { state } => ok_response(handler.import_state(&state).await?),
         Request::ConfirmPendingConfig(command) => {
             ok_response(handler.confirm_pending_config(command)?)
         }
-        Request::RestConfig => {
-            handler.reset_config().await;
-            ok_response(())
+        Request::RestConfig => ok_response(handler.reset_config().await),
+        Request::ApplyBatch { id, requests } => {
+            ok_response(apply_batch(id, requests, handler).await?)
+        }
+    }
+}
+
+/// Handles the requests of a batch with their changes collected, then applies them together
+async fn apply_batch<'a>(
+    id: &'a str,
+    requests: Vec<Request<'a>>,
+    handler: &'a Handler,
+) -> anyhow::Result<u64> {
+    if !requests.iter().all(Request::is_batchable) {
+        bail!("Only requests that change the settings of a GPU can be part of a batch");
+    }
+
+    handler.start_batch(id)?;
+    for (index, request) in requests.into_iter().enumerate() {
+        if let Err(err) = Box::pin(handle_request(request, handler)).await {
+            handler.discard_batch();
+            return Err(err.context(format!(
+                "Request {} of the batch failed, no changes were applied",
+                index + 1
+            )));
         }
     }
+    handler.apply_batch().await
 }
 
 fn ok_response<T: Serialize + Debug>(data: T) -> anyhow::Result<Vec<u8>> {
@@ -0,0 +1,443 @@
+use super::{
+    gpu_controller::fan_control::FAN_STALL_MAX_RPM,
+    handler::Handler,
+    hooks::{self, HookEvent},
+};
+use crate::config::{self, FanControlSettings};
+use lact_schema::{
+    ActiveAlert, Alert, AlertAction, DeviceStats, FanControlMode, FanStats, FAN_STALL_SENSOR,
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tokio::time::interval;
+use tracing::{error, info, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How far below the threshold the temperature has to drop before an alert clears
+const HYSTERESIS: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transition {
+    Triggered,
+    Cleared,
+}
+
+#[derive(Default)]
+struct AlertState {
+    above_since: Option<Instant>,
+    active: bool,
+    temperature: f32,
+}
+
+impl AlertState {
+    fn update(&mut self, alert: &Alert, temperature: f32, now: Instant) -> Option<Transition> {
+        self.temperature = temperature;
+
+        if self.active {
+            if temperature < alert.threshold - HYSTERESIS {
+                self.active = false;
+                self.above_since = None;
+                return Some(Transition::Cleared);
+            }
+        } else if temperature > alert.threshold {
+            let since = *self.above_since.get_or_insert(now);
+            if now.saturating_duration_since(since) >= Duration::from_secs(alert.duration) {
+                self.active = true;
+                return Some(Transition::Triggered);
+            }
+        } else {
+            self.above_since = None;
+        }
+        None
+    }
+}
+
+/// State of the configured alerts of every GPU
+#[derive(Default)]
+pub struct AlertTracker {
+    gpus: RefCell<HashMap<String, Vec<(Alert, AlertState)>>>,
+}
+
+impl AlertTracker {
+    /// Checks the alerts against the current sensor values, returning the ones that triggered or cleared.
+    /// Alerts that are removed from the config while active count as cleared.
+    fn update(
+        &self,
+        id: &str,
+        alerts: &[Alert],
+        values: &HashMap<String, f32>,
+        now: Instant,
+    ) -> Vec<(Alert, Transition)> {
+        let mut gpus = self.gpus.borrow_mut();
+        let states = gpus.entry(id.to_owned()).or_default();
+        let mut transitions = Vec::new();
+
+        if states.len() != alerts.len()
+            || states.iter().zip(alerts).any(|((old, _), new)| old != new)
+        {
+            for (alert, state) in states.drain(..) {
+                if state.active {
+                    transitions.push((alert, Transition::Cleared));
+                }
+            }
+            states.extend(
+                alerts
+                    .iter()
+                    .map(|alert| (alert.clone(), AlertState::default())),
+            );
+        }
+
+        for (alert, state) in states.iter_mut() {
+            let Some(&temperature) = values.get(&alert.sensor) else {
+                continue;
+            };
+            if let Some(transition) = state.update(alert, temperature, now) {
+                transitions.push((alert.clone(), transition));
+            }
+        }
+
+        transitions
+    }
+
+    pub fn active(&self, id: &str) -> Vec<ActiveAlert> {
+        self.gpus
+            .borrow()
+            .get(id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, state)| state.active)
+            .map(|(alert, state)| ActiveAlert {
+                sensor: alert.sensor.clone(),
+                threshold: alert.threshold,
+                temperature: state.temperature,
+                notify: alert.actions.contains(&AlertAction::Notify),
+            })
+            .collect()
+    }
+
+    fn active_alerts(&self, id: &str) -> Vec<Alert> {
+        self.gpus
+            .borrow()
+            .get(id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, state)| state.active)
+            .map(|(alert, _)| alert.clone())
+            .collect()
+    }
+}
+
+/// Evaluates the temperature alerts of every GPU and runs their actions.
+/// Fan and power cap overrides are applied on top of the configured settings without saving them,
+/// and the configured settings are restored once no alert needs them anymore.
+pub async fn run(handler: Handler) {
+    let mut interval = interval(POLL_INTERVAL);
+    // Settings with overrides that were last applied to each GPU
+    let mut overridden: HashMap<String, config::Gpu> = HashMap::new();
+
+    loop {
+        interval.tick().await;
+
+        let gpus: Vec<(String, config::Gpu)> = {
+            let config = handler.config.borrow();
+            let Ok(gpus) = config.gpus() else {
+                continue;
+            };
+            gpus.iter()
+                .filter(|(id, gpu)| !gpu.alerts.is_empty() || overridden.contains_key(*id))
+                .map(|(id, gpu)| (id.clone(), gpu.clone()))
+                .collect()
+        };
+        let now = Instant::now();
+
+        for (id, gpu_config) in gpus {
+            let Some(controller) = handler.controller(&id) else {
+                continue;
+            };
+
+            let values = sensor_values(&controller.get_stats(Some(&gpu_config)));
+            let transitions = handler
+                .alert_tracker
+                .update(&id, &gpu_config.alerts, &values, now);
+
+            for (alert, transition) in &transitions {
+                let temperature = values.get(&alert.sensor).copied().unwrap_or_default();
+                match transition {
+                    Transition::Triggered if alert.sensor == FAN_STALL_SENSOR => error!(
+                        "a fan of {id} is not spinning while being driven at {temperature:.0}%, it may have failed"
+                    ),
+                    Transition::Cleared if alert.sensor == FAN_STALL_SENSOR => {
+                        info!("fan stall alert of {id} cleared");
+                    }
+                    Transition::Triggered => warn!(
+                        "{} temperature of {id} is {temperature}°C, above the alert threshold of {}°C",
+                        alert.sensor, alert.threshold
+                    ),
+                    Transition::Cleared => info!(
+                        "{} temperature alert of {id} cleared at {temperature}°C",
+                        alert.sensor
+                    ),
+                }
+                for action in &alert.actions {
+                    if let AlertAction::Command { command } = action {
+                        run_command(command, &id, alert, temperature, *transition);
+                    }
+                }
+
+                let (gpu_id, sensor, threshold) =
+                    (id.clone(), alert.sensor.clone(), alert.threshold);
+                handler.run_hooks(&match transition {
+                    Transition::Triggered => HookEvent::AlertTriggered {
+                        gpu_id,
+                        sensor,
+                        temperature,
+                        threshold,
+                    },
+                    Transition::Cleared => HookEvent::AlertCleared {
+                        gpu_id,
+                        sensor,
+                        temperature,
+                        threshold,
+                    },
+                });
+            }
+
+            // Settings that are waiting for confirmation must not be overwritten
+            if handler.is_config_pending() {
+                continue;
+            }
+
+            let active = handler.alert_tracker.active_alerts(&id);
+            let new_config = with_overrides(&gpu_config, &active);
+            if new_config.as_ref() == overridden.get(&id) {
+                continue;
+            }
+
+            let config_to_apply = match new_config {
+                Some(new_config) => {
+                    info!("applying temperature alert overrides to {id}");
+                    overridden.insert(id.clone(), new_config.clone());
+                    new_config
+                }
+                None => {
+                    info!("no temperature alerts of {id} are active, restoring its settings");
+                    overridden.remove(&id);
+                    gpu_config
+                }
+            };
+            if let Err(err) = controller.apply_config(&config_to_apply).await {
+                error!("could not apply settings for temperature alerts: {err:#}");
+            }
+        }
+    }
+}
+
+/// Current value of every sensor that alerts can watch: the temperatures, and the fan speed of a stalled fan
+fn sensor_values(stats: &DeviceStats) -> HashMap<String, f32> {
+    let mut values: HashMap<String, f32> = stats
+        .temps
+        .iter()
+        .filter_map(|(name, temp)| Some((name.clone(), temp.current?)))
+        .collect();
+    values.insert(FAN_STALL_SENSOR.to_owned(), stalled_fan_speed(&stats.fan));
+    values
+}
+
+/// Speed in percent that the fastest driven fan without any measured RPM runs at, 0 when all of them spin.
+/// Fans that don't report their speed are not considered.
+fn stalled_fan_speed(fan: &FanStats) -> f32 {
+    let readings: Vec<(Option<u8>, Option<u32>)> = if fan.fans.is_empty() {
+        vec![(fan.pwm_current, fan.speed_current)]
+    } else {
+        fan.fans
+            .iter()
+            .map(|channel| (channel.pwm_current, channel.speed_current))
+            .collect()
+    };
+
+    readings
+        .into_iter()
+        .filter_map(|(pwm, rpm)| pwm.zip(rpm))
+        .filter(|(_, rpm)| *rpm < FAN_STALL_MAX_RPM)
+        .map(|(pwm, _)| f32::from(pwm) * 100.0 / f32::from(u8::MAX))
+        .fold(0.0, f32::max)
+}
+
+/// Settings with the fan and power cap overrides of the active alerts, or `None` when there are none
+fn with_overrides(gpu_config: &config::Gpu, active: &[Alert]) -> Option<config::Gpu> {
+    let actions: Vec<&AlertAction> = active.iter().flat_map(|alert| &alert.actions).collect();
+    let max_fans = actions
+        .iter()
+        .any(|action| matches!(action, AlertAction::MaxFans));
+    let power_cap = actions
+        .iter()
+        .filter_map(|action| match action {
+            AlertAction::PowerCap { watts } => Some(*watts),
+            _ => None,
+        })
+        .reduce(f64::min);
+
+    if !max_fans && power_cap.is_none() {
+        return None;
+    }
+
+    let mut new_config = gpu_config.clone();
+    if max_fans {
+        new_config.fan_control_enabled = true;
+        new_config.fan_control_settings = Some(FanControlSettings {
+            mode: FanControlMode::Static,
+            static_speed: 1.0,
+            ..gpu_config.fan_control_settings.clone().unwrap_or_default()
+        });
+    }
+    if let Some(cap) = power_cap {
+        new_config.power_cap = Some(gpu_config.power_cap.map_or(cap, |current| current.min(cap)));
+    }
+    Some(new_config)
+}
+
+/// The command comes from the config file, as the API only accepts commands that are already in it
+fn run_command(command: &str, id: &str, alert: &Alert, temperature: f32, transition: Transition) {
+    let state = match transition {
+        Transition::Triggered => "triggered",
+        Transition::Cleared => "cleared",
+    };
+    let env = [
+        ("LACT_GPU_ID", id.to_owned()),
+        ("LACT_ALERT_STATE", state.to_owned()),
+        ("LACT_ALERT_SENSOR", alert.sensor.clone()),
+        ("LACT_ALERT_THRESHOLD", alert.threshold.to_string()),
+        ("LACT_ALERT_TEMPERATURE", temperature.to_string()),
+    ];
+    hooks::spawn_command(command, &env, None, "alert command");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{stalled_fan_speed, with_overrides, AlertTracker, Transition};
+    use crate::config;
+    use lact_schema::{Alert, AlertAction, FanChannelStats, FanControlMode, FanStats};
+    use std::{
+        collections::HashMap,
+        time::{Duration, Instant},
+    };
+
+    fn junction(temperature: f32) -> HashMap<String, f32> {
+        [("junction".to_owned(), temperature)].into()
+    }
+
+    fn alert(actions: Vec<AlertAction>) -> Alert {
+        Alert {
+            sensor: "junction".to_owned(),
+            threshold: 100.0,
+            duration: 10,
+            actions,
+        }
+    }
+
+    #[test]
+    fn alert_triggers_after_duration() {
+        let tracker = AlertTracker::default();
+        let alerts = [alert(vec![AlertAction::Notify])];
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(tracker
+            .update("gpu", &alerts, &junction(105.0), at(0))
+            .is_empty());
+        // Dropping below the threshold restarts the duration
+        assert!(tracker
+            .update("gpu", &alerts, &junction(95.0), at(5))
+            .is_empty());
+        assert!(tracker
+            .update("gpu", &alerts, &junction(105.0), at(6))
+            .is_empty());
+        assert!(tracker
+            .update("gpu", &alerts, &junction(105.0), at(12))
+            .is_empty());
+
+        let transitions = tracker.update("gpu", &alerts, &junction(105.0), at(16));
+        assert_eq!(transitions, [(alerts[0].clone(), Transition::Triggered)]);
+        assert_eq!(tracker.active("gpu").len(), 1);
+        assert!(tracker.active("gpu")[0].notify);
+
+        // Within the hysteresis the alert stays active
+        assert!(tracker
+            .update("gpu", &alerts, &junction(98.0), at(18))
+            .is_empty());
+        let transitions = tracker.update("gpu", &alerts, &junction(90.0), at(20));
+        assert_eq!(transitions, [(alerts[0].clone(), Transition::Cleared)]);
+        assert!(tracker.active("gpu").is_empty());
+    }
+
+    #[test]
+    fn removed_alert_clears() {
+        let tracker = AlertTracker::default();
+        let alerts = [Alert {
+            duration: 0,
+            ..alert(vec![AlertAction::MaxFans])
+        }];
+        let now = Instant::now();
+
+        tracker.update("gpu", &alerts, &junction(105.0), now);
+        assert_eq!(tracker.active("gpu").len(), 1);
+
+        let transitions = tracker.update("gpu", &[], &junction(105.0), now);
+        assert_eq!(transitions, [(alerts[0].clone(), Transition::Cleared)]);
+    }
+
+    #[test]
+    fn stalled_fan_speed_of_channels() {
+        let fan = FanStats {
+            pwm_current: Some(255),
+            speed_current: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(stalled_fan_speed(&fan), 100.0);
+        assert_eq!(stalled_fan_speed(&FanStats::default()), 0.0);
+
+        let channel = |channel, pwm, rpm| FanChannelStats {
+            channel,
+            pwm_current: Some(pwm),
+            speed_current: Some(rpm),
+            curve: None,
+        };
+        // Only the stopped fan counts, the average speed of all fans is ignored
+        let fan = FanStats {
+            pwm_current: Some(153),
+            speed_current: Some(1200),
+            fans: vec![channel(1, 153, 1800), channel(2, 153, 0), channel(3, 0, 0)],
+            ..Default::default()
+        };
+        assert_eq!(stalled_fan_speed(&fan), 60.0);
+    }
+
+    #[test]
+    fn overrides_of_active_alerts() {
+        let gpu_config = config::Gpu {
+            power_cap: Some(250.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            with_overrides(&gpu_config, &[alert(vec![AlertAction::Notify])]),
+            None
+        );
+
+        let active = [
+            alert(vec![AlertAction::PowerCap { watts: 200.0 }]),
+            alert(vec![
+                AlertAction::PowerCap { watts: 150.0 },
+                AlertAction::MaxFans,
+            ]),
+        ];
+        let new_config = with_overrides(&gpu_config, &active).unwrap();
+        assert_eq!(new_config.power_cap, Some(150.0));
+        assert!(new_config.fan_control_enabled);
+        let fan_settings = new_config.fan_control_settings.unwrap();
+        assert_eq!(fan_settings.mode, FanControlMode::Static);
+        assert_eq!(fan_settings.static_speed, 1.0);
+    }
+}
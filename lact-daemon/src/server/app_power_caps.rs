@@ -0,0 +1,147 @@
+use super::{
+    handler::Handler,
+    profile_watcher::{read_processes, rule_matches, ProcessInfo},
+};
+use crate::config;
+use lact_schema::ProfileRule;
+use std::time::Duration;
+use tokio::time::interval;
+use tracing::{error, info};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Applies the power caps of running applications that have an override, and restores the configured power cap once they exit.
+/// GPUs with a thermal target are left to the thermal target task, which uses the override as its upper limit.
+pub async fn run(handler: Handler) {
+    let mut interval = interval(POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let gpus: Vec<(String, config::Gpu)> = {
+            let config = handler.config.borrow();
+            let Ok(gpus) = config.gpus() else {
+                continue;
+            };
+            let active = handler.app_power_caps.borrow();
+            gpus.iter()
+                .filter(|(id, gpu)| !gpu.app_power_caps.is_empty() || active.contains_key(*id))
+                .map(|(id, gpu)| (id.clone(), gpu.clone()))
+                .collect()
+        };
+        handler
+            .app_power_caps
+            .borrow_mut()
+            .retain(|id, _| gpus.iter().any(|(gpu_id, _)| gpu_id == id));
+
+        // Settings that are waiting for confirmation must not be overwritten
+        if gpus.is_empty() || handler.is_config_pending() {
+            continue;
+        }
+
+        let processes = read_processes();
+
+        for (id, gpu_config) in gpus {
+            let Some(controller) = handler.controller(&id) else {
+                continue;
+            };
+            let Some(hw_mon) = controller.hw_monitors().first() else {
+                continue;
+            };
+
+            let cap = matching_power_cap(&gpu_config, &processes);
+            let previous = match cap {
+                Some(cap) => handler.app_power_caps.borrow_mut().insert(id.clone(), cap),
+                None => handler.app_power_caps.borrow_mut().remove(&id),
+            };
+            if gpu_config.thermal_target.is_some() {
+                continue;
+            }
+
+            match cap {
+                Some(cap) => {
+                    let expected = cap
+                        .max(hw_mon.get_power_cap_min().unwrap_or(cap))
+                        .min(hw_mon.get_power_cap_max().unwrap_or(cap));
+                    // The cap also gets reset when the settings are applied again, e.g. after they were changed
+                    let reset = hw_mon
+                        .get_power_cap()
+                        .is_ok_and(|current| (current - expected).abs() >= 1.0);
+
+                    if previous != Some(cap) || reset {
+                        info!(
+                            "applying power cap of {expected}W to {id} for a running application"
+                        );
+                        if let Err(err) = hw_mon.set_power_cap(expected) {
+                            error!("could not apply application power cap: {err}");
+                        }
+                    }
+                }
+                None if previous.is_some() => {
+                    let cap = gpu_config
+                        .power_cap
+                        .or_else(|| hw_mon.get_power_cap_default().ok());
+                    if let Some(cap) = cap {
+                        info!("no application with a power cap override is running, restoring the power cap of {id} to {cap}W");
+                        if let Err(err) = hw_mon.set_power_cap(cap) {
+                            error!("could not restore power cap: {err}");
+                        }
+                    }
+                }
+                None => (),
+            }
+        }
+    }
+}
+
+/// Lowest power cap of the running applications, so that the strictest override wins
+fn matching_power_cap(gpu_config: &config::Gpu, processes: &[ProcessInfo]) -> Option<f64> {
+    gpu_config
+        .app_power_caps
+        .iter()
+        .filter(|(process, _)| {
+            let rule = ProfileRule {
+                process: (*process).clone(),
+                args: None,
+            };
+            processes.iter().any(|info| rule_matches(&rule, info))
+        })
+        .map(|(_, cap)| *cap)
+        .reduce(f64::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matching_power_cap;
+    use crate::{config, server::profile_watcher::ProcessInfo};
+
+    #[test]
+    fn lowest_matching_cap_wins() {
+        let gpu_config = config::Gpu {
+            app_power_caps: [
+                ("benchmark".to_owned(), 150.0),
+                ("game.exe".to_owned(), 200.0),
+                ("blender".to_owned(), 100.0),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let processes = [
+            ProcessInfo {
+                name: "benchmark".to_owned(),
+                args: vec![],
+            },
+            ProcessInfo {
+                name: "game.exe".to_owned(),
+                args: vec!["C:\\game.exe".to_owned()],
+            },
+        ];
+
+        assert_eq!(matching_power_cap(&gpu_config, &processes), Some(150.0));
+        assert_eq!(
+            matching_power_cap(&gpu_config, &processes[1..]),
+            Some(200.0)
+        );
+        assert_eq!(matching_power_cap(&gpu_config, &[]), None);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::config;
+use lact_schema::ConfigHistoryInfo;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+/// How many changes can be undone per GPU
+const MAX_UNDO_STEPS: usize = 20;
+/// Changes confirmed within this time of the previous one are undone together,
+/// as applying settings in the GUI sends a separate change for every kind of setting
+const COALESCE_WINDOW: Duration = Duration::from_secs(5);
+
+/// What a confirmed settings change does to the history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryAction {
+    Record,
+    Undo,
+    Redo,
+}
+
+#[derive(Default)]
+struct GpuHistory {
+    undo: VecDeque<config::Gpu>,
+    redo: Vec<config::Gpu>,
+    last_record: Option<Instant>,
+}
+
+/// Previous settings of every GPU, so that applied changes can be undone
+#[derive(Default)]
+pub struct ConfigHistory {
+    gpus: RefCell<HashMap<String, GpuHistory>>,
+}
+
+impl ConfigHistory {
+    /// Settings that undoing the last change would restore
+    pub fn undo_target(&self, id: &str) -> Option<config::Gpu> {
+        self.gpus.borrow().get(id)?.undo.back().cloned()
+    }
+
+    /// Settings that redoing the last undone change would restore
+    pub fn redo_target(&self, id: &str) -> Option<config::Gpu> {
+        self.gpus.borrow().get(id)?.redo.last().cloned()
+    }
+
+    /// Updates the history once a change was confirmed, `previous` being the settings it replaced
+    pub fn confirmed(&self, id: &str, action: HistoryAction, previous: config::Gpu, now: Instant) {
+        let mut gpus = self.gpus.borrow_mut();
+        let history = gpus.entry(id.to_owned()).or_default();
+
+        match action {
+            HistoryAction::Record => {
+                let coalesce = history
+                    .last_record
+                    .is_some_and(|last| now.saturating_duration_since(last) < COALESCE_WINDOW);
+                if !coalesce || history.undo.is_empty() {
+                    history.undo.push_back(previous);
+                    if history.undo.len() > MAX_UNDO_STEPS {
+                        history.undo.pop_front();
+                    }
+                }
+                history.redo.clear();
+                history.last_record = Some(now);
+            }
+            HistoryAction::Undo => {
+                history.undo.pop_back();
+                history.redo.push(previous);
+                history.last_record = None;
+            }
+            HistoryAction::Redo => {
+                history.redo.pop();
+                history.undo.push_back(previous);
+                history.last_record = None;
+            }
+        }
+    }
+
+    pub fn info(&self, id: &str) -> ConfigHistoryInfo {
+        self.gpus
+            .borrow()
+            .get(id)
+            .map(|history| ConfigHistoryInfo {
+                undo_steps: history.undo.len(),
+                redo_steps: history.redo.len(),
+            })
+            .unwrap_or_default()
+    }
+
+    /// Forgets all changes, for when the settings were replaced as a whole (e.g. by switching profiles)
+    pub fn clear(&self) {
+        self.gpus.borrow_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigHistory, HistoryAction, COALESCE_WINDOW, MAX_UNDO_STEPS};
+    use crate::config;
+    use std::time::{Duration, Instant};
+
+    fn gpu_with_cap(cap: f64) -> config::Gpu {
+        config::Gpu {
+            power_cap: Some(cap),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let history = ConfigHistory::default();
+        let start = Instant::now();
+        let later = |steps: u32| start + (COALESCE_WINDOW + Duration::from_secs(1)) * steps;
+
+        history.confirmed("gpu", HistoryAction::Record, gpu_with_cap(100.0), start);
+        history.confirmed("gpu", HistoryAction::Record, gpu_with_cap(150.0), later(1));
+        assert_eq!(history.info("gpu").undo_steps, 2);
+        assert_eq!(history.undo_target("gpu"), Some(gpu_with_cap(150.0)));
+
+        // The current settings (200W) are replaced by the undo target
+        history.confirmed("gpu", HistoryAction::Undo, gpu_with_cap(200.0), later(2));
+        assert_eq!(history.undo_target("gpu"), Some(gpu_with_cap(100.0)));
+        assert_eq!(history.redo_target("gpu"), Some(gpu_with_cap(200.0)));
+
+        history.confirmed("gpu", HistoryAction::Redo, gpu_with_cap(150.0), later(3));
+        assert_eq!(history.undo_target("gpu"), Some(gpu_with_cap(150.0)));
+        assert_eq!(history.redo_target("gpu"), None);
+
+        history.confirmed("gpu", HistoryAction::Undo, gpu_with_cap(200.0), later(4));
+        history.confirmed("gpu", HistoryAction::Record, gpu_with_cap(150.0), later(5));
+        assert_eq!(history.info("gpu").redo_steps, 0);
+        assert_eq!(history.info("other").undo_steps, 0);
+    }
+
+    #[test]
+    fn quick_changes_are_coalesced() {
+        let history = ConfigHistory::default();
+        let start = Instant::now();
+
+        history.confirmed("gpu", HistoryAction::Record, gpu_with_cap(100.0), start);
+        history.confirmed(
+            "gpu",
+            HistoryAction::Record,
+            gpu_with_cap(150.0),
+            start + Duration::from_secs(1),
+        );
+        assert_eq!(history.info("gpu").undo_steps, 1);
+        assert_eq!(history.undo_target("gpu"), Some(gpu_with_cap(100.0)));
+
+        for i in 0..MAX_UNDO_STEPS * 2 {
+            let time = start + COALESCE_WINDOW * (u32::try_from(i).unwrap() + 2);
+            history.confirmed("gpu", HistoryAction::Record, gpu_with_cap(0.0), time);
+        }
+        assert_eq!(history.info("gpu").undo_steps, MAX_UNDO_STEPS);
+    }
+}
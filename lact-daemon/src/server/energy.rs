@@ -0,0 +1,160 @@
+use super::{handler::Handler, runtime_pm::is_runtime_suspended, sampling::SampleSchedule};
+use anyhow::Context;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use tokio::time::sleep_until;
+use tracing::{error, warn};
+
+pub const ENERGY_FILE: &str = "/var/lib/lact/energy.json";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+/// Samples further apart than this (e.g. after a suspend) are not integrated
+const MAX_SAMPLE_GAP: Duration = Duration::from_secs(30);
+/// How many days of energy usage are kept per GPU
+const MAX_DAYS: usize = 400;
+
+/// Energy used by every GPU per day, integrated from power samples and persisted across restarts
+pub struct EnergyTracker {
+    path: PathBuf,
+    /// Energy in Wh per local date of every GPU
+    days: RefCell<HashMap<String, BTreeMap<String, f64>>>,
+    last_samples: RefCell<HashMap<String, (Instant, f64)>>,
+}
+
+impl EnergyTracker {
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let days = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!("could not parse energy usage from {path:?}, starting anew: {err}");
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            path,
+            days: RefCell::new(days),
+            last_samples: RefCell::default(),
+        }
+    }
+
+    /// Adds the energy used since the previous sample to the given date.
+    /// `None` means that the power usage could not be read, which interrupts the integration.
+    fn record(&self, id: &str, power: Option<f64>, now: Instant, date: &str) {
+        let mut last_samples = self.last_samples.borrow_mut();
+        let Some(power) = power else {
+            last_samples.remove(id);
+            return;
+        };
+
+        if let Some((last_time, last_power)) = last_samples.get(id) {
+            let elapsed = now.saturating_duration_since(*last_time);
+            if elapsed <= MAX_SAMPLE_GAP {
+                let energy = (last_power + power) / 2.0 * elapsed.as_secs_f64() / 3600.0;
+
+                let mut days = self.days.borrow_mut();
+                let gpu_days = days.entry(id.to_owned()).or_default();
+                *gpu_days.entry(date.to_owned()).or_default() += energy;
+                while gpu_days.len() > MAX_DAYS {
+                    gpu_days.pop_first();
+                }
+            }
+        }
+        last_samples.insert(id.to_owned(), (now, power));
+    }
+
+    pub fn days(&self, id: &str) -> BTreeMap<String, f64> {
+        self.days.borrow().get(id).cloned().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Could not create {parent:?}"))?;
+        }
+        let contents = serde_json::to_string(&*self.days.borrow())?;
+        fs::write(&self.path, contents).with_context(|| format!("Could not write {:?}", self.path))
+    }
+}
+
+/// Periodically samples the power usage of every GPU
+pub async fn run(handler: Handler) {
+    let mut schedule = SampleSchedule::new(POLL_INTERVAL);
+    let mut last_save = Instant::now();
+
+    loop {
+        let now = Instant::now();
+        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let due = schedule.due(&handler, now);
+
+        {
+            let config = handler.config.borrow();
+            let gpus = config.gpus().ok();
+            for id in &due {
+                let Some(controller) = handler.controller(id) else {
+                    continue;
+                };
+                // A powered off GPU doesn't use any energy, and reading its sensors would wake it up
+                let power = if is_runtime_suspended(controller.get_path()) {
+                    Some(0.0)
+                } else {
+                    let gpu_config = gpus.and_then(|gpus| gpus.get(id));
+                    let power = controller.get_stats(gpu_config).power;
+                    power.current.or(power.average)
+                };
+                handler.energy_tracker.record(id, power, now, &date);
+            }
+        }
+
+        if now.saturating_duration_since(last_save) >= SAVE_INTERVAL {
+            last_save = now;
+            if let Err(err) = handler.energy_tracker.save() {
+                error!("could not save energy usage: {err:#}");
+            }
+        }
+
+        sleep_until(schedule.next_wakeup(now).into()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EnergyTracker;
+    use crate::tests::TempDir;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn integrate_power_samples() {
+        let dir = TempDir::new("energy");
+        let path = dir.join("energy.json");
+        let tracker = EnergyTracker::load(&path);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        tracker.record("gpu", Some(100.0), at(0), "2024-05-01");
+        // 150W on average for half an hour
+        tracker.record("gpu", Some(200.0), at(1800), "2024-05-01");
+        assert!(tracker.days("gpu").is_empty());
+
+        tracker.record("gpu", Some(200.0), at(1818), "2024-05-01");
+        tracker.record("gpu", Some(100.0), at(1836), "2024-05-02");
+        assert_eq!(tracker.days("gpu")["2024-05-01"], 1.0);
+        assert_eq!(tracker.days("gpu")["2024-05-02"], 0.75);
+
+        // Missing power readings interrupt the integration
+        tracker.record("gpu", None, at(1838), "2024-05-02");
+        tracker.record("gpu", Some(100.0), at(1840), "2024-05-02");
+        assert_eq!(tracker.days("gpu")["2024-05-02"], 0.75);
+
+        tracker.save().unwrap();
+        let loaded = EnergyTracker::load(&path);
+        assert_eq!(loaded.days("gpu"), tracker.days("gpu"));
+        assert!(loaded.days("other").is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+use lact_schema::{ConnectionEvent, EventLogEntry};
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Maximum amount of events kept in memory
+pub const EVENT_LOG_CAPACITY: usize = 256;
+
+/// Ring buffer of recent protocol events, used for diagnosing client issues
+pub struct EventLog {
+    capacity: usize,
+    entries: RefCell<VecDeque<EventLogEntry>>,
+    last_connection_id: Cell<u64>,
+    active_connections: Cell<usize>,
+    last_disconnect: Cell<Instant>,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: RefCell::new(VecDeque::with_capacity(capacity)),
+            last_connection_id: Cell::new(0),
+            active_connections: Cell::new(0),
+            last_disconnect: Cell::new(Instant::now()),
+        }
+    }
+
+    /// Registers a new connection, returning its id
+    pub fn connect(&self) -> u64 {
+        let connection_id = self.last_connection_id.get() + 1;
+        self.last_connection_id.set(connection_id);
+        self.active_connections
+            .set(self.active_connections.get() + 1);
+
+        self.push(connection_id, ConnectionEvent::Connected);
+        connection_id
+    }
+
+    pub fn disconnect(&self, connection_id: u64, error: Option<String>) {
+        self.active_connections
+            .set(self.active_connections.get().saturating_sub(1));
+        self.last_disconnect.set(Instant::now());
+
+        self.push(connection_id, ConnectionEvent::Disconnected { error });
+    }
+
+    /// Whether no clients have been connected for at least the given duration
+    pub fn is_idle_for(&self, duration: Duration) -> bool {
+        self.active_connections.get() == 0 && self.last_disconnect.get().elapsed() >= duration
+    }
+
+    pub fn push(&self, connection_id: u64, event: ConnectionEvent) {
+        let mut entries = self.entries.borrow_mut();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(EventLogEntry {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            connection_id,
+            event,
+        });
+    }
+
+    pub fn entries(&self) -> Vec<EventLogEntry> {
+        self.entries.borrow().iter().cloned().collect()
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(EVENT_LOG_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EventLog;
+    use lact_schema::ConnectionEvent;
+    use std::time::Duration;
+
+    #[test]
+    fn capacity_is_bounded() {
+        let log = EventLog::new(10);
+        let connection_id = log.connect();
+
+        for i in 0..100 {
+            log.push(
+                connection_id,
+                ConnectionEvent::Request {
+                    command: format!("command_{i}"),
+                },
+            );
+        }
+
+        let entries = log.entries();
+        assert_eq!(entries.len(), 10);
+        assert_eq!(
+            entries.last().unwrap().event,
+            ConnectionEvent::Request {
+                command: "command_99".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn idle_only_without_connections() {
+        let log = EventLog::default();
+        assert!(log.is_idle_for(Duration::ZERO));
+
+        let first = log.connect();
+        let second = log.connect();
+        log.disconnect(first, None);
+        assert!(!log.is_idle_for(Duration::ZERO));
+
+        log.disconnect(second, Some("error".to_owned()));
+        assert!(log.is_idle_for(Duration::ZERO));
+        assert!(!log.is_idle_for(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn connection_ids_increment() {
+        let log = EventLog::default();
+        assert_eq!(log.connect(), 1);
+        assert_eq!(log.connect(), 2);
+        assert_eq!(log.entries().len(), 2);
+    }
+}
@@ -1,31 +1,51 @@
-use super::{fan_control::FanCurve, FanControlHandle, GpuController};
+use super::{
+    apu::{ApuLimitsControl, AMDGPU_IDS_FLAGS_FUSION},
+    fan_control::{
+        channel_pwm, channel_speed, curve_input_value, fan_channels, hottest_temperature,
+        parse_pmfw_setting, pwm_to_rpm, rpm_target_range, set_channel_manual_control,
+        set_channel_pwm, set_channel_rpm, FanCurve, FanSpeedState, FanStallDetector,
+    },
+    fan_watchdog::{FanFallback, FanWatchdog, PmfwFallbackCurve, PMFW_FAN_CURVE_FILE},
+    format_gpu_id, is_writable, od_extra_clocks, od_offsets,
+    pp_features::{
+        enabled_features_mask, enabled_mask, parse_pp_features, DEEP_SLEEP_FEATURES,
+        PP_FEATURES_FILE,
+    },
+    read_pci_info,
+    sysfs_write::{write_sysfs, SysfsWriteError},
+    vbios, FanControlHandle, GpuController,
+};
 use crate::{
-    config::{self, ClocksConfiguration, FanControlSettings},
-    server::vulkan::get_vulkan_info,
+    config::{self, clamp_setting, ClocksConfiguration, FanControlSettings},
+    server::{runtime_pm::apply_runtime_pm, vulkan::get_vulkan_info},
 };
 use amdgpu_sysfs::{
     error::Error,
     gpu_handle::{
-        fan_control::FanCurve as PmfwCurve,
-        overdrive::{ClocksTable, ClocksTableGen},
+        fan_control::{FanCurve as PmfwCurve, FanInfo},
+        overdrive::{ClocksTable, ClocksTableGen, Range},
         power_profile_mode::PowerProfileModesTable,
         CommitHandle, GpuHandle, PerformanceLevel, PowerLevelKind, PowerLevels,
     },
     hw_mon::{FanControlMethod, HwMon},
     sysfs::SysFS,
 };
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use futures::future::LocalBoxFuture;
 use lact_schema::{
-    ClocksInfo, ClockspeedStats, DeviceInfo, DeviceStats, DrmInfo, FanStats, GpuPciInfo, LinkInfo,
-    PciInfo, PmfwInfo, PowerState, PowerStates, PowerStats, VoltageStats, VramStats,
+    AmdOffsetsTable, BenchmarkLockStats, ClocksControlKind, ClocksInfo, ClockspeedStats,
+    DeviceCapabilities, DeviceInfo, DeviceStats, DrmInfo, ExtraClock, ExtraClockRange,
+    FanChannelStats, FanCurveInput, FanSpeedUnit, FanStats, GpuPciInfo, LinkInfo, MemoryState,
+    PmfwInfo, PowerState, PowerStates, PowerStats, ThrottleStatus, VoltageStats, VramStats,
+    OVERDRIVE_DISABLED_ERROR,
 };
-use libdrm_amdgpu_sys::AMDGPU::{ThrottleStatus, ThrottlerBit};
+use libdrm_amdgpu_sys::AMDGPU::{ThrottleStatus as DrmThrottleStatus, ThrottlerBit};
 use pciid_parser::Database;
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     cmp,
     collections::{HashMap, HashSet},
+    ops::RangeInclusive,
     path::{Path, PathBuf},
     rc::Rc,
     time::Duration,
@@ -45,13 +65,26 @@ use {
 };
 
 const GPU_CLOCKDOWN_TIMEOUT_SECS: u64 = 3;
+const PERFORMANCE_LEVEL_FILE: &str = "power_dpm_force_performance_level";
+const BENCHMARK_PERFORMANCE_LEVEL: &str = "profile_peak";
 const MAX_PSTATE_READ_ATTEMPTS: u32 = 5;
+const ZERO_RPM_ENABLE_FILE: &str = "gpu_od/fan_ctrl/fan_zero_rpm_enable";
+const ZERO_RPM_TEMPERATURE_FILE: &str = "gpu_od/fan_ctrl/fan_zero_rpm_stop_temperature";
 
 pub struct AmdGpuController {
     handle: GpuHandle,
     drm_handle: Option<DrmHandle>,
     pci_info: Option<GpuPciInfo>,
     fan_control_handle: RefCell<Option<FanControlHandle>>,
+    /// External hwmon used for fan control along with its original control method
+    external_fan_hw_mon: RefCell<Option<(HwMon, FanControlMethod)>>,
+    /// Raw performance level that was active before the benchmark lock was enabled
+    benchmark_lock: RefCell<Option<String>>,
+    /// Mask of the deep sleep features that were turned off by the low latency mode
+    deep_sleep_disabled: Cell<u64>,
+    fan_stall_detector: RefCell<FanStallDetector>,
+    /// Set on integrated GPUs, which share their power limits with the CPU
+    apu_limits: Option<ApuLimitsControl>,
 }
 
 impl AmdGpuController {
@@ -71,48 +104,29 @@ impl AmdGpuController {
             }
         }
 
-        let mut device_pci_info = None;
-        let mut subsystem_pci_info = None;
+        let pci_info = read_pci_info(&handle, pci_db);
 
-        if let Some((vendor_id, model_id)) = handle.get_pci_id() {
-            device_pci_info = Some(PciInfo {
-                vendor_id: vendor_id.to_owned(),
-                vendor: None,
-                model_id: model_id.to_owned(),
-                model: None,
+        let apu_limits = drm_handle
+            .as_ref()
+            .and_then(|drm_handle| drm_handle.device_info().ok())
+            .filter(|drm_info| drm_info.ids_flags & AMDGPU_IDS_FLAGS_FUSION != 0)
+            .map(|drm_info| {
+                ApuLimitsControl::new(
+                    &drm_info.get_asic_name().to_string(),
+                    handle.hw_monitors.first(),
+                )
             });
 
-            if let Some((subsys_vendor_id, subsys_model_id)) = handle.get_pci_subsys_id() {
-                let pci_device_info =
-                    pci_db.get_device_info(vendor_id, model_id, subsys_vendor_id, subsys_model_id);
-
-                device_pci_info = Some(PciInfo {
-                    vendor_id: vendor_id.to_owned(),
-                    vendor: pci_device_info.vendor_name.map(str::to_owned),
-                    model_id: model_id.to_owned(),
-                    model: pci_device_info.device_name.map(str::to_owned),
-                });
-                subsystem_pci_info = Some(PciInfo {
-                    vendor_id: subsys_vendor_id.to_owned(),
-                    vendor: pci_device_info.subvendor_name.map(str::to_owned),
-                    model_id: subsys_model_id.to_owned(),
-                    model: pci_device_info.subdevice_name.map(str::to_owned),
-                });
-            };
-        }
-
-        let pci_info = device_pci_info.and_then(|device_pci_info| {
-            Some(GpuPciInfo {
-                device_pci_info,
-                subsystem_pci_info: subsystem_pci_info?,
-            })
-        });
-
         Ok(Self {
             handle,
             drm_handle,
             pci_info,
             fan_control_handle: RefCell::new(None),
+            external_fan_hw_mon: RefCell::new(None),
+            benchmark_lock: RefCell::new(None),
+            deep_sleep_disabled: Cell::new(0),
+            fan_stall_detector: RefCell::new(FanStallDetector::default()),
+            apu_limits,
         })
     }
 
@@ -124,15 +138,73 @@ impl AmdGpuController {
         self.handle.hw_monitors.first().map(f)
     }
 
+    /// Voltage in mV of the channel with the given label, as the channel numbers differ between dGPUs and APUs
+    fn read_labeled_voltage(&self, label: &str) -> Option<u64> {
+        let hw_mon = self.handle.hw_monitors.first()?;
+        read_labeled_voltage(hw_mon.get_path(), label)
+    }
+
+    /// Gets the hardware monitor used for fan control, which is either the configured external one or the GPU's own
+    fn fan_hw_mon(&self, external_hw_mon: Option<HwMon>) -> anyhow::Result<HwMon> {
+        match external_hw_mon {
+            Some(hw_mon) => {
+                self.use_external_fan_hw_mon(&hw_mon);
+                Ok(hw_mon)
+            }
+            None => self
+                .handle
+                .hw_monitors
+                .first()
+                .cloned()
+                .context("This GPU has no monitor"),
+        }
+    }
+
+    fn use_external_fan_hw_mon(&self, hw_mon: &HwMon) {
+        let mut guard = self.external_fan_hw_mon.borrow_mut();
+        if guard
+            .as_ref()
+            .is_some_and(|(current, _)| current.get_path() == hw_mon.get_path())
+        {
+            return;
+        }
+
+        let original_method = hw_mon
+            .get_fan_control_method()
+            .unwrap_or(FanControlMethod::Auto);
+        *guard = Some((hw_mon.clone(), original_method));
+    }
+
+    /// Gives control of a previously used external hwmon back to its original mode
+    fn restore_external_fan_hw_mon(&self) {
+        if let Some((hw_mon, original_method)) = self.external_fan_hw_mon.borrow_mut().take() {
+            debug!(
+                "restoring fan control method {original_method:?} on {:?}",
+                hw_mon.get_path()
+            );
+            if let Err(err) = hw_mon.set_fan_control_method(original_method) {
+                warn!("could not restore external fan control method: {err}");
+            }
+        }
+    }
+
     async fn set_static_fan_control(
         &self,
         static_speed: f64,
+        speed_unit: FanSpeedUnit,
+        external_hw_mon: Option<HwMon>,
     ) -> anyhow::Result<Option<CommitHandle>> {
         // Stop existing task to set static speed
         self.stop_fan_control(false).await?;
 
         // Use PMFW curve functionality for static speed when it is available
-        if let Ok(current_curve) = self.handle.get_fan_curve() {
+        let pmfw_curve = if external_hw_mon.is_none() && speed_unit == FanSpeedUnit::Pwm {
+            self.handle.get_fan_curve().ok()
+        } else {
+            None
+        };
+
+        if let Some(current_curve) = pmfw_curve {
             let allowed_ranges = current_curve.allowed_ranges.clone().ok_or_else(|| {
                 anyhow!("The GPU does not allow setting custom fan values (is overdrive enabled?)")
             })?;
@@ -162,12 +234,19 @@ impl AmdGpuController {
 
             Ok(Some(commit_handle))
         } else {
-            let hw_mon = self
-                .handle
-                .hw_monitors
-                .first()
-                .cloned()
-                .context("This GPU has no monitor")?;
+            let is_external = external_hw_mon.is_some();
+            let hw_mon = self.fan_hw_mon(external_hw_mon)?;
+
+            let extra_channels = if is_external {
+                vec![]
+            } else {
+                extra_fan_channels(&hw_mon)
+            };
+            // Checked before touching any fan, so that an unsupported mode doesn't leave them half configured
+            let rpm_ranges = std::iter::once(1)
+                .chain(extra_channels.iter().copied())
+                .map(|channel| channel_rpm_range(&hw_mon, channel, speed_unit))
+                .collect::<anyhow::Result<Vec<_>>>()?;
 
             hw_mon
                 .set_fan_control_method(FanControlMethod::Manual)
@@ -176,9 +255,12 @@ impl AmdGpuController {
             #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
             let static_pwm = (f64::from(u8::MAX) * static_speed) as u8;
 
-            hw_mon
-                .set_fan_pwm(static_pwm)
-                .context("could not set fan speed")?;
+            for (channel, rpm_range) in std::iter::once(1).chain(extra_channels).zip(rpm_ranges) {
+                if channel != 1 {
+                    set_channel_manual_control(&hw_mon, channel, true)?;
+                }
+                set_channel_speed(&hw_mon, channel, static_pwm, rpm_range.as_ref())?;
+            }
 
             debug!("set fan speed to {}", static_speed);
 
@@ -190,10 +272,26 @@ impl AmdGpuController {
         &self,
         curve: FanCurve,
         settings: FanControlSettings,
+        external_hw_mon: Option<HwMon>,
     ) -> anyhow::Result<Option<CommitHandle>> {
         // Use the PMFW curve functionality when it is available
         // Otherwise, fall back to manual fan control via a task
-        if let Ok(current_curve) = self.handle.get_fan_curve() {
+        let pmfw_curve = if external_hw_mon.is_none() && settings.speed_unit == FanSpeedUnit::Pwm {
+            self.handle.get_fan_curve().ok()
+        } else {
+            None
+        };
+
+        if let Some(current_curve) = pmfw_curve {
+            if settings.curve_input != FanCurveInput::Temperature {
+                bail!("The fan curve of this GPU is handled by its firmware, which can only follow the temperature");
+            }
+            if !settings.fan_curves.is_empty() {
+                warn!(
+                    "the GPU firmware only supports a single fan curve, per-fan curves are ignored"
+                );
+            }
+
             let new_curve = curve
                 .into_pmfw_curve(current_curve.clone())
                 .context("Invalid fan curve")?;
@@ -207,28 +305,120 @@ impl AmdGpuController {
 
             Ok(Some(commit_handle))
         } else {
-            self.start_curve_fan_control_task(curve, settings).await?;
+            self.start_curve_fan_control_task(settings, external_hw_mon)
+                .await?;
             Ok(None)
         }
     }
 
     async fn start_curve_fan_control_task(
         &self,
-        curve: FanCurve,
         settings: FanControlSettings,
+        external_hw_mon: Option<HwMon>,
     ) -> anyhow::Result<()> {
         // Stop existing task to re-apply new curve
         self.stop_fan_control(false).await?;
 
-        let hw_mon = self
+        // Temperature is always read from the GPU, even when the fan is controlled through an external hwmon
+        let temp_hw_mon = self
             .handle
             .hw_monitors
             .first()
             .cloned()
             .context("This GPU has no monitor")?;
+
+        let temp_keys: Vec<String> = settings
+            .temperature_keys()
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        let available_temps = temp_hw_mon.get_temps();
+        if !temp_keys
+            .iter()
+            .any(|key| available_temps.contains_key(key))
+        {
+            let mut available: Vec<_> = available_temps.keys().map(String::as_str).collect();
+            available.sort_unstable();
+            return Err(anyhow!(
+                "None of the selected temperature sensors ({}) are available, the GPU has: {}",
+                temp_keys.join(", "),
+                available.join(", ")
+            ));
+        }
+
+        let curve_input = settings.curve_input;
+        let read_power = move |hw_mon: &HwMon| {
+            if curve_input == FanCurveInput::Temperature {
+                return (None, None);
+            }
+            let power = hw_mon
+                .get_power_input()
+                .or_else(|_| hw_mon.get_power_average())
+                .ok();
+            (power, hw_mon.get_power_cap().ok())
+        };
+        let (power, power_cap) = read_power(&temp_hw_mon);
+        if curve_input_value(curve_input, 0.0, power, power_cap).is_none() {
+            return Err(anyhow!(
+                "The fan curve follows the power draw, but the power usage or power cap of this GPU is not available"
+            ));
+        }
+
+        let is_external = external_hw_mon.is_some();
+        let hw_mon = self.fan_hw_mon(external_hw_mon)?;
+
+        let extra_channels = if is_external {
+            // External monitors can have unrelated fans on their other channels
+            vec![]
+        } else {
+            extra_fan_channels(&hw_mon)
+        };
+        let mut channels = std::iter::once(1)
+            .chain(extra_channels.iter().copied())
+            .map(|channel| {
+                let rpm_range = channel_rpm_range(&hw_mon, channel, settings.speed_unit)?;
+                Ok(ChannelControl::new(
+                    channel,
+                    settings.curve_for_channel(channel).clone(),
+                    rpm_range,
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
         hw_mon
             .set_fan_control_method(FanControlMethod::Manual)
             .context("Could not set fan control method")?;
+        for channel in &extra_channels {
+            set_channel_manual_control(&hw_mon, *channel, true)?;
+        }
+        let pwm_enable_paths = std::iter::once(1)
+            .chain(extra_channels.iter().copied())
+            .map(|channel| hw_mon.get_path().join(format!("pwm{channel}_enable")))
+            .collect();
+        // The firmware can follow a temperature curve on its own when the loop stops, it's only used by the loop for fan RPM targets
+        let pmfw_curve = if is_external || curve_input != FanCurveInput::Temperature {
+            None
+        } else {
+            self.handle
+                .get_fan_curve()
+                .ok()
+                .and_then(|current_curve| {
+                    settings
+                        .curve_for_channel(1)
+                        .clone()
+                        .into_pmfw_curve(current_curve)
+                        .ok()
+                })
+                .map(|curve| PmfwFallbackCurve {
+                    device_path: self.handle.get_path().to_path_buf(),
+                    points: curve.points.into_vec(),
+                })
+        };
+        let pmfw_curve_path = pmfw_curve.as_ref().map(|curve| curve.device_path.clone());
+        let fallback = FanFallback::Hwmon {
+            pwm_enable_paths,
+            pmfw_curve,
+        };
 
         let mut notify_guard = self
             .fan_control_handle
@@ -240,68 +430,106 @@ impl AmdGpuController {
 
         debug!("spawning new fan control task");
         let handle = tokio::task::spawn_local(async move {
-            let mut last_pwm = (None, Instant::now());
-            let mut last_temp = 0.0;
+            let mut last_value = None;
 
-            // If the fan speed could was able to be set at least once
-            let mut control_available = false;
-
-            let temp_key = settings.temperature_key.clone();
+            let temp_keys: Vec<&str> = temp_keys.iter().map(String::as_str).collect();
             let interval = Duration::from_millis(settings.interval_ms);
-            let spindown_delay = Duration::from_millis(settings.spindown_delay_ms.unwrap_or(0));
-            #[allow(clippy::cast_precision_loss)]
-            let change_threshold = settings.change_threshold.unwrap_or(0) as f32;
+            let timing = settings.curve_timing();
+            let start = Instant::now();
+            let watchdog = FanWatchdog::new(fallback, interval);
 
             loop {
                 select! {
                     () = sleep(interval) => (),
-                    () = task_notify.notified() => break,
+                    () = task_notify.notified() => {
+                        debug!("exited fan control task");
+                        return;
+                    }
+                }
+                if !watchdog.tick() {
+                    warn!("fan control was stalled and had been handed back to the hardware, taking control again");
+                    if let Err(err) =
+                        retake_fan_control(&hw_mon, &extra_channels, pmfw_curve_path.as_deref())
+                    {
+                        error!("could not take control of the fans again: {err:#}");
+                        break;
+                    }
+                    // Every fan gets set right away, as the hardware has been controlling them
+                    last_value = None;
+                    for channel in &mut channels {
+                        channel.speed = FanSpeedState::default();
+                    }
                 }
 
-                let mut temps = hw_mon.get_temps();
-                let temp = temps
-                    .remove(&temp_key)
-                    .expect("Could not get temperature by given key");
+                let Some(temp) = hottest_temperature(temp_hw_mon.get_temps(), &temp_keys) else {
+                    error!("could not read any of the selected temperature sensors, disabling fan control");
+                    break;
+                };
 
                 let current_temp = temp.current.expect("Missing temp");
+                let (power, power_cap) = read_power(&temp_hw_mon);
+                let Some(value) = curve_input_value(curve_input, current_temp, power, power_cap)
+                else {
+                    error!("could not read the power usage, disabling fan control");
+                    break;
+                };
+                let unit = curve_input.unit();
 
-                if (last_temp - current_temp).abs() < change_threshold {
-                    trace!("temperature changed from {last_temp}°C to {current_temp}°C, which is less than the {change_threshold}°C threshold, skipping speed adjustment");
+                let ramping = channels.iter().any(|channel| channel.speed.ramping);
+                if timing.skip_adjustment(last_value, value, ramping) {
+                    trace!("curve input changed to {value}{unit}, which is less than the {}{unit} threshold, skipping speed adjustment", timing.change_threshold);
                     continue;
                 }
 
-                let target_pwm = curve.pwm_at_temp(temp);
-                let now = Instant::now();
+                let now = start.elapsed();
+                // The value is only remembered once every fan has followed it, so that a delayed spindown gets retried
+                let mut spindown_delayed = false;
 
-                if let (Some(previous_pwm), previous_timestamp) = last_pwm {
-                    let diff = now - previous_timestamp;
-                    if target_pwm < previous_pwm && diff < spindown_delay {
-                        trace!(
-                            "delaying fan spindown ({}ms left)",
-                            (spindown_delay - diff).as_millis()
-                        );
+                for channel in &mut channels {
+                    let target_pwm = channel.curve.pwm_at(curve_input, value, &temp);
+                    let Some(pwm) = channel.speed.next_pwm(target_pwm, now, &timing) else {
+                        trace!("delaying spindown of fan {}", channel.channel);
+                        spindown_delayed = true;
                         continue;
+                    };
+
+                    trace!(
+                        "fan control tick: setting pwm of fan {} to {pwm} (target {target_pwm})",
+                        channel.channel
+                    );
+
+                    let result = set_channel_speed(
+                        &hw_mon,
+                        channel.channel,
+                        pwm,
+                        channel.rpm_range.as_ref(),
+                    );
+                    match result {
+                        Ok(()) => channel.control_available = true,
+                        Err(err) => {
+                            error!("could not set speed of fan {}: {err:#}", channel.channel);
+                            if channel.control_available {
+                                info!("fan control was previously available, assuming the error is temporary");
+                            } else {
+                                info!("disabling control of fan {}", channel.channel);
+                                channel.disabled = true;
+                            }
+                        }
                     }
                 }
 
-                last_pwm = (Some(target_pwm), now);
-                last_temp = current_temp;
-
-                trace!("fan control tick: setting pwm to {target_pwm}");
+                channels.retain(|channel| !channel.disabled);
+                if channels.is_empty() {
+                    info!("disabling fan control");
+                    break;
+                }
 
-                match hw_mon.set_fan_pwm(target_pwm) {
-                    Ok(()) => control_available = true,
-                    Err(err) => {
-                        error!("could not set fan speed: {err}");
-                        if control_available {
-                            info!("fan control was previously available, assuming the error is temporary");
-                        } else {
-                            info!("disabling fan control");
-                            break;
-                        }
-                    }
+                if !spindown_delayed {
+                    last_value = Some(value);
                 }
             }
+            // The fans would otherwise stay at the last speed that was set
+            watchdog.release();
             debug!("exited fan control task");
         });
 
@@ -327,6 +555,8 @@ impl AmdGpuController {
         }
 
         if reset_mode {
+            self.restore_external_fan_hw_mon();
+
             if self.handle.get_fan_curve().is_ok() {
                 if let Err(err) = self.handle.reset_fan_curve() {
                     warn!("could not reset fan curve: {err:#}");
@@ -341,6 +571,12 @@ impl AmdGpuController {
                             .context("Could not set fan control back to automatic")?;
                     }
                 }
+
+                for channel in extra_fan_channels(&hw_mon) {
+                    if let Err(err) = set_channel_manual_control(&hw_mon, channel, false) {
+                        warn!("{err:#}");
+                    }
+                }
             }
         }
 
@@ -393,6 +629,184 @@ impl AmdGpuController {
             .context("GPU has no hardware monitor")
     }
 
+    /// Holds the GPU at its highest clocks with deep sleep turned off.
+    /// Pinning the PCIe link speed needs the manual performance level, which is then limited to the highest power states
+    /// to behave like the `high` level. GPUs without configurable PCIe states use the `high` level directly.
+    fn apply_low_latency(&self) -> anyhow::Result<()> {
+        let pcie_configurable = self.handle.get_pcie_clock_levels().is_ok()
+            && is_writable(&self.handle.get_path().join("pp_dpm_pcie"));
+
+        if pcie_configurable {
+            self.handle
+                .set_power_force_performance_level(PerformanceLevel::Manual)
+                .context("Failed to set manual performance level")?;
+
+            for kind in [
+                PowerLevelKind::CoreClock,
+                PowerLevelKind::MemoryClock,
+                PowerLevelKind::PcieSpeed,
+            ] {
+                let count = self
+                    .handle
+                    .get_clock_levels(kind)
+                    .map_or(0, |levels| levels.levels.len());
+                let Some(highest) = count
+                    .checked_sub(1)
+                    .and_then(|index| u8::try_from(index).ok())
+                else {
+                    continue;
+                };
+                self.handle
+                    .set_enabled_power_levels(kind, &[highest])
+                    .with_context(|| format!("Could not pin {kind:?} to its highest state"))?;
+            }
+        } else {
+            self.handle
+                .set_power_force_performance_level(PerformanceLevel::High)
+                .context("Failed to set high performance level")?;
+        }
+
+        self.set_deep_sleep(false)
+    }
+
+    /// Turns off the deep sleep features of the SMU, or turns back on the ones that were turned off before.
+    /// GPUs without `pp_features` are left as they are.
+    fn set_deep_sleep(&self, enabled: bool) -> anyhow::Result<()> {
+        let disabled = self.deep_sleep_disabled.get();
+        if enabled && disabled == 0 {
+            return Ok(());
+        }
+
+        let Ok(contents) = self.handle.read_file(PP_FEATURES_FILE) else {
+            debug!("{PP_FEATURES_FILE} is not available, not changing deep sleep");
+            return Ok(());
+        };
+        let features = parse_pp_features(&contents);
+        let mask = enabled_mask(&features);
+
+        let new_mask = if enabled {
+            mask | disabled
+        } else {
+            mask & !enabled_features_mask(&features, DEEP_SLEEP_FEATURES)
+        };
+        if new_mask != mask {
+            debug!("changing enabled SMU features from {mask:#x} to {new_mask:#x}");
+            self.handle
+                .write_file(PP_FEATURES_FILE, format!("{new_mask:#x}"))
+                .context("Could not write SMU features")?;
+        }
+
+        self.deep_sleep_disabled.set(if enabled {
+            0
+        } else {
+            disabled | (mask & !new_mask)
+        });
+        Ok(())
+    }
+
+    /// Reads one of the `mem_info_*` files, which contain a size in bytes
+    fn read_mem_info(&self, name: &str) -> Option<u64> {
+        self.handle
+            .read_file(format!("mem_info_{name}"))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    /// Reads a PMFW fan setting that is not covered by the sysfs library
+    fn read_pmfw_setting(&self, file: &str) -> anyhow::Result<FanInfo> {
+        let raw = self.handle.read_file(file)?;
+        parse_pmfw_setting(&raw).with_context(|| format!("Could not parse {file}"))
+    }
+
+    fn write_pmfw_setting(&self, file: &str, value: u32) -> anyhow::Result<()> {
+        let info = self.read_pmfw_setting(file)?;
+        if info.current == value {
+            return Ok(());
+        }
+
+        if let Some((min, max)) = info.allowed_range {
+            if !(min..=max).contains(&value) {
+                return Err(anyhow!(
+                    "Value {value} is outside of the allowed range {min}-{max}"
+                ));
+            }
+        }
+
+        write_sysfs(self.handle.get_path(), file, &value.to_string())?;
+        write_sysfs(self.handle.get_path(), file, "c")?;
+        Ok(())
+    }
+
+    /// Writing the whole table doesn't tell which value the driver didn't accept,
+    /// so the commands are written one by one to find it. The table is reset afterwards.
+    fn find_rejected_clocks_command(&self, commands: &[String]) -> Option<SysfsWriteError> {
+        let rejected = commands.iter().find_map(|command| {
+            write_sysfs(self.handle.get_path(), "pp_od_clk_voltage", command).err()
+        });
+        if let Err(err) = self.handle.reset_clocks_table() {
+            warn!("could not reset clocks table: {err}");
+        }
+        rejected
+    }
+
+    fn apply_offsets_table(
+        &self,
+        table: &AmdOffsetsTable,
+        clocks_configuration: &ClocksConfiguration,
+    ) -> anyhow::Result<()> {
+        let commands = od_offsets::commands(table, clocks_configuration)
+            .context("Failed to apply clocks configuration")?;
+        debug!("writing clocks commands: {commands:#?}");
+
+        for command in &commands {
+            if let Err(err) = write_sysfs(self.handle.get_path(), "pp_od_clk_voltage", command) {
+                if let Err(err) = self.handle.reset_clocks_table() {
+                    warn!("could not reset clocks table: {err}");
+                }
+                return Err(anyhow::Error::new(err).context("Could not write clocks table"));
+            }
+        }
+        write_sysfs(self.handle.get_path(), "pp_od_clk_voltage", "c")
+            .context("Could not commit clocks table")?;
+        Ok(())
+    }
+
+    /// Clocks table of GPUs that are overclocked with offsets, which `amdgpu-sysfs` can't parse
+    fn get_offsets_table(&self) -> Option<AmdOffsetsTable> {
+        let contents = fs::read_to_string(self.handle.get_path().join("pp_od_clk_voltage")).ok()?;
+        od_offsets::parse(&contents)
+    }
+
+    fn get_extra_clocks(&self) -> BTreeMap<ExtraClock, ExtraClockRange> {
+        fs::read_to_string(self.handle.get_path().join("pp_od_clk_voltage"))
+            .map(|contents| od_extra_clocks::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    /// Extra clocks are committed on their own, as the table of `amdgpu-sysfs` only covers the GPU and VRAM clocks
+    fn apply_extra_clocks(&self, clocks_configuration: &ClocksConfiguration) -> anyhow::Result<()> {
+        let commands = od_extra_clocks::commands(&self.get_extra_clocks(), clocks_configuration)
+            .context("Failed to apply clocks configuration")?;
+        if commands.is_empty() {
+            return Ok(());
+        }
+        debug!("writing extra clocks commands: {commands:#?}");
+
+        for command in &commands {
+            if let Err(err) = write_sysfs(self.handle.get_path(), "pp_od_clk_voltage", command) {
+                if let Err(err) = self.handle.reset_clocks_table() {
+                    warn!("could not reset clocks table: {err}");
+                }
+                return Err(anyhow::Error::new(err).context("Could not write extra clocks"));
+            }
+        }
+        write_sysfs(self.handle.get_path(), "pp_od_clk_voltage", "c")
+            .context("Could not commit extra clocks")?;
+        Ok(())
+    }
+
     fn get_current_gfxclk(&self) -> Option<u16> {
         self.drm_handle
             .as_ref()
@@ -460,44 +874,48 @@ impl AmdGpuController {
         }
     }
 
-    fn get_throttle_info(&self) -> Option<BTreeMap<String, Vec<String>>> {
-        use libdrm_amdgpu_sys::AMDGPU::ThrottlerType;
-
+    /// Raw throttler status from the GPU metrics, with one bit per throttler
+    fn get_throttle_value(&self) -> Option<u64> {
         self.drm_handle
             .as_ref()
             .and_then(|drm_handle| drm_handle.get_gpu_metrics().ok())
             .and_then(|metrics| metrics.get_indep_throttle_status())
-            .map(|throttle_value| {
-                let mut grouped_bits: HashMap<ThrottlerType, HashSet<u8>> = HashMap::new();
+    }
 
-                if throttle_value == u64::MAX {
-                    return [("Everything".to_owned(), vec!["Yes".to_owned()])]
-                        .into_iter()
-                        .collect();
-                }
+    fn get_throttle_info(&self) -> Option<BTreeMap<String, Vec<String>>> {
+        use libdrm_amdgpu_sys::AMDGPU::ThrottlerType;
 
-                let throttle = ThrottleStatus::new(throttle_value);
+        self.get_throttle_value().map(|throttle_value| {
+            let mut grouped_bits: HashMap<ThrottlerType, HashSet<u8>> = HashMap::new();
 
-                for bit in throttle.get_all_throttler() {
-                    let throttle_type = ThrottlerType::from(bit);
-                    grouped_bits
-                        .entry(throttle_type)
-                        .or_default()
-                        .insert(bit as u8);
-                }
+            if throttle_value == u64::MAX {
+                return [("Everything".to_owned(), vec!["Yes".to_owned()])]
+                    .into_iter()
+                    .collect();
+            }
+
+            let throttle = DrmThrottleStatus::new(throttle_value);
 
+            for bit in throttle.get_all_throttler() {
+                let throttle_type = ThrottlerType::from(bit);
                 grouped_bits
-                    .into_iter()
-                    .map(|(throttle_type, bits)| {
-                        let mut names: Vec<String> = bits
-                            .into_iter()
-                            .map(|bit| ThrottlerBit::from(bit).to_string())
-                            .collect();
-                        names.sort_unstable();
-                        (throttle_type.to_string(), names)
-                    })
-                    .collect()
-            })
+                    .entry(throttle_type)
+                    .or_default()
+                    .insert(bit as u8);
+            }
+
+            grouped_bits
+                .into_iter()
+                .map(|(throttle_type, bits)| {
+                    let mut names: Vec<String> = bits
+                        .into_iter()
+                        .map(|bit| ThrottlerBit::from(bit).to_string())
+                        .collect();
+                    names.sort_unstable();
+                    (throttle_type.to_string(), names)
+                })
+                .collect()
+        })
     }
 
     fn debugfs_path(&self) -> Option<PathBuf> {
@@ -531,9 +949,10 @@ impl GpuController for AmdGpuController {
             .get_pci_slot_name()
             .context("Device has no pci slot")?;
 
-        Ok(format!(
-            "{}:{}-{}:{}-{}",
-            pci_id.0, pci_id.1, pci_subsys_id.0, pci_subsys_id.1, pci_slot_name
+        Ok(format_gpu_id(
+            (&pci_id.0, &pci_id.1),
+            (&pci_subsys_id.0, &pci_subsys_id.1),
+            &pci_slot_name,
         ))
     }
 
@@ -584,6 +1003,43 @@ impl GpuController for AmdGpuController {
 
     fn get_stats(&self, gpu_config: Option<&config::Gpu>) -> DeviceStats {
         let fan_settings = gpu_config.and_then(|config| config.fan_control_settings.as_ref());
+        let external_fan_hw_mon = gpu_config
+            .and_then(|config| config.fan_control_hwmon_path.clone())
+            .and_then(|path| HwMon::new_from_path(path).ok());
+        let fan_hw_mon = external_fan_hw_mon
+            .as_ref()
+            .or(self.handle.hw_monitors.first());
+
+        let pwm_current = fan_hw_mon.and_then(|hw_mon| hw_mon.get_fan_pwm().ok());
+        let speed_current = fan_hw_mon.and_then(|hw_mon| hw_mon.get_fan_current().ok());
+        let stall_detected =
+            self.fan_stall_detector
+                .borrow_mut()
+                .update(pwm_current, speed_current, Instant::now());
+
+        // Individual fans are only reported for the GPU's own monitor, as external ones can have unrelated fans
+        let fans = match (&external_fan_hw_mon, self.handle.hw_monitors.first()) {
+            (None, Some(hw_mon)) => {
+                let channels = fan_channels(hw_mon.get_path());
+                if channels.len() > 1 {
+                    channels
+                        .into_iter()
+                        .map(|channel| FanChannelStats {
+                            channel,
+                            pwm_current: channel_pwm(hw_mon, channel),
+                            speed_current: channel_speed(hw_mon, channel),
+                            curve: fan_settings
+                                .and_then(|settings| settings.fan_curves.get(&channel))
+                                .map(|curve| curve.0.clone()),
+                        })
+                        .collect()
+                } else {
+                    vec![]
+                }
+            }
+            _ => vec![],
+        };
+
         DeviceStats {
             fan: FanStats {
                 control_enabled: gpu_config.is_some_and(|config| config.fan_control_enabled),
@@ -592,15 +1048,38 @@ impl GpuController for AmdGpuController {
                 curve: fan_settings.map(|settings| settings.curve.0.clone()),
                 spindown_delay_ms: fan_settings.and_then(|settings| settings.spindown_delay_ms),
                 change_threshold: fan_settings.and_then(|settings| settings.change_threshold),
-                speed_current: self.hw_mon_and_then(HwMon::get_fan_current),
-                speed_max: self.hw_mon_and_then(HwMon::get_fan_max),
-                speed_min: self.hw_mon_and_then(HwMon::get_fan_min),
-                pwm_current: self.hw_mon_and_then(HwMon::get_fan_pwm),
+                max_ramp_rate: fan_settings.and_then(|settings| settings.max_ramp_rate),
+                temperature_sensors: fan_settings
+                    .map(|settings| {
+                        settings
+                            .temperature_keys()
+                            .into_iter()
+                            .map(str::to_owned)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                speed_current,
+                speed_max: fan_hw_mon.and_then(|hw_mon| hw_mon.get_fan_max().ok()),
+                speed_min: fan_hw_mon.and_then(|hw_mon| hw_mon.get_fan_min().ok()),
+                pwm_current,
+                stall_detected,
+                fans,
+                speed_unit: fan_settings
+                    .map(|settings| settings.speed_unit)
+                    .unwrap_or_default(),
+                curve_input: fan_settings
+                    .map(|settings| settings.curve_input)
+                    .unwrap_or_default(),
                 pmfw_info: PmfwInfo {
                     acoustic_limit: self.handle.get_fan_acoustic_limit().ok(),
                     acoustic_target: self.handle.get_fan_acoustic_target().ok(),
                     target_temp: self.handle.get_fan_target_temperature().ok(),
                     minimum_pwm: self.handle.get_fan_minimum_pwm().ok(),
+                    zero_rpm_enable: self
+                        .read_pmfw_setting(ZERO_RPM_ENABLE_FILE)
+                        .ok()
+                        .map(|info| info.current != 0),
+                    zero_rpm_temperature: self.read_pmfw_setting(ZERO_RPM_TEMPERATURE_FILE).ok(),
                 },
             },
             clockspeed: ClockspeedStats {
@@ -609,12 +1088,20 @@ impl GpuController for AmdGpuController {
                 vram_clockspeed: self.hw_mon_and_then(HwMon::get_vram_clockspeed),
             },
             voltage: VoltageStats {
-                gpu: self.hw_mon_and_then(HwMon::get_gpu_voltage),
-                northbridge: self.hw_mon_and_then(HwMon::get_northbridge_voltage),
+                gpu: self
+                    .read_labeled_voltage("vddgfx")
+                    .or_else(|| self.hw_mon_and_then(HwMon::get_gpu_voltage)),
+                northbridge: self
+                    .read_labeled_voltage("vddnb")
+                    .or_else(|| self.hw_mon_and_then(HwMon::get_northbridge_voltage)),
             },
             vram: VramStats {
                 total: self.handle.get_total_vram().ok(),
                 used: self.handle.get_used_vram().ok(),
+                visible_total: self.read_mem_info("vis_vram_total"),
+                visible_used: self.read_mem_info("vis_vram_used"),
+                gtt_total: self.read_mem_info("gtt_total"),
+                gtt_used: self.read_mem_info("gtt_used"),
             },
             power: PowerStats {
                 average: self.hw_mon_and_then(HwMon::get_power_average),
@@ -623,6 +1110,8 @@ impl GpuController for AmdGpuController {
                 cap_max: self.hw_mon_and_then(HwMon::get_power_cap_max),
                 cap_min: self.hw_mon_and_then(HwMon::get_power_cap_min),
                 cap_default: self.hw_mon_and_then(HwMon::get_power_cap_default),
+                thermal_target: gpu_config.and_then(|config| config.thermal_target),
+                app_power_cap: None,
             },
             temps: self.hw_mon_map(HwMon::get_temps).unwrap_or_default(),
             busy_percent: self.handle.get_busy_percent().ok(),
@@ -643,15 +1132,112 @@ impl GpuController for AmdGpuController {
                 .ok()
                 .and_then(|levels| levels.active),
             throttle_info: self.get_throttle_info(),
+            throttle_status: self.get_throttle_status(),
+            throttle_events: None,
+            link_info: Some(self.get_link_info()),
+            benchmark_lock: self.benchmark_lock.borrow().as_ref().map(|_| {
+                let active_level = |levels: PowerLevels<u64>| {
+                    levels
+                        .active
+                        .and_then(|active| levels.levels.get(active).copied())
+                };
+                BenchmarkLockStats {
+                    core_clock: self
+                        .handle
+                        .get_core_clock_levels()
+                        .ok()
+                        .and_then(active_level),
+                    memory_clock: self
+                        .handle
+                        .get_memory_clock_levels()
+                        .ok()
+                        .and_then(active_level),
+                }
+            }),
+            low_latency: gpu_config.is_some_and(|config| config.low_latency),
+            smoothed: None,
+            alerts: vec![],
+            engine_usage: BTreeMap::new(),
+            runtime_pm: None,
         }
     }
 
     fn get_clocks_info(&self) -> anyhow::Result<ClocksInfo> {
-        let clocks_table = self
-            .handle
-            .get_clocks_table()
-            .context("Clocks table not available")?;
-        Ok(clocks_table.into())
+        let mut info: ClocksInfo = match self.get_offsets_table() {
+            Some(table) => table.into(),
+            None => self
+                .handle
+                .get_clocks_table()
+                .context("Clocks table not available")?
+                .into(),
+        };
+        info.extra_clocks = self.get_extra_clocks();
+        Ok(info)
+    }
+
+    fn get_capabilities(&self) -> DeviceCapabilities {
+        let path = self.handle.get_path();
+        let clocks_table = self.handle.get_clocks_table().ok();
+        let offsets_table = self.get_offsets_table();
+
+        let clocks_control = if offsets_table.is_some() {
+            Some(ClocksControlKind::Offset)
+        } else {
+            clocks_table.as_ref().map(|table| match table {
+                ClocksTableGen::Vega10(_) => ClocksControlKind::PowerStates,
+                ClocksTableGen::Vega20(_) => ClocksControlKind::Range,
+            })
+        };
+        let voltage_offset = match &offsets_table {
+            Some(table) => table.voltage_offset.is_some(),
+            None => matches!(
+                clocks_table,
+                Some(ClocksTableGen::Vega20(ref table)) if table.voltage_offset.is_some()
+            ),
+        };
+
+        let pwm_fan_control = self.first_hw_mon().is_ok_and(|hw_mon| {
+            hw_mon.get_fan_pwm().is_ok()
+                && is_writable(&hw_mon.get_path().join("pwm1"))
+                && is_writable(&hw_mon.get_path().join("pwm1_enable"))
+        });
+        let rpm_fan_control = pwm_fan_control
+            && self
+                .first_hw_mon()
+                .is_ok_and(|hw_mon| rpm_target_range(hw_mon.get_path(), 1).is_some());
+
+        let power_cap_range = self
+            .hw_mon_and_then(HwMon::get_power_cap_min)
+            .zip(self.hw_mon_and_then(HwMon::get_power_cap_max));
+
+        let mut temperature_sensors: Vec<String> = self
+            .hw_mon_map(HwMon::get_temps)
+            .unwrap_or_default()
+            .into_keys()
+            .collect();
+        temperature_sensors.sort_unstable();
+
+        DeviceCapabilities {
+            overdrive: (clocks_table.is_some() || offsets_table.is_some())
+                && is_writable(&path.join("pp_od_clk_voltage")),
+            clocks_control,
+            voltage_offset,
+            pmfw_fan_control: self.handle.get_fan_curve().is_ok(),
+            pwm_fan_control,
+            rpm_fan_control,
+            power_cap_range,
+            temperature_sensors,
+            power_profile_modes: self.handle.get_power_profile_modes().is_ok(),
+            pcie_power_states: self.handle.get_pcie_clock_levels().is_ok()
+                && is_writable(&path.join("pp_dpm_pcie")),
+            ecc: path.join("ras").join("features").exists(),
+            apu_limits: self
+                .apu_limits
+                .as_ref()
+                .map(ApuLimitsControl::supported_limits)
+                .unwrap_or_default(),
+            low_latency_mode: is_writable(&path.join(PERFORMANCE_LEVEL_FILE)),
+        }
     }
 
     fn get_power_states(&self, gpu_config: Option<&config::Gpu>) -> PowerStates {
@@ -660,10 +1246,58 @@ impl GpuController for AmdGpuController {
         PowerStates { core, vram }
     }
 
+    fn get_pcie_power_states(&self) -> Vec<String> {
+        self.handle
+            .get_pcie_clock_levels()
+            .map(|levels| levels.levels.iter().map(ToString::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    fn get_memory_states(&self) -> Vec<MemoryState> {
+        match self.handle.get_clocks_table() {
+            Ok(ClocksTableGen::Vega10(table)) => table
+                .mclk_levels
+                .iter()
+                .enumerate()
+                .map(|(index, level)| MemoryState {
+                    index,
+                    clockspeed: level.clockspeed,
+                    voltage: level.voltage,
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
     fn get_power_profile_modes(&self) -> anyhow::Result<PowerProfileModesTable> {
         Ok(self.handle.get_power_profile_modes()?)
     }
 
+    fn get_throttle_status(&self) -> Option<ThrottleStatus> {
+        use libdrm_amdgpu_sys::AMDGPU::ThrottlerType;
+
+        let throttle_value = self.get_throttle_value()?;
+        // All bits are set when the firmware does not report the status
+        if throttle_value == u64::MAX {
+            return None;
+        }
+
+        let mut status = ThrottleStatus::default();
+        for bit in DrmThrottleStatus::new(throttle_value).get_all_throttler() {
+            match ThrottlerType::from(bit) {
+                ThrottlerType::Power => status.power = true,
+                ThrottlerType::Temperature => status.thermal = true,
+                ThrottlerType::Current => status.current = true,
+                _ => status.other = true,
+            }
+        }
+        Some(status)
+    }
+
+    fn has_fan_control_task(&self) -> bool {
+        self.fan_control_handle.borrow().is_some()
+    }
+
     fn reset_pmfw_settings(&self) {
         let handle = &self.handle;
         if self.handle.get_fan_target_temperature().is_ok() {
@@ -686,11 +1320,111 @@ impl GpuController for AmdGpuController {
                 warn!("Could not reset minimum pwm: {err:#}");
             }
         }
+        for file in [ZERO_RPM_ENABLE_FILE, ZERO_RPM_TEMPERATURE_FILE] {
+            if self.read_pmfw_setting(file).is_ok() {
+                if let Err(err) = handle.write_file(file, "r\n") {
+                    warn!("Could not reset {file}: {err:#}");
+                }
+            }
+        }
+    }
+
+    fn set_benchmark_lock(&self, enabled: bool) -> anyhow::Result<()> {
+        let mut lock = self.benchmark_lock.borrow_mut();
+
+        if enabled {
+            if lock.is_some() {
+                return Ok(());
+            }
+
+            // The raw value is used to be able to restore levels not covered by `PerformanceLevel`, such as other profiles
+            let original_level = self
+                .handle
+                .read_file(PERFORMANCE_LEVEL_FILE)
+                .context("Could not read current performance level")?;
+            self.handle
+                .write_file(PERFORMANCE_LEVEL_FILE, BENCHMARK_PERFORMANCE_LEVEL)
+                .context("Could not lock performance level")?;
+
+            info!("enabled benchmark lock, previous performance level was {original_level}");
+            *lock = Some(original_level);
+        } else if let Some(original_level) = lock.take() {
+            self.handle
+                .write_file(PERFORMANCE_LEVEL_FILE, &original_level)
+                .with_context(|| {
+                    format!("Could not restore original performance level {original_level}")
+                })?;
+            info!("released benchmark lock, restored performance level {original_level}");
+        }
+
+        Ok(())
     }
 
     fn vbios_dump(&self) -> anyhow::Result<Vec<u8>> {
-        let debugfs = self.debugfs_path().context("DebugFS not found")?;
-        fs::read(debugfs.join("amdgpu_vbios")).context("Could not read VBIOS file")
+        let rom = match self.debugfs_path() {
+            Some(debugfs) => {
+                fs::read(debugfs.join("amdgpu_vbios")).context("Could not read VBIOS file")
+            }
+            None => Err(anyhow!("DebugFS not found")),
+        }
+        .or_else(|err| {
+            debug!("{err:#}, reading the PCI ROM instead");
+            vbios::read_pci_rom(self.handle.get_path())
+        })?;
+        vbios::validate_rom(rom)
+    }
+
+    fn reset_gpu(&self) -> anyhow::Result<()> {
+        // Reading this file runs the driver's hang recovery, which is preferred over a reset of the PCI device
+        if let Some(recover_path) = self
+            .debugfs_path()
+            .map(|debugfs| debugfs.join("amdgpu_gpu_recover"))
+            .filter(|path| path.exists())
+        {
+            info!("triggering GPU recovery through {recover_path:?}");
+            fs::read_to_string(&recover_path).context("Could not trigger GPU recovery")?;
+            return Ok(());
+        }
+
+        let device_path = self.handle.get_path();
+        if device_path.join("reset").exists() {
+            info!("resetting PCI device {device_path:?}");
+            write_sysfs(device_path, "reset", "1").context("Could not reset the PCI device")?;
+            return Ok(());
+        }
+
+        bail!("GPU reset is not supported by the kernel, neither amdgpu_gpu_recover in debugfs nor a PCI reset method is available")
+    }
+
+    fn is_ready(&self, config: &config::Gpu) -> bool {
+        if config.is_core_clocks_used() {
+            let od_path = self.handle.get_path().join("pp_od_clk_voltage");
+            if !is_writable(&od_path)
+                || (self.handle.get_clocks_table().is_err() && self.get_offsets_table().is_none())
+            {
+                return false;
+            }
+        }
+
+        if config.power_cap.is_some() || config.fan_control_enabled {
+            let Ok(hw_mon) = self.first_hw_mon() else {
+                return false;
+            };
+
+            if config.power_cap.is_some() && !is_writable(&hw_mon.get_path().join("power1_cap")) {
+                return false;
+            }
+
+            if config.fan_control_enabled
+                && config.fan_control_hwmon_path.is_none()
+                && self.handle.get_fan_curve().is_err()
+                && !is_writable(&hw_mon.get_path().join("pwm1_enable"))
+            {
+                return false;
+            }
+        }
+
+        true
     }
 
     #[allow(clippy::too_many_lines)]
@@ -699,8 +1433,21 @@ impl GpuController for AmdGpuController {
         config: &'a config::Gpu,
     ) -> LocalBoxFuture<'a, anyhow::Result<()>> {
         Box::pin(async {
+            apply_runtime_pm(self.handle.get_path(), config.runtime_pm)?;
+
+            // Applying settings overrides the performance level, so the lock is no longer valid
+            if self.benchmark_lock.borrow_mut().take().is_some() {
+                info!("settings are being applied, releasing benchmark lock");
+            }
+
             if let Some(cap) = config.power_cap {
                 let hw_mon = self.first_hw_mon()?;
+                let cap = clamp_setting(
+                    "power cap",
+                    cap,
+                    hw_mon.get_power_cap_min().ok(),
+                    hw_mon.get_power_cap_max().ok(),
+                );
 
                 let current_usage = hw_mon
                     .get_power_input()
@@ -769,6 +1516,17 @@ impl GpuController for AmdGpuController {
                 }
             }
 
+            // Applied after the power cap, as it can be the same hwmon file as the slow PPT limit
+            match &self.apu_limits {
+                Some(apu_limits) => apu_limits
+                    .apply(&config.apu_limits)
+                    .context("Failed to apply APU limits")?,
+                None if !config.apu_limits.is_empty() => {
+                    bail!("APU limits can only be set on integrated GPUs");
+                }
+                None => (),
+            }
+
             let mut commit_handles = Vec::new();
 
             // Reset the clocks table in case the settings get reverted back to not having a clocks value configured
@@ -780,34 +1538,49 @@ impl GpuController for AmdGpuController {
                 .ok();
 
             if config.is_core_clocks_used() {
-                let original_table = self
-                    .handle
-                    .get_clocks_table()
-                    .context("Failed to get clocks table")?;
-                let mut table = original_table.clone();
-                config
-                    .clocks_configuration
-                    .apply_to_table(&mut table)
-                    .context("Failed to apply clocks configuration to table")?;
-
-                debug!(
-                    "writing clocks commands: {:#?}",
-                    table
-                        .get_commands(&original_table)
-                        .context("Failed to get table commands")?
-                );
+                // A cheap check to give a meaningful error instead of a failed write
+                if !is_writable(&self.handle.get_path().join("pp_od_clk_voltage")) {
+                    return Err(anyhow!(OVERDRIVE_DISABLED_ERROR));
+                }
 
-                let handle = self
-                    .handle
-                    .set_clocks_table(&table)
-                    .context("Could not write clocks table")
-                    .with_context(|| {
-                        format!(
-                            "Clocks table commands: {:?}",
-                            table.get_commands(&original_table)
-                        )
-                    })?;
-                commit_handles.push(handle);
+                if let Some(offsets_table) = self.get_offsets_table() {
+                    self.apply_offsets_table(&offsets_table, &config.clocks_configuration)?;
+                } else {
+                    let original_table = self
+                        .handle
+                        .get_clocks_table()
+                        .context("Failed to get clocks table")?;
+                    let mut table = original_table.clone();
+
+                    let mut clocks_configuration = config.clocks_configuration.clone();
+                    clocks_configuration.clamp_to_table(&original_table);
+                    clocks_configuration
+                        .apply_to_table(&mut table)
+                        .context("Failed to apply clocks configuration to table")?;
+
+                    debug!(
+                        "writing clocks commands: {:#?}",
+                        table
+                            .get_commands(&original_table)
+                            .context("Failed to get table commands")?
+                    );
+
+                    let handle = match self.handle.set_clocks_table(&table) {
+                        Ok(handle) => handle,
+                        Err(err) => {
+                            let commands = table.get_commands(&original_table).unwrap_or_default();
+                            let err = match self.find_rejected_clocks_command(&commands) {
+                                Some(rejected) => anyhow::Error::new(rejected),
+                                None => anyhow!(err)
+                                    .context(format!("Clocks table commands: {commands:?}")),
+                            };
+                            return Err(err.context("Could not write clocks table"));
+                        }
+                    };
+                    commit_handles.push(handle);
+                }
+
+                self.apply_extra_clocks(&config.clocks_configuration)?;
             }
 
             if let Some(level) = config.performance_level {
@@ -849,12 +1622,43 @@ impl GpuController for AmdGpuController {
                     .with_context(|| format!("Could not set {kind:?} power states"))?;
             }
 
+            // Applied on top of the performance level and power states, which are used again once it's disabled
+            if config.low_latency {
+                self.apply_low_latency()
+                    .context("Failed to enable low latency mode")?;
+            } else {
+                self.set_deep_sleep(true)
+                    .context("Could not re-enable deep sleep")?;
+            }
+
             if config.fan_control_enabled {
+                let external_hw_mon = config
+                    .fan_control_hwmon_path
+                    .as_deref()
+                    .map(load_external_fan_hw_mon)
+                    .transpose()
+                    .context("Invalid fan control hwmon")?;
+
+                let external_hw_mon_changed = self
+                    .external_fan_hw_mon
+                    .borrow()
+                    .as_ref()
+                    .is_some_and(|(current, _)| {
+                        Some(current.get_path()) != external_hw_mon.as_ref().map(HwMon::get_path)
+                    });
+                if external_hw_mon_changed {
+                    self.restore_external_fan_hw_mon();
+                }
+
                 if let Some(ref settings) = config.fan_control_settings {
                     match settings.mode {
                         lact_schema::FanControlMode::Static => {
                             if let Some(commit_handle) = self
-                                .set_static_fan_control(settings.static_speed)
+                                .set_static_fan_control(
+                                    settings.static_speed,
+                                    settings.speed_unit,
+                                    external_hw_mon,
+                                )
                                 .await
                                 .context("Failed to set static fan control")?
                             {
@@ -867,7 +1671,11 @@ impl GpuController for AmdGpuController {
                             }
 
                             if let Some(commit_handle) = self
-                                .start_curve_fan_control(settings.curve.clone(), settings.clone())
+                                .start_curve_fan_control(
+                                    settings.curve.clone(),
+                                    settings.clone(),
+                                    external_hw_mon,
+                                )
                                 .await
                                 .context("Failed to set curve fan control")?
                             {
@@ -948,6 +1756,17 @@ impl GpuController for AmdGpuController {
                     .context("Failed to stop fan control")?;
             }
 
+            // Zero RPM is handled by the firmware, so it also applies on top of custom curves
+            let pmfw = &config.pmfw_options;
+            if let Some(zero_rpm) = pmfw.zero_rpm {
+                self.write_pmfw_setting(ZERO_RPM_ENABLE_FILE, u32::from(zero_rpm))
+                    .context("Could not set zero RPM mode")?;
+            }
+            if let Some(threshold) = pmfw.zero_rpm_threshold {
+                self.write_pmfw_setting(ZERO_RPM_TEMPERATURE_FILE, threshold)
+                    .context("Could not set zero RPM temperature")?;
+            }
+
             for handle in commit_handles {
                 handle.commit()?;
             }
@@ -957,7 +1776,7 @@ impl GpuController for AmdGpuController {
     }
 
     fn cleanup_clocks(&self) -> anyhow::Result<()> {
-        if self.handle.get_clocks_table().is_err() {
+        if self.handle.get_clocks_table().is_err() && self.get_offsets_table().is_none() {
             return Ok(());
         }
 
@@ -967,6 +1786,121 @@ impl GpuController for AmdGpuController {
     }
 }
 
+/// Fan channels besides the first one, which is handled through the regular `HwMon` methods
+fn extra_fan_channels(hw_mon: &HwMon) -> Vec<u32> {
+    fan_channels(hw_mon.get_path())
+        .into_iter()
+        .filter(|channel| *channel > 1)
+        .collect()
+}
+
+/// Reads `in{N}_input` of the voltage channel whose `in{N}_label` matches
+fn read_labeled_voltage(hw_mon_path: &Path, label: &str) -> Option<u64> {
+    (0..8).find_map(|channel| {
+        let channel_label =
+            fs::read_to_string(hw_mon_path.join(format!("in{channel}_label"))).ok()?;
+        if channel_label.trim() != label {
+            return None;
+        }
+        fs::read_to_string(hw_mon_path.join(format!("in{channel}_input")))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    })
+}
+
+/// Speed range of a fan channel when it is controlled with RPM targets, `None` when PWM is used
+fn channel_rpm_range(
+    hw_mon: &HwMon,
+    channel: u32,
+    speed_unit: FanSpeedUnit,
+) -> anyhow::Result<Option<RangeInclusive<u32>>> {
+    match speed_unit {
+        FanSpeedUnit::Pwm => Ok(None),
+        FanSpeedUnit::Rpm => rpm_target_range(hw_mon.get_path(), channel)
+            .map(Some)
+            .with_context(|| format!("Fan {channel} does not support RPM targets")),
+    }
+}
+
+/// Sets the speed of a fan channel that is in manual mode.
+/// With an RPM range the PWM value is converted into a target speed.
+fn set_channel_speed(
+    hw_mon: &HwMon,
+    channel: u32,
+    pwm: u8,
+    rpm_range: Option<&RangeInclusive<u32>>,
+) -> anyhow::Result<()> {
+    match rpm_range {
+        Some(range) => set_channel_rpm(hw_mon, channel, pwm_to_rpm(pwm, range)),
+        None if channel == 1 => hw_mon.set_fan_pwm(pwm).context("Could not set fan speed"),
+        None => set_channel_pwm(hw_mon, channel, pwm),
+    }
+}
+
+/// State of a fan channel driven by the curve fan control task
+struct ChannelControl {
+    channel: u32,
+    curve: FanCurve,
+    /// Target RPM range when the fan is controlled by RPM instead of PWM
+    rpm_range: Option<RangeInclusive<u32>>,
+    speed: FanSpeedState,
+    /// If the fan speed was able to be set at least once
+    control_available: bool,
+    disabled: bool,
+}
+
+impl ChannelControl {
+    fn new(channel: u32, curve: FanCurve, rpm_range: Option<RangeInclusive<u32>>) -> Self {
+        Self {
+            channel,
+            curve,
+            rpm_range,
+            speed: FanSpeedState::default(),
+            control_available: false,
+            disabled: false,
+        }
+    }
+}
+
+/// Takes manual control of the fans again after the watchdog handed them back to the hardware
+fn retake_fan_control(
+    hw_mon: &HwMon,
+    extra_channels: &[u32],
+    pmfw_device_path: Option<&Path>,
+) -> anyhow::Result<()> {
+    if let Some(device_path) = pmfw_device_path {
+        // The fallback curve would otherwise stay in the firmware once the loop is stopped
+        write_sysfs(device_path, PMFW_FAN_CURVE_FILE, "r")
+            .and_then(|()| write_sysfs(device_path, PMFW_FAN_CURVE_FILE, "c"))
+            .context("Could not reset the firmware fan curve")?;
+    }
+    hw_mon
+        .set_fan_control_method(FanControlMethod::Manual)
+        .context("Could not set fan control method")?;
+    for channel in extra_channels {
+        set_channel_manual_control(hw_mon, *channel, true)?;
+    }
+    Ok(())
+}
+
+fn load_external_fan_hw_mon(path: &Path) -> anyhow::Result<HwMon> {
+    if !path.exists() {
+        return Err(anyhow!("Path {path:?} does not exist"));
+    }
+
+    for file_name in ["pwm1", "pwm1_enable"] {
+        let file_path = path.join(file_name);
+        if !is_writable(&file_path) {
+            return Err(anyhow!("{file_path:?} is missing or not writable"));
+        }
+    }
+
+    HwMon::new_from_path(path.to_owned())
+        .with_context(|| format!("Could not initialize hwmon at {path:?}"))
+}
+
 fn get_drm_handle(handle: &GpuHandle) -> anyhow::Result<DrmHandle> {
     let slot_name = handle
         .get_pci_slot_name()
@@ -983,6 +1917,96 @@ fn get_drm_handle(handle: &GpuHandle) -> anyhow::Result<DrmHandle> {
 }
 
 impl ClocksConfiguration {
+    /// Clamps the configured values to the ranges advertised by the GPU
+    fn clamp_to_table(&mut self, table: &ClocksTableGen) {
+        fn clamp_to_range(name: &str, value: &mut Option<i32>, range: Option<Range>) {
+            if let (Some(value), Some(range)) = (value.as_mut(), range) {
+                *value = clamp_setting(name, *value, range.min, range.max);
+            }
+        }
+
+        clamp_to_range(
+            "min core clock",
+            &mut self.min_core_clock,
+            table.get_min_sclk_range(),
+        );
+        clamp_to_range(
+            "min memory clock",
+            &mut self.min_memory_clock,
+            table.get_min_mclk_range(),
+        );
+        clamp_to_range(
+            "min voltage",
+            &mut self.min_voltage,
+            table.get_min_voltage_range(),
+        );
+        clamp_to_range(
+            "max core clock",
+            &mut self.max_core_clock,
+            table.get_max_sclk_range(),
+        );
+        clamp_to_range(
+            "max memory clock",
+            &mut self.max_memory_clock,
+            table.get_max_mclk_range(),
+        );
+        clamp_to_range(
+            "max voltage",
+            &mut self.max_voltage,
+            table.get_max_voltage_range(),
+        );
+
+        if let ClocksTableGen::Vega10(_) = table {
+            for state in &mut self.memory_states {
+                let mut clockspeed = Some(state.clockspeed);
+                clamp_to_range(
+                    &format!("memory state {} clockspeed", state.index),
+                    &mut clockspeed,
+                    table.get_max_mclk_range(),
+                );
+                let mut voltage = Some(state.voltage);
+                clamp_to_range(
+                    &format!("memory state {} voltage", state.index),
+                    &mut voltage,
+                    table.get_max_voltage_range(),
+                );
+
+                state.clockspeed = clockspeed.unwrap_or(state.clockspeed);
+                state.voltage = voltage.unwrap_or(state.voltage);
+            }
+        }
+
+        if let ClocksTableGen::Vega20(table) = table {
+            clamp_to_range(
+                "voltage offset",
+                &mut self.voltage_offset,
+                table.od_range.voltage_offset,
+            );
+
+            for point in &mut self.gpu_curve {
+                let mut clockspeed = Some(point.clockspeed);
+                clamp_to_range(
+                    &format!("curve point {} clockspeed", point.index),
+                    &mut clockspeed,
+                    table.od_range.curve_sclk_points.get(point.index).copied(),
+                );
+                let mut voltage = Some(point.voltage);
+                clamp_to_range(
+                    &format!("curve point {} voltage", point.index),
+                    &mut voltage,
+                    table
+                        .od_range
+                        .curve_voltage_points
+                        .get(point.index)
+                        .copied(),
+                );
+
+                point.clockspeed = clockspeed.unwrap_or(point.clockspeed);
+                point.voltage = voltage.unwrap_or(point.voltage);
+            }
+        }
+    }
+
     fn apply_to_table(&self, table: &mut ClocksTableGen) -> anyhow::Result<()> {
         if let ClocksTableGen::Vega20(ref mut table) = table {
             // Avoid writing settings to the clocks table except the user-specified ones
@@ -996,10 +2020,38 @@ impl ClocksConfiguration {
             // Normalize the VDDC curve - make sure all of the values are within the allowed range
             table.normalize_vddc_curve();
 
+            for point in &self.gpu_curve {
+                let level = table
+                    .vddc_curve
+                    .get_mut(point.index)
+                    .with_context(|| format!("GPU curve point {} does not exist", point.index))?;
+                level.clockspeed = point.clockspeed;
+                level.voltage = point.voltage;
+            }
+
             match self.voltage_offset {
                 Some(offset) => table.set_voltage_offset(offset)?,
                 None => table.voltage_offset = None,
             }
+        } else if !self.gpu_curve.is_empty() {
+            return Err(anyhow!(
+                "Editing GPU curve points is not supported on this GPU"
+            ));
+        }
+
+        if let ClocksTableGen::Vega10(ref mut table) = table {
+            for state in &self.memory_states {
+                let level = table
+                    .mclk_levels
+                    .get_mut(state.index)
+                    .with_context(|| format!("Memory state {} does not exist", state.index))?;
+                level.clockspeed = state.clockspeed;
+                level.voltage = state.voltage;
+            }
+        } else if !self.memory_states.is_empty() {
+            return Err(anyhow!(
+                "Editing memory states is only supported on GPUs that set clocks per power state"
+            ));
         }
 
         if let Some(min_clockspeed) = self.min_core_clock {
@@ -0,0 +1,241 @@
+//! Power limits of AMD APUs. They are shared between the CPU and the integrated GPU,
+//! so they are managed by the SMU instead of the overdrive interface of dedicated GPUs.
+//!
+//! The fast/slow PPT limits are set through the amdgpu hwmon when the driver exposes them (e.g. Van Gogh).
+//! Other limits need the out-of-tree [`ryzen_smu`](https://github.com/amkillam/ryzen_smu) module,
+//! which gives access to the SMU mailbox used by RyzenAdj.
+use super::sysfs_write::write_sysfs;
+use amdgpu_sysfs::hw_mon::HwMon;
+use anyhow::{bail, Context};
+use lact_schema::ApuLimitKind;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::{debug, info};
+
+/// Set in the `ids_flags` of the DRM device info on integrated GPUs
+pub const AMDGPU_IDS_FLAGS_FUSION: u64 = 0x1;
+
+const RYZEN_SMU_PATH: &str = "/sys/kernel/ryzen_smu_drv";
+const SMU_COMMAND_FILE: &str = "mp1_smu_cmd";
+const SMU_ARGS_FILE: &str = "smu_args";
+/// The mailbox takes 6 little-endian 32-bit arguments
+const SMU_ARGS_COUNT: usize = 6;
+const SMU_STATUS_OK: u32 = 0x1;
+
+/// MP1 message ids of an APU generation, as used by RyzenAdj
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SmuMessages {
+    stapm: u32,
+    fast: u32,
+    slow: u32,
+    temperature: u32,
+}
+
+impl SmuMessages {
+    /// Only generations with known message ids are supported, as unknown messages can have other effects
+    fn for_asic(asic_name: &str) -> Option<Self> {
+        match asic_name {
+            // Raven, Picasso and Dali
+            "RAVEN" | "RAVEN2" => Some(Self {
+                stapm: 0x1a,
+                fast: 0x1b,
+                slow: 0x1c,
+                temperature: 0x1f,
+            }),
+            // Renoir (including Lucienne and Cezanne), Van Gogh, Rembrandt, Mendocino and Phoenix
+            "RENOIR" | "VANGOGH" | "YELLOW_CARP" | "GFX1037" | "GFX1103_R1" | "GFX1103_R2" => {
+                Some(Self {
+                    stapm: 0x14,
+                    fast: 0x15,
+                    slow: 0x16,
+                    temperature: 0x19,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn message(self, kind: ApuLimitKind) -> u32 {
+        match kind {
+            ApuLimitKind::Stapm => self.stapm,
+            ApuLimitKind::Fast => self.fast,
+            ApuLimitKind::Slow => self.slow,
+            ApuLimitKind::Temperature => self.temperature,
+        }
+    }
+}
+
+pub struct ApuLimitsControl {
+    /// Directory of the hwmon with `fastPPT`/`slowPPT` power caps, along with their channels
+    hw_mon_caps: Option<(PathBuf, BTreeMap<ApuLimitKind, u32>)>,
+    smu: Option<SmuMessages>,
+}
+
+impl ApuLimitsControl {
+    pub fn new(asic_name: &str, hw_mon: Option<&HwMon>) -> Self {
+        let hw_mon_caps = hw_mon.and_then(|hw_mon| {
+            let channels = ppt_channels(hw_mon.get_path());
+            (!channels.is_empty()).then(|| (hw_mon.get_path().to_owned(), channels))
+        });
+
+        let smu = SmuMessages::for_asic(asic_name)
+            .filter(|_| Path::new(RYZEN_SMU_PATH).join(SMU_COMMAND_FILE).exists());
+        if smu.is_some() {
+            info!("using the ryzen_smu driver for APU limits");
+        }
+
+        Self { hw_mon_caps, smu }
+    }
+
+    pub fn supported_limits(&self) -> Vec<ApuLimitKind> {
+        [
+            ApuLimitKind::Stapm,
+            ApuLimitKind::Fast,
+            ApuLimitKind::Slow,
+            ApuLimitKind::Temperature,
+        ]
+        .into_iter()
+        .filter(|kind| self.smu.is_some() || self.hw_mon_channel(*kind).is_some())
+        .collect()
+    }
+
+    /// Writes the configured limits. Limits that are no longer configured are restored to their defaults
+    /// when the hwmon reports them, while the ones set through the SMU stay in effect until a reboot.
+    pub fn apply(&self, limits: &BTreeMap<ApuLimitKind, f64>) -> anyhow::Result<()> {
+        for (kind, value) in limits {
+            if *value <= 0.0 {
+                bail!("The {kind:?} limit has to be positive");
+            }
+
+            if let Some((path, channel)) = self.hw_mon_channel(*kind) {
+                // Power caps are in microwatts
+                let value = (value * 1_000_000.0).round().to_string();
+                write_sysfs(path, &format!("power{channel}_cap"), &value)
+                    .with_context(|| format!("Could not set the {kind:?} limit"))?;
+            } else if let Some(smu) = self.smu {
+                // The SMU takes power limits in milliwatts
+                let scale = match kind {
+                    ApuLimitKind::Temperature => 1.0,
+                    _ => 1000.0,
+                };
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let value = (value * scale).round() as u32;
+                send_smu_message(Path::new(RYZEN_SMU_PATH), smu.message(*kind), value)
+                    .with_context(|| format!("Could not set the {kind:?} limit"))?;
+            } else {
+                bail!("The {kind:?} limit is not supported on this APU");
+            }
+        }
+
+        if let Some((path, channels)) = &self.hw_mon_caps {
+            for (kind, channel) in channels {
+                if limits.contains_key(kind) {
+                    continue;
+                }
+                if let Ok(default) =
+                    fs::read_to_string(path.join(format!("power{channel}_cap_default")))
+                {
+                    write_sysfs(path, &format!("power{channel}_cap"), default.trim())
+                        .with_context(|| format!("Could not reset the {kind:?} limit"))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn hw_mon_channel(&self, kind: ApuLimitKind) -> Option<(&Path, u32)> {
+        let (path, channels) = self.hw_mon_caps.as_ref()?;
+        channels
+            .get(&kind)
+            .map(|channel| (path.as_path(), *channel))
+    }
+}
+
+/// Power cap channels labeled as `fastPPT` or `slowPPT`
+fn ppt_channels(hw_mon_path: &Path) -> BTreeMap<ApuLimitKind, u32> {
+    (1..=2)
+        .filter_map(|channel| {
+            let label =
+                fs::read_to_string(hw_mon_path.join(format!("power{channel}_label"))).ok()?;
+            let kind = match label.trim() {
+                "fastPPT" => ApuLimitKind::Fast,
+                "slowPPT" => ApuLimitKind::Slow,
+                _ => return None,
+            };
+            hw_mon_path
+                .join(format!("power{channel}_cap"))
+                .exists()
+                .then_some((kind, channel))
+        })
+        .collect()
+}
+
+fn smu_args(value: u32) -> Vec<u8> {
+    let mut args = [0u32; SMU_ARGS_COUNT];
+    args[0] = value;
+    args.iter().flat_map(|arg| arg.to_le_bytes()).collect()
+}
+
+fn send_smu_message(driver_path: &Path, message: u32, value: u32) -> anyhow::Result<()> {
+    debug!("sending SMU message {message:#x} with value {value}");
+
+    fs::write(driver_path.join(SMU_ARGS_FILE), smu_args(value))
+        .context("Could not write SMU arguments")?;
+    fs::write(driver_path.join(SMU_COMMAND_FILE), message.to_le_bytes())
+        .context("Could not send SMU message")?;
+
+    let status =
+        fs::read(driver_path.join(SMU_COMMAND_FILE)).context("Could not read SMU status")?;
+    let status = status
+        .get(..4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_le_bytes)
+        .context("Invalid SMU status")?;
+    if status != SMU_STATUS_OK {
+        bail!("The SMU rejected message {message:#x} with status {status:#x}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ppt_channels, smu_args, SmuMessages};
+    use crate::tests::TempDir;
+    use lact_schema::ApuLimitKind;
+    use std::{collections::BTreeMap, fs};
+
+    #[test]
+    fn smu_messages_by_generation() {
+        let raven = SmuMessages::for_asic("RAVEN").unwrap();
+        assert_eq!(raven.message(ApuLimitKind::Stapm), 0x1a);
+        let phoenix = SmuMessages::for_asic("GFX1103_R1").unwrap();
+        assert_eq!(phoenix.message(ApuLimitKind::Temperature), 0x19);
+        assert_eq!(SmuMessages::for_asic("NAVI21"), None);
+    }
+
+    #[test]
+    fn smu_args_layout() {
+        let args = smu_args(15000);
+        assert_eq!(args.len(), 24);
+        assert_eq!(&args[..4], &15000u32.to_le_bytes());
+        assert!(args[4..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn detect_ppt_channels() {
+        let dir = TempDir::new("apu-hwmon");
+        fs::write(dir.join("power1_label"), "slowPPT\n").unwrap();
+        fs::write(dir.join("power1_cap"), "15000000\n").unwrap();
+        fs::write(dir.join("power2_label"), "fastPPT\n").unwrap();
+        fs::write(dir.join("power2_cap"), "20000000\n").unwrap();
+
+        assert_eq!(
+            ppt_channels(&dir),
+            BTreeMap::from([(ApuLimitKind::Fast, 2), (ApuLimitKind::Slow, 1)])
+        );
+    }
+}
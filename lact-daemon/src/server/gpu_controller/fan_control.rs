@@ -1,13 +1,65 @@
-use std::cmp;
+use std::{
+    cmp,
+    collections::HashMap,
+    fs,
+    ops::RangeInclusive,
+    path::Path,
+    time::{Duration, Instant},
+};
 
-use amdgpu_sysfs::{gpu_handle::fan_control::FanCurve as PmfwCurve, hw_mon::Temperature};
+use super::{is_writable, sysfs_write::write_sysfs};
+use amdgpu_sysfs::{
+    gpu_handle::fan_control::{FanCurve as PmfwCurve, FanInfo},
+    hw_mon::{HwMon, Temperature},
+    sysfs::SysFS,
+};
 use anyhow::{anyhow, Context};
-use lact_schema::{default_fan_curve, FanCurveMap};
+use lact_schema::{default_fan_curve, FanCurveInput, FanCurveMap};
 use serde::{Deserialize, Serialize};
 use tracing::warn;
 
+/// Temperature by which a fan curve has to reach the minimum safe speed
+pub const SAFETY_FLOOR_TEMP: i32 = 80;
+/// Minimum fan speed that a curve has to reach by [`SAFETY_FLOOR_TEMP`]
+pub const SAFETY_FLOOR_SPEED: f32 = 0.3;
+/// Range of temperatures that fan curve points can be placed at
+pub const CURVE_TEMP_RANGE: RangeInclusive<i32> = 0..=120;
+/// Range of power draws in watts that fan curve points can be placed at
+pub const CURVE_POWER_RANGE: RangeInclusive<i32> = 0..=1000;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct FanCurve(pub FanCurveMap);
+pub struct FanCurve(#[serde(with = "curve_points")] pub FanCurveMap);
+
+/// Curve points as they are stored in the config, with the temperatures as keys
+mod curve_points {
+    use lact_schema::FanCurveMap;
+    use serde::{de, ser, ser::SerializeMap, Deserialize, Deserializer, Serializer};
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(curve: &FanCurveMap, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(curve.len()))?;
+        for (temp, speed) in curve {
+            // TOML only has string keys and 64-bit floats, where 0.35 as `f32` would become 0.3499999940395355
+            let speed: f64 = speed.to_string().parse().map_err(ser::Error::custom)?;
+            map.serialize_entry(&temp.to_string(), &speed)?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<FanCurveMap, D::Error> {
+        BTreeMap::<String, f32>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(temp, speed)| {
+                let temp = temp
+                    .parse()
+                    .map_err(|_| de::Error::custom(format!("invalid temperature `{temp}`")))?;
+                Ok((temp, speed))
+            })
+            .collect()
+    }
+}
 
 #[allow(
     clippy::cast_possible_truncation,
@@ -15,7 +67,14 @@ pub struct FanCurve(pub FanCurveMap);
     clippy::cast_sign_loss
 )]
 impl FanCurve {
-    pub fn pwm_at_temp(&self, temp: Temperature) -> u8 {
+    pub fn pwm_at_temp(&self, temp: &Temperature) -> u8 {
+        let current = temp.current.expect("No current temp");
+        self.pwm_at(FanCurveInput::Temperature, current, temp)
+    }
+
+    /// Fan speed at the given value of the curve input, see [`curve_input_value`].
+    /// Curves that don't follow the temperature alone still reach the safety floor speed once the GPU is hot.
+    pub fn pwm_at(&self, input: FanCurveInput, value: f32, temp: &Temperature) -> u8 {
         let current = temp.current.expect("No current temp");
 
         // This scenario is most likely unreachable as the kernel shuts down the GPU when it reaches critical temperature
@@ -26,21 +85,26 @@ impl FanCurve {
             return u8::MAX;
         }
 
-        let current = current as i32;
-        let maybe_lower = self.0.range(..current).next_back();
-        let maybe_higher = self.0.range(current..).next();
+        let mut percentage = self.speed_at_temp(value as i32);
+        if input != FanCurveInput::Temperature && current >= SAFETY_FLOOR_TEMP as f32 {
+            percentage = percentage.max(SAFETY_FLOOR_SPEED);
+        }
+        (f32::from(u8::MAX) * percentage) as u8
+    }
+
+    fn speed_at_temp(&self, temp: i32) -> f32 {
+        let maybe_lower = self.0.range(..temp).next_back();
+        let maybe_higher = self.0.range(temp..).next();
 
-        let percentage = match (maybe_lower, maybe_higher) {
+        match (maybe_lower, maybe_higher) {
             (Some((lower_temp, lower_speed)), Some((higher_temp, higher_speed))) => {
-                let speed_ratio = (current - lower_temp) as f32 / (higher_temp - lower_temp) as f32;
+                let speed_ratio = (temp - lower_temp) as f32 / (higher_temp - lower_temp) as f32;
                 lower_speed + (higher_speed - lower_speed) * speed_ratio
             }
             (Some((_, lower_speed)), None) => *lower_speed,
             (None, Some((_, higher_speed))) => *higher_speed,
             (None, None) => panic!("Could not find fan speed on the curve! This is a bug."),
-        };
-
-        (f32::from(u8::MAX) * percentage) as u8
+        }
     }
 
     pub fn into_pmfw_curve(self, current_pmfw_curve: PmfwCurve) -> anyhow::Result<PmfwCurve> {
@@ -75,14 +139,372 @@ impl FanCurve {
 }
 
 impl FanCurve {
-    pub fn validate(&self) -> anyhow::Result<()> {
-        for percentage in self.0.values() {
+    pub fn validate(&self, input: FanCurveInput) -> anyhow::Result<()> {
+        if self.0.is_empty() {
+            return Err(anyhow!("The fan curve must have at least one point"));
+        }
+
+        let unit = input.unit();
+        let range = match input {
+            FanCurveInput::Temperature => CURVE_TEMP_RANGE,
+            FanCurveInput::Power => CURVE_POWER_RANGE,
+            FanCurveInput::Composite { power_weight } => {
+                if !(0.0..=1.0).contains(&power_weight) {
+                    return Err(anyhow!(
+                        "The power weight must be between 0 and 1, got {power_weight}"
+                    ));
+                }
+                CURVE_TEMP_RANGE
+            }
+        };
+
+        for (point, percentage) in &self.0 {
+            if !range.contains(point) {
+                return Err(anyhow!(
+                    "Fan curve point at {point}{unit} is outside of the allowed range of {}-{}{unit}",
+                    range.start(),
+                    range.end()
+                ));
+            }
             if !(0.0..=1.0).contains(percentage) {
-                return Err(anyhow!("Fan speed percentage must be between 0 and 1"));
+                return Err(anyhow!(
+                    "Fan speed percentage at {point}{unit} must be between 0 and 1, got {percentage}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the fan speed never goes down as the temperature (or other input) rises
+    pub fn validate_monotonic(&self, input: FanCurveInput) -> anyhow::Result<()> {
+        let unit = input.unit();
+        let quantity = match input {
+            FanCurveInput::Power => "power draw",
+            FanCurveInput::Temperature | FanCurveInput::Composite { .. } => "temperature",
+        };
+        let points: Vec<_> = self.0.iter().collect();
+        for pair in points.windows(2) {
+            let (lower_temp, lower_speed) = pair[0];
+            let (higher_temp, higher_speed) = pair[1];
+            if higher_speed < lower_speed {
+                return Err(anyhow!(
+                    "The fan speed drops from {:.0}% at {lower_temp}{unit} to {:.0}% at {higher_temp}{unit}, \
+                    the curve must not lower the speed as the {quantity} rises. \
+                    This check can be disabled with the `allow_unsafe_fan_curves` daemon option",
+                    lower_speed * 100.0,
+                    higher_speed * 100.0,
+                ));
             }
         }
         Ok(())
     }
+
+    /// Checks that the curve doesn't leave the GPU without cooling under load
+    pub fn validate_safety_floor(&self) -> anyhow::Result<()> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let speed = self.speed_at_temp(SAFETY_FLOOR_TEMP);
+        if speed < SAFETY_FLOOR_SPEED {
+            return Err(anyhow!(
+                "The fan curve only reaches {:.0}% at {SAFETY_FLOOR_TEMP}°C, at least {:.0}% is required to keep the GPU cooled under load. \
+                This check can be disabled with the `allow_unsafe_fan_curves` daemon option",
+                speed * 100.0,
+                SAFETY_FLOOR_SPEED * 100.0,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Limits how far the PWM can move from its previous value, given the maximum change rate in percent per second
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn ramp_pwm(previous: u8, target: u8, max_rate_percent: u64, elapsed: Duration) -> u8 {
+    let max_step =
+        (f64::from(u8::MAX) * max_rate_percent as f64 / 100.0 * elapsed.as_secs_f64()).max(1.0);
+    let step = (f64::from(target) - f64::from(previous)).clamp(-max_step, max_step);
+    (f64::from(previous) + step).round() as u8
+}
+
+/// Settings that control how the fan speed follows the curve over time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CurveTiming {
+    pub spindown_delay: Duration,
+    /// Smallest change of the curve input that adjusts the fan speed
+    pub change_threshold: f32,
+    /// Maximum fan speed change in percent per second
+    pub max_ramp_rate: Option<u64>,
+}
+
+impl CurveTiming {
+    /// Whether the curve input changed too little since the last adjustment to change the fan speeds.
+    /// Fans that are still ramping towards their target are always adjusted.
+    pub fn skip_adjustment(&self, last_value: Option<f32>, value: f32, ramping: bool) -> bool {
+        !ramping
+            && last_value
+                .is_some_and(|last_value| (last_value - value).abs() < self.change_threshold)
+    }
+}
+
+/// Speed that the curve logic last set a single fan to.
+/// Used by the fan control tasks and by [`simulate_curve`], so that the preview follows the same rules.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FanSpeedState {
+    /// Last speed and when it was set, relative to the start of fan control
+    last_pwm: Option<(u8, Duration)>,
+    /// If the last set speed was limited by the ramp rate and has not reached the curve value yet
+    pub ramping: bool,
+}
+
+impl FanSpeedState {
+    /// Speed to set the fan to for the target speed of the curve, `now` being the time since fan control started.
+    /// Returns `None` while the spindown to a lower speed is delayed.
+    pub fn next_pwm(&mut self, target_pwm: u8, now: Duration, timing: &CurveTiming) -> Option<u8> {
+        if let Some((previous_pwm, previous)) = self.last_pwm {
+            if !self.ramping
+                && target_pwm < previous_pwm
+                && now.saturating_sub(previous) < timing.spindown_delay
+            {
+                return None;
+            }
+        }
+
+        let pwm = match (timing.max_ramp_rate, self.last_pwm) {
+            (Some(rate), Some((previous_pwm, previous))) => {
+                ramp_pwm(previous_pwm, target_pwm, rate, now.saturating_sub(previous))
+            }
+            _ => target_pwm,
+        };
+        self.ramping = pwm != target_pwm;
+        self.last_pwm = Some((pwm, now));
+        Some(pwm)
+    }
+
+    pub fn last_pwm(&self) -> Option<u8> {
+        self.last_pwm.map(|(pwm, _)| pwm)
+    }
+}
+
+/// Replays the curve fan control logic on recorded `(unix timestamp in ms, temperature)` samples.
+/// Returns the fan speed between 0 and 1 that would have been set at every sample.
+pub fn simulate_curve(curve: &FanCurve, samples: &[(i64, f32)], timing: &CurveTiming) -> Vec<f32> {
+    let start = samples.first().map_or(0, |(timestamp, _)| *timestamp);
+    let mut last_temp = None;
+    let mut state = FanSpeedState::default();
+
+    samples
+        .iter()
+        .map(|&(timestamp, temp)| {
+            if !timing.skip_adjustment(last_temp, temp, state.ramping) {
+                let target_pwm = curve.pwm_at_temp(&Temperature {
+                    current: Some(temp),
+                    crit: None,
+                    crit_hyst: None,
+                });
+                let now = Duration::from_millis(u64::try_from(timestamp - start).unwrap_or(0));
+                if state.next_pwm(target_pwm, now, timing).is_some() {
+                    last_temp = Some(temp);
+                }
+            }
+
+            state
+                .last_pwm()
+                .map_or(0.0, |pwm| f32::from(pwm) / f32::from(u8::MAX))
+        })
+        .collect()
+}
+
+/// Value that the fan curve is evaluated at, given the temperature of the followed sensors and the power draw in watts.
+/// `None` when the power draw (or the power cap for a composite input) is not available.
+pub fn curve_input_value(
+    input: FanCurveInput,
+    temperature: f32,
+    power: Option<f64>,
+    power_cap: Option<f64>,
+) -> Option<f32> {
+    #[allow(clippy::cast_possible_truncation)]
+    match input {
+        FanCurveInput::Temperature => Some(temperature),
+        FanCurveInput::Power => power.map(|power| power as f32),
+        FanCurveInput::Composite { power_weight } => {
+            let cap = power_cap.filter(|cap| *cap > 0.0)?;
+            let power_percentage = (power? / cap * 100.0) as f32;
+            Some((1.0 - power_weight) * temperature + power_weight * power_percentage)
+        }
+    }
+}
+
+/// Picks the hottest of the given sensors, which is the one the fan curve should follow
+pub fn hottest_temperature(
+    mut temps: HashMap<String, Temperature>,
+    keys: &[&str],
+) -> Option<Temperature> {
+    keys.iter()
+        .filter_map(|key| temps.remove(*key))
+        .filter(|temp| temp.current.is_some())
+        .max_by(|a, b| {
+            a.current
+                .partial_cmp(&b.current)
+                .unwrap_or(cmp::Ordering::Equal)
+        })
+}
+
+/// Numbers of the fan channels (`pwm1`, `pwm2`, ...) exposed by a hardware monitor
+pub fn fan_channels(hw_mon_path: &Path) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir(hw_mon_path) else {
+        return vec![];
+    };
+
+    let mut channels: Vec<u32> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("pwm")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    channels.sort_unstable();
+    channels
+}
+
+/// Switches a fan channel between manual and automatic control.
+/// The first channel is also covered by [`HwMon::set_fan_control_method`].
+pub fn set_channel_manual_control(
+    hw_mon: &HwMon,
+    channel: u32,
+    manual: bool,
+) -> anyhow::Result<()> {
+    let value = if manual { "1" } else { "2" };
+    write_sysfs(hw_mon.get_path(), &format!("pwm{channel}_enable"), value)
+        .with_context(|| format!("Could not set control method of fan {channel}"))
+}
+
+pub fn set_channel_pwm(hw_mon: &HwMon, channel: u32, pwm: u8) -> anyhow::Result<()> {
+    write_sysfs(
+        hw_mon.get_path(),
+        &format!("pwm{channel}"),
+        &pwm.to_string(),
+    )
+    .with_context(|| format!("Could not set speed of fan {channel}"))
+}
+
+/// Speed range of a fan channel that can be set to a target RPM through `fan[N]_target`
+pub fn rpm_target_range(hw_mon_path: &Path, channel: u32) -> Option<RangeInclusive<u32>> {
+    let read = |name: &str| -> Option<u32> {
+        fs::read_to_string(hw_mon_path.join(format!("fan{channel}_{name}")))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+    let min = read("min")?;
+    let max = read("max")?;
+
+    (max > min && is_writable(&hw_mon_path.join(format!("fan{channel}_target"))))
+        .then_some(min..=max)
+}
+
+/// Converts a PWM value into the equivalent share of the fan's RPM range
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub fn pwm_to_rpm(pwm: u8, range: &RangeInclusive<u32>) -> u32 {
+    let ratio = f64::from(pwm) / f64::from(u8::MAX);
+    let span = f64::from(range.end() - range.start());
+    range.start() + (span * ratio).round() as u32
+}
+
+pub fn set_channel_rpm(hw_mon: &HwMon, channel: u32, rpm: u32) -> anyhow::Result<()> {
+    write_sysfs(
+        hw_mon.get_path(),
+        &format!("fan{channel}_target"),
+        &rpm.to_string(),
+    )
+    .with_context(|| format!("Could not set target speed of fan {channel}"))
+}
+
+pub fn channel_pwm(hw_mon: &HwMon, channel: u32) -> Option<u8> {
+    hw_mon
+        .read_file(format!("pwm{channel}"))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+pub fn channel_speed(hw_mon: &HwMon, channel: u32) -> Option<u32> {
+    hw_mon
+        .read_file(format!("fan{channel}_input"))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Parses a single value PMFW fan setting, such as `fan_zero_rpm_enable`, in the following format:
+/// ```text
+/// FAN_ZERO_RPM_STOP_TEMPERATURE:
+/// 50
+/// OD_RANGE:
+/// ZERO_RPM_STOP_TEMPERATURE: 25 100
+/// ```
+pub fn parse_pmfw_setting(raw: &str) -> Option<FanInfo> {
+    let mut lines = raw.lines().map(str::trim).filter(|line| !line.is_empty());
+
+    lines.next()?;
+    let current = lines.next()?.parse().ok()?;
+
+    let allowed_range = lines
+        .skip_while(|line| *line != "OD_RANGE:")
+        .nth(1)
+        .and_then(|line| {
+            let mut values = line.split_whitespace().skip(1);
+            let min = values.next()?.parse().ok()?;
+            let max = values.next()?.parse().ok()?;
+            Some((min, max))
+        });
+
+    Some(FanInfo {
+        current,
+        allowed_range,
+    })
+}
+
+/// PWM value (40%) above which a fan is expected to be spinning
+pub const FAN_STALL_MIN_PWM: u8 = 102;
+/// Fan speed below which a fan driven above [`FAN_STALL_MIN_PWM`] is considered stalled
+pub const FAN_STALL_MAX_RPM: u32 = 100;
+/// How long the stall condition has to persist before being reported, to allow for spin-up
+pub const FAN_STALL_DEBOUNCE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Default)]
+pub struct FanStallDetector {
+    stalled_since: Option<Instant>,
+}
+
+impl FanStallDetector {
+    /// Updates the detector with the latest fan readings, returning whether the fan is considered stalled
+    pub fn update(&mut self, pwm: Option<u8>, rpm: Option<u32>, now: Instant) -> Option<bool> {
+        let (pwm, rpm) = pwm.zip(rpm)?;
+
+        if pwm >= FAN_STALL_MIN_PWM && rpm < FAN_STALL_MAX_RPM {
+            let since = *self.stalled_since.get_or_insert(now);
+            Some(now.saturating_duration_since(since) >= FAN_STALL_DEBOUNCE)
+        } else {
+            self.stalled_since = None;
+            Some(false)
+        }
+    }
 }
 
 impl Default for FanCurve {
@@ -93,8 +515,18 @@ impl Default for FanCurve {
 
 #[cfg(test)]
 mod tests {
-    use super::{FanCurve, PmfwCurve};
+    use super::{
+        curve_input_value, fan_channels, hottest_temperature, parse_pmfw_setting, pwm_to_rpm,
+        ramp_pwm, rpm_target_range, simulate_curve, CurveTiming, FanCurve, FanCurveInput,
+        FanCurveMap, FanSpeedState, FanStallDetector, PmfwCurve, FAN_STALL_DEBOUNCE,
+    };
+    use crate::tests::TempDir;
     use amdgpu_sysfs::{gpu_handle::fan_control::FanCurveRanges, hw_mon::Temperature};
+    use std::{
+        collections::HashMap,
+        fs,
+        time::{Duration, Instant},
+    };
 
     fn simple_pwm(temp: f32) -> u8 {
         let curve = FanCurve([(0, 0.0), (100, 1.0)].into());
@@ -103,7 +535,7 @@ mod tests {
             crit: Some(150.0),
             crit_hyst: Some(-100.0),
         };
-        curve.pwm_at_temp(temp)
+        curve.pwm_at_temp(&temp)
     }
 
     #[test]
@@ -144,7 +576,7 @@ mod tests {
             crit: Some(90.0),
             crit_hyst: Some(0.0),
         };
-        let pwm = curve.pwm_at_temp(temp);
+        let pwm = curve.pwm_at_temp(&temp);
         assert_eq!(pwm, 255);
     }
 
@@ -157,7 +589,7 @@ mod tests {
                 crit: Some(90.0),
                 crit_hyst: Some(0.0),
             };
-            curve.pwm_at_temp(temp)
+            curve.pwm_at_temp(&temp)
         };
 
         assert_eq!(pwm_at_temp(30.0), 0);
@@ -178,7 +610,7 @@ mod tests {
                 crit: Some(90.0),
                 crit_hyst: Some(0.0),
             };
-            curve.pwm_at_temp(temp)
+            curve.pwm_at_temp(&temp)
         };
         assert_eq!(pwm_at_temp(40.0), 51);
         assert_eq!(pwm_at_temp(60.0), 127);
@@ -190,6 +622,322 @@ mod tests {
         assert_eq!(pwm_at_temp(-5.0), 255);
     }
 
+    #[test]
+    fn default_curve_safety_floor() {
+        FanCurve::default().validate_safety_floor().unwrap();
+    }
+
+    #[test]
+    fn flat_zero_curve_safety_floor() {
+        let curve = FanCurve([(20, 0.0), (60, 0.0), (100, 0.0)].into());
+        assert!(curve.validate_safety_floor().is_err());
+    }
+
+    #[test]
+    fn late_curve_safety_floor() {
+        let curve = FanCurve([(40, 0.0), (85, 0.3), (95, 1.0)].into());
+        assert!(curve.validate_safety_floor().is_err());
+
+        let curve = FanCurve([(40, 0.0), (75, 0.3), (95, 1.0)].into());
+        curve.validate_safety_floor().unwrap();
+    }
+
+    #[test]
+    fn validate_curve_points() {
+        let temperature = FanCurveInput::Temperature;
+        FanCurve::default().validate(temperature).unwrap();
+        assert!(FanCurve(FanCurveMap::new()).validate(temperature).is_err());
+        assert!(FanCurve([(40, 0.2), (150, 1.0)].into())
+            .validate(temperature)
+            .is_err());
+        assert!(FanCurve([(-10, 0.2), (80, 1.0)].into())
+            .validate(temperature)
+            .is_err());
+        assert!(FanCurve([(40, 0.2), (80, 1.5)].into())
+            .validate(temperature)
+            .is_err());
+
+        let power_curve = FanCurve([(50, 0.2), (300, 1.0)].into());
+        power_curve.validate(FanCurveInput::Power).unwrap();
+        assert!(power_curve.validate(temperature).is_err());
+        assert!(FanCurve::default()
+            .validate(FanCurveInput::Composite { power_weight: 1.5 })
+            .is_err());
+    }
+
+    #[test]
+    fn non_monotonic_curve() {
+        let temperature = FanCurveInput::Temperature;
+        FanCurve::default().validate_monotonic(temperature).unwrap();
+        FanCurve([(40, 0.3), (60, 0.3), (80, 1.0)].into())
+            .validate_monotonic(temperature)
+            .unwrap();
+
+        let curve = FanCurve([(40, 0.3), (60, 0.8), (80, 0.5)].into());
+        let err = curve.validate_monotonic(temperature).unwrap_err();
+        assert!(err.to_string().contains("80% at 60°C to 50% at 80°C"));
+        let err = curve.validate_monotonic(FanCurveInput::Power).unwrap_err();
+        assert!(err.to_string().contains("80% at 60W to 50% at 80W"));
+    }
+
+    #[test]
+    fn detect_fan_channels() {
+        let dir = TempDir::new("fan-channels");
+        for file in [
+            "pwm1",
+            "pwm1_enable",
+            "pwm3",
+            "pwm2",
+            "pwm2_max",
+            "fan1_input",
+        ] {
+            fs::write(dir.join(file), "0").unwrap();
+        }
+
+        assert_eq!(fan_channels(&dir), vec![1, 2, 3]);
+        assert!(fan_channels(&dir.join("missing")).is_empty());
+    }
+
+    #[test]
+    fn detect_rpm_targets() {
+        let dir = TempDir::new("fan-targets");
+        for (file, value) in [
+            ("fan1_min", "800"),
+            ("fan1_max", "3200\n"),
+            ("fan1_target", "1500"),
+            ("fan2_min", "0"),
+            ("fan2_max", "3000"),
+        ] {
+            fs::write(dir.join(file), value).unwrap();
+        }
+
+        assert_eq!(rpm_target_range(&dir, 1), Some(800..=3200));
+        // No target file
+        assert_eq!(rpm_target_range(&dir, 2), None);
+        assert_eq!(rpm_target_range(&dir, 3), None);
+    }
+
+    #[test]
+    fn pwm_as_rpm() {
+        let range = 800..=3200;
+        assert_eq!(pwm_to_rpm(0, &range), 800);
+        assert_eq!(pwm_to_rpm(u8::MAX, &range), 3200);
+        assert_eq!(pwm_to_rpm(128, &range), 2005);
+    }
+
+    #[test]
+    fn parse_zero_rpm_temperature() {
+        let raw =
+            "FAN_ZERO_RPM_STOP_TEMPERATURE:\n50\nOD_RANGE:\nZERO_RPM_STOP_TEMPERATURE: 25 100\n";
+        let info = parse_pmfw_setting(raw).unwrap();
+        assert_eq!(info.current, 50);
+        assert_eq!(info.allowed_range, Some((25, 100)));
+    }
+
+    #[test]
+    fn parse_zero_rpm_enable_without_range() {
+        let info = parse_pmfw_setting("FAN_ZERO_RPM_ENABLE:\n1\n").unwrap();
+        assert_eq!(info.current, 1);
+        assert_eq!(info.allowed_range, None);
+        assert!(parse_pmfw_setting("").is_none());
+    }
+
+    #[test]
+    fn ramp_pwm_limited() {
+        // 10% per second of 255 is 25.5 PWM per second
+        assert_eq!(ramp_pwm(100, 255, 10, Duration::from_secs(1)), 126);
+        assert_eq!(ramp_pwm(200, 0, 10, Duration::from_secs(2)), 149);
+    }
+
+    #[test]
+    fn ramp_pwm_reaches_target() {
+        assert_eq!(ramp_pwm(100, 110, 10, Duration::from_secs(1)), 110);
+        assert_eq!(ramp_pwm(100, 100, 10, Duration::from_secs(1)), 100);
+    }
+
+    #[test]
+    fn ramp_pwm_always_progresses() {
+        assert_eq!(ramp_pwm(100, 200, 1, Duration::from_millis(10)), 101);
+    }
+
+    #[test]
+    fn fan_stall_debounced() {
+        let mut detector = FanStallDetector::default();
+        let start = Instant::now();
+
+        assert_eq!(detector.update(Some(255), Some(0), start), Some(false));
+        assert_eq!(
+            detector.update(Some(255), Some(0), start + Duration::from_secs(1)),
+            Some(false)
+        );
+        assert_eq!(
+            detector.update(Some(255), Some(0), start + FAN_STALL_DEBOUNCE),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn fan_stall_reset_on_spin_up() {
+        let mut detector = FanStallDetector::default();
+        let start = Instant::now();
+
+        detector.update(Some(255), Some(0), start);
+        assert_eq!(
+            detector.update(Some(255), Some(1500), start + Duration::from_secs(3)),
+            Some(false)
+        );
+        assert_eq!(
+            detector.update(Some(255), Some(0), start + FAN_STALL_DEBOUNCE),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn fan_stall_ignored_at_low_pwm() {
+        let mut detector = FanStallDetector::default();
+        let start = Instant::now();
+
+        detector.update(Some(0), Some(0), start);
+        assert_eq!(
+            detector.update(Some(0), Some(0), start + FAN_STALL_DEBOUNCE * 2),
+            Some(false)
+        );
+        assert_eq!(detector.update(None, Some(0), start), None);
+    }
+
+    #[test]
+    fn simulate_spindown_delay_and_ramp() {
+        let curve = FanCurve([(40, 0.0), (80, 1.0)].into());
+        let samples = [(0, 80.0), (1000, 40.0), (2000, 40.0), (4000, 40.0)];
+
+        let spindown = CurveTiming {
+            spindown_delay: Duration::from_secs(3),
+            ..Default::default()
+        };
+        let speeds = simulate_curve(&curve, &samples, &spindown);
+        assert_eq!(speeds, [1.0, 1.0, 1.0, 0.0]);
+
+        let ramp = CurveTiming {
+            max_ramp_rate: Some(20),
+            ..Default::default()
+        };
+        let speeds = simulate_curve(&curve, &samples, &ramp);
+        let expected: Vec<f32> = [255, 204, 153, 51]
+            .into_iter()
+            .map(|pwm: u8| f32::from(pwm) / 255.0)
+            .collect();
+        assert_eq!(speeds, expected);
+    }
+
+    #[test]
+    fn change_threshold_skips_small_changes() {
+        let timing = CurveTiming {
+            change_threshold: 3.0,
+            ..Default::default()
+        };
+        assert!(!timing.skip_adjustment(None, 50.0, false));
+        assert!(timing.skip_adjustment(Some(50.0), 52.0, false));
+        assert!(!timing.skip_adjustment(Some(50.0), 52.0, true));
+        assert!(!timing.skip_adjustment(Some(50.0), 53.0, false));
+    }
+
+    #[test]
+    fn fan_speed_state_keeps_ramping_past_spindown_delay() {
+        let timing = CurveTiming {
+            spindown_delay: Duration::from_secs(10),
+            max_ramp_rate: Some(20),
+            ..Default::default()
+        };
+        let mut state = FanSpeedState::default();
+        assert_eq!(state.next_pwm(255, Duration::ZERO, &timing), Some(255));
+        assert_eq!(state.next_pwm(0, Duration::from_secs(1), &timing), None);
+        assert_eq!(state.last_pwm(), Some(255));
+
+        let mut state = FanSpeedState::default();
+        assert_eq!(state.next_pwm(0, Duration::ZERO, &timing), Some(0));
+        assert_eq!(
+            state.next_pwm(255, Duration::from_secs(1), &timing),
+            Some(51)
+        );
+        assert!(state.ramping);
+        // Ramping continues even if the target drops again within the spindown delay
+        assert_eq!(
+            state.next_pwm(40, Duration::from_secs(2), &timing),
+            Some(40)
+        );
+        assert!(!state.ramping);
+    }
+
+    fn temps(values: &[(&str, Option<f32>)]) -> HashMap<String, Temperature> {
+        values
+            .iter()
+            .map(|(key, current)| {
+                (
+                    (*key).to_owned(),
+                    Temperature {
+                        current: *current,
+                        crit: None,
+                        crit_hyst: None,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hottest_of_selected_sensors() {
+        let temps = temps(&[
+            ("edge", Some(60.0)),
+            ("junction", Some(85.0)),
+            ("mem", Some(90.0)),
+        ]);
+        let temp = hottest_temperature(temps, &["edge", "junction"]).unwrap();
+        assert_eq!(temp.current, Some(85.0));
+    }
+
+    #[test]
+    fn hottest_ignores_missing_sensors() {
+        let temps = temps(&[("edge", Some(60.0)), ("junction", None)]);
+        let temp = hottest_temperature(temps.clone(), &["junction", "mem", "edge"]).unwrap();
+        assert_eq!(temp.current, Some(60.0));
+        assert!(hottest_temperature(temps, &["mem"]).is_none());
+    }
+
+    #[test]
+    fn curve_input_values() {
+        let composite = FanCurveInput::Composite { power_weight: 0.25 };
+        assert_eq!(
+            curve_input_value(FanCurveInput::Temperature, 60.0, None, None),
+            Some(60.0)
+        );
+        assert_eq!(
+            curve_input_value(FanCurveInput::Power, 60.0, Some(180.5), None),
+            Some(180.5)
+        );
+        assert_eq!(
+            curve_input_value(FanCurveInput::Power, 60.0, None, None),
+            None
+        );
+        assert_eq!(
+            curve_input_value(composite, 60.0, Some(200.0), Some(200.0)),
+            Some(70.0)
+        );
+        assert_eq!(curve_input_value(composite, 60.0, Some(200.0), None), None);
+    }
+
+    #[test]
+    fn power_curve_keeps_safety_floor() {
+        let curve = FanCurve([(50, 0.0), (300, 1.0)].into());
+        let temp = |current| Temperature {
+            current: Some(current),
+            crit: None,
+            crit_hyst: None,
+        };
+        assert_eq!(curve.pwm_at(FanCurveInput::Power, 50.0, &temp(60.0)), 0);
+        assert_eq!(curve.pwm_at(FanCurveInput::Power, 50.0, &temp(85.0)), 76);
+        assert_eq!(curve.pwm_at(FanCurveInput::Power, 300.0, &temp(85.0)), 255);
+    }
+
     #[test]
     fn default_curve_to_pmfw() {
         let curve = FanCurve::default();
@@ -0,0 +1,234 @@
+//! Hands fan control back to the hardware when a fan control loop stops unexpectedly.
+//! Without it the fans would stay at the last speed that was set, no matter how hot the GPU gets.
+use super::sysfs_write::{write_sysfs, SysfsWriteError};
+use nvml_wrapper::Nvml;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, Once,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{error, info};
+
+/// Shortest time a fan control loop may go without ticking before its fans are handed back
+const MIN_STALL_TIMEOUT: Duration = Duration::from_secs(15);
+/// Amount of missed fan control ticks after which the loop is considered to be stalled
+const STALL_TICKS: u32 = 5;
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Values of `pwmN_enable` to try, automatic control and then no control (full speed)
+const FALLBACK_MODES: [&str; 2] = ["2", "0"];
+pub const PMFW_FAN_CURVE_FILE: &str = "gpu_od/fan_ctrl/fan_curve";
+
+static WATCHED: Mutex<BTreeMap<u64, Watched>> = Mutex::new(BTreeMap::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static INSTALL: Once = Once::new();
+
+/// How the fans of a loop are handed back to the hardware
+pub enum FanFallback {
+    Hwmon {
+        pwm_enable_paths: Vec<PathBuf>,
+        /// Firmware fan curve that approximates the curve of the loop, so the fans still follow it.
+        /// It is committed after automatic control has been restored.
+        pmfw_curve: Option<PmfwFallbackCurve>,
+    },
+    /// Resets the fans to their default policy through a separate NVML handle, as the one of the daemon can't leave its thread
+    Nvidia { pci_slot_id: String, fan_count: u32 },
+}
+
+pub struct PmfwFallbackCurve {
+    pub device_path: PathBuf,
+    pub points: Vec<(i32, u8)>,
+}
+
+struct Watched {
+    fallback: FanFallback,
+    last_tick: Instant,
+    stall_timeout: Duration,
+    released: bool,
+}
+
+/// Watches a fan control loop, which has to call [`FanWatchdog::tick`] on every iteration.
+/// Dropping it stops watching without changing the fans, which is done when the loop is stopped on purpose.
+pub struct FanWatchdog {
+    id: u64,
+}
+
+impl FanWatchdog {
+    pub fn new(fallback: FanFallback, interval: Duration) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let watched = Watched {
+            fallback,
+            last_tick: Instant::now(),
+            stall_timeout: (interval * STALL_TICKS).max(MIN_STALL_TIMEOUT),
+            released: false,
+        };
+        WATCHED.lock().unwrap().insert(id, watched);
+        Self { id }
+    }
+
+    /// Returns `false` when the fans were handed back because the loop stalled, in which case the loop has to take control of them again.
+    /// The loop is watched again from then on.
+    pub fn tick(&self) -> bool {
+        match WATCHED.lock().unwrap().get_mut(&self.id) {
+            Some(watched) => {
+                watched.last_tick = Instant::now();
+                !std::mem::replace(&mut watched.released, false)
+            }
+            None => false,
+        }
+    }
+
+    /// Hands the fans back to the hardware, for when the loop has to stop because of an error
+    pub fn release(self) {
+        if let Some(watched) = WATCHED.lock().unwrap().remove(&self.id) {
+            restore_fans(&watched.fallback);
+        }
+    }
+}
+
+impl Drop for FanWatchdog {
+    fn drop(&mut self) {
+        WATCHED.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Starts the watchdog thread and makes panics hand the fans back before the daemon exits
+pub fn install() {
+    INSTALL.call_once(|| {
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // The lock may be poisoned by the panic itself, the paths are still valid then
+            let watched = WATCHED.lock().unwrap_or_else(|err| err.into_inner());
+            for watched in watched.values() {
+                restore_fans(&watched.fallback);
+            }
+            drop(watched);
+            previous_hook(info);
+        }));
+
+        let result = std::thread::Builder::new()
+            .name("fan-watchdog".to_owned())
+            .spawn(|| loop {
+                std::thread::sleep(CHECK_INTERVAL);
+                release_stalled(Instant::now());
+            });
+        if let Err(err) = result {
+            error!("could not start the fan control watchdog: {err}");
+        }
+    });
+}
+
+/// Hands back the fans of loops that have not ticked within their timeout
+fn release_stalled(now: Instant) {
+    let mut watched = WATCHED.lock().unwrap_or_else(|err| err.into_inner());
+    for watched in watched.values_mut() {
+        if !watched.released
+            && now.saturating_duration_since(watched.last_tick) > watched.stall_timeout
+        {
+            error!(
+                "fan control has not updated the fans for over {}s, handing control back to the hardware",
+                watched.stall_timeout.as_secs()
+            );
+            restore_fans(&watched.fallback);
+            watched.released = true;
+        }
+    }
+}
+
+fn restore_fans(fallback: &FanFallback) {
+    match fallback {
+        FanFallback::Hwmon {
+            pwm_enable_paths,
+            pmfw_curve,
+        } => {
+            for path in pwm_enable_paths {
+                if FALLBACK_MODES
+                    .iter()
+                    .any(|mode| fs::write(path, mode).is_ok())
+                {
+                    info!("restored fan control at {path:?}");
+                } else {
+                    error!("could not restore fan control at {path:?}");
+                }
+            }
+
+            if let Some(curve) = pmfw_curve {
+                match write_pmfw_curve(curve) {
+                    Ok(()) => info!("applied the fan curve to the GPU firmware"),
+                    Err(err) => error!("could not apply the fan curve to the GPU firmware: {err}"),
+                }
+            }
+        }
+        FanFallback::Nvidia {
+            pci_slot_id,
+            fan_count,
+        } => {
+            let result = Nvml::init().and_then(|nvml| {
+                let mut device = nvml.device_by_pci_bus_id(pci_slot_id.as_str())?;
+                (0..*fan_count).try_for_each(|fan| device.set_default_fan_speed(fan))
+            });
+            match result {
+                Ok(()) => info!("restored fan control of GPU {pci_slot_id}"),
+                Err(err) => error!("could not restore fan control of GPU {pci_slot_id}: {err}"),
+            }
+        }
+    }
+}
+
+fn write_pmfw_curve(curve: &PmfwFallbackCurve) -> Result<(), SysfsWriteError> {
+    for (index, (temperature, speed)) in curve.points.iter().enumerate() {
+        write_sysfs(
+            &curve.device_path,
+            PMFW_FAN_CURVE_FILE,
+            &format!("{index} {temperature} {speed}"),
+        )?;
+    }
+    write_sysfs(&curve.device_path, PMFW_FAN_CURVE_FILE, "c")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{release_stalled, FanFallback, FanWatchdog, MIN_STALL_TIMEOUT};
+    use crate::tests::TempDir;
+    use std::{
+        fs,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn stalled_loop_restores_fans() {
+        let dir = TempDir::new("fan-watchdog");
+        let stalled_path = dir.join("pwm1_enable");
+        let ticking_path = dir.join("pwm2_enable");
+        fs::write(&stalled_path, "1").unwrap();
+        fs::write(&ticking_path, "1").unwrap();
+
+        let hwmon = |path| FanFallback::Hwmon {
+            pwm_enable_paths: vec![path],
+            pmfw_curve: None,
+        };
+        let stalled = FanWatchdog::new(hwmon(stalled_path.clone()), Duration::from_millis(500));
+        let ticking = FanWatchdog::new(hwmon(ticking_path.clone()), Duration::from_secs(10));
+
+        release_stalled(Instant::now() + MIN_STALL_TIMEOUT + Duration::from_secs(1));
+        assert_eq!(fs::read_to_string(&stalled_path).unwrap(), "2");
+        // The timeout scales with the interval of the loop
+        assert_eq!(fs::read_to_string(&ticking_path).unwrap(), "1");
+        assert!(!stalled.tick());
+        assert!(ticking.tick());
+
+        // The loop takes control again after recovering and is watched from then on
+        fs::write(&stalled_path, "1").unwrap();
+        assert!(stalled.tick());
+        release_stalled(Instant::now() + MIN_STALL_TIMEOUT + Duration::from_secs(1));
+        assert_eq!(fs::read_to_string(&stalled_path).unwrap(), "2");
+
+        drop(stalled);
+        ticking.release();
+        assert_eq!(fs::read_to_string(&ticking_path).unwrap(), "2");
+    }
+}
@@ -0,0 +1,619 @@
+use super::{
+    format_gpu_id, is_writable, read_pci_info, sysfs_write::write_sysfs, vbios, GpuController,
+};
+use crate::{
+    config::{self, clamp_setting},
+    server::{runtime_pm::apply_runtime_pm, vulkan::get_vulkan_info},
+};
+use amdgpu_sysfs::{
+    gpu_handle::{power_profile_mode::PowerProfileModesTable, GpuHandle, PerformanceLevel},
+    hw_mon::HwMon,
+    sysfs::SysFS,
+};
+use anyhow::{anyhow, bail, Context};
+use futures::future::LocalBoxFuture;
+use lact_schema::{
+    ClocksControlKind, ClocksInfo, ClocksTable, ClockspeedStats, DeviceCapabilities, DeviceInfo,
+    DeviceStats, ExtraClock, FanStats, GpuPciInfo, IntelClocksTable, LinkInfo, MemoryState,
+    PowerStates, PowerStats, ThrottleStatus, VoltageStats,
+};
+use pciid_parser::Database;
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tracing::{debug, warn};
+
+/// Lowest power limit that can be configured, as the driver does not report one
+const MIN_POWER_CAP: f64 = 1.0;
+/// Energy readings closer together than this are too noisy to calculate the power usage from
+const MIN_ENERGY_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IntelDriver {
+    I915,
+    Xe,
+}
+
+/// Names of the files used by a driver for frequency control, relative to the frequency directory
+struct FreqFiles {
+    min: &'static str,
+    max: &'static str,
+    boost: Option<&'static str>,
+    rp0: &'static str,
+    rpn: &'static str,
+    act: &'static str,
+}
+
+const I915_FREQ_FILES: FreqFiles = FreqFiles {
+    min: "gt_min_freq_mhz",
+    max: "gt_max_freq_mhz",
+    boost: Some("gt_boost_freq_mhz"),
+    rp0: "gt_RP0_freq_mhz",
+    rpn: "gt_RPn_freq_mhz",
+    act: "gt_act_freq_mhz",
+};
+
+const XE_FREQ_FILES: FreqFiles = FreqFiles {
+    min: "min_freq",
+    max: "max_freq",
+    boost: None,
+    rp0: "rp0_freq",
+    rpn: "rpn_freq",
+    act: "act_freq",
+};
+
+pub struct IntelGpuController {
+    handle: GpuHandle,
+    driver: IntelDriver,
+    pci_info: Option<GpuPciInfo>,
+    /// Directory with the frequency controls of the primary GT
+    freq_dir: PathBuf,
+    /// Directory with the `throttle_reason_*` (i915) or `reason_*` (xe) files
+    throttle_dir: PathBuf,
+    /// SLPC power profile file, used for power hints
+    power_profile_file: PathBuf,
+    /// Last energy counter reading in microjoules and the power usage calculated from it
+    last_energy: RefCell<Option<(u64, Instant, Option<f64>)>>,
+}
+
+impl IntelGpuController {
+    /// Returns `None` when the card is not driven by i915 or xe
+    pub fn new_from_path(card_path: &Path, pci_db: &Database) -> anyhow::Result<Option<Self>> {
+        let handle = GpuHandle::new_from_path(card_path.join("device"))
+            .map_err(|error| anyhow!("failed to initialize gpu handle: {error}"))?;
+
+        let driver = match handle.get_driver() {
+            "i915" => IntelDriver::I915,
+            "xe" => IntelDriver::Xe,
+            _ => return Ok(None),
+        };
+
+        let (freq_dir, throttle_dir, power_profile_file) = match driver {
+            IntelDriver::I915 => {
+                let gt_dir = card_path.join("gt").join("gt0");
+                (
+                    card_path.to_owned(),
+                    gt_dir.clone(),
+                    gt_dir.join("slpc_power_profile"),
+                )
+            }
+            IntelDriver::Xe => {
+                let freq_dir = handle.get_path().join("tile0/gt0/freq0");
+                (
+                    freq_dir.clone(),
+                    freq_dir.join("throttle"),
+                    freq_dir.join("power_profile"),
+                )
+            }
+        };
+
+        let pci_info = read_pci_info(&handle, pci_db);
+
+        Ok(Some(Self {
+            handle,
+            driver,
+            pci_info,
+            freq_dir,
+            throttle_dir,
+            power_profile_file,
+            last_energy: RefCell::new(None),
+        }))
+    }
+
+    fn freq_files(&self) -> &'static FreqFiles {
+        match self.driver {
+            IntelDriver::I915 => &I915_FREQ_FILES,
+            IntelDriver::Xe => &XE_FREQ_FILES,
+        }
+    }
+
+    fn read_freq(&self, file: &str) -> anyhow::Result<i32> {
+        let path = self.freq_dir.join(file);
+        let contents =
+            fs::read_to_string(&path).with_context(|| format!("Could not read {path:?}"))?;
+        contents
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid frequency in {path:?}"))
+    }
+
+    fn write_freq(&self, file: &str, value: i32) -> anyhow::Result<()> {
+        write_sysfs(&self.freq_dir, file, &value.to_string())?;
+        Ok(())
+    }
+
+    /// Lowest and highest frequency supported by the hardware
+    fn freq_range(&self) -> anyhow::Result<(i32, i32)> {
+        let files = self.freq_files();
+        Ok((self.read_freq(files.rpn)?, self.read_freq(files.rp0)?))
+    }
+
+    /// Applies the frequency limits, using the hardware limits for unset values
+    fn set_freqs(
+        &self,
+        min: Option<i32>,
+        max: Option<i32>,
+        boost: Option<i32>,
+    ) -> anyhow::Result<()> {
+        let files = self.freq_files();
+        let (rpn, rp0) = self.freq_range()?;
+
+        let min = clamp_setting("min core clock", min.unwrap_or(rpn), Some(rpn), Some(rp0));
+        let max = clamp_setting("max core clock", max.unwrap_or(rp0), Some(rpn), Some(rp0));
+        if min > max {
+            bail!("Minimum GPU clock {min}MHz is higher than the maximum {max}MHz");
+        }
+
+        // The driver rejects a minimum above the current maximum and vice versa,
+        // so the order of the writes depends on the direction of the change
+        let current_max = self.read_freq(files.max)?;
+        if min > current_max {
+            self.write_freq(files.max, max)?;
+            self.write_freq(files.min, min)?;
+        } else {
+            self.write_freq(files.min, min)?;
+            self.write_freq(files.max, max)?;
+        }
+
+        match files.boost {
+            Some(boost_file) => {
+                let boost = clamp_setting(
+                    "boost core clock",
+                    boost.unwrap_or(rp0),
+                    Some(rpn),
+                    Some(rp0),
+                );
+                self.write_freq(boost_file, boost)?;
+            }
+            None if boost.is_some() => {
+                warn!(
+                    "boost clock is not supported by the {:?} driver, ignoring",
+                    self.driver
+                );
+            }
+            None => (),
+        }
+
+        Ok(())
+    }
+
+    fn first_hw_mon(&self) -> anyhow::Result<&HwMon> {
+        self.handle
+            .hw_monitors
+            .first()
+            .context("GPU has no hardware monitor")
+    }
+
+    /// Reads a power value from the hwmon, converting it from microwatts
+    #[allow(clippy::cast_precision_loss)]
+    fn read_power(&self, file: &str) -> Option<f64> {
+        let raw = self.first_hw_mon().ok()?.read_file(file).ok()?;
+        raw.trim()
+            .parse::<u64>()
+            .ok()
+            .map(|microwatts| microwatts as f64 / 1_000_000.0)
+    }
+
+    /// The driver only exposes an energy counter, so the power usage is calculated from the difference between readings
+    #[allow(clippy::cast_precision_loss)]
+    fn get_power_usage(&self) -> Option<f64> {
+        let energy: u64 = self
+            .first_hw_mon()
+            .ok()?
+            .read_file("energy1_input")
+            .ok()?
+            .trim()
+            .parse()
+            .ok()?;
+        let now = Instant::now();
+
+        let mut last_energy = self.last_energy.borrow_mut();
+        let power = match *last_energy {
+            Some((_, last_time, power)) if now - last_time < MIN_ENERGY_INTERVAL => return power,
+            Some((last_value, last_time, _)) if energy >= last_value => {
+                Some((energy - last_value) as f64 / 1_000_000.0 / (now - last_time).as_secs_f64())
+            }
+            _ => None,
+        };
+        *last_energy = Some((energy, now, power));
+        power
+    }
+
+    fn get_power_profile(&self) -> Option<PerformanceLevel> {
+        let contents = fs::read_to_string(&self.power_profile_file).ok()?;
+        match active_option(&contents)? {
+            "base" => Some(PerformanceLevel::Auto),
+            "power_saving" => Some(PerformanceLevel::Low),
+            _ => None,
+        }
+    }
+
+    fn set_power_profile(&self, level: PerformanceLevel) -> anyhow::Result<()> {
+        let profile = match level {
+            PerformanceLevel::Auto => "base",
+            PerformanceLevel::Low => "power_saving",
+            other => bail!("Performance level {other:?} is not supported on Intel GPUs"),
+        };
+
+        if self.get_power_profile() != Some(level) {
+            let dir = self
+                .power_profile_file
+                .parent()
+                .context("Invalid power profile path")?;
+            let file = self
+                .power_profile_file
+                .file_name()
+                .and_then(|name| name.to_str())
+                .context("Invalid power profile path")?;
+            write_sysfs(dir, file, profile)?;
+        }
+        Ok(())
+    }
+
+    fn get_link_info(&self) -> LinkInfo {
+        LinkInfo {
+            current_width: self.handle.get_current_link_width().ok(),
+            current_speed: self.handle.get_current_link_speed().ok(),
+            max_width: self.handle.get_max_link_width().ok(),
+            max_speed: self.handle.get_max_link_speed().ok(),
+        }
+    }
+
+    fn get_throttle_info(&self) -> Option<BTreeMap<String, Vec<String>>> {
+        let prefix = match self.driver {
+            IntelDriver::I915 => "throttle_reason_",
+            IntelDriver::Xe => "reason_",
+        };
+        read_throttle_reasons(&self.throttle_dir, prefix)
+    }
+}
+
+impl GpuController for IntelGpuController {
+    fn get_id(&self) -> anyhow::Result<String> {
+        let handle = &self.handle;
+        let pci_id = handle.get_pci_id().context("Device has no vendor id")?;
+        let pci_subsys_id = handle
+            .get_pci_subsys_id()
+            .context("Device has no subsys id")?;
+        let pci_slot_name = handle
+            .get_pci_slot_name()
+            .context("Device has no pci slot")?;
+
+        Ok(format_gpu_id(
+            (&pci_id.0, &pci_id.1),
+            (&pci_subsys_id.0, &pci_subsys_id.1),
+            &pci_slot_name,
+        ))
+    }
+
+    fn get_pci_info(&self) -> Option<&GpuPciInfo> {
+        self.pci_info.as_ref()
+    }
+
+    fn get_path(&self) -> &Path {
+        self.handle.get_path()
+    }
+
+    fn get_info(&self) -> DeviceInfo {
+        let vulkan_info = self.pci_info.as_ref().and_then(|pci_info| {
+            match get_vulkan_info(
+                &pci_info.device_pci_info.vendor_id,
+                &pci_info.device_pci_info.model_id,
+            ) {
+                Ok(info) => Some(info),
+                Err(err) => {
+                    warn!("could not load vulkan info: {err}");
+                    None
+                }
+            }
+        });
+
+        DeviceInfo {
+            pci_info: self.pci_info.clone(),
+            vulkan_info,
+            driver: self.handle.get_driver().to_owned(),
+            vbios_version: None,
+            link_info: self.get_link_info(),
+            drm_info: None,
+        }
+    }
+
+    fn hw_monitors(&self) -> &[HwMon] {
+        &self.handle.hw_monitors
+    }
+
+    fn get_pci_slot_name(&self) -> Option<String> {
+        self.handle.get_pci_slot_name().map(str::to_owned)
+    }
+
+    fn get_stats(&self, _gpu_config: Option<&config::Gpu>) -> DeviceStats {
+        let hw_mon = self.first_hw_mon().ok();
+        let files = self.freq_files();
+
+        DeviceStats {
+            fan: FanStats {
+                speed_current: hw_mon.and_then(|hw_mon| hw_mon.get_fan_current().ok()),
+                ..Default::default()
+            },
+            clockspeed: ClockspeedStats {
+                gpu_clockspeed: self
+                    .read_freq(files.act)
+                    .ok()
+                    .and_then(|freq| u64::try_from(freq).ok()),
+                ..Default::default()
+            },
+            power: PowerStats {
+                current: self.get_power_usage(),
+                cap_current: self.read_power("power1_max"),
+                cap_max: self.read_power("power1_rated_max"),
+                cap_min: self.read_power("power1_rated_max").map(|_| MIN_POWER_CAP),
+                cap_default: self.read_power("power1_rated_max"),
+                ..Default::default()
+            },
+            temps: hw_mon.map(HwMon::get_temps).unwrap_or_default(),
+            voltage: VoltageStats {
+                gpu: hw_mon.and_then(|hw_mon| hw_mon.get_gpu_voltage().ok()),
+                northbridge: None,
+            },
+            performance_level: self.get_power_profile(),
+            throttle_info: self.get_throttle_info(),
+            throttle_status: self.get_throttle_status(),
+            link_info: Some(self.get_link_info()),
+            ..Default::default()
+        }
+    }
+
+    fn get_clocks_info(&self) -> anyhow::Result<ClocksInfo> {
+        let files = self.freq_files();
+        let table = IntelClocksTable {
+            gt_freq_range: self.freq_range().ok(),
+            gt_freq_min: Some(self.read_freq(files.min)?),
+            gt_freq_max: Some(self.read_freq(files.max)?),
+            gt_freq_boost: files.boost.and_then(|file| self.read_freq(file).ok()),
+        };
+
+        Ok(ClocksInfo {
+            max_sclk: table.gt_freq_max,
+            table: Some(ClocksTable::Intel(table)),
+            ..Default::default()
+        })
+    }
+
+    fn get_capabilities(&self) -> DeviceCapabilities {
+        let files = self.freq_files();
+        let clocks_control = self.freq_range().is_ok()
+            && is_writable(&self.freq_dir.join(files.min))
+            && is_writable(&self.freq_dir.join(files.max));
+
+        let power_cap_range = self
+            .first_hw_mon()
+            .ok()
+            .filter(|hw_mon| is_writable(&hw_mon.get_path().join("power1_max")))
+            .and_then(|_| self.read_power("power1_rated_max"))
+            .map(|max| (MIN_POWER_CAP, max));
+
+        let mut temperature_sensors: Vec<String> = self
+            .first_hw_mon()
+            .map(|hw_mon| hw_mon.get_temps().into_keys().collect())
+            .unwrap_or_default();
+        temperature_sensors.sort_unstable();
+
+        DeviceCapabilities {
+            overdrive: clocks_control,
+            clocks_control: clocks_control.then_some(ClocksControlKind::Range),
+            power_cap_range,
+            temperature_sensors,
+            ..Default::default()
+        }
+    }
+
+    fn get_power_states(&self, _gpu_config: Option<&config::Gpu>) -> PowerStates {
+        PowerStates::default()
+    }
+
+    fn get_pcie_power_states(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn get_memory_states(&self) -> Vec<MemoryState> {
+        vec![]
+    }
+
+    fn get_power_profile_modes(&self) -> anyhow::Result<PowerProfileModesTable> {
+        Err(anyhow!("Not supported on Intel"))
+    }
+
+    fn get_throttle_status(&self) -> Option<ThrottleStatus> {
+        let reasons = self.get_throttle_info()?;
+        let mut status = ThrottleStatus::default();
+        for reason in reasons.keys() {
+            match reason.as_str() {
+                "pl1" | "pl2" | "pl4" => status.power = true,
+                "thermal" | "prochot" | "ratl" | "vr_thermalert" => status.thermal = true,
+                "vr_tdc" => status.current = true,
+                _ => status.other = true,
+            }
+        }
+        Some(status)
+    }
+
+    fn reset_pmfw_settings(&self) {}
+
+    fn has_fan_control_task(&self) -> bool {
+        false
+    }
+
+    fn set_benchmark_lock(&self, _enabled: bool) -> anyhow::Result<()> {
+        Err(anyhow!("Not supported on Intel"))
+    }
+
+    fn vbios_dump(&self) -> anyhow::Result<Vec<u8>> {
+        let rom = vbios::read_pci_rom(self.handle.get_path())?;
+        vbios::validate_rom(rom)
+    }
+
+    fn reset_gpu(&self) -> anyhow::Result<()> {
+        Err(anyhow!("Not supported on Intel"))
+    }
+
+    fn is_ready(&self, _config: &config::Gpu) -> bool {
+        true
+    }
+
+    fn apply_config<'a>(
+        &'a self,
+        config: &'a config::Gpu,
+    ) -> LocalBoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async {
+            apply_runtime_pm(self.handle.get_path(), config.runtime_pm)?;
+
+            let rated_max = self.read_power("power1_rated_max");
+            if let Some(cap) = config.power_cap.or(rated_max) {
+                let cap = clamp_setting("power cap", cap, Some(MIN_POWER_CAP), rated_max);
+                if self.read_power("power1_max") != Some(cap) {
+                    debug!("setting power cap to {cap}W");
+                    let hw_mon = self.first_hw_mon()?;
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let microwatts = (cap * 1_000_000.0) as u64;
+                    write_sysfs(hw_mon.get_path(), "power1_max", &microwatts.to_string())
+                        .context("Could not set power cap")?;
+                }
+            }
+
+            let clocks = &config.clocks_configuration;
+            if clocks.min_memory_clock.is_some()
+                || clocks.max_memory_clock.is_some()
+                || clocks.min_voltage.is_some()
+                || clocks.max_voltage.is_some()
+                || clocks.voltage_offset.is_some()
+                || clocks.core_clock_offset.is_some()
+                || ExtraClock::ALL
+                    .into_iter()
+                    .any(|clock| clocks.extra_clock(clock) != (None, None))
+                || !clocks.gpu_curve.is_empty()
+                || !clocks.memory_states.is_empty()
+            {
+                warn!(
+                    "only GPU clock limits are supported on Intel, ignoring other clock settings"
+                );
+            }
+            if config.is_core_clocks_used() {
+                self.set_freqs(
+                    clocks.min_core_clock,
+                    clocks.max_core_clock,
+                    clocks.boost_core_clock,
+                )
+                .context("Could not set GPU clocks")?;
+            } else {
+                self.cleanup_clocks()?;
+            }
+
+            match config.performance_level {
+                Some(level) => self
+                    .set_power_profile(level)
+                    .context("Could not set power profile")?,
+                None => {
+                    if self.power_profile_file.exists() {
+                        self.set_power_profile(PerformanceLevel::Auto)
+                            .context("Could not reset power profile")?;
+                    }
+                }
+            }
+
+            if config.fan_control_enabled {
+                warn!("fan control is not supported on Intel, ignoring");
+            }
+
+            Ok(())
+        })
+    }
+
+    fn cleanup_clocks(&self) -> anyhow::Result<()> {
+        if self.freq_range().is_ok() {
+            self.set_freqs(None, None, None)
+                .context("Could not reset GPU clocks")?;
+        }
+        Ok(())
+    }
+}
+
+/// Gets the selected value from a sysfs option list such as `[base] power_saving`
+fn active_option(contents: &str) -> Option<&str> {
+    contents
+        .split_whitespace()
+        .find_map(|option| option.strip_prefix('[')?.strip_suffix(']'))
+}
+
+/// Lists the active throttle reasons, the `status` file only tells if any of them is active
+fn read_throttle_reasons(dir: &Path, prefix: &str) -> Option<BTreeMap<String, Vec<String>>> {
+    let mut reasons = BTreeMap::new();
+    let mut found = false;
+
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let file_name = entry.file_name();
+        let Some(reason) = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix(prefix))
+            .filter(|reason| *reason != "status")
+        else {
+            continue;
+        };
+        found = true;
+
+        if fs::read_to_string(entry.path()).is_ok_and(|value| value.trim() == "1") {
+            reasons.insert(reason.to_owned(), vec![]);
+        }
+    }
+
+    found.then_some(reasons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{active_option, read_throttle_reasons};
+    use crate::tests::TempDir;
+    use std::fs;
+
+    #[test]
+    fn parse_active_option() {
+        assert_eq!(active_option("[base] power_saving\n"), Some("base"));
+        assert_eq!(active_option("base [power_saving]\n"), Some("power_saving"));
+        assert_eq!(active_option("base power_saving"), None);
+    }
+
+    #[test]
+    fn active_throttle_reasons() {
+        let dir = TempDir::new("intel-throttle");
+        fs::write(dir.join("throttle_reason_status"), "1\n").unwrap();
+        fs::write(dir.join("throttle_reason_pl1"), "1\n").unwrap();
+        fs::write(dir.join("throttle_reason_thermal"), "0\n").unwrap();
+
+        let reasons = read_throttle_reasons(&dir, "throttle_reason_").unwrap();
+        assert_eq!(reasons.keys().collect::<Vec<_>>(), ["pl1"]);
+        assert!(read_throttle_reasons(&dir, "reason_").is_none());
+    }
+}
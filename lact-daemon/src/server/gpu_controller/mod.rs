@@ -1,21 +1,46 @@
 #![allow(clippy::module_name_repetitions)]
 mod amd;
+mod apu;
 pub mod fan_control;
+pub mod fan_watchdog;
+mod intel;
 mod nvidia;
+mod od_extra_clocks;
+mod od_offsets;
+mod pp_features;
+pub mod sysfs_write;
+mod vbios;
 
 pub use amd::AmdGpuController;
+pub use intel::IntelGpuController;
 pub use nvidia::NvidiaGpuController;
 
 use crate::config::{self};
-use amdgpu_sysfs::gpu_handle::power_profile_mode::PowerProfileModesTable;
+use amdgpu_sysfs::gpu_handle::{power_profile_mode::PowerProfileModesTable, GpuHandle};
 use amdgpu_sysfs::hw_mon::HwMon;
 use futures::future::LocalBoxFuture;
-use lact_schema::{ClocksInfo, DeviceInfo, DeviceStats, GpuPciInfo, PowerStates};
+use lact_schema::{
+    ClocksInfo, DeviceCapabilities, DeviceInfo, DeviceStats, GpuPciInfo, MemoryState, PciInfo,
+    PowerStates, ThrottleStatus,
+};
+use nix::unistd::{access, AccessFlags};
+use pciid_parser::Database;
 use std::{path::Path, rc::Rc};
 use tokio::{sync::Notify, task::JoinHandle};
 
 type FanControlHandle = (Rc<Notify>, JoinHandle<()>);
 
+/// Builds the id of a GPU as `vendor:device-subsystem_vendor:subsystem_device-pci_slot`.
+/// It only depends on the hardware, so it stays the same across reboots and recreated configs,
+/// and only changes when the card is moved to another PCI slot.
+fn format_gpu_id(
+    (vendor, device): (&str, &str),
+    (subsystem_vendor, subsystem_device): (&str, &str),
+    pci_slot: &str,
+) -> String {
+    format!("{vendor}:{device}-{subsystem_vendor}:{subsystem_device}-{pci_slot}")
+}
+
 pub trait GpuController {
     fn get_id(&self) -> anyhow::Result<String>;
 
@@ -27,6 +52,10 @@ pub trait GpuController {
 
     fn get_pci_slot_name(&self) -> Option<String>;
 
+    /// Whether the driver exposes everything needed to apply the given settings.
+    /// Some GPUs take a while to fully initialize after boot.
+    fn is_ready(&self, config: &config::Gpu) -> bool;
+
     fn apply_config<'a>(
         &'a self,
         config: &'a config::Gpu,
@@ -36,15 +65,78 @@ pub trait GpuController {
 
     fn get_clocks_info(&self) -> anyhow::Result<ClocksInfo>;
 
+    fn get_capabilities(&self) -> DeviceCapabilities;
+
     fn get_power_states(&self, gpu_config: Option<&config::Gpu>) -> PowerStates;
 
+    /// Link speeds of the PCIe power states, empty when they can't be configured
+    fn get_pcie_power_states(&self) -> Vec<String>;
+
+    /// Memory power states with their clocks and voltages, empty when the GPU doesn't set clocks per power state
+    fn get_memory_states(&self) -> Vec<MemoryState>;
+
+    /// Kinds of throttling that are currently active, when the driver reports them
+    fn get_throttle_status(&self) -> Option<ThrottleStatus>;
+
     fn reset_pmfw_settings(&self);
 
+    /// Whether the fans follow a curve that is driven by the daemon, and stops when it exits
+    fn has_fan_control_task(&self) -> bool;
+
+    /// Pins the GPU to a fixed power state for consistent benchmarking, or releases it
+    fn set_benchmark_lock(&self, enabled: bool) -> anyhow::Result<()>;
+
     fn cleanup_clocks(&self) -> anyhow::Result<()>;
 
     fn get_power_profile_modes(&self) -> anyhow::Result<PowerProfileModesTable>;
 
     fn vbios_dump(&self) -> anyhow::Result<Vec<u8>>;
 
+    /// Resets the GPU to recover it from a hang, interrupting everything that is using it
+    fn reset_gpu(&self) -> anyhow::Result<()>;
+
     fn hw_monitors(&self) -> &[HwMon];
 }
+
+fn read_pci_info(handle: &GpuHandle, pci_db: &Database) -> Option<GpuPciInfo> {
+    let mut device_pci_info = None;
+    let mut subsystem_pci_info = None;
+
+    if let Some((vendor_id, model_id)) = handle.get_pci_id() {
+        device_pci_info = Some(PciInfo {
+            vendor_id: vendor_id.to_owned(),
+            vendor: None,
+            model_id: model_id.to_owned(),
+            model: None,
+        });
+
+        if let Some((subsys_vendor_id, subsys_model_id)) = handle.get_pci_subsys_id() {
+            let pci_device_info =
+                pci_db.get_device_info(vendor_id, model_id, subsys_vendor_id, subsys_model_id);
+
+            device_pci_info = Some(PciInfo {
+                vendor_id: vendor_id.to_owned(),
+                vendor: pci_device_info.vendor_name.map(str::to_owned),
+                model_id: model_id.to_owned(),
+                model: pci_device_info.device_name.map(str::to_owned),
+            });
+            subsystem_pci_info = Some(PciInfo {
+                vendor_id: subsys_vendor_id.to_owned(),
+                vendor: pci_device_info.subvendor_name.map(str::to_owned),
+                model_id: subsys_model_id.to_owned(),
+                model: pci_device_info.subdevice_name.map(str::to_owned),
+            });
+        };
+    }
+
+    device_pci_info.and_then(|device_pci_info| {
+        Some(GpuPciInfo {
+            device_pci_info,
+            subsystem_pci_info: subsystem_pci_info?,
+        })
+    })
+}
+
+fn is_writable(path: &Path) -> bool {
+    access(path, AccessFlags::W_OK).is_ok()
+}
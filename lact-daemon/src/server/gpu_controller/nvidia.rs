@@ -1,9 +1,13 @@
 use crate::{
-    config::{self, FanControlSettings},
-    server::vulkan::get_vulkan_info,
+    config::{self, clamp_setting, FanControlSettings},
+    server::{runtime_pm::apply_runtime_pm, vulkan::get_vulkan_info},
 };
 
-use super::{fan_control::FanCurve, FanControlHandle, GpuController};
+use super::{
+    fan_control::{curve_input_value, FanCurve, FanSpeedState},
+    fan_watchdog::{FanFallback, FanWatchdog},
+    format_gpu_id, vbios, FanControlHandle, GpuController,
+};
 use amdgpu_sysfs::{
     gpu_handle::power_profile_mode::PowerProfileModesTable,
     hw_mon::{HwMon, Temperature},
@@ -11,9 +15,10 @@ use amdgpu_sysfs::{
 use anyhow::{anyhow, Context};
 use futures::future::LocalBoxFuture;
 use lact_schema::{
-    ClocksInfo, ClocksTable, ClockspeedStats, DeviceInfo, DeviceStats, DrmInfo, DrmMemoryInfo,
-    FanControlMode, FanStats, GpuPciInfo, LinkInfo, NvidiaClockInfo, NvidiaClocksTable, PmfwInfo,
-    PowerState, PowerStates, PowerStats, VoltageStats, VramStats,
+    ClocksControlKind, ClocksInfo, ClocksTable, ClockspeedStats, DeviceCapabilities, DeviceInfo,
+    DeviceStats, DrmInfo, DrmMemoryInfo, FanChannelStats, FanControlMode, FanCurveInput,
+    FanSpeedUnit, FanStats, GpuPciInfo, LinkInfo, MemoryState, NvidiaClockInfo, NvidiaClocksTable,
+    PmfwInfo, PowerState, PowerStates, PowerStats, ThrottleStatus, VoltageStats, VramStats,
 };
 use nvml_wrapper::{
     bitmasks::device::ThrottleReasons,
@@ -22,7 +27,7 @@ use nvml_wrapper::{
 };
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::Write,
     path::{Path, PathBuf},
     rc::Rc,
@@ -69,7 +74,6 @@ impl NvidiaGpuController {
 
     async fn start_curve_fan_control_task(
         &self,
-        curve: FanCurve,
         settings: FanControlSettings,
     ) -> anyhow::Result<()> {
         // Stop existing task to re-apply new curve
@@ -85,6 +89,14 @@ impl NvidiaGpuController {
             return Err(anyhow!("Device has no fans"));
         }
 
+        let curve_input = settings.curve_input;
+        let (power, power_cap) = read_power(&device, curve_input);
+        if curve_input_value(curve_input, 0.0, power, power_cap).is_none() {
+            return Err(anyhow!(
+                "The fan curve follows the power draw, but the power usage or power cap of this GPU is not available"
+            ));
+        }
+
         let mut notify_guard = self
             .fan_control_handle
             .try_borrow_mut()
@@ -102,64 +114,97 @@ impl NvidiaGpuController {
                 .device_by_pci_bus_id(pci_slot_id.as_str())
                 .expect("Can no longer get device");
 
-            let mut last_pwm = (None, Instant::now());
-            let mut last_temp = 0;
+            let mut fans: Vec<FanState> = (0..fan_count)
+                .map(|index| FanState {
+                    index,
+                    curve: settings.curve_for_channel(index + 1).clone(),
+                    speed: FanSpeedState::default(),
+                })
+                .collect();
+            let mut last_value = None;
 
             let interval = Duration::from_millis(settings.interval_ms);
-            let spindown_delay = Duration::from_millis(settings.spindown_delay_ms.unwrap_or(0));
-            #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
-            let change_threshold = settings.change_threshold.unwrap_or(0) as i32;
+            let timing = settings.curve_timing();
+            let start = Instant::now();
+            let watchdog = FanWatchdog::new(
+                FanFallback::Nvidia {
+                    pci_slot_id,
+                    fan_count,
+                },
+                interval,
+            );
 
             loop {
                 select! {
                     () = sleep(interval) => (),
-                    () = task_notify.notified() => break,
+                    () = task_notify.notified() => {
+                        debug!("exited fan control task");
+                        return;
+                    }
+                }
+                if !watchdog.tick() {
+                    // Setting a fan speed takes manual control of the fan again
+                    warn!("fan control was stalled and had been handed back to the GPU, taking control again");
+                    last_value = None;
+                    for fan in &mut fans {
+                        fan.speed = FanSpeedState::default();
+                    }
                 }
 
-                #[allow(clippy::cast_possible_wrap)]
+                #[allow(clippy::cast_precision_loss)]
                 let current_temp = device
                     .temperature(TemperatureSensor::Gpu)
-                    .expect("Could not read temperature") as i32;
-
-                if (last_temp - current_temp).abs() < change_threshold {
-                    trace!("temperature changed from {last_temp}°C to {current_temp}°C, which is less than the {change_threshold}°C threshold, skipping speed adjustment");
+                    .expect("Could not read temperature") as f32;
+                let (power, power_cap) = read_power(&device, curve_input);
+                let Some(value) = curve_input_value(curve_input, current_temp, power, power_cap)
+                else {
+                    error!("could not read the power usage, disabling fan control");
+                    break;
+                };
+                let unit = curve_input.unit();
+
+                let ramping = fans.iter().any(|fan| fan.speed.ramping);
+                if timing.skip_adjustment(last_value, value, ramping) {
+                    trace!("curve input changed to {value}{unit}, which is less than the {}{unit} threshold, skipping speed adjustment", timing.change_threshold);
                     continue;
                 }
 
-                let target_pwm = curve.pwm_at_temp(Temperature {
-                    #[allow(clippy::cast_precision_loss)]
-                    current: Some(current_temp as f32),
+                let temperature = Temperature {
+                    current: Some(current_temp),
                     crit: None,
                     crit_hyst: None,
-                });
-                let now = Instant::now();
-
-                if let (Some(previous_pwm), previous_timestamp) = last_pwm {
-                    let diff = now - previous_timestamp;
-                    if target_pwm < previous_pwm && diff < spindown_delay {
-                        trace!(
-                            "delaying fan spindown ({}ms left)",
-                            (spindown_delay - diff).as_millis()
-                        );
+                };
+                let now = start.elapsed();
+                // A delayed spindown has to be re-evaluated on the next tick even if the value stays the same
+                let mut delayed = false;
+
+                for fan in &mut fans {
+                    let target_pwm = fan.curve.pwm_at(curve_input, value, &temperature);
+                    let Some(pwm) = fan.speed.next_pwm(target_pwm, now, &timing) else {
+                        trace!("delaying spindown of fan {}", fan.index);
+                        delayed = true;
                         continue;
-                    }
-                }
+                    };
 
-                last_pwm = (Some(target_pwm), now);
-                last_temp = current_temp;
+                    trace!(
+                        "fan control tick: setting pwm of fan {} to {pwm} (target {target_pwm})",
+                        fan.index
+                    );
 
-                trace!("fan control tick: setting pwm to {target_pwm}");
-
-                for fan in 0..fan_count {
                     #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
                     if let Err(err) =
-                        device.set_fan_speed(fan, (f64::from(target_pwm) / 2.5) as u32)
+                        device.set_fan_speed(fan.index, (f64::from(pwm) / 2.55) as u32)
                     {
-                        error!("could not set fan speed: {err}, disabling fan control");
-                        break;
+                        error!("could not set speed of fan {}: {err}", fan.index);
                     }
                 }
+
+                if !delayed {
+                    last_value = Some(value);
+                }
             }
+            // The fans would otherwise stay at the last speed that was set
+            watchdog.release();
             debug!("exited fan control task");
         });
 
@@ -258,13 +303,10 @@ impl GpuController for NvidiaGpuController {
             subsystem_pci_info,
         } = &self.pci_info;
 
-        Ok(format!(
-            "{}:{}-{}:{}-{}",
-            device_pci_info.vendor_id,
-            device_pci_info.model_id,
-            subsystem_pci_info.vendor_id,
-            subsystem_pci_info.model_id,
-            self.pci_slot_id
+        Ok(format_gpu_id(
+            (&device_pci_info.vendor_id, &device_pci_info.model_id),
+            (&subsystem_pci_info.vendor_id, &subsystem_pci_info.model_id),
+            &self.pci_slot_id,
         ))
     }
 
@@ -301,31 +343,7 @@ impl GpuController for NvidiaGpuController {
                 .vbios_version()
                 .map_err(|err| error!("could not get VBIOS version: {err}"))
                 .ok(),
-            link_info: LinkInfo {
-                current_width: device.current_pcie_link_width().map(|v| v.to_string()).ok(),
-                current_speed: device
-                    .pcie_link_speed()
-                    .map(|v| {
-                        let mut output = format!("{} GT/s", v / 1000);
-                        if let Ok(gen) = device.current_pcie_link_gen() {
-                            let _ = write!(output, " PCIe gen {gen}");
-                        }
-                        output
-                    })
-                    .ok(),
-                max_width: device.max_pcie_link_width().map(|v| v.to_string()).ok(),
-                max_speed: device
-                    .max_pcie_link_speed()
-                    .ok()
-                    .and_then(|v| v.as_integer())
-                    .map(|v| {
-                        let mut output = format!("{} GT/s", v / 1000);
-                        if let Ok(gen) = device.current_pcie_link_gen() {
-                            let _ = write!(output, " PCIe gen {gen}");
-                        }
-                        output
-                    }),
-            },
+            link_info: link_info(&device),
             drm_info: Some(DrmInfo {
                 device_name: device.name().ok(),
                 pci_revision_id: None,
@@ -390,7 +408,8 @@ impl GpuController for NvidiaGpuController {
 
         let fan_settings = gpu_config.and_then(|config| config.fan_control_settings.as_ref());
 
-        let pwm_current = if device.num_fans().is_ok_and(|num| num > 0) {
+        let fan_count = device.num_fans().unwrap_or(0);
+        let pwm_current = if fan_count > 0 {
             device
                 .fan_speed(0)
                 .ok()
@@ -399,11 +418,31 @@ impl GpuController for NvidiaGpuController {
             None
         };
 
+        // Individual fans are only listed when there is more than one
+        let fans = if fan_count > 1 {
+            (0..fan_count)
+                .map(|index| FanChannelStats {
+                    channel: index + 1,
+                    pwm_current: device
+                        .fan_speed(index)
+                        .ok()
+                        .map(|value| (f64::from(value) * 2.55) as u8),
+                    speed_current: None,
+                    curve: fan_settings
+                        .and_then(|settings| settings.fan_curves.get(&(index + 1)))
+                        .map(|curve| curve.0.clone()),
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
         let vram = device
             .memory_info()
             .map(|info| VramStats {
                 total: Some(info.total),
                 used: Some(info.used),
+                ..Default::default()
             })
             .unwrap_or_default();
 
@@ -421,10 +460,26 @@ impl GpuController for NvidiaGpuController {
                 curve: fan_settings.map(|settings| settings.curve.0.clone()),
                 spindown_delay_ms: fan_settings.and_then(|settings| settings.spindown_delay_ms),
                 change_threshold: fan_settings.and_then(|settings| settings.change_threshold),
+                max_ramp_rate: fan_settings.and_then(|settings| settings.max_ramp_rate),
+                temperature_sensors: fan_settings
+                    .map(|settings| {
+                        settings
+                            .temperature_keys()
+                            .into_iter()
+                            .map(str::to_owned)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
                 speed_current: None,
                 speed_max: None,
                 speed_min: None,
                 pwm_current,
+                stall_detected: None,
+                fans,
+                speed_unit: FanSpeedUnit::Pwm,
+                curve_input: fan_settings
+                    .map(|settings| settings.curve_input)
+                    .unwrap_or_default(),
                 pmfw_info: PmfwInfo::default(),
             },
             power: PowerStats {
@@ -446,6 +501,8 @@ impl GpuController for NvidiaGpuController {
                     .power_management_limit_default()
                     .map(|mw| f64::from(mw) / 1000.0)
                     .ok(),
+                thermal_target: None,
+                app_power_cap: None,
             },
             busy_percent: device
                 .utilization_rates()
@@ -468,11 +525,20 @@ impl GpuController for NvidiaGpuController {
                     })
                     .collect()
             }),
+            throttle_status: self.get_throttle_status(),
+            throttle_events: None,
             voltage: VoltageStats::default(), // Voltage reporting is not supported
+            link_info: Some(link_info(&device)),
             performance_level: None,
             core_power_state: active_pstate,
             memory_power_state: active_pstate,
             pcie_power_state: None,
+            benchmark_lock: None,
+            low_latency: false,
+            smoothed: None,
+            alerts: vec![],
+            engine_usage: BTreeMap::new(),
+            runtime_pm: None,
         }
     }
 
@@ -526,6 +592,45 @@ impl GpuController for NvidiaGpuController {
         })
     }
 
+    fn get_capabilities(&self) -> DeviceCapabilities {
+        let device = self.device();
+
+        let clock_offsets = device.gpc_clk_min_max_vf_offset().is_ok()
+            || device.mem_clk_min_max_vf_offset().is_ok();
+
+        let power_cap_range = device
+            .power_management_limit_constraints()
+            .map(|constraints| {
+                (
+                    f64::from(constraints.min_limit) / 1000.0,
+                    f64::from(constraints.max_limit) / 1000.0,
+                )
+            })
+            .ok();
+
+        let temperature_sensors = if device.temperature(TemperatureSensor::Gpu).is_ok() {
+            vec!["GPU".to_owned()]
+        } else {
+            vec![]
+        };
+
+        DeviceCapabilities {
+            overdrive: clock_offsets,
+            clocks_control: clock_offsets.then_some(ClocksControlKind::Offset),
+            voltage_offset: false,
+            pmfw_fan_control: false,
+            pwm_fan_control: device.num_fans().is_ok_and(|num| num > 0),
+            rpm_fan_control: false,
+            power_cap_range,
+            temperature_sensors,
+            power_profile_modes: false,
+            pcie_power_states: false,
+            ecc: device.is_ecc_enabled().is_ok(),
+            apu_limits: vec![],
+            low_latency_mode: false,
+        }
+    }
+
     fn get_power_states(&self, _gpu_config: Option<&config::Gpu>) -> PowerStates {
         self.try_get_power_states().unwrap_or_else(|err| {
             warn!("could not get pstates info: {err:#}");
@@ -533,27 +638,79 @@ impl GpuController for NvidiaGpuController {
         })
     }
 
+    fn get_pcie_power_states(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn get_memory_states(&self) -> Vec<MemoryState> {
+        vec![]
+    }
+
     fn get_power_profile_modes(&self) -> anyhow::Result<PowerProfileModesTable> {
         Err(anyhow!("Not supported on Nvidia"))
     }
 
+    fn get_throttle_status(&self) -> Option<ThrottleStatus> {
+        let reasons = self.device().current_throttle_reasons().ok()?;
+        Some(ThrottleStatus {
+            power: reasons.intersects(
+                ThrottleReasons::SW_POWER_CAP | ThrottleReasons::HW_POWER_BRAKE_SLOWDOWN,
+            ),
+            thermal: reasons.intersects(
+                ThrottleReasons::SW_THERMAL_SLOWDOWN | ThrottleReasons::HW_THERMAL_SLOWDOWN,
+            ),
+            current: false,
+            other: reasons.intersects(ThrottleReasons::HW_SLOWDOWN | ThrottleReasons::SYNC_BOOST),
+        })
+    }
+
     fn reset_pmfw_settings(&self) {}
 
+    fn has_fan_control_task(&self) -> bool {
+        self.fan_control_handle.borrow().is_some()
+    }
+
+    fn set_benchmark_lock(&self, _enabled: bool) -> anyhow::Result<()> {
+        Err(anyhow!("Not supported on Nvidia"))
+    }
+
     fn vbios_dump(&self) -> anyhow::Result<Vec<u8>> {
+        let rom = vbios::read_pci_rom(&self.sysfs_path)?;
+        vbios::validate_rom(rom)
+    }
+
+    fn reset_gpu(&self) -> anyhow::Result<()> {
         Err(anyhow!("Not supported on Nvidia"))
     }
 
+    fn is_ready(&self, _config: &config::Gpu) -> bool {
+        true
+    }
+
     #[allow(clippy::cast_possible_wrap)]
     fn apply_config<'a>(
         &'a self,
         config: &'a config::Gpu,
     ) -> LocalBoxFuture<'a, anyhow::Result<()>> {
         Box::pin(async {
+            apply_runtime_pm(&self.sysfs_path, config.runtime_pm)?;
+
             let mut device = self.device();
 
             if let Some(cap) = config.power_cap {
                 #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
                 let cap = (cap * 1000.0) as u32;
+                let constraints = device.power_management_limit_constraints().ok();
+                let cap = clamp_setting(
+                    "power cap",
+                    cap,
+                    constraints
+                        .as_ref()
+                        .map(|constraints| constraints.min_limit),
+                    constraints
+                        .as_ref()
+                        .map(|constraints| constraints.max_limit),
+                );
 
                 let current_cap = device
                     .power_management_limit()
@@ -585,7 +742,13 @@ impl GpuController for NvidiaGpuController {
                 let default_max_clock = device
                     .max_clock_info(Clock::Graphics)
                     .context("Could not read max graphics clock")?;
-                let offset = max_gpu_clock - default_max_clock as i32;
+                let offset_range = device.gpc_clk_min_max_vf_offset().ok();
+                let offset = clamp_setting(
+                    "graphics clock offset",
+                    max_gpu_clock - default_max_clock as i32,
+                    offset_range.map(|range| range.0),
+                    offset_range.map(|range| range.1),
+                );
                 debug!("Using graphics clock offset {offset}");
 
                 device
@@ -599,7 +762,13 @@ impl GpuController for NvidiaGpuController {
                 let default_max_clock = device
                     .max_clock_info(Clock::Memory)
                     .context("Could not read max memory clock")?;
-                let offset = max_mem_clock - default_max_clock as i32;
+                let offset_range = device.mem_clk_min_max_vf_offset().ok();
+                let offset = clamp_setting(
+                    "memory clock offset",
+                    max_mem_clock - default_max_clock as i32,
+                    offset_range.map(|range| range.0),
+                    offset_range.map(|range| range.1),
+                );
                 debug!("Using mem clock offset {offset}");
 
                 device
@@ -610,6 +779,10 @@ impl GpuController for NvidiaGpuController {
             }
 
             if config.fan_control_enabled {
+                if config.fan_control_hwmon_path.is_some() {
+                    warn!("external fan control hwmon is not supported on Nvidia, ignoring");
+                }
+
                 let settings = config
                     .fan_control_settings
                     .as_ref()
@@ -631,8 +804,7 @@ impl GpuController for NvidiaGpuController {
                         }
                     }
                     FanControlMode::Curve => {
-                        self.start_curve_fan_control_task(settings.curve.clone(), settings.clone())
-                            .await?;
+                        self.start_curve_fan_control_task(settings.clone()).await?;
                     }
                 }
             } else {
@@ -671,3 +843,49 @@ impl GpuController for NvidiaGpuController {
         Ok(())
     }
 }
+
+/// Power usage and power cap in watts, only read when the fan curve follows the power draw
+fn read_power(device: &Device<'_>, curve_input: FanCurveInput) -> (Option<f64>, Option<f64>) {
+    if curve_input == FanCurveInput::Temperature {
+        return (None, None);
+    }
+    let to_watts = |mw: u32| f64::from(mw) / 1000.0;
+    (
+        device.power_usage().map(to_watts).ok(),
+        device.power_management_limit().map(to_watts).ok(),
+    )
+}
+
+fn link_info(device: &Device<'_>) -> LinkInfo {
+    LinkInfo {
+        current_width: device.current_pcie_link_width().map(|v| v.to_string()).ok(),
+        current_speed: device
+            .pcie_link_speed()
+            .map(|v| {
+                let mut output = format!("{} GT/s", f64::from(v) / 1000.0);
+                if let Ok(gen) = device.current_pcie_link_gen() {
+                    let _ = write!(output, " PCIe gen {gen}");
+                }
+                output
+            })
+            .ok(),
+        max_width: device.max_pcie_link_width().map(|v| v.to_string()).ok(),
+        max_speed: device
+            .max_pcie_link_speed()
+            .ok()
+            .and_then(|v| v.as_integer())
+            .map(|v| {
+                let mut output = format!("{} GT/s", f64::from(v) / 1000.0);
+                if let Ok(gen) = device.max_pcie_link_gen() {
+                    let _ = write!(output, " PCIe gen {gen}");
+                }
+                output
+            }),
+    }
+}
+
+struct FanState {
+    index: u32,
+    curve: FanCurve,
+    speed: FanSpeedState,
+}
@@ -0,0 +1,147 @@
+//! Clocks besides the GPU and VRAM clock that some AMD GPUs allow overclocking, such as the data fabric clock.
+//! Each of them has its own `OD_<name>` section in `pp_od_clk_voltage` with a min and max level, which `amdgpu-sysfs` doesn't parse.
+use super::od_offsets::parse_value;
+use crate::config::{clamp_setting, ClocksConfiguration};
+use anyhow::bail;
+use lact_schema::{ExtraClock, ExtraClockRange};
+use std::collections::BTreeMap;
+
+/// Parses the sections of the extra clocks, GPUs without any of them return an empty map
+pub fn parse(contents: &str) -> BTreeMap<ExtraClock, ExtraClockRange> {
+    let mut levels: BTreeMap<ExtraClock, Vec<i32>> = BTreeMap::new();
+    let mut ranges = BTreeMap::new();
+    let mut section = "";
+
+    for line in contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        if let Some(name) = line.strip_suffix(':') {
+            section = name;
+            continue;
+        }
+
+        let Some((name, values)) = line.split_once(':') else {
+            continue;
+        };
+        if section == "OD_RANGE" {
+            let Some(clock) = find_clock(name.trim()) else {
+                continue;
+            };
+            let mut values = values.split_whitespace().filter_map(parse_value);
+            if let Some(range) = values.next().zip(values.next()) {
+                ranges.insert(clock, range);
+            }
+        } else if let Some(clock) = section.strip_prefix("OD_").and_then(find_clock) {
+            if let Some(value) = parse_value(values) {
+                levels.entry(clock).or_default().push(value);
+            }
+        }
+    }
+
+    levels
+        .into_iter()
+        .filter_map(|(clock, levels)| {
+            let [min, max] = levels[..] else {
+                return None;
+            };
+            let range = ranges.get(&clock).copied();
+            Some((clock, ExtraClockRange { min, max, range }))
+        })
+        .collect()
+}
+
+fn find_clock(od_name: &str) -> Option<ExtraClock> {
+    ExtraClock::ALL
+        .into_iter()
+        .find(|clock| clock.od_name() == od_name)
+}
+
+/// Commands to write to `pp_od_clk_voltage` for the configured extra clocks, without the final commit.
+/// The levels are set with `<name> <level> <clock>`, like `m` for the VRAM clock, and clamped to the allowed ranges.
+pub fn commands(
+    clocks: &BTreeMap<ExtraClock, ExtraClockRange>,
+    config: &ClocksConfiguration,
+) -> anyhow::Result<Vec<String>> {
+    let mut commands = Vec::new();
+
+    for clock in ExtraClock::ALL {
+        let (min, max) = config.extra_clock(clock);
+        if min.is_none() && max.is_none() {
+            continue;
+        }
+        let Some(info) = clocks.get(&clock) else {
+            bail!("Setting the {clock} clock is not supported on this GPU");
+        };
+
+        let command = clock.od_name().to_lowercase();
+        let (range_min, range_max) = info.range.unzip();
+        for (level, value) in [(0, min), (1, max)] {
+            if let Some(value) = value {
+                let value = clamp_setting(&format!("{clock} clock"), value, range_min, range_max);
+                commands.push(format!("{command} {level} {value}"));
+            }
+        }
+    }
+
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{commands, parse};
+    use crate::config::ClocksConfiguration;
+    use lact_schema::{ExtraClock, ExtraClockRange};
+
+    const TABLE: &str = "OD_SCLK:
+0: 500Mhz
+1: 2890Mhz
+OD_MCLK:
+0: 97Mhz
+1: 1249MHz
+OD_FCLK:
+0: 1200Mhz
+1: 1800Mhz
+OD_RANGE:
+SCLK:     500Mhz       3000Mhz
+MCLK:      97Mhz       1500Mhz
+FCLK:    1200Mhz       2100Mhz
+";
+
+    #[test]
+    fn parse_extra_clocks() {
+        let clocks = parse(TABLE);
+        assert_eq!(clocks.len(), 1);
+        assert_eq!(
+            clocks[&ExtraClock::Fabric],
+            ExtraClockRange {
+                min: 1200,
+                max: 1800,
+                range: Some((1200, 2100)),
+            }
+        );
+
+        // The GPU and VRAM clock ranges alone are not extra clocks
+        assert!(parse("OD_RANGE:\nSCLK: 500Mhz 3000Mhz\n").is_empty());
+    }
+
+    #[test]
+    fn extra_clock_commands() {
+        let clocks = parse(TABLE);
+        let config = ClocksConfiguration {
+            max_fabric_clock: Some(2500),
+            ..Default::default()
+        };
+        assert_eq!(commands(&clocks, &config).unwrap(), ["fclk 1 2100"]);
+
+        let config = ClocksConfiguration {
+            min_soc_clock: Some(800),
+            ..Default::default()
+        };
+        assert!(commands(&clocks, &config).is_err());
+        assert!(commands(&clocks, &ClocksConfiguration::default())
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+//! Offset based overclocking of AMD GPUs, used by RDNA4 instead of min/max clock ranges.
+//! See <https://docs.kernel.org/gpu/amdgpu/thermal.html#pp-od-clk-voltage>.
+use crate::config::{clamp_setting, ClocksConfiguration};
+use anyhow::bail;
+use lact_schema::{AmdOffsetsTable, ClockOffset};
+
+/// Parses `pp_od_clk_voltage`, returns `None` when the GPU doesn't use offsets
+pub fn parse(contents: &str) -> Option<AmdOffsetsTable> {
+    let mut table = AmdOffsetsTable::default();
+    let mut section = "";
+    let mut mclk_levels = Vec::new();
+
+    for line in contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        if let Some(name) = line.strip_suffix(':') {
+            section = name;
+            continue;
+        }
+
+        match section {
+            "OD_SCLK_OFFSET" => {
+                table.sclk_offset = Some(ClockOffset {
+                    offset: parse_value(line)?,
+                    range: None,
+                });
+            }
+            "OD_VDDGFX_OFFSET" => {
+                table.voltage_offset = Some(ClockOffset {
+                    offset: parse_value(line)?,
+                    range: None,
+                });
+            }
+            "OD_MCLK" => {
+                let (_, value) = line.split_once(':')?;
+                mclk_levels.push(parse_value(value)?);
+            }
+            "OD_RANGE" => {
+                let Some((name, values)) = line.split_once(':') else {
+                    continue;
+                };
+                let mut values = values.split_whitespace().filter_map(parse_value);
+                let Some(range) = values.next().zip(values.next()) else {
+                    continue;
+                };
+                match name {
+                    "SCLK_OFFSET" => {
+                        if let Some(offset) = &mut table.sclk_offset {
+                            offset.range = Some(range);
+                        }
+                    }
+                    "VDDGFX_OFFSET" => {
+                        if let Some(offset) = &mut table.voltage_offset {
+                            offset.range = Some(range);
+                        }
+                    }
+                    "MCLK" => table.mclk_range = Some(range),
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if let [min, max] = mclk_levels[..] {
+        table.mclk = Some((min, max));
+    }
+
+    table.sclk_offset.is_some().then_some(table)
+}
+
+/// Parses values such as `-500Mhz` or `-200mV`
+pub(super) fn parse_value(value: &str) -> Option<i32> {
+    let value = value.trim().to_lowercase();
+    let number = value
+        .strip_suffix("mhz")
+        .or_else(|| value.strip_suffix("mv"))
+        .unwrap_or(&value);
+    number.trim().parse().ok()
+}
+
+/// Commands to write to `pp_od_clk_voltage` for the given configuration, without the final commit.
+/// Values are clamped to the ranges advertised by the GPU.
+pub fn commands(
+    table: &AmdOffsetsTable,
+    config: &ClocksConfiguration,
+) -> anyhow::Result<Vec<String>> {
+    if !config.gpu_curve.is_empty() || !config.memory_states.is_empty() {
+        bail!("Editing GPU curve points or memory states is not supported on this GPU");
+    }
+    if config.min_core_clock.is_some()
+        || config.max_core_clock.is_some()
+        || config.min_voltage.is_some()
+        || config.max_voltage.is_some()
+    {
+        bail!("This GPU only supports GPU clock and voltage offsets, not absolute values");
+    }
+
+    let mut commands = Vec::new();
+
+    let offsets = [
+        (
+            "s",
+            "GPU clock offset",
+            config.core_clock_offset,
+            table.sclk_offset,
+        ),
+        (
+            "vo",
+            "voltage offset",
+            config.voltage_offset,
+            table.voltage_offset,
+        ),
+    ];
+    for (command, name, value, current) in offsets {
+        if let Some(value) = value {
+            let Some(current) = current else {
+                bail!("Setting the {name} is not supported on this GPU");
+            };
+            let (min, max) = current.range.unzip();
+            let value = clamp_setting(name, value, min, max);
+            commands.push(format!("{command} {value}"));
+        }
+    }
+
+    let mclk = [(0, config.min_memory_clock), (1, config.max_memory_clock)];
+    for (level, value) in mclk {
+        if let Some(value) = value {
+            let (min, max) = table.mclk_range.unzip();
+            let value = clamp_setting("memory clock", value, min, max);
+            commands.push(format!("m {level} {value}"));
+        }
+    }
+
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{commands, parse};
+    use crate::config::ClocksConfiguration;
+    use lact_schema::ClockOffset;
+
+    const RDNA4_TABLE: &str = "OD_SCLK_OFFSET:
+0Mhz
+OD_MCLK:
+0: 97Mhz
+1: 1259MHz
+OD_VDDGFX_OFFSET:
+0mV
+OD_RANGE:
+SCLK_OFFSET:    -500Mhz       1000Mhz
+MCLK:      97Mhz       1500Mhz
+VDDGFX_OFFSET:    -200mv          0mv
+";
+
+    const RDNA3_TABLE: &str = "OD_SCLK:
+0: 500Mhz
+1: 2890Mhz
+OD_MCLK:
+0: 97Mhz
+1: 1249MHz
+OD_VDDGFX_OFFSET:
+0mV
+OD_RANGE:
+SCLK:     500Mhz       3000Mhz
+MCLK:      97Mhz       1500Mhz
+";
+
+    #[test]
+    fn parse_offsets_table() {
+        let table = parse(RDNA4_TABLE).unwrap();
+        assert_eq!(
+            table.sclk_offset,
+            Some(ClockOffset {
+                offset: 0,
+                range: Some((-500, 1000)),
+            })
+        );
+        assert_eq!(
+            table.voltage_offset,
+            Some(ClockOffset {
+                offset: 0,
+                range: Some((-200, 0)),
+            })
+        );
+        assert_eq!(table.mclk, Some((97, 1259)));
+        assert_eq!(table.mclk_range, Some((97, 1500)));
+
+        assert!(parse(RDNA3_TABLE).is_none());
+    }
+
+    #[test]
+    fn offset_commands() {
+        let table = parse(RDNA4_TABLE).unwrap();
+        let config = ClocksConfiguration {
+            core_clock_offset: Some(2000),
+            voltage_offset: Some(-50),
+            max_memory_clock: Some(1300),
+            ..Default::default()
+        };
+        assert_eq!(
+            commands(&table, &config).unwrap(),
+            ["s 1000", "vo -50", "m 1 1300"]
+        );
+
+        let config = ClocksConfiguration {
+            max_core_clock: Some(3000),
+            ..Default::default()
+        };
+        assert!(commands(&table, &config).is_err());
+    }
+}
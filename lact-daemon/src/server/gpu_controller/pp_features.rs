@@ -0,0 +1,121 @@
+//! Power management features of the SMU listed in `pp_features`, which can be turned off by writing a new mask of the enabled features.
+//! The file is formatted differently by the SMU driver (Navi and newer) and the older powerplay driver (Vega).
+pub const PP_FEATURES_FILE: &str = "pp_features";
+
+/// Features that let the GPU and memory clocks drop into deep sleep when idle
+pub const DEEP_SLEEP_FEATURES: &[&str] = &["DS_GFXCLK", "DS_UCLK"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PpFeature {
+    pub name: String,
+    pub bit: u32,
+    pub enabled: bool,
+}
+
+/// Parses the features from the contents of `pp_features`
+pub fn parse_pp_features(contents: &str) -> Vec<PpFeature> {
+    contents
+        .lines()
+        .filter_map(|line| parse_smu_line(line).or_else(|| parse_powerplay_line(line)))
+        .collect()
+}
+
+/// `00. DPM_PREFETCHER          ( 0) : enabled`
+fn parse_smu_line(line: &str) -> Option<PpFeature> {
+    let (name_part, rest) = line.split_once('(')?;
+    let (bit, state) = rest.split_once(')')?;
+    let name = name_part.split_whitespace().nth(1)?;
+    let state = state.trim().strip_prefix(':')?.trim();
+
+    Some(PpFeature {
+        name: name.to_owned(),
+        bit: bit.trim().parse().ok()?,
+        enabled: state == "enabled",
+    })
+}
+
+/// `DPM_PREFETCHER      0x0000000000000001      Y`
+fn parse_powerplay_line(line: &str) -> Option<PpFeature> {
+    let mut parts = line.split_whitespace();
+    let (name, mask, state) = (parts.next()?, parts.next()?, parts.next()?);
+    let mask = u64::from_str_radix(mask.strip_prefix("0x")?, 16).ok()?;
+    if mask.count_ones() != 1 || parts.next().is_some() {
+        return None;
+    }
+
+    Some(PpFeature {
+        name: name.to_owned(),
+        bit: mask.trailing_zeros(),
+        enabled: match state {
+            "Y" => true,
+            "N" => false,
+            _ => return None,
+        },
+    })
+}
+
+/// Mask of the enabled features, in the format that is written back to `pp_features`
+pub fn enabled_mask(features: &[PpFeature]) -> u64 {
+    features
+        .iter()
+        .filter(|feature| feature.enabled)
+        .fold(0, |mask, feature| {
+            mask | 1u64.checked_shl(feature.bit).unwrap_or_default()
+        })
+}
+
+/// Mask of the enabled features with one of the given names
+pub fn enabled_features_mask(features: &[PpFeature], names: &[&str]) -> u64 {
+    features
+        .iter()
+        .filter(|feature| feature.enabled && names.contains(&feature.name.as_str()))
+        .fold(0, |mask, feature| {
+            mask | 1u64.checked_shl(feature.bit).unwrap_or_default()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{enabled_features_mask, enabled_mask, parse_pp_features, DEEP_SLEEP_FEATURES};
+
+    #[test]
+    fn parse_smu_features() {
+        let contents = "features high: 0x00000623 low: 0xb3cdaffb
+No.  Feature               Bit : State
+00. DPM_PREFETCHER          ( 0) : enabled
+01. DPM_GFXCLK              ( 1) : enabled
+02. DPM_UCLK                ( 2) : disabled
+28. DS_GFXCLK               (28) : enabled
+31. DS_UCLK                 (31) : enabled
+";
+        let features = parse_pp_features(contents);
+        assert_eq!(features.len(), 5);
+        assert_eq!(features[3].name, "DS_GFXCLK");
+        assert_eq!(features[3].bit, 28);
+        assert!(!features[2].enabled);
+
+        assert_eq!(enabled_mask(&features), 0b1 | 0b10 | (1 << 28) | (1 << 31));
+        assert_eq!(
+            enabled_features_mask(&features, DEEP_SLEEP_FEATURES),
+            (1 << 28) | (1 << 31)
+        );
+    }
+
+    #[test]
+    fn parse_powerplay_features() {
+        let contents = "Current ppfeatures: 0x0000000019a1ffff
+FEATURES            BITMASK                ENABLEMENT
+DPM_PREFETCHER      0x0000000000000001      Y
+DS_GFXCLK           0x0000000000100000      Y
+DS_SOCCLK           0x0000000000200000      N
+";
+        let features = parse_pp_features(contents);
+        assert_eq!(features.len(), 3);
+        assert_eq!(features[1].bit, 20);
+        assert!(!features[2].enabled);
+        assert_eq!(
+            enabled_features_mask(&features, DEEP_SLEEP_FEATURES),
+            1 << 20
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use nix::errno::Errno;
+use std::{fmt, fs, io, path::Path};
+
+/// A value that the driver refused to accept, along with the file it was written to
+#[derive(Debug)]
+pub struct SysfsWriteError {
+    pub file: String,
+    pub value: String,
+    pub source: io::Error,
+}
+
+impl SysfsWriteError {
+    /// Explanation of what the error code usually means when returned by the amdgpu driver
+    fn hint(&self) -> Option<&'static str> {
+        let errno = Errno::from_raw(self.source.raw_os_error()?);
+        match errno {
+            Errno::EINVAL => {
+                Some("the value is not accepted by the driver, it may exceed the GPU's limits")
+            }
+            Errno::EPERM | Errno::EACCES => {
+                Some("the driver does not allow changing this setting, is overdrive enabled?")
+            }
+            Errno::EBUSY => Some("the GPU is busy"),
+            Errno::ENOENT | Errno::ENODEV => Some("the setting is not available on this GPU"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SysfsWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: value `{}` was rejected", self.file, self.value)?;
+        if let Some(hint) = self.hint() {
+            write!(f, " ({hint})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SysfsWriteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Writes a value to a sysfs file, keeping the file and value in the error so they can be shown to the user
+pub fn write_sysfs(dir: &Path, file: &str, value: &str) -> Result<(), SysfsWriteError> {
+    fs::write(dir.join(file), format!("{value}\n")).map_err(|source| SysfsWriteError {
+        file: file.to_owned(),
+        value: value.to_owned(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_sysfs, SysfsWriteError};
+    use crate::tests::TempDir;
+    use std::io;
+
+    #[test]
+    fn error_describes_rejected_value() {
+        let err = SysfsWriteError {
+            file: "pp_od_clk_voltage".to_owned(),
+            value: "s 1 2800".to_owned(),
+            source: io::Error::from_raw_os_error(22),
+        };
+        assert_eq!(
+            err.to_string(),
+            "pp_od_clk_voltage: value `s 1 2800` was rejected (the value is not accepted by the driver, it may exceed the GPU's limits)"
+        );
+        assert!(format!("{:#}", anyhow::Error::new(err)).contains("Invalid argument"));
+    }
+
+    #[test]
+    fn write_to_missing_file() {
+        let dir = TempDir::new("missing");
+        let err = write_sysfs(&dir, "pwm2", "128").unwrap_err();
+        assert_eq!(err.file, "pwm2");
+        assert_eq!(err.value, "128");
+        assert!(err.to_string().contains("not available on this GPU"));
+    }
+}
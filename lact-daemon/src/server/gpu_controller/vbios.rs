@@ -0,0 +1,112 @@
+use anyhow::{anyhow, bail, Context};
+use std::{fs, path::Path};
+use tracing::warn;
+
+const ROM_SIGNATURE: [u8; 2] = [0x55, 0xAA];
+const PCI_DATA_SIGNATURE: &[u8; 4] = b"PCIR";
+const IMAGE_BLOCK_SIZE: usize = 512;
+/// Code type of legacy x86 images, the only ones that are required to have a valid checksum
+const CODE_TYPE_X86: u8 = 0;
+
+/// Reads the option ROM exposed by the kernel for a PCI device.
+/// Reading has to be enabled by writing to the `rom` file first, and is disabled again afterwards.
+pub fn read_pci_rom(device_path: &Path) -> anyhow::Result<Vec<u8>> {
+    let rom_path = device_path.join("rom");
+    fs::write(&rom_path, "1").context("Could not enable reading the PCI ROM")?;
+
+    let result = fs::read(&rom_path).context("Could not read the PCI ROM");
+
+    if let Err(err) = fs::write(&rom_path, "0") {
+        warn!("could not disable reading the PCI ROM: {err}");
+    }
+    result
+}
+
+/// Checks the signatures of all images in the ROM and the checksum of legacy images.
+/// Returns the ROM truncated to the length of its images, as the kernel may pad it.
+pub fn validate_rom(mut rom: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    let mut offset = 0;
+
+    loop {
+        let image = rom
+            .get(offset..)
+            .filter(|image| image.len() >= 0x1A)
+            .ok_or_else(|| anyhow!("VBIOS image at offset {offset:#x} is truncated"))?;
+        if image[..2] != ROM_SIGNATURE {
+            bail!("Invalid VBIOS signature at offset {offset:#x}");
+        }
+
+        let data_offset = usize::from(u16::from_le_bytes([image[0x18], image[0x19]]));
+        let data = image
+            .get(data_offset..data_offset + 0x18)
+            .filter(|data| &data[..4] == PCI_DATA_SIGNATURE)
+            .ok_or_else(|| anyhow!("Missing PCI data structure in VBIOS image at {offset:#x}"))?;
+
+        let image_len =
+            usize::from(u16::from_le_bytes([data[0x10], data[0x11]])) * IMAGE_BLOCK_SIZE;
+        if image_len == 0 || image_len > image.len() {
+            bail!("Invalid length of VBIOS image at offset {offset:#x}");
+        }
+
+        if data[0x14] == CODE_TYPE_X86 {
+            let checksum = image[..image_len]
+                .iter()
+                .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+            if checksum != 0 {
+                bail!("Invalid checksum of VBIOS image at offset {offset:#x}");
+            }
+        }
+
+        offset += image_len;
+        // The highest bit of the indicator marks the last image
+        if data[0x15] & 0x80 != 0 {
+            break;
+        }
+    }
+
+    rom.truncate(offset);
+    Ok(rom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_rom;
+
+    fn image(blocks: u8, last: bool) -> Vec<u8> {
+        let mut image = vec![0; usize::from(blocks) * 512];
+        image[..3].copy_from_slice(&[0x55, 0xAA, blocks]);
+        image[0x18] = 0x40;
+        image[0x40..0x44].copy_from_slice(b"PCIR");
+        image[0x50] = blocks;
+        image[0x55] = if last { 0x80 } else { 0 };
+
+        let sum = image.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        image[0x100] = 0u8.wrapping_sub(sum);
+        image
+    }
+
+    #[test]
+    fn validate_images() {
+        let mut rom = image(2, false);
+        rom.extend(image(1, true));
+        let len = rom.len();
+        rom.extend([0xFF; 512]);
+
+        assert_eq!(validate_rom(rom.clone()).unwrap().len(), len);
+
+        rom[0x200] ^= 1;
+        let err = validate_rom(rom).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid checksum of VBIOS image at offset 0x0"
+        );
+    }
+
+    #[test]
+    fn reject_missing_signature() {
+        let mut rom = image(1, true);
+        rom[0] = 0;
+        assert!(validate_rom(rom).is_err());
+        assert!(validate_rom(vec![0x55, 0xAA]).is_err());
+    }
+}
@@ -0,0 +1,319 @@
+use lact_schema::GpuProcess;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::Path,
+    time::Instant,
+};
+use tracing::trace;
+
+/// A DRM client (an opened GPU device) as described in its fdinfo.
+/// See <https://docs.kernel.org/gpu/drm-usage-stats.html>.
+#[derive(Debug, Default, PartialEq)]
+struct DrmClient {
+    pdev: String,
+    client_id: u64,
+    /// Busy time of each engine in nanoseconds
+    engines: BTreeMap<String, u64>,
+    vram: Option<u64>,
+    gtt: Option<u64>,
+}
+
+struct EngineSample {
+    time: Instant,
+    engines: BTreeMap<String, u64>,
+}
+
+/// Remembers the engine counters of every client, as usage can only be calculated between two samples
+#[derive(Default)]
+pub struct ProcessTracker {
+    samples: RefCell<HashMap<(String, u32, u64), EngineSample>>,
+}
+
+impl ProcessTracker {
+    /// Lists the processes that have the GPU with the given PCI slot name open
+    pub fn processes(&self, pci_slot: &str) -> Vec<GpuProcess> {
+        self.collect(Path::new("/proc"), pci_slot, Instant::now())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn collect(&self, proc_dir: &Path, pci_slot: &str, now: Instant) -> Vec<GpuProcess> {
+        let mut samples = self.samples.borrow_mut();
+        let mut seen = HashSet::new();
+        let mut processes = Vec::new();
+
+        let Ok(entries) = fs::read_dir(proc_dir) else {
+            return processes;
+        };
+        for entry in entries.flatten() {
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u32>().ok())
+            else {
+                continue;
+            };
+
+            let clients = read_clients(&entry.path(), pci_slot);
+            if clients.is_empty() {
+                continue;
+            }
+
+            let name = fs::read_to_string(entry.path().join("comm"))
+                .map(|comm| comm.trim().to_owned())
+                .unwrap_or_default();
+            let mut process = GpuProcess {
+                pid,
+                name,
+                engine_usage: BTreeMap::new(),
+                vram: None,
+                gtt: None,
+            };
+
+            for client in clients {
+                process.vram = sum(process.vram, client.vram);
+                process.gtt = sum(process.gtt, client.gtt);
+
+                let key = (pci_slot.to_owned(), pid, client.client_id);
+                if let Some(previous) = samples.get(&key) {
+                    let elapsed = now.saturating_duration_since(previous.time).as_nanos() as f64;
+                    if elapsed > 0.0 {
+                        for (engine, busy) in &client.engines {
+                            let previous_busy = previous.engines.get(engine).copied().unwrap_or(0);
+                            let busy_time = busy.saturating_sub(previous_busy) as f64;
+                            *process.engine_usage.entry(engine.clone()).or_default() +=
+                                busy_time / elapsed * 100.0;
+                        }
+                    }
+                }
+                seen.insert(key.clone());
+                samples.insert(
+                    key,
+                    EngineSample {
+                        time: now,
+                        engines: client.engines,
+                    },
+                );
+            }
+
+            // Usage can go slightly over 100% because of timing differences between the samples
+            for usage in process.engine_usage.values_mut() {
+                *usage = usage.min(100.0);
+            }
+            processes.push(process);
+        }
+
+        samples.retain(|key, _| key.0 != pci_slot || seen.contains(key));
+        processes.sort_by_key(|process| process.pid);
+        trace!("found {} processes using {pci_slot}", processes.len());
+        processes
+    }
+}
+
+/// Reads the DRM clients of a process that belong to the given GPU.
+/// A client can be opened through multiple file descriptors, so every client is only counted once.
+fn read_clients(process_dir: &Path, pci_slot: &str) -> Vec<DrmClient> {
+    let mut clients: Vec<DrmClient> = Vec::new();
+
+    let Ok(entries) = fs::read_dir(process_dir.join("fdinfo")) else {
+        return clients;
+    };
+    for entry in entries.flatten() {
+        let Ok(contents) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        if let Some(client) = parse_fdinfo(&contents) {
+            if client.pdev == pci_slot
+                && !clients
+                    .iter()
+                    .any(|existing| existing.client_id == client.client_id)
+            {
+                clients.push(client);
+            }
+        }
+    }
+
+    clients
+}
+
+fn parse_fdinfo(contents: &str) -> Option<DrmClient> {
+    let mut client = DrmClient::default();
+    let mut has_client_id = false;
+    // Older kernels only report `drm-memory-*`, newer ones also have `drm-resident-*`
+    let mut resident_vram = None;
+    let mut resident_gtt = None;
+
+    for line in contents.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+
+        match key {
+            "drm-pdev" => client.pdev = value.to_owned(),
+            "drm-client-id" => {
+                client.client_id = value.parse().ok()?;
+                has_client_id = true;
+            }
+            "drm-memory-vram" => client.vram = parse_memory(value),
+            "drm-memory-gtt" => client.gtt = parse_memory(value),
+            "drm-resident-vram" => resident_vram = parse_memory(value),
+            "drm-resident-gtt" => resident_gtt = parse_memory(value),
+            _ => {
+                if let Some(engine) = key.strip_prefix("drm-engine-") {
+                    // `drm-engine-capacity-*` is the amount of engines of that kind, not a busy time
+                    if engine.starts_with("capacity-") {
+                        continue;
+                    }
+                    if let Some(Ok(busy)) = value.strip_suffix(" ns").map(str::parse) {
+                        client.engines.insert(engine.to_owned(), busy);
+                    }
+                }
+            }
+        }
+    }
+
+    client.vram = client.vram.or(resident_vram);
+    client.gtt = client.gtt.or(resident_gtt);
+
+    (has_client_id && !client.pdev.is_empty()).then_some(client)
+}
+
+/// Parses a memory size such as `1024 KiB` into bytes
+fn parse_memory(value: &str) -> Option<u64> {
+    let (amount, unit) = value.split_once(' ').unwrap_or((value, ""));
+    let multiplier = match unit {
+        "" => 1,
+        "KiB" => 1024,
+        "MiB" => 1024 * 1024,
+        "GiB" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    amount.parse::<u64>().ok().map(|amount| amount * multiplier)
+}
+
+/// Busy percentage of each engine summed over all processes.
+/// Processes take turns on an engine, so their usage adds up to at most 100%.
+pub fn total_engine_usage(processes: &[GpuProcess]) -> BTreeMap<String, f64> {
+    let mut total: BTreeMap<String, f64> = BTreeMap::new();
+    for process in processes {
+        for (engine, usage) in &process.engine_usage {
+            *total.entry(engine.clone()).or_default() += usage;
+        }
+    }
+    for usage in total.values_mut() {
+        *usage = usage.min(100.0);
+    }
+    total
+}
+
+fn sum(total: Option<u64>, value: Option<u64>) -> Option<u64> {
+    match (total, value) {
+        (Some(total), Some(value)) => Some(total + value),
+        (total, value) => total.or(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_fdinfo, total_engine_usage, ProcessTracker};
+    use crate::tests::TempDir;
+    use lact_schema::GpuProcess;
+    use std::{
+        collections::BTreeMap,
+        fs,
+        time::{Duration, Instant},
+    };
+
+    const FDINFO: &str = "pos:	0
+flags:	02100002
+mnt_id:	26
+ino:	1035
+drm-driver:	amdgpu
+drm-client-id:	42
+drm-pdev:	0000:0b:00.0
+pasid:	32775
+drm-memory-vram:	204800 KiB
+drm-memory-gtt: 	2048 KiB
+drm-memory-cpu: 	0 KiB
+amd-memory-visible-vram:	1024 KiB
+drm-engine-gfx:	1000000 ns
+drm-engine-compute:	0 ns
+drm-engine-capacity-compute:	2
+";
+
+    #[test]
+    fn parse_amdgpu_fdinfo() {
+        let client = parse_fdinfo(FDINFO).unwrap();
+        assert_eq!(client.pdev, "0000:0b:00.0");
+        assert_eq!(client.client_id, 42);
+        assert_eq!(client.vram, Some(204_800 * 1024));
+        assert_eq!(client.gtt, Some(2048 * 1024));
+        assert_eq!(client.engines.len(), 2);
+        assert_eq!(client.engines["gfx"], 1_000_000);
+
+        assert!(parse_fdinfo("pos:	0\nflags:	02100002\n").is_none());
+    }
+
+    #[test]
+    fn engine_usage_between_samples() {
+        let proc_dir = TempDir::new("gpu-processes");
+        let process_dir = proc_dir.join("1234");
+        fs::create_dir_all(process_dir.join("fdinfo")).unwrap();
+        fs::write(process_dir.join("comm"), "game\n").unwrap();
+        fs::write(process_dir.join("fdinfo/5"), FDINFO).unwrap();
+        // The same client opened through another file descriptor
+        fs::write(process_dir.join("fdinfo/6"), FDINFO).unwrap();
+
+        let tracker = ProcessTracker::default();
+        let start = Instant::now();
+
+        let processes = tracker.collect(&proc_dir, "0000:0b:00.0", start);
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].pid, 1234);
+        assert_eq!(processes[0].name, "game");
+        assert_eq!(processes[0].vram, Some(204_800 * 1024));
+        assert!(processes[0].engine_usage.is_empty());
+
+        let busy = FDINFO.replace("1000000 ns", "251000000 ns");
+        fs::write(process_dir.join("fdinfo/5"), &busy).unwrap();
+        fs::write(process_dir.join("fdinfo/6"), &busy).unwrap();
+
+        let processes = tracker.collect(&proc_dir, "0000:0b:00.0", start + Duration::from_secs(1));
+        assert_eq!(processes[0].engine_usage["gfx"], 25.0);
+        assert_eq!(processes[0].engine_usage["compute"], 0.0);
+
+        assert!(tracker.collect(&proc_dir, "0000:0c:00.0", start).is_empty());
+    }
+
+    #[test]
+    fn engine_usage_of_all_processes() {
+        let process = |pid, usage: &[(&str, f64)]| GpuProcess {
+            pid,
+            name: "process".to_owned(),
+            engine_usage: usage
+                .iter()
+                .map(|(engine, usage)| ((*engine).to_owned(), *usage))
+                .collect(),
+            vram: None,
+            gtt: None,
+        };
+        let processes = [
+            process(1, &[("gfx", 40.0), ("enc", 15.0)]),
+            process(2, &[("gfx", 70.0), ("dec", 5.0)]),
+            process(3, &[]),
+        ];
+
+        let total = total_engine_usage(&processes);
+        assert_eq!(
+            total,
+            BTreeMap::from([
+                ("dec".to_owned(), 5.0),
+                ("enc".to_owned(), 15.0),
+                ("gfx".to_owned(), 100.0),
+            ])
+        );
+        assert!(total_engine_usage(&[]).is_empty());
+    }
+}
@@ -1,10 +1,32 @@
 use super::{
-    gpu_controller::{fan_control::FanCurve, GpuController},
+    alerts::AlertTracker,
+    config_history::{ConfigHistory, HistoryAction},
+    energy::{EnergyTracker, ENERGY_FILE},
+    event_log::EventLog,
+    gpu_controller::{
+        fan_control::{simulate_curve, FanCurve},
+        GpuController,
+    },
+    gpu_processes::{total_engine_usage, ProcessTracker},
+    hooks::{self, HookEvent},
+    profile_comparison::{self, ProfileComparison},
+    profile_watcher,
+    rate_limit::ApplyRateLimiter,
+    runtime_pm::{process_names, read_runtime_pm, WakeTracker},
+    stats_history::{StatsHistory, MAX_STATS_HISTORY_SAMPLES, STATS_HISTORY_DIR},
+    stats_smoothing::StatsSmoother,
     system::{self, detect_initramfs_type, PP_FEATURE_MASK_PATH},
+    system_sensors,
+    throttle_tracker::ThrottleTracker,
+    undervolt::{self, UndervoltAssistant},
+    vulkan::clear_vulkan_cache,
 };
 use crate::{
-    config::{self, default_fan_static_speed, Config, FanControlSettings, Profile},
-    server::gpu_controller::{AmdGpuController, NvidiaGpuController},
+    config::{
+        self, default_fan_static_speed, match_gpu_ids, ClocksConfiguration, Config, ExportedState,
+        FanControlSettings, Profile, EXPORTED_STATE_VERSION,
+    },
+    server::gpu_controller::{AmdGpuController, IntelGpuController, NvidiaGpuController},
 };
 use amdgpu_sysfs::{
     gpu_handle::{power_profile_mode::PowerProfileModesTable, PerformanceLevel, PowerLevelKind},
@@ -12,10 +34,15 @@ use amdgpu_sysfs::{
 };
 use anyhow::{anyhow, bail, Context};
 use lact_schema::{
-    default_fan_curve,
+    default_fan_curve, quiet_fan_curve,
     request::{ConfirmCommand, ProfileBase, SetClocksCommand},
-    ClocksInfo, DeviceInfo, DeviceListEntry, DeviceStats, FanControlMode, FanOptions, PmfwOptions,
-    PowerStates, ProfilesInfo,
+    Alert, AlertAction, ApuLimitKind, ClocksInfo, DeviceCapabilities, DeviceEvent, DeviceInfo,
+    DeviceListEntry, DeviceStats, EnergyPrice, EnergyStats, FanControlMode, FanCurveInput,
+    FanCurveMap, FanCurvePreviewPoint, FanOptions, FanSpeedUnit, GpuProcess, ImportStateResult,
+    MemoryState, PmfwOptions, PowerState, PowerStates, ProfileComparisonOptions,
+    ProfileComparisonStatus, ProfileRule, ProfileSchedule, ProfilesInfo, ResetResult,
+    RuntimePmStats, SensorChip, StatsHistorySample, UndervoltOptions, UndervoltStatus,
+    FAN_STALL_SENSOR,
 };
 use libflate::gzip;
 use nix::libc;
@@ -25,27 +52,37 @@ use pciid_parser::Database;
 use serde_json::json;
 use std::{
     cell::RefCell,
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     env,
     fs::{self, File, Permissions},
     io::{BufWriter, Cursor, Write},
-    os::unix::fs::{MetadataExt, PermissionsExt},
+    os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt},
     path::{Path, PathBuf},
     rc::Rc,
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
-use tokio::{process::Command, sync::oneshot, time::sleep};
+use tokio::{
+    process::Command,
+    sync::{broadcast, oneshot},
+    time::sleep,
+};
 use tracing::{debug, error, info, trace, warn};
 
 const CONTROLLERS_LOAD_RETRY_ATTEMPTS: u8 = 5;
 const CONTROLLERS_LOAD_RETRY_INTERVAL: u64 = 3;
-
-const SNAPSHOT_GLOBAL_FILES: &[&str] = &[
-    PP_FEATURE_MASK_PATH,
-    "/etc/lact/config.yaml",
-    "/proc/version",
-];
+const CONTROLLERS_READY_TIMEOUT_SECS: u64 = 15;
+const CONTROLLERS_READY_POLL_INTERVAL_MILLIS: u64 = 250;
+const SYSFS_ROOT: &str = "/sys";
+const IDLE_POWER_PROFILE_MODE: &str = "POWER_SAVING";
+/// Device events that can be queued for a slow client before it misses some
+const DEVICE_EVENTS_CAPACITY: usize = 16;
+
+const SNAPSHOT_GLOBAL_FILES: &[&str] = &[PP_FEATURE_MASK_PATH, "/proc/version", "/proc/cmdline"];
+/// Kernel modules whose parameters are included in snapshots
+const SNAPSHOT_DRIVER_MODULES: &[&str] = &["amdgpu", "i915", "xe", "nvidia"];
+/// Kernel log lines containing any of these are included in snapshots
+const SNAPSHOT_KERNEL_LOG_PATTERNS: &[&str] = &["amdgpu", "drm", "i915", "xe ", "nvidia", "nvrm"];
 const SNAPSHOT_DEVICE_FILES: &[&str] = &[
     "uevent",
     "vendor",
@@ -80,23 +117,54 @@ const SNAPSHOT_HWMON_FILE_PREFIXES: &[&str] =
 #[derive(Clone)]
 pub struct Handler {
     pub config: Rc<RefCell<Config>>,
-    pub gpu_controllers: Rc<BTreeMap<String, Box<dyn GpuController>>>,
+    /// Controllers of the currently present GPUs, which change when GPUs are hot-plugged
+    gpu_controllers: Rc<RefCell<BTreeMap<String, Rc<dyn GpuController>>>>,
+    device_events: broadcast::Sender<DeviceEvent>,
     confirm_config_tx: Rc<RefCell<Option<oneshot::Sender<ConfirmCommand>>>>,
     pub config_last_saved: Arc<Mutex<Instant>>,
+    apply_rate_limiter: Rc<ApplyRateLimiter>,
+    pub event_log: Rc<EventLog>,
+    stats_smoother: Rc<StatsSmoother>,
+    pub stats_history: Option<Rc<StatsHistory>>,
+    /// Power caps of running applications that currently override the configured ones
+    pub app_power_caps: Rc<RefCell<HashMap<String, f64>>>,
+    pub throttle_tracker: Rc<ThrottleTracker>,
+    process_tracker: Rc<ProcessTracker>,
+    /// Kept apart from `process_tracker`, so that stats requests don't shorten the sampling period of process listings
+    engine_usage_tracker: Rc<ProcessTracker>,
+    pub wake_tracker: Rc<WakeTracker>,
+    pub config_history: Rc<ConfigHistory>,
+    pub alert_tracker: Rc<AlertTracker>,
+    pub energy_tracker: Rc<EnergyTracker>,
+    pub undervolt: Rc<UndervoltAssistant>,
+    pub profile_comparison: Rc<ProfileComparison>,
+    /// Settings collected from the requests of a batch, which are only applied once all of them succeeded
+    batch: Rc<RefCell<Option<PendingBatch>>>,
+    /// Root of the sysfs tree the GPUs are loaded from, which is only changed for testing
+    sysfs_root: Rc<Path>,
+}
+
+struct PendingBatch {
+    id: String,
+    config: config::Gpu,
 }
 
 impl<'a> Handler {
-    pub async fn new(config: Config) -> anyhow::Result<Self> {
+    /// Loads the GPUs from the given sysfs root, or `/sys` when none is given
+    pub async fn new(config: Config, sysfs_root: Option<PathBuf>) -> anyhow::Result<Self> {
+        let sysfs_root: Rc<Path> = sysfs_root
+            .unwrap_or_else(|| PathBuf::from(SYSFS_ROOT))
+            .into();
         let mut controllers = BTreeMap::new();
 
         // Sometimes LACT starts too early in the boot process, before the sysfs is initialized.
         // For such scenarios there is a retry logic when no GPUs were found,
         // or if some of the PCI devices don't have a drm entry yet.
         for i in 1..=CONTROLLERS_LOAD_RETRY_ATTEMPTS {
-            controllers = load_controllers()?;
+            controllers = load_controllers(&sysfs_root)?;
 
             let mut should_retry = false;
-            if let Ok(devices) = fs::read_dir("/sys/bus/pci/devices") {
+            if let Ok(devices) = fs::read_dir(sysfs_root.join("bus/pci/devices")) {
                 for device in devices.flatten() {
                     if let Ok(uevent) = fs::read_to_string(device.path().join("uevent")) {
                         let uevent = uevent.replace('\0', "");
@@ -133,12 +201,42 @@ impl<'a> Handler {
         }
         info!("initialized {} GPUs", controllers.len());
 
+        let stats_history = config.daemon.stats_history_interval.and_then(|interval| {
+            let samples = config.daemon.stats_history_hours * 3600 / interval.max(1);
+            let capacity = u32::try_from(samples).unwrap_or(MAX_STATS_HISTORY_SAMPLES);
+            match StatsHistory::new(STATS_HISTORY_DIR, capacity) {
+                Ok(history) => Some(Rc::new(history)),
+                Err(err) => {
+                    error!("could not initialize stats history: {err:#}");
+                    None
+                }
+            }
+        });
+
         let handler = Self {
-            gpu_controllers: Rc::new(controllers),
+            gpu_controllers: Rc::new(RefCell::new(controllers)),
+            device_events: broadcast::channel(DEVICE_EVENTS_CAPACITY).0,
             config: Rc::new(RefCell::new(config)),
             confirm_config_tx: Rc::new(RefCell::new(None)),
             config_last_saved: Arc::new(Mutex::new(Instant::now())),
+            apply_rate_limiter: Rc::new(ApplyRateLimiter::default()),
+            event_log: Rc::new(EventLog::default()),
+            stats_smoother: Rc::new(StatsSmoother::default()),
+            stats_history,
+            app_power_caps: Rc::new(RefCell::new(HashMap::new())),
+            throttle_tracker: Rc::new(ThrottleTracker::default()),
+            process_tracker: Rc::new(ProcessTracker::default()),
+            engine_usage_tracker: Rc::new(ProcessTracker::default()),
+            wake_tracker: Rc::new(WakeTracker::default()),
+            config_history: Rc::new(ConfigHistory::default()),
+            alert_tracker: Rc::new(AlertTracker::default()),
+            energy_tracker: Rc::new(EnergyTracker::load(ENERGY_FILE)),
+            undervolt: Rc::new(UndervoltAssistant::default()),
+            profile_comparison: Rc::new(ProfileComparison::default()),
+            batch: Rc::new(RefCell::new(None)),
+            sysfs_root,
         };
+        handler.wait_controllers_ready().await;
         if let Err(err) = handler.apply_current_config().await {
             error!("could not apply config: {err:#}");
         }
@@ -154,14 +252,62 @@ impl<'a> Handler {
         Ok(handler)
     }
 
+    /// Waits until the configured GPUs expose the sysfs files needed to apply their settings
+    async fn wait_controllers_ready(&self) {
+        let config = self.config.borrow().clone();
+        let Ok(gpus) = config.gpus() else {
+            return;
+        };
+
+        let start = Instant::now();
+        let timeout = Duration::from_secs(CONTROLLERS_READY_TIMEOUT_SECS);
+        let mut waited = false;
+
+        loop {
+            let pending: Vec<&String> = gpus
+                .iter()
+                .filter(|(id, gpu_config)| {
+                    self.controller(id)
+                        .is_some_and(|controller| !controller.is_ready(gpu_config))
+                })
+                .map(|(id, _)| id)
+                .collect();
+
+            if pending.is_empty() {
+                if waited {
+                    info!("GPUs became ready after {}ms", start.elapsed().as_millis());
+                }
+                break;
+            }
+
+            if start.elapsed() >= timeout {
+                warn!(
+                    "GPUs {pending:?} were not ready after {CONTROLLERS_READY_TIMEOUT_SECS}s, applying settings anyway"
+                );
+                break;
+            }
+
+            debug!("waiting for GPUs {pending:?} to become ready");
+            waited = true;
+            sleep(Duration::from_millis(
+                CONTROLLERS_READY_POLL_INTERVAL_MILLIS,
+            ))
+            .await;
+        }
+    }
+
     pub async fn apply_current_config(&self) -> anyhow::Result<()> {
         let config = self.config.borrow().clone(); // Clone to avoid locking the RwLock on an await point
 
         let gpus = config.gpus()?;
         for (id, gpu_config) in gpus {
-            if let Some(controller) = self.gpu_controllers.get(id) {
+            if let Some(controller) = self.controller(id) {
                 if let Err(err) = controller.apply_config(gpu_config).await {
                     error!("could not apply existing config for gpu {id}: {err}");
+                    self.run_hooks(&HookEvent::ApplyFailed {
+                        gpu_id: id.clone(),
+                        error: format!("{err:#}"),
+                    });
                 }
             } else {
                 info!("could not find GPU with id {id} defined in configuration");
@@ -175,6 +321,15 @@ impl<'a> Handler {
         &self,
         id: String,
         f: F,
+    ) -> anyhow::Result<u64> {
+        self.change_gpu_config(id, HistoryAction::Record, f).await
+    }
+
+    async fn change_gpu_config<F: FnOnce(&mut config::Gpu)>(
+        &self,
+        id: String,
+        history_action: HistoryAction,
+        f: F,
     ) -> anyhow::Result<u64> {
         if self
             .confirm_config_tx
@@ -194,18 +349,64 @@ impl<'a> Handler {
             (gpu_config, apply_timer)
         };
 
+        if let Some(batch) = self
+            .batch
+            .try_borrow_mut()
+            .map_err(|err| anyhow!("{err}"))?
+            .as_mut()
+        {
+            if batch.id != id {
+                bail!("All changes of a batch have to be for the same GPU");
+            }
+            f(&mut batch.config);
+            return Ok(apply_timer);
+        }
+
         let mut new_config = gpu_config.clone();
         f(&mut new_config);
 
         let controller = self.controller_by_id(&id)?;
 
+        // Requests that don't change anything are coalesced if the GPU was written to recently
+        if new_config == gpu_config && self.apply_rate_limiter.is_recent(&id, Instant::now()) {
+            debug!("skipping unchanged settings for GPU {id}, they were applied recently");
+            self.wait_config_confirm(id, gpu_config, new_config, apply_timer, history_action)?;
+            return Ok(apply_timer);
+        }
+
+        let wait_time = self.apply_rate_limiter.wait_time(&id, Instant::now());
+        if !wait_time.is_zero() {
+            debug!(
+                "deferring settings write to GPU {id} by {}ms",
+                wait_time.as_millis()
+            );
+            if self.apply_rate_limiter.defer(&id, new_config.clone()) {
+                let handler = self.clone();
+                let id = id.clone();
+                let previous_config = gpu_config.clone();
+                tokio::task::spawn_local(async move {
+                    sleep(wait_time).await;
+                    handler.write_deferred_config(&id, &previous_config).await;
+                });
+            }
+            self.wait_config_confirm(id, gpu_config, new_config, apply_timer, history_action)?;
+            return Ok(apply_timer);
+        }
+        // Deferred settings that were not written yet are superseded by these
+        self.apply_rate_limiter.take_deferred(&id);
+        self.apply_rate_limiter.record(&id, Instant::now());
+
         match controller.apply_config(&new_config).await {
             Ok(()) => {
-                self.wait_config_confirm(id, gpu_config, new_config, apply_timer)?;
+                self.wait_config_confirm(id, gpu_config, new_config, apply_timer, history_action)?;
                 Ok(apply_timer)
             }
             Err(apply_err) => {
                 error!("could not apply settings: {apply_err:?}");
+                self.run_hooks(&HookEvent::ApplyFailed {
+                    gpu_id: id.clone(),
+                    error: format!("{apply_err:#}"),
+                });
                 match controller.apply_config(&gpu_config).await {
                     Ok(()) => Err(apply_err.context("Could not apply settings")),
                     Err(err) => Err(apply_err.context(err.context(
@@ -216,6 +417,165 @@ impl<'a> Handler {
         }
     }
 
+    /// Writes the latest settings that were deferred by the rate limiter, unless they were superseded or reverted since.
+    /// Errors can't be returned to the client anymore, so the previous settings are restored like for a failed request,
+    /// both on the GPU and in the stored config.
+    async fn write_deferred_config(&self, id: &str, previous_config: &config::Gpu) {
+        let Some(config) = self.apply_rate_limiter.take_deferred(id) else {
+            return;
+        };
+        let Some(controller) = self.controller(id) else {
+            return;
+        };
+        self.apply_rate_limiter.record(id, Instant::now());
+
+        if let Err(err) = controller.apply_config(&config).await {
+            error!("could not apply deferred settings: {err:?}");
+            self.run_hooks(&HookEvent::ApplyFailed {
+                gpu_id: id.to_owned(),
+                error: format!("{err:#}"),
+            });
+
+            // While the change is waiting for confirmation the stored settings are still the previous ones,
+            // so it's reverted like an unconfirmed change. Otherwise it was saved already and has to be undone.
+            let pending_confirm = match self.confirm_config_tx.try_borrow_mut() {
+                Ok(mut tx) => tx.take(),
+                Err(err) => {
+                    error!("{err}");
+                    None
+                }
+            };
+            if let Some(tx) = pending_confirm {
+                let _ = tx.send(ConfirmCommand::Revert);
+                return;
+            }
+
+            if let Err(err) = controller.apply_config(previous_config).await {
+                error!("could not restore the previous settings: {err:#}");
+            }
+
+            *self.config_last_saved.lock().unwrap() = Instant::now();
+            let mut config_guard = self.config.borrow_mut();
+            match config_guard.gpus_mut() {
+                Ok(gpus) => {
+                    gpus.insert(id.to_owned(), previous_config.clone());
+                }
+                Err(err) => error!("{err:#}"),
+            }
+            if let Err(err) = config_guard.save() {
+                error!("could not save the restored settings: {err:#}");
+            }
+            *self.config_last_saved.lock().unwrap() = Instant::now();
+        }
+    }
+
+    /// Starts collecting the changes of the following requests instead of applying each of them
+    pub fn start_batch(&self, id: &str) -> anyhow::Result<()> {
+        self.controller_by_id(id)?;
+        if self.is_config_pending() {
+            bail!("There is an unconfirmed configuration change pending");
+        }
+
+        let mut batch = self
+            .batch
+            .try_borrow_mut()
+            .map_err(|err| anyhow!("{err}"))?;
+        if batch.is_some() {
+            bail!("Another batch of changes is being applied");
+        }
+        let config = self
+            .config
+            .try_borrow()
+            .map_err(|err| anyhow!("{err}"))?
+            .gpus()?
+            .get(id)
+            .cloned()
+            .unwrap_or_default();
+        *batch = Some(PendingBatch {
+            id: id.to_owned(),
+            config,
+        });
+        Ok(())
+    }
+
+    /// Drops the collected changes without applying any of them
+    pub fn discard_batch(&self) {
+        match self.batch.try_borrow_mut() {
+            Ok(mut batch) => *batch = None,
+            Err(err) => error!("{err}"),
+        }
+    }
+
+    /// Applies the collected changes at once. If that fails, the previous settings are restored like for a single change
+    pub async fn apply_batch(&self) -> anyhow::Result<u64> {
+        let batch = self
+            .batch
+            .try_borrow_mut()
+            .map_err(|err| anyhow!("{err}"))?
+            .take()
+            .context("No batch of changes was started")?;
+        // Unlike single changes a batch is not deferred by the rate limiter, so that its errors reach the client
+        sleep(self.apply_rate_limiter.wait_time(&batch.id, Instant::now())).await;
+        self.edit_gpu_config(batch.id, |config| *config = batch.config)
+            .await
+    }
+
+    pub fn run_hooks(&self, event: &HookEvent) {
+        hooks::run_hooks(&self.config.borrow().daemon.hooks, event);
+    }
+
+    /// Whether applied settings are waiting for confirmation
+    pub fn is_config_pending(&self) -> bool {
+        self.confirm_config_tx
+            .try_borrow()
+            .map_or(true, |tx| tx.is_some())
+    }
+
+    /// Reason why the daemon has to keep running without any connected clients, if there is one
+    pub fn idle_exit_blocker(&self) -> Option<&'static str> {
+        if self.is_config_pending() {
+            return Some("settings are waiting for confirmation");
+        }
+
+        let config = self.config.borrow();
+        let daemon = &config.daemon;
+        if daemon.tcp_listen_address.is_some() {
+            return Some("the TCP listener is enabled");
+        }
+        if daemon.metrics_listen_address.is_some() {
+            return Some("metrics are served");
+        }
+        if daemon.web_listen_address.is_some() {
+            return Some("the web interface is enabled");
+        }
+        if daemon.dbus_interface {
+            return Some("the D-Bus interface is enabled");
+        }
+        if daemon.stats_history_interval.is_some() {
+            return Some("the stats history is recorded");
+        }
+        if config.auto_switch_profiles {
+            return Some("profiles are switched automatically");
+        }
+
+        let Ok(gpus) = config.gpus() else {
+            return Some("the GPU settings could not be read");
+        };
+        gpus.values().find_map(|gpu| {
+            if gpu.fan_control_enabled {
+                Some("fan control is enabled")
+            } else if gpu.thermal_target.is_some() {
+                Some("a thermal target is set")
+            } else if !gpu.app_power_caps.is_empty() {
+                Some("application power caps are set")
+            } else if !gpu.alerts.is_empty() {
+                Some("temperature alerts are set")
+            } else {
+                None
+            }
+        })
+    }
+
     /// Should be called after applying new config without writing it
     fn wait_config_confirm(
         &self,
@@ -223,6 +583,7 @@ impl<'a> Handler {
         previous_config: config::Gpu,
         new_config: config::Gpu,
         apply_timer: u64,
+        history_action: HistoryAction,
     ) -> anyhow::Result<()> {
         let (tx, rx) = oneshot::channel();
         *self
@@ -231,24 +592,44 @@ impl<'a> Handler {
             .map_err(|err| anyhow!("{err}"))? = Some(tx);
 
         let handler = self.clone();
+        let controller = self.controller_by_id(&id)?;
 
         tokio::task::spawn_local(async move {
-            let controller = handler
-                .controller_by_id(&id)
-                .expect("GPU controller disappeared");
+            let unchanged = previous_config == new_config;
 
             tokio::select! {
                 () = tokio::time::sleep(Duration::from_secs(apply_timer)) => {
                     info!("no confirmation received, reverting settings");
+                    handler.apply_rate_limiter.take_deferred(&id);
 
-                    if let Err(err) = controller.apply_config(&previous_config).await {
-                        error!("could not revert settings: {err:#}");
+                    if !unchanged {
+                        if let Err(err) = controller.apply_config(&previous_config).await {
+                            error!("could not revert settings: {err:#}");
+                        }
                     }
                 }
                 result = rx => {
                     match result {
+                        Ok(ConfirmCommand::Confirm) if unchanged => {
+                            debug!("confirmed settings are unchanged, not saving config");
+                            // The history entry is still used up, so that undoing doesn't get stuck on it
+                            if history_action != HistoryAction::Record {
+                                handler.config_history.confirmed(
+                                    &id,
+                                    history_action,
+                                    previous_config,
+                                    Instant::now(),
+                                );
+                            }
+                        }
                         Ok(ConfirmCommand::Confirm) => {
                             info!("saving updated config");
+                            handler.config_history.confirmed(
+                                &id,
+                                history_action,
+                                previous_config,
+                                Instant::now(),
+                            );
                             *handler.config_last_saved.lock().unwrap() = Instant::now();
 
                             let mut config_guard = handler.config.borrow_mut();
@@ -266,7 +647,10 @@ impl<'a> Handler {
                             *handler.config_last_saved.lock().unwrap() = Instant::now();
                         }
                         Ok(ConfirmCommand::Revert) | Err(_) => {
-                            if let Err(err) = controller.apply_config(&previous_config).await {
+                            handler.apply_rate_limiter.take_deferred(&id);
+                            if unchanged {
+                                debug!("settings are unchanged, nothing to revert");
+                            } else if let Err(err) = controller.apply_config(&previous_config).await {
                                 error!("could not revert settings: {err:#}");
                             }
                         }
@@ -283,16 +667,100 @@ impl<'a> Handler {
         Ok(())
     }
 
-    fn controller_by_id(&self, id: &str) -> anyhow::Result<&dyn GpuController> {
-        Ok(self
-            .gpu_controllers
-            .get(id)
-            .context("No controller with such id")?
-            .as_ref())
+    /// Controllers of the currently present GPUs
+    pub fn controllers(&self) -> Vec<(String, Rc<dyn GpuController>)> {
+        self.gpu_controllers
+            .borrow()
+            .iter()
+            .map(|(id, controller)| (id.clone(), controller.clone()))
+            .collect()
+    }
+
+    pub fn controller(&self, id: &str) -> Option<Rc<dyn GpuController>> {
+        self.gpu_controllers.borrow().get(id).cloned()
+    }
+
+    fn controller_by_id(&self, id: &str) -> anyhow::Result<Rc<dyn GpuController>> {
+        self.controller(id).context("No controller with such id")
+    }
+
+    pub fn subscribe_device_events(&self) -> broadcast::Receiver<DeviceEvent> {
+        self.device_events.subscribe()
+    }
+
+    /// Picks up GPUs that were added or removed since the controllers were loaded.
+    /// Added GPUs get their stored settings applied.
+    pub async fn reload_controllers(&self) -> anyhow::Result<()> {
+        clear_vulkan_cache();
+        let loaded = load_controllers(&self.sysfs_root)?;
+
+        let removed: Vec<(String, Rc<dyn GpuController>)> = {
+            let mut controllers = self.gpu_controllers.borrow_mut();
+            let removed_ids: Vec<String> = controllers
+                .keys()
+                .filter(|id| !loaded.contains_key(*id))
+                .cloned()
+                .collect();
+            removed_ids
+                .iter()
+                .filter_map(|id| controllers.remove_entry(id))
+                .collect()
+        };
+        for (id, controller) in removed {
+            info!("GPU {id} was removed");
+            if controller.has_fan_control_task() {
+                let gpu_config = self
+                    .config
+                    .borrow()
+                    .gpus()
+                    .ok()
+                    .and_then(|gpus| gpus.get(&id).cloned())
+                    .unwrap_or_default();
+                let new_config = config::Gpu {
+                    fan_control_enabled: false,
+                    ..gpu_config
+                };
+                // Stops the fan curve task, most of the settings can't be written anymore
+                if let Err(err) = controller.apply_config(&new_config).await {
+                    debug!("could not reset fan control of removed GPU {id}: {err:#}");
+                }
+            }
+            let _ = self.device_events.send(DeviceEvent::Removed(id));
+        }
+
+        let added: Vec<(String, Rc<dyn GpuController>)> = {
+            let controllers = self.gpu_controllers.borrow();
+            loaded
+                .into_iter()
+                .filter(|(id, _)| !controllers.contains_key(id))
+                .collect()
+        };
+        for (id, controller) in added {
+            info!("GPU {id} was added");
+            let gpu_config = self
+                .config
+                .borrow()
+                .gpus()
+                .ok()
+                .and_then(|gpus| gpus.get(&id).cloned());
+            if let Some(gpu_config) = gpu_config {
+                if let Err(err) = controller.apply_config(&gpu_config).await {
+                    error!("could not apply settings for GPU {id}: {err:#}");
+                }
+            }
+            self.gpu_controllers
+                .borrow_mut()
+                .insert(id.clone(), controller);
+            let _ = self.device_events.send(DeviceEvent::Added(id));
+        }
+
+        Ok(())
     }
 
     pub fn list_devices(&'a self) -> Vec<DeviceListEntry> {
+        let config = self.config.borrow();
         self.gpu_controllers
+            .borrow()
             .iter()
             .map(|(id, controller)| {
                 let name = controller
@@ -301,11 +769,36 @@ impl<'a> Handler {
                 DeviceListEntry {
                     id: id.to_owned(),
                     name,
+                    label: config.gpu_labels.get(id).cloned(),
                 }
             })
             .collect()
     }
 
+    pub fn set_gpu_label(&self, id: &str, label: Option<String>) -> anyhow::Result<()> {
+        self.controller_by_id(id)?;
+
+        let mut config = self
+            .config
+            .try_borrow_mut()
+            .map_err(|err| anyhow!("{err}"))?;
+
+        match label.map(|label| label.trim().to_owned()) {
+            Some(label) if !label.is_empty() => {
+                config.gpu_labels.insert(id.to_owned(), label);
+            }
+            _ => {
+                config.gpu_labels.remove(id);
+            }
+        }
+
+        *self.config_last_saved.lock().unwrap() = Instant::now();
+        config.save()?;
+        *self.config_last_saved.lock().unwrap() = Instant::now();
+
+        Ok(())
+    }
+
     pub fn get_device_info(&'a self, id: &str) -> anyhow::Result<DeviceInfo> {
         Ok(self.controller_by_id(id)?.get_info())
     }
@@ -316,24 +809,205 @@ impl<'a> Handler {
             .try_borrow()
             .map_err(|err| anyhow!("Could not read config: {err:?}"))?;
         let gpu_config = config.gpus()?.get(id);
-        Ok(self.controller_by_id(id)?.get_stats(gpu_config))
+        let controller = self.controller_by_id(id)?;
+        let runtime_pm = read_runtime_pm(controller.get_path());
+
+        // Reading the sensors of a suspended GPU would wake it up
+        let mut stats = if runtime_pm
+            .as_ref()
+            .is_some_and(RuntimePmStats::is_suspended)
+        {
+            DeviceStats::default()
+        } else {
+            controller.get_stats(gpu_config)
+        };
+        stats.power.app_power_cap = self.app_power_caps.borrow().get(id).copied();
+
+        let processes = controller
+            .get_pci_slot_name()
+            .map(|pci_slot| self.engine_usage_tracker.processes(&pci_slot))
+            .unwrap_or_default();
+        stats.engine_usage = total_engine_usage(&processes);
+        stats.runtime_pm = runtime_pm.map(|runtime_pm| RuntimePmStats {
+            processes: process_names(&processes),
+            last_wake: self.wake_tracker.last_wake(id),
+            ..runtime_pm
+        });
+        stats.throttle_events = stats
+            .throttle_status
+            .map(|status| self.throttle_tracker.update(id, status));
+        stats.alerts = self.alert_tracker.active(id);
+
+        if let Some(window) = config.daemon.stats_smoothing_window {
+            self.stats_smoother.apply(id, &mut stats, window);
+        }
+
+        Ok(stats)
+    }
+
+    pub fn get_stats_history(
+        &'a self,
+        id: &str,
+        since: Option<i64>,
+    ) -> anyhow::Result<Vec<StatsHistorySample>> {
+        self.controller_by_id(id)?;
+        let history = self
+            .stats_history
+            .as_ref()
+            .context("Stats history is disabled")?;
+        history.get(id, since)
+    }
+
+    pub fn get_energy_stats(&self, id: &str) -> anyhow::Result<EnergyStats> {
+        self.controller_by_id(id)?;
+        Ok(EnergyStats {
+            days: self.energy_tracker.days(id),
+            price: self.config.borrow().energy_price.clone(),
+        })
+    }
+
+    pub fn set_energy_price(&self, price: Option<EnergyPrice>) -> anyhow::Result<()> {
+        if let Some(price) = &price {
+            if !price.per_kwh.is_finite() || price.per_kwh < 0.0 {
+                bail!("Invalid energy price {}", price.per_kwh);
+            }
+        }
+
+        let mut config = self.config.borrow_mut();
+        config.energy_price = price;
+        config.save()?;
+        Ok(())
+    }
+
+    pub fn start_undervolt(&self, id: &str, options: UndervoltOptions) -> anyhow::Result<()> {
+        undervolt::start(self, id, options)
+    }
+
+    pub fn get_undervolt_status(&self, id: &str) -> anyhow::Result<UndervoltStatus> {
+        self.controller_by_id(id)?;
+        Ok(self.undervolt.status(id))
+    }
+
+    pub fn cancel_undervolt(&self, id: &str) -> anyhow::Result<()> {
+        self.undervolt.cancel(id)
+    }
+
+    pub fn start_profile_comparison(
+        &self,
+        id: &str,
+        options: ProfileComparisonOptions,
+    ) -> anyhow::Result<()> {
+        profile_comparison::start(self, id, options)
+    }
+
+    pub fn get_profile_comparison_status(
+        &self,
+        id: &str,
+    ) -> anyhow::Result<ProfileComparisonStatus> {
+        self.controller_by_id(id)?;
+        Ok(self.profile_comparison.status(id))
+    }
+
+    pub fn cancel_profile_comparison(&self, id: &str) -> anyhow::Result<()> {
+        self.profile_comparison.cancel(id)
+    }
+
+    pub fn preview_fan_curve(
+        &'a self,
+        id: &str,
+        curve: FanCurveMap,
+        since: Option<i64>,
+    ) -> anyhow::Result<Vec<FanCurvePreviewPoint>> {
+        if self.stats_history.is_none() {
+            bail!("The fan curve preview replays the recorded temperatures, but the stats history is disabled. It can be enabled with the `stats_history_interval` daemon option");
+        }
+
+        // The history only has temperatures, so curves are always previewed as temperature curves
+        let curve = FanCurve(curve);
+        curve.validate(FanCurveInput::Temperature)?;
+
+        let settings = self
+            .config
+            .borrow()
+            .gpus()?
+            .get(id)
+            .and_then(|gpu_config| gpu_config.fan_control_settings.clone())
+            .unwrap_or_default();
+        let temp_keys = settings.temperature_keys();
+
+        let samples: Vec<(i64, f32)> = self
+            .get_stats_history(id, since)?
+            .into_iter()
+            .filter_map(|sample| {
+                let temp = temp_keys
+                    .iter()
+                    .filter_map(|key| sample.temperatures.get(*key).copied())
+                    .reduce(f32::max)?;
+                Some((sample.timestamp, temp))
+            })
+            .collect();
+
+        let speeds = simulate_curve(&curve, &samples, &settings.curve_timing());
+
+        Ok(samples
+            .into_iter()
+            .zip(speeds)
+            .map(|((timestamp, temperature), speed)| FanCurvePreviewPoint {
+                timestamp,
+                temperature,
+                speed,
+            })
+            .collect())
     }
 
     pub fn get_clocks_info(&'a self, id: &str) -> anyhow::Result<ClocksInfo> {
         self.controller_by_id(id)?.get_clocks_info()
     }
 
+    pub fn get_capabilities(&'a self, id: &str) -> anyhow::Result<DeviceCapabilities> {
+        Ok(self.controller_by_id(id)?.get_capabilities())
+    }
+
     pub async fn set_fan_control(&'a self, opts: FanOptions<'_>) -> anyhow::Result<u64> {
+        if opts.speed_unit == Some(FanSpeedUnit::Rpm)
+            && !self
+                .controller_by_id(opts.id)?
+                .get_capabilities()
+                .rpm_fan_control
+        {
+            bail!("This GPU does not support setting fan speeds in RPM");
+        }
+
         let settings = {
             let mut config_guard = self
                 .config
                 .try_borrow_mut()
                 .map_err(|err| anyhow!("{err}"))?;
+            let allow_unsafe_fan_curves = config_guard.daemon.allow_unsafe_fan_curves;
             let gpu_config = config_guard
                 .gpus_mut()?
                 .entry(opts.id.to_owned())
                 .or_default();
 
+            let curve_input = opts.curve_input.unwrap_or_else(|| {
+                gpu_config
+                    .fan_control_settings
+                    .as_ref()
+                    .map(|settings| settings.curve_input)
+                    .unwrap_or_default()
+            });
+            let validate_curve = |curve: &FanCurve| -> anyhow::Result<()> {
+                curve.validate(curve_input)?;
+                if !allow_unsafe_fan_curves {
+                    curve.validate_monotonic(curve_input)?;
+                    // Curves that don't follow the temperature get the safety floor applied while running instead
+                    if curve_input != FanCurveInput::Power {
+                        curve.validate_safety_floor()?;
+                    }
+                }
+                Ok(())
+            };
+
             match opts.mode {
                 Some(mode) => match mode {
                     FanControlMode::Static => {
@@ -369,23 +1043,58 @@ impl<'a> Handler {
                             if let Some(spindown_delay) = opts.spindown_delay_ms {
                                 existing_settings.spindown_delay_ms = Some(spindown_delay);
                             }
+                            if let Some(max_ramp_rate) = opts.max_ramp_rate {
+                                existing_settings.max_ramp_rate = Some(max_ramp_rate);
+                            }
+                            if let Some(sensors) = &opts.temperature_sensors {
+                                existing_settings.set_temperature_keys(sensors)?;
+                            }
 
                             if let Some(raw_curve) = opts.curve {
                                 let curve = FanCurve(raw_curve);
-                                curve.validate()?;
+                                validate_curve(&curve)?;
                                 existing_settings.curve = curve;
                             }
+                            if let Some(fan_curves) = opts.fan_curves {
+                                if fan_curves.is_empty() {
+                                    existing_settings.fan_curves.clear();
+                                }
+                                for (channel, raw_curve) in fan_curves {
+                                    let curve = FanCurve(raw_curve);
+                                    validate_curve(&curve)?;
+                                    existing_settings.fan_curves.insert(channel, curve);
+                                }
+                            }
+                            if opts.curve_input.is_some() {
+                                // The points of the existing curves get a different meaning
+                                for curve in std::iter::once(&existing_settings.curve)
+                                    .chain(existing_settings.fan_curves.values())
+                                {
+                                    validate_curve(curve)?;
+                                }
+                            }
                             Some(existing_settings)
                         } else {
                             let curve = FanCurve(opts.curve.unwrap_or_else(default_fan_curve));
-                            curve.validate()?;
-                            Some(FanControlSettings {
+                            validate_curve(&curve)?;
+                            let mut settings = FanControlSettings {
                                 mode,
                                 curve,
                                 change_threshold: opts.change_threshold,
                                 spindown_delay_ms: opts.spindown_delay_ms,
+                                max_ramp_rate: opts.max_ramp_rate,
+                                curve_input,
                                 ..Default::default()
-                            })
+                            };
+                            if let Some(sensors) = &opts.temperature_sensors {
+                                settings.set_temperature_keys(sensors)?;
+                            }
+                            for (channel, raw_curve) in opts.fan_curves.unwrap_or_default() {
+                                let curve = FanCurve(raw_curve);
+                                validate_curve(&curve)?;
+                                settings.fan_curves.insert(channel, curve);
+                            }
+                            Some(settings)
                         }
                     }
                 },
@@ -395,7 +1104,13 @@ impl<'a> Handler {
 
         self.edit_gpu_config(opts.id.to_owned(), |config| {
             config.fan_control_enabled = opts.enabled;
-            if let Some(settings) = settings {
+            if let Some(mut settings) = settings {
+                if let Some(speed_unit) = opts.speed_unit {
+                    settings.speed_unit = speed_unit;
+                }
+                if let Some(curve_input) = opts.curve_input {
+                    settings.curve_input = curve_input;
+                }
                 config.fan_control_settings = Some(settings);
             }
             config.pmfw_options = opts.pmfw;
@@ -404,6 +1119,56 @@ impl<'a> Handler {
         .context("Failed to edit GPU config")
     }
 
+    pub async fn set_pmfw_options(&self, id: &str, options: PmfwOptions) -> anyhow::Result<u64> {
+        let pmfw_info = self.controller_by_id(id)?.get_stats(None).fan.pmfw_info;
+        if options.zero_rpm.is_some() && pmfw_info.zero_rpm_enable.is_none() {
+            bail!("Setting the zero RPM mode is not supported on this GPU");
+        }
+
+        let settings = [
+            (
+                "acoustic limit",
+                options.acoustic_limit,
+                pmfw_info.acoustic_limit,
+            ),
+            (
+                "acoustic target",
+                options.acoustic_target,
+                pmfw_info.acoustic_target,
+            ),
+            (
+                "target temperature",
+                options.target_temperature,
+                pmfw_info.target_temp,
+            ),
+            ("minimum PWM", options.minimum_pwm, pmfw_info.minimum_pwm),
+            (
+                "zero RPM temperature",
+                options.zero_rpm_threshold,
+                pmfw_info.zero_rpm_temperature,
+            ),
+        ];
+        for (name, value, info) in settings {
+            let Some(value) = value else {
+                continue;
+            };
+            let Some(info) = info else {
+                bail!("Setting the {name} is not supported on this GPU");
+            };
+            if let Some((min, max)) = info.allowed_range {
+                if !(min..=max).contains(&value) {
+                    bail!("The {name} has to be between {min} and {max}, got {value}");
+                }
+            }
+        }
+
+        self.edit_gpu_config(id.to_owned(), |config| {
+            config.pmfw_options.merge(options);
+        })
+        .await
+        .context("Failed to edit GPU config")
+    }
+
     pub async fn reset_pmfw(&self, id: &str) -> anyhow::Result<u64> {
         info!("Resetting PMFW settings");
         self.controller_by_id(id)?.reset_pmfw_settings();
@@ -415,6 +1180,44 @@ impl<'a> Handler {
         .context("Failed to edit GPU config and reset pmfw")
     }
 
+    /// Resets only the fan-related settings to stock, keeping clocks and power settings intact
+    pub async fn reset_fan_control(&self, id: &str) -> anyhow::Result<u64> {
+        info!("Resetting fan control");
+        self.controller_by_id(id)?.reset_pmfw_settings();
+
+        self.edit_gpu_config(id.to_owned(), |config| {
+            config.fan_control_enabled = false;
+            config.fan_control_settings = None;
+            config.pmfw_options = PmfwOptions::default();
+        })
+        .await
+        .context("Failed to edit GPU config and reset fan control")
+    }
+
+    /// Resets only the clocks and voltages to stock, keeping fan and power settings intact
+    pub async fn reset_clocks(&self, id: &str) -> anyhow::Result<u64> {
+        info!("Resetting clocks");
+        self.controller_by_id(id)?.cleanup_clocks()?;
+
+        self.edit_gpu_config(id.to_owned(), |config| {
+            config.clocks_configuration = ClocksConfiguration::default();
+        })
+        .await
+        .context("Failed to edit GPU config and reset clocks")
+    }
+
+    /// Resets only the power cap to the default, applying the config writes the default cap once none is set
+    pub async fn reset_power_cap(&self, id: &str) -> anyhow::Result<u64> {
+        info!("Resetting power cap");
+
+        self.edit_gpu_config(id.to_owned(), |config| {
+            config.power_cap = None;
+            config.thermal_target = None;
+        })
+        .await
+        .context("Failed to edit GPU config and reset power cap")
+    }
+
     pub async fn set_power_cap(&'a self, id: &str, maybe_cap: Option<f64>) -> anyhow::Result<u64> {
         self.edit_gpu_config(id.to_owned(), |gpu_config| {
             gpu_config.power_cap = maybe_cap;
@@ -423,6 +1226,132 @@ impl<'a> Handler {
         .context("Failed to edit GPU config and set power cap")
     }
 
+    pub async fn set_thermal_target(
+        &'a self,
+        id: &str,
+        temperature: Option<f32>,
+    ) -> anyhow::Result<u64> {
+        if let Some(temperature) = temperature {
+            if !(40.0..=110.0).contains(&temperature) {
+                bail!("Thermal target has to be between 40 and 110°C");
+            }
+            let supported = self
+                .controller_by_id(id)?
+                .hw_monitors()
+                .first()
+                .is_some_and(|hw_mon| hw_mon.get_power_cap().is_ok());
+            if !supported {
+                bail!("Thermal target mode requires a GPU with an adjustable power cap");
+            }
+        }
+
+        self.edit_gpu_config(id.to_owned(), |gpu_config| {
+            gpu_config.thermal_target = temperature;
+        })
+        .await
+        .context("Failed to edit GPU config and set thermal target")
+    }
+
+    pub fn get_alerts(&'a self, id: &str) -> anyhow::Result<Vec<Alert>> {
+        self.controller_by_id(id)?;
+        let config = self
+            .config
+            .try_borrow()
+            .map_err(|err| anyhow!("Could not read config: {err:?}"))?;
+        Ok(config
+            .gpus()?
+            .get(id)
+            .map(|gpu| gpu.alerts.clone())
+            .unwrap_or_default())
+    }
+
+    pub async fn set_alerts(&'a self, id: &str, alerts: Vec<Alert>) -> anyhow::Result<u64> {
+        let capabilities = self.controller_by_id(id)?.get_capabilities();
+        let configured_commands = self.config.borrow().alert_commands();
+        ensure_alert_commands_configured(
+            alerts.iter().flat_map(Alert::commands),
+            &configured_commands,
+        )?;
+        for alert in &alerts {
+            if alert.sensor == FAN_STALL_SENSOR {
+                if !(0.0..100.0).contains(&alert.threshold) {
+                    bail!("Fan stall alerts need a fan speed threshold between 0 and 100%");
+                }
+            } else if !capabilities.temperature_sensors.is_empty()
+                && !capabilities.temperature_sensors.contains(&alert.sensor)
+            {
+                bail!(
+                    "Temperature sensor '{}' does not exist, available sensors: {}",
+                    alert.sensor,
+                    capabilities.temperature_sensors.join(", ")
+                );
+            }
+            if alert.actions.is_empty() {
+                bail!("Alert for '{}' has no actions", alert.sensor);
+            }
+
+            for action in &alert.actions {
+                match action {
+                    AlertAction::MaxFans
+                        if !capabilities.pwm_fan_control && !capabilities.pmfw_fan_control =>
+                    {
+                        bail!("Fan control is not supported on this GPU");
+                    }
+                    AlertAction::PowerCap { watts } => {
+                        let Some((min, max)) = capabilities.power_cap_range else {
+                            bail!("Power cap is not supported on this GPU");
+                        };
+                        if !(min..=max).contains(watts) {
+                            bail!("Power cap has to be between {min} and {max}W");
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        self.edit_gpu_config(id.to_owned(), |gpu_config| {
+            gpu_config.alerts = alerts;
+        })
+        .await
+        .context("Failed to edit GPU config and set alerts")
+    }
+
+    pub async fn set_app_power_cap(
+        &'a self,
+        id: &str,
+        process: String,
+        power_cap: Option<f64>,
+    ) -> anyhow::Result<u64> {
+        if process.trim().is_empty() {
+            bail!("Process name cannot be empty");
+        }
+        if let Some(cap) = power_cap {
+            if cap <= 0.0 {
+                bail!("Power cap has to be positive");
+            }
+            let supported = self
+                .controller_by_id(id)?
+                .hw_monitors()
+                .first()
+                .is_some_and(|hw_mon| hw_mon.get_power_cap().is_ok());
+            if !supported {
+                bail!("Application power caps require a GPU with an adjustable power cap");
+            }
+        }
+
+        self.edit_gpu_config(id.to_owned(), |gpu_config| match power_cap {
+            Some(cap) => {
+                gpu_config.app_power_caps.insert(process, cap);
+            }
+            None => {
+                gpu_config.app_power_caps.remove(&process);
+            }
+        })
+        .await
+        .context("Failed to edit GPU config and set application power cap")
+    }
+
     pub fn get_power_states(&self, id: &str) -> anyhow::Result<PowerStates> {
         let config = self
             .config
@@ -434,20 +1363,131 @@ impl<'a> Handler {
         Ok(states)
     }
 
-    pub async fn set_performance_level(
-        &self,
-        id: &str,
-        level: PerformanceLevel,
-    ) -> anyhow::Result<u64> {
+    pub async fn set_performance_level(
+        &self,
+        id: &str,
+        level: PerformanceLevel,
+    ) -> anyhow::Result<u64> {
+        self.edit_gpu_config(id.to_owned(), |gpu_config| {
+            gpu_config.performance_level = Some(level);
+
+            if level != PerformanceLevel::Manual {
+                gpu_config.power_states.clear();
+            }
+        })
+        .await
+        .context("Failed to edit GPU config and set performance level")
+    }
+
+    /// Holds the GPU at its peak clocks until released. This is not persisted in the config.
+    pub fn set_benchmark_lock(&self, id: &str, enabled: bool) -> anyhow::Result<()> {
+        self.controller_by_id(id)?.set_benchmark_lock(enabled)
+    }
+
+    /// Holds the GPU at its highest clocks with deep sleep disabled and the PCIe link speed pinned,
+    /// on top of the configured performance level and power states, which are used again when it's disabled
+    pub async fn set_low_latency_mode(&self, id: &str, enabled: bool) -> anyhow::Result<u64> {
+        if enabled
+            && !self
+                .controller_by_id(id)?
+                .get_capabilities()
+                .low_latency_mode
+        {
+            bail!("This GPU does not support the low latency mode");
+        }
+
+        self.edit_gpu_config(id.to_owned(), |gpu_config| {
+            gpu_config.low_latency = enabled;
+        })
+        .await
+        .context("Failed to edit GPU config and set low latency mode")
+    }
+
+    pub async fn set_runtime_pm(&self, id: &str, auto: Option<bool>) -> anyhow::Result<u64> {
+        if auto.is_some() && read_runtime_pm(self.controller_by_id(id)?.get_path()).is_none() {
+            bail!("This GPU does not support runtime power management");
+        }
+
+        self.edit_gpu_config(id.to_owned(), |gpu_config| {
+            gpu_config.runtime_pm = auto;
+        })
+        .await
+        .context("Failed to edit GPU config and set runtime power management")
+    }
+
+    /// Applies the settings that lower idle power usage which are supported by the GPU.
+    /// Can be undone by resetting the config.
+    pub async fn apply_idle_optimization(&self, id: &str) -> anyhow::Result<u64> {
+        let controller = self.controller_by_id(id)?;
+        let capabilities = controller.get_capabilities();
+        let power_states = controller.get_power_states(None);
+        let power_saving_mode = controller.get_power_profile_modes().ok().and_then(|table| {
+            table
+                .modes
+                .iter()
+                .find(|(_, mode)| mode.name.eq_ignore_ascii_case(IDLE_POWER_PROFILE_MODE))
+                .map(|(index, _)| *index)
+        });
+        let fan_control_supported = capabilities.pwm_fan_control || capabilities.pmfw_fan_control;
+
+        if power_states.is_empty()
+            && !capabilities.pcie_power_states
+            && power_saving_mode.is_none()
+            && !fan_control_supported
+        {
+            bail!("This GPU does not support any idle power optimizations");
+        }
+
         self.edit_gpu_config(id.to_owned(), |gpu_config| {
-            gpu_config.performance_level = Some(level);
+            if !power_states.is_empty()
+                || capabilities.pcie_power_states
+                || power_saving_mode.is_some()
+            {
+                gpu_config.performance_level = Some(PerformanceLevel::Manual);
+            }
 
-            if level != PerformanceLevel::Manual {
-                gpu_config.power_states.clear();
+            // Make sure the lowest states are enabled, so the GPU can clock down when idle
+            let all_states = |states: &[PowerState]| -> Vec<u8> {
+                states.iter().filter_map(|state| state.index).collect()
+            };
+            if !power_states.core.is_empty() {
+                gpu_config
+                    .power_states
+                    .insert(PowerLevelKind::CoreClock, all_states(&power_states.core));
+            }
+            if !power_states.vram.is_empty() {
+                gpu_config
+                    .power_states
+                    .insert(PowerLevelKind::MemoryClock, all_states(&power_states.vram));
+            }
+
+            if capabilities.pcie_power_states {
+                gpu_config
+                    .power_states
+                    .insert(PowerLevelKind::PcieSpeed, vec![0]);
+            }
+
+            if let Some(index) = power_saving_mode {
+                gpu_config.power_profile_mode_index = Some(index);
+                gpu_config.custom_power_profile_mode_hueristics.clear();
+            }
+
+            if fan_control_supported {
+                let mut settings = gpu_config.fan_control_settings.clone().unwrap_or_default();
+                settings.mode = FanControlMode::Curve;
+                settings.curve = FanCurve(quiet_fan_curve());
+                // The quiet curve is placed at temperatures, per-fan curves that follow the power draw can't be kept
+                if settings.curve_input != FanCurveInput::Temperature {
+                    settings.curve_input = FanCurveInput::Temperature;
+                    settings.fan_curves.clear();
+                }
+
+                gpu_config.fan_control_enabled = true;
+                gpu_config.fan_control_settings = Some(settings);
             }
         })
         .await
-        .context("Failed to edit GPU config and set performance level")
+        .context("Failed to edit GPU config and apply idle optimization")
     }
 
     pub async fn set_clocks_value(
@@ -458,6 +1498,7 @@ impl<'a> Handler {
         if let SetClocksCommand::Reset = command {
             self.controller_by_id(id)?.cleanup_clocks()?;
         }
+        self.check_clocks_limits(id, std::slice::from_ref(&command))?;
 
         self.edit_gpu_config(id.to_owned(), |gpu_config| {
             gpu_config.apply_clocks_command(&command);
@@ -471,6 +1512,8 @@ impl<'a> Handler {
         id: &str,
         commands: Vec<SetClocksCommand>,
     ) -> anyhow::Result<u64> {
+        self.check_clocks_limits(id, &commands)?;
+
         self.edit_gpu_config(id.to_owned(), |gpu_config| {
             for command in commands {
                 gpu_config.apply_clocks_command(&command);
@@ -480,20 +1523,82 @@ impl<'a> Handler {
         .context("Failed to edit GPU config and batch set clocks")
     }
 
+    /// Rejects values outside of the ranges advertised by the GPU
+    pub fn check_clocks_limits(
+        &self,
+        id: &str,
+        commands: &[SetClocksCommand],
+    ) -> anyhow::Result<()> {
+        // GPUs without a clocks table fail when the config is applied instead
+        let Ok(info) = self.controller_by_id(id)?.get_clocks_info() else {
+            return Ok(());
+        };
+        for command in commands {
+            info.check(command)?;
+        }
+        Ok(())
+    }
+
     pub fn get_power_profile_modes(&self, id: &str) -> anyhow::Result<PowerProfileModesTable> {
         let modes_table = self.controller_by_id(id)?.get_power_profile_modes()?;
         Ok(modes_table)
     }
 
+    /// Setting a mode also switches to the manual performance level, as the driver ignores the mode otherwise.
+    /// Resetting the mode goes back to the automatic level, unless power states still need the manual one.
     pub async fn set_power_profile_mode(
         &self,
         id: &str,
         index: Option<u16>,
         custom_heuristics: Vec<Vec<Option<i32>>>,
     ) -> anyhow::Result<u64> {
+        if let Some(index) = index {
+            let table = self
+                .controller_by_id(id)?
+                .get_power_profile_modes()
+                .context("Power profile modes are not supported by this GPU")?;
+            let mode = table
+                .modes
+                .get(&index)
+                .with_context(|| format!("Power profile mode {index} does not exist"))?;
+
+            if !custom_heuristics.is_empty() {
+                if !mode.is_custom() {
+                    bail!(
+                        "Heuristics can only be changed for the custom power profile mode, not {}",
+                        mode.name
+                    );
+                }
+                if custom_heuristics.len() != mode.components.len() {
+                    bail!(
+                        "The {} power profile mode has {} components, but heuristics were given for {}",
+                        mode.name,
+                        mode.components.len(),
+                        custom_heuristics.len()
+                    );
+                }
+                for (component, values) in mode.components.iter().zip(&custom_heuristics) {
+                    if values.len() != component.values.len() {
+                        bail!(
+                            "Expected {} heuristic values per component, got {}",
+                            component.values.len(),
+                            values.len()
+                        );
+                    }
+                }
+            }
+        } else if !custom_heuristics.is_empty() {
+            bail!("Custom heuristics require a power profile mode");
+        }
+
         self.edit_gpu_config(id.to_owned(), |gpu_config| {
             gpu_config.power_profile_mode_index = index;
             gpu_config.custom_power_profile_mode_hueristics = custom_heuristics;
+            if index.is_some() {
+                gpu_config.performance_level = Some(PerformanceLevel::Manual);
+            } else {
+                release_manual_performance_level(gpu_config);
+            }
         })
         .await
         .context("Failed to edit GPU config and set power profile mode")
@@ -505,23 +1610,239 @@ impl<'a> Handler {
         kind: PowerLevelKind,
         enabled_states: Vec<u8>,
     ) -> anyhow::Result<u64> {
+        if enabled_states.is_empty() {
+            bail!("At least one power state has to be enabled");
+        }
+
+        let power_states = self.controller_by_id(id)?.get_power_states(None);
+        let available = match kind {
+            PowerLevelKind::CoreClock => Some(&power_states.core),
+            PowerLevelKind::MemoryClock => Some(&power_states.vram),
+            _ => None,
+        };
+        if let Some(available) = available {
+            if available.is_empty() {
+                bail!("This GPU does not support configuring {kind:?} power states");
+            }
+            if let Some(state) = enabled_states
+                .iter()
+                .find(|state| usize::from(**state) >= available.len())
+            {
+                bail!(
+                    "{kind:?} power state {state} does not exist, the highest one is {}",
+                    available.len() - 1
+                );
+            }
+        }
+
         self.edit_gpu_config(id.to_owned(), |gpu| {
+            // Power states can only be masked with the manual performance level
+            gpu.performance_level = Some(PerformanceLevel::Manual);
             gpu.power_states.insert(kind, enabled_states);
         })
         .await
         .context("Failed to edit GPU config and set enabled power states")
     }
 
+    pub fn get_pcie_power_states(&self, id: &str) -> anyhow::Result<Vec<String>> {
+        Ok(self.controller_by_id(id)?.get_pcie_power_states())
+    }
+
+    pub async fn set_min_pcie_state(&self, id: &str, state: Option<u8>) -> anyhow::Result<u64> {
+        let count = self.controller_by_id(id)?.get_pcie_power_states().len();
+        self.set_min_power_state(id, PowerLevelKind::PcieSpeed, "PCIe", count, state)
+            .await
+    }
+
+    /// Keeping the VRAM clock from dropping to the lowest states is a common fix for flickering with multiple monitors
+    pub async fn set_min_vram_state(&self, id: &str, state: Option<u8>) -> anyhow::Result<u64> {
+        let count = self.controller_by_id(id)?.get_power_states(None).vram.len();
+        self.set_min_power_state(id, PowerLevelKind::MemoryClock, "VRAM", count, state)
+            .await
+    }
+
+    /// Enables the given state and all states above it, out of `count` states
+    async fn set_min_power_state(
+        &self,
+        id: &str,
+        kind: PowerLevelKind,
+        name: &str,
+        count: usize,
+        state: Option<u8>,
+    ) -> anyhow::Result<u64> {
+        if let Some(state) = state {
+            if count == 0 {
+                bail!("This GPU does not support configuring {name} power states");
+            }
+            if usize::from(state) >= count {
+                bail!(
+                    "{name} power state {state} does not exist, the highest one is {}",
+                    count - 1
+                );
+            }
+        }
+
+        self.edit_gpu_config(id.to_owned(), |gpu_config| match state {
+            Some(state) => {
+                let last_state = u8::try_from(count - 1).unwrap_or(u8::MAX);
+                gpu_config.performance_level = Some(PerformanceLevel::Manual);
+                gpu_config
+                    .power_states
+                    .insert(kind, (state..=last_state).collect());
+            }
+            None => {
+                gpu_config.power_states.remove(&kind);
+                release_manual_performance_level(gpu_config);
+            }
+        })
+        .await
+        .with_context(|| {
+            format!("Failed to edit GPU config and set the minimum {name} power state")
+        })
+    }
+
+    pub async fn set_apu_limit(
+        &self,
+        id: &str,
+        kind: ApuLimitKind,
+        value: Option<f64>,
+    ) -> anyhow::Result<u64> {
+        if let Some(value) = value {
+            if !self
+                .controller_by_id(id)?
+                .get_capabilities()
+                .apu_limits
+                .contains(&kind)
+            {
+                bail!("The {kind:?} limit is not available on this GPU");
+            }
+            if value <= 0.0 {
+                bail!("The {kind:?} limit has to be positive");
+            }
+        }
+
+        self.edit_gpu_config(id.to_owned(), |gpu_config| match value {
+            Some(value) => {
+                gpu_config.apu_limits.insert(kind, value);
+            }
+            None => {
+                gpu_config.apu_limits.remove(&kind);
+            }
+        })
+        .await
+        .context("Failed to edit GPU config and set the APU limit")
+    }
+
+    pub fn get_memory_states(&self, id: &str) -> anyhow::Result<Vec<MemoryState>> {
+        Ok(self.controller_by_id(id)?.get_memory_states())
+    }
+
+    pub fn get_processes(&self, id: &str) -> anyhow::Result<Vec<GpuProcess>> {
+        let pci_slot = self
+            .controller_by_id(id)?
+            .get_pci_slot_name()
+            .context("GPU has no PCI slot name")?;
+        Ok(self.process_tracker.processes(&pci_slot))
+    }
+
+    pub fn get_sensor_chips(&self) -> Vec<SensorChip> {
+        let names = self.config.borrow().daemon.sensor_chips.clone();
+        system_sensors::read_sensor_chips(Path::new(system_sensors::HWMON_ROOT), &names)
+    }
+
+    pub async fn undo_last_change(&self, id: &str) -> anyhow::Result<u64> {
+        let previous = self
+            .config_history
+            .undo_target(id)
+            .context("There are no changes to undo")?;
+        self.change_gpu_config(id.to_owned(), HistoryAction::Undo, |gpu_config| {
+            *gpu_config = previous;
+        })
+        .await
+        .context("Failed to undo the last change")
+    }
+
+    pub async fn redo_last_change(&self, id: &str) -> anyhow::Result<u64> {
+        let next = self
+            .config_history
+            .redo_target(id)
+            .context("There are no undone changes to redo")?;
+        self.change_gpu_config(id.to_owned(), HistoryAction::Redo, |gpu_config| {
+            *gpu_config = next;
+        })
+        .await
+        .context("Failed to redo the last change")
+    }
+
+    pub async fn lock_memory_state(&self, id: &str, state: Option<u8>) -> anyhow::Result<u64> {
+        if let Some(state) = state {
+            let count = self.controller_by_id(id)?.get_memory_states().len();
+            if count == 0 {
+                bail!("This GPU does not support configuring memory states");
+            }
+            if usize::from(state) >= count {
+                bail!(
+                    "Memory state {state} does not exist, the highest one is {}",
+                    count - 1
+                );
+            }
+        }
+
+        self.edit_gpu_config(id.to_owned(), |gpu_config| match state {
+            Some(state) => {
+                gpu_config.performance_level = Some(PerformanceLevel::Manual);
+                gpu_config
+                    .power_states
+                    .insert(PowerLevelKind::MemoryClock, vec![state]);
+            }
+            None => {
+                gpu_config.power_states.remove(&PowerLevelKind::MemoryClock);
+                release_manual_performance_level(gpu_config);
+            }
+        })
+        .await
+        .context("Failed to edit GPU config and lock the memory state")
+    }
+
     pub fn vbios_dump(&self, id: &str) -> anyhow::Result<Vec<u8>> {
         self.controller_by_id(id)?.vbios_dump()
     }
 
+    pub async fn reset_gpu(&self, id: &str, confirm: bool) -> anyhow::Result<()> {
+        if !confirm {
+            bail!(
+                "Resetting the GPU interrupts all applications using it and needs to be confirmed"
+            );
+        }
+
+        let controller = self.controller_by_id(id)?;
+        controller.reset_gpu()?;
+        self.run_hooks(&HookEvent::GpuReset {
+            gpu_id: id.to_owned(),
+        });
+
+        // The driver loses custom settings on reset
+        let gpu_config = self.config.borrow().gpus()?.get(id).cloned();
+        if let Some(gpu_config) = gpu_config {
+            controller
+                .apply_config(&gpu_config)
+                .await
+                .context("GPU was reset, but its settings could not be applied again")?;
+        }
+        Ok(())
+    }
+
     pub async fn generate_snapshot(&self) -> anyhow::Result<String> {
         let datetime = chrono::Local::now().format("%Y%m%d-%H%M%S");
         let out_path = format!("/tmp/LACT-sysfs-snapshot-{datetime}.tar.gz");
 
-        let out_file = File::create(&out_path)
-            .with_context(|| "Could not create output file at {out_path}")?;
+        // Snapshots are meant to be attached to bug reports, so they are readable by the user but never contain secrets
+        let out_file = File::options()
+            .write(true)
+            .create_new(true)
+            .mode(0o644)
+            .open(&out_path)
+            .with_context(|| format!("Could not create output file at {out_path}"))?;
         let out_writer = gzip::Encoder::new(BufWriter::new(out_file))
             .context("Could not create GZIP encoder")?;
 
@@ -532,7 +1853,18 @@ impl<'a> Handler {
             add_path_to_archive(&mut archive, path)?;
         }
 
-        for controller in self.gpu_controllers.values() {
+        // The config file holds the auth tokens, so a copy without them is added in its place
+        let raw_config = toml::to_string(&self.config.borrow().without_secrets())
+            .context("Could not serialize config")?;
+        let config_path = config::get_path();
+        let config_entry = config_path.strip_prefix("/").unwrap_or(&config_path);
+        add_data_to_archive(
+            &mut archive,
+            &config_entry.to_string_lossy(),
+            raw_config.into_bytes(),
+        )?;
+
+        for (_, controller) in self.controllers() {
             let controller_path = controller.get_path();
 
             for device_file in SNAPSHOT_DEVICE_FILES {
@@ -568,6 +1900,15 @@ impl<'a> Handler {
             }
         }
 
+        for module in SNAPSHOT_DRIVER_MODULES {
+            let parameters_path = Path::new("/sys/module").join(module).join("parameters");
+            if let Ok(entries) = std::fs::read_dir(&parameters_path) {
+                for entry in entries.flatten() {
+                    add_path_to_archive(&mut archive, &entry.path())?;
+                }
+            }
+        }
+
         let service_journal_output = Command::new("journalctl")
             .args(["-u", "lactd", "-b"])
             .output()
@@ -578,18 +1919,21 @@ impl<'a> Handler {
                 if !output.status.success() {
                     warn!("service log output has status code {}", output.status);
                 }
-                let mut header = tar::Header::new_gnu();
-                header.set_size(output.stdout.len().try_into().unwrap());
-                header.set_mode(0o755);
-                header.set_cksum();
-
-                archive
-                    .append_data(&mut header, "lactd.log", Cursor::new(output.stdout))
-                    .context("Could not write data to archive")?;
+                add_data_to_archive(&mut archive, "lactd.log", output.stdout)?;
             }
             Err(err) => warn!("could not read service log: {err}"),
         }
 
+        match Command::new("dmesg").output().await {
+            Ok(output) if output.status.success() => {
+                let kernel_log = String::from_utf8_lossy(&output.stdout);
+                let excerpt = filter_kernel_log(&kernel_log);
+                add_data_to_archive(&mut archive, "kernel.log", excerpt.into_bytes())?;
+            }
+            Ok(output) => warn!("kernel log output has status code {}", output.status),
+            Err(err) => warn!("could not read kernel log: {err}"),
+        }
+
         let system_info = system::info()
             .await
             .ok()
@@ -603,6 +1947,7 @@ impl<'a> Handler {
 
         let devices: BTreeMap<String, serde_json::Value> = self
             .gpu_controllers
+            .borrow()
             .iter()
             .map(|(id, controller)| {
                 let config = self.config.try_borrow();
@@ -625,15 +1970,10 @@ impl<'a> Handler {
             "system_info": system_info,
             "initramfs_type": initramfs_type,
             "devices": devices,
+            "event_log": self.event_log.entries(),
         });
         let info_data = serde_json::to_vec_pretty(&info).unwrap();
-
-        let mut info_header = tar::Header::new_gnu();
-        info_header.set_size(info_data.len().try_into().unwrap());
-        info_header.set_mode(0o755);
-        info_header.set_cksum();
-
-        archive.append_data(&mut info_header, "info.json", Cursor::new(info_data))?;
+        add_data_to_archive(&mut archive, "info.json", info_data)?;
 
         let mut writer = archive.into_inner().context("Could not finish archive")?;
         writer.flush().context("Could not flush output file")?;
@@ -643,7 +1983,7 @@ impl<'a> Handler {
             .into_result()
             .context("Could not finish GZIP archive")?
             .into_inner()?
-            .set_permissions(Permissions::from_mode(0o775))
+            .set_permissions(Permissions::from_mode(0o644))
             .context("Could not set permissions on output file")?;
 
         Ok(out_path)
@@ -654,6 +1994,18 @@ impl<'a> Handler {
         ProfilesInfo {
             profiles: config.profiles.keys().cloned().collect(),
             current_profile: config.current_profile.clone(),
+            auto_switch: config.auto_switch_profiles,
+            fallback_profile: config.fallback_profile.clone(),
+            rules: config
+                .profiles
+                .iter()
+                .filter_map(|(name, profile)| Some((name.clone(), profile.rule.clone()?)))
+                .collect(),
+            schedules: config
+                .profiles
+                .iter()
+                .filter_map(|(name, profile)| Some((name.clone(), profile.schedule.clone()?)))
+                .collect(),
         }
     }
 
@@ -663,10 +2015,12 @@ impl<'a> Handler {
         }
 
         self.cleanup().await;
-        self.config.borrow_mut().current_profile = name;
+        self.config_history.clear();
+        self.config.borrow_mut().current_profile.clone_from(&name);
 
         self.apply_current_config().await?;
         self.config.borrow_mut().save()?;
+        self.run_hooks(&HookEvent::ProfileActivated { profile: name });
 
         Ok(())
     }
@@ -680,7 +2034,11 @@ impl<'a> Handler {
         let profile = match base {
             ProfileBase::Empty => Profile::default(),
             ProfileBase::Default => config.default_profile(),
-            ProfileBase::Profile(name) => config.profile(&name)?.clone(),
+            ProfileBase::Profile(name) => Profile {
+                rule: None,
+                schedule: None,
+                ..config.profile(&name)?.clone()
+            },
         };
         config.profiles.insert(name, profile);
         config.save()?;
@@ -691,11 +2049,128 @@ impl<'a> Handler {
         if self.config.borrow().current_profile.as_ref() == Some(&name) {
             self.set_profile(None).await?;
         }
-        self.config.borrow_mut().profiles.shift_remove(&name);
-        self.config.borrow().save()?;
+        let mut config = self.config.borrow_mut();
+        config.profiles.shift_remove(&name);
+        if config.fallback_profile.as_ref() == Some(&name) {
+            config.fallback_profile = None;
+        }
+        config.save()?;
+        Ok(())
+    }
+
+    pub fn set_profile_rule(&self, name: &str, rule: Option<ProfileRule>) -> anyhow::Result<()> {
+        if let Some(rule) = &rule {
+            if rule.process.trim().is_empty() {
+                bail!("Process name cannot be empty");
+            }
+        }
+
+        let mut config = self.config.borrow_mut();
+        config
+            .profiles
+            .get_mut(name)
+            .with_context(|| format!("Profile {name} not found"))?
+            .rule = rule;
+        config.save()?;
+        Ok(())
+    }
+
+    pub fn set_profile_schedule(
+        &self,
+        name: &str,
+        schedule: Option<ProfileSchedule>,
+    ) -> anyhow::Result<()> {
+        if let Some(schedule) = &schedule {
+            profile_watcher::validate_schedule(schedule)?;
+        }
+
+        let mut config = self.config.borrow_mut();
+        config
+            .profiles
+            .get_mut(name)
+            .with_context(|| format!("Profile {name} not found"))?
+            .schedule = schedule;
+        config.save()?;
+        Ok(())
+    }
+
+    pub fn set_auto_switch_profiles(&self, enabled: bool) -> anyhow::Result<()> {
+        let mut config = self.config.borrow_mut();
+        config.auto_switch_profiles = enabled;
+        config.save()?;
         Ok(())
     }
 
+    pub fn set_fallback_profile(&self, name: Option<String>) -> anyhow::Result<()> {
+        let mut config = self.config.borrow_mut();
+        if let Some(name) = &name {
+            config.profile(name)?;
+        }
+        config.fallback_profile = name;
+        config.save()?;
+        Ok(())
+    }
+
+    pub fn export_state(&self) -> anyhow::Result<String> {
+        let state = ExportedState {
+            version: EXPORTED_STATE_VERSION,
+            config: self.config.borrow().sanitized(),
+        };
+        serde_json::to_string_pretty(&state).context("Could not serialize state")
+    }
+
+    pub async fn import_state(&self, raw_state: &str) -> anyhow::Result<ImportStateResult> {
+        let state: ExportedState =
+            serde_json::from_str(raw_state).context("Could not parse state")?;
+        if state.version > EXPORTED_STATE_VERSION {
+            bail!(
+                "State version {} is newer than the supported version {EXPORTED_STATE_VERSION}",
+                state.version
+            );
+        }
+
+        let mut new_config = state.config;
+        if let Some(profile) = &new_config.current_profile {
+            new_config.profile(profile)?;
+        }
+        if let Some(profile) = &new_config.fallback_profile {
+            new_config.profile(profile)?;
+        }
+        // Daemon settings such as admin groups and listen addresses are specific to the system
+        new_config.daemon = self.config.borrow().daemon.clone();
+        let configured_commands = self.config.borrow().alert_commands();
+        ensure_alert_commands_configured(
+            new_config.alert_commands().iter().map(String::as_str),
+            &configured_commands,
+        )?;
+
+        let gpu_ids: Vec<String> = self.gpu_controllers.borrow().keys().cloned().collect();
+        let matched_gpus = match_gpu_ids(&new_config.gpu_ids(), gpu_ids.iter());
+        for (imported_id, id) in &matched_gpus {
+            info!("importing settings for GPU {imported_id} to {id}, which is the same model");
+        }
+        new_config.rename_gpus(&matched_gpus);
+
+        let skipped_gpus = new_config.retain_gpus(|id| gpu_ids.contains(id));
+        for id in &skipped_gpus {
+            warn!("skipping settings for GPU {id} from imported state, as it is not present");
+        }
+
+        self.cleanup().await;
+        self.config_history.clear();
+        *self.config.borrow_mut() = new_config;
+        self.apply_current_config().await?;
+
+        *self.config_last_saved.lock().unwrap() = Instant::now();
+        self.config.borrow().save()?;
+        *self.config_last_saved.lock().unwrap() = Instant::now();
+
+        Ok(ImportStateResult {
+            skipped_gpus,
+            matched_gpus,
+        })
+    }
+
     pub fn confirm_pending_config(&self, command: ConfirmCommand) -> anyhow::Result<()> {
         if let Some(tx) = self
             .confirm_config_tx
@@ -710,8 +2185,16 @@ impl<'a> Handler {
         }
     }
 
-    pub async fn reset_config(&self) {
-        self.cleanup().await;
+    /// Writes the data that is otherwise only saved periodically, before the daemon exits
+    pub fn flush_state(&self) {
+        if let Err(err) = self.energy_tracker.save() {
+            error!("could not save energy usage: {err:#}");
+        }
+    }
+
+    pub async fn reset_config(&self) -> ResetResult {
+        let result = self.cleanup().await;
+        self.config_history.clear();
 
         let mut config = self.config.borrow_mut();
         config.clear();
@@ -722,20 +2205,70 @@ impl<'a> Handler {
         }
 
         *self.config_last_saved.lock().unwrap() = Instant::now();
+
+        result
+    }
+
+    /// Hands fan control back to the GPU while keeping all other settings applied,
+    /// as a custom fan curve cannot be updated without the daemon running
+    pub async fn release_fan_control(&self) {
+        self.release_fan_control_where(|_| true).await;
+    }
+
+    /// Hands fan control back to the GPUs whose fan curve is followed by the daemon.
+    /// Static fan speeds and curves stored in the firmware stay applied.
+    pub async fn release_fan_control_tasks(&self) {
+        self.release_fan_control_where(|controller| controller.has_fan_control_task())
+            .await;
+    }
+
+    async fn release_fan_control_where(&self, filter: impl Fn(&dyn GpuController) -> bool) {
+        let gpus = match self.config.borrow().gpus() {
+            Ok(gpus) => gpus.clone(),
+            Err(err) => {
+                error!("could not get GPU configs: {err:#}");
+                return;
+            }
+        };
+
+        for (id, gpu_config) in gpus {
+            if !gpu_config.fan_control_enabled {
+                continue;
+            }
+
+            if let Some(controller) = self
+                .controller(&id)
+                .filter(|controller| filter(controller.as_ref()))
+            {
+                let new_config = config::Gpu {
+                    fan_control_enabled: false,
+                    ..gpu_config
+                };
+                if let Err(err) = controller.apply_config(&new_config).await {
+                    error!("could not reset fan control for GPU {id}: {err:#}");
+                }
+            }
+        }
     }
 
-    pub async fn cleanup(&self) {
+    /// Resets all GPUs to their default settings, collecting the errors for each GPU
+    pub async fn cleanup(&self) -> ResetResult {
         let disable_clocks_cleanup = self
             .config
             .try_borrow()
             .map(|config| config.daemon.disable_clocks_cleanup)
             .unwrap_or(false);
 
-        for (id, controller) in &*self.gpu_controllers {
+        let mut result = ResetResult::default();
+
+        for (id, controller) in self.controllers() {
+            let mut errors = Vec::new();
+
             if !disable_clocks_cleanup {
                 debug!("resetting clocks table");
                 if let Err(err) = controller.cleanup_clocks() {
-                    error!("could not reset the clocks table: {err}");
+                    error!("could not reset the clocks table for controller {id}: {err}");
+                    errors.push(format!("Could not reset the clocks table: {err}"));
                 }
             }
 
@@ -743,17 +2276,27 @@ impl<'a> Handler {
 
             if let Err(err) = controller.apply_config(&config::Gpu::default()).await {
                 error!("Could not reset settings for controller {id}: {err:#}");
+                errors.push(format!("Could not reset settings: {err:#}"));
+            }
+
+            if !errors.is_empty() {
+                result.errors.insert(id, errors);
             }
         }
+
+        result
     }
 }
 
-fn load_controllers() -> anyhow::Result<BTreeMap<String, Box<dyn GpuController>>> {
+/// Loads the GPUs in `class/drm` of the given sysfs root
+pub(crate) fn load_controllers(
+    sysfs_root: &Path,
+) -> anyhow::Result<BTreeMap<String, Rc<dyn GpuController>>> {
     let mut controllers = BTreeMap::new();
 
     let base_path = match env::var("_LACT_DRM_SYSFS_PATH") {
         Ok(custom_path) => PathBuf::from(custom_path),
-        Err(_) => PathBuf::from("/sys/class/drm"),
+        Err(_) => sysfs_root.join("class/drm"),
     };
 
     let pci_db = Database::read().unwrap_or_else(|err| {
@@ -787,6 +2330,29 @@ fn load_controllers() -> anyhow::Result<BTreeMap<String, Box<dyn GpuController>>
             .map_err(|_| anyhow!("non-utf path"))?;
         if name.starts_with("card") && !name.contains('-') {
             trace!("trying gpu controller at {:?}", entry.path());
+
+            match IntelGpuController::new_from_path(&entry.path(), &pci_db) {
+                Ok(Some(controller)) => match controller.get_id() {
+                    Ok(id) => {
+                        info!(
+                            "initialized Intel GPU controller {id} for path {:?}",
+                            controller.get_path()
+                        );
+                        controllers.insert(id, Rc::new(controller) as Rc<dyn GpuController>);
+                        continue;
+                    }
+                    Err(err) => warn!("could not initialize Intel controller: {err:#}"),
+                },
+                Ok(None) => (),
+                Err(err) => {
+                    warn!(
+                        "failed to initialize controller at {:?}, {err}",
+                        entry.path()
+                    );
+                    continue;
+                }
+            }
+
             let device_path = entry.path().join("device");
             match AmdGpuController::new_from_path(device_path, &pci_db) {
                 Ok(controller) => match controller.get_id() {
@@ -810,7 +2376,7 @@ fn load_controllers() -> anyhow::Result<BTreeMap<String, Box<dyn GpuController>>
                                                 info!("initialized Nvidia GPU controller {id} for path {path:?}");
                                                 controllers.insert(
                                                     id,
-                                                    Box::new(controller) as Box<dyn GpuController>,
+                                                    Rc::new(controller) as Rc<dyn GpuController>,
                                                 );
                                                 continue;
                                             }
@@ -832,7 +2398,7 @@ fn load_controllers() -> anyhow::Result<BTreeMap<String, Box<dyn GpuController>>
                         }
 
                         info!("initialized GPU controller {id} for path {path:?}");
-                        controllers.insert(id, Box::new(controller) as Box<dyn GpuController>);
+                        controllers.insert(id, Rc::new(controller) as Rc<dyn GpuController>);
                     }
                     Err(err) => warn!("could not initialize controller: {err:#}"),
                 },
@@ -849,6 +2415,48 @@ fn load_controllers() -> anyhow::Result<BTreeMap<String, Box<dyn GpuController>>
     Ok(controllers)
 }
 
+/// Power states and power profile modes switch to the manual performance level, as the driver needs it for them.
+/// Once none of them are configured anymore, the GPU goes back to the automatic level.
+fn release_manual_performance_level(gpu_config: &mut config::Gpu) {
+    if gpu_config.performance_level == Some(PerformanceLevel::Manual)
+        && gpu_config.power_states.is_empty()
+        && gpu_config.power_profile_mode_index.is_none()
+    {
+        gpu_config.performance_level = Some(PerformanceLevel::Auto);
+    }
+}
+
+fn add_data_to_archive(
+    archive: &mut tar::Builder<impl Write>,
+    name: &str,
+    data: Vec<u8>,
+) -> anyhow::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len().try_into().unwrap());
+    header.set_mode(0o755);
+    header.set_cksum();
+
+    archive
+        .append_data(&mut header, name, Cursor::new(data))
+        .context("Could not write data to archive")
+}
+
+/// Keeps only the kernel log lines that are related to GPU drivers
+fn filter_kernel_log(log: &str) -> String {
+    log.lines()
+        .filter(|line| {
+            let line = line.to_lowercase();
+            SNAPSHOT_KERNEL_LOG_PATTERNS
+                .iter()
+                .any(|pattern| line.contains(pattern))
+        })
+        .fold(String::new(), |mut excerpt, line| {
+            excerpt.push_str(line);
+            excerpt.push('\n');
+            excerpt
+        })
+}
+
 fn add_path_to_archive(
     archive: &mut tar::Builder<impl Write>,
     full_path: &Path,
@@ -879,3 +2487,73 @@ fn add_path_to_archive(
     }
     Ok(())
 }
+
+/// Alert commands run as root, so like hooks they can only be added by editing the config file.
+/// Requests can keep the commands that are already configured, e.g. when the GUI saves the other alerts.
+fn ensure_alert_commands_configured<'c>(
+    commands: impl IntoIterator<Item = &'c str>,
+    configured: &BTreeSet<String>,
+) -> anyhow::Result<()> {
+    for command in commands {
+        if !configured.contains(command) {
+            bail!("Alert command `{command}` is not in the config file, commands run as root so they can only be added by editing it");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ensure_alert_commands_configured, filter_kernel_log, release_manual_performance_level,
+    };
+    use crate::config;
+    use amdgpu_sysfs::gpu_handle::{PerformanceLevel, PowerLevelKind};
+
+    #[test]
+    fn kernel_log_excerpt() {
+        let log = "[    0.000000] Linux version 6.12.1
+[    2.512345] [drm] amdgpu kernel modesetting enabled.
+[    2.600000] usb 1-1: new high-speed USB device number 2
+[    3.100000] amdgpu 0000:0b:00.0: amdgpu: SMU is initialized successfully!
+[    4.000000] NVRM: loading NVIDIA UNIX x86_64 Kernel Module
+";
+        assert_eq!(
+            filter_kernel_log(log),
+            "[    2.512345] [drm] amdgpu kernel modesetting enabled.
+[    3.100000] amdgpu 0000:0b:00.0: amdgpu: SMU is initialized successfully!
+[    4.000000] NVRM: loading NVIDIA UNIX x86_64 Kernel Module
+"
+        );
+    }
+
+    #[test]
+    fn manual_level_released_without_power_states() {
+        let mut gpu_config = config::Gpu {
+            performance_level: Some(PerformanceLevel::Manual),
+            power_states: [(PowerLevelKind::PcieSpeed, vec![1])].into(),
+            ..Default::default()
+        };
+        release_manual_performance_level(&mut gpu_config);
+        assert_eq!(gpu_config.performance_level, Some(PerformanceLevel::Manual));
+
+        gpu_config.power_states.clear();
+        release_manual_performance_level(&mut gpu_config);
+        assert_eq!(gpu_config.performance_level, Some(PerformanceLevel::Auto));
+
+        let mut gpu_config = config::Gpu {
+            performance_level: Some(PerformanceLevel::High),
+            ..Default::default()
+        };
+        release_manual_performance_level(&mut gpu_config);
+        assert_eq!(gpu_config.performance_level, Some(PerformanceLevel::High));
+    }
+
+    #[test]
+    fn only_configured_alert_commands_are_accepted() {
+        let configured = ["notify-send hot".to_owned()].into();
+        ensure_alert_commands_configured(["notify-send hot"], &configured).unwrap();
+        ensure_alert_commands_configured(["notify-send hot", "rm -rf /"], &configured).unwrap_err();
+        ensure_alert_commands_configured([], &configured).unwrap();
+    }
+}
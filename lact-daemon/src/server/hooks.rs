@@ -0,0 +1,241 @@
+//! User commands that are run when something happens in the daemon, configured as `hooks` in the daemon config.
+//! The details of the event are passed in `LACT_*` environment variables and as JSON on stdin.
+use crate::config::Hook;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+use tracing::{debug, error, warn};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEventKind {
+    ProfileActivated,
+    AlertTriggered,
+    AlertCleared,
+    ApplyFailed,
+    GpuReset,
+    GpuWoke,
+}
+
+impl HookEventKind {
+    fn name(self) -> &'static str {
+        match self {
+            HookEventKind::ProfileActivated => "profile_activated",
+            HookEventKind::AlertTriggered => "alert_triggered",
+            HookEventKind::AlertCleared => "alert_cleared",
+            HookEventKind::ApplyFailed => "apply_failed",
+            HookEventKind::GpuReset => "gpu_reset",
+            HookEventKind::GpuWoke => "gpu_woke",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HookEvent {
+    /// A profile was switched to, `None` is the default profile
+    ProfileActivated {
+        profile: Option<String>,
+    },
+    AlertTriggered {
+        gpu_id: String,
+        sensor: String,
+        temperature: f32,
+        threshold: f32,
+    },
+    AlertCleared {
+        gpu_id: String,
+        sensor: String,
+        temperature: f32,
+        threshold: f32,
+    },
+    /// Settings could not be applied, the previous ones are kept when possible
+    ApplyFailed {
+        gpu_id: String,
+        error: String,
+    },
+    GpuReset {
+        gpu_id: String,
+    },
+    /// The GPU woke up from runtime suspend, `processes` are the ones that had it open right after
+    GpuWoke {
+        gpu_id: String,
+        processes: Vec<String>,
+    },
+}
+
+impl HookEvent {
+    pub fn kind(&self) -> HookEventKind {
+        match self {
+            HookEvent::ProfileActivated { .. } => HookEventKind::ProfileActivated,
+            HookEvent::AlertTriggered { .. } => HookEventKind::AlertTriggered,
+            HookEvent::AlertCleared { .. } => HookEventKind::AlertCleared,
+            HookEvent::ApplyFailed { .. } => HookEventKind::ApplyFailed,
+            HookEvent::GpuReset { .. } => HookEventKind::GpuReset,
+            HookEvent::GpuWoke { .. } => HookEventKind::GpuWoke,
+        }
+    }
+
+    fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![("LACT_EVENT", self.kind().name().to_owned())];
+        match self {
+            HookEvent::ProfileActivated { profile } => {
+                env.push(("LACT_PROFILE", profile.clone().unwrap_or_default()));
+            }
+            HookEvent::AlertTriggered {
+                gpu_id,
+                sensor,
+                temperature,
+                threshold,
+            }
+            | HookEvent::AlertCleared {
+                gpu_id,
+                sensor,
+                temperature,
+                threshold,
+            } => {
+                env.push(("LACT_GPU_ID", gpu_id.clone()));
+                env.push(("LACT_ALERT_SENSOR", sensor.clone()));
+                env.push(("LACT_ALERT_TEMPERATURE", temperature.to_string()));
+                env.push(("LACT_ALERT_THRESHOLD", threshold.to_string()));
+            }
+            HookEvent::ApplyFailed { gpu_id, error } => {
+                env.push(("LACT_GPU_ID", gpu_id.clone()));
+                env.push(("LACT_ERROR", error.clone()));
+            }
+            HookEvent::GpuReset { gpu_id } => {
+                env.push(("LACT_GPU_ID", gpu_id.clone()));
+            }
+            HookEvent::GpuWoke { gpu_id, processes } => {
+                env.push(("LACT_GPU_ID", gpu_id.clone()));
+                env.push(("LACT_PROCESSES", processes.join(",")));
+            }
+        }
+        env
+    }
+}
+
+/// Hooks that run on the given event, hooks without any events listed run on all of them
+fn matching_hooks(hooks: &[Hook], kind: HookEventKind) -> impl Iterator<Item = &Hook> {
+    hooks
+        .iter()
+        .filter(move |hook| hook.events.is_empty() || hook.events.contains(&kind))
+}
+
+pub fn run_hooks(hooks: &[Hook], event: &HookEvent) {
+    let mut hooks = matching_hooks(hooks, event.kind()).peekable();
+    if hooks.peek().is_none() {
+        return;
+    }
+
+    let payload = match serde_json::to_vec(event) {
+        Ok(payload) => payload,
+        Err(err) => {
+            error!("could not serialize hook event: {err}");
+            return;
+        }
+    };
+    let env = event.env();
+    for hook in hooks {
+        debug!("running hook `{}` for {:?}", hook.command, event.kind());
+        spawn_command(&hook.command, &env, Some(payload.clone()), "hook");
+    }
+}
+
+/// Runs a shell command as root in the background, optionally writing the given data to its stdin.
+/// It is waited on in a separate thread so that the command can't block the daemon.
+pub fn spawn_command(
+    command: &str,
+    env: &[(&str, String)],
+    stdin: Option<Vec<u8>>,
+    description: &'static str,
+) {
+    let result = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .envs(env.iter().map(|(key, value)| (*key, value)))
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .spawn();
+
+    match result {
+        Ok(mut child) => {
+            let child_stdin = child.stdin.take();
+            std::thread::spawn(move || {
+                if let (Some(mut child_stdin), Some(data)) = (child_stdin, stdin) {
+                    // The command may exit without reading its input
+                    if let Err(err) = child_stdin.write_all(&data) {
+                        debug!("could not write {description} input: {err}");
+                    }
+                }
+
+                match child.wait() {
+                    Ok(status) if !status.success() => {
+                        warn!("{description} exited with {status}");
+                    }
+                    Ok(_) => (),
+                    Err(err) => error!("could not wait for {description}: {err}"),
+                }
+            });
+        }
+        Err(err) => error!("could not run {description} `{command}`: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{matching_hooks, HookEvent, HookEventKind};
+    use crate::config::Hook;
+    use serde_json::json;
+
+    #[test]
+    fn event_payload() {
+        let event = HookEvent::ApplyFailed {
+            gpu_id: "1002:73BF".to_owned(),
+            error: "Invalid clocks".to_owned(),
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({"event": "apply_failed", "gpu_id": "1002:73BF", "error": "Invalid clocks"})
+        );
+        assert_eq!(
+            event.env(),
+            vec![
+                ("LACT_EVENT", "apply_failed".to_owned()),
+                ("LACT_GPU_ID", "1002:73BF".to_owned()),
+                ("LACT_ERROR", "Invalid clocks".to_owned()),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(HookEventKind::GpuReset).unwrap(),
+            HookEventKind::GpuReset.name()
+        );
+    }
+
+    #[test]
+    fn hooks_filtered_by_event() {
+        let hooks = [
+            Hook {
+                events: vec![],
+                command: "all".to_owned(),
+            },
+            Hook {
+                events: vec![HookEventKind::AlertTriggered, HookEventKind::AlertCleared],
+                command: "alerts".to_owned(),
+            },
+        ];
+
+        let commands = |kind| {
+            matching_hooks(&hooks, kind)
+                .map(|hook| hook.command.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(commands(HookEventKind::AlertCleared), ["all", "alerts"]);
+        assert_eq!(commands(HookEventKind::ProfileActivated), ["all"]);
+    }
+}
@@ -0,0 +1,159 @@
+use super::handler::Handler;
+use anyhow::Context;
+use nix::sys::socket::{
+    bind, recv, socket, AddressFamily, MsgFlags, NetlinkAddr, SockFlag, SockProtocol, SockType,
+};
+use std::{
+    io,
+    os::fd::{AsRawFd, OwnedFd},
+    time::Duration,
+};
+use tokio::{io::unix::AsyncFd, time::timeout};
+use tracing::{error, info, warn};
+
+/// Multicast group of the uevents sent by the kernel
+const KERNEL_UEVENT_GROUP: u32 = 1;
+/// Several events arrive for a single device, and drivers keep initializing it for a moment after they were sent
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+const MAX_UEVENT_SIZE: usize = 8192;
+
+/// Kernel uevents of devices being added and removed
+struct UeventSocket {
+    fd: AsyncFd<OwnedFd>,
+}
+
+impl UeventSocket {
+    fn open() -> anyhow::Result<Self> {
+        let fd = socket(
+            AddressFamily::Netlink,
+            SockType::Datagram,
+            SockFlag::SOCK_CLOEXEC | SockFlag::SOCK_NONBLOCK,
+            SockProtocol::NetlinkKObjectUEvent,
+        )
+        .context("Could not create uevent socket")?;
+        bind(fd.as_raw_fd(), &NetlinkAddr::new(0, KERNEL_UEVENT_GROUP))
+            .context("Could not listen to uevents")?;
+
+        Ok(Self {
+            fd: AsyncFd::new(fd)?,
+        })
+    }
+
+    /// Waits until a GPU is added or removed
+    async fn next_gpu_change(&self) -> anyhow::Result<()> {
+        let mut buf = [0; MAX_UEVENT_SIZE];
+        loop {
+            let mut guard = self.fd.readable().await?;
+            let result = guard.try_io(|fd| {
+                recv(fd.as_raw_fd(), &mut buf, MsgFlags::empty()).map_err(io::Error::from)
+            });
+
+            match result {
+                Ok(Ok(len)) => {
+                    if is_gpu_change(&buf[..len]) {
+                        return Ok(());
+                    }
+                }
+                Ok(Err(err)) => return Err(err).context("Could not read uevent"),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+/// Adds GPUs that appear while the daemon is running (like Thunderbolt eGPUs or devices rebound from vfio),
+/// and removes the ones that are unplugged
+pub async fn run(handler: Handler) {
+    let socket = match UeventSocket::open() {
+        Ok(socket) => socket,
+        Err(err) => {
+            warn!("GPU hot-plug detection is not available: {err:#}");
+            return;
+        }
+    };
+
+    loop {
+        if let Err(err) = socket.next_gpu_change().await {
+            error!("stopping GPU hot-plug detection: {err:#}");
+            return;
+        }
+
+        info!("GPUs changed, waiting for them to settle");
+        while let Ok(Ok(())) = timeout(SETTLE_DELAY, socket.next_gpu_change()).await {}
+
+        if let Err(err) = handler.reload_controllers().await {
+            error!("could not reload GPUs: {err:#}");
+        }
+    }
+}
+
+/// Whether the uevent is about a GPU (not one of its connectors) being added or removed
+fn is_gpu_change(message: &[u8]) -> bool {
+    let mut action = None;
+    let mut subsystem = None;
+    let mut devname = None;
+
+    for field in message.split(|byte| *byte == 0) {
+        let Some((key, value)) = std::str::from_utf8(field)
+            .ok()
+            .and_then(|field| field.split_once('='))
+        else {
+            continue;
+        };
+        match key {
+            "ACTION" => action = Some(value),
+            "SUBSYSTEM" => subsystem = Some(value),
+            "DEVNAME" => devname = Some(value),
+            _ => (),
+        }
+    }
+
+    matches!(action, Some("add" | "remove"))
+        && subsystem == Some("drm")
+        && devname
+            .and_then(|name| name.strip_prefix("dri/card"))
+            .is_some_and(|number| {
+                !number.is_empty() && number.bytes().all(|byte| byte.is_ascii_digit())
+            })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_gpu_change;
+
+    fn uevent(fields: &[&str]) -> Vec<u8> {
+        fields.join("\0").into_bytes()
+    }
+
+    #[test]
+    fn detect_gpu_changes() {
+        let added = uevent(&[
+            "add@/devices/pci0000:00/0000:00:01.0/0000:01:00.0/drm/card1",
+            "ACTION=add",
+            "DEVPATH=/devices/pci0000:00/0000:00:01.0/0000:01:00.0/drm/card1",
+            "SUBSYSTEM=drm",
+            "DEVNAME=dri/card1",
+            "DEVTYPE=drm_minor",
+            "SEQNUM=4321",
+        ]);
+        assert!(is_gpu_change(&added));
+
+        let removed = uevent(&["ACTION=remove", "SUBSYSTEM=drm", "DEVNAME=dri/card12"]);
+        assert!(is_gpu_change(&removed));
+
+        // Monitors being plugged in
+        let hotplug = uevent(&[
+            "ACTION=change",
+            "SUBSYSTEM=drm",
+            "DEVNAME=dri/card1",
+            "HOTPLUG=1",
+        ]);
+        assert!(!is_gpu_change(&hotplug));
+        let render_node = uevent(&["ACTION=add", "SUBSYSTEM=drm", "DEVNAME=dri/renderD128"]);
+        assert!(!is_gpu_change(&render_node));
+        let connector = uevent(&["ACTION=add", "SUBSYSTEM=drm"]);
+        assert!(!is_gpu_change(&connector));
+        let other = uevent(&["ACTION=add", "SUBSYSTEM=usb", "DEVNAME=bus/usb/001/004"]);
+        assert!(!is_gpu_change(&other));
+    }
+}
@@ -0,0 +1,199 @@
+//! Minimal HTTP/1.1 handling for the metrics and web interface listeners.
+//! Every connection serves a single request and is closed afterwards.
+use anyhow::{bail, Context};
+use std::fmt;
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    time::{timeout, Duration},
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Largest request body that is accepted
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+/// Longest request line or header line that is accepted, including the line ending
+const MAX_LINE_LENGTH: u64 = 8192;
+const MAX_HEADERS: usize = 64;
+
+/// Error for a request that is answered with the given status instead of being handled
+#[derive(Debug)]
+pub struct RejectedRequest(pub &'static str);
+
+impl fmt::Display for RejectedRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Request rejected with {}", self.0)
+    }
+}
+
+impl std::error::Error for RejectedRequest {}
+
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Value of the header with the given (case-insensitive) name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub async fn read_request<R: AsyncBufRead + Unpin>(stream: &mut R) -> anyhow::Result<HttpRequest> {
+    timeout(REQUEST_TIMEOUT, read_request_inner(stream))
+        .await
+        .context("Timed out reading the request")?
+}
+
+async fn read_request_inner<R: AsyncBufRead + Unpin>(
+    stream: &mut R,
+) -> anyhow::Result<HttpRequest> {
+    let mut request_line = String::new();
+    read_line(stream, &mut request_line, "400 Bad Request").await?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let path = parts.next().unwrap_or_default().to_owned();
+
+    let mut headers = vec![];
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if read_line(stream, &mut line, "431 Request Header Fields Too Large").await? == 0
+            || line.trim().is_empty()
+        {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            bail!(RejectedRequest("431 Request Header Fields Too Large"));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_owned(), value.trim().to_owned()));
+        }
+    }
+
+    let mut request = HttpRequest {
+        method,
+        path,
+        headers,
+        body: vec![],
+    };
+
+    if let Some(length) = request.header("Content-Length") {
+        let length: usize = length.parse().context("Invalid content length")?;
+        if length > MAX_BODY_SIZE {
+            bail!(RejectedRequest("413 Content Too Large"));
+        }
+        request.body.resize(length, 0);
+        stream.read_exact(&mut request.body).await?;
+    }
+
+    Ok(request)
+}
+
+/// Reads a line of the request head, rejecting the request with `status` if it's over [`MAX_LINE_LENGTH`]
+async fn read_line<R: AsyncBufRead + Unpin>(
+    stream: &mut R,
+    line: &mut String,
+    status: &'static str,
+) -> anyhow::Result<usize> {
+    let read = (&mut *stream).take(MAX_LINE_LENGTH).read_line(line).await?;
+    if read as u64 == MAX_LINE_LENGTH && !line.ends_with('\n') {
+        bail!(RejectedRequest(status));
+    }
+    Ok(read)
+}
+
+/// Answers a request that could not be read with the status of its [`RejectedRequest`] error, if it has one
+pub async fn reject<W: AsyncWrite + Unpin>(
+    stream: &mut W,
+    err: &anyhow::Error,
+) -> anyhow::Result<()> {
+    if let Some(RejectedRequest(status)) = err.downcast_ref::<RejectedRequest>() {
+        write_response(
+            stream,
+            status,
+            "text/plain",
+            format!("{status}\n").as_bytes(),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+pub async fn write_response<W: AsyncWrite + Unpin>(
+    stream: &mut W,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> anyhow::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_request, RejectedRequest, MAX_HEADERS};
+
+    #[tokio::test]
+    async fn parse_request() {
+        let mut data: &[u8] = b"POST /api HTTP/1.1\r\nHost: localhost\r\nauthorization: Bearer abc\r\nContent-Length: 4\r\n\r\n{}\r\nignored";
+        let request = read_request(&mut data).await.unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/api");
+        assert_eq!(request.header("Authorization"), Some("Bearer abc"));
+        assert_eq!(request.header("Cookie"), None);
+        assert_eq!(request.body, b"{}\r\n");
+    }
+
+    #[tokio::test]
+    async fn reject_large_body() {
+        let mut data: &[u8] = b"POST /api HTTP/1.1\r\nContent-Length: 99999999\r\n\r\n";
+        assert!(read_request(&mut data).await.is_err());
+    }
+
+    async fn rejected_status(data: &[u8]) -> &'static str {
+        let err = read_request(&mut &data[..]).await.err().unwrap();
+        err.downcast_ref::<RejectedRequest>().unwrap().0
+    }
+
+    #[tokio::test]
+    async fn reject_long_lines() {
+        let long = "a".repeat(10_000);
+
+        let data = format!("GET /{long} HTTP/1.1\r\n\r\n");
+        assert_eq!(rejected_status(data.as_bytes()).await, "400 Bad Request");
+
+        let data = format!("GET / HTTP/1.1\r\nCookie: {long}\r\n\r\n");
+        assert_eq!(
+            rejected_status(data.as_bytes()).await,
+            "431 Request Header Fields Too Large"
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_too_many_headers() {
+        let data = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X-A: b\r\n".repeat(MAX_HEADERS + 1)
+        );
+        assert_eq!(
+            rejected_status(data.as_bytes()).await,
+            "431 Request Header Fields Too Large"
+        );
+
+        let data = format!("GET / HTTP/1.1\r\n{}\r\n", "X-A: b\r\n".repeat(MAX_HEADERS));
+        assert!(read_request(&mut data.as_bytes()).await.is_ok());
+    }
+}
@@ -0,0 +1,197 @@
+use super::{handler::Handler, http};
+use anyhow::Context;
+use lact_schema::DeviceStats;
+use std::fmt::Write;
+use tokio::{
+    io::BufReader,
+    net::{TcpListener, TcpStream},
+};
+use tracing::{debug, error, info};
+
+const METRICS_PATH: &str = "/metrics";
+
+/// Serves the stats of all GPUs in the Prometheus text exposition format
+pub async fn listen(address: &str, handler: Handler) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Could not bind metrics listener to {address}"))?;
+    info!(
+        "serving metrics on http://{}{METRICS_PATH}",
+        listener.local_addr()?
+    );
+
+    tokio::task::spawn_local(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let handler = handler.clone();
+                    tokio::task::spawn_local(async move {
+                        if let Err(err) = handle_connection(stream, &handler).await {
+                            debug!("metrics request failed: {err:#}");
+                        }
+                    });
+                }
+                Err(err) => error!("failed to accept metrics connection: {err}"),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+async fn handle_connection(stream: TcpStream, handler: &Handler) -> anyhow::Result<()> {
+    let mut stream = BufReader::new(stream);
+    let request = match http::read_request(&mut stream).await {
+        Ok(request) => request,
+        Err(err) => {
+            http::reject(stream.get_mut(), &err).await?;
+            return Err(err);
+        }
+    };
+
+    let (status, body) = if request.method == "GET" && request.path == METRICS_PATH {
+        ("200 OK", collect_metrics(handler))
+    } else {
+        ("404 Not Found", "Not found\n".to_owned())
+    };
+
+    http::write_response(
+        stream.get_mut(),
+        status,
+        "text/plain; version=0.0.4",
+        body.as_bytes(),
+    )
+    .await
+}
+
+fn collect_metrics(handler: &Handler) -> String {
+    let devices = handler.list_devices();
+    let config = handler.config.borrow();
+
+    let stats: Vec<_> = devices
+        .into_iter()
+        .filter_map(|device| {
+            let controller = handler.controller(&device.id)?;
+            let gpu_config = config.gpus().ok().and_then(|gpus| gpus.get(&device.id));
+            let name = device.label.or(device.name).unwrap_or_default();
+            // The controller is queried directly so that scrapes don't feed the stats smoother
+            Some((device.id, name, controller.get_stats(gpu_config)))
+        })
+        .collect();
+
+    render_metrics(&stats)
+}
+
+type MetricFn = fn(&DeviceStats) -> Vec<(Option<&str>, f64)>;
+
+#[allow(clippy::cast_precision_loss)]
+fn render_metrics(devices: &[(String, String, DeviceStats)]) -> String {
+    let metrics: &[(&str, &str, MetricFn)] = &[
+        ("lact_gpu_temperature_celsius", "GPU temperature", |stats| {
+            stats
+                .temps
+                .iter()
+                .filter_map(|(sensor, temp)| Some((Some(sensor.as_str()), temp.current?.into())))
+                .collect()
+        }),
+        ("lact_gpu_fan_speed_rpm", "Fan speed", |stats| {
+            opt(stats.fan.speed_current.map(f64::from))
+        }),
+        ("lact_gpu_fan_pwm", "Fan PWM value (0-255)", |stats| {
+            opt(stats.fan.pwm_current.map(f64::from))
+        }),
+        ("lact_gpu_power_watts", "Current power draw", |stats| {
+            opt(stats.power.current.or(stats.power.average))
+        }),
+        (
+            "lact_gpu_power_cap_watts",
+            "Configured power cap",
+            |stats| opt(stats.power.cap_current),
+        ),
+        ("lact_gpu_core_clock_mhz", "GPU core clockspeed", |stats| {
+            opt(stats.clockspeed.gpu_clockspeed.map(|value| value as f64))
+        }),
+        ("lact_gpu_memory_clock_mhz", "VRAM clockspeed", |stats| {
+            opt(stats.clockspeed.vram_clockspeed.map(|value| value as f64))
+        }),
+        ("lact_gpu_voltage_millivolts", "GPU voltage", |stats| {
+            opt(stats.voltage.gpu.map(|value| value as f64))
+        }),
+        ("lact_gpu_vram_used_bytes", "Used VRAM", |stats| {
+            opt(stats.vram.used.map(|value| value as f64))
+        }),
+        ("lact_gpu_vram_total_bytes", "Total VRAM", |stats| {
+            opt(stats.vram.total.map(|value| value as f64))
+        }),
+        ("lact_gpu_gtt_used_bytes", "Used GTT", |stats| {
+            opt(stats.vram.gtt_used.map(|value| value as f64))
+        }),
+        ("lact_gpu_gtt_total_bytes", "Total GTT", |stats| {
+            opt(stats.vram.gtt_total.map(|value| value as f64))
+        }),
+        ("lact_gpu_busy_percent", "GPU utilization", |stats| {
+            opt(stats.busy_percent.map(f64::from))
+        }),
+    ];
+
+    let mut out = String::new();
+
+    for (metric, help, f) in metrics {
+        let _ = writeln!(out, "# HELP {metric} {help}");
+        let _ = writeln!(out, "# TYPE {metric} gauge");
+
+        for (id, name, stats) in devices {
+            for (sensor, value) in f(stats) {
+                let _ = write!(
+                    out,
+                    "{metric}{{id=\"{}\",name=\"{}\"",
+                    escape_label(id),
+                    escape_label(name)
+                );
+                if let Some(sensor) = sensor {
+                    let _ = write!(out, ",sensor=\"{}\"", escape_label(sensor));
+                }
+                let _ = writeln!(out, "}} {value}");
+            }
+        }
+    }
+
+    out
+}
+
+fn opt(value: Option<f64>) -> Vec<(Option<&'static str>, f64)> {
+    value.map(|value| (None, value)).into_iter().collect()
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_metrics;
+    use lact_schema::DeviceStats;
+
+    #[test]
+    fn render_device_metrics() {
+        let mut stats = DeviceStats::default();
+        stats.fan.speed_current = Some(1200);
+        stats.busy_percent = Some(42);
+
+        let output = render_metrics(&[(
+            "1002:687F-1043:0555-0000:0b:00.0".to_owned(),
+            "Vega \"56\"".to_owned(),
+            stats,
+        )]);
+
+        assert!(output.contains("# TYPE lact_gpu_fan_speed_rpm gauge"));
+        assert!(output.contains(
+            "lact_gpu_fan_speed_rpm{id=\"1002:687F-1043:0555-0000:0b:00.0\",name=\"Vega \\\"56\\\"\"} 1200\n"
+        ));
+        assert!(output.contains("lact_gpu_busy_percent{"));
+        assert!(!output.contains("lact_gpu_power_watts{"));
+    }
+}
@@ -0,0 +1,108 @@
+//! Authorization of local clients outside of the admin group through polkit,
+//! e.g. the GUI running confined in a Flatpak sandbox.
+use super::ClientAccess;
+use anyhow::{bail, Context};
+use nix::unistd::Gid;
+use std::fs;
+use tokio::{net::UnixStream, process::Command};
+use tracing::{info, warn};
+
+pub const ACTION_ID: &str = "io.github.lact-linux.change-settings";
+
+/// Root and members of the admin group are trusted, other users need polkit authorization
+pub fn client_access(stream: &UnixStream, admin_gid: Gid) -> ClientAccess {
+    let cred = match stream.peer_cred() {
+        Ok(cred) => cred,
+        Err(err) => {
+            warn!("could not get the credentials of a client: {err}");
+            return ClientAccess::ReadOnly;
+        }
+    };
+    let Some(pid) = cred.pid() else {
+        return ClientAccess::ReadOnly;
+    };
+
+    if cred.uid() == 0 || in_group(pid, cred.gid(), admin_gid) {
+        ClientAccess::Trusted
+    } else {
+        ClientAccess::Polkit {
+            pid,
+            uid: cred.uid(),
+        }
+    }
+}
+
+/// Whether the process is a member of the group, either as its primary or a supplementary group
+fn in_group(pid: i32, primary_gid: u32, group: Gid) -> bool {
+    if primary_gid == group.as_raw() {
+        return true;
+    }
+    fs::read_to_string(format!("/proc/{pid}/status"))
+        .ok()
+        .and_then(|status| parse_groups(&status))
+        .is_some_and(|groups| groups.contains(&group.as_raw()))
+}
+
+/// Asks polkit whether the process may change settings.
+/// This shows an authentication prompt through the polkit agent of the user's session when needed.
+pub async fn check_authorization(pid: i32, uid: u32) -> anyhow::Result<()> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat"))
+        .with_context(|| format!("Could not read the info of client process {pid}"))?;
+    let start_time = parse_start_time(&stat).context("Could not parse process info")?;
+
+    // Including the start time and uid prevents the pid from being reused by another process
+    let subject = format!("{pid},{start_time},{uid}");
+    let output = Command::new("pkcheck")
+        .args(["--action-id", ACTION_ID, "--process", &subject])
+        .arg("--allow-user-interaction")
+        .output()
+        .await
+        .context("Could not run pkcheck, make sure that polkit is installed")?;
+
+    match output.status.code() {
+        Some(0) => {
+            info!("process {pid} of user {uid} was authorized through polkit");
+            Ok(())
+        }
+        Some(2) => bail!("Authentication was dismissed"),
+        _ => bail!(
+            "Not authorized to change settings: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+    }
+}
+
+/// Parses the supplementary groups from `/proc/<pid>/status`
+fn parse_groups(status: &str) -> Option<Vec<u32>> {
+    let groups = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Groups:"))?;
+    groups
+        .split_whitespace()
+        .map(|gid| gid.parse().ok())
+        .collect()
+}
+
+/// Parses the start time (in clock ticks since boot) from `/proc/<pid>/stat`
+fn parse_start_time(stat: &str) -> Option<u64> {
+    // The process name can contain spaces and parentheses, so fields are counted from its end.
+    // The start time is the 22nd field, which is the 20th one after the name.
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_groups, parse_start_time};
+
+    #[test]
+    fn parse_process_info() {
+        let status = "Name:\tlact\nUmask:\t0022\nGid:\t1000\t1000\t1000\t1000\nGroups:\t10 998 1000 \nNSpid:\t4242\n";
+        assert_eq!(parse_groups(status), Some(vec![10, 998, 1000]));
+        assert_eq!(parse_groups("Groups:\t\n"), Some(vec![]));
+
+        let stat = "4242 (lact gui (1)) S 1 4242 4242 0 -1 4194560 12345 0 0 0 150 30 0 0 20 0 12 0 987654 1234567 8901 18446744073709551615";
+        assert_eq!(parse_start_time(stat), Some(987_654));
+        assert_eq!(parse_start_time("4242 (lact)"), None);
+    }
+}
@@ -0,0 +1,332 @@
+//! Compares the power usage, temperatures and clocks of two profiles under the same workload.
+//! The GPU settings of each profile are applied in turn without switching the active profile, and the averages of the stats
+//! are recorded after they had some time to settle. The previous settings are restored at the end.
+use super::handler::Handler;
+use crate::config;
+use anyhow::{bail, Context};
+use lact_schema::{
+    DeviceStats, ProfileComparisonOptions, ProfileComparisonState, ProfileComparisonStatus,
+    ProfileStatsSummary,
+};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+    time::Duration,
+};
+use tokio::{
+    pin, select,
+    sync::Notify,
+    time::{interval, sleep},
+};
+use tracing::{error, info};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Shortest time in seconds that the stats are recorded with every profile
+const MIN_DURATION: u64 = 10;
+
+/// State of the comparisons of every GPU
+#[derive(Default)]
+pub struct ProfileComparison {
+    runs: RefCell<HashMap<String, ComparisonRun>>,
+}
+
+struct ComparisonRun {
+    status: ProfileComparisonStatus,
+    cancel: Rc<Notify>,
+}
+
+impl ProfileComparison {
+    pub fn status(&self, id: &str) -> ProfileComparisonStatus {
+        self.runs
+            .borrow()
+            .get(id)
+            .map(|run| run.status.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn is_running(&self, id: &str) -> bool {
+        matches!(
+            self.status(id).state,
+            ProfileComparisonState::Running { .. }
+        )
+    }
+
+    pub fn cancel(&self, id: &str) -> anyhow::Result<()> {
+        match self.runs.borrow().get(id) {
+            Some(run) if matches!(run.status.state, ProfileComparisonState::Running { .. }) => {
+                run.cancel.notify_one();
+                Ok(())
+            }
+            _ => bail!("No profile comparison is running on this GPU"),
+        }
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut ProfileComparisonStatus)) {
+        if let Some(run) = self.runs.borrow_mut().get_mut(id) {
+            f(&mut run.status);
+        }
+    }
+}
+
+/// Starts recording the stats of both profiles in the background
+pub fn start(handler: &Handler, id: &str, options: ProfileComparisonOptions) -> anyhow::Result<()> {
+    if options.duration < MIN_DURATION {
+        bail!("The stats have to be recorded for at least {MIN_DURATION} seconds");
+    }
+    if options.profiles[0] == options.profiles[1] {
+        bail!("Two different profiles are needed for a comparison");
+    }
+    handler
+        .controller(id)
+        .context("No controller with such id")?;
+    if handler.profile_comparison.is_running(id) {
+        bail!("A profile comparison is already running on this GPU");
+    }
+    if handler.undervolt.is_running(id) {
+        bail!("The undervolting assistant is running on this GPU");
+    }
+    if handler.is_config_pending() {
+        bail!("There is an unconfirmed configuration change pending");
+    }
+    for profile in &options.profiles {
+        profile_gpu_config(handler, id, profile.as_deref())?;
+    }
+
+    let cancel = Rc::new(Notify::new());
+    handler.profile_comparison.runs.borrow_mut().insert(
+        id.to_owned(),
+        ComparisonRun {
+            status: ProfileComparisonStatus {
+                state: ProfileComparisonState::Running {
+                    profile: 0,
+                    recording: false,
+                },
+                profiles: options.profiles.clone(),
+                results: vec![],
+            },
+            cancel: cancel.clone(),
+        },
+    );
+
+    let handler = handler.clone();
+    let id = id.to_owned();
+    tokio::task::spawn_local(async move {
+        let state = match compare(&handler, &id, &options, &cancel).await {
+            Ok(state) => state,
+            Err(err) => {
+                error!("comparing profiles on {id} failed: {err:#}");
+                ProfileComparisonState::Failed {
+                    error: format!("{err:#}"),
+                }
+            }
+        };
+        handler
+            .profile_comparison
+            .update(&id, |status| status.state = state);
+    });
+
+    Ok(())
+}
+
+async fn compare(
+    handler: &Handler,
+    id: &str,
+    options: &ProfileComparisonOptions,
+    cancel: &Notify,
+) -> anyhow::Result<ProfileComparisonState> {
+    let controller = handler.controller(id).context("The GPU was removed")?;
+    let base_config = handler
+        .config
+        .borrow()
+        .gpus()?
+        .get(id)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut cancelled = false;
+    for (index, profile) in options.profiles.iter().enumerate() {
+        let gpu_config = profile_gpu_config(handler, id, profile.as_deref())?;
+        info!(
+            "comparing profiles on {id}: applying {}",
+            profile.as_deref().unwrap_or("the default profile")
+        );
+        if let Err(err) = controller.apply_config(&gpu_config).await {
+            controller
+                .apply_config(&base_config)
+                .await
+                .context("Could not restore the previous settings")?;
+            return Err(err.context("Could not apply the settings of the profile"));
+        }
+
+        handler.profile_comparison.update(id, |status| {
+            status.state = ProfileComparisonState::Running {
+                profile: index,
+                recording: false,
+            };
+        });
+        select! {
+            () = sleep(Duration::from_secs(options.settle_time)) => (),
+            () = cancel.notified() => {
+                cancelled = true;
+                break;
+            }
+        }
+
+        handler.profile_comparison.update(id, |status| {
+            status.state = ProfileComparisonState::Running {
+                profile: index,
+                recording: true,
+            };
+        });
+        let mut samples = Vec::new();
+        let mut interval = interval(SAMPLE_INTERVAL);
+        let end = sleep(Duration::from_secs(options.duration));
+        pin!(end);
+        loop {
+            select! {
+                _ = interval.tick() => samples.push(controller.get_stats(Some(&gpu_config))),
+                () = &mut end => break,
+                () = cancel.notified() => {
+                    cancelled = true;
+                    break;
+                }
+            }
+        }
+        if cancelled {
+            break;
+        }
+
+        let summary = summarize(profile.clone(), &samples);
+        handler
+            .profile_comparison
+            .update(id, |status| status.results.push(summary));
+    }
+
+    controller
+        .apply_config(&base_config)
+        .await
+        .context("Could not restore the previous settings")?;
+
+    if cancelled {
+        Ok(ProfileComparisonState::Cancelled)
+    } else {
+        Ok(ProfileComparisonState::Finished)
+    }
+}
+
+/// Settings of the GPU in the given profile, `None` being the default profile
+fn profile_gpu_config(
+    handler: &Handler,
+    id: &str,
+    profile: Option<&str>,
+) -> anyhow::Result<config::Gpu> {
+    let config = handler.config.borrow();
+    let gpu_config = match profile {
+        Some(name) => config.profile(name)?.gpus.get(id).cloned(),
+        None => config.default_profile().gpus.get(id).cloned(),
+    };
+    Ok(gpu_config.unwrap_or_default())
+}
+
+/// Averages the stats, values that were missing in some samples are averaged over the others
+#[allow(clippy::cast_precision_loss)]
+fn summarize(profile: Option<String>, samples: &[DeviceStats]) -> ProfileStatsSummary {
+    fn average(values: impl Iterator<Item = f64>) -> Option<f64> {
+        let (sum, count) = values.fold((0.0, 0_usize), |(sum, count), value| {
+            (sum + value, count + 1)
+        });
+        (count > 0).then(|| sum / count as f64)
+    }
+
+    let mut temperatures: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for stats in samples {
+        for (sensor, temp) in &stats.temps {
+            if let Some(current) = temp.current {
+                temperatures
+                    .entry(sensor.clone())
+                    .or_default()
+                    .push(f64::from(current));
+            }
+        }
+    }
+
+    ProfileStatsSummary {
+        profile,
+        samples: samples.len(),
+        power: average(
+            samples
+                .iter()
+                .filter_map(|stats| stats.power.current.or(stats.power.average)),
+        ),
+        temperatures: temperatures
+            .into_iter()
+            .filter_map(|(sensor, values)| Some((sensor, average(values.into_iter())?)))
+            .collect(),
+        gpu_clockspeed: average(
+            samples
+                .iter()
+                .filter_map(|stats| stats.clockspeed.gpu_clockspeed)
+                .map(|value| value as f64),
+        ),
+        vram_clockspeed: average(
+            samples
+                .iter()
+                .filter_map(|stats| stats.clockspeed.vram_clockspeed)
+                .map(|value| value as f64),
+        ),
+        fan_speed: average(
+            samples
+                .iter()
+                .filter_map(|stats| stats.fan.speed_current)
+                .map(f64::from),
+        ),
+        busy_percent: average(
+            samples
+                .iter()
+                .filter_map(|stats| stats.busy_percent)
+                .map(f64::from),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::summarize;
+    use amdgpu_sysfs::hw_mon::Temperature;
+    use lact_schema::DeviceStats;
+
+    fn stats(power: Option<f64>, temp: f32) -> DeviceStats {
+        let mut stats = DeviceStats::default();
+        stats.power.current = power;
+        stats.temps.insert(
+            "edge".to_owned(),
+            Temperature {
+                current: Some(temp),
+                crit: None,
+                crit_hyst: None,
+            },
+        );
+        stats
+    }
+
+    #[test]
+    fn averages() {
+        let samples = [
+            stats(Some(200.0), 60.0),
+            stats(None, 70.0),
+            stats(Some(100.0), 80.0),
+        ];
+        let summary = summarize(Some("undervolt".to_owned()), &samples);
+
+        assert_eq!(summary.profile.as_deref(), Some("undervolt"));
+        assert_eq!(summary.samples, 3);
+        assert_eq!(summary.power, Some(150.0));
+        assert_eq!(summary.temperatures["edge"], 70.0);
+        assert_eq!(summary.gpu_clockspeed, None);
+
+        let empty = summarize(None, &[]);
+        assert_eq!(empty.power, None);
+        assert!(empty.temperatures.is_empty());
+    }
+}
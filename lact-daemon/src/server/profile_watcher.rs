@@ -0,0 +1,390 @@
+use super::handler::Handler;
+use crate::config::Profile;
+use anyhow::{bail, Context};
+use chrono::{Datelike, Timelike};
+use indexmap::IndexMap;
+use lact_schema::{ProfileRule, ProfileSchedule};
+use std::{fs, time::Duration};
+use tokio::time::interval;
+use tracing::{debug, error, info};
+
+const PROCESS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+pub(super) struct ProcessInfo {
+    /// Name from `/proc/<pid>/comm`, which the kernel truncates to 15 characters
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+/// Local time as the day of the week (1 = Monday) and the minute of the day
+#[derive(Debug, Clone, Copy)]
+struct LocalTime {
+    weekday: u8,
+    minute: u16,
+}
+
+impl LocalTime {
+    fn now() -> Self {
+        let now = chrono::Local::now();
+        Self {
+            weekday: u8::try_from(now.weekday().number_from_monday()).unwrap(),
+            minute: u16::try_from(now.hour() * 60 + now.minute()).unwrap(),
+        }
+    }
+}
+
+impl ProcessInfo {
+    /// File name of the executable, which is not truncated and also covers Windows paths under Wine
+    fn executable_name(&self) -> Option<&str> {
+        let arg0 = self.args.first()?;
+        arg0.rsplit(['/', '\\']).next()
+    }
+}
+
+/// Periodically checks the running processes and activates the first profile whose rule matches,
+/// or otherwise the first profile whose schedule covers the current time.
+/// When an automatically activated profile stops matching, the fallback profile (or the default settings) is used.
+/// Profiles that were selected manually are kept until a rule or schedule matches.
+pub async fn run(handler: Handler) {
+    let mut interval = interval(PROCESS_POLL_INTERVAL);
+    // Avoids retrying the same switch on every poll if applying the profile fails
+    let mut failed_target = None;
+    // Profile that was activated by the watcher. One with a rule or schedule may still be active from before a restart
+    let mut activated = {
+        let config = handler.config.borrow();
+        config
+            .current_profile
+            .as_ref()
+            .and_then(|name| config.profiles.get(name))
+            .is_some_and(|profile| profile.rule.is_some() || profile.schedule.is_some())
+            .then(|| config.current_profile.clone())
+    };
+
+    loop {
+        interval.tick().await;
+
+        let target = {
+            let config = handler.config.borrow();
+            if !config.auto_switch_profiles {
+                failed_target = None;
+                continue;
+            }
+            if activated
+                .as_ref()
+                .is_some_and(|activated| *activated != config.current_profile)
+            {
+                debug!("profile was changed manually since it was activated automatically");
+                activated = None;
+            }
+
+            let processes = read_processes();
+            let matching = find_matching_profile(&config.profiles, &processes, LocalTime::now());
+            let Some(target) = switch_target(
+                matching,
+                activated.is_some(),
+                config.fallback_profile.as_deref(),
+            ) else {
+                continue;
+            };
+            if target == config.current_profile || failed_target.as_ref() == Some(&target) {
+                continue;
+            }
+            target
+        };
+
+        info!(
+            "automatically switching to profile {}",
+            target.as_deref().unwrap_or("Default")
+        );
+        match handler.set_profile(target.clone()).await {
+            Ok(()) => {
+                failed_target = None;
+                activated = Some(target);
+            }
+            Err(err) => {
+                error!("could not switch profile: {err:#}");
+                failed_target = Some(target);
+            }
+        }
+    }
+}
+
+fn find_matching_profile<'a>(
+    profiles: &'a IndexMap<String, Profile>,
+    processes: &[ProcessInfo],
+    time: LocalTime,
+) -> Option<&'a str> {
+    let by_rule = profiles.iter().find_map(|(name, profile)| {
+        let rule = profile.rule.as_ref()?;
+        processes
+            .iter()
+            .any(|process| rule_matches(rule, process))
+            .then_some(name.as_str())
+    });
+
+    by_rule.or_else(|| {
+        profiles.iter().find_map(|(name, profile)| {
+            let schedule = profile.schedule.as_ref()?;
+            schedule_matches(schedule, time).then_some(name.as_str())
+        })
+    })
+}
+
+/// Profile to switch to (`Some(None)` being the default settings), or `None` to keep the current one.
+/// Without a match, only automatically activated profiles are switched away from.
+fn switch_target(
+    matching: Option<&str>,
+    auto_activated: bool,
+    fallback: Option<&str>,
+) -> Option<Option<String>> {
+    match matching {
+        Some(name) => Some(Some(name.to_owned())),
+        None if auto_activated => Some(fallback.map(str::to_owned)),
+        None => None,
+    }
+}
+
+fn schedule_matches(schedule: &ProfileSchedule, time: LocalTime) -> bool {
+    let (Some(start), Some(end)) = (parse_time(&schedule.start), parse_time(&schedule.end)) else {
+        return false;
+    };
+    let starts_on = |day: u8| schedule.days.is_empty() || schedule.days.contains(&day);
+
+    if start < end {
+        starts_on(time.weekday) && (start..end).contains(&time.minute)
+    } else {
+        // The window continues past midnight, so it could have started on the previous day
+        let previous_day = if time.weekday == 1 {
+            7
+        } else {
+            time.weekday - 1
+        };
+        (time.minute >= start && starts_on(time.weekday))
+            || (time.minute < end && starts_on(previous_day))
+    }
+}
+
+pub(super) fn validate_schedule(schedule: &ProfileSchedule) -> anyhow::Result<()> {
+    let start = parse_time(&schedule.start)
+        .with_context(|| format!("Invalid start time '{}', expected HH:MM", schedule.start))?;
+    let end = parse_time(&schedule.end)
+        .with_context(|| format!("Invalid end time '{}', expected HH:MM", schedule.end))?;
+    if start == end {
+        bail!("The schedule has to end at a different time than it starts");
+    }
+    if let Some(day) = schedule.days.iter().find(|day| !(1..=7).contains(*day)) {
+        bail!("Invalid day {day}, days are numbered from 1 (Monday) to 7 (Sunday)");
+    }
+    Ok(())
+}
+
+/// Parses a time of day such as `22:30` into the minute of the day
+fn parse_time(value: &str) -> Option<u16> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    let hours: u16 = hours.parse().ok()?;
+    let minutes: u16 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+pub(super) fn rule_matches(rule: &ProfileRule, process: &ProcessInfo) -> bool {
+    let name_matches =
+        process.name == rule.process || process.executable_name() == Some(rule.process.as_str());
+
+    let args_match = match &rule.args {
+        Some(args) => process.args.join(" ").contains(args.as_str()),
+        None => true,
+    };
+
+    name_matches && args_match
+}
+
+pub(super) fn read_processes() -> Vec<ProcessInfo> {
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(err) => {
+            debug!("could not list processes: {err}");
+            return vec![];
+        }
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.bytes().all(|c| c.is_ascii_digit()))
+        })
+        .filter_map(|entry| {
+            // Processes can exit while being read
+            let path = entry.path();
+            let name = fs::read_to_string(path.join("comm")).ok()?;
+            let cmdline = fs::read(path.join("cmdline")).ok()?;
+            let args = cmdline
+                .split(|c| *c == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect();
+
+            Some(ProcessInfo {
+                name: name.trim_end().to_owned(),
+                args,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        find_matching_profile, rule_matches, schedule_matches, switch_target, validate_schedule,
+        LocalTime, ProcessInfo,
+    };
+    use crate::config::Profile;
+    use indexmap::IndexMap;
+    use lact_schema::{ProfileRule, ProfileSchedule};
+
+    fn process(name: &str, args: &[&str]) -> ProcessInfo {
+        ProcessInfo {
+            name: name.to_owned(),
+            args: args.iter().map(|arg| (*arg).to_owned()).collect(),
+        }
+    }
+
+    fn time(weekday: u8, hour: u16, minute: u16) -> LocalTime {
+        LocalTime {
+            weekday,
+            minute: hour * 60 + minute,
+        }
+    }
+
+    fn schedule(start: &str, end: &str, days: &[u8]) -> ProfileSchedule {
+        ProfileSchedule {
+            start: start.to_owned(),
+            end: end.to_owned(),
+            days: days.to_vec(),
+        }
+    }
+
+    fn rule(process: &str, args: Option<&str>) -> ProfileRule {
+        ProfileRule {
+            process: process.to_owned(),
+            args: args.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn match_by_truncated_name_or_executable() {
+        let game = process(
+            "Cyberpunk2077.e",
+            &["Z:\\Games\\Cyberpunk 2077\\bin\\x64\\Cyberpunk2077.exe"],
+        );
+        assert!(rule_matches(&rule("Cyberpunk2077.exe", None), &game));
+        assert!(rule_matches(&rule("Cyberpunk2077.e", None), &game));
+        assert!(!rule_matches(&rule("Cyberpunk", None), &game));
+
+        let native = process("blender", &["/usr/bin/blender", "--background"]);
+        assert!(rule_matches(
+            &rule("blender", Some("--background")),
+            &native
+        ));
+        assert!(!rule_matches(&rule("blender", Some("--render")), &native));
+    }
+
+    #[test]
+    fn first_matching_profile_wins() {
+        let profiles: IndexMap<String, Profile> = [
+            ("Quiet", None),
+            ("Performance", Some(rule("game", None))),
+            ("Render", Some(rule("blender", None))),
+        ]
+        .into_iter()
+        .map(|(name, rule)| {
+            (
+                name.to_owned(),
+                Profile {
+                    rule,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+
+        let processes = [process("blender", &[]), process("game", &[])];
+        let noon = time(1, 12, 0);
+        assert_eq!(
+            find_matching_profile(&profiles, &processes, noon),
+            Some("Performance")
+        );
+        assert_eq!(find_matching_profile(&profiles, &[], noon), None);
+    }
+
+    #[test]
+    fn schedules_match_time_windows() {
+        let work_hours = schedule("09:00", "17:30", &[1, 2, 3, 4, 5]);
+        assert!(schedule_matches(&work_hours, time(1, 9, 0)));
+        assert!(schedule_matches(&work_hours, time(5, 17, 29)));
+        assert!(!schedule_matches(&work_hours, time(5, 17, 30)));
+        assert!(!schedule_matches(&work_hours, time(6, 12, 0)));
+
+        // Friday night continues into Saturday morning, but Sunday night isn't included
+        let overnight = schedule("22:00", "07:00", &[5]);
+        assert!(schedule_matches(&overnight, time(5, 23, 0)));
+        assert!(schedule_matches(&overnight, time(6, 6, 59)));
+        assert!(!schedule_matches(&overnight, time(5, 6, 0)));
+        assert!(!schedule_matches(&overnight, time(7, 23, 0)));
+
+        let every_night = schedule("22:00", "07:00", &[]);
+        assert!(schedule_matches(&every_night, time(1, 3, 0)));
+
+        assert!(validate_schedule(&work_hours).is_ok());
+        assert!(validate_schedule(&schedule("9:00", "25:00", &[])).is_err());
+        assert!(validate_schedule(&schedule("09:00", "09:00", &[])).is_err());
+        assert!(validate_schedule(&schedule("09:00", "10:00", &[8])).is_err());
+    }
+
+    #[test]
+    fn process_rules_take_priority_over_schedules() {
+        let profiles: IndexMap<String, Profile> = [
+            (
+                "Silent".to_owned(),
+                Profile {
+                    schedule: Some(schedule("22:00", "07:00", &[])),
+                    ..Default::default()
+                },
+            ),
+            (
+                "Performance".to_owned(),
+                Profile {
+                    rule: Some(rule("game", None)),
+                    ..Default::default()
+                },
+            ),
+        ]
+        .into();
+
+        let night = time(3, 23, 0);
+        assert_eq!(find_matching_profile(&profiles, &[], night), Some("Silent"));
+        assert_eq!(
+            find_matching_profile(&profiles, &[process("game", &[])], night),
+            Some("Performance")
+        );
+        assert_eq!(find_matching_profile(&profiles, &[], time(3, 12, 0)), None);
+    }
+
+    #[test]
+    fn manual_selection_is_kept_without_match() {
+        assert_eq!(switch_target(None, false, None), None);
+        assert_eq!(switch_target(None, false, Some("Quiet")), None);
+        assert_eq!(switch_target(None, true, None), Some(None));
+        assert_eq!(
+            switch_target(None, true, Some("Quiet")),
+            Some(Some("Quiet".to_owned()))
+        );
+        assert_eq!(
+            switch_target(Some("Performance"), false, Some("Quiet")),
+            Some(Some("Performance".to_owned()))
+        );
+    }
+}
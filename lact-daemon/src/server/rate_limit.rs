@@ -0,0 +1,116 @@
+use crate::config;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Minimum amount of time between separate settings writes to the same GPU
+pub const MIN_APPLY_INTERVAL_MILLIS: u64 = 250;
+
+/// Tracks when settings were last written to each GPU, so that clients spamming requests
+/// can't thrash sysfs and the config file.
+/// Settings that arrive within the interval are deferred, and only the latest ones are written once it passed.
+pub struct ApplyRateLimiter {
+    interval: Duration,
+    last_applied: RefCell<HashMap<String, Instant>>,
+    deferred: RefCell<HashMap<String, config::Gpu>>,
+}
+
+impl ApplyRateLimiter {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_applied: RefCell::new(HashMap::new()),
+            deferred: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the GPU had settings written to it within the rate limiting interval
+    pub fn is_recent(&self, id: &str, now: Instant) -> bool {
+        self.last_applied
+            .borrow()
+            .get(id)
+            .is_some_and(|last| now.saturating_duration_since(*last) < self.interval)
+    }
+
+    /// How long to wait before settings can be written to the GPU again
+    pub fn wait_time(&self, id: &str, now: Instant) -> Duration {
+        self.last_applied
+            .borrow()
+            .get(id)
+            .map_or(Duration::ZERO, |last| {
+                self.interval
+                    .saturating_sub(now.saturating_duration_since(*last))
+            })
+    }
+
+    pub fn record(&self, id: &str, now: Instant) {
+        self.last_applied.borrow_mut().insert(id.to_owned(), now);
+    }
+
+    /// Keeps the settings to be written after the wait time, replacing deferred ones that were not written yet.
+    /// Returns `true` when nothing was deferred before, in which case the caller has to schedule the write.
+    pub fn defer(&self, id: &str, config: config::Gpu) -> bool {
+        self.deferred
+            .borrow_mut()
+            .insert(id.to_owned(), config)
+            .is_none()
+    }
+
+    /// Takes the deferred settings of the GPU, either to write them or because they were superseded or reverted
+    pub fn take_deferred(&self, id: &str) -> Option<config::Gpu> {
+        self.deferred.borrow_mut().remove(id)
+    }
+}
+
+impl Default for ApplyRateLimiter {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(MIN_APPLY_INTERVAL_MILLIS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApplyRateLimiter;
+    use crate::config::Gpu;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn wait_time_after_write() {
+        let limiter = ApplyRateLimiter::new(Duration::from_millis(250));
+        let now = Instant::now();
+        limiter.record("gpu", now);
+
+        let later = now + Duration::from_millis(100);
+        assert!(limiter.is_recent("gpu", later));
+        assert_eq!(limiter.wait_time("gpu", later), Duration::from_millis(150));
+        assert!(!limiter.is_recent("gpu", now + Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn deferred_settings_replaced() {
+        let limiter = ApplyRateLimiter::default();
+        let settings = |power_cap| Gpu {
+            power_cap: Some(power_cap),
+            ..Default::default()
+        };
+
+        // Only the first deferral schedules a write, which then writes the latest settings
+        assert!(limiter.defer("gpu", settings(100.0)));
+        assert!(!limiter.defer("gpu", settings(150.0)));
+        assert_eq!(limiter.take_deferred("gpu").unwrap().power_cap, Some(150.0));
+        assert!(limiter.take_deferred("gpu").is_none());
+    }
+
+    #[test]
+    fn gpus_limited_separately() {
+        let limiter = ApplyRateLimiter::new(Duration::from_millis(250));
+        let now = Instant::now();
+        limiter.record("gpu1", now);
+
+        assert!(limiter.is_recent("gpu1", now));
+        assert!(!limiter.is_recent("gpu2", now));
+        assert_eq!(limiter.wait_time("gpu2", now), Duration::ZERO);
+    }
+}
@@ -0,0 +1,218 @@
+//! Runtime power management of the PCI device, which lets the dGPU of hybrid laptops power off while nothing uses it.
+//! The files in `power` can be read without waking a suspended device, unlike most other sysfs files of the GPU.
+use super::{
+    gpu_controller::sysfs_write::write_sysfs, gpu_processes::ProcessTracker, handler::Handler,
+    hooks::HookEvent,
+};
+use anyhow::Context;
+use lact_schema::{GpuProcess, RuntimePmStats, RuntimePmWake};
+use std::{cell::RefCell, collections::HashMap, fs, path::Path, time::Duration};
+use tokio::time::interval;
+use tracing::{debug, info, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const CONTROL_FILE: &str = "power/control";
+const STATUS_FILE: &str = "power/runtime_status";
+
+/// `None` when the device doesn't support runtime power management
+pub fn read_runtime_pm(device_path: &Path) -> Option<RuntimePmStats> {
+    let read = |file: &str| {
+        fs::read_to_string(device_path.join(file))
+            .ok()
+            .map(|contents| contents.trim().to_owned())
+    };
+
+    let status = read(STATUS_FILE)?;
+    if status == "unsupported" {
+        return None;
+    }
+
+    Some(RuntimePmStats {
+        status,
+        auto: read(CONTROL_FILE).is_some_and(|control| control == "auto"),
+        suspended_time_ms: read("power/runtime_suspended_time").and_then(|time| time.parse().ok()),
+        active_time_ms: read("power/runtime_active_time").and_then(|time| time.parse().ok()),
+        processes: vec![],
+        last_wake: None,
+    })
+}
+
+/// Whether the device is powered off, in which case reading its sensors would wake it up
+pub fn is_runtime_suspended(device_path: &Path) -> bool {
+    fs::read_to_string(device_path.join(STATUS_FILE))
+        .is_ok_and(|status| status.trim() == "suspended")
+}
+
+/// Lets the device power off while unused (`auto`) or keeps it powered on. The current setting is kept when none is configured.
+pub fn apply_runtime_pm(device_path: &Path, auto: Option<bool>) -> anyhow::Result<()> {
+    let Some(auto) = auto else {
+        return Ok(());
+    };
+    let control = if auto { "auto" } else { "on" };
+
+    let current = fs::read_to_string(device_path.join(CONTROL_FILE))
+        .context("The GPU does not support runtime power management")?;
+    if current.trim() != control {
+        debug!("setting runtime power management control to {control}");
+        write_sysfs(device_path, CONTROL_FILE, control)
+            .context("Could not set the runtime power management control")?;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct GpuState {
+    suspended: bool,
+    last_wake: Option<RuntimePmWake>,
+}
+
+/// Remembers when each GPU last woke up from runtime suspend
+#[derive(Default)]
+pub struct WakeTracker {
+    gpus: RefCell<HashMap<String, GpuState>>,
+}
+
+impl WakeTracker {
+    /// Records the current status, returning the wake when the GPU was suspended previously.
+    /// The processes are only listed on a wake, as that needs to go through all of them.
+    fn update(
+        &self,
+        id: &str,
+        suspended: bool,
+        timestamp: i64,
+        processes: impl FnOnce() -> Vec<String>,
+    ) -> Option<RuntimePmWake> {
+        let mut gpus = self.gpus.borrow_mut();
+        let state = gpus.entry(id.to_owned()).or_insert_with(|| GpuState {
+            suspended,
+            last_wake: None,
+        });
+
+        let woke = state.suspended && !suspended;
+        state.suspended = suspended;
+        if !woke {
+            return None;
+        }
+
+        let wake = RuntimePmWake {
+            timestamp,
+            processes: processes(),
+        };
+        state.last_wake = Some(wake.clone());
+        Some(wake)
+    }
+
+    pub fn last_wake(&self, id: &str) -> Option<RuntimePmWake> {
+        self.gpus
+            .borrow()
+            .get(id)
+            .and_then(|state| state.last_wake.clone())
+    }
+}
+
+/// Watches the runtime status of every GPU, reporting when one wakes up and which processes have it open at that point.
+/// A wake without any processes was caused by something that only touched the GPU briefly, such as `lspci` or a sensor read.
+pub async fn run(handler: Handler) {
+    let mut interval = interval(POLL_INTERVAL);
+    let process_tracker = ProcessTracker::default();
+
+    loop {
+        interval.tick().await;
+
+        for (id, controller) in handler.controllers() {
+            let Some(runtime_pm) = read_runtime_pm(controller.get_path()) else {
+                continue;
+            };
+
+            let processes = || {
+                controller
+                    .get_pci_slot_name()
+                    .map(|pci_slot| process_names(&process_tracker.processes(&pci_slot)))
+                    .unwrap_or_default()
+            };
+            let timestamp = chrono::Utc::now().timestamp_millis();
+            let Some(wake) =
+                handler
+                    .wake_tracker
+                    .update(&id, runtime_pm.is_suspended(), timestamp, processes)
+            else {
+                continue;
+            };
+
+            if wake.processes.is_empty() {
+                warn!("{id} woke up from runtime suspend without any process using it");
+            } else {
+                info!(
+                    "{id} woke up from runtime suspend, used by {}",
+                    wake.processes.join(", ")
+                );
+            }
+            handler.run_hooks(&HookEvent::GpuWoke {
+                gpu_id: id,
+                processes: wake.processes,
+            });
+        }
+    }
+}
+
+/// Names of the processes that have the GPU open, which keep it from suspending
+pub fn process_names(processes: &[GpuProcess]) -> Vec<String> {
+    let mut names: Vec<String> = processes
+        .iter()
+        .map(|process| process.name.clone())
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_runtime_pm, read_runtime_pm, WakeTracker};
+    use crate::tests::TempDir;
+    use std::fs;
+
+    #[test]
+    fn wakes() {
+        let tracker = WakeTracker::default();
+        let processes = || vec!["game".to_owned()];
+
+        // The first status is only remembered
+        assert!(tracker.update("gpu", false, 1, processes).is_none());
+        assert!(tracker.update("gpu", true, 2, processes).is_none());
+        assert!(tracker.update("gpu", true, 3, processes).is_none());
+
+        let wake = tracker.update("gpu", false, 4, processes).unwrap();
+        assert_eq!(wake.timestamp, 4);
+        assert_eq!(wake.processes, ["game"]);
+        assert!(tracker.update("gpu", false, 5, processes).is_none());
+        assert_eq!(tracker.last_wake("gpu"), Some(wake));
+        assert_eq!(tracker.last_wake("other"), None);
+    }
+
+    #[test]
+    fn control_file() {
+        let device = TempDir::new("runtime-pm");
+        fs::create_dir_all(device.join("power")).unwrap();
+        fs::write(device.join("power/control"), "on\n").unwrap();
+        fs::write(device.join("power/runtime_status"), "active\n").unwrap();
+        fs::write(device.join("power/runtime_suspended_time"), "5000\n").unwrap();
+
+        let stats = read_runtime_pm(&device).unwrap();
+        assert_eq!(stats.status, "active");
+        assert!(!stats.auto);
+        assert_eq!(stats.suspended_time_ms, Some(5000));
+        assert_eq!(stats.active_time_ms, None);
+
+        apply_runtime_pm(&device, None).unwrap();
+        assert_eq!(
+            fs::read_to_string(device.join("power/control")).unwrap(),
+            "on\n"
+        );
+        apply_runtime_pm(&device, Some(true)).unwrap();
+        assert!(read_runtime_pm(&device).unwrap().auto);
+
+        fs::write(device.join("power/runtime_status"), "unsupported\n").unwrap();
+        assert!(read_runtime_pm(&device).is_none());
+    }
+}
@@ -0,0 +1,118 @@
+//! Intervals of the background tasks that sample GPU sensors (energy usage, throttling).
+//! The interval can be set per GPU with `stats_interval_ms`. With `adaptive_stats_polling`, sampling slows down
+//! while no clients are connected and the GPU has no alerts, to reduce wakeups on laptops.
+use super::handler::Handler;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+const MIN_STATS_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_STATS_INTERVAL: Duration = Duration::from_secs(10);
+/// How much slower GPUs are sampled without clients
+const IDLE_INTERVAL_FACTOR: u32 = 4;
+/// Kept below the gap after which energy samples are no longer counted
+const MAX_IDLE_INTERVAL: Duration = Duration::from_secs(20);
+/// How long no clients have to be connected before sampling slows down
+const IDLE_DELAY: Duration = Duration::from_secs(10);
+
+/// Interval between samples of a GPU, using the task's default when the GPU has no configured interval
+pub fn sample_interval(configured_ms: Option<u64>, default: Duration, idle: bool) -> Duration {
+    let interval = configured_ms.map_or(default, |ms| {
+        Duration::from_millis(ms).clamp(MIN_STATS_INTERVAL, MAX_STATS_INTERVAL)
+    });
+
+    if idle {
+        (interval * IDLE_INTERVAL_FACTOR).min(MAX_IDLE_INTERVAL.max(interval))
+    } else {
+        interval
+    }
+}
+
+/// Tracks when each GPU was last sampled by a background task
+pub struct SampleSchedule {
+    default_interval: Duration,
+    /// Time of the last sample and the interval until the next one
+    gpus: HashMap<String, (Instant, Duration)>,
+}
+
+impl SampleSchedule {
+    pub fn new(default_interval: Duration) -> Self {
+        Self {
+            default_interval,
+            gpus: HashMap::new(),
+        }
+    }
+
+    /// Returns the ids of the GPUs that should be sampled now
+    pub fn due(&mut self, handler: &Handler, now: Instant) -> Vec<String> {
+        let config = handler.config.borrow();
+        let adaptive = config.daemon.adaptive_stats_polling;
+        let no_clients = handler.event_log.is_idle_for(IDLE_DELAY);
+        let gpus = config.gpus().ok();
+
+        let ids: Vec<String> = handler
+            .controllers()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        self.gpus.retain(|id, _| ids.contains(id));
+
+        ids.into_iter()
+            .filter(|id| {
+                let gpu_config = gpus.and_then(|gpus| gpus.get(id));
+                let idle = adaptive
+                    && no_clients
+                    && gpu_config.map_or(true, |gpu_config| gpu_config.alerts.is_empty());
+                let interval = sample_interval(
+                    gpu_config.and_then(|gpu_config| gpu_config.stats_interval_ms),
+                    self.default_interval,
+                    idle,
+                );
+
+                let due = self.gpus.get(id).map_or(true, |(last, _)| {
+                    now.saturating_duration_since(*last) >= interval
+                });
+                if due {
+                    self.gpus.insert(id.clone(), (now, interval));
+                }
+                due
+            })
+            .collect()
+    }
+
+    /// When the next GPU should be sampled
+    pub fn next_wakeup(&self, now: Instant) -> Instant {
+        self.gpus
+            .values()
+            .map(|(last, interval)| *last + *interval)
+            .min()
+            .unwrap_or(now + self.default_interval)
+            .max(now + MIN_STATS_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sample_interval;
+    use std::time::Duration;
+
+    #[test]
+    fn intervals() {
+        let default = Duration::from_secs(2);
+        assert_eq!(sample_interval(None, default, false), default);
+        assert_eq!(sample_interval(None, default, true), Duration::from_secs(8));
+        assert_eq!(
+            sample_interval(Some(10), default, false),
+            Duration::from_millis(250)
+        );
+        assert_eq!(
+            sample_interval(Some(60_000), default, false),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            sample_interval(Some(10_000), default, true),
+            Duration::from_secs(20)
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use super::{handler::Handler, runtime_pm::is_runtime_suspended};
+use anyhow::{anyhow, Context};
+use lact_schema::{DeviceStats, StatsHistorySample};
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+use tokio::time::interval;
+use tracing::{error, warn};
+
+pub const STATS_HISTORY_DIR: &str = "/var/lib/lact/history";
+/// Upper bound for the amount of samples kept per GPU, to limit the disk usage
+pub const MAX_STATS_HISTORY_SAMPLES: u32 = 1_000_000;
+
+const MAGIC: &[u8; 8] = b"LACTHST1";
+/// Magic, capacity, index of the next record and amount of stored records
+const HEADER_SIZE: usize = 8 + 4 + 4 + 4;
+/// Sensors that get a slot in the on-disk records
+const TEMPERATURE_SENSORS: [&str; 4] = ["edge", "junction", "mem", "GPU"];
+const RECORD_VALUES: usize = TEMPERATURE_SENSORS.len() + 6;
+const RECORD_SIZE: usize = 8 + RECORD_VALUES * 4;
+
+/// Stats samples of every GPU, each kept in a fixed-size ring buffer file
+pub struct StatsHistory {
+    dir: PathBuf,
+    capacity: u32,
+    buffers: RefCell<HashMap<String, RingFile>>,
+}
+
+impl StatsHistory {
+    pub fn new(dir: impl Into<PathBuf>, capacity: u32) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).with_context(|| format!("Could not create {dir:?}"))?;
+
+        Ok(Self {
+            dir,
+            capacity: capacity.clamp(1, MAX_STATS_HISTORY_SAMPLES),
+            buffers: RefCell::default(),
+        })
+    }
+
+    pub fn record(&self, id: &str, stats: &DeviceStats, timestamp: i64) -> anyhow::Result<()> {
+        let sample = sample_from_stats(stats, timestamp);
+        self.with_buffer(id, |buffer| buffer.push(&sample))
+    }
+
+    /// Returns the samples of the given GPU from oldest to newest
+    pub fn get(&self, id: &str, since: Option<i64>) -> anyhow::Result<Vec<StatsHistorySample>> {
+        self.with_buffer(id, |buffer| buffer.read_since(since))
+    }
+
+    fn with_buffer<T>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut RingFile) -> io::Result<T>,
+    ) -> anyhow::Result<T> {
+        let mut buffers = self.buffers.borrow_mut();
+        let buffer = match buffers.entry(id.to_owned()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let path = self.dir.join(format!("{id}.bin"));
+                let file = RingFile::open(&path, self.capacity)
+                    .with_context(|| format!("Could not open stats history file {path:?}"))?;
+                entry.insert(file)
+            }
+        };
+        f(buffer).map_err(|err| anyhow!("Could not access stats history: {err}"))
+    }
+}
+
+/// Periodically records the stats of every GPU
+pub async fn run(handler: Handler, history: Rc<StatsHistory>, period: Duration) {
+    let mut interval = interval(period);
+
+    loop {
+        interval.tick().await;
+        let timestamp = chrono::Utc::now().timestamp_millis();
+
+        // The controllers are queried directly so that the samples don't feed the stats smoother
+        let stats: Vec<_> = {
+            let config = handler.config.borrow();
+            let gpus = config.gpus().ok();
+            handler
+                .controllers()
+                .iter()
+                // Reading the sensors of a powered off GPU would wake it up
+                .filter(|(_, controller)| !is_runtime_suspended(controller.get_path()))
+                .map(|(id, controller)| {
+                    let gpu_config = gpus.and_then(|gpus| gpus.get(id));
+                    (id.clone(), controller.get_stats(gpu_config))
+                })
+                .collect()
+        };
+
+        for (id, stats) in stats {
+            if let Err(err) = history.record(&id, &stats, timestamp) {
+                error!("could not record stats history for {id}: {err:#}");
+            }
+        }
+    }
+}
+
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+fn sample_from_stats(stats: &DeviceStats, timestamp: i64) -> StatsHistorySample {
+    StatsHistorySample {
+        timestamp,
+        temperatures: TEMPERATURE_SENSORS
+            .iter()
+            .filter_map(|sensor| {
+                let temp = stats.temps.get(*sensor)?.current?;
+                Some(((*sensor).to_owned(), temp))
+            })
+            .collect(),
+        power: stats
+            .power
+            .current
+            .or(stats.power.average)
+            .map(|value| value as f32),
+        gpu_clockspeed: stats.clockspeed.gpu_clockspeed.map(|value| value as f32),
+        vram_clockspeed: stats.clockspeed.vram_clockspeed.map(|value| value as f32),
+        fan_speed: stats.fan.speed_current.map(|value| value as f32),
+        busy_percent: stats.busy_percent.map(f32::from),
+        vram_used: stats.vram.used.map(|value| (value / 1024 / 1024) as f32),
+    }
+}
+
+struct RingFile {
+    file: File,
+    capacity: u32,
+    /// Index of the slot that will be written next
+    head: u32,
+    len: u32,
+}
+
+impl RingFile {
+    fn open(path: &Path, capacity: u32) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let mut header = [0; HEADER_SIZE];
+        if file.read_exact(&mut header).is_ok() && &header[..8] == MAGIC {
+            let stored_capacity = read_u32(&header[8..12]);
+            let head = read_u32(&header[12..16]);
+            let len = read_u32(&header[16..20]);
+
+            if stored_capacity == capacity && head < capacity && len <= capacity {
+                return Ok(Self {
+                    file,
+                    capacity,
+                    head,
+                    len,
+                });
+            }
+            warn!("stats history in {path:?} has a different size, starting a new one");
+        }
+
+        file.set_len(0)?;
+        file.set_len((HEADER_SIZE + capacity as usize * RECORD_SIZE) as u64)?;
+        let mut ring = Self {
+            file,
+            capacity,
+            head: 0,
+            len: 0,
+        };
+        ring.write_header()?;
+        Ok(ring)
+    }
+
+    fn push(&mut self, sample: &StatsHistorySample) -> io::Result<()> {
+        let offset = (HEADER_SIZE + self.head as usize * RECORD_SIZE) as u64;
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(&encode_record(sample))?;
+
+        self.head = (self.head + 1) % self.capacity;
+        self.len = (self.len + 1).min(self.capacity);
+        self.write_header()
+    }
+
+    fn read_since(&mut self, since: Option<i64>) -> io::Result<Vec<StatsHistorySample>> {
+        let mut data = Vec::new();
+        self.file.seek(SeekFrom::Start(HEADER_SIZE as u64))?;
+        self.file.read_to_end(&mut data)?;
+
+        let start = (self.head + self.capacity - self.len) % self.capacity;
+        let samples = (0..self.len)
+            .filter_map(|i| {
+                let offset = ((start + i) % self.capacity) as usize * RECORD_SIZE;
+                decode_record(data.get(offset..offset + RECORD_SIZE)?)
+            })
+            .filter(|sample| match since {
+                Some(since) => sample.timestamp >= since,
+                None => true,
+            })
+            .collect();
+        Ok(samples)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&self.capacity.to_le_bytes());
+        header.extend_from_slice(&self.head.to_le_bytes());
+        header.extend_from_slice(&self.len.to_le_bytes());
+
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)
+    }
+}
+
+fn encode_record(sample: &StatsHistorySample) -> Vec<u8> {
+    let mut values: Vec<Option<f32>> = TEMPERATURE_SENSORS
+        .iter()
+        .map(|sensor| sample.temperatures.get(*sensor).copied())
+        .collect();
+    values.extend([
+        sample.power,
+        sample.gpu_clockspeed,
+        sample.vram_clockspeed,
+        sample.fan_speed,
+        sample.busy_percent,
+        sample.vram_used,
+    ]);
+
+    let mut record = Vec::with_capacity(RECORD_SIZE);
+    record.extend_from_slice(&sample.timestamp.to_le_bytes());
+    for value in values {
+        // Missing values are stored as NaN
+        record.extend_from_slice(&value.unwrap_or(f32::NAN).to_le_bytes());
+    }
+    record
+}
+
+fn decode_record(record: &[u8]) -> Option<StatsHistorySample> {
+    let timestamp = i64::from_le_bytes(record[..8].try_into().ok()?);
+    let values: Vec<Option<f32>> = record[8..]
+        .chunks_exact(4)
+        .map(|chunk| {
+            let value = f32::from_le_bytes(chunk.try_into().unwrap());
+            (!value.is_nan()).then_some(value)
+        })
+        .collect();
+    let (temps, values) = values.split_at(TEMPERATURE_SENSORS.len());
+
+    Some(StatsHistorySample {
+        timestamp,
+        temperatures: TEMPERATURE_SENSORS
+            .iter()
+            .zip(temps)
+            .filter_map(|(sensor, temp)| Some(((*sensor).to_owned(), (*temp)?)))
+            .collect(),
+        power: values[0],
+        gpu_clockspeed: values[1],
+        vram_clockspeed: values[2],
+        fan_speed: values[3],
+        busy_percent: values[4],
+        vram_used: values[5],
+    })
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes.try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatsHistory;
+    use crate::tests::TempDir;
+    use lact_schema::DeviceStats;
+
+    fn stats_with_clock(clock: u64) -> DeviceStats {
+        let mut stats = DeviceStats::default();
+        stats.clockspeed.gpu_clockspeed = Some(clock);
+        stats
+    }
+
+    #[test]
+    fn ring_buffer_wraps_around() {
+        let dir = TempDir::new("history-wrap");
+        let history = StatsHistory::new(&*dir, 3).unwrap();
+
+        for i in 0..5 {
+            history
+                .record(
+                    "gpu",
+                    &stats_with_clock(1000 + i),
+                    i64::try_from(i).unwrap(),
+                )
+                .unwrap();
+        }
+
+        let samples = history.get("gpu", None).unwrap();
+        let timestamps: Vec<_> = samples.iter().map(|sample| sample.timestamp).collect();
+        assert_eq!(timestamps, vec![2, 3, 4]);
+        assert_eq!(samples[0].gpu_clockspeed, Some(1002.0));
+        assert_eq!(samples[0].power, None);
+
+        let samples = history.get("gpu", Some(4)).unwrap();
+        assert_eq!(samples.len(), 1);
+    }
+
+    #[test]
+    fn history_persists_across_restarts() {
+        let dir = TempDir::new("history-persist");
+
+        let history = StatsHistory::new(&*dir, 10).unwrap();
+        history.record("gpu", &stats_with_clock(1500), 1).unwrap();
+        drop(history);
+
+        let history = StatsHistory::new(&*dir, 10).unwrap();
+        history.record("gpu", &stats_with_clock(1600), 2).unwrap();
+        assert_eq!(history.get("gpu", None).unwrap().len(), 2);
+
+        // A different size discards the old data
+        let history = StatsHistory::new(&*dir, 5).unwrap();
+        assert!(history.get("gpu", None).unwrap().is_empty());
+    }
+}
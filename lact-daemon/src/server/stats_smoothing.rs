@@ -0,0 +1,116 @@
+use lact_schema::{DeviceStats, SmoothedStats};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+};
+
+/// Upper bound for the smoothing window, to keep the per-GPU buffers small
+pub const MAX_STATS_SMOOTHING_WINDOW: usize = 60;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Sample {
+    gpu_clockspeed: Option<f64>,
+    vram_clockspeed: Option<f64>,
+    power_average: Option<f64>,
+    power_current: Option<f64>,
+}
+
+/// Keeps the most recent stats samples of each GPU to compute moving averages of noisy readings
+#[derive(Default)]
+pub struct StatsSmoother {
+    samples: RefCell<HashMap<String, VecDeque<Sample>>>,
+}
+
+impl StatsSmoother {
+    /// Records the current stats and fills in their moving average over the last `window` samples
+    pub fn apply(&self, id: &str, stats: &mut DeviceStats, window: usize) {
+        let window = window.min(MAX_STATS_SMOOTHING_WINDOW);
+        if window <= 1 {
+            self.samples.borrow_mut().remove(id);
+            return;
+        }
+
+        let mut samples = self.samples.borrow_mut();
+        let buffer = samples.entry(id.to_owned()).or_default();
+
+        buffer.push_back(Sample {
+            gpu_clockspeed: stats.clockspeed.gpu_clockspeed.map(|value| value as f64),
+            vram_clockspeed: stats.clockspeed.vram_clockspeed.map(|value| value as f64),
+            power_average: stats.power.average,
+            power_current: stats.power.current,
+        });
+        while buffer.len() > window {
+            buffer.pop_front();
+        }
+
+        stats.smoothed = Some(SmoothedStats {
+            window: buffer.len(),
+            gpu_clockspeed: average(buffer, |sample| sample.gpu_clockspeed),
+            vram_clockspeed: average(buffer, |sample| sample.vram_clockspeed),
+            power_average: average(buffer, |sample| sample.power_average),
+            power_current: average(buffer, |sample| sample.power_current),
+        });
+    }
+}
+
+fn average(samples: &VecDeque<Sample>, f: impl Fn(&Sample) -> Option<f64>) -> Option<f64> {
+    let (sum, count) = samples
+        .iter()
+        .filter_map(f)
+        .fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+
+    if count == 0 {
+        None
+    } else {
+        Some(sum / f64::from(count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StatsSmoother, MAX_STATS_SMOOTHING_WINDOW};
+    use lact_schema::DeviceStats;
+
+    fn stats_with_clock(clock: u64) -> DeviceStats {
+        let mut stats = DeviceStats::default();
+        stats.clockspeed.gpu_clockspeed = Some(clock);
+        stats
+    }
+
+    #[test]
+    fn moving_average_over_window() {
+        let smoother = StatsSmoother::default();
+
+        for clock in [1000, 2000, 3000, 4000] {
+            let mut stats = stats_with_clock(clock);
+            smoother.apply("gpu", &mut stats, 3);
+
+            if clock == 4000 {
+                let smoothed = stats.smoothed.unwrap();
+                assert_eq!(smoothed.window, 3);
+                assert_eq!(smoothed.gpu_clockspeed, Some(3000.0));
+                assert_eq!(smoothed.power_average, None);
+                assert_eq!(stats.clockspeed.gpu_clockspeed, Some(4000));
+            }
+        }
+    }
+
+    #[test]
+    fn window_is_bounded() {
+        let smoother = StatsSmoother::default();
+
+        for _ in 0..MAX_STATS_SMOOTHING_WINDOW * 2 {
+            let mut stats = stats_with_clock(1000);
+            smoother.apply("gpu", &mut stats, usize::MAX);
+            assert!(stats.smoothed.unwrap().window <= MAX_STATS_SMOOTHING_WINDOW);
+        }
+    }
+
+    #[test]
+    fn disabled_smoothing() {
+        let smoother = StatsSmoother::default();
+        let mut stats = stats_with_clock(1000);
+        smoother.apply("gpu", &mut stats, 1);
+        assert!(stats.smoothed.is_none());
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::{anyhow, ensure, Context};
-use lact_schema::{InitramfsType, SystemInfo, GIT_COMMIT};
+use lact_schema::{InitramfsType, SystemInfo, GIT_COMMIT, PROTOCOL_VERSION};
 use os_release::{OsRelease, OS_RELEASE};
 use std::{
     fs::{self, File, Permissions},
@@ -14,8 +14,10 @@ use tracing::{info, warn};
 static OC_TOGGLED: AtomicBool = AtomicBool::new(false);
 
 const PP_OVERDRIVE_MASK: u64 = 0x4000;
+const PP_FEATURE_MASK_CMDLINE_ARG: &str = "amdgpu.ppfeaturemask";
 pub const PP_FEATURE_MASK_PATH: &str = "/sys/module/amdgpu/parameters/ppfeaturemask";
 pub const MODULE_CONF_PATH: &str = "/etc/modprobe.d/99-amdgpu-overdrive.conf";
+const KERNEL_CMDLINE_PATH: &str = "/proc/cmdline";
 
 pub async fn info() -> anyhow::Result<SystemInfo> {
     let version = env!("CARGO_PKG_VERSION").to_owned();
@@ -36,18 +38,19 @@ pub async fn info() -> anyhow::Result<SystemInfo> {
         .trim()
         .to_owned();
 
-    let amdgpu_overdrive_enabled = if let Ok(mask) = read_current_mask() {
-        Some((mask & PP_OVERDRIVE_MASK) > 0)
-    } else {
-        None
-    };
+    let current_mask = read_current_mask().ok();
+    let amdgpu_overdrive_enabled = current_mask.map(|mask| (mask & PP_OVERDRIVE_MASK) > 0);
+    let amdgpu_overdrive_reboot_required = OC_TOGGLED.load(Ordering::SeqCst)
+        || current_mask.is_some_and(|mask| is_overdrive_change_pending(mask, read_boot_mask()));
 
     Ok(SystemInfo {
         version,
         profile,
         kernel_version,
         amdgpu_overdrive_enabled,
+        amdgpu_overdrive_reboot_required,
         commit: Some(GIT_COMMIT.to_owned()),
+        protocol_version: Some(PROTOCOL_VERSION),
     })
 }
 
@@ -59,6 +62,19 @@ pub async fn enable_overdrive() -> anyhow::Result<String> {
 
     let current_mask = read_current_mask()?;
 
+    // Options on the kernel command line take priority over the modprobe config
+    if let Some(cmdline_mask) = read_cmdline_mask() {
+        ensure!(
+            cmdline_mask & PP_OVERDRIVE_MASK == 0,
+            "Overdrive is already enabled through the kernel command line, please reboot to apply it"
+        );
+        return Err(anyhow!(
+            "The feature mask is set on the kernel command line, which overrides {MODULE_CONF_PATH}. \
+            Change the kernel argument in your bootloader config to {PP_FEATURE_MASK_CMDLINE_ARG}=0x{:X} and reboot",
+            cmdline_mask | PP_OVERDRIVE_MASK
+        ));
+    }
+
     let new_mask = current_mask | PP_OVERDRIVE_MASK;
     if new_mask == current_mask {
         return Err(anyhow!("Overdrive mask already enabled"));
@@ -76,9 +92,11 @@ pub async fn enable_overdrive() -> anyhow::Result<String> {
     let message = match regenerate_initramfs().await {
         Ok(initramfs_type) => {
             OC_TOGGLED.store(true, Ordering::SeqCst);
-            format!("Initramfs was successfully regenerated (detected type {initramfs_type:?})")
+            format!("Initramfs was successfully regenerated (detected type {initramfs_type:?}). A reboot is required to apply the changes")
         }
-        Err(err) => format!("{err:#}"),
+        Err(err) => format!(
+            "{err:#}\nAlternatively, add the kernel argument {PP_FEATURE_MASK_CMDLINE_ARG}=0x{new_mask:X} in your bootloader config and reboot"
+        ),
     };
 
     Ok(message)
@@ -92,15 +110,19 @@ pub async fn disable_overdrive() -> anyhow::Result<String> {
 
     if Path::new(MODULE_CONF_PATH).exists() {
         fs::remove_file(MODULE_CONF_PATH).context("Could not remove module config file")?;
-        match regenerate_initramfs().await {
+        let mut message = match regenerate_initramfs().await {
             Ok(initramfs_type) => {
                 OC_TOGGLED.store(true, Ordering::SeqCst);
-                Ok(format!(
-                    "Initramfs was successfully regenerated (detected type {initramfs_type:?})"
-                ))
+                format!("Initramfs was successfully regenerated (detected type {initramfs_type:?}). A reboot is required to apply the changes")
             }
-            Err(err) => Ok(format!("{err:#}")),
+            Err(err) => format!("{err:#}"),
+        };
+        if read_cmdline_mask().is_some_and(|mask| mask & PP_OVERDRIVE_MASK != 0) {
+            message.push_str(&format!(
+                "\nOverdrive is also enabled through the kernel command line, remove the {PP_FEATURE_MASK_CMDLINE_ARG} argument from your bootloader config to disable it"
+            ));
         }
+        Ok(message)
     } else {
         Err(anyhow!(
             "Overclocking was not enabled through LACT (file at {MODULE_CONF_PATH} does not exist)"
@@ -118,6 +140,63 @@ fn read_current_mask() -> anyhow::Result<u64> {
     u64::from_str_radix(ppfeaturemask, 16).context("Invalid ppfeaturemask")
 }
 
+/// Mask that will be used on the next boot, if it is configured explicitly
+fn read_boot_mask() -> Option<u64> {
+    read_cmdline_mask().or_else(|| {
+        let conf = fs::read_to_string(MODULE_CONF_PATH).ok()?;
+        parse_module_conf_mask(&conf)
+    })
+}
+
+fn read_cmdline_mask() -> Option<u64> {
+    let cmdline = fs::read_to_string(KERNEL_CMDLINE_PATH).ok()?;
+    parse_cmdline_mask(&cmdline)
+}
+
+fn parse_cmdline_mask(cmdline: &str) -> Option<u64> {
+    // The last occurrence of an argument wins
+    cmdline
+        .split_whitespace()
+        .filter_map(|arg| {
+            arg.strip_prefix(PP_FEATURE_MASK_CMDLINE_ARG)?
+                .strip_prefix('=')
+        })
+        .last()
+        .and_then(parse_mask)
+}
+
+fn parse_module_conf_mask(conf: &str) -> Option<u64> {
+    conf.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            if parts.next() != Some("options") || parts.next() != Some("amdgpu") {
+                return None;
+            }
+            parts
+                .filter_map(|option| option.strip_prefix("ppfeaturemask="))
+                .last()
+        })
+        .last()
+        .and_then(parse_mask)
+}
+
+/// Parses a mask the way the kernel does for module parameters, which accepts both hex and decimal values
+fn parse_mask(value: &str) -> Option<u64> {
+    let value = value.trim_matches('"');
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Whether the overdrive bit of the mask configured for the next boot differs from the running one
+fn is_overdrive_change_pending(current_mask: u64, boot_mask: Option<u64>) -> bool {
+    boot_mask.is_some_and(|boot_mask| (boot_mask ^ current_mask) & PP_OVERDRIVE_MASK != 0)
+}
+
 async fn regenerate_initramfs() -> anyhow::Result<InitramfsType> {
     let os_release = OS_RELEASE.as_ref().context("Could not detect distro")?;
     match detect_initramfs_type(os_release).await {
@@ -192,7 +271,10 @@ async fn run_command(exec: &str, args: &[&str]) -> anyhow::Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use crate::server::system::detect_initramfs_type;
+    use crate::server::system::{
+        detect_initramfs_type, is_overdrive_change_pending, parse_cmdline_mask,
+        parse_module_conf_mask,
+    };
     use lact_schema::InitramfsType;
     use os_release::OsRelease;
 
@@ -236,4 +318,36 @@ UBUNTU_CODENAME=jammy
             detect_initramfs_type(&os_release).await
         );
     }
+
+    #[test]
+    fn parse_feature_masks() {
+        assert_eq!(
+            Some(0xFFF7_FFFF),
+            parse_cmdline_mask(
+                "BOOT_IMAGE=/vmlinuz-linux root=UUID=1234 rw amdgpu.ppfeaturemask=0xfff7ffff quiet"
+            )
+        );
+        assert_eq!(
+            Some(16384),
+            parse_cmdline_mask("amdgpu.ppfeaturemask=0x1 amdgpu.ppfeaturemask=16384")
+        );
+        assert_eq!(None, parse_cmdline_mask("root=UUID=1234 amdgpu.dc=1"));
+
+        assert_eq!(
+            Some(0xFFFF_FFFF),
+            parse_module_conf_mask("# overdrive\noptions amdgpu ppfeaturemask=0xFFFFFFFF")
+        );
+        assert_eq!(
+            None,
+            parse_module_conf_mask("options radeon ppfeaturemask=0x4000")
+        );
+    }
+
+    #[test]
+    fn overdrive_change_pending() {
+        assert!(is_overdrive_change_pending(0xFFF7_BFFF, Some(0xFFF7_FFFF)));
+        assert!(is_overdrive_change_pending(0xFFF7_FFFF, Some(0xFFF7_BFFF)));
+        assert!(!is_overdrive_change_pending(0xFFF7_FFFF, Some(0xFFFF_FFFF)));
+        assert!(!is_overdrive_change_pending(0xFFF7_BFFF, None));
+    }
 }
@@ -0,0 +1,125 @@
+use lact_schema::SensorChip;
+use std::{collections::BTreeMap, fs, path::Path};
+
+pub const HWMON_ROOT: &str = "/sys/class/hwmon";
+
+/// Reads the temperatures and fan speeds of the hwmon chips with the given names,
+/// such as `k10temp` for AMD CPUs, motherboard Super I/O chips or `nvme` drives.
+/// Chips that are present more than once (e.g. several drives) are all included.
+pub fn read_sensor_chips(hwmon_root: &Path, names: &[String]) -> Vec<SensorChip> {
+    if names.is_empty() {
+        return vec![];
+    }
+
+    let Ok(entries) = fs::read_dir(hwmon_root) else {
+        return vec![];
+    };
+    let mut chips: Vec<SensorChip> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = read_trimmed(&path.join("name"))?;
+            if !names.contains(&name) {
+                return None;
+            }
+
+            Some(SensorChip {
+                name,
+                hwmon: entry.file_name().to_string_lossy().into_owned(),
+                temperatures: read_channels(&path, "temp", |value| {
+                    value
+                        .parse::<f32>()
+                        .ok()
+                        .map(|millidegrees| millidegrees / 1000.0)
+                }),
+                fans: read_channels(&path, "fan", |value| value.parse().ok()),
+            })
+        })
+        .collect();
+
+    // Keep the order of the config, with the hwmon index as a stable tiebreaker
+    chips.sort_by_key(|chip| {
+        let position = names.iter().position(|name| *name == chip.name);
+        let index: u32 = chip
+            .hwmon
+            .trim_start_matches("hwmon")
+            .parse()
+            .unwrap_or(u32::MAX);
+        (position, index)
+    });
+    chips
+}
+
+/// Reads `{prefix}N_input` values keyed by their `{prefix}N_label`, or `{prefix}N` when they have no label
+fn read_channels<T>(
+    path: &Path,
+    prefix: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> BTreeMap<String, T> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return BTreeMap::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let channel = file_name
+                .to_str()?
+                .strip_prefix(prefix)?
+                .strip_suffix("_input")?;
+            channel.parse::<u32>().ok()?;
+
+            let value = parse(&read_trimmed(&entry.path())?)?;
+            let label = read_trimmed(&path.join(format!("{prefix}{channel}_label")))
+                .unwrap_or_else(|| format!("{prefix}{channel}"));
+            Some((label, value))
+        })
+        .collect()
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_sensor_chips;
+    use crate::tests::TempDir;
+    use std::fs;
+
+    #[test]
+    fn read_whitelisted_chips() {
+        let root = TempDir::new("system-sensors");
+        let write = |file: &str, contents: &str| {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        write("hwmon0/name", "amdgpu\n");
+        write("hwmon0/temp1_input", "50000\n");
+        write("hwmon3/name", "k10temp\n");
+        write("hwmon3/temp1_input", "45500\n");
+        write("hwmon3/temp1_label", "Tctl\n");
+        write("hwmon3/temp3_input", "40000\n");
+        write("hwmon4/name", "nct6798\n");
+        write("hwmon4/fan2_input", "1200\n");
+        write("hwmon4/fan2_label", "CHA_FAN1\n");
+        write("hwmon4/fan2_min", "0\n");
+
+        let names = ["nct6798".to_owned(), "k10temp".to_owned()];
+        let chips = read_sensor_chips(&root, &names);
+
+        assert_eq!(chips.len(), 2);
+        assert_eq!(chips[0].name, "nct6798");
+        assert_eq!(chips[0].hwmon, "hwmon4");
+        assert_eq!(chips[0].fans.get("CHA_FAN1"), Some(&1200));
+        assert!(chips[0].temperatures.is_empty());
+        assert_eq!(chips[1].temperatures.get("Tctl"), Some(&45.5));
+        assert_eq!(chips[1].temperatures.get("temp3"), Some(&40.0));
+
+        assert!(read_sensor_chips(&root, &[]).is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+use super::handler::Handler;
+use std::{collections::HashSet, time::Duration};
+use tokio::time::interval;
+use tracing::{debug, error, info};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How far below the target the temperature has to be before the power cap is raised again
+const HYSTERESIS: f32 = 3.0;
+/// Smallest power cap change in watts, larger steps are used when the GPU is far above the target
+const MIN_STEP: f64 = 5.0;
+/// Additional watts removed per degree above the target
+const STEP_PER_DEGREE: f64 = 2.0;
+/// Sensors the target applies to, in order of preference
+const TARGET_SENSORS: [&str; 2] = ["junction", "edge"];
+
+/// Adjusts the power cap of GPUs that have a thermal target so that they stay at or below it.
+/// The power cap of a running application, the configured one or the maximum one is used as the upper limit.
+pub async fn run(handler: Handler) {
+    let mut interval = interval(POLL_INTERVAL);
+    // GPUs whose power cap was changed by this task, to restore it when the target gets removed
+    let mut managed = HashSet::new();
+
+    loop {
+        interval.tick().await;
+
+        let config = handler.config.borrow();
+        let Ok(gpus) = config.gpus() else {
+            continue;
+        };
+
+        for (id, controller) in handler.controllers().iter() {
+            let gpu_config = gpus.get(id);
+            let app_cap = handler.app_power_caps.borrow().get(id).copied();
+            let Some(hw_mon) = controller.hw_monitors().first() else {
+                continue;
+            };
+
+            let Some(target) = gpu_config.and_then(|gpu| gpu.thermal_target) else {
+                if managed.remove(id) {
+                    let cap = app_cap
+                        .or_else(|| gpu_config.and_then(|gpu| gpu.power_cap))
+                        .or_else(|| hw_mon.get_power_cap_default().ok());
+                    if let Some(cap) = cap {
+                        info!("thermal target removed, restoring power cap of {id} to {cap}W");
+                        if let Err(err) = hw_mon.set_power_cap(cap) {
+                            error!("could not restore power cap: {err}");
+                        }
+                    }
+                }
+                continue;
+            };
+
+            let temps = hw_mon.get_temps();
+            let Some(temp) = TARGET_SENSORS
+                .iter()
+                .find_map(|sensor| temps.get(*sensor)?.current)
+            else {
+                continue;
+            };
+
+            let (Ok(current_cap), Ok(min_cap), Ok(max_cap)) = (
+                hw_mon.get_power_cap(),
+                hw_mon.get_power_cap_min(),
+                hw_mon.get_power_cap_max(),
+            ) else {
+                continue;
+            };
+            let upper_cap = app_cap
+                .or_else(|| gpu_config.and_then(|gpu| gpu.power_cap))
+                .unwrap_or(max_cap)
+                .min(max_cap);
+
+            let new_cap = next_power_cap(current_cap, temp, target, min_cap, upper_cap);
+            if (new_cap - current_cap).abs() >= 1.0 {
+                debug!("{id} is at {temp}°C with a target of {target}°C, changing power cap from {current_cap}W to {new_cap}W");
+                match hw_mon.set_power_cap(new_cap) {
+                    Ok(()) => {
+                        managed.insert(id.clone());
+                    }
+                    Err(err) => error!("could not set power cap of {id}: {err}"),
+                }
+            }
+        }
+    }
+}
+
+fn next_power_cap(current_cap: f64, temp: f32, target: f32, min_cap: f64, max_cap: f64) -> f64 {
+    let cap = if temp > target {
+        current_cap - (f64::from(temp - target) * STEP_PER_DEGREE).max(MIN_STEP)
+    } else if temp < target - HYSTERESIS {
+        current_cap + MIN_STEP
+    } else {
+        current_cap
+    };
+    cap.clamp(min_cap, max_cap.max(min_cap)).round()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::next_power_cap;
+
+    #[test]
+    fn power_cap_follows_temperature() {
+        // Far above the target, larger steps are taken
+        assert_eq!(next_power_cap(250.0, 100.0, 85.0, 100.0, 300.0), 220.0);
+        assert_eq!(next_power_cap(250.0, 86.0, 85.0, 100.0, 300.0), 245.0);
+        // Within the hysteresis range nothing changes
+        assert_eq!(next_power_cap(250.0, 83.0, 85.0, 100.0, 300.0), 250.0);
+        assert_eq!(next_power_cap(250.0, 70.0, 85.0, 100.0, 300.0), 255.0);
+        // The limits are respected
+        assert_eq!(next_power_cap(250.0, 70.0, 85.0, 100.0, 250.0), 250.0);
+        assert_eq!(next_power_cap(102.0, 95.0, 85.0, 100.0, 300.0), 100.0);
+    }
+}
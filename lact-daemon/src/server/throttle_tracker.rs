@@ -0,0 +1,100 @@
+use super::{handler::Handler, sampling::SampleSchedule};
+use lact_schema::{ThrottleEvents, ThrottleStatus};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tokio::time::sleep_until;
+use tracing::debug;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Counts how often each kind of throttling starts, per GPU
+#[derive(Default)]
+pub struct ThrottleTracker {
+    gpus: RefCell<HashMap<String, (ThrottleStatus, ThrottleEvents)>>,
+}
+
+impl ThrottleTracker {
+    /// Records the current status, counting the kinds of throttling that were not active previously
+    pub fn update(&self, id: &str, status: ThrottleStatus) -> ThrottleEvents {
+        let mut gpus = self.gpus.borrow_mut();
+        let (last_status, events) = gpus.entry(id.to_owned()).or_default();
+
+        let count = |active: bool, was_active: bool, counter: &mut u64, name: &str| {
+            if active && !was_active {
+                debug!("{name} throttling started on {id}");
+                *counter += 1;
+            }
+        };
+        count(status.power, last_status.power, &mut events.power, "power");
+        count(
+            status.thermal,
+            last_status.thermal,
+            &mut events.thermal,
+            "thermal",
+        );
+        count(
+            status.current,
+            last_status.current,
+            &mut events.current,
+            "current",
+        );
+        count(status.other, last_status.other, &mut events.other, "other");
+
+        *last_status = status;
+        *events
+    }
+}
+
+/// Polls the throttle status of every GPU, so that short throttling periods between stats requests are counted too
+pub async fn run(handler: Handler) {
+    let mut schedule = SampleSchedule::new(POLL_INTERVAL);
+
+    loop {
+        let now = Instant::now();
+        for id in schedule.due(&handler, now) {
+            let Some(controller) = handler.controller(&id) else {
+                continue;
+            };
+            if let Some(status) = controller.get_throttle_status() {
+                handler.throttle_tracker.update(&id, status);
+            }
+        }
+
+        sleep_until(schedule.next_wakeup(now).into()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ThrottleTracker;
+    use lact_schema::ThrottleStatus;
+
+    #[test]
+    fn counts_throttling_starts() {
+        let tracker = ThrottleTracker::default();
+        let power = ThrottleStatus {
+            power: true,
+            ..Default::default()
+        };
+
+        tracker.update("gpu", power);
+        tracker.update("gpu", power);
+        tracker.update("gpu", ThrottleStatus::default());
+        let events = tracker.update(
+            "gpu",
+            ThrottleStatus {
+                power: true,
+                thermal: true,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(events.power, 2);
+        assert_eq!(events.thermal, 1);
+        assert_eq!(events.current, 0);
+        assert_eq!(tracker.update("other", power).power, 1);
+    }
+}
@@ -0,0 +1,331 @@
+//! Assistant that finds the lowest stable voltage offset of a GPU.
+//! The offset is lowered step by step while a stress command runs. A step fails when the command exits with an error,
+//! or when the kernel log shows that the GPU hung and had to be reset.
+use super::handler::Handler;
+use anyhow::{bail, Context};
+use lact_schema::{
+    request::{ConfirmCommand, SetClocksCommand},
+    UndervoltOptions, UndervoltState, UndervoltStatus,
+};
+use nix::{
+    sys::signal::{killpg, Signal},
+    unistd::Pid,
+};
+use std::{cell::RefCell, collections::HashMap, process::Stdio, rc::Rc, time::Duration};
+use tokio::{process::Command, select, sync::Notify, time::sleep};
+use tracing::{error, info};
+
+/// Kernel log messages (in lowercase) about the GPU hanging or being reset
+const GPU_FAULT_PATTERNS: &[&str] = &[
+    "gpu reset",
+    "gpu recovery",
+    "job_timedout",
+    "timeout, signaled seq",
+];
+/// Shortest time in seconds that the stress command has to run at every offset
+const MIN_STEP_DURATION: u64 = 10;
+
+/// State of the undervolting runs of every GPU
+#[derive(Default)]
+pub struct UndervoltAssistant {
+    runs: RefCell<HashMap<String, UndervoltRun>>,
+}
+
+struct UndervoltRun {
+    status: UndervoltStatus,
+    cancel: Rc<Notify>,
+}
+
+impl UndervoltAssistant {
+    pub fn status(&self, id: &str) -> UndervoltStatus {
+        self.runs
+            .borrow()
+            .get(id)
+            .map(|run| run.status.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn is_running(&self, id: &str) -> bool {
+        matches!(self.status(id).state, UndervoltState::Running { .. })
+    }
+
+    pub fn cancel(&self, id: &str) -> anyhow::Result<()> {
+        match self.runs.borrow().get(id) {
+            Some(run) if matches!(run.status.state, UndervoltState::Running { .. }) => {
+                run.cancel.notify_one();
+                Ok(())
+            }
+            _ => bail!("The undervolting assistant is not running on this GPU"),
+        }
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut UndervoltStatus)) {
+        if let Some(run) = self.runs.borrow_mut().get_mut(id) {
+            f(&mut run.status);
+        }
+    }
+
+    fn log(&self, id: &str, message: String) {
+        info!("undervolting {id}: {message}");
+        self.update(id, |status| status.log.push(message));
+    }
+}
+
+enum StepResult {
+    Stable,
+    Unstable(String),
+    Cancelled,
+}
+
+/// Starts searching for the lowest stable voltage offset in the background.
+/// The search starts below the currently configured offset.
+pub fn start(handler: &Handler, id: &str, options: UndervoltOptions) -> anyhow::Result<()> {
+    if options.stress_command.trim().is_empty() {
+        bail!("A stress test command is required");
+    }
+    if options.step <= 0 {
+        bail!("The offset step has to be above 0 mV");
+    }
+    if options.step_duration < MIN_STEP_DURATION {
+        bail!("Every step has to run for at least {MIN_STEP_DURATION} seconds");
+    }
+
+    let controller = handler
+        .controller(id)
+        .context("No controller with such id")?;
+    if !controller.get_capabilities().voltage_offset {
+        bail!("This GPU does not support a voltage offset");
+    }
+    if handler.undervolt.is_running(id) {
+        bail!("The undervolting assistant is already running on this GPU");
+    }
+    if handler.profile_comparison.is_running(id) {
+        bail!("A profile comparison is running on this GPU");
+    }
+    if handler.is_config_pending() {
+        bail!("There is an unconfirmed configuration change pending");
+    }
+
+    let start_offset = handler
+        .config
+        .borrow()
+        .gpus()?
+        .get(id)
+        .and_then(|gpu| gpu.clocks_configuration.voltage_offset)
+        .unwrap_or(0);
+    if options.min_offset >= start_offset {
+        bail!("The lowest offset has to be below the current offset of {start_offset} mV");
+    }
+    handler.check_clocks_limits(id, &[SetClocksCommand::VoltageOffset(options.min_offset)])?;
+
+    let cancel = Rc::new(Notify::new());
+    handler.undervolt.runs.borrow_mut().insert(
+        id.to_owned(),
+        UndervoltRun {
+            status: UndervoltStatus {
+                state: UndervoltState::Running {
+                    offset: start_offset,
+                },
+                ..Default::default()
+            },
+            cancel: cancel.clone(),
+        },
+    );
+
+    let handler = handler.clone();
+    let id = id.to_owned();
+    tokio::task::spawn_local(async move {
+        let state = match search(&handler, &id, &options, start_offset, &cancel).await {
+            Ok(state) => state,
+            Err(err) => {
+                error!("undervolting {id} failed: {err:#}");
+                UndervoltState::Failed {
+                    error: format!("{err:#}"),
+                }
+            }
+        };
+        handler.undervolt.update(&id, |status| status.state = state);
+    });
+
+    Ok(())
+}
+
+async fn search(
+    handler: &Handler,
+    id: &str,
+    options: &UndervoltOptions,
+    start_offset: i32,
+    cancel: &Notify,
+) -> anyhow::Result<UndervoltState> {
+    let controller = handler.controller(id).context("The GPU was removed")?;
+    let base_config = handler
+        .config
+        .borrow()
+        .gpus()?
+        .get(id)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut stable_offset = None;
+    let mut cancelled = false;
+    let mut offset = start_offset - options.step;
+
+    while offset >= options.min_offset {
+        handler.undervolt.update(id, |status| {
+            status.state = UndervoltState::Running { offset }
+        });
+
+        // Test offsets are only applied to the GPU, not saved to the config
+        let mut test_config = base_config.clone();
+        test_config.apply_clocks_command(&SetClocksCommand::VoltageOffset(offset));
+        if let Err(err) = controller.apply_config(&test_config).await {
+            handler
+                .undervolt
+                .log(id, format!("{offset} mV could not be applied: {err:#}"));
+            break;
+        }
+
+        match run_step(id, options, offset, cancel).await? {
+            StepResult::Stable => {
+                handler.undervolt.log(id, format!("{offset} mV is stable"));
+                stable_offset = Some(offset);
+                handler
+                    .undervolt
+                    .update(id, |status| status.stable_offset = stable_offset);
+            }
+            StepResult::Unstable(reason) => {
+                handler
+                    .undervolt
+                    .log(id, format!("{offset} mV is unstable: {reason}"));
+                break;
+            }
+            StepResult::Cancelled => {
+                handler
+                    .undervolt
+                    .log(id, format!("cancelled while testing {offset} mV"));
+                cancelled = true;
+                break;
+            }
+        }
+
+        offset -= options.step;
+    }
+
+    // The GPU may still be running at an unstable offset
+    controller
+        .apply_config(&base_config)
+        .await
+        .context("Could not restore the previous settings")?;
+
+    if cancelled {
+        return Ok(UndervoltState::Cancelled);
+    }
+
+    if let Some(stable_offset) = stable_offset {
+        handler
+            .set_clocks_value(id, SetClocksCommand::VoltageOffset(stable_offset))
+            .await?;
+        handler.confirm_pending_config(ConfirmCommand::Confirm)?;
+        handler.undervolt.log(
+            id,
+            format!("applied the lowest stable offset of {stable_offset} mV"),
+        );
+    }
+
+    Ok(UndervoltState::Finished { stable_offset })
+}
+
+/// Runs the stress command at the current offset
+async fn run_step(
+    id: &str,
+    options: &UndervoltOptions,
+    offset: i32,
+    cancel: &Notify,
+) -> anyhow::Result<StepResult> {
+    let faults_before = count_gpu_faults().await?;
+
+    // The command gets its own process group, so that everything it started can be stopped together
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(&options.stress_command)
+        .env("LACT_GPU_ID", id)
+        .env("LACT_VOLTAGE_OFFSET", offset.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .kill_on_drop(true)
+        .spawn()
+        .context("Could not run the stress command")?;
+    let process_group = child.id();
+
+    let result = select! {
+        status = child.wait() => {
+            let status = status.context("Could not wait for the stress command")?;
+            if status.success() {
+                StepResult::Stable
+            } else {
+                StepResult::Unstable(format!("the stress command exited with {status}"))
+            }
+        }
+        () = sleep(Duration::from_secs(options.step_duration)) => StepResult::Stable,
+        () = cancel.notified() => StepResult::Cancelled,
+    };
+
+    if let Some(pgid) = process_group.and_then(|pid| i32::try_from(pid).ok()) {
+        // Fails when everything has already exited
+        let _ = killpg(Pid::from_raw(pgid), Signal::SIGKILL);
+    }
+    let _ = child.wait().await;
+
+    if !matches!(result, StepResult::Cancelled) && count_gpu_faults().await? > faults_before {
+        return Ok(StepResult::Unstable("the GPU was reset".to_owned()));
+    }
+    Ok(result)
+}
+
+async fn count_gpu_faults() -> anyhow::Result<usize> {
+    let output = Command::new("dmesg")
+        .output()
+        .await
+        .context("Could not read the kernel log")?;
+    if !output.status.success() {
+        bail!(
+            "Could not read the kernel log: dmesg exited with {}",
+            output.status
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| is_gpu_fault(line))
+        .count())
+}
+
+fn is_gpu_fault(line: &str) -> bool {
+    let line = line.to_lowercase();
+    GPU_FAULT_PATTERNS
+        .iter()
+        .any(|pattern| line.contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_gpu_fault;
+
+    #[test]
+    fn detect_gpu_faults() {
+        assert!(is_gpu_fault("[ 1234.5678] [drm:amdgpu_job_timedout [amdgpu]] *ERROR* ring gfx_0.0.0 timeout, signaled seq=1234, emitted seq=1236"));
+        assert!(is_gpu_fault(
+            "[ 1234.6789] amdgpu 0000:03:00.0: amdgpu: GPU reset begin!"
+        ));
+        assert!(is_gpu_fault(
+            "[ 1240.1234] amdgpu 0000:03:00.0: amdgpu: GPU reset(2) succeeded!"
+        ));
+        assert!(!is_gpu_fault(
+            "[    3.1234] amdgpu 0000:03:00.0: amdgpu: SMU is initialized successfully!"
+        ));
+        assert!(!is_gpu_fault(
+            "[    4.5678] [drm] Initialized amdgpu 3.57.0 20150101 for 0000:03:00.0 on minor 1"
+        ));
+    }
+}
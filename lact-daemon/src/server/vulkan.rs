@@ -1,17 +1,43 @@
-use anyhow::{anyhow, Context};
+use anyhow::Context;
 use lact_schema::{VulkanDriverInfo, VulkanInfo};
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::Mutex};
 use tracing::trace;
 use vulkano::{
     instance::{Instance, InstanceCreateInfo},
     VulkanLibrary,
 };
 
+/// Devices keyed by (vendor id, device id).
+/// Creating a vulkan instance is slow and blocks all other requests, so it is only done once.
+/// Errors are not cached, as the driver may become available later (e.g. after a module is loaded).
+static VULKAN_DEVICES: Mutex<Option<Vec<((u32, u32), VulkanInfo)>>> = Mutex::new(None);
+
 pub fn get_vulkan_info<'a>(vendor_id: &'a str, device_id: &'a str) -> anyhow::Result<VulkanInfo> {
-    trace!("Reading vulkan info");
     let vendor_id = u32::from_str_radix(vendor_id, 16)?;
     let device_id = u32::from_str_radix(device_id, 16)?;
 
+    let mut cached_devices = VULKAN_DEVICES.lock().unwrap();
+    let devices = match &mut *cached_devices {
+        Some(devices) => devices,
+        cache => cache.insert(load_vulkan_devices()?),
+    };
+
+    // Not sure how this works with systems that have multiple identical GPUs
+    devices
+        .iter()
+        .find(|(ids, _)| *ids == (vendor_id, device_id))
+        .map(|(_, info)| info.clone())
+        .context("Could not find a vulkan device with matching pci ids")
+}
+
+/// Makes the next info request enumerate the vulkan devices again, used when the list of GPUs changes
+pub fn clear_vulkan_cache() {
+    *VULKAN_DEVICES.lock().unwrap() = None;
+}
+
+fn load_vulkan_devices() -> anyhow::Result<Vec<((u32, u32), VulkanInfo)>> {
+    trace!("Reading vulkan info");
+
     let library = VulkanLibrary::new().context("Could not create vulkan library")?;
     let instance = Instance::new(library, InstanceCreateInfo::default())
         .context("Could not create vulkan instance")?;
@@ -20,11 +46,9 @@ pub fn get_vulkan_info<'a>(vendor_id: &'a str, device_id: &'a str) -> anyhow::Re
         .enumerate_physical_devices()
         .context("Could not enumerate vulkan devices")?;
 
-    for device in devices {
-        let properties = device.properties();
-
-        // Not sure how this works with systems that have multiple identical GPUs
-        if (properties.vendor_id, properties.device_id) == (vendor_id, device_id) {
+    let infos = devices
+        .map(|device| {
+            let properties = device.properties();
             let info = VulkanInfo {
                 device_name: properties.device_name.clone(),
                 api_version: device.api_version().to_string(),
@@ -44,13 +68,11 @@ pub fn get_vulkan_info<'a>(vendor_id: &'a str, device_id: &'a str) -> anyhow::Re
                     .into_iter()
                     .map(|(name, enabled)| (Cow::Borrowed(name), enabled))
                     .collect(),
-                enabled_layers,
+                enabled_layers: enabled_layers.clone(),
             };
-            return Ok(info);
-        }
-    }
+            ((properties.vendor_id, properties.device_id), info)
+        })
+        .collect();
 
-    Err(anyhow!(
-        "Could not find a vulkan device with matching pci ids"
-    ))
+    Ok(infos)
 }
@@ -0,0 +1,129 @@
+//! Web interface for managing GPUs from a browser, for systems without a desktop.
+//! The page sends the same JSON requests as the socket API to [`API_PATH`],
+//! authenticated with the `web_auth_token` from the config as a bearer token.
+use super::{constant_time_eq, handle_request, handler::Handler, http};
+use anyhow::{anyhow, bail, Context};
+use lact_schema::{Request, Response};
+use tokio::{
+    io::BufReader,
+    net::{TcpListener, TcpStream},
+};
+use tracing::{debug, error, info};
+
+const INDEX_PAGE: &str = include_str!("index.html");
+const API_PATH: &str = "/api";
+
+pub async fn listen(address: &str, handler: Handler) -> anyhow::Result<()> {
+    if handler.config.borrow().daemon.web_auth_token.is_none() {
+        bail!("The web interface is not started, as it requires `web_auth_token` to be set");
+    }
+
+    let listener = TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Could not bind web interface listener to {address}"))?;
+    info!(
+        "serving the web interface on http://{}",
+        listener.local_addr()?
+    );
+
+    tokio::task::spawn_local(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let handler = handler.clone();
+                    tokio::task::spawn_local(async move {
+                        if let Err(err) = handle_connection(stream, &handler).await {
+                            debug!("web request failed: {err:#}");
+                        }
+                    });
+                }
+                Err(err) => error!("failed to accept web connection: {err}"),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+async fn handle_connection(stream: TcpStream, handler: &Handler) -> anyhow::Result<()> {
+    let mut stream = BufReader::new(stream);
+    let request = match http::read_request(&mut stream).await {
+        Ok(request) => request,
+        Err(err) => {
+            http::reject(stream.get_mut(), &err).await?;
+            return Err(err);
+        }
+    };
+    let path = request
+        .path
+        .split_once('?')
+        .map_or(request.path.as_str(), |(path, _)| path);
+
+    let (status, content_type, body) = match (request.method.as_str(), path) {
+        ("GET", "/") => (
+            "200 OK",
+            "text/html; charset=utf-8",
+            INDEX_PAGE.as_bytes().to_vec(),
+        ),
+        ("POST", API_PATH) => {
+            let expected_token = handler.config.borrow().daemon.web_auth_token.clone();
+            let (status, body) =
+                if is_authorized(request.header("Authorization"), expected_token.as_deref()) {
+                    ("200 OK", api_response(&request.body, handler).await?)
+                } else {
+                    (
+                        "401 Unauthorized",
+                        serde_json::to_vec(&Response::<()>::from(anyhow!(
+                            "Invalid authentication token"
+                        )))?,
+                    )
+                };
+            (status, "application/json", body)
+        }
+        _ => ("404 Not Found", "text/plain", b"Not found\n".to_vec()),
+    };
+
+    http::write_response(stream.get_mut(), status, content_type, &body).await
+}
+
+async fn api_response(body: &[u8], handler: &Handler) -> anyhow::Result<Vec<u8>> {
+    let result = match serde_json::from_slice::<Request>(body) {
+        Ok(Request::SubscribeStats { .. } | Request::SubscribeDeviceEvents) => Err(anyhow!(
+            "Subscriptions are not available in the web interface, the stats have to be polled"
+        )),
+        Ok(request) => handle_request(request, handler).await,
+        Err(err) => Err(anyhow::Error::new(err).context("Failed to deserialize request")),
+    };
+
+    match result {
+        Ok(response) => Ok(response),
+        Err(err) => Ok(serde_json::to_vec(&Response::<()>::from(err))?),
+    }
+}
+
+/// Checks the `Authorization: Bearer <token>` header. Requests are rejected when no token is configured.
+fn is_authorized(header: Option<&str>, expected_token: Option<&str>) -> bool {
+    match (
+        header.and_then(|value| value.strip_prefix("Bearer ")),
+        expected_token,
+    ) {
+        (Some(token), Some(expected)) => {
+            constant_time_eq(token.trim().as_bytes(), expected.as_bytes())
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_authorized;
+
+    #[test]
+    fn check_authorization() {
+        assert!(is_authorized(Some("Bearer secret"), Some("secret")));
+        assert!(!is_authorized(Some("Bearer wrong"), Some("secret")));
+        assert!(!is_authorized(Some("secret"), Some("secret")));
+        assert!(!is_authorized(None, Some("secret")));
+        assert!(!is_authorized(Some("Bearer secret"), None));
+    }
+}
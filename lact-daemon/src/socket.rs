@@ -1,33 +1,89 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, bail, Context};
+use lact_schema::{DEFAULT_SOCKET_PATH, SOCKET_PATH_ENV};
 use nix::{
     sys::stat::{umask, Mode},
-    unistd::{chown, getuid, Gid, Group},
+    unistd::{chown, getuid, Gid, Group, Uid},
+};
+use std::{
+    env,
+    ffi::OsStr,
+    fs::{self, Permissions},
+    os::{
+        fd::{FromRawFd, RawFd},
+        unix::fs::{MetadataExt, PermissionsExt},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
 };
-use std::{fs, path::PathBuf, str::FromStr};
 use tokio::net::UnixListener;
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn};
+
+/// Read and write access for the owner and the admin group only
+const SOCKET_MODE: u32 = 0o660;
+/// Read and write access for everyone, with changes from outside of the admin group authorized through polkit
+const POLKIT_SOCKET_MODE: u32 = 0o666;
+/// First file descriptor passed by systemd, see `sd_listen_fds(3)`
+const SD_LISTEN_FDS_START: RawFd = 3;
+
+/// The socket of systemd socket activation is owned by systemd, so it must not be removed
+static SOCKET_ACTIVATED: AtomicBool = AtomicBool::new(false);
+/// Socket created by the daemon, which is removed on exit
+static SOCKET_PATH: OnceLock<PathBuf> = OnceLock::new();
 
-pub fn get_socket_path() -> PathBuf {
-    let uid = getuid();
-    if uid.is_root() {
-        PathBuf::from_str("/var/run/lactd.sock").unwrap()
+/// The socket path from the command line takes precedence over [`SOCKET_PATH_ENV`], which takes precedence over the config
+pub fn get_socket_path(arg: Option<&Path>, configured: Option<&Path>) -> PathBuf {
+    resolve_socket_path(
+        arg,
+        env::var_os(SOCKET_PATH_ENV).as_deref(),
+        configured,
+        getuid(),
+    )
+}
+
+fn resolve_socket_path(
+    arg: Option<&Path>,
+    env_value: Option<&OsStr>,
+    configured: Option<&Path>,
+    uid: Uid,
+) -> PathBuf {
+    if let Some(path) = arg
+        .or(env_value.filter(|value| !value.is_empty()).map(Path::new))
+        .or(configured)
+    {
+        path.to_owned()
+    } else if uid.is_root() {
+        PathBuf::from(DEFAULT_SOCKET_PATH)
     } else {
-        PathBuf::from_str(&format!("/var/run/user/{uid}/lactd.sock")).unwrap()
+        PathBuf::from(format!("/run/user/{uid}/lactd.sock"))
     }
 }
 
 pub fn cleanup() {
-    let socket_path = get_socket_path();
+    if SOCKET_ACTIVATED.load(Ordering::Relaxed) {
+        return;
+    }
+    let Some(socket_path) = SOCKET_PATH.get() else {
+        return;
+    };
 
     if socket_path.exists() {
-        fs::remove_file(socket_path).expect("failed to remove socket");
+        // Runs while shutting down, so a failure must not prevent the rest of the cleanup
+        if let Err(err) = fs::remove_file(socket_path) {
+            error!("could not remove socket {socket_path:?}: {err}");
+            return;
+        }
     }
     debug!("removed socket");
 }
 
-pub fn listen(admin_groups: &[String]) -> anyhow::Result<UnixListener> {
-    let socket_path = get_socket_path();
-
+pub fn listen(
+    socket_path: PathBuf,
+    admin_groups: &[String],
+    polkit: bool,
+) -> anyhow::Result<UnixListener> {
     if socket_path.exists() {
         return Err(anyhow!(
             "Socket {socket_path:?} already exists. \
@@ -36,32 +92,178 @@ pub fn listen(admin_groups: &[String]) -> anyhow::Result<UnixListener> {
         ));
     }
 
-    let socket_mask = Mode::S_IXUSR | Mode::S_IXGRP | Mode::S_IRWXO;
-    umask(socket_mask);
+    if let Some(parent) = socket_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Could not create the socket directory {parent:?}"))?;
+    }
+
+    set_umask();
 
-    let listener = UnixListener::bind(&socket_path)?;
+    let listener = UnixListener::bind(&socket_path)
+        .with_context(|| format!("Could not listen on {socket_path:?}"))?;
 
-    chown(&socket_path, None, Some(socket_gid(admin_groups)))?;
+    let gid = socket_gid(admin_groups);
+    chown(&socket_path, None, Some(gid))
+        .with_context(|| format!("Could not change the group of {socket_path:?}"))?;
+    set_mode(&socket_path, polkit)?;
 
     info!("listening on {socket_path:?}");
+    let _ = SOCKET_PATH.set(socket_path);
     Ok(listener)
 }
 
-fn socket_gid(admin_groups: &[String]) -> Gid {
-    if getuid().is_root() {
-        // Check if the group exists
-        for group_name in admin_groups {
-            if let Ok(Some(group)) = Group::from_name(group_name) {
-                return group.gid;
+/// Takes the socket passed by systemd when the daemon was started through socket activation
+pub fn listen_activated(polkit: bool) -> anyhow::Result<Option<UnixListener>> {
+    let fd_count = activated_fd_count(
+        env::var("LISTEN_PID").ok().as_deref(),
+        env::var("LISTEN_FDS").ok().as_deref(),
+        std::process::id(),
+    );
+    // The variables only apply to this process, not to the ones it spawns
+    for var in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        env::remove_var(var);
+    }
+
+    match fd_count {
+        0 => Ok(None),
+        1 => {
+            set_umask();
+
+            // SAFETY: systemd passes the listening socket as the first file descriptor, which is not used by anything else
+            let listener =
+                unsafe { std::os::unix::net::UnixListener::from_raw_fd(SD_LISTEN_FDS_START) };
+            listener
+                .set_nonblocking(true)
+                .context("Could not configure the socket passed by systemd")?;
+            let listener = UnixListener::from_std(listener)
+                .context("Could not use the socket passed by systemd")?;
+
+            SOCKET_ACTIVATED.store(true, Ordering::Relaxed);
+            if polkit {
+                set_mode(&listener_path(&listener)?, polkit)?;
             }
+            info!("listening on the socket passed by systemd");
+            Ok(Some(listener))
         }
+        count => bail!("Expected a single socket from systemd, got {count}"),
+    }
+}
+
+/// Group of the socket, whose members can change settings without polkit authorization
+pub fn socket_group(listener: &UnixListener) -> anyhow::Result<Gid> {
+    let path = listener_path(listener)?;
+    let metadata =
+        fs::metadata(&path).with_context(|| format!("Could not read the info of {path:?}"))?;
+    Ok(Gid::from_raw(metadata.gid()))
+}
+
+fn listener_path(listener: &UnixListener) -> anyhow::Result<PathBuf> {
+    listener
+        .local_addr()?
+        .as_pathname()
+        .map(PathBuf::from)
+        .context("The socket has no path")
+}
 
-        if let Ok(Some(group)) = Group::from_gid(Gid::from_raw(1000)) {
-            group.gid
-        } else {
-            Gid::current()
+fn set_mode(socket_path: &Path, polkit: bool) -> anyhow::Result<()> {
+    let mode = if polkit {
+        POLKIT_SOCKET_MODE
+    } else {
+        SOCKET_MODE
+    };
+    fs::set_permissions(socket_path, Permissions::from_mode(mode))
+        .with_context(|| format!("Could not set permissions of {socket_path:?}"))
+}
+
+/// Amount of sockets passed through socket activation, which only applies when it was meant for this process
+fn activated_fd_count(listen_pid: Option<&str>, listen_fds: Option<&str>, pid: u32) -> usize {
+    let listen_pid = listen_pid.and_then(|listen_pid| listen_pid.parse::<u32>().ok());
+    match (listen_pid, listen_fds.and_then(|fds| fds.parse().ok())) {
+        (Some(listen_pid), Some(count)) if listen_pid == pid => count,
+        _ => 0,
+    }
+}
+
+fn set_umask() {
+    // Also keeps the files created by the daemon later on (like the config, which can contain the TCP auth token) private
+    let socket_mask = Mode::S_IXUSR | Mode::S_IXGRP | Mode::S_IRWXO;
+    umask(socket_mask);
+}
+
+fn socket_gid(admin_groups: &[String]) -> Gid {
+    if !getuid().is_root() {
+        return Gid::current();
+    }
+
+    for group_name in admin_groups {
+        if let Ok(Some(group)) = Group::from_name(group_name) {
+            info!("socket access is given to the {group_name} group");
+            return group.gid;
         }
+    }
+
+    // Usually the primary group of the first user on single-user systems
+    if let Ok(Some(group)) = Group::from_gid(Gid::from_raw(1000)) {
+        warn!(
+            "none of the admin groups ({}) exist, giving socket access to the {} group",
+            admin_groups.join(", "),
+            group.name
+        );
+        group.gid
     } else {
+        warn!(
+            "none of the admin groups ({}) exist, only root can access the socket",
+            admin_groups.join(", ")
+        );
         Gid::current()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{activated_fd_count, resolve_socket_path};
+    use nix::unistd::Uid;
+    use std::{ffi::OsStr, path::Path};
+
+    #[test]
+    fn activation_only_for_own_pid() {
+        assert_eq!(activated_fd_count(Some("42"), Some("1"), 42), 1);
+        assert_eq!(activated_fd_count(Some("41"), Some("1"), 42), 0);
+        assert_eq!(activated_fd_count(None, Some("1"), 42), 0);
+        assert_eq!(activated_fd_count(Some("42"), None, 42), 0);
+        assert_eq!(activated_fd_count(Some("42"), Some("invalid"), 42), 0);
+    }
+
+    #[test]
+    fn socket_path_precedence() {
+        let arg = Path::new("/tmp/arg.sock");
+        let env = OsStr::new("/tmp/env.sock");
+        let configured = Path::new("/tmp/config.sock");
+
+        assert_eq!(
+            resolve_socket_path(Some(arg), Some(env), Some(configured), Uid::from_raw(0)),
+            arg
+        );
+        assert_eq!(
+            resolve_socket_path(None, Some(env), Some(configured), Uid::from_raw(0)),
+            Path::new(env)
+        );
+        assert_eq!(
+            resolve_socket_path(
+                None,
+                Some(OsStr::new("")),
+                Some(configured),
+                Uid::from_raw(0)
+            ),
+            configured
+        );
+        assert_eq!(
+            resolve_socket_path(None, None, None, Uid::from_raw(0)),
+            Path::new("/run/lactd.sock")
+        );
+        assert_eq!(
+            resolve_socket_path(None, None, None, Uid::from_raw(1000)),
+            Path::new("/run/user/1000/lactd.sock")
+        );
+    }
+}
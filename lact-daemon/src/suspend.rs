@@ -1,17 +1,29 @@
 use crate::server::handler::Handler;
 use futures::StreamExt;
-use tracing::{error, info};
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{debug, error, info, warn};
 use zbus::{Connection, Proxy};
 
+const RESUME_APPLY_ATTEMPTS: u32 = 3;
+const RESUME_RETRY_DELAY: Duration = Duration::from_secs(2);
+
 pub async fn listen_events(handler: Handler) {
     match connect_proxy().await {
-        // Note: despite the name, the events get triggered both on suspend and resume
         Ok(proxy) => match proxy.receive_signal("PrepareForSleep").await {
             Ok(mut stream) => {
-                while stream.next().await.is_some() {
-                    info!("suspend/resume event detected, reloading config");
-                    if let Err(err) = handler.apply_current_config().await {
-                        error!("could not reapply config: {err:#}");
+                while let Some(message) = stream.next().await {
+                    // The signal is sent with `true` before suspending and with `false` after resuming
+                    match message.body().deserialize::<bool>() {
+                        Ok(true) => debug!("system is going to sleep"),
+                        Ok(false) => {
+                            info!("resume detected, reapplying config");
+                            reapply_config(&handler).await;
+                        }
+                        Err(err) => {
+                            warn!("could not parse sleep signal ({err}), reapplying config");
+                            reapply_config(&handler).await;
+                        }
                     }
                 }
             }
@@ -24,6 +36,25 @@ pub async fn listen_events(handler: Handler) {
     error!("suspend/resume events will not be handled.");
 }
 
+/// The GPU might not be fully initialized right after resuming, so applying the settings is retried
+async fn reapply_config(handler: &Handler) {
+    for attempt in 1..=RESUME_APPLY_ATTEMPTS {
+        match handler.apply_current_config().await {
+            Ok(()) => {
+                info!("config reapplied after resume");
+                return;
+            }
+            Err(err) if attempt < RESUME_APPLY_ATTEMPTS => {
+                warn!(
+                    "could not reapply config (attempt {attempt}/{RESUME_APPLY_ATTEMPTS}): {err:#}"
+                );
+                sleep(RESUME_RETRY_DELAY).await;
+            }
+            Err(err) => error!("could not reapply config: {err:#}"),
+        }
+    }
+}
+
 async fn connect_proxy() -> anyhow::Result<Proxy<'static>> {
     let conn = Box::pin(Connection::system()).await?;
     let proxy = Proxy::new_owned(
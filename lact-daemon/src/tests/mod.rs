@@ -0,0 +1,347 @@
+//! Tests of the GPU controllers against fake sysfs trees, filled with data recorded from real GPUs.
+//! Each file in `data` lists the files of a tree as a `== <path>` line followed by the contents of the file.
+//! The daemon can be pointed at such a tree with `lact daemon --test-sysfs <dir>`.
+use crate::{
+    config::{self, Config, FanControlSettings, Gpu},
+    server::{
+        gpu_controller::GpuController,
+        handler::{load_controllers, Handler},
+        rate_limit::MIN_APPLY_INTERVAL_MILLIS,
+    },
+};
+use lact_schema::{
+    request::ConfirmCommand, ClocksControlKind, ClocksTable, FanControlMode, FanOptions,
+};
+use notify::{
+    event::{AccessKind, AccessMode},
+    EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{task::LocalSet, time::sleep};
+
+const POLARIS: &str = include_str!("data/polaris.sysfs");
+const VEGA: &str = include_str!("data/vega.sysfs");
+const NAVI10: &str = include_str!("data/navi10.sysfs");
+const NAVI31: &str = include_str!("data/navi31.sysfs");
+
+static NEXT_TEMP_DIR: AtomicU32 = AtomicU32::new(0);
+
+/// An empty temporary directory, which is removed when dropped so failing tests don't leave it behind
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// The name only makes the directory recognizable, every directory gets its own path as tests run in parallel
+    pub fn new(name: &str) -> Self {
+        let index = NEXT_TEMP_DIR.fetch_add(1, Ordering::Relaxed);
+        let path = env::temp_dir().join(format!("lact-{name}-{}-{index}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// A sysfs tree in a temporary directory, which is removed when dropped
+struct FakeSysfs {
+    root: TempDir,
+}
+
+impl FakeSysfs {
+    fn new(name: &str, data: &str) -> Self {
+        let root = TempDir::new(&format!("sysfs-{name}"));
+
+        for (path, contents) in parse_tree(data) {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        Self { root }
+    }
+
+    fn read(&self, path: &str) -> String {
+        fs::read_to_string(self.root.join(path)).unwrap()
+    }
+
+    fn controllers(&self) -> BTreeMap<String, Rc<dyn GpuController>> {
+        load_controllers(&self.root).unwrap()
+    }
+
+    fn controller(&self) -> Rc<dyn GpuController> {
+        let controllers = self.controllers();
+        assert_eq!(controllers.len(), 1);
+        controllers.into_values().next().unwrap()
+    }
+}
+
+/// Splits the data of a tree into the relative paths and contents of its files, lines before the first file are comments
+fn parse_tree(data: &str) -> Vec<(&str, String)> {
+    let mut files: Vec<(&str, String)> = vec![];
+    for line in data.lines() {
+        if let Some(path) = line.strip_prefix("== ") {
+            files.push((path.trim(), String::new()));
+        } else if let Some((_, contents)) = files.last_mut() {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+    }
+    files
+}
+
+fn static_fan_config(static_speed: f64) -> Gpu {
+    Gpu {
+        fan_control_enabled: true,
+        fan_control_settings: Some(FanControlSettings {
+            mode: FanControlMode::Static,
+            static_speed,
+            ..FanControlSettings::default()
+        }),
+        ..Gpu::default()
+    }
+}
+
+#[test]
+fn gpu_ids() {
+    for (name, data, id) in [
+        ("ids-polaris", POLARIS, "1002:67DF-1DA2:E387-0000:01:00.0"),
+        ("ids-vega", VEGA, "1002:687F-1002:0B36-0000:03:00.0"),
+        ("ids-navi10", NAVI10, "1002:731F-1DA2:E411-0000:0b:00.0"),
+        ("ids-navi31", NAVI31, "1002:744C-1DA2:471E-0000:03:00.0"),
+    ] {
+        let sysfs = FakeSysfs::new(name, data);
+        // Connectors and render nodes in `class/drm` are not GPUs
+        let ids: Vec<String> = sysfs.controllers().into_keys().collect();
+        assert_eq!(ids, [id], "{name}");
+    }
+}
+
+#[test]
+fn polaris_stats() {
+    let sysfs = FakeSysfs::new("stats-polaris", POLARIS);
+    let stats = sysfs.controller().get_stats(None);
+
+    assert_eq!(stats.temps["edge"].current, Some(45.0));
+    assert_eq!(stats.fan.speed_current, Some(1012));
+    assert_eq!(stats.fan.speed_max, Some(3200));
+    assert_eq!(stats.fan.pwm_current, Some(80));
+    assert_eq!(stats.power.average, Some(34.0));
+    assert_eq!(stats.power.cap_current, Some(145.0));
+    assert_eq!(stats.power.cap_max, Some(175.0));
+    assert_eq!(stats.vram.total, Some(8_589_934_592));
+    assert_eq!(stats.busy_percent, Some(0));
+    assert_eq!(stats.core_power_state, Some(0));
+    assert_eq!(stats.pcie_power_state, Some(1));
+    assert_eq!(stats.voltage.gpu, Some(750));
+    assert_eq!(stats.voltage.northbridge, None);
+}
+
+#[test]
+fn navi31_stats() {
+    let sysfs = FakeSysfs::new("stats-navi31", NAVI31);
+    let stats = sysfs.controller().get_stats(None);
+
+    let mut sensors: Vec<&str> = stats.temps.keys().map(String::as_str).collect();
+    sensors.sort_unstable();
+    assert_eq!(sensors, ["edge", "junction", "mem"]);
+    assert_eq!(stats.temps["junction"].current, Some(49.0));
+    assert_eq!(stats.power.current, Some(28.0));
+    assert_eq!(stats.power.average, None);
+    assert_eq!(stats.power.cap_min, Some(305.0));
+    assert_eq!(stats.memory_power_state, Some(0));
+    assert_eq!(stats.voltage.gpu, Some(31));
+}
+
+#[test]
+fn voltage_channel_labels() {
+    // Files listed again replace the recorded ones, here the GPU voltage is moved to the second channel like on APUs
+    let hwmon = "class/drm/card0/device/hwmon/hwmon1";
+    let data = format!(
+        "{NAVI10}== {hwmon}/in0_input\n900\n== {hwmon}/in0_label\nvddnb\n\
+         == {hwmon}/in1_input\n1100\n== {hwmon}/in1_label\nvddgfx\n"
+    );
+    let sysfs = FakeSysfs::new("voltage-labels", &data);
+    let stats = sysfs.controller().get_stats(None);
+
+    assert_eq!(stats.voltage.gpu, Some(1100));
+    assert_eq!(stats.voltage.northbridge, Some(900));
+}
+
+#[test]
+fn clocks_tables() {
+    for (name, data, control) in [
+        ("clocks-polaris", POLARIS, ClocksControlKind::PowerStates),
+        ("clocks-vega", VEGA, ClocksControlKind::PowerStates),
+        ("clocks-navi10", NAVI10, ClocksControlKind::Range),
+        ("clocks-navi31", NAVI31, ClocksControlKind::Range),
+    ] {
+        let sysfs = FakeSysfs::new(name, data);
+        let controller = sysfs.controller();
+
+        let clocks_info = controller.get_clocks_info().unwrap();
+        assert!(
+            matches!(clocks_info.table, Some(ClocksTable::Amd(_))),
+            "{name}"
+        );
+        assert!(clocks_info.max_sclk.is_some(), "{name}");
+
+        let capabilities = controller.get_capabilities();
+        assert!(capabilities.overdrive, "{name}");
+        assert_eq!(capabilities.clocks_control, Some(control), "{name}");
+    }
+}
+
+#[test]
+fn fan_control_capabilities() {
+    let polaris = FakeSysfs::new("fan-caps-polaris", POLARIS);
+    let capabilities = polaris.controller().get_capabilities();
+    assert!(capabilities.pwm_fan_control);
+    assert!(!capabilities.pmfw_fan_control);
+    assert_eq!(capabilities.power_cap_range, Some((0.0, 175.0)));
+
+    let navi31 = FakeSysfs::new("fan-caps-navi31", NAVI31);
+    assert!(navi31.controller().get_capabilities().pmfw_fan_control);
+}
+
+#[tokio::test]
+async fn polaris_static_fan_speed() {
+    let sysfs = FakeSysfs::new("static-fan-polaris", POLARIS);
+    let controller = sysfs.controller();
+
+    controller
+        .apply_config(&static_fan_config(0.5))
+        .await
+        .unwrap();
+
+    let hwmon = "class/drm/card0/device/hwmon/hwmon0";
+    assert_eq!(sysfs.read(&format!("{hwmon}/pwm1_enable")).trim(), "1");
+    assert_eq!(sysfs.read(&format!("{hwmon}/pwm1")).trim(), "127");
+    // The power cap is already at the default, so it's not written again
+    assert_eq!(
+        sysfs.read(&format!("{hwmon}/power1_cap")).trim(),
+        "145000000"
+    );
+}
+
+#[tokio::test]
+async fn navi10_low_latency() {
+    let sysfs = FakeSysfs::new("low-latency-navi10", NAVI10);
+    let controller = sysfs.controller();
+    assert!(controller.get_capabilities().low_latency_mode);
+
+    let config = Gpu {
+        low_latency: true,
+        ..Gpu::default()
+    };
+    controller.apply_config(&config).await.unwrap();
+
+    let device = "class/drm/card0/device";
+    assert_eq!(
+        sysfs
+            .read(&format!("{device}/power_dpm_force_performance_level"))
+            .trim(),
+        "manual"
+    );
+    // DS_GFXCLK and DS_UCLK are turned off
+    assert_eq!(
+        sysfs.read(&format!("{device}/pp_features")).trim(),
+        "0x140fb"
+    );
+}
+
+#[tokio::test]
+async fn rapid_fan_changes_coalesced() {
+    let sysfs = FakeSysfs::new("rate-limit-polaris", POLARIS);
+    // Confirmed changes are saved, which goes to a directory of the test process
+    let config_dir = config::get_path().parent().unwrap().to_owned();
+    fs::create_dir_all(&config_dir).unwrap();
+
+    let (event_tx, event_rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = RecommendedWatcher::new(event_tx, notify::Config::default()).unwrap();
+    watcher
+        .watch(
+            &sysfs.root.join("class/drm/card0/device/hwmon/hwmon0"),
+            RecursiveMode::NonRecursive,
+        )
+        .unwrap();
+
+    let elapsed = LocalSet::new()
+        .run_until(async {
+            let handler = Handler::new(Config::default(), Some(sysfs.root.to_path_buf()))
+                .await
+                .unwrap();
+            let id = handler.controllers()[0].0.clone();
+            let start = Instant::now();
+
+            // A slider sending a new value every 10ms for a second
+            for i in 0..100_u32 {
+                handler
+                    .set_fan_control(FanOptions {
+                        id: &id,
+                        enabled: true,
+                        mode: Some(FanControlMode::Static),
+                        static_speed: Some(f64::from(i) / 100.0),
+                        ..FanOptions::default()
+                    })
+                    .await
+                    .unwrap();
+                handler
+                    .confirm_pending_config(ConfirmCommand::Confirm)
+                    .unwrap();
+                sleep(Duration::from_millis(10)).await;
+            }
+            // The last deferred change is written once the interval has passed
+            sleep(Duration::from_millis(MIN_APPLY_INTERVAL_MILLIS * 2)).await;
+            start.elapsed()
+        })
+        .await;
+
+    let pwm_writes = event_rx
+        .try_iter()
+        .filter_map(Result::ok)
+        .filter(|event| {
+            event.kind == EventKind::Access(AccessKind::Close(AccessMode::Write))
+                && event.paths.iter().any(|path| path.ends_with("pwm1"))
+        })
+        .count();
+    let _ = fs::remove_dir_all(config_dir);
+
+    // Writes are at least the interval apart, plus the one that happened right away
+    let max_writes =
+        usize::try_from(elapsed.as_millis() / u128::from(MIN_APPLY_INTERVAL_MILLIS)).unwrap() + 1;
+    assert!(
+        (2..=max_writes).contains(&pwm_writes),
+        "{pwm_writes} writes in {}ms",
+        elapsed.as_millis()
+    );
+    assert_eq!(
+        sysfs
+            .read("class/drm/card0/device/hwmon/hwmon0/pwm1")
+            .trim(),
+        "252"
+    );
+}
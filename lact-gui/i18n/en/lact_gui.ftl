@@ -0,0 +1,426 @@
+## Common
+
+ok = OK
+cancel = Cancel
+close = Close
+save = Save
+import = Import
+apply = Apply
+revert = Revert
+reset = Reset
+remove = Remove
+create = Create
+default = Default
+unknown = Unknown
+error = Error
+enabled = Enabled
+disabled = Disabled
+no = No
+all = All
+
+## Pages
+
+info-page = Information
+oc-page = OC
+thermals-page = Thermals
+processes-page = Processes
+sensors-page = Sensors
+energy-page = Energy
+software-page = Software
+overview-page = Overview
+
+## Daemon connection
+
+connection-lost = Connection Lost
+daemon-connection-lost = Daemon connection lost, reconnecting...
+daemon-info = Daemon info
+embedded-mode-info =
+    Could not connect to daemon, running in embedded mode.
+    Please make sure the lactd service is running.
+    Using embedded mode, you will not be able to change any settings.
+
+    Error info: { $error }
+
+    To enable the daemon, run the following command:
+protocol-version-mismatch = Protocol version mismatch between GUI and daemon ({ $gui_version } vs { $daemon_version })! Make sure you have restarted the service if you have updated LACT.
+version-mismatch = Version mismatch between GUI and daemon ({ $gui_version } vs { $daemon_version })! Make sure you have restarted the service if you have updated LACT.
+
+## Overclocking support (AMD)
+
+enable-amd-oc = Enable Overclocking
+enable-amd-oc-description = This will enable the overdrive feature of the amdgpu driver by creating a file at <b>{ $path }</b> and updating the initramfs. Are you sure you want to do this?
+disable-amd-oc = Disable Overclocking
+disable-amd-oc-description = This will disable overclocking support on next reboot.
+amd-oc-disabled = Overclocking Disabled
+amd-oc-disabled-description = Changing clocks and voltages requires the overdrive feature of the amdgpu driver. Do you want to enable it? This will create a file at <b>{ $path }</b> and update the initramfs.
+amd-oc-enabled-message =
+    Overclocking enabled.
+    System message:
+amd-oc-disabled-message =
+    Overclocking disabled.
+    System message:
+amd-oc-info = Overclock info
+regenerating-initramfs = Regenerating initramfs (this may take a while)
+
+## Settings
+
+confirm-settings = Confirm settings
+confirm-settings-description = Do you want to keep the new settings? (Reverting in { $seconds_left } seconds)
+reset-config = Reset configuration
+reset-config-description = Are you sure you want to reset all GPU configuration?
+reset-config-failed =
+    Some GPUs could not be reset:
+    { $gpus }
+export-config = Export configuration
+import-config = Import configuration
+config-imported-title = Configuration imported
+config-imported = The configuration has been imported.
+config-imported-matched-gpu = Settings for { $imported_id } were applied to { $id }, which is the same model.
+config-imported-skipped-gpus =
+    Settings for these GPUs were skipped, as no matching GPU was found:
+    { $gpus }
+invalid-file-path = Selected file has an invalid path
+
+## Tools
+
+save-vbios-file = Save VBIOS file
+export-graphs = Export graphs
+snapshot-generated = Snapshot generated
+snapshot-saved-at = Debug snapshot saved at:
+snapshot-saved-at-path = Debug snapshot saved at <b>{ $path }</b>
+temperature-alert = GPU temperature alert
+temperature-alert-description = The { $sensor } temperature is { $temperature }°C, above the alert threshold of { $threshold }°C
+fan-stall-alert = GPU fan failure
+fan-stall-alert-description = A fan is not spinning while being driven at { $speed }%, it may have failed
+
+## Header
+
+gpu = GPU
+gpu-selector-label = GPU { $index } | { $profile }
+settings-profile = Settings Profile
+schedule-profile = Schedule the profile
+redo-change = Reapply the last undone change
+undo-change = Undo the last applied change
+show-historical-charts = Show historical charts
+undervolt-assistant = Undervolting assistant
+compare-profiles = Compare profiles
+generate-debug-snapshot = Generate debug snapshot
+dump-vbios = Dump VBIOS
+disable-amd-oc-support = Disable overclocking support
+reset-all-config = Reset all configuration
+unapplied-changes = Unapplied changes
+unapplied-changes-description = The settings of { $gpu } were changed, but not applied. Discard the changes and switch GPUs?
+
+## Profiles
+
+name = Name
+create-profile = Create Profile
+base-profile = Base profile:
+profile-base-empty = Empty
+delete-profile = Delete profile
+delete-profile-description = Are you sure you want to delete profile "{ $name }"
+profile-schedule-title = Schedule of { $name }
+profile-schedule-time = Activate the profile between (HH:MM):
+profile-schedule-and = and
+profile-schedule-days = Starting on (every day when none are selected):
+profile-schedule-auto-switch-note = Automatic profile switching has to be enabled for schedules to be used.
+monday-short = Mon
+tuesday-short = Tue
+wednesday-short = Wed
+thursday-short = Thu
+friday-short = Fri
+saturday-short = Sat
+sunday-short = Sun
+
+## Undervolting assistant
+
+start = Start
+stress-test-command = Stress test command
+undervolt-description = The voltage offset is lowered step by step while the stress test command runs. A step passes when the command keeps running (or exits successfully) and the GPU is not reset. The lowest stable offset is applied at the end.
+undervolt-warning = Unstable offsets can crash applications or the whole system, save your work first.
+undervolt-step = Step (mV):
+undervolt-lowest-offset = Lowest offset (mV):
+undervolt-step-duration = Duration per step (s):
+undervolt-none-yet = none yet
+undervolt-idle = Not started
+undervolt-running = Testing { $offset } mV, lowest stable offset: { $stable }
+undervolt-finished = Finished, applied the lowest stable offset of { $offset } mV
+undervolt-finished-none = Finished, no lower offset was stable
+undervolt-cancelled = Cancelled, lowest stable offset: { $stable }
+undervolt-failed = Failed: { $error }
+
+## Profile comparison
+
+compare-profiles-description = The settings of both profiles are applied in turn, without switching the active profile, and the average power usage, temperatures and clocks are recorded with each of them. Keep the same workload, such as a game or a benchmark, running the whole time. The previous settings are restored at the end.
+compare-profiles-first = First profile:
+compare-profiles-second = Second profile:
+compare-profiles-duration = Recording time per profile (s):
+compare-profiles-settle-time = Settling time per profile (s):
+compare-profiles-idle = Not started
+compare-profiles-settling = Waiting for the stats of { $profile } to settle
+compare-profiles-recording = Recording { $profile }
+compare-profiles-finished = Finished, the previous settings were restored
+compare-profiles-cancelled = Cancelled, the previous settings were restored
+compare-profiles-failed = Failed: { $error }
+compare-profiles-difference = Difference
+compare-profiles-power = Power usage (W)
+compare-profiles-gpu-clock = GPU clock (MHz)
+compare-profiles-vram-clock = VRAM clock (MHz)
+compare-profiles-fan-speed = Fan speed (RPM)
+compare-profiles-gpu-usage = GPU usage (%)
+compare-profiles-temperature = { $sensor } temperature (°C)
+
+## Tray icon
+
+tray-open = Open LACT
+tray-quit = Quit
+
+## Information page
+
+hardware-info = Hardware Information
+gpu-model = GPU Model:
+card-manufacturer = Card Manufacturer:
+card-model = Card Model:
+card-model-info = The card displayed here may be of a sibling model, e.g. XT vs XTX variety. This is normal, as such models often use the same device ID, and it is not possible to differentiate between them.
+gpu-family = GPU Family:
+asic-name = ASIC Name:
+compute-units = Compute Units:
+cuda-cores = Cuda Cores:
+vbios-version = VBIOS Version:
+driver-used = Driver Used:
+vram-size = VRAM Size:
+vram-type = VRAM Type:
+peak-vram-bandwidth = Peak VRAM Bandwidth:
+l1-cache = L1 Cache (Per CU):
+l2-cache = L2 Cache:
+l3-cache = L3 Cache:
+rebar = Resizeable BAR:
+cpu-accessible-vram = CPU Accessible VRAM:
+link-speed = Link Speed:
+link-generation = gen { $gen } of { $max_gen }
+vulkan-info = Vulkan Information
+vulkan-unavailable = Vulkan is not available on this GPU
+device-name = Device name
+vulkan-version = Vulkan version:
+driver-name = Driver name:
+driver-version = Driver version:
+features = Features:
+extensions = Extensions:
+show = Show
+vulkan-features = Vulkan features
+vulkan-extensions = Vulkan extensions
+
+## Overview page
+
+configure = Configure
+
+## Processes page
+
+no-processes = No processes are using this GPU
+process-pid = PID
+process-name = Name
+process-vram = VRAM
+process-gtt = GTT
+process-engine-usage = Engine usage
+
+## Sensors page
+
+no-sensor-chips = No sensor chips are shown. Add the names of hwmon chips such as `k10temp` or `nct6798` to `sensor_chips` in the daemon config to show their temperatures and fan speeds here.
+
+## Energy page
+
+energy-usage = Energy usage
+energy-last-days = Last days
+energy-today = Today
+energy-last-n-days = Last { $days } days
+electricity-price = Electricity price
+price-per-kwh = per kWh in
+currency = Currency
+energy-description = Energy is calculated from the reported power usage, which may not include the whole board on some GPUs. A price of 0 hides the costs.
+
+## Software page
+
+daemon-version = LACT Daemon:
+gui-version = LACT GUI:
+kernel-version = Kernel Version:
+
+## Overclocking page
+
+oc-information = Overclocking information
+oc-disabled =
+    Overclocking support is not enabled! You can still change basic settings, but the more advanced clocks and voltage control will not be available.
+oc-reboot-required = Overclocking support has been enabled, but a system reboot is required to apply it.
+enable-oc = Enable Overclocking
+
+statistics = Statistics
+vram-usage = VRAM Usage:
+gtt-usage = GTT Usage:
+gtt-usage-description = System memory used by the GPU, which happens when the VRAM is full
+gpu-core-clock-average = GPU Core Clock (Average):
+gpu-core-clock-target = GPU Core Clock (Target):
+gpu-voltage = GPU Voltage:
+gpu-temperature = GPU Temperature (hotspot):
+gpu-memory-clock = GPU Memory Clock:
+gpu-usage = GPU Usage:
+power-usage = Power Usage:
+throttling = Throttling:
+throttling-active = { $kinds } throttling active
+throttle-events = power limit reached { $power } times, thermal { $thermal } times
+
+power-usage-limit = Power usage limit
+
+performance = Performance
+performance-level = Performance level:
+performance-level-auto = Automatic
+performance-level-high = Highest Clocks
+performance-level-low = Lowest Clocks
+performance-level-manual = Manual
+performance-level-auto-description = Automatically adjust GPU and VRAM clocks. (Default)
+performance-level-high-description = Always use the highest clockspeeds for GPU and VRAM.
+performance-level-low-description = Always use the lowest clockspeeds for GPU and VRAM.
+performance-level-manual-description = Manual performance control.
+low-latency-mode = Low latency mode
+low-latency-mode-description = Keeps the GPU, VRAM and PCIe link at their highest speeds and turns off deep sleep, to avoid the delay of clocking up. This overrides the performance level and power states, and increases idle power usage.
+min-vram-state = Minimum VRAM clock
+min-vram-state-description = Keeps the VRAM clock from dropping below the selected state, which commonly fixes flickering with multiple or high refresh rate monitors. This switches the performance level to manual and increases idle power usage.
+min-vram-state-any = Automatic
+power-level-mode = Power level mode:
+manual-level-needed = Performance level has to be set to "manual" to use power states and modes
+runtime-pm = Runtime Power Management
+runtime-pm-status = Status:
+runtime-pm-active = Powered on
+runtime-pm-suspended = Powered off
+runtime-pm-suspended-time = Time powered off:
+runtime-pm-processes = Used by:
+runtime-pm-no-processes = No processes
+runtime-pm-last-wake = Last woke up:
+runtime-pm-auto = Allow powering off
+runtime-pm-auto-description = Lets the GPU power off completely while no process uses it, which saves battery on laptops with a dedicated GPU. Programs that briefly open the GPU, such as monitoring tools, wake it up again.
+
+clocks-and-voltage = Clockspeed and voltage
+clocks-warning = Warning: changing these values may lead to system instability and potentially damage your hardware!
+configuration-mode = Configuration mode:
+basic = Basic
+advanced = Advanced
+min-gpu-clock = Minimum GPU Clock (MHz)
+min-vram-clock = Minimum VRAM Clock (MHz)
+min-gpu-voltage = Minimum GPU voltage (mV)
+gpu-clock-offset = GPU Clock offset (MHz)
+max-gpu-clock = Maximum GPU Clock (MHz)
+max-gpu-voltage = Maximum GPU voltage (mV)
+max-vram-clock = Maximum VRAM Clock (MHz)
+min-fabric-clock = Minimum Fabric Clock (MHz)
+max-fabric-clock = Maximum Fabric Clock (MHz)
+min-soc-clock = Minimum SoC Clock (MHz)
+max-soc-clock = Maximum SoC Clock (MHz)
+gpu-voltage-offset = GPU voltage offset (mV)
+boost-gpu-clock = Boost GPU Clock (MHz)
+curve-point-clock = Curve point { $index } GPU Clock (MHz)
+curve-point-voltage = Curve point { $index } voltage (mV)
+reset-clocks-tooltip = Warning: this resets all clock settings to defaults!
+no-clocks-data = No clocks data available
+
+power-states = Power states
+gpu-power-states = GPU power states
+vram-power-states = VRAM power states
+
+## Thermals page
+
+temperatures = Temperatures:
+fan-speed = Fan speed:
+no-sensors = No sensors found
+no-fan = No fan detected
+fan = Fan { $channel }
+fan-stalled = stalled!
+fan-control = Fan control
+fan-control-automatic = Automatic
+fan-control-curve = Curve
+fan-control-static = Static
+fan-control-reboot-warning = Warning! Due to a driver bug, a reboot may be required for fan control to properly switch back to automatic.
+pmfw-warning = Warning: Overclocking support is disabled, fan control functionality is not available.
+static-speed = Speed (in %)
+
+target-temperature = Target temperature (°C)
+acoustic-limit = Acoustic limit (RPM)
+acoustic-target = Acoustic target (RPM)
+minimum-fan-speed = Minimum fan speed (%)
+zero-rpm = Zero RPM
+zero-rpm-temperature = Zero RPM stop temperature (°C)
+reset-pmfw-tooltip = Warning: this resets the fan firmware settings!
+reset-fan-control-tooltip = Return the fans to automatic control, keeping the clock and power settings
+
+fan-curve = Fan Curve
+fan-speed-percent = Fan speed (%)
+fan-speed-rpm = Fan speed (RPM)
+temperature-celsius = Temperature (°C)
+power-draw-watts = Power draw (W)
+composite-input = Temperature and power draw ({ $power_weight }% power)
+control-by-rpm = Control by RPM
+control-by-rpm-description = Set the fan to a target speed in RPM instead of a PWM percentage
+snap-to-grid = Snap to grid
+fan-curve-editor-hint = Drag the points to change the curve, double click to add a point and right click a point to remove it
+spindown-delay = Spindown delay
+spindown-delay-description = How long the GPU needs to remain at a lower temperature point for before ramping down the fan
+speed-change-threshold = Speed change threshold
+hysteresis = Hysteresis
+max-speed-change = Maximum speed change
+max-speed-change-description = How fast the fan speed is allowed to change, 0 means no limit
+preview = Preview
+fan-curve-preview = Fan Curve Preview
+fan-curve-preview-description = Replays the temperatures recorded in the stats history with this curve, using the applied spindown delay, speed change threshold and maximum speed change
+fan-curve-preview-temperature = Temperature
+fan-curve-preview-speed = Fan speed
+fan-curve-preview-summary = Highest temperature: { $max_temperature }°C, highest fan speed: { $max_speed }%, average fan speed: { $average_speed }%
+fan-curve-preview-empty = The stats history has no temperature samples yet
+
+## Historical charts
+
+historical-data = Historical data
+time-range = Time range:
+time-range-minutes =
+    { $minutes ->
+        [one] { $minutes } minute
+       *[other] { $minutes } minutes
+    }
+time-range-hours =
+    { $hours ->
+        [one] { $hours } hour
+       *[other] { $hours } hours
+    }
+reset-zoom = Reset zoom
+reset-zoom-description = Scroll over a graph to zoom and drag it to move through time
+export-csv = Export CSV
+export-png = Export PNG
+graph-style = Style
+line-style = Line style
+line-style-solid = Solid
+line-style-dashed = Dashed
+line-style-dotted = Dotted
+line-width = Line width
+series-colors = Series colors
+accent-color = Accent color, also used for the fan curve
+series-color = Color of series { $index }
+reset-graph-style = Reset style
+
+temperature-graph = Temperature
+fan-speed-graph = Fan speed
+clockspeed-graph = Clockspeed
+power-usage-graph = Power usage
+memory-usage-graph = Memory usage
+voltage-graph = Voltage
+
+series-average = Average
+series-current = Current
+series-limit = Limit
+series-maximum = Maximum
+series-minimum = Minimum
+series-percentage = Percentage
+series-gpu-average = GPU (Avg)
+series-gpu-target = GPU (Trgt)
+series-cpu-visible-vram = CPU visible VRAM
+series-gpu-usage = GPU usage
+series-northbridge = Northbridge
+series-engine-gfx = GFX engine
+series-engine-compute = Compute engine
+series-engine-encode = Video encoder
+series-engine-decode = Video decoder
@@ -1,48 +1,78 @@
 mod apply_revealer;
 mod confirmation_dialog;
 mod graphs_window;
+mod gui_settings;
 mod header;
 mod info_row;
 mod msg;
 mod page_section;
 mod pages;
+mod profile_comparison_dialog;
+mod tray;
+mod undervolt_dialog;
 
 use crate::{APP_ID, GUI_VERSION};
 use anyhow::{anyhow, Context};
 use apply_revealer::{ApplyRevealer, ApplyRevealerMsg};
 use confirmation_dialog::ConfirmationDialog;
-use graphs_window::GraphsWindow;
+use graphs_window::{GraphsExportFormat, GraphsWindow};
 use gtk::{
+    gio,
     glib::{self, clone, ControlFlow},
     prelude::{
-        BoxExt, ButtonExt, Cast, DialogExtManual, FileChooserExt, FileExt, GtkWindowExt,
-        OrientableExt, WidgetExt,
+        ApplicationExt, BoxExt, ButtonExt, Cast, DialogExtManual, FileChooserExt, FileExt,
+        GtkWindowExt, OrientableExt, WidgetExt,
     },
     ApplicationWindow, ButtonsType, FileChooserAction, FileChooserDialog, MessageDialog,
     MessageType, ResponseType,
 };
+use gui_settings::{apply_accent_color, GuiSettings};
 use header::{Header, HeaderMsg};
 use lact_client::{ConnectionStatusMsg, DaemonClient};
 use lact_daemon::MODULE_CONF_PATH;
 use lact_schema::{
-    args::GuiArgs,
-    request::{ConfirmCommand, SetClocksCommand},
-    FanOptions, GIT_COMMIT,
+    args::GuiArgs, is_overdrive_disabled_error, request::ConfirmCommand, ActiveAlert,
+    DeviceListEntry, DeviceStats, FanOptions, ImportStateResult, Request, UndervoltState,
+    FAN_STALL_SENSOR, GIT_COMMIT, PROTOCOL_VERSION,
 };
 use msg::AppMsg;
 use pages::{
-    info_page::InformationPage, oc_page::OcPage, software_page::SoftwarePage,
-    thermals_page::ThermalsPage, PageUpdate,
+    energy_page::{EnergyMsg, EnergyPage},
+    info_page::InformationPage,
+    oc_page::OcPage,
+    overview_page::{OverviewMsg, OverviewPage},
+    processes_page::{ProcessesMsg, ProcessesPage},
+    sensors_page::{SensorsMsg, SensorsPage},
+    software_page::SoftwarePage,
+    thermals_page::ThermalsPage,
+    PageUpdate,
 };
+use profile_comparison_dialog::{ProfileComparisonDialog, ProfileComparisonDialogMsg};
 use relm4::{
     actions::{RelmAction, RelmActionGroup},
     prelude::{AsyncComponent, AsyncComponentParts},
     tokio, AsyncComponentSender, Component, ComponentController,
 };
-use std::{os::unix::net::UnixStream, rc::Rc, sync::atomic::AtomicBool, time::Duration};
+use std::{
+    collections::HashMap,
+    os::unix::net::UnixStream,
+    path::Path,
+    rc::Rc,
+    sync::atomic::AtomicBool,
+    time::{Duration, Instant},
+};
 use tracing::{debug, error, info, trace, warn};
+use tray::{Tray, TrayMsg};
+use undervolt_dialog::{UndervoltDialog, UndervoltDialogMsg};
 
 const STATS_POLL_INTERVAL_MS: u64 = 250;
+const PROCESSES_POLL_INTERVAL_MS: u64 = 2000;
+const SENSORS_POLL_INTERVAL_MS: u64 = 2000;
+const ENERGY_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// How quickly the energy usage is shown after switching to its page
+const ENERGY_PAGE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const UNDERVOLT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const PROFILE_COMPARISON_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 pub struct AppModel {
     daemon_client: DaemonClient,
@@ -52,10 +82,27 @@ pub struct AppModel {
     oc_page: OcPage,
     thermals_page: ThermalsPage,
     software_page: relm4::Controller<SoftwarePage>,
+    processes_page: relm4::Controller<ProcessesPage>,
+    sensors_page: relm4::Controller<SensorsPage>,
+    energy_page: relm4::Controller<EnergyPage>,
+    /// Only shown when there are multiple GPUs
+    overview_page: relm4::Controller<OverviewPage>,
+    /// Stats streams of the GPUs shown in the overview
+    overview_task_handles: HashMap<String, glib::JoinHandle<()>>,
 
     header: relm4::Controller<Header>,
     apply_revealer: relm4::Controller<ApplyRevealer>,
     stats_task_handle: Option<glib::JoinHandle<()>>,
+    processes_task_handle: Option<glib::JoinHandle<()>>,
+    energy_task_handle: Option<glib::JoinHandle<()>>,
+    undervolt_dialog: Option<relm4::Controller<UndervoltDialog>>,
+    undervolt_task_handle: Option<glib::JoinHandle<()>>,
+    profile_comparison_dialog: Option<relm4::Controller<ProfileComparisonDialog>>,
+    profile_comparison_task_handle: Option<glib::JoinHandle<()>>,
+    /// Alerts of the current GPU that were active in the last stats, to only notify about new ones
+    active_alerts: Vec<ActiveAlert>,
+    tray: Option<Tray>,
+    gui_settings: GuiSettings,
 }
 
 #[relm4::component(pub, async)]
@@ -87,10 +134,13 @@ impl AsyncComponent for AppModel {
                     set_margin_start: 30,
                     set_margin_end: 30,
 
-                    add_titled[Some("info_page"), "Information"] = model.info_page.widget(),
-                    add_titled[Some("oc_page"), "OC"] = &model.oc_page.container.clone(),
-                    add_titled[Some("thermals_page"), "Thermals"] = &model.thermals_page.container.clone(),
-                    add_titled[Some("software_page"), "Software"] = model.software_page.widget(),
+                    add_titled[Some("info_page"), &fl!("info-page")] = model.info_page.widget(),
+                    add_titled[Some("oc_page"), &fl!("oc-page")] = &model.oc_page.container.clone(),
+                    add_titled[Some("thermals_page"), &fl!("thermals-page")] = &model.thermals_page.container.clone(),
+                    add_titled[Some("processes_page"), &fl!("processes-page")] = model.processes_page.widget(),
+                    add_titled[Some("sensors_page"), &fl!("sensors-page")] = model.sensors_page.widget(),
+                    add_titled[Some("energy_page"), &fl!("energy-page")] = model.energy_page.widget(),
+                    add_titled[Some("software_page"), &fl!("software-page")] = model.software_page.widget(),
                 },
 
                 model.apply_revealer.widget(),
@@ -103,9 +153,9 @@ impl AsyncComponent for AppModel {
             gtk::DialogFlags::MODAL,
             gtk::MessageType::Error,
             gtk::ButtonsType::None,
-            "Daemon connection lost, reconnecting...",
+            &fl!("daemon-connection-lost"),
         ) -> gtk::MessageDialog {
-            set_title: Some("Connection Lost"),
+            set_title: Some(&fl!("connection-lost")),
         }
     }
 
@@ -114,21 +164,22 @@ impl AsyncComponent for AppModel {
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
+        let socket_path = args.socket_path.as_deref();
         let (daemon_client, conn_err) = match args.tcp_address {
             Some(remote_addr) => {
                 info!("establishing connection to {remote_addr}");
-                match DaemonClient::connect_tcp(&remote_addr).await {
+                match DaemonClient::connect_tcp(&remote_addr, args.tcp_token).await {
                     Ok(conn) => (conn, None),
                     Err(err) => {
                         error!("TCP connection error: {err:#}");
-                        let (conn, _) = create_connection()
+                        let (conn, _) = create_connection(socket_path)
                             .await
                             .expect("Could not create fallback connection");
                         (conn, Some(err))
                     }
                 }
             }
-            None => create_connection()
+            None => create_connection(socket_path)
                 .await
                 .expect("Could not establish any daemon connection"),
         };
@@ -160,8 +211,27 @@ impl AsyncComponent for AppModel {
             .expect("Could not list devices");
         let devices = devices_buf.inner().expect("Could not access devices");
 
-        if system_info.version != GUI_VERSION || system_info.commit.as_deref() != Some(GIT_COMMIT) {
-            let err = anyhow!("Version mismatch between GUI and daemon ({GUI_VERSION}-{GIT_COMMIT} vs {}-{})! Make sure you have restarted the service if you have updated LACT.", system_info.version, system_info.commit.as_deref().unwrap_or_default());
+        if system_info.protocol_version != Some(PROTOCOL_VERSION) {
+            let err = anyhow!(fl!(
+                "protocol-version-mismatch",
+                gui_version = PROTOCOL_VERSION,
+                daemon_version = system_info
+                    .protocol_version
+                    .map_or_else(|| fl!("unknown"), |version| version.to_string())
+            ));
+            sender.input(AppMsg::Error(err.into()));
+        } else if system_info.version != GUI_VERSION
+            || system_info.commit.as_deref() != Some(GIT_COMMIT)
+        {
+            let err = anyhow!(fl!(
+                "version-mismatch",
+                gui_version = format!("{GUI_VERSION}-{GIT_COMMIT}"),
+                daemon_version = format!(
+                    "{}-{}",
+                    system_info.version,
+                    system_info.commit.as_deref().unwrap_or_default()
+                )
+            ));
             sender.input(AppMsg::Error(err.into()));
         }
 
@@ -174,9 +244,21 @@ impl AsyncComponent for AppModel {
             .launch((system_info, daemon_client.embedded))
             .detach();
 
+        let processes_page = ProcessesPage::builder().launch(()).detach();
+        let sensors_page = SensorsPage::builder().launch(()).detach();
+        start_sensors_update_loop(daemon_client.clone(), &sensors_page);
+        let energy_page = EnergyPage::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| msg);
+
+        let overview_page = OverviewPage::builder()
+            .launch(devices.clone())
+            .forward(sender.input_sender(), |msg| msg);
+
         let header = Header::builder()
-            .launch(devices)
+            .launch(devices.clone())
             .forward(sender.input_sender(), |msg| msg);
+        start_device_events_loop(daemon_client.clone(), sender.clone());
 
         let apply_revealer = ApplyRevealer::builder()
             .launch(())
@@ -189,6 +271,25 @@ impl AsyncComponent for AppModel {
                 sender.input(AppMsg::ResetClocks);
             }
         ));
+        oc_page
+            .performance_frame
+            .connect_low_latency_toggled(clone!(
+                #[strong]
+                sender,
+                move |enabled| sender.input(AppMsg::SetLowLatencyMode(enabled))
+            ));
+        oc_page
+            .performance_frame
+            .connect_min_vram_state_changed(clone!(
+                #[strong]
+                sender,
+                move |state| sender.input(AppMsg::SetMinVramState(state))
+            ));
+        oc_page.runtime_pm_frame.connect_auto_toggled(clone!(
+            #[strong]
+            sender,
+            move |auto| sender.input(AppMsg::SetRuntimePm(auto))
+        ));
         thermals_page.connect_reset_pmfw(clone!(
             #[strong]
             sender,
@@ -196,6 +297,20 @@ impl AsyncComponent for AppModel {
                 sender.input(AppMsg::ResetPmfw);
             }
         ));
+        thermals_page.connect_reset_fan_control(clone!(
+            #[strong]
+            sender,
+            move || {
+                sender.input(AppMsg::ResetFanControl);
+            }
+        ));
+        thermals_page.connect_preview_fan_curve(clone!(
+            #[strong]
+            sender,
+            move |curve| {
+                sender.input(AppMsg::PreviewFanCurve(curve));
+            }
+        ));
 
         if let Some(ref button) = oc_page.enable_overclocking_button {
             button.connect_clicked(clone!(
@@ -204,8 +319,8 @@ impl AsyncComponent for AppModel {
                 move |_| {
                     sender.input(AppMsg::ask_confirmation(
                         AppMsg::EnableOverdrive,
-                        "Enable Overclocking",
-                        format!("This will enable the overdrive feature of the amdgpu driver by creating a file at <b>{MODULE_CONF_PATH}</b> and updating the initramfs. Are you sure you want to do this?"),
+                        fl!("enable-amd-oc"),
+                        fl!("enable-amd-oc-description", path = MODULE_CONF_PATH),
                         gtk::ButtonsType::OkCancel,
                     ));
                 }
@@ -213,20 +328,62 @@ impl AsyncComponent for AppModel {
         }
 
         let graphs_window = GraphsWindow::new();
+        graphs_window.connect_time_range_notify(clone!(
+            #[strong]
+            sender,
+            move |_| sender.input(AppMsg::ReloadGraphsHistory)
+        ));
+        graphs_window.connect_export(clone!(
+            #[strong]
+            sender,
+            move |format| sender.input(AppMsg::ExportGraphs(format))
+        ));
 
-        let model = AppModel {
+        let gui_settings = GuiSettings::load();
+        apply_accent_color(&gui_settings.graphs);
+        graphs_window.set_style(&gui_settings.graphs);
+        graphs_window.connect_style_changed(clone!(
+            #[strong]
+            sender,
+            move |style| sender.input(AppMsg::SetGraphStyle(style))
+        ));
+
+        let mut model = AppModel {
             daemon_client,
             graphs_window,
             info_page,
             oc_page,
             thermals_page,
             software_page,
+            processes_page,
+            sensors_page,
+            energy_page,
+            overview_page,
+            overview_task_handles: HashMap::new(),
             apply_revealer,
             header,
             stats_task_handle: None,
+            processes_task_handle: None,
+            energy_task_handle: None,
+            undervolt_dialog: None,
+            undervolt_task_handle: None,
+            profile_comparison_dialog: None,
+            profile_comparison_task_handle: None,
+            active_alerts: Vec::new(),
+            tray: args.tray.then(|| start_tray(&sender)),
+            gui_settings,
         };
 
         let widgets = view_output!();
+        // The tray keeps the app running with a hidden window
+        root.set_hide_on_close(model.tray.is_some());
+
+        widgets.root_stack.add_titled(
+            model.overview_page.widget(),
+            Some("overview_page"),
+            &fl!("overview-page"),
+        );
+        model.update_overview_streams(&devices, &sender).await;
 
         if let Some(err) = conn_err {
             show_embedded_info(&root, err);
@@ -249,13 +406,54 @@ impl AsyncComponent for AppModel {
     ) {
         trace!("processing state update");
         if let Err(err) = self.handle_msg(msg, sender.clone(), root, widgets).await {
-            show_error(root, &err);
+            if is_overdrive_disabled_error(&err) {
+                sender.input(AppMsg::ask_confirmation(
+                    AppMsg::EnableOverdrive,
+                    fl!("amd-oc-disabled"),
+                    fl!("amd-oc-disabled-description", path = MODULE_CONF_PATH),
+                    gtk::ButtonsType::OkCancel,
+                ));
+            } else {
+                show_error(root, &err);
+            }
         }
         self.update_view(widgets, sender);
     }
 }
 
 impl AppModel {
+    /// Shows a desktop notification for alerts that were not active in the previous stats
+    fn notify_new_alerts(&mut self, stats: &DeviceStats) {
+        for alert in &stats.alerts {
+            let is_new = !self
+                .active_alerts
+                .iter()
+                .any(|active| active.sensor == alert.sensor && active.threshold == alert.threshold);
+            if alert.notify && is_new {
+                let notification = if alert.sensor == FAN_STALL_SENSOR {
+                    let notification = gio::Notification::new(&fl!("fan-stall-alert"));
+                    notification.set_body(Some(&fl!(
+                        "fan-stall-alert-description",
+                        speed = format!("{:.0}", alert.temperature)
+                    )));
+                    notification
+                } else {
+                    let notification = gio::Notification::new(&fl!("temperature-alert"));
+                    notification.set_body(Some(&fl!(
+                        "temperature-alert-description",
+                        sensor = alert.sensor.as_str(),
+                        temperature = format!("{:.0}", alert.temperature),
+                        threshold = format!("{:.0}", alert.threshold)
+                    )));
+                    notification
+                };
+                notification.set_priority(gio::NotificationPriority::Urgent);
+                relm4::main_application().send_notification(None, &notification);
+            }
+        }
+        self.active_alerts.clone_from(&stats.alerts);
+    }
+
     async fn handle_msg(
         &mut self,
         msg: AppMsg,
@@ -277,7 +475,21 @@ impl AppModel {
                     self.update_gpu_data(gpu_id, sender).await?;
                 }
             }
+            AppMsg::SelectGpu(id) => {
+                self.header.emit(HeaderMsg::SelectGpuId(id));
+            }
+            AppMsg::DiscardChangesAndSelectGpu(id) => {
+                self.header.emit(HeaderMsg::PendingChanges(false));
+                self.header.emit(HeaderMsg::SelectGpuId(id));
+            }
+            AppMsg::SettingsChanged => {
+                self.apply_revealer.emit(ApplyRevealerMsg::Show);
+                self.header.emit(HeaderMsg::PendingChanges(true));
+            }
             AppMsg::SelectProfile(profile) => {
+                if let Some(tray) = &self.tray {
+                    tray.set_current_profile(profile.clone());
+                }
                 self.daemon_client.set_profile(profile).await?;
                 sender.input(AppMsg::ReloadData { full: false });
             }
@@ -292,12 +504,42 @@ impl AppModel {
                 self.daemon_client.delete_profile(profile).await?;
                 sender.input(AppMsg::ReloadProfiles);
             }
+            AppMsg::SetEnergyPrice(price) => {
+                self.daemon_client.set_energy_price(price).await?;
+                if let Ok(gpu_id) = self.current_gpu_id() {
+                    let stats = self
+                        .daemon_client
+                        .get_energy_stats(&gpu_id)
+                        .await?
+                        .inner()?;
+                    self.energy_page.emit(EnergyMsg::Stats(stats));
+                }
+            }
+            AppMsg::SetProfileSchedule(profile, schedule) => {
+                self.daemon_client
+                    .set_profile_schedule(profile, schedule)
+                    .await?;
+                sender.input(AppMsg::ReloadProfiles);
+            }
             AppMsg::Stats(stats) => {
                 self.info_page.emit(PageUpdate::Stats(stats.clone()));
 
                 self.thermals_page.set_stats(&stats, false);
                 self.oc_page.set_stats(&stats, false);
                 self.graphs_window.set_stats(&stats);
+                if let Some(tray) = &self.tray {
+                    tray.set_stats(&stats);
+                }
+                self.notify_new_alerts(&stats);
+            }
+            AppMsg::OverviewStats(id, stats) => {
+                self.overview_page.emit(OverviewMsg::Stats(id, stats));
+            }
+            AppMsg::DevicesChanged(devices) => {
+                self.update_overview_streams(&devices, &sender).await;
+                self.overview_page
+                    .emit(OverviewMsg::Devices(devices.clone()));
+                self.header.emit(HeaderMsg::Devices(devices));
             }
             AppMsg::ApplyChanges => {
                 self.apply_settings(self.current_gpu_id()?, root, &sender)
@@ -309,11 +551,31 @@ impl AppModel {
             AppMsg::RevertChanges => {
                 sender.input(AppMsg::ReloadData { full: false });
             }
-            AppMsg::ResetClocks => {
+            AppMsg::UndoChange => {
                 let gpu_id = self.current_gpu_id()?;
                 self.daemon_client
-                    .set_clocks_value(&gpu_id, SetClocksCommand::Reset)
+                    .undo_last_change(&gpu_id)
+                    .await?
+                    .inner()?;
+                self.daemon_client
+                    .confirm_pending_config(ConfirmCommand::Confirm)
                     .await?;
+                sender.input(AppMsg::ReloadData { full: false });
+            }
+            AppMsg::RedoChange => {
+                let gpu_id = self.current_gpu_id()?;
+                self.daemon_client
+                    .redo_last_change(&gpu_id)
+                    .await?
+                    .inner()?;
+                self.daemon_client
+                    .confirm_pending_config(ConfirmCommand::Confirm)
+                    .await?;
+                sender.input(AppMsg::ReloadData { full: false });
+            }
+            AppMsg::ResetClocks => {
+                let gpu_id = self.current_gpu_id()?;
+                self.daemon_client.reset_clocks(&gpu_id).await?.inner()?;
                 self.daemon_client
                     .confirm_pending_config(ConfirmCommand::Confirm)
                     .await?;
@@ -327,15 +589,116 @@ impl AppModel {
                     .await?;
                 sender.input(AppMsg::ReloadData { full: false });
             }
+            AppMsg::ResetFanControl => {
+                let gpu_id = self.current_gpu_id()?;
+                self.daemon_client
+                    .reset_fan_control(&gpu_id)
+                    .await?
+                    .inner()?;
+                self.daemon_client
+                    .confirm_pending_config(ConfirmCommand::Confirm)
+                    .await?;
+                sender.input(AppMsg::ReloadData { full: false });
+            }
+            AppMsg::PreviewFanCurve(curve) => {
+                let gpu_id = self.current_gpu_id()?;
+                let points = self
+                    .daemon_client
+                    .preview_fan_curve(&gpu_id, curve, None)
+                    .await?;
+                self.thermals_page.show_fan_curve_preview(points);
+            }
+            AppMsg::SetLowLatencyMode(enabled) => {
+                let gpu_id = self.current_gpu_id()?;
+                // Shows the actual state again if the daemon rejects it
+                sender.input(AppMsg::ReloadData { full: false });
+                self.daemon_client
+                    .set_low_latency_mode(&gpu_id, enabled)
+                    .await?;
+                self.daemon_client
+                    .confirm_pending_config(ConfirmCommand::Confirm)
+                    .await?;
+            }
+            AppMsg::SetMinVramState(state) => {
+                let gpu_id = self.current_gpu_id()?;
+                sender.input(AppMsg::ReloadData { full: false });
+                self.daemon_client
+                    .set_min_vram_state(&gpu_id, state)
+                    .await?;
+                self.daemon_client
+                    .confirm_pending_config(ConfirmCommand::Confirm)
+                    .await?;
+            }
+            AppMsg::SetRuntimePm(auto) => {
+                let gpu_id = self.current_gpu_id()?;
+                sender.input(AppMsg::ReloadData { full: false });
+                self.daemon_client
+                    .set_runtime_pm(&gpu_id, Some(auto))
+                    .await?;
+                self.daemon_client
+                    .confirm_pending_config(ConfirmCommand::Confirm)
+                    .await?;
+            }
+            AppMsg::ShowWindow => {
+                root.present();
+            }
             AppMsg::ShowGraphsWindow => {
                 self.graphs_window.show();
             }
+            AppMsg::ShowUndervoltAssistant => {
+                self.show_undervolt_assistant(self.current_gpu_id()?, &sender);
+            }
+            AppMsg::StartUndervolt(gpu_id, options) => {
+                self.daemon_client.start_undervolt(&gpu_id, options).await?;
+            }
+            AppMsg::CancelUndervolt(gpu_id) => {
+                self.daemon_client.cancel_undervolt(&gpu_id).await?;
+            }
+            AppMsg::ShowProfileComparison => {
+                self.show_profile_comparison(self.current_gpu_id()?, &sender)
+                    .await?;
+            }
+            AppMsg::StartProfileComparison(gpu_id, options) => {
+                self.daemon_client
+                    .start_profile_comparison(&gpu_id, options)
+                    .await?;
+            }
+            AppMsg::CancelProfileComparison(gpu_id) => {
+                self.daemon_client
+                    .cancel_profile_comparison(&gpu_id)
+                    .await?;
+            }
+            AppMsg::ReloadGraphsHistory => {
+                self.graphs_window.clear();
+                self.load_graphs_history(&self.current_gpu_id()?).await;
+            }
+            AppMsg::ExportGraphs(format) => {
+                self.export_graphs(format, root);
+            }
+            AppMsg::SetGraphStyle(style) => {
+                apply_accent_color(&style);
+                self.gui_settings.graphs = style;
+                self.gui_settings.save()?;
+            }
             AppMsg::DumpVBios => {
                 self.dump_vbios(&self.current_gpu_id()?, root).await;
             }
             AppMsg::DebugSnapshot => {
                 self.generate_debug_snapshot(root).await;
             }
+            AppMsg::ExportConfig => {
+                self.export_config(root).await?;
+            }
+            AppMsg::ImportConfig => {
+                ask_import_config_file(root, sender.clone());
+            }
+            AppMsg::ImportConfigFile(path) => {
+                let state = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Could not read {}", path.display()))?;
+                let result = self.daemon_client.import_state(state).await?.inner()?;
+                sender.input(AppMsg::ReloadData { full: true });
+                show_import_result(root, &result);
+            }
             AppMsg::EnableOverdrive => {
                 toggle_overdrive(&self.daemon_client, true, root.clone()).await;
             }
@@ -343,8 +706,13 @@ impl AppModel {
                 toggle_overdrive(&self.daemon_client, false, root.clone()).await;
             }
             AppMsg::ResetConfig => {
-                self.daemon_client.reset_config().await?;
+                let result = self.daemon_client.reset_config().await?.inner()?;
                 sender.input(AppMsg::ReloadData { full: true });
+                if !result.is_ok() {
+                    return Err(
+                        anyhow!(fl!("reset-config-failed", gpus = result.to_string())).into(),
+                    );
+                }
             }
             AppMsg::ConnectionStatus(status) => match status {
                 ConnectionStatusMsg::Disconnected => widgets.reconnecting_dialog.present(),
@@ -366,6 +734,104 @@ impl AppModel {
         Ok(())
     }
 
+    fn show_undervolt_assistant(
+        &mut self,
+        gpu_id: String,
+        sender: &AsyncComponentSender<AppModel>,
+    ) {
+        if let Some(task) = self.undervolt_task_handle.take() {
+            task.abort();
+        }
+        if let Some(dialog) = self.undervolt_dialog.take() {
+            dialog.widget().destroy();
+        }
+
+        let dialog = UndervoltDialog::builder()
+            .launch(gpu_id.clone())
+            .forward(sender.input_sender(), |msg| msg);
+        self.undervolt_task_handle = Some(start_undervolt_update_loop(
+            gpu_id,
+            self.daemon_client.clone(),
+            &dialog,
+            sender.clone(),
+        ));
+        self.undervolt_dialog = Some(dialog);
+    }
+
+    async fn show_profile_comparison(
+        &mut self,
+        gpu_id: String,
+        sender: &AsyncComponentSender<AppModel>,
+    ) -> anyhow::Result<()> {
+        let profiles = self.daemon_client.list_profiles().await?.inner()?;
+
+        if let Some(task) = self.profile_comparison_task_handle.take() {
+            task.abort();
+        }
+        if let Some(dialog) = self.profile_comparison_dialog.take() {
+            dialog.widget().destroy();
+        }
+
+        let dialog = ProfileComparisonDialog::builder()
+            .launch((gpu_id.clone(), profiles.profiles))
+            .forward(sender.input_sender(), |msg| msg);
+        self.profile_comparison_task_handle = Some(start_profile_comparison_update_loop(
+            gpu_id,
+            self.daemon_client.clone(),
+            &dialog,
+        ));
+        self.profile_comparison_dialog = Some(dialog);
+        Ok(())
+    }
+
+    /// Streams the stats of every GPU to the overview while there are multiple GPUs, which is when it's shown
+    async fn update_overview_streams(
+        &mut self,
+        devices: &[DeviceListEntry],
+        sender: &AsyncComponentSender<AppModel>,
+    ) {
+        let shown = devices.len() > 1;
+        self.overview_task_handles.retain(|id, handle| {
+            let keep = shown && devices.iter().any(|device| device.id == *id);
+            if !keep {
+                handle.abort();
+            }
+            keep
+        });
+        if !shown {
+            return;
+        }
+
+        for device in devices {
+            if self.overview_task_handles.contains_key(&device.id) {
+                continue;
+            }
+
+            if let Ok(info) = self
+                .daemon_client
+                .get_device_info(&device.id)
+                .await
+                .and_then(|buffer| buffer.inner())
+            {
+                if let Some(drm_info) = info.drm_info {
+                    self.overview_page.emit(OverviewMsg::VramClockRatio(
+                        device.id.clone(),
+                        drm_info.vram_clock_ratio,
+                    ));
+                }
+            }
+
+            let id = device.id.clone();
+            let handle = start_stats_update_loop(
+                device.id.clone(),
+                self.daemon_client.clone(),
+                sender.clone(),
+                move |stats| AppMsg::OverviewStats(id.clone(), stats),
+            );
+            self.overview_task_handles.insert(device.id.clone(), handle);
+        }
+    }
+
     fn current_gpu_id(&self) -> anyhow::Result<String> {
         self.header
             .model()
@@ -375,6 +841,9 @@ impl AppModel {
 
     async fn reload_profiles(&mut self) -> anyhow::Result<()> {
         let profiles = self.daemon_client.list_profiles().await?.inner()?;
+        if let Some(tray) = &self.tray {
+            tray.set_profiles(&profiles);
+        }
         self.header.emit(HeaderMsg::Profiles(profiles));
         Ok(())
     }
@@ -402,15 +871,41 @@ impl AppModel {
             .unwrap_or(1.0);
         self.graphs_window.set_vram_clock_ratio(vram_clock_ratio);
 
-        self.update_gpu_data(gpu_id, sender).await?;
+        match daemon_client
+            .get_device_capabilities(&gpu_id)
+            .await
+            .and_then(|buf| buf.inner())
+        {
+            Ok(capabilities) => {
+                self.thermals_page.set_capabilities(&capabilities);
+                self.oc_page.set_capabilities(&capabilities);
+            }
+            Err(err) => debug!("could not fetch device capabilities: {err:#}"),
+        }
+
+        self.update_gpu_data(gpu_id.clone(), sender).await?;
 
         self.thermals_page.set_info(&info);
 
         self.graphs_window.clear();
+        self.load_graphs_history(&gpu_id).await;
 
         Ok(())
     }
 
+    async fn load_graphs_history(&self, gpu_id: &str) {
+        // The history is optional, so it's fine if the daemon doesn't have it enabled
+        let since = chrono::Utc::now().timestamp_millis() - self.graphs_window.time_range() * 1000;
+        match self
+            .daemon_client
+            .get_stats_history(gpu_id, Some(since))
+            .await
+        {
+            Ok(samples) => self.graphs_window.set_history(&samples),
+            Err(err) => debug!("could not load stats history: {err:#}"),
+        }
+    }
+
     async fn update_gpu_data(
         &mut self,
         gpu_id: String,
@@ -419,6 +914,12 @@ impl AppModel {
         if let Some(stats_task) = self.stats_task_handle.take() {
             stats_task.abort();
         }
+        if let Some(processes_task) = self.processes_task_handle.take() {
+            processes_task.abort();
+        }
+        if let Some(energy_task) = self.energy_task_handle.take() {
+            energy_task.abort();
+        }
 
         debug!("updating info for gpu {gpu_id}");
 
@@ -435,9 +936,9 @@ impl AppModel {
 
         self.info_page.emit(PageUpdate::Stats(stats));
 
-        let maybe_clocks_table = match self.daemon_client.get_device_clocks_info(&gpu_id).await {
+        let maybe_clocks_info = match self.daemon_client.get_device_clocks_info(&gpu_id).await {
             Ok(clocks_buf) => match clocks_buf.inner() {
-                Ok(info) => info.table,
+                Ok(info) => Some(info),
                 Err(err) => {
                     debug!("could not extract clocks info: {err:?}");
                     None
@@ -448,7 +949,7 @@ impl AppModel {
                 None
             }
         };
-        self.oc_page.set_clocks_table(maybe_clocks_table);
+        self.oc_page.set_clocks_info(maybe_clocks_info);
 
         let maybe_modes_table = match self
             .daemon_client
@@ -478,6 +979,9 @@ impl AppModel {
             .and_then(|states| states.inner())
         {
             Ok(power_states) => {
+                self.oc_page
+                    .performance_frame
+                    .set_vram_states(&power_states.vram);
                 self.oc_page
                     .power_states_frame
                     .set_power_states(power_states);
@@ -488,10 +992,10 @@ impl AppModel {
         // Show apply button on setting changes
         // This is done here because new widgets may appear after applying settings (like fan curve points) which should be connected
         let show_revealer = clone!(
-            #[strong(rename_to = apply_sender)]
-            self.apply_revealer.sender(),
+            #[strong]
+            sender,
             move || {
-                apply_sender.send(ApplyRevealerMsg::Show).unwrap();
+                sender.input(AppMsg::SettingsChanged);
             }
         );
 
@@ -504,11 +1008,33 @@ impl AppModel {
             .sender()
             .send(ApplyRevealerMsg::Hide)
             .unwrap();
+        self.header.emit(HeaderMsg::PendingChanges(false));
+
+        match self
+            .daemon_client
+            .get_config_history(&gpu_id)
+            .await
+            .and_then(|buffer| buffer.inner())
+        {
+            Ok(history) => self.header.emit(HeaderMsg::ConfigHistory(history)),
+            Err(err) => debug!("could not get settings history: {err:#}"),
+        }
 
         self.stats_task_handle = Some(start_stats_update_loop(
             gpu_id.to_owned(),
             self.daemon_client.clone(),
             sender,
+            AppMsg::Stats,
+        ));
+        self.processes_task_handle = Some(start_processes_update_loop(
+            gpu_id.clone(),
+            self.daemon_client.clone(),
+            &self.processes_page,
+        ));
+        self.energy_task_handle = Some(start_energy_update_loop(
+            gpu_id,
+            self.daemon_client.clone(),
+            &self.energy_page,
         ));
 
         Ok(())
@@ -524,37 +1050,28 @@ impl AppModel {
 
         debug!("applying settings on gpu {gpu_id}");
 
-        if let Some(cap) = self.oc_page.get_power_cap() {
-            self.daemon_client
-                .set_power_cap(&gpu_id, Some(cap))
-                .await
-                .context("Failed to set power cap")?;
+        // Everything is applied at once, so that a failing setting doesn't leave the others half applied
+        let mut requests = vec![];
 
-            self.daemon_client
-                .confirm_pending_config(ConfirmCommand::Confirm)
-                .await
-                .context("Could not commit config")?;
+        if let Some(cap) = self.oc_page.get_power_cap() {
+            requests.push(Request::SetPowerCap {
+                id: &gpu_id,
+                cap: Some(cap),
+            });
         }
 
         // Reset the power profile mode for switching to/from manual performance level
-        self.daemon_client
-            .set_power_profile_mode(&gpu_id, None, vec![])
-            .await
-            .context("Could not set default power profile mode")?;
-        self.daemon_client
-            .confirm_pending_config(ConfirmCommand::Confirm)
-            .await
-            .context("Could not commit config")?;
+        requests.push(Request::SetPowerProfileMode {
+            id: &gpu_id,
+            index: None,
+            custom_heuristics: vec![],
+        });
 
         if let Some(level) = self.oc_page.get_performance_level() {
-            self.daemon_client
-                .set_performance_level(&gpu_id, level)
-                .await
-                .context("Failed to set power profile")?;
-            self.daemon_client
-                .confirm_pending_config(ConfirmCommand::Confirm)
-                .await
-                .context("Could not commit config")?;
+            requests.push(Request::SetPerformanceLevel {
+                id: &gpu_id,
+                performance_level: level,
+            });
 
             let mode_index = self
                 .oc_page
@@ -565,19 +1082,16 @@ impl AppModel {
                 .performance_frame
                 .get_power_profile_mode_custom_heuristics();
 
-            self.daemon_client
-                .set_power_profile_mode(&gpu_id, mode_index, custom_heuristics)
-                .await
-                .context("Could not set active power profile mode")?;
-            self.daemon_client
-                .confirm_pending_config(ConfirmCommand::Confirm)
-                .await
-                .context("Could not commit config")?;
+            requests.push(Request::SetPowerProfileMode {
+                id: &gpu_id,
+                index: mode_index,
+                custom_heuristics,
+            });
         }
 
         if let Some(thermals_settings) = self.thermals_page.get_thermals_settings() {
             debug!("applying thermal settings: {thermals_settings:?}");
-            let opts = FanOptions {
+            requests.push(Request::SetFanControl(FanOptions {
                 id: &gpu_id,
                 enabled: thermals_settings.manual_fan_control,
                 mode: thermals_settings.mode,
@@ -586,16 +1100,12 @@ impl AppModel {
                 pmfw: thermals_settings.pmfw,
                 spindown_delay_ms: thermals_settings.spindown_delay_ms,
                 change_threshold: thermals_settings.change_threshold,
-            };
-
-            self.daemon_client
-                .set_fan_control(opts)
-                .await
-                .context("Could not set fan control")?;
-            self.daemon_client
-                .confirm_pending_config(ConfirmCommand::Confirm)
-                .await
-                .context("Could not commit config")?;
+                max_ramp_rate: thermals_settings.max_ramp_rate,
+                temperature_sensors: None,
+                fan_curves: None,
+                speed_unit: thermals_settings.speed_unit,
+                curve_input: thermals_settings.curve_input,
+            }));
         }
 
         let clocks_commands = self.oc_page.clocks_frame.get_commands();
@@ -606,25 +1116,35 @@ impl AppModel {
 
         for (kind, states) in enabled_power_states {
             if !states.is_empty() {
-                self.daemon_client
-                    .set_enabled_power_states(&gpu_id, kind, states)
-                    .await
-                    .context("Could not set power states")?;
-
-                self.daemon_client
-                    .confirm_pending_config(ConfirmCommand::Confirm)
-                    .await
-                    .context("Could not commit config")?;
+                requests.push(Request::SetEnabledPowerStates {
+                    id: &gpu_id,
+                    kind,
+                    states,
+                });
             }
         }
 
-        if !clocks_commands.is_empty() {
-            let delay = self
-                .daemon_client
-                .batch_set_clocks_value(&gpu_id, clocks_commands)
-                .await
-                .context("Could not commit clocks settings")?;
+        let clocks_changed = !clocks_commands.is_empty();
+        if clocks_changed {
+            requests.push(Request::BatchSetClocksValue {
+                id: &gpu_id,
+                commands: clocks_commands,
+            });
+        }
+
+        let delay = self
+            .daemon_client
+            .apply_batch(&gpu_id, requests)
+            .await
+            .context("Could not apply settings")?;
+
+        if clocks_changed {
             self.ask_settings_confirmation(delay, root, sender).await;
+        } else {
+            self.daemon_client
+                .confirm_pending_config(ConfirmCommand::Confirm)
+                .await
+                .context("Could not commit config")?;
         }
 
         sender.input(AppMsg::ReloadData { full: false });
@@ -640,7 +1160,7 @@ impl AppModel {
     ) {
         let text = confirmation_text(delay);
         let dialog = MessageDialog::builder()
-            .title("Confirm settings")
+            .title(fl!("confirm-settings"))
             .text(text)
             .message_type(MessageType::Question)
             .buttons(ButtonsType::YesNo)
@@ -714,12 +1234,12 @@ impl AppModel {
         {
             Ok(vbios_data) => {
                 let file_chooser = FileChooserDialog::new(
-                    Some("Save VBIOS file"),
+                    Some(&fl!("save-vbios-file")),
                     Some(root),
                     FileChooserAction::Save,
                     &[
-                        ("Save", ResponseType::Accept),
-                        ("Cancel", ResponseType::Cancel),
+                        (fl!("save").as_str(), ResponseType::Accept),
+                        (fl!("cancel").as_str(), ResponseType::Cancel),
                     ],
                 );
 
@@ -744,10 +1264,7 @@ impl AppModel {
                                             show_error(&root, &err);
                                         }
                                     }
-                                    None => show_error(
-                                        &root,
-                                        &anyhow!("Selected file has an invalid path"),
-                                    ),
+                                    None => show_error(&root, &anyhow!(fl!("invalid-file-path"))),
                                 }
                             }
                         }
@@ -758,6 +1275,85 @@ impl AppModel {
         }
     }
 
+    fn export_graphs(&self, format: GraphsExportFormat, root: &gtk::ApplicationWindow) {
+        let file_chooser = FileChooserDialog::new(
+            Some(&fl!("export-graphs")),
+            Some(&self.graphs_window),
+            FileChooserAction::Save,
+            &[
+                (fl!("save").as_str(), ResponseType::Accept),
+                (fl!("cancel").as_str(), ResponseType::Cancel),
+            ],
+        );
+
+        let time = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        let extension = match format {
+            GraphsExportFormat::Csv => "csv",
+            GraphsExportFormat::Png => "png",
+        };
+        file_chooser.set_current_name(&format!("lact_graphs_{time}.{extension}"));
+        file_chooser.run_async(clone!(
+            #[strong]
+            root,
+            #[strong(rename_to = graphs_window)]
+            self.graphs_window,
+            move |diag, response| {
+                diag.close();
+
+                if response == gtk::ResponseType::Accept {
+                    let Some(path) = diag.file().and_then(|file| file.path()) else {
+                        show_error(&root, &anyhow!(fl!("invalid-file-path")));
+                        return;
+                    };
+
+                    let result = match format {
+                        GraphsExportFormat::Csv => std::fs::write(&path, graphs_window.to_csv())
+                            .context("Could not save the graphs data"),
+                        GraphsExportFormat::Png => graphs_window.save_png(&path),
+                    };
+                    if let Err(err) = result {
+                        show_error(&root, &err);
+                    }
+                }
+            }
+        ));
+    }
+
+    async fn export_config(&self, root: &gtk::ApplicationWindow) -> anyhow::Result<()> {
+        let state = self.daemon_client.export_state().await?.inner()?;
+
+        let file_chooser = FileChooserDialog::new(
+            Some(&fl!("export-config")),
+            Some(root),
+            FileChooserAction::Save,
+            &[
+                (fl!("save").as_str(), ResponseType::Accept),
+                (fl!("cancel").as_str(), ResponseType::Cancel),
+            ],
+        );
+        file_chooser.set_current_name("lact_config.json");
+        file_chooser.run_async(clone!(
+            #[strong]
+            root,
+            move |diag, response| {
+                diag.close();
+
+                if response == gtk::ResponseType::Accept {
+                    let Some(path) = diag.file().and_then(|file| file.path()) else {
+                        show_error(&root, &anyhow!(fl!("invalid-file-path")));
+                        return;
+                    };
+                    if let Err(err) =
+                        std::fs::write(path, &state).context("Could not save the configuration")
+                    {
+                        show_error(&root, &err);
+                    }
+                }
+            }
+        ));
+        Ok(())
+    }
+
     async fn generate_debug_snapshot(&self, root: &gtk::ApplicationWindow) {
         match self
             .daemon_client
@@ -780,14 +1376,14 @@ impl AppModel {
                     .margin_end(10)
                     .build();
 
-                vbox.append(&gtk::Label::new(Some("Debug snapshot saved at:")));
+                vbox.append(&gtk::Label::new(Some(&fl!("snapshot-saved-at"))));
                 vbox.append(&path_label);
 
                 let diag = MessageDialog::builder()
-                    .title("Snapshot generated")
+                    .title(fl!("snapshot-generated"))
                     .message_type(MessageType::Info)
                     .use_markup(true)
-                    .text(format!("Debug snapshot saved at <b>{path}</b>"))
+                    .text(fl!("snapshot-saved-at-path", path = path.as_str()))
                     .buttons(ButtonsType::Ok)
                     .transient_for(root)
                     .build();
@@ -817,7 +1413,7 @@ fn show_error(parent: &ApplicationWindow, err: &anyhow::Error) {
     warn!("{text}");
 
     let diag = MessageDialog::builder()
-        .title("Error")
+        .title(fl!("error"))
         .message_type(MessageType::Error)
         .text(text)
         .buttons(ButtonsType::Close)
@@ -828,16 +1424,64 @@ fn show_error(parent: &ApplicationWindow, err: &anyhow::Error) {
     })
 }
 
-fn show_embedded_info(parent: &ApplicationWindow, err: anyhow::Error) {
-    let error_text = format!("Error info: {err:#}\n\n");
-
-    let text = format!(
-        "Could not connect to daemon, running in embedded mode. \n\
-                        Please make sure the lactd service is running. \n\
-                        Using embedded mode, you will not be able to change any settings. \n\n\
-                        {error_text}\
-                        To enable the daemon, run the following command:"
+fn ask_import_config_file(root: &ApplicationWindow, sender: AsyncComponentSender<AppModel>) {
+    let file_chooser = FileChooserDialog::new(
+        Some(&fl!("import-config")),
+        Some(root),
+        FileChooserAction::Open,
+        &[
+            (fl!("import").as_str(), ResponseType::Accept),
+            (fl!("cancel").as_str(), ResponseType::Cancel),
+        ],
     );
+    file_chooser.run_async(clone!(
+        #[strong]
+        root,
+        move |diag, response| {
+            diag.close();
+
+            if response == gtk::ResponseType::Accept {
+                match diag.file().and_then(|file| file.path()) {
+                    Some(path) => sender.input(AppMsg::ImportConfigFile(path)),
+                    None => show_error(&root, &anyhow!(fl!("invalid-file-path"))),
+                }
+            }
+        }
+    ));
+}
+
+fn show_import_result(parent: &ApplicationWindow, result: &ImportStateResult) {
+    let mut text = fl!("config-imported");
+    for (imported_id, id) in &result.matched_gpus {
+        text.push_str("\n\n");
+        text.push_str(&fl!(
+            "config-imported-matched-gpu",
+            imported_id = imported_id.as_str(),
+            id = id.as_str()
+        ));
+    }
+    if !result.skipped_gpus.is_empty() {
+        text.push_str("\n\n");
+        text.push_str(&fl!(
+            "config-imported-skipped-gpus",
+            gpus = result.skipped_gpus.join("\n")
+        ));
+    }
+
+    let diag = MessageDialog::builder()
+        .title(fl!("config-imported-title"))
+        .message_type(MessageType::Info)
+        .text(text)
+        .buttons(ButtonsType::Close)
+        .transient_for(parent)
+        .build();
+    diag.run_async(|diag, _| {
+        diag.close();
+    })
+}
+
+fn show_embedded_info(parent: &ApplicationWindow, err: anyhow::Error) {
+    let text = fl!("embedded-mode-info", error = format!("{err:#}"));
 
     let text_label = gtk::Label::new(Some(&text));
     let enable_label = gtk::Entry::builder()
@@ -853,7 +1497,7 @@ fn show_embedded_info(parent: &ApplicationWindow, err: anyhow::Error) {
         .margin_end(10)
         .build();
 
-    let close_button = gtk::Button::builder().label("Close").build();
+    let close_button = gtk::Button::builder().label(fl!("close")).build();
 
     vbox.append(&text_label);
     vbox.append(&enable_label);
@@ -866,7 +1510,7 @@ fn show_embedded_info(parent: &ApplicationWindow, err: anyhow::Error) {
         gtk::ButtonsType::Ok,
         "",
     );
-    diag.set_title(Some("Daemon info"));
+    diag.set_title(Some(&fl!("daemon-info")));
     diag.set_child(Some(&vbox));
 
     close_button.connect_clicked(clone!(
@@ -880,14 +1524,247 @@ fn show_embedded_info(parent: &ApplicationWindow, err: anyhow::Error) {
     })
 }
 
+/// Updates the GPU list when GPUs are added or removed while the GUI is running
+fn start_tray(sender: &AsyncComponentSender<AppModel>) -> Tray {
+    let (tray_sender, tray_receiver) = relm4::channel();
+    relm4::spawn_local(clone!(
+        #[strong]
+        sender,
+        async move {
+            while let Some(msg) = tray_receiver.recv().await {
+                match msg {
+                    TrayMsg::ShowWindow => sender.input(AppMsg::ShowWindow),
+                    TrayMsg::SelectProfile(profile) => {
+                        sender.input(AppMsg::SelectProfile(profile));
+                        // Updates the profile shown in the header
+                        sender.input(AppMsg::ReloadProfiles);
+                    }
+                    TrayMsg::Quit => relm4::main_application().quit(),
+                }
+            }
+        }
+    ));
+    Tray::spawn(tray_sender)
+}
+
+fn start_device_events_loop(daemon_client: DaemonClient, sender: AsyncComponentSender<AppModel>) {
+    relm4::spawn_local(async move {
+        let mut subscription = match daemon_client.subscribe_device_events().await {
+            Ok(subscription) => subscription,
+            Err(err) => {
+                debug!("could not subscribe to device events: {err:#}");
+                return;
+            }
+        };
+
+        loop {
+            match subscription.next().await {
+                Ok(event) => {
+                    info!("GPUs changed: {event:?}");
+                    match daemon_client
+                        .list_devices()
+                        .await
+                        .and_then(|buffer| buffer.inner())
+                    {
+                        Ok(devices) => sender.input(AppMsg::DevicesChanged(devices)),
+                        Err(err) => error!("could not list devices: {err:#}"),
+                    }
+                }
+                Err(err) => {
+                    error!("device event subscription ended: {err:#}");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// Polls the processes using the GPU while the processes page is shown
+fn start_processes_update_loop(
+    gpu_id: String,
+    daemon_client: DaemonClient,
+    page: &relm4::Controller<ProcessesPage>,
+) -> glib::JoinHandle<()> {
+    let widget = page.widget().clone();
+    let sender = page.sender().clone();
+    relm4::spawn_local(async move {
+        loop {
+            if widget.is_mapped() {
+                match daemon_client
+                    .get_processes(&gpu_id)
+                    .await
+                    .and_then(|buffer| buffer.inner())
+                {
+                    Ok(processes) => {
+                        let _ = sender.send(ProcessesMsg::Processes(processes));
+                    }
+                    Err(err) => {
+                        debug!("could not fetch processes: {err:#}");
+                    }
+                }
+            }
+
+            tokio::time::sleep(Duration::from_millis(PROCESSES_POLL_INTERVAL_MS)).await;
+        }
+    })
+}
+
+/// Polls the system sensors while the sensors page is shown. They don't depend on the GPU,
+/// so the loop keeps running when switching GPUs
+fn start_sensors_update_loop(daemon_client: DaemonClient, page: &relm4::Controller<SensorsPage>) {
+    let widget = page.widget().clone();
+    let sender = page.sender().clone();
+    relm4::spawn_local(async move {
+        loop {
+            if widget.is_mapped() {
+                match daemon_client
+                    .get_sensor_chips()
+                    .await
+                    .and_then(|buffer| buffer.inner())
+                {
+                    Ok(chips) => {
+                        let _ = sender.send(SensorsMsg::Chips(chips));
+                    }
+                    Err(err) => {
+                        debug!("could not fetch sensor chips: {err:#}");
+                    }
+                }
+            }
+
+            tokio::time::sleep(Duration::from_millis(SENSORS_POLL_INTERVAL_MS)).await;
+        }
+    });
+}
+
+/// Polls the energy usage of the GPU while the energy page is shown
+fn start_energy_update_loop(
+    gpu_id: String,
+    daemon_client: DaemonClient,
+    page: &relm4::Controller<EnergyPage>,
+) -> glib::JoinHandle<()> {
+    let widget = page.widget().clone();
+    let sender = page.sender().clone();
+    relm4::spawn_local(async move {
+        let mut last_fetch: Option<Instant> = None;
+        loop {
+            if !widget.is_mapped() {
+                last_fetch = None;
+            } else if last_fetch.map_or(true, |time| time.elapsed() >= ENERGY_POLL_INTERVAL) {
+                last_fetch = Some(Instant::now());
+                match daemon_client
+                    .get_energy_stats(&gpu_id)
+                    .await
+                    .and_then(|buffer| buffer.inner())
+                {
+                    Ok(stats) => {
+                        let _ = sender.send(EnergyMsg::Stats(stats));
+                    }
+                    Err(err) => {
+                        debug!("could not fetch energy usage: {err:#}");
+                    }
+                }
+            }
+
+            tokio::time::sleep(ENERGY_PAGE_CHECK_INTERVAL).await;
+        }
+    })
+}
+
+/// Polls the status of the undervolting assistant while its dialog is shown
+fn start_undervolt_update_loop(
+    gpu_id: String,
+    daemon_client: DaemonClient,
+    dialog: &relm4::Controller<UndervoltDialog>,
+    sender: AsyncComponentSender<AppModel>,
+) -> glib::JoinHandle<()> {
+    let widget = dialog.widget().clone();
+    let dialog_sender = dialog.sender().clone();
+    relm4::spawn_local(async move {
+        let mut was_running = false;
+        loop {
+            if widget.is_visible() {
+                match daemon_client
+                    .get_undervolt_status(&gpu_id)
+                    .await
+                    .and_then(|buffer| buffer.inner())
+                {
+                    Ok(status) => {
+                        let running = matches!(status.state, UndervoltState::Running { .. });
+                        // The found offset is saved to the config
+                        if was_running && !running {
+                            sender.input(AppMsg::ReloadData { full: false });
+                        }
+                        was_running = running;
+                        let _ = dialog_sender.send(UndervoltDialogMsg::Status(status));
+                    }
+                    Err(err) => {
+                        debug!("could not fetch undervolting status: {err:#}");
+                    }
+                }
+            }
+
+            tokio::time::sleep(UNDERVOLT_POLL_INTERVAL).await;
+        }
+    })
+}
+
+/// Polls the status of the profile comparison while its dialog is shown
+fn start_profile_comparison_update_loop(
+    gpu_id: String,
+    daemon_client: DaemonClient,
+    dialog: &relm4::Controller<ProfileComparisonDialog>,
+) -> glib::JoinHandle<()> {
+    let widget = dialog.widget().clone();
+    let dialog_sender = dialog.sender().clone();
+    relm4::spawn_local(async move {
+        loop {
+            if widget.is_visible() {
+                match daemon_client
+                    .get_profile_comparison_status(&gpu_id)
+                    .await
+                    .and_then(|buffer| buffer.inner())
+                {
+                    Ok(status) => {
+                        let _ = dialog_sender.send(ProfileComparisonDialogMsg::Status(status));
+                    }
+                    Err(err) => {
+                        debug!("could not fetch profile comparison status: {err:#}");
+                    }
+                }
+            }
+
+            tokio::time::sleep(PROFILE_COMPARISON_POLL_INTERVAL).await;
+        }
+    })
+}
+
 fn start_stats_update_loop(
     gpu_id: String,
     daemon_client: DaemonClient,
     sender: AsyncComponentSender<AppModel>,
+    to_msg: impl Fn(Rc<DeviceStats>) -> AppMsg + 'static,
 ) -> glib::JoinHandle<()> {
     debug!("spawning new stats update task with {STATS_POLL_INTERVAL_MS}ms interval");
     let duration = Duration::from_millis(STATS_POLL_INTERVAL_MS);
     relm4::spawn_local(async move {
+        // Polling is only used when the daemon can't push updates (older daemons or an embedded one)
+        match daemon_client.subscribe_stats(&gpu_id, duration).await {
+            Ok(mut subscription) => loop {
+                match subscription.next().await {
+                    Ok(stats) => {
+                        sender.input(to_msg(Rc::new(stats)));
+                    }
+                    Err(err) => {
+                        error!("stats subscription ended: {err:#}, falling back to polling");
+                        break;
+                    }
+                }
+            },
+            Err(err) => {
+                debug!("could not subscribe to stats: {err:#}, polling instead");
+            }
+        }
+
         loop {
             tokio::time::sleep(duration).await;
 
@@ -897,7 +1774,7 @@ fn start_stats_update_loop(
                 .and_then(|buffer| buffer.inner())
             {
                 Ok(stats) => {
-                    sender.input(AppMsg::Stats(Rc::new(stats)));
+                    sender.input(to_msg(Rc::new(stats)));
                 }
                 Err(err) => {
                     error!("could not fetch stats: {err:#}");
@@ -908,8 +1785,6 @@ fn start_stats_update_loop(
 }
 
 fn oc_toggled_dialog(enabled: bool, msg: &str) {
-    let enabled_text = if enabled { "enabled" } else { "disabled" };
-
     let child = gtk::Box::builder()
         .orientation(gtk::Orientation::Vertical)
         .spacing(5)
@@ -918,7 +1793,12 @@ fn oc_toggled_dialog(enabled: bool, msg: &str) {
         .margin_start(10)
         .margin_end(10)
         .build();
-    child.append(&gtk::Label::new(Some(&format!("Overclocking {enabled_text}. A system reboot is required to apply the changes.\nSystem message:"))));
+    let text = if enabled {
+        fl!("amd-oc-enabled-message")
+    } else {
+        fl!("amd-oc-disabled-message")
+    };
+    child.append(&gtk::Label::new(Some(&text)));
 
     let msg_label = gtk::Label::builder()
         .label(msg)
@@ -928,11 +1808,11 @@ fn oc_toggled_dialog(enabled: bool, msg: &str) {
     let msg_scrollable = gtk::ScrolledWindow::builder().child(&msg_label).build();
     child.append(&msg_scrollable);
 
-    let ok_button = gtk::Button::builder().label("OK").build();
+    let ok_button = gtk::Button::builder().label(fl!("ok")).build();
     child.append(&ok_button);
 
     let success_dialog = MessageDialog::builder()
-        .title("Overclock info")
+        .title(fl!("amd-oc-info"))
         .child(&child)
         .message_type(MessageType::Info)
         .build();
@@ -949,11 +1829,11 @@ fn oc_toggled_dialog(enabled: bool, msg: &str) {
 }
 
 fn confirmation_text(seconds_left: u64) -> String {
-    format!("Do you want to keep the new settings? (Reverting in {seconds_left} seconds)")
+    fl!("confirm-settings-description", seconds_left = seconds_left)
 }
 
 async fn toggle_overdrive(daemon_client: &DaemonClient, enable: bool, root: ApplicationWindow) {
-    let dialog = spinner_dialog(&root, "Regenerating initramfs (this may take a while)");
+    let dialog = spinner_dialog(&root, &fl!("regenerating-initramfs"));
     dialog.show();
 
     let result = if enable {
@@ -971,7 +1851,7 @@ async fn toggle_overdrive(daemon_client: &DaemonClient, enable: bool, root: Appl
     dialog.hide();
 
     match result {
-        Ok(msg) => oc_toggled_dialog(false, &msg),
+        Ok(msg) => oc_toggled_dialog(enable, &msg),
         Err(err) => {
             show_error(&root, &err);
         }
@@ -1016,14 +1896,18 @@ fn register_actions(sender: &AsyncComponentSender<AppModel>) {
 
     actions! {
         (ShowGraphsWindow, AppMsg::ShowGraphsWindow),
+        (ShowUndervoltAssistant, AppMsg::ShowUndervoltAssistant),
+        (ShowProfileComparison, AppMsg::ShowProfileComparison),
         (DumpVBios, AppMsg::DumpVBios),
         (DebugSnapshot, AppMsg::DebugSnapshot),
+        (ExportConfig, AppMsg::ExportConfig),
+        (ImportConfig, AppMsg::ImportConfig),
         (
             DisableOverdrive,
             AppMsg::ask_confirmation(
                 AppMsg::DisableOverdrive,
-                "Disable Overclocking",
-                "This will disable overclocking support on next reboot.",
+                fl!("disable-amd-oc"),
+                fl!("disable-amd-oc-description"),
                 gtk::ButtonsType::OkCancel,
             )
         ),
@@ -1031,8 +1915,8 @@ fn register_actions(sender: &AsyncComponentSender<AppModel>) {
             ResetConfig,
             AppMsg::ask_confirmation(
                 AppMsg::ResetConfig,
-                "Reset configuration",
-                "Are you sure you want to reset all GPU configuration?",
+                fl!("reset-config"),
+                fl!("reset-config-description"),
                 gtk::ButtonsType::YesNo,
             )
         ),
@@ -1043,13 +1927,31 @@ fn register_actions(sender: &AsyncComponentSender<AppModel>) {
 
 relm4::new_action_group!(AppActionGroup, "app");
 relm4::new_stateless_action!(ShowGraphsWindow, AppActionGroup, "show-graphs-window");
+relm4::new_stateless_action!(
+    ShowUndervoltAssistant,
+    AppActionGroup,
+    "show-undervolt-assistant"
+);
+relm4::new_stateless_action!(
+    ShowProfileComparison,
+    AppActionGroup,
+    "show-profile-comparison"
+);
 relm4::new_stateless_action!(DumpVBios, AppActionGroup, "dump-vbios");
 relm4::new_stateless_action!(DebugSnapshot, AppActionGroup, "generate-debug-snapshot");
+relm4::new_stateless_action!(ExportConfig, AppActionGroup, "export-config");
+relm4::new_stateless_action!(ImportConfig, AppActionGroup, "import-config");
 relm4::new_stateless_action!(DisableOverdrive, AppActionGroup, "disable-overdrive");
 relm4::new_stateless_action!(ResetConfig, AppActionGroup, "reset-config");
 
-async fn create_connection() -> anyhow::Result<(DaemonClient, Option<anyhow::Error>)> {
-    match DaemonClient::connect().await {
+async fn create_connection(
+    socket_path: Option<&Path>,
+) -> anyhow::Result<(DaemonClient, Option<anyhow::Error>)> {
+    let connection = match socket_path {
+        Some(path) => DaemonClient::connect_path(path).await,
+        None => DaemonClient::connect().await,
+    };
+    match connection {
         Ok(connection) => {
             debug!("Established daemon connection");
             Ok((connection, None))
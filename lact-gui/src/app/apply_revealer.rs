@@ -28,13 +28,13 @@ impl SimpleComponent for ApplyRevealer {
                 set_spacing: 5,
 
                 gtk::Button {
-                    set_label: "Apply",
+                    set_label: &fl!("apply"),
                     set_hexpand: true,
                     connect_clicked[sender] => move |_| { sender.output(super::AppMsg::ApplyChanges).unwrap(); },
                 },
 
                 gtk::Button {
-                    set_label: "Revert",
+                    set_label: &fl!("revert"),
                     connect_clicked[sender] => move |_| { sender.output(super::AppMsg::RevertChanges).unwrap(); },
                 },
             }
@@ -1,14 +1,35 @@
 pub(crate) mod plot;
 
-use self::plot::PlotData;
-use glib::Object;
+use self::plot::{Plot, PlotData};
+use super::gui_settings::{to_hex_color, GraphStyle, LineStyle, MAX_LINE_WIDTH};
+use anyhow::Context;
+use glib::{clone, Object};
 use gtk::{
+    gdk,
     glib::{self, subclass::types::ObjectSubclassIsExt},
-    prelude::WidgetExt,
+    prelude::*,
 };
-use lact_client::schema::DeviceStats;
+use lact_client::schema::{DeviceStats, StatsHistorySample};
+use plotters::style::{Color, Palette, Palette99};
+use std::path::Path;
 
-const GRAPH_WIDTH_SECONDS: i64 = 60;
+pub const GRAPH_WIDTH_SECONDS: i64 = 60;
+/// Time ranges that can be selected, matching the entries of the time range dropdown
+const TIME_RANGES_SECONDS: [i64; 4] = [GRAPH_WIDTH_SECONDS, 5 * 60, 60 * 60, 24 * 60 * 60];
+/// Number of series colors that can be picked, the other series use the default palette
+const SERIES_COLOR_COUNT: usize = 5;
+
+struct StyleControls {
+    line_style_dropdown: gtk::DropDown,
+    line_width_spin: gtk::SpinButton,
+    color_buttons: Vec<gtk::ColorButton>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum GraphsExportFormat {
+    Csv,
+    Png,
+}
 
 glib::wrapper! {
     pub struct GraphsWindow(ObjectSubclass<imp::GraphsWindow>)
@@ -18,7 +39,226 @@ glib::wrapper! {
 
 impl GraphsWindow {
     pub fn new() -> Self {
-        Object::builder().property("vram_clock_ratio", 1.0).build()
+        Object::builder()
+            .property("vram_clock_ratio", 1.0)
+            .property("time_range", GRAPH_WIDTH_SECONDS)
+            .build()
+    }
+
+    pub fn connect_export<F: Fn(GraphsExportFormat) + Clone + 'static>(&self, f: F) {
+        let imp = self.imp();
+        let csv_f = f.clone();
+        imp.export_csv_button
+            .connect_clicked(move |_| csv_f(GraphsExportFormat::Csv));
+        imp.export_png_button
+            .connect_clicked(move |_| f(GraphsExportFormat::Png));
+    }
+
+    pub fn connect_style_changed<F: Fn(GraphStyle) + 'static>(&self, f: F) {
+        *self.imp().style_changed.borrow_mut() = Some(Box::new(f));
+    }
+
+    /// Applies the style to the graphs and shows it in the style popover
+    #[allow(deprecated)]
+    pub fn set_style(&self, style: &GraphStyle) {
+        let imp = self.imp();
+        *imp.style.borrow_mut() = style.clone();
+        for plot in self.plots() {
+            plot.set_style(style.clone());
+        }
+
+        if let Some(controls) = imp.style_controls.get() {
+            imp.updating_style_controls.set(true);
+
+            let line_style_index = LineStyle::ALL
+                .iter()
+                .position(|line_style| *line_style == style.line_style)
+                .unwrap_or_default();
+            controls
+                .line_style_dropdown
+                .set_selected(line_style_index as u32);
+            controls
+                .line_width_spin
+                .set_value(f64::from(style.line_width));
+            for (index, button) in controls.color_buttons.iter().enumerate() {
+                let color = match style.series_color(index) {
+                    Some((r, g, b)) => format!("#{r:02x}{g:02x}{b:02x}"),
+                    None => default_series_color(index),
+                };
+                if let Ok(rgba) = gdk::RGBA::parse(color) {
+                    button.set_rgba(&rgba);
+                }
+            }
+
+            imp.updating_style_controls.set(false);
+        }
+    }
+
+    /// Changes the style after it was edited in the popover and reports it to be saved
+    fn edit_style(&self, edit: impl FnOnce(&mut GraphStyle)) {
+        let imp = self.imp();
+        if imp.updating_style_controls.get() {
+            return;
+        }
+
+        let mut style = imp.style.borrow().clone();
+        edit(&mut style);
+        self.set_style(&style);
+
+        if let Some(f) = &*imp.style_changed.borrow() {
+            f(style);
+        }
+    }
+
+    #[allow(deprecated)]
+    fn setup_style_controls(&self) {
+        let imp = self.imp();
+        imp.style_button.set_label(&fl!("graph-style"));
+
+        let line_styles: gtk::StringList = [
+            fl!("line-style-solid"),
+            fl!("line-style-dashed"),
+            fl!("line-style-dotted"),
+        ]
+        .iter()
+        .map(String::as_str)
+        .collect();
+        let line_style_dropdown = gtk::DropDown::builder().model(&line_styles).build();
+        line_style_dropdown.connect_selected_notify(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |dropdown| {
+                if let Some(line_style) = LineStyle::ALL.get(dropdown.selected() as usize) {
+                    window.edit_style(|style| style.line_style = *line_style);
+                }
+            }
+        ));
+
+        let line_width_spin = gtk::SpinButton::with_range(1.0, f64::from(MAX_LINE_WIDTH), 1.0);
+        line_width_spin.connect_value_changed(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |spin| {
+                let line_width = spin.value_as_int() as u32;
+                window.edit_style(|style| style.line_width = line_width);
+            }
+        ));
+
+        let colors_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+        let color_buttons: Vec<gtk::ColorButton> = (0..SERIES_COLOR_COUNT)
+            .map(|index| {
+                let button = gtk::ColorButton::new();
+                let tooltip = if index == 0 {
+                    fl!("accent-color")
+                } else {
+                    fl!("series-color", index = index + 1)
+                };
+                button.set_tooltip_text(Some(&tooltip));
+                button.connect_color_set(clone!(
+                    #[weak(rename_to = window)]
+                    self,
+                    move |button| {
+                        let color = to_hex_color(&button.rgba());
+                        window.edit_style(|style| {
+                            // The colors before the picked one are kept as they are shown
+                            while style.series_colors.len() <= index {
+                                let position = style.series_colors.len();
+                                style.series_colors.push(default_series_color(position));
+                            }
+                            style.series_colors[index] = color;
+                        });
+                    }
+                ));
+                colors_box.append(&button);
+                button
+            })
+            .collect();
+
+        let reset_button = gtk::Button::with_label(&fl!("reset-graph-style"));
+        reset_button.connect_clicked(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |_| window.edit_style(|style| *style = GraphStyle::default())
+        ));
+
+        let grid = gtk::Grid::builder()
+            .row_spacing(5)
+            .column_spacing(10)
+            .margin_top(5)
+            .margin_bottom(5)
+            .margin_start(5)
+            .margin_end(5)
+            .build();
+        let label = |text: String| {
+            gtk::Label::builder()
+                .label(text)
+                .halign(gtk::Align::Start)
+                .build()
+        };
+        grid.attach(&label(fl!("line-style")), 0, 0, 1, 1);
+        grid.attach(&line_style_dropdown, 1, 0, 1, 1);
+        grid.attach(&label(fl!("line-width")), 0, 1, 1, 1);
+        grid.attach(&line_width_spin, 1, 1, 1, 1);
+        grid.attach(&label(fl!("series-colors")), 0, 2, 1, 1);
+        grid.attach(&colors_box, 1, 2, 1, 1);
+        grid.attach(&reset_button, 0, 3, 2, 1);
+
+        let popover = gtk::Popover::builder().child(&grid).build();
+        imp.style_button.set_popover(Some(&popover));
+
+        let _ = imp.style_controls.set(StyleControls {
+            line_style_dropdown,
+            line_width_spin,
+            color_buttons,
+        });
+        let style = imp.style.borrow().clone();
+        self.set_style(&style);
+    }
+
+    fn plots(&self) -> [&Plot; 6] {
+        let imp = self.imp();
+        [
+            &*imp.temperature_plot,
+            &*imp.fan_plot,
+            &*imp.clockspeed_plot,
+            &*imp.power_plot,
+            &*imp.memory_plot,
+            &*imp.voltage_plot,
+        ]
+    }
+
+    /// All points currently shown in the graphs
+    pub fn to_csv(&self) -> String {
+        let imp = self.imp();
+        let mut out = String::from("time,graph,series,value\n");
+        for plot in [
+            &imp.temperature_plot,
+            &imp.fan_plot,
+            &imp.clockspeed_plot,
+            &imp.power_plot,
+            &imp.memory_plot,
+            &imp.voltage_plot,
+        ] {
+            plot.data().write_csv(&plot.title(), &mut out);
+        }
+        out
+    }
+
+    /// Saves the graphs as they are currently shown
+    pub fn save_png(&self, path: &Path) -> anyhow::Result<()> {
+        let grid = &*self.imp().plots_grid;
+        let paintable = gtk::WidgetPaintable::new(Some(grid));
+        let snapshot = gtk::Snapshot::new();
+        paintable.snapshot(&snapshot, f64::from(grid.width()), f64::from(grid.height()));
+
+        let node = snapshot
+            .to_node()
+            .context("The graphs have not been drawn yet")?;
+        let renderer = self.renderer().context("The graphs window is not shown")?;
+        let texture: gdk::Texture = renderer.render_texture(node, None);
+        texture
+            .save_to_png(path)
+            .context("Could not save the image")
     }
 
     pub fn set_stats(&self, stats: &DeviceStats) {
@@ -28,13 +268,15 @@ impl GraphsWindow {
         let mut clockspeed_plot = imp.clockspeed_plot.data_mut();
         let mut power_plot = imp.power_plot.data_mut();
         let mut fan_plot = imp.fan_plot.data_mut();
+        let mut memory_plot = imp.memory_plot.data_mut();
+        let mut voltage_plot = imp.voltage_plot.data_mut();
 
         let throttling_plots = [&mut temperature_plot, &mut clockspeed_plot, &mut power_plot];
         match &stats.throttle_info {
             Some(throttle_info) => {
                 if throttle_info.is_empty() {
                     for plot in throttling_plots {
-                        plot.push_throttling("No", false);
+                        plot.push_throttling(&fl!("no"), false);
                     }
                 } else {
                     let type_text: Vec<String> = throttle_info
@@ -53,7 +295,7 @@ impl GraphsWindow {
             }
             None => {
                 for plot in throttling_plots {
-                    plot.push_throttling("Unknown", false);
+                    plot.push_throttling(&fl!("unknown"), false);
                 }
             }
         }
@@ -62,51 +304,159 @@ impl GraphsWindow {
             temperature_plot.push_line_series(name, value.current.unwrap_or(0.0) as f64);
         }
 
-        if let Some(average) = stats.power.average {
-            power_plot.push_line_series("Average", average);
+        let smoothed = stats.smoothed.unwrap_or_default();
+
+        if let Some(average) = smoothed.power_average.or(stats.power.average) {
+            power_plot.push_line_series(&fl!("series-average"), average);
         }
-        if let Some(current) = stats.power.current {
-            power_plot.push_line_series("Current", current);
+        if let Some(current) = smoothed.power_current.or(stats.power.current) {
+            power_plot.push_line_series(&fl!("series-current"), current);
         }
         if let Some(limit) = stats.power.cap_current {
-            power_plot.push_line_series("Limit", limit);
+            power_plot.push_line_series(&fl!("series-limit"), limit);
         }
 
-        if let Some(point) = stats.clockspeed.gpu_clockspeed {
-            clockspeed_plot.push_line_series("GPU (Avg)", point as f64);
+        if let Some(point) = smoothed
+            .gpu_clockspeed
+            .or(stats.clockspeed.gpu_clockspeed.map(|point| point as f64))
+        {
+            clockspeed_plot.push_line_series(&fl!("series-gpu-average"), point);
         }
         if let Some(point) = stats.clockspeed.current_gfxclk {
-            clockspeed_plot.push_line_series("GPU (Trgt)", point as f64);
+            clockspeed_plot.push_line_series(&fl!("series-gpu-target"), point as f64);
         }
-        if let Some(point) = stats.clockspeed.vram_clockspeed {
-            clockspeed_plot.push_line_series("VRAM", point as f64 * self.vram_clock_ratio());
+        if let Some(point) = smoothed
+            .vram_clockspeed
+            .or(stats.clockspeed.vram_clockspeed.map(|point| point as f64))
+        {
+            clockspeed_plot.push_line_series("VRAM", point * self.vram_clock_ratio());
         }
 
         if let Some(max_speed) = stats.fan.speed_max {
-            fan_plot.push_line_series("Maximum", max_speed as f64);
+            fan_plot.push_line_series(&fl!("series-maximum"), max_speed as f64);
         }
         if let Some(min_speed) = stats.fan.speed_min {
-            fan_plot.push_line_series("Minimum", min_speed as f64);
+            fan_plot.push_line_series(&fl!("series-minimum"), min_speed as f64);
         }
 
         if let Some(current_speed) = stats.fan.speed_current {
-            fan_plot.push_line_series("Current", current_speed as f64);
+            fan_plot.push_line_series(&fl!("series-current"), current_speed as f64);
         }
 
         if let Some(pwm) = stats.fan.pwm_current {
-            fan_plot
-                .push_secondary_line_series("Percentage", (pwm as f64 / u8::MAX as f64) * 100.0);
+            fan_plot.push_secondary_line_series(
+                &fl!("series-percentage"),
+                (pwm as f64 / u8::MAX as f64) * 100.0,
+            );
         }
 
-        temperature_plot.trim_data(GRAPH_WIDTH_SECONDS);
-        clockspeed_plot.trim_data(GRAPH_WIDTH_SECONDS);
-        power_plot.trim_data(GRAPH_WIDTH_SECONDS);
-        fan_plot.trim_data(GRAPH_WIDTH_SECONDS);
+        let mib = |bytes: u64| (bytes / 1024 / 1024) as f64;
+        if let Some(used) = stats.vram.used {
+            memory_plot.push_line_series("VRAM", mib(used));
+        }
+        if let Some(used) = stats.vram.visible_used {
+            memory_plot.push_line_series(&fl!("series-cpu-visible-vram"), mib(used));
+        }
+        if let Some(used) = stats.vram.gtt_used {
+            memory_plot.push_line_series("GTT", mib(used));
+        }
+        if let Some(busy) = stats.busy_percent {
+            memory_plot.push_secondary_line_series(&fl!("series-gpu-usage"), f64::from(busy));
+        }
+        for (engine, usage) in &stats.engine_usage {
+            memory_plot.push_secondary_line_series(&engine_series_name(engine), *usage);
+        }
+
+        if let Some(voltage) = stats.voltage.gpu {
+            voltage_plot.push_line_series("GPU", voltage as f64);
+        }
+        if let Some(voltage) = stats.voltage.northbridge {
+            voltage_plot.push_line_series(&fl!("series-northbridge"), voltage as f64);
+        }
+
+        let time_range = self.time_range();
+        temperature_plot.trim_data(time_range);
+        clockspeed_plot.trim_data(time_range);
+        power_plot.trim_data(time_range);
+        fan_plot.trim_data(time_range);
+        memory_plot.trim_data(time_range);
+        voltage_plot.trim_data(time_range);
+
+        imp.temperature_plot.queue_draw();
+        imp.clockspeed_plot.queue_draw();
+        imp.power_plot.queue_draw();
+        imp.fan_plot.queue_draw();
+        imp.memory_plot.queue_draw();
+        imp.voltage_plot.queue_draw();
+    }
+
+    /// Fills the graphs with samples recorded by the daemon before the window was opened
+    pub fn set_history(&self, samples: &[StatsHistorySample]) {
+        let imp = self.imp();
+
+        let mut temperature_plot = imp.temperature_plot.data_mut();
+        let mut clockspeed_plot = imp.clockspeed_plot.data_mut();
+        let mut power_plot = imp.power_plot.data_mut();
+        let mut fan_plot = imp.fan_plot.data_mut();
+        let mut memory_plot = imp.memory_plot.data_mut();
+
+        for sample in samples {
+            let Some(time) = chrono::DateTime::from_timestamp_millis(sample.timestamp) else {
+                continue;
+            };
+            let time = time.with_timezone(&chrono::Local).naive_local();
+
+            for (name, value) in &sample.temperatures {
+                temperature_plot.push_line_series_with_time(name, f64::from(*value), time);
+            }
+            if let Some(power) = sample.power {
+                power_plot.push_line_series_with_time(
+                    &fl!("series-current"),
+                    f64::from(power),
+                    time,
+                );
+            }
+            if let Some(clockspeed) = sample.gpu_clockspeed {
+                clockspeed_plot.push_line_series_with_time(
+                    &fl!("series-gpu-average"),
+                    f64::from(clockspeed),
+                    time,
+                );
+            }
+            if let Some(clockspeed) = sample.vram_clockspeed {
+                clockspeed_plot.push_line_series_with_time(
+                    "VRAM",
+                    f64::from(clockspeed) * self.vram_clock_ratio(),
+                    time,
+                );
+            }
+            if let Some(speed) = sample.fan_speed {
+                fan_plot.push_line_series_with_time(&fl!("series-current"), f64::from(speed), time);
+            }
+            if let Some(used) = sample.vram_used {
+                memory_plot.push_line_series_with_time("VRAM", f64::from(used), time);
+            }
+            if let Some(busy) = sample.busy_percent {
+                memory_plot.push_secondary_line_series_with_time(
+                    &fl!("series-gpu-usage"),
+                    f64::from(busy),
+                    time,
+                );
+            }
+        }
+
+        let time_range = self.time_range();
+        temperature_plot.trim_data(time_range);
+        clockspeed_plot.trim_data(time_range);
+        power_plot.trim_data(time_range);
+        fan_plot.trim_data(time_range);
+        memory_plot.trim_data(time_range);
 
         imp.temperature_plot.queue_draw();
         imp.clockspeed_plot.queue_draw();
         imp.power_plot.queue_draw();
         imp.fan_plot.queue_draw();
+        imp.memory_plot.queue_draw();
     }
 
     pub fn clear(&self) {
@@ -115,11 +465,15 @@ impl GraphsWindow {
         *imp.clockspeed_plot.data_mut() = PlotData::default();
         *imp.power_plot.data_mut() = PlotData::default();
         *imp.fan_plot.data_mut() = PlotData::default();
+        *imp.memory_plot.data_mut() = PlotData::default();
+        *imp.voltage_plot.data_mut() = PlotData::default();
 
         imp.temperature_plot.queue_draw();
         imp.clockspeed_plot.queue_draw();
         imp.power_plot.queue_draw();
         imp.fan_plot.queue_draw();
+        imp.memory_plot.queue_draw();
+        imp.voltage_plot.queue_draw();
     }
 }
 
@@ -129,10 +483,27 @@ impl Default for GraphsWindow {
     }
 }
 
+/// Names the engines that are most often looked at, others (e.g. `dma` or `jpeg`) keep the name from the driver
+fn engine_series_name(engine: &str) -> String {
+    match engine {
+        "gfx" => fl!("series-engine-gfx"),
+        "compute" => fl!("series-engine-compute"),
+        "enc" => fl!("series-engine-encode"),
+        "dec" => fl!("series-engine-decode"),
+        _ => engine.to_owned(),
+    }
+}
+
+fn default_series_color(index: usize) -> String {
+    let (r, g, b) = Palette99::pick(index).rgb();
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
 mod imp {
-    use super::plot::Plot;
+    use super::{plot::Plot, StyleControls, TIME_RANGES_SECONDS};
+    use crate::app::gui_settings::GraphStyle;
     use gtk::{
-        glib::{self, subclass::InitializingObject, Properties},
+        glib::{self, clone, subclass::InitializingObject, Properties},
         prelude::*,
         subclass::{
             prelude::*,
@@ -140,7 +511,7 @@ mod imp {
         },
         CompositeTemplate,
     };
-    use std::cell::Cell;
+    use std::cell::{Cell, OnceCell, RefCell};
 
     #[derive(CompositeTemplate, Default, Properties)]
     #[properties(wrapper_type = super::GraphsWindow)]
@@ -154,9 +525,36 @@ mod imp {
         pub(super) power_plot: TemplateChild<Plot>,
         #[template_child]
         pub(super) fan_plot: TemplateChild<Plot>,
+        #[template_child]
+        pub(super) memory_plot: TemplateChild<Plot>,
+        #[template_child]
+        pub(super) voltage_plot: TemplateChild<Plot>,
+        #[template_child]
+        pub(super) plots_grid: TemplateChild<gtk::Grid>,
+        #[template_child]
+        time_range_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        time_range_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        reset_zoom_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) export_csv_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) export_png_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) style_button: TemplateChild<gtk::MenuButton>,
+
+        pub(super) style: RefCell<GraphStyle>,
+        pub(super) style_controls: OnceCell<StyleControls>,
+        /// Set while the style controls are updated from the style, so that it isn't reported as edited
+        pub(super) updating_style_controls: Cell<bool>,
+        pub(super) style_changed: RefCell<Option<Box<dyn Fn(GraphStyle)>>>,
 
         #[property(get, set)]
         pub vram_clock_ratio: Cell<f64>,
+        /// How many seconds of data are kept in the graphs
+        #[property(get, set)]
+        pub time_range: Cell<i64>,
     }
 
     #[glib::object_subclass]
@@ -177,7 +575,75 @@ mod imp {
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for GraphsWindow {}
+    impl ObjectImpl for GraphsWindow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.set_title(Some(&fl!("historical-data")));
+            self.time_range_label.set_label(&fl!("time-range"));
+            let time_ranges: gtk::StringList = [
+                fl!("time-range-minutes", minutes = 1),
+                fl!("time-range-minutes", minutes = 5),
+                fl!("time-range-hours", hours = 1),
+                fl!("time-range-hours", hours = 24),
+            ]
+            .iter()
+            .map(String::as_str)
+            .collect();
+            self.time_range_dropdown.set_model(Some(&time_ranges));
+            self.reset_zoom_button.set_label(&fl!("reset-zoom"));
+            self.reset_zoom_button
+                .set_tooltip_text(Some(&fl!("reset-zoom-description")));
+            self.export_csv_button.set_label(&fl!("export-csv"));
+            self.export_png_button.set_label(&fl!("export-png"));
+            obj.setup_style_controls();
+
+            self.temperature_plot.set_title(fl!("temperature-graph"));
+            self.fan_plot.set_title(fl!("fan-speed-graph"));
+            self.clockspeed_plot.set_title(fl!("clockspeed-graph"));
+            self.power_plot.set_title(fl!("power-usage-graph"));
+            self.memory_plot.set_title(fl!("memory-usage-graph"));
+            self.voltage_plot.set_title(fl!("voltage-graph"));
+
+            // Zooming and panning one graph moves all of them
+            for plot in [
+                &self.fan_plot,
+                &self.clockspeed_plot,
+                &self.power_plot,
+                &self.memory_plot,
+                &self.voltage_plot,
+            ] {
+                for property in ["zoom", "pan"] {
+                    self.temperature_plot
+                        .bind_property(property, &**plot, property)
+                        .bidirectional()
+                        .sync_create()
+                        .build();
+                }
+            }
+
+            let plot = self.temperature_plot.get();
+            self.reset_zoom_button.connect_clicked(clone!(
+                #[weak]
+                plot,
+                move |_| {
+                    plot.set_zoom(1.0);
+                    plot.set_pan(0.0);
+                }
+            ));
+
+            self.time_range_dropdown.connect_selected_notify(clone!(
+                #[weak]
+                obj,
+                move |dropdown| {
+                    if let Some(range) = TIME_RANGES_SECONDS.get(dropdown.selected() as usize) {
+                        obj.set_time_range(*range);
+                    }
+                }
+            ));
+        }
+    }
 
     impl WidgetImpl for GraphsWindow {}
     impl WindowImpl for GraphsWindow {}
@@ -1,13 +1,16 @@
 use chrono::NaiveDateTime;
 use glib::Properties;
 
-use gtk::{glib, prelude::*, subclass::prelude::*};
+use gtk::{glib, glib::clone, prelude::*, subclass::prelude::*};
 
 use std::cell::Cell;
 use std::cell::RefCell;
 use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::rc::Rc;
 
-use super::render_thread::{RenderRequest, RenderThread};
+use super::render_thread::{PlotTheme, RenderRequest, RenderThread};
+use crate::app::gui_settings::GraphStyle;
 
 #[derive(Properties, Default)]
 #[properties(wrapper_type = super::Plot)]
@@ -22,8 +25,17 @@ pub struct Plot {
     y_label_area_relative_size: Cell<f64>,
     #[property(get, set)]
     secondary_y_label_area_relative_size: Cell<f64>,
+    /// Visible part of the time range, between 0 and 1
+    #[property(get, set)]
+    zoom: Cell<f64>,
+    /// How far the visible part is moved back from the latest data, relative to the time range
+    #[property(get, set)]
+    pan: Cell<f64>,
     pub(super) data: RefCell<PlotData>,
     pub(super) dirty: Cell<bool>,
+    pub(super) style: RefCell<GraphStyle>,
+    /// Theme of the last render request, to redraw when it changes
+    theme: Cell<PlotTheme>,
     render_thread: RenderThread,
 }
 
@@ -43,6 +55,52 @@ impl ObjectImpl for Plot {
         obj.set_height_request(250);
         obj.set_hexpand(true);
         obj.set_vexpand(true);
+        obj.set_zoom(1.0);
+
+        let redraw = |obj: &super::Plot| {
+            obj.imp().dirty.set(true);
+            obj.queue_draw();
+        };
+        obj.connect_zoom_notify(redraw);
+        obj.connect_pan_notify(redraw);
+
+        let scroll_controller =
+            gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+        scroll_controller.connect_scroll(clone!(
+            #[weak]
+            obj,
+            #[upgrade_or]
+            glib::Propagation::Proceed,
+            move |_, _, dy| {
+                let zoom = (obj.zoom() * 1.25f64.powf(dy)).clamp(0.01, 1.0);
+                obj.set_zoom(zoom);
+                obj.set_pan(obj.pan().min(1.0 - zoom));
+                glib::Propagation::Stop
+            }
+        ));
+        obj.add_controller(scroll_controller);
+
+        let drag_start_pan = Rc::new(Cell::new(0.0));
+        let drag_gesture = gtk::GestureDrag::new();
+        drag_gesture.connect_drag_begin(clone!(
+            #[weak]
+            obj,
+            #[strong]
+            drag_start_pan,
+            move |_, _, _| {
+                drag_start_pan.set(obj.pan());
+            }
+        ));
+        drag_gesture.connect_drag_update(clone!(
+            #[weak]
+            obj,
+            move |_, dx, _| {
+                let width = f64::from(obj.width().max(1));
+                let pan = drag_start_pan.get() + dx / width * obj.zoom();
+                obj.set_pan(pan.clamp(0.0, 1.0 - obj.zoom()));
+            }
+        ));
+        obj.add_controller(drag_gesture);
     }
 }
 
@@ -61,7 +119,17 @@ impl WidgetImpl for Plot {
             .map(|texture| (texture.width() as u32, texture.height() as u32) != (width, height))
             .unwrap_or(true);
 
-        if self.dirty.replace(false) || size_changed {
+        // A light text color means that a dark theme (or its dark variant) is used
+        #[allow(deprecated)]
+        let text_color = self.obj().style_context().color();
+        let luminance =
+            0.2126 * text_color.red() + 0.7152 * text_color.green() + 0.0722 * text_color.blue();
+        let theme = PlotTheme {
+            dark: luminance > 0.5,
+        };
+        let theme_changed = self.theme.replace(theme) != theme;
+
+        if self.dirty.replace(false) || size_changed || theme_changed {
             self.render_thread.replace_render_request(RenderRequest {
                 data: self.data.borrow().clone(),
                 width,
@@ -74,6 +142,10 @@ impl WidgetImpl for Plot {
                     .secondary_y_label_area_relative_size
                     .get(),
                 supersample_factor: 4,
+                zoom: self.zoom.get(),
+                pan: self.pan.get(),
+                theme,
+                style: self.style.borrow().clone(),
             });
         }
 
@@ -145,6 +217,22 @@ impl PlotData {
             .map(|(time, (name, point))| (*time, name.as_str(), *point))
     }
 
+    /// Writes all points as `time,graph,series,value` rows
+    pub fn write_csv(&self, graph: &str, out: &mut String) {
+        for (name, data) in self
+            .line_series_iter()
+            .chain(self.secondary_line_series_iter())
+        {
+            for (time, value) in data {
+                let Some(time) = chrono::DateTime::from_timestamp_millis(*time) else {
+                    continue;
+                };
+                let time = time.naive_utc().format("%Y-%m-%d %H:%M:%S%.3f");
+                writeln!(out, "{time},{graph},{name},{value}").unwrap();
+            }
+        }
+    }
+
     pub fn trim_data(&mut self, last_seconds: i64) {
         // Limit data to N seconds
         for data in self.line_series.values_mut() {
@@ -181,3 +269,29 @@ impl PlotData {
             .retain(|(time_point, _)| ((maximum_point - *time_point) / 1000) < last_seconds);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PlotData;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn write_csv() {
+        let time = NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_milli_opt(12, 30, 0, 250)
+            .unwrap();
+
+        let mut data = PlotData::default();
+        data.push_line_series_with_time("Current", 150.5, time);
+        data.push_secondary_line_series_with_time("Percentage", 40.0, time);
+
+        let mut out = String::new();
+        data.write_csv("Fan speed", &mut out);
+        assert_eq!(
+            out,
+            "2024-05-01 12:30:00.250,Fan speed,Current,150.5\n\
+             2024-05-01 12:30:00.250,Fan speed,Percentage,40\n"
+        );
+    }
+}
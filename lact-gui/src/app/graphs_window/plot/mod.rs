@@ -3,11 +3,15 @@ mod imp;
 mod render_thread;
 mod to_texture_ext;
 
-use std::cell::RefMut;
+use crate::app::gui_settings::GraphStyle;
+use std::cell::{Ref, RefMut};
 
 pub use imp::PlotData;
 
-use gtk::glib::{self, subclass::types::ObjectSubclassIsExt};
+use gtk::{
+    glib::{self, subclass::types::ObjectSubclassIsExt},
+    prelude::WidgetExt,
+};
 
 glib::wrapper! {
     pub struct Plot(ObjectSubclass<imp::Plot>)
@@ -19,4 +23,14 @@ impl Plot {
         self.imp().dirty.set(true);
         self.imp().data.borrow_mut()
     }
+
+    pub fn data(&self) -> Ref<'_, PlotData> {
+        self.imp().data.borrow()
+    }
+
+    pub fn set_style(&self, style: GraphStyle) {
+        *self.imp().style.borrow_mut() = style;
+        self.imp().dirty.set(true);
+        self.queue_draw();
+    }
 }
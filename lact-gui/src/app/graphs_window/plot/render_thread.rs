@@ -1,13 +1,14 @@
-use super::cubic_spline::cubic_spline_interpolation;
+use super::cubic_spline::{cubic_spline_interpolation, CubicSplineSegment, TimePeriod};
 use super::to_texture_ext::ToTextureExt;
 use super::PlotData;
+use crate::app::gui_settings::{GraphStyle, LineStyle, MAX_LINE_WIDTH};
 use anyhow::Context;
 use cairo::{Context as CairoContext, ImageSurface};
 
 use gtk::gdk::MemoryTexture;
 use itertools::Itertools;
 use plotters::prelude::*;
-use plotters::style::colors::full_palette::DEEPORANGE_100;
+use plotters::style::colors::full_palette::{DEEPORANGE_100, DEEPORANGE_900};
 use plotters::style::RelativeSize;
 use plotters_cairo::CairoBackend;
 use std::cmp::{max, min};
@@ -35,6 +36,46 @@ pub struct RenderRequest {
     pub height: u32,
 
     pub supersample_factor: u32,
+
+    /// Visible part of the time range, between 0 and 1
+    pub zoom: f64,
+    /// How far the visible part is moved back from the latest data, relative to the time range
+    pub pan: f64,
+
+    pub theme: PlotTheme,
+    pub style: GraphStyle,
+}
+
+/// Colors of the plot background and text, following the dark or light variant of the GTK theme
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PlotTheme {
+    pub dark: bool,
+}
+
+impl PlotTheme {
+    fn background(self) -> RGBColor {
+        if self.dark {
+            RGBColor(0x24, 0x24, 0x24)
+        } else {
+            WHITE
+        }
+    }
+
+    fn foreground(self) -> RGBColor {
+        if self.dark {
+            RGBColor(0xde, 0xdd, 0xda)
+        } else {
+            BLACK
+        }
+    }
+
+    fn throttling(self) -> RGBAColor {
+        if self.dark {
+            DEEPORANGE_900.mix(0.4)
+        } else {
+            DEEPORANGE_100.mix(1.0)
+        }
+    }
 }
 
 #[derive(Default)]
@@ -187,6 +228,19 @@ impl RenderRequest {
         min(self.height, self.width) as f64 * ratio
     }
 
+    /// Color of the series at the given position of the plot, `fallback` is its index in the default palette
+    fn series_color(&self, position: usize, fallback: usize) -> RGBColor {
+        let (r, g, b) = self
+            .style
+            .series_color(position)
+            .unwrap_or_else(|| Palette99::pick(fallback).rgb());
+        RGBColor(r, g, b)
+    }
+
+    fn line_width(&self) -> u32 {
+        self.style.line_width.clamp(1, MAX_LINE_WIDTH) * self.supersample_factor
+    }
+
     // Method to handle the actual drawing of the chart.
     pub fn draw<'a, DB>(&self, backend: DB) -> anyhow::Result<()>
     where
@@ -211,18 +265,44 @@ impl RenderRequest {
         let end_date_main = data
             .line_series_iter()
             .map(|(_, value)| value)
-            .filter_map(|data| Some(data.first()?.0))
+            .filter_map(|data| Some(data.last()?.0))
             .max()
             .unwrap_or_default();
         let end_date_secondary = data
             .secondary_line_series_iter()
             .map(|(_, value)| value)
-            .filter_map(|data| Some(data.first()?.0))
+            .filter_map(|data| Some(data.last()?.0))
             .max()
             .unwrap_or_default();
 
         let start_date = max(start_date_main, start_date_secondary);
-        let end_date = max(end_date_main, end_date_secondary);
+        let end_date = max(
+            max(end_date_main, end_date_secondary),
+            start_date + 60 * 1000,
+        );
+
+        // Only show the zoomed in part of the time range
+        let full_range = (end_date - start_date) as f64;
+        let end_date = end_date - (full_range * self.pan.clamp(0.0, 1.0)) as i64;
+        let start_date = end_date - (full_range * self.zoom.clamp(0.01, 1.0)) as i64;
+
+        // Longer time ranges don't need a point for every millisecond
+        let interpolation_step = usize::try_from(
+            (end_date - start_date) / i64::from(self.width * self.supersample_factor).max(1),
+        )
+        .unwrap_or(1)
+        .max(1);
+        let visible_segments =
+            move |((first_time, second_time), segment): (TimePeriod, CubicSplineSegment)| {
+                (first_time < end_date && second_time > start_date)
+                    .then(move || {
+                        (max(first_time, start_date)..min(second_time, end_date))
+                            .step_by(interpolation_step)
+                            .map(move |current_date| (current_date, segment.evaluate(current_date)))
+                    })
+                    .into_iter()
+                    .flatten()
+            };
 
         // Calculate the maximum value for the y-axis.
         let mut maximum_value = data
@@ -237,7 +317,9 @@ impl RenderRequest {
             maximum_value = 100.0f64;
         }
 
-        root.fill(&WHITE)?; // Fill the background with white color.
+        let background = self.theme.background();
+        let foreground = self.theme.foreground();
+        root.fill(&background)?;
 
         let y_label_area_relative_size =
             if data.line_series.is_empty() && !data.secondary_line_series.is_empty() {
@@ -256,16 +338,10 @@ impl RenderRequest {
             .margin(RelativeSize::Smaller(0.045))
             .caption(
                 self.title.as_str(),
-                ("sans-serif", RelativeSize::Smaller(0.08)),
+                ("sans-serif", RelativeSize::Smaller(0.08), &foreground),
             )
-            .build_cartesian_2d(
-                start_date..max(end_date, start_date + 60 * 1000),
-                0f64..maximum_value,
-            )?
-            .set_secondary_coord(
-                start_date..max(end_date, start_date + 60 * 1000),
-                0.0..100.0,
-            );
+            .build_cartesian_2d(start_date..end_date, 0f64..maximum_value)?
+            .set_secondary_coord(start_date..end_date, 0.0..100.0);
 
         // Configure the x-axis and y-axis mesh.
         chart
@@ -277,7 +353,10 @@ impl RenderRequest {
             .y_label_formatter(&|x| format!("{x}{}", &self.value_suffix))
             .x_labels(5)
             .y_labels(10)
-            .label_style(("sans-serif", RelativeSize::Smaller(0.08)))
+            .label_style(("sans-serif", RelativeSize::Smaller(0.08), &foreground))
+            .axis_style(foreground)
+            .bold_line_style(foreground.mix(0.1))
+            .light_line_style(foreground.mix(0.05))
             .draw()
             .context("Failed to draw mesh")?;
 
@@ -286,7 +365,8 @@ impl RenderRequest {
             .configure_secondary_axes()
             .y_label_formatter(&|x: &f64| format!("{x}{}", self.secondary_value_suffix.as_str()))
             .y_labels(10)
-            .label_style(("sans-serif", RelativeSize::Smaller(0.08)))
+            .label_style(("sans-serif", RelativeSize::Smaller(0.08), &foreground))
+            .axis_style(foreground)
             .draw()
             .context("Failed to draw mesh")?;
 
@@ -311,60 +391,82 @@ impl RenderRequest {
                             Err(((start1, end1), (start2, end2)))
                         }
                     })
+                    .map(|(start_time, end_time)| {
+                        (max(start_time, start_date), min(end_time, end_date))
+                    })
+                    .filter(|(start_time, end_time)| start_time < end_time)
                     .map(|(start_time, end_time)| {
                         Rectangle::new(
                             [(start_time, 0f64), (end_time, maximum_value)],
-                            DEEPORANGE_100.filled(),
+                            self.theme.throttling().filled(),
                         )
                     }),
             )
             .context("Failed to draw throttling histogram")?;
 
+        let line_width = self.line_width();
+        // Dashes and gaps scale with the line width
+        let (dash_size, dash_spacing) = match self.style.line_style {
+            LineStyle::Solid => (0, 0),
+            LineStyle::Dashed => (line_width * 4, line_width * 2),
+            LineStyle::Dotted => (line_width, line_width * 2),
+        };
+
         // Draw the main line series using cubic spline interpolation.
         for (idx, (caption, data)) in (0..).zip(data.line_series_iter()) {
-            chart
-                .draw_series(LineSeries::new(
-                    cubic_spline_interpolation(data.iter())
-                        .into_iter()
-                        .flat_map(|((first_time, second_time), segment)| {
-                            // Interpolate in intervals of one millisecond.
-                            (first_time..second_time).map(move |current_date| {
-                                (current_date, segment.evaluate(current_date))
-                            })
-                        }),
-                    Palette99::pick(idx).stroke_width(8),
-                ))
+            let color = self.series_color(idx, idx);
+            let points: Vec<_> = cubic_spline_interpolation(data.iter())
+                .into_iter()
+                .flat_map(visible_segments)
+                .collect();
+            let line_style = color.stroke_width(line_width);
+
+            let series =
+                match self.style.line_style {
+                    LineStyle::Solid => chart.draw_series(LineSeries::new(points, line_style)),
+                    LineStyle::Dashed | LineStyle::Dotted => chart.draw_series(
+                        DashedLineSeries::new(points, dash_size, dash_spacing, line_style),
+                    ),
+                };
+            series
                 .context("Failed to draw series")?
                 .label(caption)
                 .legend(move |(x, y)| {
                     let offset = self.relative_size(0.04) as i32;
                     Rectangle::new(
                         [(x - offset, y - offset), (x + offset, y + offset)],
-                        Palette99::pick(idx).filled(),
+                        color.filled(),
                     )
                 });
         }
 
         // Draw the secondary line series on the secondary y-axis.
+        // Custom colors continue after the ones of the main series
+        let main_series_count = data.line_series.len();
         for (idx, (caption, data)) in (0..).zip(data.secondary_line_series_iter()) {
-            chart
-                .draw_secondary_series(LineSeries::new(
-                    cubic_spline_interpolation(data.iter())
-                        .into_iter()
-                        .flat_map(|((first_time, second_time), segment)| {
-                            (first_time..second_time).map(move |current_date| {
-                                (current_date, segment.evaluate(current_date))
-                            })
-                        }),
-                    Palette99::pick(idx + 10).stroke_width(8),
-                ))
+            let color = self.series_color(main_series_count + idx, idx + 10);
+            let points: Vec<_> = cubic_spline_interpolation(data.iter())
+                .into_iter()
+                .flat_map(visible_segments)
+                .collect();
+            let line_style = color.stroke_width(line_width);
+
+            let series = match self.style.line_style {
+                LineStyle::Solid => {
+                    chart.draw_secondary_series(LineSeries::new(points, line_style))
+                }
+                LineStyle::Dashed | LineStyle::Dotted => chart.draw_secondary_series(
+                    DashedLineSeries::new(points, dash_size, dash_spacing, line_style),
+                ),
+            };
+            series
                 .context("Failed to draw series")?
                 .label(caption)
                 .legend(move |(x, y)| {
                     let offset = self.relative_size(0.04) as i32;
                     Rectangle::new(
                         [(x - offset, y - offset), (x + offset, y + offset)],
-                        Palette99::pick(idx + 10).filled(),
+                        color.filled(),
                     )
                 });
         }
@@ -373,11 +475,11 @@ impl RenderRequest {
         chart
             .configure_series_labels()
             .margin(RelativeSize::Smaller(0.10))
-            .label_font(("sans-serif", RelativeSize::Smaller(0.08)))
+            .label_font(("sans-serif", RelativeSize::Smaller(0.08), &foreground))
             .position(SeriesLabelPosition::LowerRight)
             .legend_area_size(RelativeSize::Smaller(0.045))
-            .background_style(WHITE.mix(0.8))
-            .border_style(BLACK)
+            .background_style(background.mix(0.8))
+            .border_style(foreground)
             .draw()
             .context("Failed to draw series labels")?;
 
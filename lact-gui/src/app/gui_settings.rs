@@ -0,0 +1,152 @@
+//! Settings of the GUI itself, such as the look of the graphs.
+//! They are stored per user in `~/.config/lact/gui.json` instead of the daemon config, as they don't affect the GPU.
+use anyhow::Context;
+use gtk::{gdk, glib};
+use serde::{Deserialize, Serialize};
+use std::{cell::Cell, fs, path::PathBuf};
+use tracing::warn;
+
+const SETTINGS_FILE_NAME: &str = "gui.json";
+pub const DEFAULT_LINE_WIDTH: u32 = 2;
+pub const MAX_LINE_WIDTH: u32 = 5;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct GuiSettings {
+    pub graphs: GraphStyle,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct GraphStyle {
+    /// Colors of the series of each graph in order, as `#rrggbb`. Series without a color use the default palette.
+    /// The first color is the accent, which is also used for the fan curve editor.
+    pub series_colors: Vec<String>,
+    pub line_style: LineStyle,
+    /// Width of the lines in pixels
+    pub line_width: u32,
+}
+
+impl Default for GraphStyle {
+    fn default() -> Self {
+        Self {
+            series_colors: vec![],
+            line_style: LineStyle::default(),
+            line_width: DEFAULT_LINE_WIDTH,
+        }
+    }
+}
+
+impl GraphStyle {
+    pub fn series_color(&self, index: usize) -> Option<(u8, u8, u8)> {
+        self.series_colors
+            .get(index)
+            .and_then(|color| parse_hex_color(color))
+    }
+
+    pub fn accent_color(&self) -> Option<(u8, u8, u8)> {
+        self.series_color(0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineStyle {
+    pub const ALL: [LineStyle; 3] = [LineStyle::Solid, LineStyle::Dashed, LineStyle::Dotted];
+}
+
+impl GuiSettings {
+    /// Falls back to the defaults when the file doesn't exist or is invalid
+    pub fn load() -> Self {
+        let path = settings_path();
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!("invalid GUI settings in {}: {err}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = settings_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Could not create {}", parent.display()))?;
+        }
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(&path, contents)
+            .with_context(|| format!("Could not save the GUI settings to {}", path.display()))
+    }
+}
+
+fn settings_path() -> PathBuf {
+    glib::user_config_dir()
+        .join("lact")
+        .join(SETTINGS_FILE_NAME)
+}
+
+pub fn parse_hex_color(value: &str) -> Option<(u8, u8, u8)> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |range: std::ops::Range<usize>| u8::from_str_radix(hex.get(range)?, 16).ok();
+    Some((channel(0..2)?, channel(2..4)?, channel(4..6)?))
+}
+
+pub fn to_hex_color(color: &gdk::RGBA) -> String {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.red()),
+        channel(color.green()),
+        channel(color.blue())
+    )
+}
+
+thread_local! {
+    static ACCENT_COLOR: Cell<Option<(u8, u8, u8)>> = const { Cell::new(None) };
+}
+
+/// Sets the accent color that the fan curve editor is drawn with, it uses its default color when there is none
+pub fn apply_accent_color(style: &GraphStyle) {
+    ACCENT_COLOR.with(|color| color.set(style.accent_color()));
+}
+
+pub fn accent_color() -> Option<(u8, u8, u8)> {
+    ACCENT_COLOR.with(Cell::get)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_hex_color, GraphStyle, GuiSettings, LineStyle, DEFAULT_LINE_WIDTH};
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(parse_hex_color("#3584e4"), Some((0x35, 0x84, 0xe4)));
+        assert_eq!(parse_hex_color("3584e4"), None);
+        assert_eq!(parse_hex_color("#3584e"), None);
+        assert_eq!(parse_hex_color("#zz84e4"), None);
+    }
+
+    #[test]
+    fn partial_settings() {
+        let settings: GuiSettings = serde_json::from_str(
+            r##"{"graphs": {"series_colors": ["#ff0000", "invalid"], "line_style": "dashed"}}"##,
+        )
+        .unwrap();
+        assert_eq!(settings.graphs.line_style, LineStyle::Dashed);
+        assert_eq!(settings.graphs.line_width, DEFAULT_LINE_WIDTH);
+        assert_eq!(settings.graphs.accent_color(), Some((255, 0, 0)));
+        assert_eq!(settings.graphs.series_color(1), None);
+        assert_eq!(GraphStyle::default().accent_color(), None);
+    }
+}
@@ -1,23 +1,35 @@
 mod new_profile_dialog;
+mod profile_schedule_dialog;
 
-use super::{AppMsg, DebugSnapshot, DisableOverdrive, DumpVBios, ResetConfig, ShowGraphsWindow};
+use super::{
+    AppMsg, DebugSnapshot, DisableOverdrive, DumpVBios, ExportConfig, ImportConfig, ResetConfig,
+    ShowGraphsWindow, ShowProfileComparison, ShowUndervoltAssistant,
+};
 use glib::clone;
 use gtk::prelude::*;
 use gtk::*;
 use lact_client::schema::DeviceListEntry;
-use lact_schema::ProfilesInfo;
+use lact_schema::{ConfigHistoryInfo, ProfileSchedule, ProfilesInfo};
 use new_profile_dialog::NewProfileDialog;
+use profile_schedule_dialog::ProfileScheduleDialog;
 use relm4::{
     typed_view::list::{RelmListItem, TypedListView},
     Component, ComponentController, ComponentParts, ComponentSender, RelmWidgetExt,
 };
-use std::fmt;
+use std::{collections::BTreeMap, fmt};
 
 pub struct Header {
     gpu_selector: TypedListView<GpuListItem, gtk::SingleSelection>,
     profile_selector: TypedListView<ProfileListItem, gtk::SingleSelection>,
     selector_label: String,
     stack: Option<Stack>,
+    /// Index of the GPU whose settings are shown
+    active_gpu_index: Option<u32>,
+    /// Whether the settings of the active GPU were changed without being applied
+    pending_changes: bool,
+    /// Applied changes of the active GPU that can be undone
+    history: ConfigHistoryInfo,
+    schedules: BTreeMap<String, ProfileSchedule>,
 }
 
 #[derive(Debug)]
@@ -26,8 +38,14 @@ pub enum HeaderMsg {
     Profiles(ProfilesInfo),
     SelectProfile,
     SelectGpu,
+    SelectGpuId(String),
+    /// The GPUs changed while the GUI was running, e.g. an eGPU was plugged in
+    Devices(Vec<DeviceListEntry>),
+    PendingChanges(bool),
+    ConfigHistory(ConfigHistoryInfo),
     CreateProfile,
     DeleteProfile,
+    EditProfileSchedule,
 }
 
 #[relm4::component(pub)]
@@ -61,7 +79,7 @@ impl Component for Header {
                         set_spacing: 5,
 
                         gtk::Frame {
-                            set_label: Some("GPU"),
+                            set_label: Some(&fl!("gpu")),
                             set_label_align: 0.05,
                             set_margin_all: 5,
 
@@ -75,7 +93,7 @@ impl Component for Header {
                         },
 
                         gtk::Frame {
-                            set_label: Some("Settings Profile"),
+                            set_label: Some(&fl!("settings-profile")),
                             set_label_align: 0.05,
                             set_margin_all: 5,
 
@@ -108,6 +126,15 @@ impl Component for Header {
                                         #[watch]
                                         set_sensitive: model.profile_selector.selection_model.selected() != 0,
                                     },
+
+                                    gtk::Button {
+                                        set_expand: true,
+                                        set_icon_name: "alarm-symbolic",
+                                        set_tooltip_text: Some(&fl!("schedule-profile")),
+                                        connect_clicked => HeaderMsg::EditProfileSchedule,
+                                        #[watch]
+                                        set_sensitive: model.profile_selector.selection_model.selected() != 0,
+                                    },
                                 }
                             }
                         },
@@ -118,7 +145,27 @@ impl Component for Header {
             pack_end = &gtk::MenuButton {
                 set_icon_name: "open-menu-symbolic",
                 set_menu_model: Some(&app_menu),
-            }
+            },
+
+            pack_end = &gtk::Button {
+                set_icon_name: "edit-redo-symbolic",
+                set_tooltip_text: Some(&fl!("redo-change")),
+                #[watch]
+                set_sensitive: model.history.redo_steps > 0,
+                connect_clicked[sender] => move |_| {
+                    sender.output(AppMsg::RedoChange).unwrap();
+                },
+            },
+
+            pack_end = &gtk::Button {
+                set_icon_name: "edit-undo-symbolic",
+                set_tooltip_text: Some(&fl!("undo-change")),
+                #[watch]
+                set_sensitive: model.history.undo_steps > 0,
+                connect_clicked[sender] => move |_| {
+                    sender.output(AppMsg::UndoChange).unwrap();
+                },
+            },
         },
 
     }
@@ -126,15 +173,21 @@ impl Component for Header {
     menu! {
         app_menu: {
             section! {
-                "Show historical charts" => ShowGraphsWindow,
+                fl!("show-historical-charts") => ShowGraphsWindow,
+                fl!("undervolt-assistant") => ShowUndervoltAssistant,
+                fl!("compare-profiles") => ShowProfileComparison,
             },
             section! {
-                "Generate debug snapshot" => DebugSnapshot,
-                "Dump VBIOS" => DumpVBios,
+                fl!("generate-debug-snapshot") => DebugSnapshot,
+                fl!("dump-vbios") => DumpVBios,
             } ,
             section! {
-                "Disable overclocking support" => DisableOverdrive,
-                "Reset all configuration" => ResetConfig,
+                fl!("export-config") => ExportConfig,
+                fl!("import-config") => ImportConfig,
+            },
+            section! {
+                fl!("disable-amd-oc-support") => DisableOverdrive,
+                fl!("reset-all-config") => ResetConfig,
             }
         }
     }
@@ -175,6 +228,10 @@ impl Component for Header {
             profile_selector,
             selector_label: String::new(),
             stack: None,
+            active_gpu_index: None,
+            pending_changes: false,
+            history: ConfigHistoryInfo::default(),
+            schedules: BTreeMap::new(),
         };
 
         let gpu_selector = &model.gpu_selector.view;
@@ -228,6 +285,7 @@ impl Component for Header {
                     None => 0,
                 };
 
+                self.schedules = profiles_info.schedules;
                 self.profile_selector.clear();
                 self.profile_selector.append(ProfileListItem::Default);
 
@@ -240,7 +298,67 @@ impl Component for Header {
                     .selection_model
                     .set_selected(selected_index as u32);
             }
-            HeaderMsg::SelectGpu => sender.output(AppMsg::ReloadData { full: true }).unwrap(),
+            HeaderMsg::SelectGpu => {
+                let selected = self.gpu_selector.selection_model.selected();
+                match self.active_gpu_index {
+                    Some(active) if active == selected => (),
+                    Some(active) if self.pending_changes => {
+                        // Stay on the GPU with the changes until the user decides what to do with them
+                        self.gpu_selector.selection_model.set_selected(active);
+
+                        if let (Some(current), Some(target)) =
+                            (self.gpu_name(active), self.gpu_id(selected))
+                        {
+                            sender
+                                .output(AppMsg::ask_confirmation(
+                                    AppMsg::DiscardChangesAndSelectGpu(target),
+                                    fl!("unapplied-changes"),
+                                    fl!("unapplied-changes-description", gpu = current),
+                                    gtk::ButtonsType::OkCancel,
+                                ))
+                                .unwrap();
+                        }
+                    }
+                    _ => {
+                        self.active_gpu_index = Some(selected);
+                        sender.output(AppMsg::ReloadData { full: true }).unwrap();
+                    }
+                }
+            }
+            HeaderMsg::SelectGpuId(id) => {
+                let index = (0..self.gpu_selector.len())
+                    .find(|index| self.gpu_id(*index).as_ref() == Some(&id));
+                if let Some(index) = index {
+                    self.gpu_selector.selection_model.set_selected(index);
+                }
+            }
+            HeaderMsg::Devices(devices) => {
+                let active_id = self.active_gpu_index.and_then(|index| self.gpu_id(index));
+
+                self.gpu_selector.clear();
+                self.gpu_selector
+                    .extend_from_iter(devices.into_iter().map(GpuListItem));
+
+                let active_index = active_id.and_then(|id| {
+                    (0..self.gpu_selector.len())
+                        .find(|index| self.gpu_id(*index).as_ref() == Some(&id))
+                });
+                match active_index {
+                    Some(index) => {
+                        self.active_gpu_index = Some(index);
+                        self.gpu_selector.selection_model.set_selected(index);
+                    }
+                    None => {
+                        // The shown GPU was removed, so its changes can't be applied anymore
+                        self.active_gpu_index = None;
+                        self.pending_changes = false;
+                        self.gpu_selector.selection_model.set_selected(0);
+                    }
+                }
+                sender.input(HeaderMsg::SelectGpu);
+            }
+            HeaderMsg::PendingChanges(pending) => self.pending_changes = pending,
+            HeaderMsg::ConfigHistory(history) => self.history = history,
             HeaderMsg::SelectProfile => {
                 let selected_profile = self.selected_profile();
                 sender
@@ -255,15 +373,28 @@ impl Component for Header {
                     });
                 diag_controller.detach_runtime();
             }
+            HeaderMsg::EditProfileSchedule => {
+                if let Some(selected_profile) = self.selected_profile() {
+                    let schedule = self.schedules.get(&selected_profile).cloned();
+                    let mut diag_controller = ProfileScheduleDialog::builder()
+                        .launch((selected_profile, schedule))
+                        .forward(sender.output_sender(), |(name, schedule)| {
+                            AppMsg::SetProfileSchedule(name, schedule)
+                        });
+                    diag_controller.detach_runtime();
+                }
+            }
             HeaderMsg::DeleteProfile => {
                 if let Some(selected_profile) = self.selected_profile() {
-                    let msg =
-                        format!("Are you sure you want to delete profile \"{selected_profile}\"");
+                    let msg = fl!(
+                        "delete-profile-description",
+                        name = selected_profile.as_str()
+                    );
                     sender
                         .output(AppMsg::ask_confirmation(
                             AppMsg::DeleteProfile(selected_profile),
-                            "Delete profile",
-                            &msg,
+                            fl!("delete-profile"),
+                            msg,
                             gtk::ButtonsType::OkCancel,
                         ))
                         .unwrap();
@@ -285,6 +416,20 @@ impl Header {
             .map(|item| item.borrow().0.id.clone())
     }
 
+    fn gpu_id(&self, index: u32) -> Option<String> {
+        self.gpu_selector
+            .get(index)
+            .as_ref()
+            .map(|item| item.borrow().0.id.clone())
+    }
+
+    fn gpu_name(&self, index: u32) -> Option<String> {
+        self.gpu_selector
+            .get(index)
+            .as_ref()
+            .map(|item| item.borrow().0.to_string())
+    }
+
     fn custom_profiles(&self) -> Vec<String> {
         let mut profiles = Vec::with_capacity(self.profile_selector.len() as usize);
         for i in 0..self.profile_selector.len() {
@@ -317,9 +462,9 @@ impl Header {
             .get(self.profile_selector.selection_model.selected())
             .as_ref()
             .map(|item| item.borrow().to_string())
-            .unwrap_or_else(|| "<Unknown>".to_owned());
+            .unwrap_or_else(|| format!("<{}>", fl!("unknown")));
 
-        self.selector_label = format!("GPU {gpu_index} | {profile}");
+        self.selector_label = fl!("gpu-selector-label", index = gpu_index, profile = profile);
     }
 }
 
@@ -336,7 +481,7 @@ impl RelmListItem for GpuListItem {
     }
 
     fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
-        widgets.set_label(self.0.name.as_deref().unwrap_or(&self.0.id));
+        widgets.set_label(&self.0.to_string());
     }
 }
 
@@ -348,11 +493,10 @@ enum ProfileListItem {
 
 impl fmt::Display for ProfileListItem {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let text = match self {
-            ProfileListItem::Default => "Default",
-            ProfileListItem::Profile(name) => name,
-        };
-        text.fmt(f)
+        match self {
+            ProfileListItem::Default => fl!("default").fmt(f),
+            ProfileListItem::Profile(name) => name.fmt(f),
+        }
     }
 }
 
@@ -4,10 +4,25 @@ use relm4::{
     Component, ComponentController, ComponentParts, ComponentSender, Controller, RelmWidgetExt,
 };
 use relm4_components::simple_combo_box::SimpleComboBox;
+use std::fmt;
 
 pub struct NewProfileDialog {
     name_buffer: gtk::EntryBuffer,
-    base_selector: Controller<SimpleComboBox<ProfileBase>>,
+    base_selector: Controller<SimpleComboBox<ProfileBaseItem>>,
+}
+
+/// Shows the translated name of a profile base
+#[derive(Debug, Clone)]
+struct ProfileBaseItem(ProfileBase);
+
+impl fmt::Display for ProfileBaseItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            ProfileBase::Empty => fl!("profile-base-empty").fmt(f),
+            ProfileBase::Default => fl!("default").fmt(f),
+            ProfileBase::Profile(name) => name.fmt(f),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -25,7 +40,7 @@ impl Component for NewProfileDialog {
     view! {
         gtk::Window {
             set_default_size: (250, 130),
-            set_title: Some("Create Profile"),
+            set_title: Some(&fl!("create-profile")),
             set_hide_on_close: true,
 
             gtk::Box {
@@ -34,7 +49,7 @@ impl Component for NewProfileDialog {
                 set_margin_all: 10,
 
                 gtk::Entry {
-                    set_placeholder_text: Some("Name"),
+                    set_placeholder_text: Some(&fl!("name")),
                     set_buffer: &model.name_buffer,
                 },
 
@@ -43,7 +58,7 @@ impl Component for NewProfileDialog {
                     set_spacing: 5,
 
                     gtk::Label {
-                        set_label: "Base profile:",
+                        set_label: &fl!("base-profile"),
                     },
 
                     #[local_ref]
@@ -62,7 +77,7 @@ impl Component for NewProfileDialog {
                     set_valign: gtk::Align::End,
 
                     gtk::Button {
-                        set_label: "Cancel",
+                        set_label: &fl!("cancel"),
                         set_hexpand: true,
 
                         connect_clicked[root] => move |_| {
@@ -71,7 +86,7 @@ impl Component for NewProfileDialog {
                     },
 
                     gtk::Button {
-                        set_label: "Create",
+                        set_label: &fl!("create"),
                         set_hexpand: true,
 
                         connect_clicked => NewProfileDialogMsg::Create,
@@ -86,8 +101,15 @@ impl Component for NewProfileDialog {
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let mut variants = vec![ProfileBase::Empty, ProfileBase::Default];
-        variants.extend(current_profiles.into_iter().map(ProfileBase::Profile));
+        let mut variants = vec![
+            ProfileBaseItem(ProfileBase::Empty),
+            ProfileBaseItem(ProfileBase::Default),
+        ];
+        variants.extend(
+            current_profiles
+                .into_iter()
+                .map(|name| ProfileBaseItem(ProfileBase::Profile(name))),
+        );
 
         let base_selector = SimpleComboBox::<ProfileBase>::builder()
             .launch(SimpleComboBox {
@@ -115,7 +137,7 @@ impl Component for NewProfileDialog {
             NewProfileDialogMsg::Create => {
                 if self.name_buffer.length() != 0 {
                     if let Some(selected) = self.base_selector.model().active_index {
-                        let base = self.base_selector.model().variants[selected].clone();
+                        let base = self.base_selector.model().variants[selected].0.clone();
                         sender
                             .output((self.name_buffer.text().to_string(), base))
                             .unwrap();
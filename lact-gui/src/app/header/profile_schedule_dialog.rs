@@ -0,0 +1,186 @@
+use gtk::prelude::*;
+use lact_schema::ProfileSchedule;
+use relm4::{Component, ComponentParts, ComponentSender, RelmWidgetExt};
+
+/// Edits the time window during which a profile is activated automatically
+pub struct ProfileScheduleDialog {
+    name: String,
+    start_buffer: gtk::EntryBuffer,
+    end_buffer: gtk::EntryBuffer,
+    day_buttons: Vec<gtk::CheckButton>,
+}
+
+#[derive(Debug)]
+pub enum ProfileScheduleDialogMsg {
+    Save,
+    Remove,
+}
+
+#[relm4::component(pub)]
+impl Component for ProfileScheduleDialog {
+    type Init = (String, Option<ProfileSchedule>);
+    type Input = ProfileScheduleDialogMsg;
+    type Output = (String, Option<ProfileSchedule>);
+    type CommandOutput = ();
+
+    view! {
+        gtk::Window {
+            set_default_size: (300, 150),
+            set_title: Some(&fl!("profile-schedule-title", name = model.name.as_str())),
+            set_hide_on_close: true,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 5,
+                set_margin_all: 10,
+
+                gtk::Label {
+                    set_label: &fl!("profile-schedule-time"),
+                    set_halign: gtk::Align::Start,
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 5,
+
+                    gtk::Entry {
+                        set_placeholder_text: Some("22:00"),
+                        set_buffer: &model.start_buffer,
+                        set_hexpand: true,
+                    },
+
+                    gtk::Label {
+                        set_label: &fl!("profile-schedule-and"),
+                    },
+
+                    gtk::Entry {
+                        set_placeholder_text: Some("07:00"),
+                        set_buffer: &model.end_buffer,
+                        set_hexpand: true,
+                    },
+                },
+
+                gtk::Label {
+                    set_label: &fl!("profile-schedule-days"),
+                    set_halign: gtk::Align::Start,
+                },
+
+                #[name = "days_box"]
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 5,
+                },
+
+                gtk::Label {
+                    set_label: &fl!("profile-schedule-auto-switch-note"),
+                    set_wrap: true,
+                    set_halign: gtk::Align::Start,
+                    add_css_class: "dim-label",
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 5,
+                    set_hexpand: true,
+                    set_vexpand: true,
+                    set_valign: gtk::Align::End,
+
+                    gtk::Button {
+                        set_label: &fl!("remove"),
+                        set_hexpand: true,
+
+                        connect_clicked => ProfileScheduleDialogMsg::Remove,
+                    },
+
+                    gtk::Button {
+                        set_label: &fl!("cancel"),
+                        set_hexpand: true,
+
+                        connect_clicked[root] => move |_| {
+                            root.hide();
+                        },
+                    },
+
+                    gtk::Button {
+                        set_label: &fl!("save"),
+                        set_hexpand: true,
+
+                        connect_clicked => ProfileScheduleDialogMsg::Save,
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        (name, schedule): Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let (start, end, days) = schedule
+            .map(|schedule| (schedule.start, schedule.end, schedule.days))
+            .unwrap_or_default();
+
+        let day_labels = [
+            fl!("monday-short"),
+            fl!("tuesday-short"),
+            fl!("wednesday-short"),
+            fl!("thursday-short"),
+            fl!("friday-short"),
+            fl!("saturday-short"),
+            fl!("sunday-short"),
+        ];
+        let day_buttons = day_labels
+            .iter()
+            .zip(1..)
+            .map(|(label, day)| {
+                gtk::CheckButton::builder()
+                    .label(label)
+                    .active(days.contains(&day))
+                    .build()
+            })
+            .collect();
+
+        let model = Self {
+            name,
+            start_buffer: gtk::EntryBuffer::new(Some(start)),
+            end_buffer: gtk::EntryBuffer::new(Some(end)),
+            day_buttons,
+        };
+
+        let widgets = view_output!();
+        for button in &model.day_buttons {
+            widgets.days_box.append(button);
+        }
+
+        root.present();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, root: &Self::Root) {
+        let schedule = match msg {
+            ProfileScheduleDialogMsg::Save => {
+                if self.start_buffer.length() == 0 || self.end_buffer.length() == 0 {
+                    return;
+                }
+                let days = self
+                    .day_buttons
+                    .iter()
+                    .zip(1..)
+                    .filter(|(button, _)| button.is_active())
+                    .map(|(_, day)| day)
+                    .collect();
+                Some(ProfileSchedule {
+                    start: self.start_buffer.text().trim().to_owned(),
+                    end: self.end_buffer.text().trim().to_owned(),
+                    days,
+                })
+            }
+            ProfileScheduleDialogMsg::Remove => None,
+        };
+
+        sender.output((self.name.clone(), schedule)).unwrap();
+        root.hide();
+    }
+}
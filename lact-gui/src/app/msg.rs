@@ -1,20 +1,62 @@
-use super::confirmation_dialog::ConfirmationOptions;
+use super::{
+    confirmation_dialog::ConfirmationOptions, graphs_window::GraphsExportFormat,
+    gui_settings::GraphStyle,
+};
 use lact_client::ConnectionStatusMsg;
-use lact_schema::{request::ProfileBase, DeviceStats};
-use std::rc::Rc;
+use lact_schema::{
+    request::ProfileBase, DeviceListEntry, DeviceStats, EnergyPrice, FanCurveMap,
+    ProfileComparisonOptions, ProfileSchedule, UndervoltOptions,
+};
+use std::{path::PathBuf, rc::Rc};
 
 #[derive(Debug, Clone)]
 pub enum AppMsg {
     Error(Rc<anyhow::Error>),
-    ReloadData { full: bool },
+    ReloadData {
+        full: bool,
+    },
+    /// Shows the settings of a GPU, asking what to do with unapplied changes of the current one first
+    SelectGpu(String),
+    DiscardChangesAndSelectGpu(String),
+    SettingsChanged,
     Stats(Rc<DeviceStats>),
+    /// The list of GPUs changed, e.g. because one was plugged in or removed
+    DevicesChanged(Vec<DeviceListEntry>),
+    /// Stats of any GPU, shown in the overview
+    OverviewStats(String, Rc<DeviceStats>),
     ApplyChanges,
     RevertChanges,
+    UndoChange,
+    RedoChange,
     ResetClocks,
     ResetPmfw,
+    ResetFanControl,
+    /// Replays the stats history of the current GPU with the given curve
+    PreviewFanCurve(FanCurveMap),
+    /// Turns the low latency mode of the current GPU on or off, which is applied right away
+    SetLowLatencyMode(bool),
+    SetRuntimePm(bool),
+    SetMinVramState(Option<u8>),
+    /// Brings the main window back, e.g. after it was closed to the tray
+    ShowWindow,
     ShowGraphsWindow,
+    ShowUndervoltAssistant,
+    StartUndervolt(String, UndervoltOptions),
+    CancelUndervolt(String),
+    ShowProfileComparison,
+    StartProfileComparison(String, ProfileComparisonOptions),
+    CancelProfileComparison(String),
+    /// Reloads the graphs from the stats history after their time range was changed
+    ReloadGraphsHistory,
+    ExportGraphs(GraphsExportFormat),
+    /// The look of the graphs was changed, which is saved in the GUI settings
+    SetGraphStyle(GraphStyle),
     DumpVBios,
     DebugSnapshot,
+    ExportConfig,
+    /// Asks for a file with a config exported by `ExportConfig` and imports it
+    ImportConfig,
+    ImportConfigFile(PathBuf),
     EnableOverdrive,
     DisableOverdrive,
     ResetConfig,
@@ -22,6 +64,8 @@ pub enum AppMsg {
     SelectProfile(Option<String>),
     CreateProfile(String, ProfileBase),
     DeleteProfile(String),
+    SetProfileSchedule(String, Option<ProfileSchedule>),
+    SetEnergyPrice(Option<EnergyPrice>),
     ConnectionStatus(ConnectionStatusMsg),
     AskConfirmation(ConfirmationOptions, Box<AppMsg>),
 }
@@ -29,13 +73,13 @@ pub enum AppMsg {
 impl AppMsg {
     pub fn ask_confirmation(
         inner: AppMsg,
-        title: &'static str,
+        title: impl Into<String>,
         message: impl Into<String>,
         buttons_type: gtk::ButtonsType,
     ) -> Self {
         Self::AskConfirmation(
             ConfirmationOptions {
-                title,
+                title: title.into(),
                 message: message.into(),
                 buttons_type,
             },
@@ -1,6 +1,10 @@
+pub mod energy_page;
 pub mod info_page;
 pub mod oc_adjustment;
 pub mod oc_page;
+pub mod overview_page;
+pub mod processes_page;
+pub mod sensors_page;
 pub mod software_page;
 pub mod thermals_page;
 
@@ -0,0 +1,228 @@
+use crate::app::msg::AppMsg;
+use chrono::{Days, Local, NaiveDate};
+use gtk::prelude::*;
+use lact_schema::{EnergyPrice, EnergyStats};
+use relm4::{ComponentParts, ComponentSender, RelmWidgetExt, SimpleComponent};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+/// Days shown in the daily usage list
+const DAILY_DAYS: u64 = 7;
+
+/// Energy used by the current GPU per day, with estimated costs
+pub struct EnergyPage {
+    stats: EnergyStats,
+    price_adjustment: gtk::Adjustment,
+    currency_buffer: gtk::EntryBuffer,
+    totals_grid: gtk::Grid,
+    daily_grid: gtk::Grid,
+}
+
+#[derive(Debug)]
+pub enum EnergyMsg {
+    Stats(EnergyStats),
+    SavePrice,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for EnergyPage {
+    type Init = ();
+    type Input = EnergyMsg;
+    type Output = AppMsg;
+
+    view! {
+        gtk::ScrolledWindow {
+            set_hscrollbar_policy: gtk::PolicyType::Never,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 15,
+                set_margin_all: 5,
+
+                gtk::Label {
+                    set_label: &format!("<b>{}</b>", fl!("energy-usage")),
+                    set_use_markup: true,
+                    set_halign: gtk::Align::Start,
+                },
+
+                #[local_ref]
+                totals_grid -> gtk::Grid {
+                    set_row_spacing: 5,
+                    set_column_spacing: 20,
+                },
+
+                gtk::Label {
+                    set_label: &format!("<b>{}</b>", fl!("energy-last-days")),
+                    set_use_markup: true,
+                    set_halign: gtk::Align::Start,
+                },
+
+                #[local_ref]
+                daily_grid -> gtk::Grid {
+                    set_row_spacing: 5,
+                    set_column_spacing: 20,
+                },
+
+                gtk::Label {
+                    set_label: &format!("<b>{}</b>", fl!("electricity-price")),
+                    set_use_markup: true,
+                    set_halign: gtk::Align::Start,
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 5,
+
+                    gtk::SpinButton {
+                        set_adjustment: &model.price_adjustment,
+                        set_digits: 3,
+                    },
+
+                    gtk::Label {
+                        set_label: &fl!("price-per-kwh"),
+                    },
+
+                    gtk::Entry {
+                        set_placeholder_text: Some(&fl!("currency")),
+                        set_buffer: &model.currency_buffer,
+                        set_width_chars: 6,
+                    },
+
+                    gtk::Button {
+                        set_label: &fl!("save"),
+                        connect_clicked => EnergyMsg::SavePrice,
+                    },
+                },
+
+                gtk::Label {
+                    set_label: &fl!("energy-description"),
+                    set_wrap: true,
+                    set_halign: gtk::Align::Start,
+                    add_css_class: "dim-label",
+                },
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            stats: EnergyStats::default(),
+            price_adjustment: gtk::Adjustment::new(0.0, 0.0, 1000.0, 0.01, 0.1, 0.0),
+            currency_buffer: gtk::EntryBuffer::default(),
+            totals_grid: gtk::Grid::new(),
+            daily_grid: gtk::Grid::new(),
+        };
+        let totals_grid = &model.totals_grid;
+        let daily_grid = &model.daily_grid;
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            EnergyMsg::Stats(stats) => {
+                // Only touch the price fields when the price changed, so that editing them isn't interrupted
+                if stats.price != self.stats.price {
+                    let (per_kwh, currency) = stats
+                        .price
+                        .as_ref()
+                        .map(|price| (price.per_kwh, price.currency.as_str()))
+                        .unwrap_or((0.0, ""));
+                    self.price_adjustment.set_value(per_kwh);
+                    self.currency_buffer.set_text(currency);
+                }
+                self.stats = stats;
+                self.update_usage();
+            }
+            EnergyMsg::SavePrice => {
+                let per_kwh = self.price_adjustment.value();
+                let price = (per_kwh > 0.0).then(|| EnergyPrice {
+                    per_kwh,
+                    currency: self.currency_buffer.text().trim().to_owned(),
+                });
+                sender.output(AppMsg::SetEnergyPrice(price)).unwrap();
+            }
+        }
+    }
+}
+
+impl EnergyPage {
+    fn update_usage(&self) {
+        let today = Local::now().date_naive();
+        let since = |days: u64| format_date(today - Days::new(days - 1));
+        let today_text = format_date(today);
+
+        let totals = [
+            (
+                fl!("energy-today"),
+                self.stats.total_between(&today_text, &today_text),
+            ),
+            (
+                fl!("energy-last-n-days", days = 7),
+                self.stats.total_between(&since(7), &today_text),
+            ),
+            (
+                fl!("energy-last-n-days", days = 30),
+                self.stats.total_between(&since(30), &today_text),
+            ),
+        ];
+        fill_grid(
+            &self.totals_grid,
+            totals
+                .into_iter()
+                .map(|(title, energy)| (title, self.format_energy(energy))),
+        );
+
+        let daily = (0..DAILY_DAYS).map(|days_ago| {
+            let date = format_date(today - Days::new(days_ago));
+            let energy = self.stats.days.get(&date).copied().unwrap_or(0.0);
+            (date, self.format_energy(energy))
+        });
+        fill_grid(&self.daily_grid, daily);
+    }
+
+    fn format_energy(&self, energy: f64) -> String {
+        let text = if energy >= 1000.0 {
+            format!("{:.2} kWh", energy / 1000.0)
+        } else {
+            format!("{energy:.0} Wh")
+        };
+
+        match (self.stats.cost(energy), &self.stats.price) {
+            (Some(cost), Some(price)) if price.currency.is_empty() => {
+                format!("{text} (~{cost:.2})")
+            }
+            (Some(cost), Some(price)) => format!("{text} (~{cost:.2} {})", price.currency),
+            _ => text,
+        }
+    }
+}
+
+fn fill_grid(grid: &gtk::Grid, rows: impl Iterator<Item = (String, String)>) {
+    while let Some(child) = grid.first_child() {
+        grid.remove(&child);
+    }
+
+    for (row, (title, value)) in rows.enumerate() {
+        let title_label = gtk::Label::builder()
+            .label(title)
+            .halign(gtk::Align::Start)
+            .build();
+        let value_label = gtk::Label::builder()
+            .label(value)
+            .halign(gtk::Align::Start)
+            .selectable(true)
+            .build();
+        grid.attach(&title_label, 0, row as i32, 1, 1);
+        grid.attach(&value_label, 1, row as i32, 1, 1);
+    }
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format(DATE_FORMAT).to_string()
+}
@@ -32,7 +32,7 @@ impl Component for InformationPage {
                 model.hardware_info.clone(),
 
                 #[name = "vulkan_section"]
-                PageSection::new("Vulkan Information") -> PageSection {
+                PageSection::new(&fl!("vulkan-info")) -> PageSection {
                     set_spacing: 10,
                     set_margin_start: 15,
 
@@ -41,7 +41,7 @@ impl Component for InformationPage {
 
                 #[name = "vulkan_unavailable_label"]
                 gtk::Label {
-                    set_label: "Vulkan is not available on this GPU",
+                    set_label: &fl!("vulkan-unavailable"),
                     set_visible: false,
                     set_margin_horizontal: 10,
                     set_halign: gtk::Align::Start,
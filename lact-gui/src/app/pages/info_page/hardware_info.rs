@@ -1,6 +1,6 @@
 use crate::app::page_section::PageSection;
 use gtk::glib::{self, object::ObjectExt, subclass::object::DerivedObjectProperties, Object};
-use lact_client::schema::{DeviceInfo, DeviceStats, DrmInfo};
+use lact_client::schema::{DeviceInfo, DeviceStats, DrmInfo, LinkInfo};
 use std::fmt::Write;
 
 glib::wrapper! {
@@ -23,8 +23,7 @@ impl HardwareInfoSection {
                 .as_ref()
                 .and_then(|drm| drm.device_name.as_deref())
                 .or(pci_info.device_pci_info.model.as_deref())
-                .unwrap_or("Unknown")
-                .to_owned();
+                .map_or_else(|| fl!("unknown"), str::to_owned);
 
             match &info.drm_info {
                 Some(DrmInfo {
@@ -52,8 +51,7 @@ impl HardwareInfoSection {
                 .subsystem_pci_info
                 .vendor
                 .as_deref()
-                .unwrap_or("Unknown")
-                .to_owned();
+                .map_or_else(|| fl!("unknown"), str::to_owned);
             let _ = write!(
                 card_manufacturer,
                 " (0x{})",
@@ -65,8 +63,7 @@ impl HardwareInfoSection {
                 .subsystem_pci_info
                 .model
                 .as_deref()
-                .unwrap_or("Unknown")
-                .to_owned();
+                .map_or_else(|| fl!("unknown"), str::to_owned);
             let _ = write!(card_model, " (0x{})", pci_info.subsystem_pci_info.model_id);
             self.set_card_model(card_model);
         }
@@ -103,7 +100,11 @@ impl HardwareInfoSection {
 
             if let Some(memory_info) = &drm_info.memory_info {
                 if let Some(rebar) = memory_info.resizeable_bar {
-                    let rebar = if rebar { "Enabled" } else { "Disabled" };
+                    let rebar = if rebar {
+                        fl!("enabled")
+                    } else {
+                        fl!("disabled")
+                    };
                     self.set_rebar(rebar);
                 }
 
@@ -120,17 +121,35 @@ impl HardwareInfoSection {
             self.set_vbios_version(vbios.clone());
         }
 
-        if let (Some(link_speed), Some(link_width)) =
-            (&info.link_info.current_speed, &info.link_info.current_width)
-        {
-            self.set_link_speed(format!("{link_speed} x{link_width}",));
-        }
+        self.set_link_info(&info.link_info);
     }
 
     pub fn set_stats(&self, stats: &DeviceStats) {
         if let Some(total_vram) = stats.vram.total {
             self.set_vram_size(format!("{} MiB", total_vram / 1024 / 1024));
         }
+        // The link speed changes with power management
+        if let Some(link_info) = &stats.link_info {
+            self.set_link_info(link_info);
+        }
+    }
+
+    fn set_link_info(&self, link_info: &LinkInfo) {
+        if let (Some(link_speed), Some(link_width)) =
+            (&link_info.current_speed, &link_info.current_width)
+        {
+            let mut text = format!("{link_speed} x{link_width}");
+            if let (Some(gen), Some(max_gen)) =
+                (link_info.current_generation(), link_info.max_generation())
+            {
+                let _ = write!(
+                    text,
+                    " ({})",
+                    fl!("link-generation", gen = gen, max_gen = max_gen)
+                );
+            }
+            self.set_link_speed(text);
+        }
     }
 
     fn reset(&self) {
@@ -227,13 +246,40 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
             let obj = self.obj();
+            obj.set_property("name", fl!("hardware-info"));
 
-            for child in obj.observe_children().into_iter().flatten() {
-                if let Ok(row) = child.downcast::<InfoRow>() {
-                    row.connect_value_notify(|row| {
-                        row.set_visible(!row.value().is_empty());
-                    });
+            let rows = [
+                ("gpu-model", fl!("gpu-model")),
+                ("card-manufacturer", fl!("card-manufacturer")),
+                ("card-model", fl!("card-model")),
+                ("gpu-family", fl!("gpu-family")),
+                ("asic-name", fl!("asic-name")),
+                ("compute-units", fl!("compute-units")),
+                ("cuda-cores", fl!("cuda-cores")),
+                ("vbios-version", fl!("vbios-version")),
+                ("driver-used", fl!("driver-used")),
+                ("vram-size", fl!("vram-size")),
+                ("vram-type", fl!("vram-type")),
+                ("peak-vram-bandwidth", fl!("peak-vram-bandwidth")),
+                ("l1-cache", fl!("l1-cache")),
+                ("l2-cache", fl!("l2-cache")),
+                ("l3-cache", fl!("l3-cache")),
+                ("rebar", fl!("rebar")),
+                ("cpu-accessible-vram", fl!("cpu-accessible-vram")),
+                ("link-speed", fl!("link-speed")),
+            ];
+            for (property, name) in rows {
+                let row = InfoRow::new_selectable(&name, "");
+                if property == "card-model" {
+                    row.set_property("info-text", fl!("card-model-info"));
                 }
+                row.connect_value_notify(|row| {
+                    row.set_visible(!row.value().is_empty());
+                });
+                obj.bind_property(property, &row, "value")
+                    .sync_create()
+                    .build();
+                obj.append(&row);
             }
         }
     }
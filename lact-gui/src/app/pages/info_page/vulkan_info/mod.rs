@@ -34,30 +34,36 @@ impl VulkanInfoFrame {
         grid.set_margin_start(0);
         grid.set_margin_end(0);
 
-        let device_name_label = label_row("Device name", &grid, 0, 0, true);
-        let version_label = label_row("Vulkan version:", &grid, 1, 0, true);
-        let driver_name_label = label_row("Driver name:", &grid, 2, 0, true);
-        let driver_version_label = label_row("Driver version:", &grid, 3, 0, true);
-
-        let show_features_button = Button::builder().label("Show").halign(Align::End).build();
+        let device_name_label = label_row(&fl!("device-name"), &grid, 0, 0, true);
+        let version_label = label_row(&fl!("vulkan-version"), &grid, 1, 0, true);
+        let driver_name_label = label_row(&fl!("driver-name"), &grid, 2, 0, true);
+        let driver_version_label = label_row(&fl!("driver-version"), &grid, 3, 0, true);
+
+        let show_features_button = Button::builder()
+            .label(fl!("show"))
+            .halign(Align::End)
+            .build();
         show_features_button.connect_clicked(clone!(
             #[strong]
             features,
             move |_| {
-                show_features_window("Vulkan features", features.clone());
+                show_features_window(&fl!("vulkan-features"), features.clone());
             }
         ));
-        values_row("Features:", &grid, &show_features_button, 4, 0);
+        values_row(&fl!("features"), &grid, &show_features_button, 4, 0);
 
-        let show_extensions_button = Button::builder().label("Show").halign(Align::End).build();
+        let show_extensions_button = Button::builder()
+            .label(fl!("show"))
+            .halign(Align::End)
+            .build();
         show_extensions_button.connect_clicked(clone!(
             #[strong]
             extensions,
             move |_| {
-                show_features_window("Vulkan extensions", extensions.clone());
+                show_features_window(&fl!("vulkan-extensions"), extensions.clone());
             }
         ));
-        values_row("Extensions:", &grid, &show_extensions_button, 5, 0);
+        values_row(&fl!("extensions"), &grid, &show_extensions_button, 5, 0);
 
         container.append(&grid);
 
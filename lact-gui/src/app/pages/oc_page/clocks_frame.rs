@@ -7,12 +7,16 @@ use amdgpu_sysfs::gpu_handle::overdrive::{ClocksTable as _, ClocksTableGen as Am
 use glib::clone;
 use gtk::prelude::*;
 use gtk::*;
-use lact_schema::{request::SetClocksCommand, ClocksTable, NvidiaClockInfo, NvidiaClocksTable};
+use lact_schema::{
+    request::SetClocksCommand, AmdOffsetsTable, ClocksLimits, ClocksTable, ExtraClock,
+    ExtraClockRange, IntelClocksTable, NvidiaClockInfo, NvidiaClocksTable,
+};
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
 use subclass::prelude::ObjectSubclassIsExt;
 use tracing::debug;
 
 const DEFAULT_VOLTAGE_OFFSET_RANGE: i32 = 250;
-const WARNING_TEXT: &str = "Warning: changing these values may lead to system instability and potentially damage your hardware!";
+const DEFAULT_SCLK_OFFSET_RANGE: i32 = 500;
 
 // The AtomicBool stores if the value was changed
 #[derive(Clone)]
@@ -29,7 +33,12 @@ pub struct ClocksFrame {
     max_sclk_adjustment: AdjustmentRow,
     max_mclk_adjustment: AdjustmentRow,
     max_voltage_adjustment: AdjustmentRow,
+    boost_sclk_adjustment: AdjustmentRow,
     voltage_offset_adjustment: AdjustmentRow,
+    sclk_offset_adjustment: AdjustmentRow,
+    curve_grid: Grid,
+    curve_adjustments: Rc<RefCell<Vec<CurvePointRows>>>,
+    extra_clock_adjustments: Rc<Vec<ExtraClockRows>>,
     reset_button: Button,
     warning_label: Label,
     clocks_data_unavailable_label: Label,
@@ -37,10 +46,10 @@ pub struct ClocksFrame {
 
 impl ClocksFrame {
     pub fn new() -> Self {
-        let container = PageSection::new("Clockspeed and voltage");
+        let container = PageSection::new(&fl!("clocks-and-voltage"));
 
         let warning_label = Label::builder()
-            .label(WARNING_TEXT)
+            .label(fl!("clocks-warning"))
             .wrap_mode(pango::WrapMode::Word)
             .halign(Align::Start)
             .margin_top(5)
@@ -51,12 +60,12 @@ impl ClocksFrame {
         let modes_switcher_box = Box::new(Orientation::Horizontal, 0);
 
         let modes_switcher_label = Label::builder()
-            .label("Configuration mode:")
+            .label(fl!("configuration-mode"))
             .hexpand(true)
             .halign(Align::Start)
             .build();
-        let basic_togglebutton = ToggleButton::builder().label("Basic").build();
-        let advanced_togglebutton = ToggleButton::builder().label("Advanced").build();
+        let basic_togglebutton = ToggleButton::builder().label(fl!("basic")).build();
+        let advanced_togglebutton = ToggleButton::builder().label(fl!("advanced")).build();
 
         modes_switcher_box.append(&modes_switcher_label);
         modes_switcher_box.append(&basic_togglebutton);
@@ -67,43 +76,71 @@ impl ClocksFrame {
         let min_values_grid = Grid::builder().row_spacing(5).build();
 
         let min_sclk_adjustment =
-            AdjustmentRow::new_and_attach("Minimum GPU Clock (MHz)", &min_values_grid, 0);
+            AdjustmentRow::new_and_attach(&fl!("min-gpu-clock"), &min_values_grid, 0);
         let min_mclk_adjustment =
-            AdjustmentRow::new_and_attach("Minimum VRAM Clock (MHz)", &min_values_grid, 1);
+            AdjustmentRow::new_and_attach(&fl!("min-vram-clock"), &min_values_grid, 1);
         let min_voltage_adjustment =
-            AdjustmentRow::new_and_attach("Minimum GPU voltage (mV)", &min_values_grid, 2);
+            AdjustmentRow::new_and_attach(&fl!("min-gpu-voltage"), &min_values_grid, 2);
+        let min_fclk_adjustment =
+            AdjustmentRow::new_and_attach(&fl!("min-fabric-clock"), &min_values_grid, 3);
+        let min_socclk_adjustment =
+            AdjustmentRow::new_and_attach(&fl!("min-soc-clock"), &min_values_grid, 4);
 
         container.append(&min_values_grid);
 
         let tweaking_grid = Grid::builder().row_spacing(5).build();
 
+        let sclk_offset_adjustment =
+            AdjustmentRow::new_and_attach(&fl!("gpu-clock-offset"), &tweaking_grid, 0);
         let max_sclk_adjustment =
-            AdjustmentRow::new_and_attach("Maximum GPU Clock (MHz)", &tweaking_grid, 1);
+            AdjustmentRow::new_and_attach(&fl!("max-gpu-clock"), &tweaking_grid, 1);
         let max_voltage_adjustment =
-            AdjustmentRow::new_and_attach("Maximum GPU voltage (mV)", &tweaking_grid, 2);
+            AdjustmentRow::new_and_attach(&fl!("max-gpu-voltage"), &tweaking_grid, 2);
         let max_mclk_adjustment =
-            AdjustmentRow::new_and_attach("Maximum VRAM Clock (MHz)", &tweaking_grid, 3);
+            AdjustmentRow::new_and_attach(&fl!("max-vram-clock"), &tweaking_grid, 3);
         let voltage_offset_adjustment =
-            AdjustmentRow::new_and_attach("GPU voltage offset (mV)", &tweaking_grid, 4);
+            AdjustmentRow::new_and_attach(&fl!("gpu-voltage-offset"), &tweaking_grid, 4);
+        let boost_sclk_adjustment =
+            AdjustmentRow::new_and_attach(&fl!("boost-gpu-clock"), &tweaking_grid, 5);
+        let max_fclk_adjustment =
+            AdjustmentRow::new_and_attach(&fl!("max-fabric-clock"), &tweaking_grid, 6);
+        let max_socclk_adjustment =
+            AdjustmentRow::new_and_attach(&fl!("max-soc-clock"), &tweaking_grid, 7);
+
+        let extra_clock_adjustments = vec![
+            ExtraClockRows {
+                clock: ExtraClock::Fabric,
+                min: min_fclk_adjustment,
+                max: max_fclk_adjustment,
+            },
+            ExtraClockRows {
+                clock: ExtraClock::Soc,
+                min: min_socclk_adjustment,
+                max: max_socclk_adjustment,
+            },
+        ];
 
         let reset_button = Button::builder()
-            .label("Reset")
+            .label(fl!("reset"))
             .halign(Align::Fill)
             .margin_top(5)
             .margin_bottom(5)
-            .tooltip_text("Warning: this resets all clock settings to defaults!")
+            .tooltip_text(fl!("reset-clocks-tooltip"))
             .css_classes(["destructive-action"])
             .build();
-        tweaking_grid.attach(&reset_button, 6, 5, 1, 1);
+        tweaking_grid.attach(&reset_button, 6, 8, 1, 1);
 
         let clocks_data_unavailable_label = Label::builder()
-            .label("No clocks data available")
+            .label(fl!("no-clocks-data"))
             .margin_start(10)
             .margin_end(10)
             .halign(Align::Start)
             .build();
 
+        let curve_grid = Grid::builder().row_spacing(5).build();
+
         container.append(&tweaking_grid);
+        container.append(&curve_grid);
         container.append(&clocks_data_unavailable_label);
 
         let frame = Self {
@@ -115,9 +152,14 @@ impl ClocksFrame {
             max_sclk_adjustment,
             max_mclk_adjustment,
             max_voltage_adjustment,
+            boost_sclk_adjustment,
             reset_button,
             clocks_data_unavailable_label,
             voltage_offset_adjustment,
+            sclk_offset_adjustment,
+            curve_grid,
+            curve_adjustments: Rc::default(),
+            extra_clock_adjustments: Rc::new(extra_clock_adjustments),
             advanced_togglebutton,
             basic_togglebutton,
             min_values_grid,
@@ -145,7 +187,12 @@ impl ClocksFrame {
         frame
     }
 
-    pub fn set_table(&self, table: ClocksTable) -> anyhow::Result<()> {
+    pub fn set_table(
+        &self,
+        table: ClocksTable,
+        limits: &ClocksLimits,
+        extra_clocks: &BTreeMap<ExtraClock, ExtraClockRange>,
+    ) -> anyhow::Result<()> {
         debug!("using clocks table {table:?}");
 
         let adjustments = [
@@ -155,33 +202,48 @@ impl ClocksFrame {
             &self.max_sclk_adjustment,
             &self.max_mclk_adjustment,
             &self.max_voltage_adjustment,
+            &self.boost_sclk_adjustment,
             &self.voltage_offset_adjustment,
+            &self.sclk_offset_adjustment,
         ];
 
-        for adjustment in adjustments {
+        let extra_adjustments = self
+            .extra_clock_adjustments
+            .iter()
+            .flat_map(|rows| [&rows.min, &rows.max]);
+        for adjustment in adjustments.into_iter().chain(extra_adjustments.clone()) {
             adjustment.set_visible(false);
         }
 
+        while let Some(child) = self.curve_grid.first_child() {
+            self.curve_grid.remove(&child);
+        }
+        self.curve_adjustments.borrow_mut().clear();
+
         match table {
-            ClocksTable::Amd(table) => self.set_amd_table(table),
+            ClocksTable::Amd(table) => self.set_amd_table(table, limits),
             ClocksTable::Nvidia(table) => self.set_nvidia_table(table),
+            ClocksTable::Intel(table) => self.set_intel_table(table),
+            ClocksTable::AmdOffsets(table) => self.set_amd_offsets_table(&table, limits),
         }
+        self.set_extra_clocks(extra_clocks);
 
-        for adjustment in adjustments {
+        for adjustment in adjustments.into_iter().chain(extra_adjustments) {
             adjustment.refresh();
         }
+        for point in self.curve_adjustments.borrow().iter() {
+            point.clockspeed.refresh();
+            point.voltage.refresh();
+        }
 
         Ok(())
     }
 
-    fn set_amd_table(&self, table: AmdClocksTable) {
-        if let Some((current_sclk_min, sclk_min, sclk_max)) =
-            extract_value_and_range_amd(&table, |table| {
-                (
-                    table.get_current_sclk_range().min,
-                    table.get_min_sclk_range(),
-                )
-            })
+    fn set_amd_table(&self, table: AmdClocksTable, limits: &ClocksLimits) {
+        if let Some((current_sclk_min, (sclk_min, sclk_max))) = table
+            .get_current_sclk_range()
+            .min
+            .zip(limits.min_core_clock)
         {
             let min_sclk_adjustment = &self.min_sclk_adjustment.imp().adjustment;
             min_sclk_adjustment.set_lower(sclk_min.into());
@@ -191,13 +253,10 @@ impl ClocksFrame {
             self.min_sclk_adjustment.set_visible(true);
         }
 
-        if let Some((current_mclk_min, mclk_min, mclk_max)) =
-            extract_value_and_range_amd(&table, |table| {
-                (
-                    table.get_current_mclk_range().min,
-                    table.get_min_mclk_range(),
-                )
-            })
+        if let Some((current_mclk_min, (mclk_min, mclk_max))) = table
+            .get_current_mclk_range()
+            .min
+            .zip(limits.min_memory_clock)
         {
             let min_mclk_adjustment = &self.min_mclk_adjustment.imp().adjustment;
             min_mclk_adjustment.set_lower(mclk_min.into());
@@ -207,15 +266,10 @@ impl ClocksFrame {
             self.min_mclk_adjustment.set_visible(true);
         }
 
-        if let Some((current_min_voltage, voltage_min, voltage_max)) =
-            extract_value_and_range_amd(&table, |table| {
-                (
-                    table
-                        .get_current_voltage_range()
-                        .and_then(|range| range.min),
-                    table.get_min_voltage_range(),
-                )
-            })
+        if let Some((current_min_voltage, (voltage_min, voltage_max))) = table
+            .get_current_voltage_range()
+            .and_then(|range| range.min)
+            .zip(limits.min_voltage)
         {
             let min_voltage_adjustment = &self.min_voltage_adjustment.imp().adjustment;
 
@@ -226,10 +280,8 @@ impl ClocksFrame {
             self.min_voltage_adjustment.set_visible(true);
         }
 
-        if let Some((current_sclk_max, sclk_min, sclk_max)) =
-            extract_value_and_range_amd(&table, |table| {
-                (table.get_max_sclk(), table.get_max_sclk_range())
-            })
+        if let Some((current_sclk_max, (sclk_min, sclk_max))) =
+            table.get_max_sclk().zip(limits.max_core_clock)
         {
             let max_sclk_adjustment = &self.max_sclk_adjustment.imp().adjustment;
 
@@ -240,10 +292,8 @@ impl ClocksFrame {
             self.max_sclk_adjustment.set_visible(true);
         }
 
-        if let Some((current_mclk_max, mclk_min, mclk_max)) =
-            extract_value_and_range_amd(&table, |table| {
-                (table.get_max_mclk(), table.get_max_mclk_range())
-            })
+        if let Some((current_mclk_max, (mclk_min, mclk_max))) =
+            table.get_max_mclk().zip(limits.max_memory_clock)
         {
             let max_mclk_adjustment = &self.max_mclk_adjustment.imp().adjustment;
             max_mclk_adjustment.set_lower(mclk_min.into());
@@ -253,10 +303,8 @@ impl ClocksFrame {
             self.max_mclk_adjustment.set_visible(true);
         }
 
-        if let Some((current_voltage_max, voltage_min, voltage_max)) =
-            extract_value_and_range_amd(&table, |table| {
-                (table.get_max_sclk_voltage(), table.get_max_voltage_range())
-            })
+        if let Some((current_voltage_max, (voltage_min, voltage_max))) =
+            table.get_max_sclk_voltage().zip(limits.max_voltage)
         {
             let max_voltage_adjustment = &self.max_voltage_adjustment.imp().adjustment;
             max_voltage_adjustment.set_lower(voltage_min.into());
@@ -268,10 +316,8 @@ impl ClocksFrame {
 
         if let AmdClocksTable::Vega20(table) = table {
             if let Some(offset) = table.voltage_offset {
-                let (min_offset, max_offset) = table
-                    .od_range
+                let (min_offset, max_offset) = limits
                     .voltage_offset
-                    .and_then(|range| range.into_full())
                     .unwrap_or((-DEFAULT_VOLTAGE_OFFSET_RANGE, DEFAULT_VOLTAGE_OFFSET_RANGE));
 
                 let voltage_offset_adjustment = &self.voltage_offset_adjustment.imp().adjustment;
@@ -281,6 +327,53 @@ impl ClocksFrame {
 
                 self.voltage_offset_adjustment.set_visible(true);
             }
+
+            self.set_curve_points(&table, limits);
+        }
+    }
+
+    fn set_curve_points(
+        &self,
+        table: &amdgpu_sysfs::gpu_handle::overdrive::vega20::Table,
+        limits: &ClocksLimits,
+    ) {
+        let mut curve_adjustments = self.curve_adjustments.borrow_mut();
+
+        for (index, level) in table.vddc_curve.iter().enumerate() {
+            let point_limits = limits.curve_points.get(index).copied().unwrap_or_default();
+            let Some(((sclk_min, sclk_max), (voltage_min, voltage_max))) =
+                point_limits.clockspeed.zip(point_limits.voltage)
+            else {
+                continue;
+            };
+
+            let row = (index * 2) as i32;
+            let clockspeed = AdjustmentRow::new_and_attach(
+                &fl!("curve-point-clock", index = index),
+                &self.curve_grid,
+                row,
+            );
+            let voltage = AdjustmentRow::new_and_attach(
+                &fl!("curve-point-voltage", index = index),
+                &self.curve_grid,
+                row + 1,
+            );
+
+            let clockspeed_adjustment = &clockspeed.imp().adjustment;
+            clockspeed_adjustment.set_lower(sclk_min.into());
+            clockspeed_adjustment.set_upper(sclk_max.into());
+            clockspeed_adjustment.set_value(level.clockspeed.into());
+
+            let voltage_adjustment = &voltage.imp().adjustment;
+            voltage_adjustment.set_lower(voltage_min.into());
+            voltage_adjustment.set_upper(voltage_max.into());
+            voltage_adjustment.set_value(level.voltage.into());
+
+            curve_adjustments.push(CurvePointRows {
+                index,
+                clockspeed,
+                voltage,
+            });
         }
     }
 
@@ -293,8 +386,91 @@ impl ClocksFrame {
         }
     }
 
+    fn set_intel_table(&self, table: IntelClocksTable) {
+        let Some((freq_min, freq_max)) = table.gt_freq_range else {
+            return;
+        };
+
+        let rows = [
+            (table.gt_freq_min, &self.min_sclk_adjustment),
+            (table.gt_freq_max, &self.max_sclk_adjustment),
+            (table.gt_freq_boost, &self.boost_sclk_adjustment),
+        ];
+        for (value, row) in rows {
+            if let Some(value) = value {
+                let adjustment = &row.imp().adjustment;
+                adjustment.set_lower(freq_min.into());
+                adjustment.set_upper(freq_max.into());
+                adjustment.set_value(value.into());
+
+                row.set_visible(true);
+            }
+        }
+    }
+
+    fn set_amd_offsets_table(&self, table: &AmdOffsetsTable, limits: &ClocksLimits) {
+        if let Some(offset) = table.sclk_offset {
+            set_amd_offset(
+                offset.offset,
+                limits
+                    .core_clock_offset
+                    .unwrap_or((-DEFAULT_SCLK_OFFSET_RANGE, DEFAULT_SCLK_OFFSET_RANGE)),
+                &self.sclk_offset_adjustment,
+            );
+        }
+        if let Some(offset) = table.voltage_offset {
+            set_amd_offset(
+                offset.offset,
+                limits
+                    .voltage_offset
+                    .unwrap_or((-DEFAULT_VOLTAGE_OFFSET_RANGE, DEFAULT_VOLTAGE_OFFSET_RANGE)),
+                &self.voltage_offset_adjustment,
+            );
+        }
+
+        if let Some((min_mclk, max_mclk)) = table.mclk {
+            let rows = [
+                (min_mclk, limits.min_memory_clock, &self.min_mclk_adjustment),
+                (max_mclk, limits.max_memory_clock, &self.max_mclk_adjustment),
+            ];
+            for (value, range, row) in rows {
+                let Some((mclk_min, mclk_max)) = range else {
+                    continue;
+                };
+                let adjustment = &row.imp().adjustment;
+                adjustment.set_lower(mclk_min.into());
+                adjustment.set_upper(mclk_max.into());
+                adjustment.set_value(value.into());
+
+                row.set_visible(true);
+            }
+        }
+    }
+
+    fn set_extra_clocks(&self, extra_clocks: &BTreeMap<ExtraClock, ExtraClockRange>) {
+        for rows in self.extra_clock_adjustments.iter() {
+            let Some(info) = extra_clocks.get(&rows.clock) else {
+                continue;
+            };
+            let Some((range_min, range_max)) = info.range else {
+                continue;
+            };
+
+            for (value, row) in [(info.min, &rows.min), (info.max, &rows.max)] {
+                let adjustment = &row.imp().adjustment;
+                adjustment.set_lower(range_min.into());
+                adjustment.set_upper(range_max.into());
+                adjustment.set_value(value.into());
+
+                row.set_visible(true);
+            }
+        }
+    }
+
     pub fn show(&self) {
         self.tweaking_grid.show();
+        self.curve_grid
+            .set_visible(self.advanced_togglebutton.is_active());
         self.modes_switcher_box.show();
         self.warning_label.show();
         self.clocks_data_unavailable_label.hide();
@@ -302,6 +478,7 @@ impl ClocksFrame {
 
     pub fn hide(&self) {
         self.tweaking_grid.hide();
+        self.curve_grid.hide();
         self.modes_switcher_box.hide();
         self.warning_label.hide();
         self.clocks_data_unavailable_label.show();
@@ -343,6 +520,31 @@ impl ClocksFrame {
             .imp()
             .adjustment
             .connect_value_changed(f.clone());
+        self.boost_sclk_adjustment
+            .imp()
+            .adjustment
+            .connect_value_changed(f.clone());
+        self.sclk_offset_adjustment
+            .imp()
+            .adjustment
+            .connect_value_changed(f.clone());
+        for rows in self.extra_clock_adjustments.iter() {
+            for row in [&rows.min, &rows.max] {
+                row.imp().adjustment.connect_value_changed(f.clone());
+            }
+        }
+        for point in self.curve_adjustments.borrow().iter() {
+            point
+                .clockspeed
+                .imp()
+                .adjustment
+                .connect_value_changed(f.clone());
+            point
+                .voltage
+                .imp()
+                .adjustment
+                .connect_value_changed(f.clone());
+        }
         self.voltage_offset_adjustment
             .imp()
             .adjustment
@@ -364,6 +566,14 @@ impl ClocksFrame {
                 (&self.max_sclk_adjustment, SetClocksCommand::MaxCoreClock),
                 (&self.max_mclk_adjustment, SetClocksCommand::MaxMemoryClock),
                 (&self.max_voltage_adjustment, SetClocksCommand::MaxVoltage),
+                (
+                    &self.boost_sclk_adjustment,
+                    SetClocksCommand::BoostCoreClock,
+                ),
+                (
+                    &self.sclk_offset_adjustment,
+                    SetClocksCommand::CoreClockOffset,
+                ),
             ];
             let mut commands: Vec<SetClocksCommand> = adjustments
                 .iter()
@@ -373,12 +583,36 @@ impl ClocksFrame {
                 })
                 .collect();
 
+            for rows in self.extra_clock_adjustments.iter() {
+                if let Some(value) = rows.min.get_value() {
+                    commands.push(SetClocksCommand::MinExtraClock(rows.clock, value));
+                }
+                if let Some(value) = rows.max.get_value() {
+                    commands.push(SetClocksCommand::MaxExtraClock(rows.clock, value));
+                }
+            }
+
             if self.voltage_offset_adjustment.get_visible() {
                 if let Some(offset) = self.voltage_offset_adjustment.get_value() {
                     commands.push(SetClocksCommand::VoltageOffset(offset));
                 }
             }
 
+            if self.curve_grid.get_visible() {
+                for point in self.curve_adjustments.borrow().iter() {
+                    let clockspeed = point.clockspeed.get_value();
+                    let voltage = point.voltage.get_value();
+
+                    if clockspeed.is_some() || voltage.is_some() {
+                        commands.push(SetClocksCommand::GpuCurvePoint {
+                            index: point.index,
+                            clockspeed: point.clockspeed.imp().adjustment.value() as i32,
+                            voltage: point.voltage.imp().adjustment.value() as i32,
+                        });
+                    }
+                }
+            }
+
             commands
         } else {
             vec![]
@@ -390,22 +624,20 @@ impl ClocksFrame {
         self.basic_togglebutton.set_active(!advanced);
 
         self.min_values_grid.set_visible(advanced);
+        self.curve_grid.set_visible(advanced);
     }
 }
 
-fn extract_value_and_range_amd(
-    table: &AmdClocksTable,
-    f: fn(
-        &AmdClocksTable,
-    ) -> (
-        Option<i32>,
-        Option<amdgpu_sysfs::gpu_handle::overdrive::Range>,
-    ),
-) -> Option<(i32, i32, i32)> {
-    let (maybe_value, maybe_range) = f(table);
-    let (value, range) = maybe_value.zip(maybe_range)?;
-    let (min, max) = range.try_into().ok()?;
-    Some((value, min, max))
+struct CurvePointRows {
+    index: usize,
+    clockspeed: AdjustmentRow,
+    voltage: AdjustmentRow,
+}
+
+struct ExtraClockRows {
+    clock: ExtraClock,
+    min: AdjustmentRow,
+    max: AdjustmentRow,
 }
 
 fn set_nvidia_clock_offset(clock_info: &NvidiaClockInfo, adjustment_row: &AdjustmentRow) {
@@ -416,3 +648,12 @@ fn set_nvidia_clock_offset(clock_info: &NvidiaClockInfo, adjustment_row: &Adjust
 
     adjustment_row.set_visible(true);
 }
+
+fn set_amd_offset(offset: i32, (min, max): (i32, i32), adjustment_row: &AdjustmentRow) {
+    let oc_adjustment = &adjustment_row.imp().adjustment;
+    oc_adjustment.set_lower(min.into());
+    oc_adjustment.set_upper(max.into());
+    oc_adjustment.set_value(offset.into());
+
+    adjustment_row.set_visible(true);
+}
@@ -28,6 +28,18 @@ impl GpuStatsSection {
             stats.vram.total.unwrap_or(0) / 1024 / 1024,
         ));
 
+        if let (Some(used_gtt), Some(total_gtt)) = (stats.vram.gtt_used, stats.vram.gtt_total) {
+            self.set_gtt_available(true);
+            self.set_gtt_usage(used_gtt as f64 / total_gtt.max(1) as f64);
+            self.set_gtt_usage_text(format!(
+                "{}/{} MiB",
+                used_gtt / 1024 / 1024,
+                total_gtt / 1024 / 1024,
+            ));
+        } else {
+            self.set_gtt_available(false);
+        }
+
         let clockspeed = stats.clockspeed;
         self.set_core_clock(format_clockspeed(clockspeed.gpu_clockspeed, 1.0));
         self.set_current_core_clock(format_current_gfxclk(clockspeed.current_gfxclk));
@@ -36,8 +48,14 @@ impl GpuStatsSection {
             self.vram_clock_ratio(),
         ));
 
-        let voltage = format!("{:.3} V", stats.voltage.gpu.unwrap_or(0) as f64 / 1000f64);
-        self.set_voltage(voltage);
+        // Not every GPU reports its voltage, a reading of 0 would look like a broken undervolt
+        match stats.voltage.gpu {
+            Some(voltage) => {
+                self.set_voltage_available(true);
+                self.set_voltage(format!("{:.3} V", voltage as f64 / 1000f64));
+            }
+            None => self.set_voltage_available(false),
+        }
 
         let temperature = if stats.temps.len() == 1 {
             stats.temps.values().next().unwrap().current
@@ -70,10 +88,33 @@ impl GpuStatsSection {
             power_cap_current.unwrap_or(0.0)
         ));
 
-        match &stats.throttle_info {
-            Some(throttle_info) => {
+        match (stats.throttle_status, &stats.throttle_info) {
+            (Some(status), _) => {
+                let mut text = if status.is_active() {
+                    let mut kinds = status.active_kinds().join(", ");
+                    if let Some(first) = kinds.get_mut(..1) {
+                        first.make_ascii_uppercase();
+                    }
+                    fl!("throttling-active", kinds = kinds)
+                } else {
+                    fl!("no")
+                };
+                if let Some(events) = stats.throttle_events {
+                    let _ = write!(
+                        text,
+                        " ({})",
+                        fl!(
+                            "throttle-events",
+                            power = events.power,
+                            thermal = events.thermal
+                        )
+                    );
+                }
+                self.set_throttling(text);
+            }
+            (None, Some(throttle_info)) => {
                 if throttle_info.is_empty() {
-                    self.set_throttling("No")
+                    self.set_throttling(fl!("no"))
                 } else {
                     let type_text: Vec<String> = throttle_info
                         .iter()
@@ -89,7 +130,7 @@ impl GpuStatsSection {
                     self.set_throttling(text);
                 }
             }
-            None => self.set_throttling("Unknown"),
+            (None, None) => self.set_throttling(fl!("unknown")),
         }
     }
 }
@@ -104,12 +145,12 @@ mod imp {
     use crate::app::{info_row::InfoRow, page_section::PageSection};
     use gtk::{
         glib::{self, subclass::InitializingObject, types::StaticTypeExt, Properties},
-        prelude::ObjectExt,
+        prelude::{ButtonExt, ObjectExt, WidgetExt},
         subclass::{
             prelude::*,
             widget::{CompositeTemplateClass, WidgetImpl},
         },
-        CompositeTemplate,
+        Button, CompositeTemplate, Label, TemplateChild,
     };
     use std::cell::{Cell, RefCell};
 
@@ -126,6 +167,8 @@ mod imp {
         #[property(get, set)]
         voltage: RefCell<String>,
         #[property(get, set)]
+        voltage_available: Cell<bool>,
+        #[property(get, set)]
         temperature: RefCell<String>,
         #[property(get, set)]
         gpu_usage: RefCell<String>,
@@ -136,10 +179,41 @@ mod imp {
         #[property(get, set)]
         vram_usage_text: RefCell<String>,
         #[property(get, set)]
+        gtt_available: Cell<bool>,
+        #[property(get, set)]
+        gtt_usage: Cell<f64>,
+        #[property(get, set)]
+        gtt_usage_text: RefCell<String>,
+        #[property(get, set)]
         throttling: RefCell<String>,
 
         #[property(get, set)]
         vram_clock_ratio: Cell<f64>,
+
+        #[template_child]
+        vram_usage_title: TemplateChild<Label>,
+        #[template_child]
+        gtt_usage_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        gtt_usage_title: TemplateChild<Label>,
+        #[template_child]
+        core_clock_row: TemplateChild<InfoRow>,
+        #[template_child]
+        current_core_clock_row: TemplateChild<InfoRow>,
+        #[template_child]
+        voltage_row: TemplateChild<InfoRow>,
+        #[template_child]
+        temperature_row: TemplateChild<InfoRow>,
+        #[template_child]
+        vram_clock_row: TemplateChild<InfoRow>,
+        #[template_child]
+        gpu_usage_row: TemplateChild<InfoRow>,
+        #[template_child]
+        power_usage_row: TemplateChild<InfoRow>,
+        #[template_child]
+        throttling_row: TemplateChild<InfoRow>,
+        #[template_child]
+        graphs_button: TemplateChild<Button>,
     }
 
     #[glib::object_subclass]
@@ -159,7 +233,32 @@ mod imp {
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for GpuStatsSection {}
+    impl ObjectImpl for GpuStatsSection {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.obj().set_property("name", fl!("statistics"));
+            self.vram_usage_title.set_label(&fl!("vram-usage"));
+            self.gtt_usage_title.set_label(&fl!("gtt-usage"));
+            self.gtt_usage_box
+                .set_tooltip_text(Some(&fl!("gtt-usage-description")));
+            self.graphs_button.set_label(&fl!("show-historical-charts"));
+
+            let rows = [
+                (&self.core_clock_row, fl!("gpu-core-clock-average")),
+                (&self.current_core_clock_row, fl!("gpu-core-clock-target")),
+                (&self.voltage_row, fl!("gpu-voltage")),
+                (&self.temperature_row, fl!("gpu-temperature")),
+                (&self.vram_clock_row, fl!("gpu-memory-clock")),
+                (&self.gpu_usage_row, fl!("gpu-usage")),
+                (&self.power_usage_row, fl!("power-usage")),
+                (&self.throttling_row, fl!("throttling")),
+            ];
+            for (row, name) in rows {
+                row.set_property("name", name);
+            }
+        }
+    }
 
     impl WidgetImpl for GpuStatsSection {}
     impl BoxImpl for GpuStatsSection {}
@@ -1,9 +1,10 @@
 mod clocks_frame;
-mod gpu_stats_section;
+pub(super) mod gpu_stats_section;
 mod performance_frame;
 mod power_cap_section;
 mod power_profile;
 mod power_states;
+mod runtime_pm_frame;
 
 use self::power_cap_section::PowerCapSection;
 use self::power_states::power_states_frame::PowerStatesFrame;
@@ -12,16 +13,14 @@ use clocks_frame::ClocksFrame;
 use gpu_stats_section::GpuStatsSection;
 use gtk::*;
 use gtk::{glib::clone, prelude::*};
-use lact_client::schema::{DeviceInfo, DeviceStats, SystemInfo};
-use lact_schema::ClocksTable;
+use lact_client::schema::{DeviceCapabilities, DeviceInfo, DeviceStats, SystemInfo};
+use lact_schema::ClocksInfo;
 use performance_frame::PerformanceFrame;
+use runtime_pm_frame::RuntimePmFrame;
 // use power_cap_frame::PowerCapFrame;
 use std::collections::HashMap;
 use tracing::warn;
 
-const OVERCLOCKING_DISABLED_TEXT: &str = "Overclocking support is not enabled! \
-You can still change basic settings, but the more advanced clocks and voltage control will not be available.";
-
 #[derive(Clone)]
 pub struct OcPage {
     pub container: ScrolledWindow,
@@ -31,6 +30,7 @@ pub struct OcPage {
     power_cap_section: PowerCapSection,
     pub power_states_frame: PowerStatesFrame,
     pub clocks_frame: ClocksFrame,
+    pub runtime_pm_frame: RuntimePmFrame,
     pub enable_overclocking_button: Option<Button>,
 }
 
@@ -50,7 +50,8 @@ impl OcPage {
         let mut enable_overclocking_button = None;
 
         if system_info.amdgpu_overdrive_enabled == Some(false) {
-            let (warning_frame, button) = oc_warning_frame();
+            let (warning_frame, button) =
+                oc_warning_frame(system_info.amdgpu_overdrive_reboot_required);
             enable_overclocking_button = Some(button);
             vbox.append(&warning_frame);
         }
@@ -62,6 +63,7 @@ impl OcPage {
         let performance_level_frame = PerformanceFrame::new();
         let clocks_frame = ClocksFrame::new();
         let power_states_frame = PowerStatesFrame::new();
+        let runtime_pm_frame = RuntimePmFrame::new();
 
         performance_level_frame.connect_settings_changed(clone!(
             #[strong]
@@ -78,6 +80,7 @@ impl OcPage {
         vbox.append(&performance_level_frame.container);
         vbox.append(&power_states_frame);
         vbox.append(&clocks_frame.container);
+        vbox.append(&runtime_pm_frame.container);
 
         container.set_child(Some(&vbox));
 
@@ -89,12 +92,14 @@ impl OcPage {
             power_cap_section,
             enable_overclocking_button,
             power_states_frame,
+            runtime_pm_frame,
         }
     }
 
     pub fn set_stats(&self, stats: &DeviceStats, initial: bool) {
         self.stats_section.set_stats(stats);
         self.power_states_frame.set_stats(stats);
+        self.runtime_pm_frame.set_stats(stats.runtime_pm.as_ref());
         if initial {
             self.power_cap_section
                 .set_max_value(stats.power.cap_max.unwrap_or_default());
@@ -111,9 +116,15 @@ impl OcPage {
             }
 
             self.set_performance_level(stats.performance_level);
+            self.performance_frame.set_low_latency(stats.low_latency);
         }
     }
 
+    pub fn set_capabilities(&self, capabilities: &DeviceCapabilities) {
+        self.performance_frame
+            .set_low_latency_available(capabilities.low_latency_mode);
+    }
+
     pub fn set_info(&self, info: &DeviceInfo) {
         let vram_clock_ratio = info
             .drm_info
@@ -125,9 +136,14 @@ impl OcPage {
         self.clocks_frame.set_vram_clock_ratio(vram_clock_ratio);
     }
 
-    pub fn set_clocks_table(&self, table: Option<ClocksTable>) {
-        match table {
-            Some(table) => match self.clocks_frame.set_table(table) {
+    pub fn set_clocks_info(&self, info: Option<ClocksInfo>) {
+        match info {
+            Some(ClocksInfo {
+                table: Some(table),
+                limits,
+                extra_clocks,
+                ..
+            }) => match self.clocks_frame.set_table(table, &limits, &extra_clocks) {
                 Ok(()) => {
                     self.clocks_frame.show();
                 }
@@ -136,7 +152,7 @@ impl OcPage {
                     self.clocks_frame.hide();
                 }
             },
-            None => {
+            _ => {
                 self.clocks_frame.hide();
             }
         }
@@ -163,8 +179,9 @@ impl OcPage {
         }
     }
 
+    /// The low latency mode overrides the performance level, so the one reported in the stats is not applied
     pub fn get_performance_level(&self) -> Option<PerformanceLevel> {
-        if self.performance_frame.get_visibility() {
+        if self.performance_frame.get_visibility() && !self.performance_frame.is_low_latency() {
             let level = self.performance_frame.get_selected_performance_level();
             Some(level)
         } else {
@@ -177,7 +194,9 @@ impl OcPage {
     }
 
     pub fn get_enabled_power_states(&self) -> HashMap<PowerLevelKind, Vec<u8>> {
-        if self.performance_frame.get_selected_performance_level() == PerformanceLevel::Manual {
+        if self.performance_frame.get_selected_performance_level() == PerformanceLevel::Manual
+            && !self.performance_frame.is_low_latency()
+        {
             self.power_states_frame.get_enabled_power_states()
         } else {
             HashMap::new()
@@ -185,8 +204,8 @@ impl OcPage {
     }
 }
 
-fn oc_warning_frame() -> (Frame, Button) {
-    let container = Frame::new(Some("Overclocking information"));
+fn oc_warning_frame(reboot_required: bool) -> (Frame, Button) {
+    let container = Frame::new(Some(&fl!("oc-information")));
 
     container.set_label_align(0.3);
 
@@ -201,14 +220,19 @@ fn oc_warning_frame() -> (Frame, Button) {
 
     let warning_label = Label::builder()
         .use_markup(true)
-        .label(OVERCLOCKING_DISABLED_TEXT)
+        .label(if reboot_required {
+            fl!("oc-reboot-required")
+        } else {
+            fl!("oc-disabled")
+        })
         .wrap(true)
         .wrap_mode(pango::WrapMode::Word)
         .build();
 
     let enable_button = Button::builder()
-        .label("Enable Overclocking")
+        .label(fl!("enable-oc"))
         .halign(Align::End)
+        .sensitive(!reboot_required)
         .build();
 
     vbox.append(&warning_label);
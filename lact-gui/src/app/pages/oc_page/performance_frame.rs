@@ -4,10 +4,15 @@ use glib::clone;
 use gtk::subclass::prelude::ObjectSubclassIsExt;
 use gtk::{
     glib, DropDown, Label, ListBox, MenuButton, Notebook, NotebookPage, Popover, SelectionMode,
-    StringObject,
+    StringObject, Switch,
 };
 use gtk::{prelude::*, Align, Orientation, StringList};
-use std::{cell::RefCell, rc::Rc, str::FromStr};
+use lact_client::schema::PowerState;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    str::FromStr,
+};
 
 use super::power_profile::power_profile_heuristics_grid::PowerProfileHeuristicsGrid;
 
@@ -24,17 +29,30 @@ pub struct PerformanceFrame {
     mode_box: gtk::Box,
     modes_table: Rc<RefCell<Option<PowerProfileModesTable>>>,
     power_mode_info_notebook: Notebook,
+    low_latency_box: gtk::Box,
+    low_latency_switch: Switch,
+    /// Set while the switch is updated from the stats, so that it doesn't count as a user change
+    updating_low_latency: Rc<Cell<bool>>,
+    min_vram_box: gtk::Box,
+    min_vram_drop_down: DropDown,
+    updating_min_vram: Rc<Cell<bool>>,
 
     values_changed_callback: Rc<RefCell<Option<ValuesChangedCallback>>>,
 }
 
 impl PerformanceFrame {
     pub fn new() -> Self {
-        let container = PageSection::new("Performance");
-
-        let levels_model: StringList = ["Automatic", "Highest Clocks", "Lowest Clocks", "Manual"]
-            .into_iter()
-            .collect();
+        let container = PageSection::new(&fl!("performance"));
+
+        let levels_model: StringList = [
+            fl!("performance-level-auto"),
+            fl!("performance-level-high"),
+            fl!("performance-level-low"),
+            fl!("performance-level-manual"),
+        ]
+        .iter()
+        .map(String::as_str)
+        .collect();
 
         let level_box = gtk::Box::new(Orientation::Horizontal, 10);
 
@@ -43,7 +61,7 @@ impl PerformanceFrame {
             .sensitive(false)
             .build();
         let description_label = Label::builder().halign(Align::End).hexpand(true).build();
-        let perfromance_title_label = Label::builder().label("Performance level:").build();
+        let perfromance_title_label = Label::builder().label(fl!("performance-level")).build();
 
         level_box.append(&perfromance_title_label);
         level_box.append(&description_label);
@@ -79,9 +97,7 @@ impl PerformanceFrame {
         let modes_popover = Popover::builder().child(&modes_popover_content).build();
         mode_menu_button.set_popover(Some(&modes_popover));
 
-        let unavailable_label = Label::new(Some(
-            "Performance level has to be set to \"manual\" to use power states and modes",
-        ));
+        let unavailable_label = Label::new(Some(&fl!("manual-level-needed")));
         let mode_info_popover = Popover::builder().child(&unavailable_label).build();
         let manual_info_button = MenuButton::builder()
             .icon_name("dialog-information-symbolic")
@@ -90,13 +106,47 @@ impl PerformanceFrame {
             .popover(&mode_info_popover)
             .build();
 
-        let mode_title_label = Label::new(Some("Power level mode:"));
+        let mode_title_label = Label::new(Some(&fl!("power-level-mode")));
         mode_box.append(&mode_title_label);
         mode_box.append(&manual_info_button);
         mode_box.append(&mode_menu_button);
 
         container.append(&mode_box);
 
+        let low_latency_box = gtk::Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(10)
+            .visible(false)
+            .build();
+        let low_latency_label = Label::builder()
+            .label(fl!("low-latency-mode"))
+            .tooltip_text(fl!("low-latency-mode-description"))
+            .halign(Align::Start)
+            .hexpand(true)
+            .build();
+        let low_latency_switch = Switch::builder().valign(Align::Center).build();
+        low_latency_box.append(&low_latency_label);
+        low_latency_box.append(&low_latency_switch);
+
+        container.append(&low_latency_box);
+
+        let min_vram_box = gtk::Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(10)
+            .visible(false)
+            .build();
+        let min_vram_label = Label::builder()
+            .label(fl!("min-vram-state"))
+            .tooltip_text(fl!("min-vram-state-description"))
+            .halign(Align::Start)
+            .hexpand(true)
+            .build();
+        let min_vram_drop_down = DropDown::builder().valign(Align::Center).build();
+        min_vram_box.append(&min_vram_label);
+        min_vram_box.append(&min_vram_drop_down);
+
+        container.append(&min_vram_box);
+
         let frame = Self {
             container,
             level_drop_down,
@@ -107,6 +157,12 @@ impl PerformanceFrame {
             mode_box,
             modes_table: Rc::new(RefCell::new(None)),
             power_mode_info_notebook,
+            low_latency_box,
+            low_latency_switch,
+            updating_low_latency: Rc::default(),
+            min_vram_box,
+            min_vram_drop_down,
+            updating_min_vram: Rc::default(),
             values_changed_callback: Rc::default(),
         };
 
@@ -138,6 +194,70 @@ impl PerformanceFrame {
         self.update_from_selection();
     }
 
+    pub fn set_low_latency_available(&self, available: bool) {
+        self.low_latency_box.set_visible(available);
+    }
+
+    /// The performance level and power profile mode can't be changed while the low latency mode overrides them
+    pub fn set_low_latency(&self, enabled: bool) {
+        self.updating_low_latency.set(true);
+        self.low_latency_switch.set_active(enabled);
+        self.updating_low_latency.set(false);
+
+        self.level_drop_down.set_sensitive(!enabled);
+        self.update_from_selection();
+    }
+
+    pub fn is_low_latency(&self) -> bool {
+        self.low_latency_switch.is_active()
+    }
+
+    pub fn connect_low_latency_toggled<F: Fn(bool) + 'static>(&self, f: F) {
+        let updating = self.updating_low_latency.clone();
+        self.low_latency_switch
+            .connect_active_notify(move |switch| {
+                if !updating.get() {
+                    f(switch.is_active());
+                }
+            });
+    }
+
+    /// The first entry lets the VRAM use all states, the others keep it at or above a state.
+    /// A state is shown as the minimum when it and all states above it are the only enabled ones.
+    pub fn set_vram_states(&self, states: &[PowerState]) {
+        self.min_vram_box.set_visible(states.len() > 1);
+
+        let mut labels = vec![fl!("min-vram-state-any")];
+        labels.extend(states.iter().map(|state| format!("{} MHz", state.value)));
+        let model: StringList = labels.iter().map(String::as_str).collect();
+
+        let min_state = states
+            .iter()
+            .position(|state| state.enabled)
+            .filter(|min| *min > 0 && states[*min..].iter().all(|state| state.enabled));
+
+        self.updating_min_vram.set(true);
+        self.min_vram_drop_down.set_model(Some(&model));
+        self.min_vram_drop_down
+            .set_selected(min_state.map_or(0, |min| min as u32 + 1));
+        self.updating_min_vram.set(false);
+    }
+
+    pub fn connect_min_vram_state_changed<F: Fn(Option<u8>) + 'static>(&self, f: F) {
+        let updating = self.updating_min_vram.clone();
+        self.min_vram_drop_down
+            .connect_selected_notify(move |drop_down| {
+                if updating.get() {
+                    return;
+                }
+                let state = drop_down
+                    .selected()
+                    .checked_sub(1)
+                    .and_then(|state| u8::try_from(state).ok());
+                f(state);
+            });
+    }
+
     pub fn set_power_profile_modes(&self, table: Option<PowerProfileModesTable>) {
         self.mode_box.set_visible(table.is_some());
 
@@ -257,20 +377,21 @@ impl PerformanceFrame {
         let mut enable_mode_control = false;
 
         let text = match self.level_drop_down.selected() {
-            0 => "Automatically adjust GPU and VRAM clocks. (Default)",
-            1 => "Always use the highest clockspeeds for GPU and VRAM.",
-            2 => "Always use the lowest clockspeeds for GPU and VRAM.",
+            0 => fl!("performance-level-auto-description"),
+            1 => fl!("performance-level-high-description"),
+            2 => fl!("performance-level-low-description"),
             3 => {
                 enable_mode_control = true;
-                "Manual performance control."
+                fl!("performance-level-manual-description")
             }
             _ => unreachable!(),
         };
-        self.description_label.set_text(text);
+        self.description_label.set_text(&text);
 
         self.manual_info_button.set_visible(!enable_mode_control);
 
-        self.mode_menu_button.set_sensitive(enable_mode_control);
+        self.mode_menu_button
+            .set_sensitive(enable_mode_control && !self.is_low_latency());
         self.mode_menu_button.set_hexpand(enable_mode_control);
 
         let values_changed_callback = self.values_changed_callback.borrow();
@@ -295,7 +416,7 @@ impl PerformanceFrame {
                         let values_grid = PowerProfileHeuristicsGrid::new();
                         values_grid.set_component(component, table);
 
-                        let title = component.clock_type.as_deref().unwrap_or("All");
+                        let title = component.clock_type.clone().unwrap_or_else(|| fl!("all"));
                         let title_label = Label::builder()
                             .label(title)
                             .margin_start(5)
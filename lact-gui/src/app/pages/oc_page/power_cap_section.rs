@@ -84,6 +84,8 @@ mod imp {
             self.parent_constructed();
 
             let obj = self.obj();
+            obj.set_property("name", fl!("power-usage-limit"));
+            self.reset_button.set_label(&fl!("default"));
 
             obj.connect_current_value_notify(move |section| {
                 let text = format!("{}/{} W", section.current_value(), section.max_value());
@@ -69,7 +69,7 @@ mod imp {
     use crate::app::pages::oc_page::power_states::power_states_list::PowerStatesList;
     use gtk::{
         glib::{self, subclass::InitializingObject, types::StaticTypeExt, Properties},
-        prelude::ObjectExt,
+        prelude::{ExpanderExt, ObjectExt},
         subclass::{
             prelude::*,
             widget::{CompositeTemplateClass, WidgetImpl},
@@ -110,7 +110,15 @@ mod imp {
     }
 
     #[glib::derived_properties]
-    impl ObjectImpl for PowerStatesFrame {}
+    impl ObjectImpl for PowerStatesFrame {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.expander.set_label(Some(&fl!("power-states")));
+            self.core_states_list.set_title(fl!("gpu-power-states"));
+            self.vram_states_list.set_title(fl!("vram-power-states"));
+        }
+    }
 
     impl WidgetImpl for PowerStatesFrame {}
     impl BoxImpl for PowerStatesFrame {}
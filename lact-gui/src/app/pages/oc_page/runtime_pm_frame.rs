@@ -0,0 +1,134 @@
+use crate::app::page_section::PageSection;
+use gtk::{prelude::*, Align, Label, Orientation, Switch};
+use lact_client::schema::RuntimePmStats;
+use std::{cell::Cell, rc::Rc};
+
+/// Shows whether a laptop dGPU can power off while unused, and what keeps it awake
+#[derive(Clone)]
+pub struct RuntimePmFrame {
+    pub container: PageSection,
+    status_label: Label,
+    suspended_time_label: Label,
+    processes_label: Label,
+    last_wake_label: Label,
+    auto_switch: Switch,
+    /// Set while the switch is updated from the stats, so that it doesn't count as a user change
+    updating_auto: Rc<Cell<bool>>,
+}
+
+impl RuntimePmFrame {
+    pub fn new() -> Self {
+        let container = PageSection::new(&fl!("runtime-pm"));
+        container.set_visible(false);
+
+        let value_row = |title: String| {
+            let row = gtk::Box::new(Orientation::Horizontal, 10);
+            let title_label = Label::builder()
+                .label(title)
+                .halign(Align::Start)
+                .hexpand(true)
+                .build();
+            let value_label = Label::builder()
+                .halign(Align::End)
+                .selectable(true)
+                .wrap(true)
+                .build();
+            row.append(&title_label);
+            row.append(&value_label);
+            container.append(&row);
+            value_label
+        };
+
+        let status_label = value_row(fl!("runtime-pm-status"));
+        let suspended_time_label = value_row(fl!("runtime-pm-suspended-time"));
+        let processes_label = value_row(fl!("runtime-pm-processes"));
+        let last_wake_label = value_row(fl!("runtime-pm-last-wake"));
+
+        let auto_box = gtk::Box::new(Orientation::Horizontal, 10);
+        let auto_label = Label::builder()
+            .label(fl!("runtime-pm-auto"))
+            .tooltip_text(fl!("runtime-pm-auto-description"))
+            .halign(Align::Start)
+            .hexpand(true)
+            .build();
+        let auto_switch = Switch::builder().valign(Align::Center).build();
+        auto_box.append(&auto_label);
+        auto_box.append(&auto_switch);
+        container.append(&auto_box);
+
+        Self {
+            container,
+            status_label,
+            suspended_time_label,
+            processes_label,
+            last_wake_label,
+            auto_switch,
+            updating_auto: Rc::default(),
+        }
+    }
+
+    pub fn set_stats(&self, stats: Option<&RuntimePmStats>) {
+        let Some(stats) = stats else {
+            self.container.set_visible(false);
+            return;
+        };
+        self.container.set_visible(true);
+
+        let status = if stats.is_suspended() {
+            fl!("runtime-pm-suspended")
+        } else {
+            fl!("runtime-pm-active")
+        };
+        self.status_label.set_label(&status);
+
+        let suspended_percent = match (stats.suspended_time_ms, stats.active_time_ms) {
+            (Some(suspended), Some(active)) if suspended + active > 0 => {
+                format!(
+                    "{:.0}%",
+                    suspended as f64 / (suspended + active) as f64 * 100.0
+                )
+            }
+            _ => fl!("unknown"),
+        };
+        self.suspended_time_label.set_label(&suspended_percent);
+
+        let processes = if stats.processes.is_empty() {
+            fl!("runtime-pm-no-processes")
+        } else {
+            stats.processes.join(", ")
+        };
+        self.processes_label.set_label(&processes);
+
+        let last_wake = match &stats.last_wake {
+            Some(wake) => {
+                let time = chrono::DateTime::from_timestamp_millis(wake.timestamp)
+                    .map(|time| {
+                        time.with_timezone(&chrono::Local)
+                            .format("%H:%M:%S")
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                if wake.processes.is_empty() {
+                    time
+                } else {
+                    format!("{time} ({})", wake.processes.join(", "))
+                }
+            }
+            None => fl!("unknown"),
+        };
+        self.last_wake_label.set_label(&last_wake);
+
+        self.updating_auto.set(true);
+        self.auto_switch.set_active(stats.auto);
+        self.updating_auto.set(false);
+    }
+
+    pub fn connect_auto_toggled<F: Fn(bool) + 'static>(&self, f: F) {
+        let updating = self.updating_auto.clone();
+        self.auto_switch.connect_active_notify(move |switch| {
+            if !updating.get() {
+                f(switch.is_active());
+            }
+        });
+    }
+}
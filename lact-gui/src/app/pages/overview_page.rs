@@ -0,0 +1,125 @@
+use super::oc_page::gpu_stats_section::GpuStatsSection;
+use crate::app::msg::AppMsg;
+use gtk::{glib::clone, prelude::*};
+use lact_schema::{DeviceListEntry, DeviceStats};
+use relm4::{ComponentParts, ComponentSender, RelmWidgetExt, SimpleComponent};
+use std::{collections::HashMap, rc::Rc};
+
+/// Stats of all GPUs side by side, so that multiple GPUs can be monitored at once.
+/// Settings are still changed for one GPU at a time on the other pages, with "Configure" switching to it.
+/// The page is only shown while there are multiple GPUs.
+pub struct OverviewPage {
+    root: gtk::ScrolledWindow,
+    cards_box: gtk::FlowBox,
+    sections: HashMap<String, GpuStatsSection>,
+    sender: ComponentSender<Self>,
+}
+
+#[derive(Debug)]
+pub enum OverviewMsg {
+    /// The list of GPUs changed, e.g. because one was plugged in
+    Devices(Vec<DeviceListEntry>),
+    VramClockRatio(String, f64),
+    Stats(String, Rc<DeviceStats>),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for OverviewPage {
+    type Init = Vec<DeviceListEntry>;
+    type Input = OverviewMsg;
+    type Output = AppMsg;
+
+    view! {
+        gtk::ScrolledWindow {
+            set_hscrollbar_policy: gtk::PolicyType::Never,
+
+            #[name = "cards_box"]
+            gtk::FlowBox {
+                set_selection_mode: gtk::SelectionMode::None,
+                set_homogeneous: true,
+                set_min_children_per_line: 1,
+                set_max_children_per_line: 2,
+                set_column_spacing: 20,
+                set_row_spacing: 15,
+                set_valign: gtk::Align::Start,
+                set_margin_horizontal: 20,
+            }
+        }
+    }
+
+    fn init(
+        devices: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let widgets = view_output!();
+
+        let mut model = Self {
+            root,
+            cards_box: widgets.cards_box.clone(),
+            sections: HashMap::with_capacity(devices.len()),
+            sender,
+        };
+        model.set_devices(devices);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            OverviewMsg::Devices(devices) => self.set_devices(devices),
+            OverviewMsg::VramClockRatio(id, ratio) => {
+                if let Some(section) = self.sections.get(&id) {
+                    section.set_vram_clock_ratio(ratio);
+                }
+            }
+            OverviewMsg::Stats(id, stats) => {
+                if let Some(section) = self.sections.get(&id) {
+                    section.set_stats(&stats);
+                }
+            }
+        }
+    }
+}
+
+impl OverviewPage {
+    /// Adds cards for new GPUs and removes the ones of GPUs that are gone
+    fn set_devices(&mut self, devices: Vec<DeviceListEntry>) {
+        self.sections.retain(|id, section| {
+            let present = devices.iter().any(|device| device.id == *id);
+            if !present {
+                self.cards_box.remove(section);
+            }
+            present
+        });
+
+        for device in devices {
+            if self.sections.contains_key(&device.id) {
+                continue;
+            }
+
+            let section = GpuStatsSection::new();
+            section.set_property("name", device.to_string());
+
+            let configure_button = gtk::Button::builder()
+                .label(fl!("configure"))
+                .halign(gtk::Align::End)
+                .build();
+            configure_button.connect_clicked(clone!(
+                #[strong(rename_to = sender)]
+                self.sender,
+                #[strong(rename_to = id)]
+                device.id,
+                move |_| {
+                    sender.output(AppMsg::SelectGpu(id.clone())).unwrap();
+                }
+            ));
+            section.append(&configure_button);
+
+            self.cards_box.insert(&section, -1);
+            self.sections.insert(device.id, section);
+        }
+
+        self.root.set_visible(self.sections.len() > 1);
+    }
+}
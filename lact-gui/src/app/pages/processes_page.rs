@@ -0,0 +1,120 @@
+use gtk::prelude::*;
+use lact_schema::GpuProcess;
+use relm4::{ComponentParts, ComponentSender, RelmWidgetExt, SimpleComponent};
+
+/// Processes that use the current GPU, similar to `nvtop`
+pub struct ProcessesPage {
+    grid: gtk::Grid,
+}
+
+#[derive(Debug)]
+pub enum ProcessesMsg {
+    Processes(Vec<GpuProcess>),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for ProcessesPage {
+    type Init = ();
+    type Input = ProcessesMsg;
+    type Output = ();
+
+    view! {
+        gtk::ScrolledWindow {
+            set_hscrollbar_policy: gtk::PolicyType::Never,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 10,
+                set_margin_all: 5,
+
+                #[name = "empty_label"]
+                gtk::Label {
+                    set_label: &fl!("no-processes"),
+                    #[watch]
+                    set_visible: model.grid.first_child().is_none(),
+                },
+
+                #[local_ref]
+                grid -> gtk::Grid {
+                    set_row_spacing: 5,
+                    set_column_spacing: 20,
+                },
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            grid: gtk::Grid::new(),
+        };
+        let grid = &model.grid;
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            ProcessesMsg::Processes(mut processes) => {
+                while let Some(child) = self.grid.first_child() {
+                    self.grid.remove(&child);
+                }
+                if processes.is_empty() {
+                    return;
+                }
+
+                let columns = [
+                    fl!("process-pid"),
+                    fl!("process-name"),
+                    fl!("process-vram"),
+                    fl!("process-gtt"),
+                    fl!("process-engine-usage"),
+                ];
+                for (column, title) in columns.iter().enumerate() {
+                    let label = gtk::Label::builder()
+                        .label(format!("<b>{title}</b>"))
+                        .use_markup(true)
+                        .halign(gtk::Align::Start)
+                        .build();
+                    self.grid.attach(&label, column as i32, 0, 1, 1);
+                }
+
+                processes.sort_by_key(|process| std::cmp::Reverse(process.vram.unwrap_or(0)));
+                for (row, process) in processes.iter().enumerate() {
+                    let engines: Vec<String> = process
+                        .engine_usage
+                        .iter()
+                        .filter(|(_, usage)| **usage >= 0.1)
+                        .map(|(engine, usage)| format!("{engine} {usage:.1}%"))
+                        .collect();
+                    let values = [
+                        process.pid.to_string(),
+                        process.name.clone(),
+                        format_mib(process.vram),
+                        format_mib(process.gtt),
+                        engines.join(", "),
+                    ];
+
+                    for (column, value) in values.into_iter().enumerate() {
+                        let label = gtk::Label::builder()
+                            .label(value)
+                            .halign(gtk::Align::Start)
+                            .selectable(true)
+                            .build();
+                        self.grid
+                            .attach(&label, column as i32, row as i32 + 1, 1, 1);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn format_mib(bytes: Option<u64>) -> String {
+    bytes.map_or_else(String::new, |bytes| format!("{} MiB", bytes / 1024 / 1024))
+}
@@ -0,0 +1,107 @@
+use gtk::prelude::*;
+use lact_schema::SensorChip;
+use relm4::{ComponentParts, ComponentSender, RelmWidgetExt, SimpleComponent};
+
+/// Temperatures and fan speeds of the system's other hwmon chips (CPU, motherboard, drives)
+/// that are enabled in the daemon config, so all sensors can be watched in one place
+pub struct SensorsPage {
+    grid: gtk::Grid,
+}
+
+#[derive(Debug)]
+pub enum SensorsMsg {
+    Chips(Vec<SensorChip>),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for SensorsPage {
+    type Init = ();
+    type Input = SensorsMsg;
+    type Output = ();
+
+    view! {
+        gtk::ScrolledWindow {
+            set_hscrollbar_policy: gtk::PolicyType::Never,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 10,
+                set_margin_all: 5,
+
+                gtk::Label {
+                    set_label: &fl!("no-sensor-chips"),
+                    set_wrap: true,
+                    #[watch]
+                    set_visible: model.grid.first_child().is_none(),
+                },
+
+                #[local_ref]
+                grid -> gtk::Grid {
+                    set_row_spacing: 5,
+                    set_column_spacing: 20,
+                },
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            grid: gtk::Grid::new(),
+        };
+        let grid = &model.grid;
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+        match msg {
+            SensorsMsg::Chips(chips) => {
+                while let Some(child) = self.grid.first_child() {
+                    self.grid.remove(&child);
+                }
+
+                let mut row = 0;
+                for chip in chips {
+                    let title = gtk::Label::builder()
+                        .label(format!("<b>{} ({})</b>", chip.name, chip.hwmon))
+                        .use_markup(true)
+                        .halign(gtk::Align::Start)
+                        .margin_top(if row == 0 { 0 } else { 10 })
+                        .build();
+                    self.grid.attach(&title, 0, row, 2, 1);
+                    row += 1;
+
+                    let values = chip
+                        .temperatures
+                        .iter()
+                        .map(|(label, temperature)| (label, format!("{temperature:.1}°C")))
+                        .chain(
+                            chip.fans
+                                .iter()
+                                .map(|(label, rpm)| (label, format!("{rpm} RPM"))),
+                        );
+                    for (label, value) in values {
+                        let label = gtk::Label::builder()
+                            .label(label.as_str())
+                            .halign(gtk::Align::Start)
+                            .build();
+                        let value = gtk::Label::builder()
+                            .label(value)
+                            .halign(gtk::Align::End)
+                            .hexpand(true)
+                            .build();
+                        self.grid.attach(&label, 0, row, 1, 1);
+                        self.grid.attach(&value, 1, row, 1, 1);
+                        row += 1;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -21,9 +21,9 @@ impl SimpleComponent for SoftwarePage {
             set_margin_top: 5,
             set_margin_bottom: 5,
 
-            append = &InfoRow::new_selectable("LACT Daemon:", &daemon_version),
-            append = &InfoRow::new_selectable("LACT GUI:", &gui_version),
-            append = &InfoRow::new_selectable("Kernel Version:", &system_info.kernel_version),
+            append = &InfoRow::new_selectable(&fl!("daemon-version"), &daemon_version),
+            append = &InfoRow::new_selectable(&fl!("gui-version"), &gui_version),
+            append = &InfoRow::new_selectable(&fl!("kernel-version"), &system_info.kernel_version),
         }
     }
 
@@ -0,0 +1,622 @@
+//! Canvas for editing the fan curve. Points can be dragged, added with a double click on an empty spot and removed with a right click.
+//! The current position of the fan on the curve is shown as well, so that it's visible which part of the curve is in use.
+use crate::app::gui_settings::accent_color;
+use gtk::{
+    cairo, gdk, glib::clone, prelude::*, DrawingArea, EventControllerMotion, EventSequenceState,
+    GestureClick, GestureDrag,
+};
+use lact_client::schema::{FanCurveInput, FanCurveMap};
+use std::{
+    cell::{Cell, RefCell},
+    f64::consts::PI,
+    rc::Rc,
+};
+use tracing::warn;
+
+const POINT_RADIUS: f64 = 5.0;
+/// How close the pointer has to be to a point to grab it
+const GRAB_DISTANCE: f64 = 12.0;
+/// Space around the plot for the axis labels
+const MARGIN_LEFT: f64 = 64.0;
+const MARGIN_RIGHT: f64 = 16.0;
+const MARGIN_TOP: f64 = 12.0;
+const MARGIN_BOTTOM: f64 = 24.0;
+const GRID_LINES: u32 = 10;
+/// Points snap to the grid lines and halfway between them
+const SNAP_DIVISIONS: f64 = 20.0;
+/// Steps of the speed and the curve input without snapping
+const RATIO_STEP: f64 = 0.01;
+const INPUT_STEP: f64 = 1.0;
+const TEMPERATURE_RANGE: f64 = 100.0;
+const MIN_POWER_RANGE: f64 = 300.0;
+const MAX_POWER_RANGE: f64 = 1000.0;
+const DEFAULT_ACCENT: (u8, u8, u8) = (0x35, 0x84, 0xe4);
+
+#[derive(Clone)]
+pub struct CurveEditor {
+    pub area: DrawingArea,
+    state: Rc<EditorState>,
+}
+
+#[derive(Default)]
+struct EditorState {
+    /// Sorted by the curve input, which is unique per point
+    points: RefCell<Vec<(i32, f32)>>,
+    input: Cell<FanCurveInput>,
+    /// End of the curve input axis
+    input_range: Cell<f64>,
+    /// Speeds are shown in RPM instead of percentages with a range
+    rpm_range: Cell<Option<(u32, u32)>>,
+    snap: Cell<bool>,
+    /// Point that is being hovered or dragged
+    active_point: Cell<Option<usize>>,
+    dragging: Cell<bool>,
+    /// Current curve input value and fan speed ratio
+    current_position: Cell<(Option<f64>, Option<f64>)>,
+    changed: RefCell<Option<Rc<dyn Fn()>>>,
+}
+
+impl CurveEditor {
+    pub fn new() -> Self {
+        let area = DrawingArea::builder()
+            .content_height(260)
+            .hexpand(true)
+            .vexpand(true)
+            .tooltip_text(fl!("fan-curve-editor-hint"))
+            .build();
+
+        let state = Rc::new(EditorState {
+            input_range: Cell::new(TEMPERATURE_RANGE),
+            snap: Cell::new(true),
+            ..EditorState::default()
+        });
+        let editor = Self { area, state };
+
+        editor.area.set_draw_func(clone!(
+            #[strong(rename_to = state)]
+            editor.state,
+            move |area, context, width, height| {
+                #[allow(deprecated)]
+                let foreground = area.style_context().color();
+                if let Err(err) = draw(&state, context, width, height, &foreground) {
+                    warn!("could not draw the fan curve: {err}");
+                }
+            }
+        ));
+
+        let drag = GestureDrag::new();
+        drag.connect_drag_begin(clone!(
+            #[strong]
+            editor,
+            move |gesture, x, y| match editor.point_at(x, y) {
+                Some(index) => {
+                    editor.state.active_point.set(Some(index));
+                    editor.state.dragging.set(true);
+                    editor.area.set_cursor_from_name(Some("grabbing"));
+                }
+                None => gesture.set_state(EventSequenceState::Denied),
+            }
+        ));
+        drag.connect_drag_update(clone!(
+            #[strong]
+            editor,
+            move |gesture, offset_x, offset_y| {
+                if let Some((start_x, start_y)) = gesture.start_point() {
+                    editor.drag_to(start_x + offset_x, start_y + offset_y);
+                }
+            }
+        ));
+        drag.connect_drag_end(clone!(
+            #[strong]
+            editor,
+            move |_, _, _| {
+                editor.state.dragging.set(false);
+                editor.area.set_cursor_from_name(Some("grab"));
+                editor.area.queue_draw();
+            }
+        ));
+        editor.area.add_controller(drag);
+
+        let click = GestureClick::builder().button(0).build();
+        click.connect_pressed(clone!(
+            #[strong]
+            editor,
+            move |gesture, n_press, x, y| {
+                let point = editor.point_at(x, y);
+                match (gesture.current_button(), point) {
+                    (gdk::BUTTON_SECONDARY, Some(index)) => editor.remove_point_at(index),
+                    (gdk::BUTTON_PRIMARY, None) if n_press == 2 => editor.add_point_at(x, y),
+                    _ => return,
+                }
+                gesture.set_state(EventSequenceState::Claimed);
+            }
+        ));
+        editor.area.add_controller(click);
+
+        let motion = EventControllerMotion::new();
+        motion.connect_motion(clone!(
+            #[strong]
+            editor,
+            move |_, x, y| {
+                if editor.state.dragging.get() {
+                    return;
+                }
+                let point = editor.point_at(x, y);
+                if point != editor.state.active_point.get() {
+                    editor.state.active_point.set(point);
+                    editor.area.set_cursor_from_name(point.map(|_| "grab"));
+                    editor.area.queue_draw();
+                }
+            }
+        ));
+        motion.connect_leave(clone!(
+            #[strong]
+            editor,
+            move |_| {
+                if !editor.state.dragging.get() {
+                    editor.state.active_point.set(None);
+                    editor.area.queue_draw();
+                }
+            }
+        ));
+        editor.area.add_controller(motion);
+
+        editor
+    }
+
+    /// Replaces the curve without notifying about a change
+    pub fn set_curve(&self, curve: &FanCurveMap) {
+        self.state.points.replace(
+            curve
+                .iter()
+                .map(|(input, ratio)| (*input, *ratio))
+                .collect(),
+        );
+        self.state.active_point.set(None);
+        self.update_input_range();
+        self.area.queue_draw();
+    }
+
+    pub fn get_curve(&self) -> FanCurveMap {
+        self.state.points.borrow().iter().copied().collect()
+    }
+
+    /// The callback replaces the previous one, as it's connected again whenever the settings are reloaded
+    pub fn connect_changed<F: Fn() + 'static>(&self, f: F) {
+        self.state.changed.replace(Some(Rc::new(f)));
+    }
+
+    pub fn notify_changed(&self) {
+        let callback = self.state.changed.borrow().clone();
+        if let Some(callback) = callback {
+            callback();
+        }
+    }
+
+    pub fn set_input(&self, input: FanCurveInput) {
+        self.state.input.set(input);
+        self.update_input_range();
+        self.area.queue_draw();
+    }
+
+    pub fn set_rpm_range(&self, rpm_range: Option<(u32, u32)>) {
+        self.state.rpm_range.set(rpm_range);
+        self.area.queue_draw();
+    }
+
+    pub fn set_snap(&self, snap: bool) {
+        self.state.snap.set(snap);
+    }
+
+    /// Current value of the curve input and the speed of the fan as a ratio
+    pub fn set_current_position(&self, input: Option<f64>, ratio: Option<f64>) {
+        if self.state.current_position.replace((input, ratio)) != (input, ratio) {
+            self.area.queue_draw();
+        }
+    }
+
+    /// Adds a point after the last one
+    pub fn add_point(&self) {
+        let last = self.state.points.borrow().last().copied();
+        let (input, ratio) = match last {
+            Some((input, ratio)) => (input + 5, ratio),
+            None => (50, 0.5),
+        };
+        let input = input.min(self.input_max());
+        if insert_point(&mut self.state.points.borrow_mut(), input, ratio).is_some() {
+            self.update_input_range();
+            self.area.queue_draw();
+            self.notify_changed();
+        }
+    }
+
+    pub fn remove_last_point(&self) {
+        if self.state.points.borrow_mut().pop().is_some() {
+            self.state.active_point.set(None);
+            self.area.queue_draw();
+            self.notify_changed();
+        }
+    }
+
+    fn add_point_at(&self, x: f64, y: f64) {
+        let plot = self.plot();
+        let (input, ratio) = plot.from_screen(x, y);
+        if !(0.0..=plot.input_range).contains(&input) || !(0.0..=1.0).contains(&ratio) {
+            return;
+        }
+
+        let (input, ratio) = self.snap_position(input, ratio);
+        let index = insert_point(&mut self.state.points.borrow_mut(), input, ratio);
+        if index.is_some() {
+            self.state.active_point.set(index);
+            self.area.queue_draw();
+            self.notify_changed();
+        }
+    }
+
+    /// The last point can't be removed this way, as a curve needs at least one
+    fn remove_point_at(&self, index: usize) {
+        {
+            let mut points = self.state.points.borrow_mut();
+            if points.len() <= 1 {
+                return;
+            }
+            points.remove(index);
+        }
+        self.state.active_point.set(None);
+        self.area.queue_draw();
+        self.notify_changed();
+    }
+
+    fn drag_to(&self, x: f64, y: f64) {
+        let Some(index) = self.state.active_point.get() else {
+            return;
+        };
+        let (input, ratio) = self.plot().from_screen(x, y);
+        let (input, ratio) = self.snap_position(input, ratio);
+
+        let moved = move_point(
+            &mut self.state.points.borrow_mut(),
+            index,
+            input,
+            ratio,
+            self.input_max(),
+        );
+        if moved {
+            self.area.queue_draw();
+            self.notify_changed();
+        }
+    }
+
+    fn snap_position(&self, input: f64, ratio: f64) -> (i32, f32) {
+        if self.state.snap.get() {
+            let input_step = self.state.input_range.get() / SNAP_DIVISIONS;
+            snap_position(input, ratio, input_step, 1.0 / SNAP_DIVISIONS)
+        } else {
+            snap_position(input, ratio, INPUT_STEP, RATIO_STEP)
+        }
+    }
+
+    fn point_at(&self, x: f64, y: f64) -> Option<usize> {
+        let plot = self.plot();
+        self.state
+            .points
+            .borrow()
+            .iter()
+            .enumerate()
+            .map(|(index, (input, ratio))| {
+                let (point_x, point_y) = plot.to_screen(f64::from(*input), f64::from(*ratio));
+                (index, (point_x - x).hypot(point_y - y))
+            })
+            .filter(|(_, distance)| *distance <= GRAB_DISTANCE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+
+    fn plot(&self) -> Plot {
+        Plot::new(
+            self.area.width(),
+            self.area.height(),
+            self.state.input_range.get(),
+        )
+    }
+
+    fn input_max(&self) -> i32 {
+        self.state.input_range.get() as i32
+    }
+
+    /// Power curves get an axis that fits their points, other inputs are on the temperature scale
+    fn update_input_range(&self) {
+        let range = match self.state.input.get() {
+            FanCurveInput::Power => {
+                let highest = self
+                    .state
+                    .points
+                    .borrow()
+                    .last()
+                    .map_or(0.0, |(input, _)| f64::from(*input));
+                ((highest / 100.0).floor() * 100.0 + 100.0).clamp(MIN_POWER_RANGE, MAX_POWER_RANGE)
+            }
+            FanCurveInput::Temperature | FanCurveInput::Composite { .. } => TEMPERATURE_RANGE,
+        };
+        self.state.input_range.set(range);
+    }
+}
+
+/// Area of the widget that the curve is drawn in
+struct Plot {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    input_range: f64,
+}
+
+impl Plot {
+    fn new(width: i32, height: i32, input_range: f64) -> Self {
+        Self {
+            x: MARGIN_LEFT,
+            y: MARGIN_TOP,
+            width: (f64::from(width) - MARGIN_LEFT - MARGIN_RIGHT).max(1.0),
+            height: (f64::from(height) - MARGIN_TOP - MARGIN_BOTTOM).max(1.0),
+            input_range,
+        }
+    }
+
+    fn to_screen(&self, input: f64, ratio: f64) -> (f64, f64) {
+        (
+            self.x + input / self.input_range * self.width,
+            self.y + (1.0 - ratio) * self.height,
+        )
+    }
+
+    fn from_screen(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x - self.x) / self.width * self.input_range,
+            1.0 - (y - self.y) / self.height,
+        )
+    }
+}
+
+fn draw(
+    state: &EditorState,
+    context: &cairo::Context,
+    width: i32,
+    height: i32,
+    foreground: &gdk::RGBA,
+) -> Result<(), cairo::Error> {
+    let plot = Plot::new(width, height, state.input_range.get());
+    let unit = state.input.get().unit();
+    let rpm_range = state.rpm_range.get();
+    let (accent_r, accent_g, accent_b) = accent_color().unwrap_or(DEFAULT_ACCENT);
+    let accent = (
+        f64::from(accent_r) / 255.0,
+        f64::from(accent_g) / 255.0,
+        f64::from(accent_b) / 255.0,
+    );
+    let set_foreground = |alpha: f64| {
+        context.set_source_rgba(
+            foreground.red().into(),
+            foreground.green().into(),
+            foreground.blue().into(),
+            f64::from(foreground.alpha()) * alpha,
+        );
+    };
+
+    // Grid with the axis labels on every other line
+    context.set_font_size(11.0);
+    context.set_line_width(1.0);
+    for line in 0..=GRID_LINES {
+        let fraction = f64::from(line) / f64::from(GRID_LINES);
+        let (x, _) = plot.to_screen(fraction * plot.input_range, 0.0);
+        let (_, y) = plot.to_screen(0.0, fraction);
+
+        set_foreground(0.12);
+        context.move_to(x.round() + 0.5, plot.y);
+        context.line_to(x.round() + 0.5, plot.y + plot.height);
+        context.move_to(plot.x, y.round() + 0.5);
+        context.line_to(plot.x + plot.width, y.round() + 0.5);
+        context.stroke()?;
+
+        if line % 2 == 0 {
+            set_foreground(0.7);
+            let input_label = format!("{}{unit}", (fraction * plot.input_range).round());
+            let extents = context.text_extents(&input_label)?;
+            context.move_to(
+                x - extents.width() / 2.0,
+                plot.y + plot.height + extents.height() + 8.0,
+            );
+            context.show_text(&input_label)?;
+
+            let speed_label = speed_text(fraction, rpm_range);
+            let extents = context.text_extents(&speed_label)?;
+            context.move_to(plot.x - extents.width() - 8.0, y + extents.height() / 2.0);
+            context.show_text(&speed_label)?;
+        }
+    }
+
+    let points = state.points.borrow();
+    let screen_points: Vec<(f64, f64)> = points
+        .iter()
+        .map(|(input, ratio)| plot.to_screen(f64::from(*input), f64::from(*ratio)))
+        .collect();
+
+    // The speed stays at the first and last point outside of the curve
+    if let (Some(first), Some(last)) = (screen_points.first(), screen_points.last()) {
+        context.set_source_rgba(accent.0, accent.1, accent.2, 0.5);
+        context.set_line_width(2.0);
+        context.set_dash(&[4.0, 4.0], 0.0);
+        context.move_to(plot.x, first.1);
+        context.line_to(first.0, first.1);
+        context.move_to(last.0, last.1);
+        context.line_to(plot.x + plot.width, last.1);
+        context.stroke()?;
+        context.set_dash(&[], 0.0);
+
+        context.set_source_rgb(accent.0, accent.1, accent.2);
+        context.move_to(first.0, first.1);
+        for (x, y) in &screen_points[1..] {
+            context.line_to(*x, *y);
+        }
+        context.stroke()?;
+    }
+
+    if let (Some(input), Some(ratio)) = state.current_position.get() {
+        let (x, y) = plot.to_screen(input.clamp(0.0, plot.input_range), ratio.clamp(0.0, 1.0));
+        set_foreground(0.5);
+        context.set_line_width(1.0);
+        context.set_dash(&[2.0, 3.0], 0.0);
+        context.move_to(x, plot.y);
+        context.line_to(x, plot.y + plot.height);
+        context.stroke()?;
+        context.set_dash(&[], 0.0);
+
+        set_foreground(1.0);
+        context.set_line_width(2.0);
+        context.arc(x, y, POINT_RADIUS + 2.0, 0.0, 2.0 * PI);
+        context.stroke()?;
+        draw_label(
+            context,
+            &plot,
+            (x, y),
+            &position_text(input, ratio, unit, rpm_range),
+        )?;
+    }
+
+    let active_point = state.active_point.get();
+    for (index, (x, y)) in screen_points.iter().enumerate() {
+        let radius = if active_point == Some(index) {
+            POINT_RADIUS + 2.0
+        } else {
+            POINT_RADIUS
+        };
+        context.set_source_rgb(accent.0, accent.1, accent.2);
+        context.arc(*x, *y, radius, 0.0, 2.0 * PI);
+        context.fill()?;
+    }
+
+    if let Some(index) = active_point {
+        if let (Some((input, ratio)), Some(position)) =
+            (points.get(index), screen_points.get(index))
+        {
+            set_foreground(1.0);
+            let text = position_text(f64::from(*input), f64::from(*ratio), unit, rpm_range);
+            draw_label(context, &plot, *position, &text)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Draws a label next to a point, kept inside of the plot
+fn draw_label(
+    context: &cairo::Context,
+    plot: &Plot,
+    (x, y): (f64, f64),
+    text: &str,
+) -> Result<(), cairo::Error> {
+    let extents = context.text_extents(text)?;
+    let label_x = (x + 10.0).min(plot.x + plot.width - extents.width());
+    let label_y = (y - 10.0).max(plot.y + extents.height());
+    context.move_to(label_x, label_y);
+    context.show_text(text)
+}
+
+fn position_text(input: f64, ratio: f64, unit: &str, rpm_range: Option<(u32, u32)>) -> String {
+    format!("{}{unit}, {}", input.round(), speed_text(ratio, rpm_range))
+}
+
+pub fn speed_text(ratio: f64, rpm_range: Option<(u32, u32)>) -> String {
+    match rpm_range {
+        Some((min, max)) => {
+            let rpm = f64::from(min) + f64::from(max - min) * ratio;
+            format!("{} RPM", rpm.round())
+        }
+        None => format!("{}%", (ratio * 100.0).round()),
+    }
+}
+
+/// Rounds a position to the nearest multiple of the steps
+fn snap_position(input: f64, ratio: f64, input_step: f64, ratio_step: f64) -> (i32, f32) {
+    let input = ((input / input_step).round() * input_step).round().max(0.0);
+    let ratio = ((ratio / ratio_step).round() * ratio_step).clamp(0.0, 1.0);
+    (input as i32, ratio as f32)
+}
+
+/// Moves a point, keeping it between its neighbours so that the curve inputs stay sorted and unique.
+/// Returns whether the point was changed.
+fn move_point(
+    points: &mut [(i32, f32)],
+    index: usize,
+    input: i32,
+    ratio: f32,
+    input_max: i32,
+) -> bool {
+    let min = index
+        .checked_sub(1)
+        .and_then(|previous| points.get(previous))
+        .map_or(0, |(input, _)| input + 1);
+    let max = points
+        .get(index + 1)
+        .map_or(input_max, |(input, _)| input - 1);
+    if min > max {
+        return false;
+    }
+
+    let Some(point) = points.get_mut(index) else {
+        return false;
+    };
+    let new_point = (input.clamp(min, max), ratio);
+    let changed = *point != new_point;
+    *point = new_point;
+    changed
+}
+
+/// Inserts a point in order, returns its index or `None` when there already is a point at the same input
+fn insert_point(points: &mut Vec<(i32, f32)>, input: i32, ratio: f32) -> Option<usize> {
+    match points.binary_search_by_key(&input, |(input, _)| *input) {
+        Ok(_) => None,
+        Err(index) => {
+            points.insert(index, (input, ratio));
+            Some(index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{insert_point, move_point, snap_position};
+
+    #[test]
+    fn snapping() {
+        assert_eq!(snap_position(47.6, 0.372, 5.0, 0.05), (50, 0.35));
+        assert_eq!(snap_position(47.6, 0.372, 1.0, 0.01), (48, 0.37));
+        assert_eq!(snap_position(-3.0, 1.2, 5.0, 0.05), (0, 1.0));
+        // Power curves snap to steps of the wider axis
+        assert_eq!(snap_position(212.0, 0.5, 15.0, 0.05), (210, 0.5));
+    }
+
+    #[test]
+    fn points_stay_between_neighbours() {
+        let mut points = vec![(40, 0.2), (50, 0.35), (60, 0.5)];
+        assert!(move_point(&mut points, 1, 75, 0.4, 100));
+        assert_eq!(points[1], (59, 0.4));
+        assert!(move_point(&mut points, 0, -10, 0.1, 100));
+        assert_eq!(points[0], (0, 0.1));
+        assert!(move_point(&mut points, 2, 120, 1.0, 100));
+        assert_eq!(points[2], (100, 1.0));
+        assert!(!move_point(&mut points, 2, 100, 1.0, 100));
+
+        let mut crowded = vec![(40, 0.2), (41, 0.3), (42, 0.4)];
+        assert!(!move_point(&mut crowded, 1, 50, 0.3, 100));
+    }
+
+    #[test]
+    fn inserted_points_are_sorted() {
+        let mut points = vec![(40, 0.2), (60, 0.5)];
+        assert_eq!(insert_point(&mut points, 50, 0.3), Some(1));
+        assert_eq!(insert_point(&mut points, 50, 0.4), None);
+        assert_eq!(insert_point(&mut points, 80, 1.0), Some(3));
+        assert_eq!(points, [(40, 0.2), (50, 0.3), (60, 0.5), (80, 1.0)]);
+    }
+}
@@ -1,28 +1,40 @@
-mod point_adjustment;
+mod curve_editor;
+mod preview_window;
 
-use self::point_adjustment::PointAdjustment;
+use self::{curve_editor::CurveEditor, preview_window::PreviewWindow};
 use crate::app::pages::oc_adjustment::OcAdjustment;
 use glib::clone;
 use gtk::graphene::Point;
 use gtk::gsk::Transform;
 use gtk::prelude::*;
 use gtk::*;
-use lact_client::schema::{default_fan_curve, FanCurveMap};
-use std::cell::RefCell;
-use std::collections::BTreeMap;
+use lact_client::schema::{
+    default_fan_curve, FanCurveInput, FanCurveMap, FanCurvePreviewPoint, FanSpeedUnit,
+};
+use std::cell::Cell;
 use std::rc::Rc;
 
 const DEFAULT_CHANGE_THRESHOLD: u64 = 2;
 const DEFAULT_SPINDOWN_DELAY_MS: u64 = 5000;
+const DEFAULT_MAX_RAMP_RATE: u64 = 0;
 
 #[derive(Clone)]
 pub struct FanCurveFrame {
     pub container: Box,
-    curve_container: Frame,
-    points: Rc<RefCell<Vec<PointAdjustment>>>,
+    editor: CurveEditor,
+    ratio_title_label: Label,
+    input_title_label: Label,
+    /// What the curve points are placed at, only changed through the CLI or the config
+    curve_input: Rc<Cell<FanCurveInput>>,
+    rpm_check: CheckButton,
+    /// Speed range of the fan, used to show the curve in RPM
+    rpm_range: Rc<Cell<Option<(u32, u32)>>>,
     spindown_delay_adj: OcAdjustment,
     change_threshold_adj: OcAdjustment,
+    max_ramp_rate_adj: OcAdjustment,
     hysteresis_grid: Grid,
+    preview_button: Button,
+    preview_window: PreviewWindow,
 }
 
 impl FanCurveFrame {
@@ -31,14 +43,14 @@ impl FanCurveFrame {
 
         let hbox = Box::new(Orientation::Horizontal, 5);
 
-        let curve_container = Frame::new(Some("Fan Curve"));
+        let curve_container = Frame::new(Some(&fl!("fan-curve")));
         curve_container.set_hexpand(true);
 
         curve_container.set_margin_start(10);
         curve_container.set_margin_end(10);
         curve_container.set_margin_top(10);
 
-        let ratio_title_label = Label::builder().label("Fan speed (%)").build();
+        let ratio_title_label = Label::builder().label(fl!("fan-speed-percent")).build();
 
         let fixed = Fixed::new();
         fixed.put(&ratio_title_label, 0.0, 0.0);
@@ -50,10 +62,13 @@ impl FanCurveFrame {
             .translate(&Point::new(-200.0, 10.0));
         fixed.set_child_transform(&ratio_title_label, Some(&rotation_transform));
 
+        let editor = CurveEditor::new();
+        curve_container.set_child(Some(&editor.area));
+
         hbox.append(&fixed);
         hbox.append(&curve_container);
 
-        let temperature_title_label = Label::new(Some("Temperature (°C)"));
+        let input_title_label = Label::new(Some(&fl!("temperature-celsius")));
 
         let buttons_box = Box::builder()
             .orientation(Orientation::Horizontal)
@@ -63,26 +78,43 @@ impl FanCurveFrame {
 
         let add_button = Button::builder().icon_name("list-add-symbolic").build();
         let remove_button = Button::builder().icon_name("list-remove-symbolic").build();
-        let default_button = Button::builder().label("Default").build();
+        let default_button = Button::builder().label(fl!("default")).build();
+        let preview_button = Button::builder()
+            .label(fl!("preview"))
+            .tooltip_text(fl!("fan-curve-preview-description"))
+            .build();
+
+        let rpm_check = CheckButton::builder()
+            .label(fl!("control-by-rpm"))
+            .tooltip_text(fl!("control-by-rpm-description"))
+            .hexpand(true)
+            .visible(false)
+            .build();
+
+        let snap_check = CheckButton::builder()
+            .label(fl!("snap-to-grid"))
+            .active(true)
+            .build();
 
+        buttons_box.append(&rpm_check);
+        buttons_box.append(&snap_check);
+        buttons_box.append(&preview_button);
         buttons_box.append(&default_button);
         buttons_box.append(&remove_button);
         buttons_box.append(&add_button);
 
         root_box.append(&hbox);
-        root_box.append(&temperature_title_label);
+        root_box.append(&input_title_label);
         root_box.append(&buttons_box);
 
-        let points = Rc::new(RefCell::new(Vec::new()));
-
         let hysteresis_grid = Grid::new();
         hysteresis_grid.set_margin_top(10);
 
         let spindown_delay_adj = oc_adjustment_row(
             &hysteresis_grid,
             0,
-            "Spindown delay",
-            "How long the GPU needs to remain at a lower temperature point for before ramping down the fan",
+            &fl!("spindown-delay"),
+            &fl!("spindown-delay-description"),
             " ms",
             OcAdjustmentOptions {
                 default: DEFAULT_SPINDOWN_DELAY_MS as f64,
@@ -96,8 +128,8 @@ impl FanCurveFrame {
         let change_threshold_adj = oc_adjustment_row(
             &hysteresis_grid,
             1,
-            "Speed change threshold",
-            "Hysteresis",
+            &fl!("speed-change-threshold"),
+            &fl!("hysteresis"),
             "°C",
             OcAdjustmentOptions {
                 default: DEFAULT_CHANGE_THRESHOLD as f64,
@@ -108,15 +140,37 @@ impl FanCurveFrame {
             },
         );
 
+        let max_ramp_rate_adj = oc_adjustment_row(
+            &hysteresis_grid,
+            2,
+            &fl!("max-speed-change"),
+            &fl!("max-speed-change-description"),
+            " %/s",
+            OcAdjustmentOptions {
+                default: DEFAULT_MAX_RAMP_RATE as f64,
+                min: 0.0,
+                max: 100.0,
+                step: 1.0,
+                digits: 0,
+            },
+        );
+
         root_box.append(&hysteresis_grid);
 
         let curve_frame = Self {
             container: root_box,
-            curve_container,
-            points,
+            editor,
+            ratio_title_label,
+            input_title_label,
+            curve_input: Rc::new(Cell::new(FanCurveInput::default())),
+            rpm_check: rpm_check.clone(),
+            rpm_range: Rc::new(Cell::new(None)),
             spindown_delay_adj: spindown_delay_adj.clone(),
             change_threshold_adj: change_threshold_adj.clone(),
+            max_ramp_rate_adj: max_ramp_rate_adj.clone(),
             hysteresis_grid,
+            preview_button,
+            preview_window: PreviewWindow::new(),
         };
 
         default_button.connect_clicked(clone!(
@@ -124,9 +178,29 @@ impl FanCurveFrame {
             curve_frame,
             move |_| {
                 let curve = default_fan_curve();
+                curve_frame.set_curve_input(FanCurveInput::Temperature);
                 curve_frame.set_curve(&curve);
                 spindown_delay_adj.set_value(DEFAULT_SPINDOWN_DELAY_MS as f64);
                 change_threshold_adj.set_value(DEFAULT_CHANGE_THRESHOLD as f64);
+                max_ramp_rate_adj.set_value(DEFAULT_MAX_RAMP_RATE as f64);
+                curve_frame.editor.notify_changed();
+            }
+        ));
+
+        rpm_check.connect_toggled(clone!(
+            #[strong]
+            curve_frame,
+            move |_| {
+                curve_frame.update_unit_labels();
+                curve_frame.editor.notify_changed();
+            }
+        ));
+
+        snap_check.connect_toggled(clone!(
+            #[strong]
+            curve_frame,
+            move |check| {
+                curve_frame.editor.set_snap(check.is_active());
             }
         ));
 
@@ -134,7 +208,7 @@ impl FanCurveFrame {
             #[strong]
             curve_frame,
             move |_| {
-                curve_frame.add_point();
+                curve_frame.editor.add_point();
             }
         ));
 
@@ -142,68 +216,24 @@ impl FanCurveFrame {
             #[strong]
             curve_frame,
             move |_| {
-                curve_frame.remove_point();
+                curve_frame.editor.remove_last_point();
             }
         ));
 
         curve_frame
     }
 
-    fn add_point(&self) {
-        let mut curve = self.get_curve();
-        if let Some((temperature, ratio)) = curve.iter().last() {
-            curve.insert(temperature + 5, *ratio);
-            self.set_curve(&curve);
-        } else {
-            curve.insert(50, 0.5);
-            self.set_curve(&curve);
-        }
-    }
-
-    fn remove_point(&self) {
-        let mut curve = self.get_curve();
-        curve.pop_last();
-        self.set_curve(&curve);
-    }
-
-    fn notify_changed(&self) {
-        if let Some(point) = self.points.borrow().first() {
-            point.ratio.emit_by_name::<()>("value-changed", &[]);
-        }
-    }
-
     pub fn set_curve(&self, curve: &FanCurveMap) {
-        // Notify that the values were changed when the entire curve is overwritten, e.g. when resetting to default
-        self.notify_changed();
-
-        let points_container = Box::builder()
-            .orientation(Orientation::Horizontal)
-            .spacing(5)
-            .vexpand(true)
-            .build();
-
-        let mut adjustments = Vec::with_capacity(curve.len());
-
-        for (temperature, ratio) in curve {
-            let adjustment = PointAdjustment::new(&points_container, *ratio, *temperature);
-            adjustments.push(adjustment);
-        }
-
-        self.points.replace(adjustments);
-        self.curve_container.set_child(Some(&points_container));
+        self.editor.set_curve(curve);
     }
 
     pub fn get_curve(&self) -> FanCurveMap {
-        let mut curve = BTreeMap::new();
-
-        let points = self.points.borrow();
-        for point in &*points {
-            let temperature = point.temperature.value() as i32;
-            let ratio = point.ratio.value() as f32;
-            curve.insert(temperature, ratio);
-        }
+        self.editor.get_curve()
+    }
 
-        curve
+    /// Shows where the fan currently is on the curve, with the current value of the curve input and the fan speed as a ratio
+    pub fn set_current_position(&self, input: Option<f64>, ratio: Option<f64>) {
+        self.editor.set_current_position(input, ratio);
     }
 
     pub fn connect_adjusted<F: Fn() + 'static + Clone>(&self, f: F) {
@@ -221,19 +251,26 @@ impl FanCurveFrame {
                 f();
             }
         ));
-
-        let closure = clone!(
+        self.max_ramp_rate_adj.connect_value_changed(clone!(
             #[strong]
             f,
-            move |_: &Adjustment| {
+            move |_| {
                 f();
             }
-        );
+        ));
 
-        for point in &*self.points.borrow() {
-            point.ratio.connect_value_changed(closure.clone());
-            point.temperature.connect_value_changed(closure.clone());
-        }
+        self.editor.connect_changed(f);
+    }
+
+    /// Called with the edited curve when a preview of it is requested
+    pub fn connect_preview<F: Fn(FanCurveMap) + 'static>(&self, f: F) {
+        let editor = self.editor.clone();
+        self.preview_button
+            .connect_clicked(move |_| f(editor.get_curve()));
+    }
+
+    pub fn show_preview(&self, points: Vec<FanCurvePreviewPoint>) {
+        self.preview_window.show(points);
     }
 
     pub fn set_change_threshold(&self, value: Option<u64>) {
@@ -246,6 +283,11 @@ impl FanCurveFrame {
             .set_initial_value(value.unwrap_or(0) as f64);
     }
 
+    pub fn set_max_ramp_rate(&self, value: Option<u64>) {
+        self.max_ramp_rate_adj
+            .set_initial_value(value.unwrap_or(0) as f64);
+    }
+
     pub fn get_change_threshold(&self) -> u64 {
         self.change_threshold_adj.value() as u64
     }
@@ -254,6 +296,62 @@ impl FanCurveFrame {
         self.spindown_delay_adj.value() as u64
     }
 
+    pub fn get_max_ramp_rate(&self) -> u64 {
+        self.max_ramp_rate_adj.value() as u64
+    }
+
+    /// Whether the fan can be controlled by RPM, and its `(min, max)` speed range
+    pub fn set_rpm_control(&self, available: bool, rpm_range: Option<(u32, u32)>) {
+        self.rpm_range.set(rpm_range);
+        self.rpm_check.set_visible(available && rpm_range.is_some());
+    }
+
+    pub fn set_speed_unit(&self, unit: FanSpeedUnit) {
+        self.rpm_check
+            .set_active(unit == FanSpeedUnit::Rpm && self.rpm_check.is_visible());
+        self.update_unit_labels();
+    }
+
+    pub fn get_speed_unit(&self) -> Option<FanSpeedUnit> {
+        self.rpm_check.is_visible().then(|| {
+            if self.rpm_check.is_active() {
+                FanSpeedUnit::Rpm
+            } else {
+                FanSpeedUnit::Pwm
+            }
+        })
+    }
+
+    /// Changes what the points are placed at
+    pub fn set_curve_input(&self, input: FanCurveInput) {
+        self.curve_input.set(input);
+        self.editor.set_input(input);
+        let title = match input {
+            FanCurveInput::Temperature => fl!("temperature-celsius"),
+            FanCurveInput::Power => fl!("power-draw-watts"),
+            FanCurveInput::Composite { power_weight } => fl!(
+                "composite-input",
+                power_weight = (power_weight * 100.0).round()
+            ),
+        };
+        self.input_title_label.set_label(&title);
+    }
+
+    pub fn get_curve_input(&self) -> FanCurveInput {
+        self.curve_input.get()
+    }
+
+    fn update_unit_labels(&self) {
+        let rpm_range = self.rpm_range.get().filter(|_| self.rpm_check.is_active());
+        let title = if rpm_range.is_some() {
+            fl!("fan-speed-rpm")
+        } else {
+            fl!("fan-speed-percent")
+        };
+        self.ratio_title_label.set_label(&title);
+        self.editor.set_rpm_range(rpm_range);
+    }
+
     pub fn set_hysteresis_settings_visibile(&self, visible: bool) {
         self.hysteresis_grid.set_visible(visible);
     }
@@ -0,0 +1,236 @@
+//! Shows how the fan speed would have followed a curve during the recorded temperatures of the stats history.
+//! The daemon replays the samples with the same logic as its fan control, including the spindown delay and ramp rate.
+use crate::app::gui_settings::accent_color;
+use gtk::{cairo, gdk, glib::clone, prelude::*, DrawingArea, Label, Orientation, Window};
+use lact_client::schema::FanCurvePreviewPoint;
+use std::{cell::RefCell, rc::Rc};
+use tracing::warn;
+
+const MARGIN_LEFT: f64 = 56.0;
+const MARGIN_RIGHT: f64 = 56.0;
+const MARGIN_TOP: f64 = 28.0;
+const MARGIN_BOTTOM: f64 = 24.0;
+const GRID_LINES: u32 = 5;
+const TIME_LABELS: u32 = 4;
+const TEMPERATURE_RANGE: f64 = 100.0;
+const DEFAULT_ACCENT: (u8, u8, u8) = (0x35, 0x84, 0xe4);
+
+#[derive(Clone)]
+pub struct PreviewWindow {
+    window: Window,
+    area: DrawingArea,
+    summary_label: Label,
+    points: Rc<RefCell<Vec<FanCurvePreviewPoint>>>,
+}
+
+impl PreviewWindow {
+    pub fn new() -> Self {
+        let area = DrawingArea::builder()
+            .content_height(300)
+            .content_width(600)
+            .hexpand(true)
+            .vexpand(true)
+            .build();
+        let summary_label = Label::builder()
+            .wrap(true)
+            .halign(gtk::Align::Start)
+            .build();
+
+        let container = gtk::Box::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(5)
+            .margin_start(10)
+            .margin_end(10)
+            .margin_top(10)
+            .margin_bottom(10)
+            .build();
+        container.append(
+            &Label::builder()
+                .label(fl!("fan-curve-preview-description"))
+                .wrap(true)
+                .halign(gtk::Align::Start)
+                .build(),
+        );
+        container.append(&area);
+        container.append(&summary_label);
+
+        let window = Window::builder()
+            .title(fl!("fan-curve-preview"))
+            .hide_on_close(true)
+            .child(&container)
+            .build();
+
+        let points: Rc<RefCell<Vec<FanCurvePreviewPoint>>> = Rc::default();
+        area.set_draw_func(clone!(
+            #[strong]
+            points,
+            move |area, context, width, height| {
+                #[allow(deprecated)]
+                let foreground = area.style_context().color();
+                if let Err(err) = draw(&points.borrow(), context, width, height, &foreground) {
+                    warn!("could not draw the fan curve preview: {err}");
+                }
+            }
+        ));
+
+        Self {
+            window,
+            area,
+            summary_label,
+            points,
+        }
+    }
+
+    pub fn show(&self, points: Vec<FanCurvePreviewPoint>) {
+        let summary = match summarize(&points) {
+            Some((max_temperature, max_speed, average_speed)) => fl!(
+                "fan-curve-preview-summary",
+                max_temperature = max_temperature.round(),
+                max_speed = (max_speed * 100.0).round(),
+                average_speed = (average_speed * 100.0).round()
+            ),
+            None => fl!("fan-curve-preview-empty"),
+        };
+        self.summary_label.set_label(&summary);
+        *self.points.borrow_mut() = points;
+        self.area.queue_draw();
+        self.window.present();
+    }
+}
+
+/// Highest temperature, highest and average fan speed
+fn summarize(points: &[FanCurvePreviewPoint]) -> Option<(f32, f32, f32)> {
+    if points.is_empty() {
+        return None;
+    }
+    let max_temperature = points
+        .iter()
+        .map(|point| point.temperature)
+        .fold(f32::MIN, f32::max);
+    let max_speed = points
+        .iter()
+        .map(|point| point.speed)
+        .fold(f32::MIN, f32::max);
+    let average_speed = points.iter().map(|point| point.speed).sum::<f32>() / points.len() as f32;
+    Some((max_temperature, max_speed, average_speed))
+}
+
+fn draw(
+    points: &[FanCurvePreviewPoint],
+    context: &cairo::Context,
+    width: i32,
+    height: i32,
+    foreground: &gdk::RGBA,
+) -> Result<(), cairo::Error> {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return Ok(());
+    };
+
+    let plot_x = MARGIN_LEFT;
+    let plot_y = MARGIN_TOP;
+    let plot_width = (f64::from(width) - MARGIN_LEFT - MARGIN_RIGHT).max(1.0);
+    let plot_height = (f64::from(height) - MARGIN_TOP - MARGIN_BOTTOM).max(1.0);
+    let duration = (last.timestamp - first.timestamp).max(1) as f64;
+    let to_screen = |timestamp: i64, fraction: f64| {
+        (
+            plot_x + (timestamp - first.timestamp) as f64 / duration * plot_width,
+            plot_y + (1.0 - fraction.clamp(0.0, 1.0)) * plot_height,
+        )
+    };
+
+    let (accent_r, accent_g, accent_b) = accent_color().unwrap_or(DEFAULT_ACCENT);
+    let accent = (
+        f64::from(accent_r) / 255.0,
+        f64::from(accent_g) / 255.0,
+        f64::from(accent_b) / 255.0,
+    );
+    let set_foreground = |alpha: f64| {
+        context.set_source_rgba(
+            foreground.red().into(),
+            foreground.green().into(),
+            foreground.blue().into(),
+            f64::from(foreground.alpha()) * alpha,
+        );
+    };
+
+    // Temperatures are labeled on the left and fan speeds on the right
+    context.set_font_size(11.0);
+    context.set_line_width(1.0);
+    for line in 0..=GRID_LINES {
+        let fraction = f64::from(line) / f64::from(GRID_LINES);
+        let y = (plot_y + (1.0 - fraction) * plot_height).round() + 0.5;
+
+        set_foreground(0.12);
+        context.move_to(plot_x, y);
+        context.line_to(plot_x + plot_width, y);
+        context.stroke()?;
+
+        set_foreground(0.7);
+        let temperature_label = format!("{}°C", (fraction * TEMPERATURE_RANGE).round());
+        let extents = context.text_extents(&temperature_label)?;
+        context.move_to(plot_x - extents.width() - 8.0, y + extents.height() / 2.0);
+        context.show_text(&temperature_label)?;
+
+        let speed_label = format!("{}%", (fraction * 100.0).round());
+        let extents = context.text_extents(&speed_label)?;
+        context.move_to(plot_x + plot_width + 8.0, y + extents.height() / 2.0);
+        context.show_text(&speed_label)?;
+    }
+
+    // Times are relative to the latest sample
+    for label in 0..=TIME_LABELS {
+        let fraction = f64::from(label) / f64::from(TIME_LABELS);
+        let seconds_ago = ((1.0 - fraction) * duration / 1000.0).round();
+        let text = if seconds_ago >= 120.0 {
+            format!("-{} min", (seconds_ago / 60.0).round())
+        } else if seconds_ago > 0.0 {
+            format!("-{seconds_ago} s")
+        } else {
+            "0 s".to_owned()
+        };
+        let extents = context.text_extents(&text)?;
+        let x = (plot_x + fraction * plot_width - extents.width() / 2.0)
+            .min(f64::from(width) - extents.width())
+            .max(0.0);
+        context.move_to(x, plot_y + plot_height + extents.height() + 8.0);
+        context.show_text(&text)?;
+    }
+
+    context.set_line_width(2.0);
+    set_foreground(0.6);
+    for (index, point) in points.iter().enumerate() {
+        let (x, y) = to_screen(
+            point.timestamp,
+            f64::from(point.temperature) / TEMPERATURE_RANGE,
+        );
+        if index == 0 {
+            context.move_to(x, y);
+        } else {
+            context.line_to(x, y);
+        }
+    }
+    context.stroke()?;
+
+    context.set_source_rgb(accent.0, accent.1, accent.2);
+    for (index, point) in points.iter().enumerate() {
+        let (x, y) = to_screen(point.timestamp, f64::from(point.speed));
+        if index == 0 {
+            context.move_to(x, y);
+        } else {
+            context.line_to(x, y);
+        }
+    }
+    context.stroke()?;
+
+    // Legend
+    let temperature_legend = fl!("fan-curve-preview-temperature");
+    set_foreground(0.6);
+    context.move_to(plot_x, plot_y - 10.0);
+    context.show_text(&temperature_legend)?;
+    let extents = context.text_extents(&temperature_legend)?;
+    context.set_source_rgb(accent.0, accent.1, accent.2);
+    context.move_to(plot_x + extents.x_advance() + 15.0, plot_y - 10.0);
+    context.show_text(&fl!("fan-curve-preview-speed"))?;
+
+    Ok(())
+}
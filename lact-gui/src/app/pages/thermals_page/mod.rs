@@ -2,6 +2,7 @@ mod fan_curve_frame;
 mod pmfw_frame;
 
 use std::{
+    cell::Cell,
     rc::Rc,
     sync::atomic::{AtomicBool, Ordering},
 };
@@ -10,8 +11,8 @@ use glib::clone;
 use gtk::prelude::*;
 use gtk::*;
 use lact_client::schema::{
-    default_fan_curve, DeviceInfo, DeviceStats, FanControlMode, FanCurveMap, PmfwInfo, PmfwOptions,
-    SystemInfo,
+    default_fan_curve, DeviceCapabilities, DeviceInfo, DeviceStats, FanControlMode, FanCurveInput,
+    FanCurveMap, FanCurvePreviewPoint, FanSpeedUnit, PmfwInfo, PmfwOptions, SystemInfo,
 };
 use lact_daemon::AMDGPU_FAMILY_GC_11_0_0;
 use tracing::debug;
@@ -20,8 +21,8 @@ use self::{fan_curve_frame::FanCurveFrame, pmfw_frame::PmfwFrame};
 use super::{label_row, values_grid};
 use crate::app::page_section::PageSection;
 
-const PMFW_WARNING: &str =
-    "Warning: Overclocking support is disabled, fan control functionality is not available.";
+/// Sensor that the fan curve follows when none are configured
+const DEFAULT_TEMPERATURE_SENSOR: &str = "edge";
 
 #[derive(Debug)]
 pub struct ThermalsSettings {
@@ -32,6 +33,9 @@ pub struct ThermalsSettings {
     pub pmfw: PmfwOptions,
     pub spindown_delay_ms: Option<u64>,
     pub change_threshold: Option<u64>,
+    pub max_ramp_rate: Option<u64>,
+    pub speed_unit: Option<FanSpeedUnit>,
+    pub curve_input: Option<FanCurveInput>,
 }
 
 #[derive(Clone)]
@@ -45,7 +49,9 @@ pub struct ThermalsPage {
     fan_curve_frame: FanCurveFrame,
     fan_control_mode_stack: Stack,
     fan_control_mode_stack_switcher: StackSwitcher,
+    fan_control_reset_button: Button,
     is_amd: Rc<AtomicBool>,
+    rpm_fan_control: Rc<Cell<bool>>,
 
     overdrive_enabled: Option<bool>,
 }
@@ -60,16 +66,16 @@ impl ThermalsPage {
             .build();
 
         let pmfw_warning_label = Label::builder()
-            .label(PMFW_WARNING)
+            .label(fl!("pmfw-warning"))
             .halign(Align::Start)
             .build();
         container.append(&pmfw_warning_label);
 
-        let stats_section = PageSection::new("Statistics");
+        let stats_section = PageSection::new(&fl!("statistics"));
         let stats_grid = values_grid();
 
-        let temperatures_label = label_row("Temperatures:", &stats_grid, 0, 0, false);
-        let fan_speed_label = label_row("Fan speed:", &stats_grid, 1, 0, false);
+        let temperatures_label = label_row(&fl!("temperatures"), &stats_grid, 0, 0, false);
+        let fan_speed_label = label_row(&fl!("fan-speed"), &stats_grid, 1, 0, false);
 
         stats_section.append(&stats_grid);
 
@@ -86,7 +92,7 @@ impl ThermalsPage {
 
         let pmfw_frame = PmfwFrame::new();
 
-        let fan_control_section = PageSection::new("Fan control");
+        let fan_control_section = PageSection::new(&fl!("fan-control"));
 
         let fan_control_mode_stack = Stack::builder().build();
         let fan_control_mode_stack_switcher = StackSwitcher::builder()
@@ -95,13 +101,34 @@ impl ThermalsPage {
             .sensitive(false)
             .build();
 
-        fan_control_mode_stack.add_titled(&pmfw_frame.container, Some("automatic"), "Automatic");
-        fan_control_mode_stack.add_titled(&fan_curve_frame.container, Some("curve"), "Curve");
-        fan_control_mode_stack.add_titled(&fan_static_speed_frame, Some("static"), "Static");
+        fan_control_mode_stack.add_titled(
+            &pmfw_frame.container,
+            Some("automatic"),
+            &fl!("fan-control-automatic"),
+        );
+        fan_control_mode_stack.add_titled(
+            &fan_curve_frame.container,
+            Some("curve"),
+            &fl!("fan-control-curve"),
+        );
+        fan_control_mode_stack.add_titled(
+            &fan_static_speed_frame,
+            Some("static"),
+            &fl!("fan-control-static"),
+        );
 
         fan_control_section.append(&fan_control_mode_stack_switcher);
         fan_control_section.append(&fan_control_mode_stack);
 
+        let fan_control_reset_button = Button::builder()
+            .label(fl!("reset"))
+            .halign(Align::End)
+            .tooltip_text(fl!("reset-fan-control-tooltip"))
+            .css_classes(["destructive-action"])
+            .visible(false)
+            .build();
+        fan_control_section.append(&fan_control_reset_button);
+
         container.append(&fan_control_section);
 
         let is_amd = Rc::new(AtomicBool::new(false));
@@ -127,12 +154,18 @@ impl ThermalsPage {
             fan_curve_frame,
             fan_control_mode_stack,
             fan_control_mode_stack_switcher,
+            fan_control_reset_button,
             pmfw_frame,
             overdrive_enabled: system_info.amdgpu_overdrive_enabled,
             is_amd,
+            rpm_fan_control: Rc::new(Cell::new(false)),
         }
     }
 
+    pub fn set_capabilities(&self, capabilities: &DeviceCapabilities) {
+        self.rpm_fan_control.set(capabilities.rpm_fan_control);
+    }
+
     pub fn set_info(&self, info: &DeviceInfo) {
         let pmfw_disabled = info.drm_info.as_ref().is_some_and(|info| {
             debug!(
@@ -162,7 +195,7 @@ impl ThermalsPage {
             .collect();
         temperatures.sort();
         let temperatures_text = if temperatures.is_empty() {
-            String::from("No sensors found")
+            fl!("no-sensors")
         } else {
             temperatures.join(", ")
         };
@@ -170,7 +203,25 @@ impl ThermalsPage {
         self.temperatures_label
             .set_markup(&format!("<b>{temperatures_text}</b>",));
 
-        let fan_label = if let Some(current_rpm) = stats.fan.speed_current {
+        let fan_label = if !stats.fan.fans.is_empty() {
+            let fans: Vec<String> = stats
+                .fan
+                .fans
+                .iter()
+                .map(|fan| match (fan.speed_current, fan.pwm_current) {
+                    (Some(rpm), _) => format!("{}: {rpm} RPM", fl!("fan", channel = fan.channel)),
+                    (None, Some(pwm)) => format!(
+                        "{}: {}%",
+                        fl!("fan", channel = fan.channel),
+                        ((pwm as f64 / u8::MAX as f64) * 100.0).round()
+                    ),
+                    (None, None) => {
+                        format!("{}: {}", fl!("fan", channel = fan.channel), fl!("unknown"))
+                    }
+                })
+                .collect();
+            Some(format!("<b>{}</b>", fans.join(", ")))
+        } else if let Some(current_rpm) = stats.fan.speed_current {
             let text = match stats.fan.speed_max {
                 Some(max_rpm) => format!(
                     "<b>{current_rpm} RPM ({}%)</b>",
@@ -189,10 +240,19 @@ impl ThermalsPage {
         };
 
         match &fan_label {
+            Some(label) if stats.fan.stall_detected == Some(true) => {
+                self.fan_speed_label.set_markup(&format!(
+                    "{label} <span foreground=\"red\">({})</span>",
+                    fl!("fan-stalled")
+                ))
+            }
             Some(label) => self.fan_speed_label.set_markup(label),
-            None => self.fan_speed_label.set_text("No fan detected"),
+            None => self.fan_speed_label.set_text(&fl!("no-fan")),
         }
 
+        self.fan_control_reset_button
+            .set_visible(stats.fan.control_enabled);
+
         if initial {
             self.fan_control_mode_stack_switcher.set_visible(true);
             self.fan_control_mode_stack_switcher
@@ -214,6 +274,13 @@ impl ThermalsPage {
                     .set_value(*static_speed * 100.0);
             }
 
+            self.fan_curve_frame.set_rpm_control(
+                self.rpm_fan_control.get(),
+                stats.fan.speed_min.zip(stats.fan.speed_max),
+            );
+            self.fan_curve_frame.set_speed_unit(stats.fan.speed_unit);
+            self.fan_curve_frame.set_curve_input(stats.fan.curve_input);
+
             if let Some(curve) = &stats.fan.curve {
                 self.fan_curve_frame.set_curve(curve);
             }
@@ -222,6 +289,8 @@ impl ThermalsPage {
                 .set_spindown_delay_ms(stats.fan.spindown_delay_ms);
             self.fan_curve_frame
                 .set_change_threshold(stats.fan.change_threshold);
+            self.fan_curve_frame
+                .set_max_ramp_rate(stats.fan.max_ramp_rate);
 
             // Only show hysteresis settings when PMFW is not used
             self.fan_curve_frame
@@ -233,6 +302,15 @@ impl ThermalsPage {
 
             self.pmfw_frame.set_info(&stats.fan.pmfw_info);
         }
+
+        let input = self.fan_curve_frame.get_curve_input();
+        self.fan_curve_frame.set_current_position(
+            curve_input_value(stats, input),
+            stats
+                .fan
+                .pwm_current
+                .map(|pwm| f64::from(pwm) / f64::from(u8::MAX)),
+        );
     }
 
     pub fn connect_settings_changed<F: Fn() + 'static + Clone>(&self, f: F) {
@@ -288,6 +366,9 @@ impl ThermalsPage {
                 pmfw,
                 change_threshold: Some(self.fan_curve_frame.get_change_threshold()),
                 spindown_delay_ms: Some(self.fan_curve_frame.get_spindown_delay_ms()),
+                max_ramp_rate: Some(self.fan_curve_frame.get_max_ramp_rate()),
+                speed_unit: self.fan_curve_frame.get_speed_unit(),
+                curve_input: Some(self.fan_curve_frame.get_curve_input()),
             })
         } else {
             None
@@ -297,11 +378,23 @@ impl ThermalsPage {
     pub fn connect_reset_pmfw<F: Fn() + 'static + Clone>(&self, f: F) {
         self.pmfw_frame.connect_reset(f);
     }
+
+    pub fn connect_reset_fan_control<F: Fn() + 'static>(&self, f: F) {
+        self.fan_control_reset_button.connect_clicked(move |_| f());
+    }
+
+    pub fn connect_preview_fan_curve<F: Fn(FanCurveMap) + 'static>(&self, f: F) {
+        self.fan_curve_frame.connect_preview(f);
+    }
+
+    pub fn show_fan_curve_preview(&self, points: Vec<FanCurvePreviewPoint>) {
+        self.fan_curve_frame.show_preview(points);
+    }
 }
 
 fn static_speed_adj(parent_box: &Box) -> Adjustment {
     let label = Label::builder()
-        .label("Speed (in %)")
+        .label(fl!("static-speed"))
         .halign(Align::Start)
         .build();
 
@@ -343,9 +436,39 @@ fn static_speed_adj(parent_box: &Box) -> Adjustment {
 }
 
 fn show_fan_control_warning() {
-    let diag = MessageDialog::new(None::<&Window>, DialogFlags::empty(), MessageType::Warning, ButtonsType::Ok,
-                        "Warning! Due to a driver bug, a reboot may be required for fan control to properly switch back to automatic.");
+    let diag = MessageDialog::new(
+        None::<&Window>,
+        DialogFlags::empty(),
+        MessageType::Warning,
+        ButtonsType::Ok,
+        &fl!("fan-control-reboot-warning"),
+    );
     diag.run_async(|diag, _| {
         diag.hide();
     })
 }
+
+/// Current value of what the fan curve follows, computed the same way as in the daemon
+fn curve_input_value(stats: &DeviceStats, input: FanCurveInput) -> Option<f64> {
+    let sensors = if stats.fan.temperature_sensors.is_empty() {
+        vec![DEFAULT_TEMPERATURE_SENSOR.to_owned()]
+    } else {
+        stats.fan.temperature_sensors.clone()
+    };
+    let temperature = sensors
+        .iter()
+        .filter_map(|sensor| stats.temps.get(sensor)?.current)
+        .map(f64::from)
+        .reduce(f64::max);
+    let power = stats.power.current.or(stats.power.average);
+
+    match input {
+        FanCurveInput::Temperature => temperature,
+        FanCurveInput::Power => power,
+        FanCurveInput::Composite { power_weight } => {
+            let cap = stats.power.cap_current.filter(|cap| *cap > 0.0)?;
+            let power_weight = f64::from(power_weight);
+            Some((1.0 - power_weight) * temperature? + power_weight * (power? / cap * 100.0))
+        }
+    }
+}
@@ -3,7 +3,7 @@ use amdgpu_sysfs::gpu_handle::fan_control::FanInfo;
 use gtk::{
     glib::clone,
     prelude::{AdjustmentExt, ButtonExt, GridExt, WidgetExt},
-    Align, Button, Grid, Label, MenuButton, Orientation, Popover, Scale, SpinButton,
+    Align, Button, Grid, Label, MenuButton, Orientation, Popover, Scale, SpinButton, Switch,
 };
 use lact_client::schema::{PmfwInfo, PmfwOptions};
 
@@ -14,6 +14,9 @@ pub struct PmfwFrame {
     acoustic_limit: OcAdjustment,
     acoustic_target: OcAdjustment,
     minimum_pwm: OcAdjustment,
+    zero_rpm_label: Label,
+    zero_rpm_switch: Switch,
+    zero_rpm_temperature: OcAdjustment,
     reset_button: Button,
 }
 
@@ -28,21 +31,32 @@ impl PmfwFrame {
             .margin_end(10)
             .build();
 
-        let target_temperature = adjustment(&grid, "Target temperature (°C)", 0);
-        let acoustic_limit = adjustment(&grid, "Acoustic limit (RPM)", 1);
-        let acoustic_target = adjustment(&grid, "Acoustic target (RPM)", 2);
-        let minimum_pwm = adjustment(&grid, "Minimum fan speed (%)", 3);
+        let target_temperature = adjustment(&grid, &fl!("target-temperature"), 0);
+        let acoustic_limit = adjustment(&grid, &fl!("acoustic-limit"), 1);
+        let acoustic_target = adjustment(&grid, &fl!("acoustic-target"), 2);
+        let minimum_pwm = adjustment(&grid, &fl!("minimum-fan-speed"), 3);
+
+        let zero_rpm_label = Label::builder()
+            .label(fl!("zero-rpm"))
+            .halign(Align::Start)
+            .visible(false)
+            .build();
+        let zero_rpm_switch = Switch::builder().halign(Align::End).visible(false).build();
+        grid.attach(&zero_rpm_label, 0, 4, 1, 1);
+        grid.attach(&zero_rpm_switch, 5, 4, 1, 1);
+
+        let zero_rpm_temperature = adjustment(&grid, &fl!("zero-rpm-temperature"), 5);
 
         let reset_button = Button::builder()
-            .label("Reset")
+            .label(fl!("reset"))
             .halign(Align::Fill)
             .margin_top(5)
             .margin_bottom(5)
-            .tooltip_text("Warning: this resets the fan firmware settings!")
+            .tooltip_text(fl!("reset-pmfw-tooltip"))
             .css_classes(["destructive-action"])
             .visible(false)
             .build();
-        grid.attach(&reset_button, 5, 4, 1, 1);
+        grid.attach(&reset_button, 5, 6, 1, 1);
 
         Self {
             container: grid,
@@ -50,6 +64,9 @@ impl PmfwFrame {
             acoustic_limit,
             acoustic_target,
             minimum_pwm,
+            zero_rpm_label,
+            zero_rpm_switch,
+            zero_rpm_temperature,
             reset_button,
         }
     }
@@ -59,6 +76,14 @@ impl PmfwFrame {
         set_fan_info(&self.acoustic_target, info.acoustic_target);
         set_fan_info(&self.minimum_pwm, info.minimum_pwm);
         set_fan_info(&self.target_temperature, info.target_temp);
+        set_fan_info(&self.zero_rpm_temperature, info.zero_rpm_temperature);
+
+        self.zero_rpm_label
+            .set_visible(info.zero_rpm_enable.is_some());
+        self.zero_rpm_switch
+            .set_visible(info.zero_rpm_enable.is_some());
+        self.zero_rpm_switch
+            .set_active(info.zero_rpm_enable.unwrap_or(false));
 
         let settings_available = *info != PmfwInfo::default();
         self.reset_button.set_visible(settings_available);
@@ -93,6 +118,20 @@ impl PmfwFrame {
                 f();
             }
         ));
+        self.zero_rpm_temperature.connect_value_changed(clone!(
+            #[strong]
+            f,
+            move |_| {
+                f();
+            }
+        ));
+        self.zero_rpm_switch.connect_active_notify(clone!(
+            #[strong]
+            f,
+            move |_| {
+                f();
+            }
+        ));
     }
 
     pub fn connect_reset<F: Fn() + 'static + Clone>(&self, f: F) {
@@ -119,6 +158,14 @@ impl PmfwFrame {
                 .target_temperature
                 .get_nonzero_value()
                 .map(|value| value as u32),
+            zero_rpm: self
+                .zero_rpm_switch
+                .is_visible()
+                .then(|| self.zero_rpm_switch.is_active()),
+            zero_rpm_threshold: self
+                .zero_rpm_temperature
+                .get_nonzero_value()
+                .map(|value| value as u32),
         }
     }
 }
@@ -0,0 +1,347 @@
+use super::msg::AppMsg;
+use gtk::prelude::*;
+use lact_schema::{
+    ProfileComparisonOptions, ProfileComparisonState, ProfileComparisonStatus, ProfileStatsSummary,
+};
+use relm4::{Component, ComponentParts, ComponentSender, RelmWidgetExt};
+
+/// Records the power usage, temperatures and clocks with the settings of two profiles, so that they can be compared
+pub struct ProfileComparisonDialog {
+    gpu_id: String,
+    /// Custom profiles, the dropdowns list the default profile before them
+    profiles: Vec<String>,
+    first_dropdown: gtk::DropDown,
+    second_dropdown: gtk::DropDown,
+    duration_adjustment: gtk::Adjustment,
+    settle_adjustment: gtk::Adjustment,
+    results_grid: gtk::Grid,
+    status: ProfileComparisonStatus,
+}
+
+#[derive(Debug)]
+pub enum ProfileComparisonDialogMsg {
+    Start,
+    Cancel,
+    Status(ProfileComparisonStatus),
+}
+
+#[relm4::component(pub)]
+impl Component for ProfileComparisonDialog {
+    type Init = (String, Vec<String>);
+    type Input = ProfileComparisonDialogMsg;
+    type Output = AppMsg;
+    type CommandOutput = ();
+
+    view! {
+        gtk::Window {
+            set_default_size: (500, 450),
+            set_title: Some(&fl!("compare-profiles")),
+            set_hide_on_close: true,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 5,
+                set_margin_all: 10,
+
+                gtk::Label {
+                    set_label: &fl!("compare-profiles-description"),
+                    set_wrap: true,
+                    set_halign: gtk::Align::Start,
+                },
+
+                gtk::Grid {
+                    set_row_spacing: 5,
+                    set_column_spacing: 5,
+                    #[watch]
+                    set_sensitive: !model.is_running(),
+
+                    attach[0, 0, 1, 1] = &gtk::Label {
+                        set_label: &fl!("compare-profiles-first"),
+                        set_halign: gtk::Align::Start,
+                    },
+                    attach[1, 0, 1, 1] = &model.first_dropdown.clone(),
+                    attach[0, 1, 1, 1] = &gtk::Label {
+                        set_label: &fl!("compare-profiles-second"),
+                        set_halign: gtk::Align::Start,
+                    },
+                    attach[1, 1, 1, 1] = &model.second_dropdown.clone(),
+                    attach[0, 2, 1, 1] = &gtk::Label {
+                        set_label: &fl!("compare-profiles-duration"),
+                        set_halign: gtk::Align::Start,
+                    },
+                    attach[1, 2, 1, 1] = &gtk::SpinButton {
+                        set_adjustment: &model.duration_adjustment,
+                        set_hexpand: true,
+                    },
+                    attach[0, 3, 1, 1] = &gtk::Label {
+                        set_label: &fl!("compare-profiles-settle-time"),
+                        set_halign: gtk::Align::Start,
+                    },
+                    attach[1, 3, 1, 1] = &gtk::SpinButton {
+                        set_adjustment: &model.settle_adjustment,
+                        set_hexpand: true,
+                    },
+                },
+
+                gtk::Label {
+                    #[watch]
+                    set_label: &model.status_text(),
+                    set_wrap: true,
+                    set_halign: gtk::Align::Start,
+                },
+
+                gtk::ScrolledWindow {
+                    set_vexpand: true,
+
+                    #[local_ref]
+                    results_grid -> gtk::Grid {
+                        set_row_spacing: 5,
+                        set_column_spacing: 15,
+                    },
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 5,
+
+                    gtk::Button {
+                        set_label: &fl!("close"),
+                        set_hexpand: true,
+
+                        connect_clicked[root] => move |_| {
+                            root.hide();
+                        },
+                    },
+
+                    gtk::Button {
+                        set_label: &fl!("cancel"),
+                        set_hexpand: true,
+                        #[watch]
+                        set_sensitive: model.is_running(),
+
+                        connect_clicked => ProfileComparisonDialogMsg::Cancel,
+                    },
+
+                    gtk::Button {
+                        set_label: &fl!("start"),
+                        set_hexpand: true,
+                        add_css_class: "suggested-action",
+                        #[watch]
+                        set_sensitive: !model.is_running(),
+
+                        connect_clicked => ProfileComparisonDialogMsg::Start,
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        (gpu_id, profiles): Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let defaults = ProfileComparisonOptions::default();
+
+        let mut names = vec![fl!("default")];
+        names.extend(profiles.iter().cloned());
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let first_dropdown = gtk::DropDown::from_strings(&names);
+        let second_dropdown = gtk::DropDown::from_strings(&names);
+        first_dropdown.set_hexpand(true);
+        second_dropdown.set_hexpand(true);
+        // Compare the first custom profile with the default settings unless something else is chosen
+        first_dropdown.set_selected(1.min(profiles.len() as u32));
+
+        let model = Self {
+            gpu_id,
+            profiles,
+            first_dropdown,
+            second_dropdown,
+            duration_adjustment: gtk::Adjustment::new(
+                defaults.duration as f64,
+                10.0,
+                3600.0,
+                10.0,
+                60.0,
+                0.0,
+            ),
+            settle_adjustment: gtk::Adjustment::new(
+                defaults.settle_time as f64,
+                0.0,
+                600.0,
+                5.0,
+                30.0,
+                0.0,
+            ),
+            results_grid: gtk::Grid::new(),
+            status: ProfileComparisonStatus::default(),
+        };
+
+        let results_grid = &model.results_grid;
+        let widgets = view_output!();
+        root.present();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
+        match msg {
+            ProfileComparisonDialogMsg::Start => {
+                let options = ProfileComparisonOptions {
+                    profiles: [
+                        self.selected_profile(&self.first_dropdown),
+                        self.selected_profile(&self.second_dropdown),
+                    ],
+                    duration: self.duration_adjustment.value() as u64,
+                    settle_time: self.settle_adjustment.value() as u64,
+                };
+                sender
+                    .output(AppMsg::StartProfileComparison(self.gpu_id.clone(), options))
+                    .unwrap();
+            }
+            ProfileComparisonDialogMsg::Cancel => {
+                sender
+                    .output(AppMsg::CancelProfileComparison(self.gpu_id.clone()))
+                    .unwrap();
+            }
+            ProfileComparisonDialogMsg::Status(status) => {
+                if status.results != self.status.results {
+                    self.show_results(&status.results);
+                }
+                self.status = status;
+            }
+        }
+    }
+}
+
+impl ProfileComparisonDialog {
+    fn is_running(&self) -> bool {
+        matches!(self.status.state, ProfileComparisonState::Running { .. })
+    }
+
+    /// `None` for the default profile
+    fn selected_profile(&self, dropdown: &gtk::DropDown) -> Option<String> {
+        let index = dropdown.selected() as usize;
+        index
+            .checked_sub(1)
+            .and_then(|index| self.profiles.get(index))
+            .cloned()
+    }
+
+    fn status_text(&self) -> String {
+        match &self.status.state {
+            ProfileComparisonState::Idle => fl!("compare-profiles-idle"),
+            ProfileComparisonState::Running { profile, recording } => {
+                let name = self
+                    .status
+                    .profiles
+                    .get(*profile)
+                    .cloned()
+                    .flatten()
+                    .unwrap_or_else(|| fl!("default"));
+                if *recording {
+                    fl!("compare-profiles-recording", profile = name)
+                } else {
+                    fl!("compare-profiles-settling", profile = name)
+                }
+            }
+            ProfileComparisonState::Finished => fl!("compare-profiles-finished"),
+            ProfileComparisonState::Cancelled => fl!("compare-profiles-cancelled"),
+            ProfileComparisonState::Failed { error } => {
+                fl!("compare-profiles-failed", error = error.as_str())
+            }
+        }
+    }
+
+    /// Fills the table with a column for every recorded profile, and their difference once both are done
+    fn show_results(&self, results: &[ProfileStatsSummary]) {
+        let grid = &self.results_grid;
+        while let Some(child) = grid.first_child() {
+            grid.remove(&child);
+        }
+        if results.is_empty() {
+            return;
+        }
+
+        let mut rows: Vec<(String, Vec<Option<f64>>)> = vec![
+            (
+                fl!("compare-profiles-power"),
+                results.iter().map(|summary| summary.power).collect(),
+            ),
+            (
+                fl!("compare-profiles-gpu-clock"),
+                results
+                    .iter()
+                    .map(|summary| summary.gpu_clockspeed)
+                    .collect(),
+            ),
+            (
+                fl!("compare-profiles-vram-clock"),
+                results
+                    .iter()
+                    .map(|summary| summary.vram_clockspeed)
+                    .collect(),
+            ),
+            (
+                fl!("compare-profiles-fan-speed"),
+                results.iter().map(|summary| summary.fan_speed).collect(),
+            ),
+            (
+                fl!("compare-profiles-gpu-usage"),
+                results.iter().map(|summary| summary.busy_percent).collect(),
+            ),
+        ];
+        let mut sensors: Vec<&String> = results
+            .iter()
+            .flat_map(|summary| summary.temperatures.keys())
+            .collect();
+        sensors.sort_unstable();
+        sensors.dedup();
+        for sensor in sensors {
+            rows.push((
+                fl!("compare-profiles-temperature", sensor = sensor.as_str()),
+                results
+                    .iter()
+                    .map(|summary| summary.temperatures.get(sensor).copied())
+                    .collect(),
+            ));
+        }
+
+        let label = |text: &str, column: i32, row: i32, bold: bool| {
+            let label = gtk::Label::builder()
+                .label(text)
+                .halign(if column == 0 {
+                    gtk::Align::Start
+                } else {
+                    gtk::Align::End
+                })
+                .selectable(true)
+                .build();
+            if bold {
+                label.add_css_class("heading");
+            }
+            grid.attach(&label, column, row, 1, 1);
+        };
+
+        for (column, summary) in results.iter().enumerate() {
+            let name = summary.profile.clone().unwrap_or_else(|| fl!("default"));
+            label(&name, column as i32 + 1, 0, true);
+        }
+        if results.len() == 2 {
+            label(&fl!("compare-profiles-difference"), 3, 0, true);
+        }
+
+        for (row, (name, values)) in rows.into_iter().enumerate() {
+            let row = row as i32 + 1;
+            label(&name, 0, row, false);
+            for (column, value) in values.iter().enumerate() {
+                let text = value.map_or_else(|| "-".to_owned(), |value| format!("{value:.1}"));
+                label(&text, column as i32 + 1, row, false);
+            }
+            if let [Some(first), Some(second)] = values[..] {
+                label(&format!("{:+.1}", second - first), 3, row, false);
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Tray icon (StatusNotifierItem) showing the stats of the current GPU, with quick profile switching.
+//! The tray runs on its own thread, so it talks to the app through [`TrayMsg`]s.
+use crate::APP_ID;
+use ksni::{
+    menu::{RadioGroup, RadioItem, StandardItem},
+    MenuItem, ToolTip, TrayService,
+};
+use lact_schema::{DeviceStats, ProfilesInfo};
+
+#[derive(Debug)]
+pub enum TrayMsg {
+    ShowWindow,
+    SelectProfile(Option<String>),
+    Quit,
+}
+
+pub struct Tray {
+    handle: ksni::Handle<TrayState>,
+}
+
+impl Tray {
+    pub fn spawn(sender: relm4::Sender<TrayMsg>) -> Self {
+        let service = TrayService::new(TrayState {
+            sender,
+            status: None,
+            profiles: vec![],
+            current_profile: None,
+        });
+        let handle = service.handle();
+        service.spawn();
+        Self { handle }
+    }
+
+    pub fn set_stats(&self, stats: &DeviceStats) {
+        let status = format_status(stats);
+        self.handle.update(|tray| tray.status = status);
+    }
+
+    pub fn set_profiles(&self, info: &ProfilesInfo) {
+        let profiles = info.profiles.clone();
+        let current_profile = info.current_profile.clone();
+        self.handle.update(|tray| {
+            tray.profiles = profiles;
+            tray.current_profile = current_profile;
+        });
+    }
+
+    pub fn set_current_profile(&self, profile: Option<String>) {
+        self.handle.update(|tray| tray.current_profile = profile);
+    }
+}
+
+impl Drop for Tray {
+    fn drop(&mut self) {
+        self.handle.shutdown();
+    }
+}
+
+struct TrayState {
+    sender: relm4::Sender<TrayMsg>,
+    status: Option<String>,
+    profiles: Vec<String>,
+    current_profile: Option<String>,
+}
+
+impl ksni::Tray for TrayState {
+    fn id(&self) -> String {
+        APP_ID.to_owned()
+    }
+
+    fn title(&self) -> String {
+        match &self.status {
+            Some(status) => format!("LACT: {status}"),
+            None => "LACT".to_owned(),
+        }
+    }
+
+    fn icon_name(&self) -> String {
+        APP_ID.to_owned()
+    }
+
+    fn tool_tip(&self) -> ToolTip {
+        ToolTip {
+            title: "LACT".to_owned(),
+            description: self.status.clone().unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        self.sender.emit(TrayMsg::ShowWindow);
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        let mut items = vec![];
+
+        if let Some(status) = &self.status {
+            items.push(
+                StandardItem {
+                    label: status.clone(),
+                    enabled: false,
+                    ..Default::default()
+                }
+                .into(),
+            );
+            items.push(MenuItem::Separator);
+        }
+
+        // The first option is the default profile
+        let selected = self
+            .current_profile
+            .as_ref()
+            .and_then(|current| self.profiles.iter().position(|profile| profile == current))
+            .map_or(0, |index| index + 1);
+        let options = std::iter::once(fl!("default"))
+            .chain(self.profiles.iter().cloned())
+            .map(|label| RadioItem {
+                label,
+                ..Default::default()
+            })
+            .collect();
+        items.push(
+            RadioGroup {
+                selected,
+                select: Box::new(|tray: &mut Self, index| {
+                    let profile = index
+                        .checked_sub(1)
+                        .and_then(|index| tray.profiles.get(index).cloned());
+                    tray.current_profile.clone_from(&profile);
+                    tray.sender.emit(TrayMsg::SelectProfile(profile));
+                }),
+                options,
+            }
+            .into(),
+        );
+
+        items.push(MenuItem::Separator);
+        items.push(
+            StandardItem {
+                label: fl!("tray-open"),
+                activate: Box::new(|tray: &mut Self| tray.sender.emit(TrayMsg::ShowWindow)),
+                ..Default::default()
+            }
+            .into(),
+        );
+        items.push(
+            StandardItem {
+                label: fl!("tray-quit"),
+                icon_name: "application-exit".to_owned(),
+                activate: Box::new(|tray: &mut Self| tray.sender.emit(TrayMsg::Quit)),
+                ..Default::default()
+            }
+            .into(),
+        );
+
+        items
+    }
+}
+
+/// Short summary of the hottest temperature and the fan speed
+fn format_status(stats: &DeviceStats) -> Option<String> {
+    let temperature = stats
+        .temps
+        .values()
+        .filter_map(|temp| temp.current)
+        .reduce(f32::max)
+        .map(|temp| format!("{temp:.0}°C"));
+    let fan = match (stats.fan.speed_current, stats.fan.pwm_current) {
+        (Some(rpm), _) => Some(format!("{rpm} RPM")),
+        (None, Some(pwm)) => Some(format!(
+            "{:.0}%",
+            f64::from(pwm) / f64::from(u8::MAX) * 100.0
+        )),
+        (None, None) => None,
+    };
+
+    let parts: Vec<String> = temperature.into_iter().chain(fan).collect();
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_status;
+    use amdgpu_sysfs::hw_mon::Temperature;
+    use lact_schema::DeviceStats;
+
+    fn temp(current: Option<f32>) -> Temperature {
+        Temperature {
+            current,
+            crit: None,
+            crit_hyst: None,
+        }
+    }
+
+    #[test]
+    fn tray_status() {
+        let mut stats = DeviceStats::default();
+        assert_eq!(format_status(&stats), None);
+
+        stats.temps.insert("edge".to_owned(), temp(Some(54.0)));
+        stats.temps.insert("junction".to_owned(), temp(Some(61.4)));
+        stats.temps.insert("mem".to_owned(), temp(None));
+        stats.fan.pwm_current = Some(128);
+        assert_eq!(format_status(&stats).as_deref(), Some("61°C, 50%"));
+
+        stats.fan.speed_current = Some(1450);
+        assert_eq!(format_status(&stats).as_deref(), Some("61°C, 1450 RPM"));
+    }
+}
@@ -0,0 +1,242 @@
+use super::msg::AppMsg;
+use gtk::prelude::*;
+use lact_schema::{UndervoltOptions, UndervoltState, UndervoltStatus};
+use relm4::{Component, ComponentParts, ComponentSender, RelmWidgetExt};
+
+/// Guides through finding the lowest stable voltage offset, which the daemon searches by running a stress test
+pub struct UndervoltDialog {
+    gpu_id: String,
+    command_buffer: gtk::EntryBuffer,
+    step_adjustment: gtk::Adjustment,
+    min_offset_adjustment: gtk::Adjustment,
+    duration_adjustment: gtk::Adjustment,
+    log_buffer: gtk::TextBuffer,
+    status: UndervoltStatus,
+}
+
+#[derive(Debug)]
+pub enum UndervoltDialogMsg {
+    Start,
+    Cancel,
+    Status(UndervoltStatus),
+}
+
+#[relm4::component(pub)]
+impl Component for UndervoltDialog {
+    type Init = String;
+    type Input = UndervoltDialogMsg;
+    type Output = AppMsg;
+    type CommandOutput = ();
+
+    view! {
+        gtk::Window {
+            set_default_size: (450, 450),
+            set_title: Some(&fl!("undervolt-assistant")),
+            set_hide_on_close: true,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 5,
+                set_margin_all: 10,
+
+                gtk::Label {
+                    set_label: &fl!("undervolt-description"),
+                    set_wrap: true,
+                    set_halign: gtk::Align::Start,
+                },
+
+                gtk::Label {
+                    set_label: &fl!("undervolt-warning"),
+                    set_wrap: true,
+                    set_halign: gtk::Align::Start,
+                    add_css_class: "dim-label",
+                },
+
+                gtk::Entry {
+                    set_placeholder_text: Some(&fl!("stress-test-command")),
+                    set_buffer: &model.command_buffer,
+                    #[watch]
+                    set_sensitive: !model.is_running(),
+                },
+
+                gtk::Grid {
+                    set_row_spacing: 5,
+                    set_column_spacing: 5,
+                    #[watch]
+                    set_sensitive: !model.is_running(),
+
+                    attach[0, 0, 1, 1] = &gtk::Label {
+                        set_label: &fl!("undervolt-step"),
+                        set_halign: gtk::Align::Start,
+                    },
+                    attach[1, 0, 1, 1] = &gtk::SpinButton {
+                        set_adjustment: &model.step_adjustment,
+                        set_hexpand: true,
+                    },
+                    attach[0, 1, 1, 1] = &gtk::Label {
+                        set_label: &fl!("undervolt-lowest-offset"),
+                        set_halign: gtk::Align::Start,
+                    },
+                    attach[1, 1, 1, 1] = &gtk::SpinButton {
+                        set_adjustment: &model.min_offset_adjustment,
+                        set_hexpand: true,
+                    },
+                    attach[0, 2, 1, 1] = &gtk::Label {
+                        set_label: &fl!("undervolt-step-duration"),
+                        set_halign: gtk::Align::Start,
+                    },
+                    attach[1, 2, 1, 1] = &gtk::SpinButton {
+                        set_adjustment: &model.duration_adjustment,
+                        set_hexpand: true,
+                    },
+                },
+
+                gtk::Label {
+                    #[watch]
+                    set_label: &model.status_text(),
+                    set_wrap: true,
+                    set_halign: gtk::Align::Start,
+                },
+
+                gtk::ScrolledWindow {
+                    set_vexpand: true,
+
+                    gtk::TextView {
+                        set_buffer: Some(&model.log_buffer),
+                        set_editable: false,
+                        set_cursor_visible: false,
+                        set_monospace: true,
+                    },
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 5,
+
+                    gtk::Button {
+                        set_label: &fl!("close"),
+                        set_hexpand: true,
+
+                        connect_clicked[root] => move |_| {
+                            root.hide();
+                        },
+                    },
+
+                    gtk::Button {
+                        set_label: &fl!("cancel"),
+                        set_hexpand: true,
+                        #[watch]
+                        set_sensitive: model.is_running(),
+
+                        connect_clicked => UndervoltDialogMsg::Cancel,
+                    },
+
+                    gtk::Button {
+                        set_label: &fl!("start"),
+                        set_hexpand: true,
+                        add_css_class: "suggested-action",
+                        #[watch]
+                        set_sensitive: !model.is_running(),
+
+                        connect_clicked => UndervoltDialogMsg::Start,
+                    },
+                }
+            },
+        }
+    }
+
+    fn init(
+        gpu_id: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let defaults = UndervoltOptions::default();
+
+        let model = Self {
+            gpu_id,
+            command_buffer: gtk::EntryBuffer::new(None::<String>),
+            step_adjustment: gtk::Adjustment::new(defaults.step.into(), 1.0, 100.0, 1.0, 10.0, 0.0),
+            min_offset_adjustment: gtk::Adjustment::new(
+                defaults.min_offset.into(),
+                -1000.0,
+                0.0,
+                5.0,
+                50.0,
+                0.0,
+            ),
+            duration_adjustment: gtk::Adjustment::new(
+                defaults.step_duration as f64,
+                10.0,
+                3600.0,
+                10.0,
+                60.0,
+                0.0,
+            ),
+            log_buffer: gtk::TextBuffer::new(None),
+            status: UndervoltStatus::default(),
+        };
+
+        let widgets = view_output!();
+        root.present();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
+        match msg {
+            UndervoltDialogMsg::Start => {
+                let stress_command = self.command_buffer.text().trim().to_owned();
+                if stress_command.is_empty() {
+                    return;
+                }
+                let options = UndervoltOptions {
+                    stress_command,
+                    step: self.step_adjustment.value() as i32,
+                    min_offset: self.min_offset_adjustment.value() as i32,
+                    step_duration: self.duration_adjustment.value() as u64,
+                };
+                sender
+                    .output(AppMsg::StartUndervolt(self.gpu_id.clone(), options))
+                    .unwrap();
+            }
+            UndervoltDialogMsg::Cancel => {
+                sender
+                    .output(AppMsg::CancelUndervolt(self.gpu_id.clone()))
+                    .unwrap();
+            }
+            UndervoltDialogMsg::Status(status) => {
+                if status.log != self.status.log {
+                    self.log_buffer.set_text(&status.log.join("\n"));
+                }
+                self.status = status;
+            }
+        }
+    }
+}
+
+impl UndervoltDialog {
+    fn is_running(&self) -> bool {
+        matches!(self.status.state, UndervoltState::Running { .. })
+    }
+
+    fn status_text(&self) -> String {
+        let stable = match self.status.stable_offset {
+            Some(offset) => format!("{offset} mV"),
+            None => fl!("undervolt-none-yet"),
+        };
+        match &self.status.state {
+            UndervoltState::Idle => fl!("undervolt-idle"),
+            UndervoltState::Running { offset } => {
+                fl!("undervolt-running", offset = *offset, stable = stable)
+            }
+            UndervoltState::Finished {
+                stable_offset: Some(offset),
+            } => fl!("undervolt-finished", offset = *offset),
+            UndervoltState::Finished {
+                stable_offset: None,
+            } => fl!("undervolt-finished-none"),
+            UndervoltState::Cancelled => fl!("undervolt-cancelled", stable = stable),
+            UndervoltState::Failed { error } => fl!("undervolt-failed", error = error.as_str()),
+        }
+    }
+}
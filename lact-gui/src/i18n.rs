@@ -0,0 +1,45 @@
+//! Translations of the user interface, using [Fluent](https://projectfluent.org/).
+//! The translation files are embedded into the binary from `i18n/<language>/lact_gui.ftl`,
+//! with English being the fallback for missing languages and messages.
+use i18n_embed::{
+    fluent::{fluent_language_loader, FluentLanguageLoader},
+    DesktopLanguageRequester, LanguageLoader,
+};
+use rust_embed::RustEmbed;
+use std::sync::LazyLock;
+use tracing::{debug, warn};
+
+#[derive(RustEmbed)]
+#[folder = "i18n"]
+struct Localizations;
+
+pub static LANGUAGE_LOADER: LazyLock<FluentLanguageLoader> = LazyLock::new(|| {
+    let loader = fluent_language_loader!();
+
+    // Picks the languages from the environment, such as `LANGUAGE` and `LANG`
+    let requested_languages = DesktopLanguageRequester::requested_languages();
+    match i18n_embed::select(&loader, &Localizations, &requested_languages) {
+        Ok(languages) => debug!("using languages {languages:?}"),
+        Err(err) => {
+            warn!("could not load translations: {err}");
+            if let Err(err) = loader.load_fallback_language(&Localizations) {
+                warn!("could not load the fallback language: {err}");
+            }
+        }
+    }
+
+    // Unicode isolation marks around arguments show up as boxes in GTK labels
+    loader.set_use_isolating(false);
+    loader
+});
+
+/// Gets a translated message by its id, with optional arguments: `fl!("message-id", name = value)`.
+/// The ids and arguments are checked against the English translation at compile time.
+macro_rules! fl {
+    ($message_id:literal) => {{
+        i18n_embed_fl::fl!($crate::i18n::LANGUAGE_LOADER, $message_id)
+    }};
+    ($message_id:literal, $($args:expr),* $(,)?) => {{
+        i18n_embed_fl::fl!($crate::i18n::LANGUAGE_LOADER, $message_id, $($args),*)
+    }};
+}
@@ -1,3 +1,5 @@
+#[macro_use]
+mod i18n;
 pub mod app;
 
 use anyhow::Context;
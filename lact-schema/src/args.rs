@@ -1,6 +1,7 @@
 pub use clap;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 pub struct Args {
@@ -11,11 +12,26 @@ pub struct Args {
 #[derive(Subcommand)]
 pub enum Command {
     /// Run the daemon
-    Daemon,
+    Daemon(DaemonArgs),
     /// Run the GUI
     Gui(GuiArgs),
     /// Run the CLI
     Cli(CliArgs),
+    /// Apply the configured GPU settings and exit, for keeping them without running the daemon
+    Apply,
+}
+
+#[derive(Default, Parser)]
+pub struct DaemonArgs {
+    /// Apply the configured settings and exit, e.g. at boot when the daemon is started through socket activation
+    #[arg(long)]
+    pub apply_once: bool,
+    /// Path of the socket to listen on, instead of the one from `LACT_SOCKET_PATH` or the config
+    #[arg(long)]
+    pub socket_path: Option<PathBuf>,
+    /// Load the GPUs from a fake sysfs tree (with the devices in `class/drm`) instead of `/sys`, for testing without hardware
+    #[arg(long)]
+    pub test_sysfs: Option<PathBuf>,
 }
 
 #[derive(Default, Parser)]
@@ -25,6 +41,16 @@ pub struct GuiArgs {
     /// Remote TCP address to connect to
     #[arg(long)]
     pub tcp_address: Option<String>,
+    /// Token to authenticate with on the remote TCP address
+    #[arg(long)]
+    pub tcp_token: Option<String>,
+    /// Path of the daemon socket, instead of the one from `LACT_SOCKET_PATH` or the default locations
+    #[arg(long)]
+    pub socket_path: Option<PathBuf>,
+    /// Show a tray icon with the temperature, fan speed and profile switching.
+    /// Closing the window keeps LACT running in the tray.
+    #[arg(long)]
+    pub tray: bool,
 }
 
 #[derive(Parser)]
@@ -32,6 +58,13 @@ pub struct GuiArgs {
 pub struct CliArgs {
     #[arg(short, long)]
     pub gpu_id: Option<String>,
+    /// Path of the daemon socket, instead of the one from `LACT_SOCKET_PATH` or the default locations
+    #[arg(long)]
+    pub socket_path: Option<PathBuf>,
+    /// Keep changes without asking for confirmation.
+    /// Otherwise changed settings are reverted unless they are confirmed, in case they make the system unusable
+    #[arg(long, global = true)]
+    pub yes: bool,
     #[command(subcommand)]
     pub subcommand: CliCommand,
 }
@@ -42,6 +75,340 @@ pub enum CliCommand {
     ListGpus,
     /// Show GPU info
     Info,
+    /// Show current GPU stats
+    Stats,
+    /// Show the processes using the GPU along with their engine and memory usage
+    Processes,
+    /// Show the temperatures and fan speeds of the non-GPU hwmon chips listed in the `sensor_chips` daemon setting
+    Sensors,
+    /// Set the power cap in watts. Resets it to the default if no value is given
+    PowerCap { watts: Option<f64> },
+    /// Lower the power cap automatically to keep the GPU at or below the given temperature (in °C),
+    /// or disable this when no temperature is given
+    ThermalTarget { temperature: Option<f32> },
+    /// Show whether the GPU is powered off by runtime power management (hybrid laptops) and what keeps it awake,
+    /// or change whether it's allowed to power off
+    RuntimePm { mode: Option<RuntimePmArg> },
+    /// Use a different power cap while a process is running, omit the value to remove the override
+    AppPowerCap { process: String, watts: Option<f64> },
+    /// Keep the PCIe link at or above the given power state, or list the states when none is given.
+    /// Use `--reset` to allow all states again
+    PcieMinState {
+        state: Option<u8>,
+        #[arg(long, conflicts_with = "state")]
+        reset: bool,
+    },
+    /// Keep the VRAM clock at or above the given power state, which commonly fixes flickering with multiple monitors,
+    /// or list the states when none is given. Use `--reset` to allow all states again
+    VramMinState {
+        state: Option<u8>,
+        #[arg(long, conflicts_with = "state")]
+        reset: bool,
+    },
+    /// Set a power limit (in watts) or the temperature limit (in °C) of an APU, or list the available limits
+    /// when no kind is given. Restores the default if no value is given
+    ApuLimit {
+        kind: Option<ApuLimitArg>,
+        #[arg(requires = "kind")]
+        value: Option<f64>,
+    },
+    /// Apply a fan curve from a JSON file, mapping temperatures (or power draws with `--input power`)
+    /// to fan speeds between 0 and 1, e.g. `{"40": 0.2, "60": 0.5, "80": 1.0}`
+    FanCurve {
+        path: PathBuf,
+        /// Only apply the curve to the given fan (e.g. 2 for `pwm2` or the second Nvidia fan) instead of all fans
+        #[arg(long)]
+        fan: Option<u32>,
+        /// Show the fan speeds the curve would have set on the recorded stats history instead of applying it
+        #[arg(long, conflicts_with = "fan")]
+        preview: bool,
+        /// The curve maps temperatures to RPM targets, which the fans are set to instead of PWM percentages
+        #[arg(long)]
+        rpm: bool,
+        /// What the curve points are placed at
+        #[arg(long, default_value = "temperature")]
+        input: CurveInputArg,
+        /// Share of the power draw in a composite curve, between 0 and 1
+        #[arg(long, required_if_eq("input", "composite"))]
+        power_weight: Option<f32>,
+    },
+    /// Reset fan control back to automatic
+    ResetFan,
+    /// Reset the clocks and voltages back to stock
+    ResetClocks,
+    /// Reset the power cap back to the default and disable the thermal target
+    ResetPowerCap,
+    /// Restore the settings from before the last change
+    Undo,
+    /// Reapply the last change that was undone
+    Redo,
     /// Generate debug snapshot
     Snapshot,
+    /// Manage settings profiles
+    #[command(subcommand)]
+    Profile(ProfileCommand),
+    /// Print all daemon settings as JSON, for backups
+    ExportState,
+    /// Import daemon settings previously saved with `export-state`
+    ImportState { path: PathBuf },
+    /// Save the VBIOS of the GPU to a file
+    DumpVbios { path: PathBuf },
+    /// Reset the GPU to recover from a hang (AMD only).
+    /// All applications using the GPU are interrupted, so `--yes` has to be passed to confirm it
+    ResetGpu,
+    /// Manage power profile modes (`pp_power_profile_mode`)
+    #[command(subcommand)]
+    PowerProfile(PowerProfileCommand),
+    /// Manage memory power states of GPUs that set clocks per state (AMD Polaris and Vega)
+    #[command(subcommand)]
+    MemoryState(MemoryStateCommand),
+    /// Show the GPU and VRAM clock power states (DPM levels) and choose which ones may be used
+    #[command(subcommand)]
+    PowerState(PowerStateCommand),
+    /// Show or change the fan settings of the GPU firmware (AMD RDNA3 and newer)
+    #[command(subcommand)]
+    Pmfw(PmfwCommand),
+    /// Manage temperature alerts, which run actions when a sensor stays above a threshold
+    #[command(subcommand)]
+    Alert(AlertCommand),
+    /// Find the lowest stable voltage offset by lowering it step by step while a stress test runs
+    #[command(subcommand)]
+    Undervolt(UndervoltCommand),
+    /// Compare the power usage, temperatures and clocks of two profiles while a workload keeps running
+    #[command(subcommand)]
+    CompareProfiles(CompareProfilesCommand),
+}
+
+#[derive(Subcommand)]
+pub enum CompareProfilesCommand {
+    /// Start recording in the background, e.g. `compare-profiles start Undervolt` compares it with the default settings.
+    /// The settings of each profile are applied in turn without switching the active profile
+    Start {
+        first: String,
+        /// Uses the default settings if not given
+        second: Option<String>,
+        /// Seconds the stats are recorded with each profile
+        #[arg(long, default_value_t = 120)]
+        duration: u64,
+        /// Seconds to wait after applying a profile before recording, so that temperatures can settle
+        #[arg(long, default_value_t = 30)]
+        settle_time: u64,
+    },
+    /// Show the progress and the averages of every profile
+    Status,
+    /// Stop recording and restore the previous settings
+    Cancel,
+}
+
+#[derive(Subcommand)]
+pub enum UndervoltCommand {
+    /// Start searching in the background, e.g. `undervolt start "glmark2 --run-forever"`.
+    /// The stress test command runs as root
+    Start {
+        command: String,
+        /// How much the offset is lowered per step, in mV
+        #[arg(long, default_value_t = 10)]
+        step: i32,
+        /// Lowest offset to try, in mV
+        #[arg(long, default_value_t = -200, allow_negative_numbers = true)]
+        min_offset: i32,
+        /// Seconds the stress test runs at every offset
+        #[arg(long, default_value_t = 120)]
+        duration: u64,
+    },
+    /// Show the progress and the results of the steps
+    Status,
+    /// Stop searching and restore the previous offset
+    Cancel,
+}
+
+#[derive(Subcommand)]
+pub enum AlertCommand {
+    /// List the alerts of the GPU
+    List,
+    /// Add an alert, e.g. `alert add junction 100 --duration 10 --notify --max-fans`.
+    /// `alert add fan_stall 40 --duration 10 --notify` alerts when a fan driven above 40% doesn't spin
+    Add {
+        /// Temperature sensor, e.g. `junction` or `edge`, or `fan_stall` for fans that don't spin
+        sensor: String,
+        /// Temperature in °C, or the fan speed in percent for `fan_stall`
+        threshold: f32,
+        /// Seconds the temperature has to stay above the threshold
+        #[arg(long, default_value_t = 0)]
+        duration: u64,
+        /// Show a desktop notification in the GUI
+        #[arg(long)]
+        notify: bool,
+        /// Run the fans at full speed
+        #[arg(long)]
+        max_fans: bool,
+        /// Lower the power cap to the given amount of watts
+        #[arg(long)]
+        power_cap: Option<f64>,
+    },
+    /// Remove an alert by its index in the list
+    Remove { index: usize },
+    /// Remove all alerts
+    Clear,
+}
+
+#[derive(Subcommand)]
+pub enum PmfwCommand {
+    /// Show the current values along with their allowed ranges
+    Show,
+    /// Change the given settings, keeping the other ones
+    Set {
+        /// Highest fan speed in RPM
+        #[arg(long)]
+        acoustic_limit: Option<u32>,
+        /// Fan speed in RPM that the firmware aims for
+        #[arg(long)]
+        acoustic_target: Option<u32>,
+        /// Temperature in °C that the firmware tries to keep the GPU at
+        #[arg(long)]
+        target_temperature: Option<u32>,
+        /// Lowest fan speed in percent
+        #[arg(long)]
+        minimum_pwm: Option<u32>,
+        /// Stop the fans at low temperatures
+        #[arg(long)]
+        zero_rpm: Option<bool>,
+        /// Temperature in °C below which the fans are stopped
+        #[arg(long)]
+        zero_rpm_threshold: Option<u32>,
+    },
+    /// Restore the firmware defaults
+    Reset,
+}
+
+#[derive(Subcommand)]
+pub enum MemoryStateCommand {
+    /// List the memory power states with their clocks and voltages
+    List,
+    /// Set the clockspeed (in MHz) and memory voltage (in mV) of a memory power state.
+    /// The change is reverted unless it is confirmed, in case it makes the system unusable
+    Set {
+        index: usize,
+        clockspeed: i32,
+        voltage: i32,
+    },
+    /// Keep the memory in a single power state
+    Lock { index: u8 },
+    /// Allow all memory power states again
+    Unlock,
+}
+
+#[derive(Subcommand)]
+pub enum PowerStateCommand {
+    /// List the power states with their clocks, `*` marks the enabled ones
+    List,
+    /// Only allow the given power states, e.g. `power-state enable core 1 2`.
+    /// Switches the performance level to `manual`
+    Enable {
+        kind: PowerStateKind,
+        #[arg(required = true)]
+        states: Vec<u8>,
+    },
+    /// Allow all power states again by switching the performance level back to `auto`
+    Reset,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PowerStateKind {
+    /// GPU core clock (`pp_dpm_sclk`)
+    Core,
+    /// VRAM clock (`pp_dpm_mclk`)
+    Memory,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum RuntimePmArg {
+    /// Power off the GPU while it's unused
+    Auto,
+    /// Keep the GPU powered on
+    On,
+    /// Leave the setting to the system, e.g. udev rules or TLP
+    System,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum CurveInputArg {
+    /// Temperature in °C
+    Temperature,
+    /// Power draw in watts, for cards whose temperature sensor lags behind load changes
+    Power,
+    /// Mix of the temperature and the power draw as a percentage of the power cap, on the temperature scale
+    Composite,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ApuLimitArg {
+    /// Sustained power limit (STAPM)
+    Stapm,
+    /// Power limit for short boosts (fast PPT)
+    Fast,
+    /// Power limit for longer boosts (slow PPT)
+    Slow,
+    /// Temperature limit (Tctl)
+    Temperature,
+}
+
+#[derive(Subcommand)]
+pub enum PowerProfileCommand {
+    /// List the power profile modes along with their heuristics
+    List,
+    /// Activate a power profile mode by its index
+    Set { index: u16 },
+    /// Activate the custom power profile mode with heuristics from a JSON file.
+    /// The file contains a list of values for every component, `null` keeps the current value
+    Custom { path: PathBuf },
+    /// Let the driver choose the power profile mode
+    Reset,
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommand {
+    /// List available profiles
+    List,
+    /// Switch to a profile. Switches to the default settings if no name is given
+    Set { name: Option<String> },
+    /// Cycle to the next profile (after the last one, the default settings are used). Useful for binding to a hotkey
+    Next,
+    /// Save the currently active settings as a new profile
+    Save { name: String },
+    /// Delete a profile
+    Delete { name: String },
+    /// Set the rule for activating a profile automatically. Removes the rule if no process is given
+    Rule {
+        name: String,
+        /// Process (or executable) name that activates the profile while running
+        #[arg(long)]
+        process: Option<String>,
+        /// Text that the process arguments have to contain
+        #[arg(long, requires = "process")]
+        args: Option<String>,
+    },
+    /// Activate a profile automatically during a time window, e.g. `schedule Silent --start 22:00 --end 07:00`.
+    /// Removes the schedule if no start is given
+    Schedule {
+        name: String,
+        /// Start time as HH:MM
+        #[arg(long, requires = "end")]
+        start: Option<String>,
+        /// End time as HH:MM, can be before the start for windows that continue past midnight
+        #[arg(long, requires = "start")]
+        end: Option<String>,
+        /// Days of the week the window starts on, from 1 (Monday) to 7 (Sunday), e.g. `1,2,3,4,5`.
+        /// Every day when not given
+        #[arg(long, value_delimiter = ',', requires = "start")]
+        days: Vec<u8>,
+    },
+    /// Enable or disable switching profiles automatically based on their rules and schedules
+    AutoSwitch {
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+    /// Set the profile to switch back to when an automatically activated profile stops matching.
+    /// The default settings are used if no name is given
+    Fallback { name: Option<String> },
 }
@@ -18,6 +18,7 @@ use amdgpu_sysfs::{
     hw_mon::Temperature,
 };
 use indexmap::IndexMap;
+use request::SetClocksCommand;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::{
@@ -28,6 +29,25 @@ use std::{
 };
 
 pub const GIT_COMMIT: &str = env!("VERGEN_GIT_SHA");
+/// Version of the daemon protocol, should be bumped on incompatible request or response changes.
+/// Version 2 added requests such as stats subscriptions, alerts and batches, which older daemons reject.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Socket of a daemon running as root
+pub const DEFAULT_SOCKET_PATH: &str = "/run/lactd.sock";
+/// Environment variable with the socket path to use instead of the default one, read by both the daemon and clients.
+/// Allows running separate daemon instances, e.g. in containers or tests.
+pub const SOCKET_PATH_ENV: &str = "LACT_SOCKET_PATH";
+
+/// Error returned by the daemon when a setting requires the amdgpu overdrive feature, but it is not enabled
+pub const OVERDRIVE_DISABLED_ERROR: &str =
+    "Overdrive is not enabled, clocks and voltages cannot be changed";
+
+/// Checks if an error (or any of its causes) is [`OVERDRIVE_DISABLED_ERROR`]
+pub fn is_overdrive_disabled_error(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| cause.to_string() == OVERDRIVE_DISABLED_ERROR)
+}
 
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -49,12 +69,69 @@ impl FromStr for FanControlMode {
     }
 }
 
+/// What the fan speed is controlled by.
+/// The fan curve is always stored as a ratio of the maximum speed, and converted to RPM with the fan's range.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FanSpeedUnit {
+    /// PWM duty cycle percentage
+    #[default]
+    Pwm,
+    /// Target RPM, written to `fan[N]_target`
+    Rpm,
+}
+
+impl FromStr for FanSpeedUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pwm" => Ok(Self::Pwm),
+            "rpm" => Ok(Self::Rpm),
+            _ => Err("unknown fan speed unit".to_string()),
+        }
+    }
+}
+
+/// What the fan curve maps to fan speeds
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FanCurveInput {
+    /// Temperature of the selected sensors in °C
+    #[default]
+    Temperature,
+    /// Power draw in watts, which follows load changes immediately on cards whose temperature sensor lags behind
+    Power,
+    /// `(1 - power_weight) * temperature + power_weight * power`, with the power as a percentage of the power cap,
+    /// so that the curve stays on the same scale as a temperature curve
+    Composite { power_weight: f32 },
+}
+
+impl FanCurveInput {
+    pub fn is_temperature(&self) -> bool {
+        *self == Self::Temperature
+    }
+
+    /// Unit of the curve points
+    pub fn unit(self) -> &'static str {
+        match self {
+            Self::Temperature | Self::Composite { .. } => "°C",
+            Self::Power => "W",
+        }
+    }
+}
+
 pub type FanCurveMap = BTreeMap<i32, f32>;
 
 pub fn default_fan_curve() -> FanCurveMap {
     [(40, 0.2), (50, 0.35), (60, 0.5), (70, 0.75), (80, 1.0)].into()
 }
 
+/// Curve that prioritizes low noise over temperatures, used by the idle optimization preset
+pub fn quiet_fan_curve() -> FanCurveMap {
+    [(50, 0.15), (60, 0.25), (70, 0.4), (80, 0.6), (90, 1.0)].into()
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Pong;
 
@@ -65,19 +142,30 @@ pub struct SystemInfo {
     pub profile: String,
     pub kernel_version: String,
     pub amdgpu_overdrive_enabled: Option<bool>,
+    /// The overdrive setting for the next boot differs from the running one
+    #[serde(default)]
+    pub amdgpu_overdrive_reboot_required: bool,
+    /// Not reported by daemons older than the protocol versioning
+    #[serde(default)]
+    pub protocol_version: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DeviceListEntry {
     pub id: String,
     pub name: Option<String>,
+    /// Custom user-defined label
+    #[serde(default)]
+    pub label: Option<String>,
 }
 
 impl fmt::Display for DeviceListEntry {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match &self.name {
-            Some(name) => name.fmt(f),
-            None => self.id.fmt(f),
+        match (&self.label, &self.name) {
+            (Some(label), Some(name)) => write!(f, "{label} ({name})"),
+            (Some(label), None) => label.fmt(f),
+            (None, Some(name)) => name.fmt(f),
+            (None, None) => self.id.fmt(f),
         }
     }
 }
@@ -132,6 +220,175 @@ pub struct ClocksInfo {
     pub max_mclk: Option<i32>,
     pub max_voltage: Option<i32>,
     pub table: Option<ClocksTable>,
+    /// Not reported by older daemons
+    #[serde(default)]
+    pub limits: ClocksLimits,
+    /// Clocks besides the GPU and VRAM clock that the GPU allows overclocking
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_clocks: BTreeMap<ExtraClock, ExtraClockRange>,
+}
+
+/// Clocks with their own `OD_<name>` section in `pp_od_clk_voltage`, which only some AMD GPUs provide
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtraClock {
+    /// Data fabric clock (FCLK), which the memory controller runs on
+    Fabric,
+    /// SoC clock (SOCCLK)
+    Soc,
+}
+
+impl ExtraClock {
+    pub const ALL: [ExtraClock; 2] = [ExtraClock::Fabric, ExtraClock::Soc];
+
+    /// Name of the clock in `pp_od_clk_voltage`, as in the `OD_FCLK` header and the `FCLK` range
+    pub fn od_name(self) -> &'static str {
+        match self {
+            ExtraClock::Fabric => "FCLK",
+            ExtraClock::Soc => "SOCCLK",
+        }
+    }
+}
+
+impl fmt::Display for ExtraClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.od_name())
+    }
+}
+
+/// Current min and max level of an extra clock in MHz
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtraClockRange {
+    pub min: i32,
+    pub max: i32,
+    /// Allowed range of both levels, from `OD_RANGE`
+    pub range: Option<(i32, i32)>,
+}
+
+/// Allowed ranges of the clocks and voltage settings, read from the `OD_RANGE` section of `pp_od_clk_voltage`.
+/// Settings without a range reported by the GPU are not limited.
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct ClocksLimits {
+    pub min_core_clock: Option<(i32, i32)>,
+    pub max_core_clock: Option<(i32, i32)>,
+    pub min_memory_clock: Option<(i32, i32)>,
+    pub max_memory_clock: Option<(i32, i32)>,
+    pub min_voltage: Option<(i32, i32)>,
+    pub max_voltage: Option<(i32, i32)>,
+    pub voltage_offset: Option<(i32, i32)>,
+    pub core_clock_offset: Option<(i32, i32)>,
+    /// Ranges of the GPU curve points, by index
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub curve_points: Vec<CurvePointLimits>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurvePointLimits {
+    pub clockspeed: Option<(i32, i32)>,
+    pub voltage: Option<(i32, i32)>,
+}
+
+impl ClocksLimits {
+    /// Checks that the values set by a command are within the allowed ranges
+    pub fn check(&self, command: &SetClocksCommand) -> anyhow::Result<()> {
+        match *command {
+            SetClocksCommand::MaxCoreClock(value) => {
+                check_range("max core clock", value, self.max_core_clock)
+            }
+            SetClocksCommand::MaxMemoryClock(value) => {
+                check_range("max memory clock", value, self.max_memory_clock)
+            }
+            SetClocksCommand::MaxVoltage(value) => {
+                check_range("max voltage", value, self.max_voltage)
+            }
+            SetClocksCommand::MinCoreClock(value) => {
+                check_range("min core clock", value, self.min_core_clock)
+            }
+            SetClocksCommand::MinMemoryClock(value) => {
+                check_range("min memory clock", value, self.min_memory_clock)
+            }
+            SetClocksCommand::MinVoltage(value) => {
+                check_range("min voltage", value, self.min_voltage)
+            }
+            SetClocksCommand::VoltageOffset(value) => {
+                check_range("voltage offset", value, self.voltage_offset)
+            }
+            SetClocksCommand::CoreClockOffset(value) => {
+                check_range("core clock offset", value, self.core_clock_offset)
+            }
+            SetClocksCommand::GpuCurvePoint {
+                index,
+                clockspeed,
+                voltage,
+            } => {
+                let point = self.curve_points.get(index).copied().unwrap_or_default();
+                check_range(
+                    &format!("curve point {index} clockspeed"),
+                    clockspeed,
+                    point.clockspeed,
+                )?;
+                check_range(
+                    &format!("curve point {index} voltage"),
+                    voltage,
+                    point.voltage,
+                )
+            }
+            // Memory states use the same ranges as the max values
+            SetClocksCommand::MemoryState {
+                index,
+                clockspeed,
+                voltage,
+            } => {
+                check_range(
+                    &format!("memory state {index} clockspeed"),
+                    clockspeed,
+                    self.max_memory_clock,
+                )?;
+                check_range(
+                    &format!("memory state {index} voltage"),
+                    voltage,
+                    self.max_voltage,
+                )
+            }
+            // The ranges of extra clocks are part of `ClocksInfo`
+            SetClocksCommand::BoostCoreClock(_)
+            | SetClocksCommand::MinExtraClock(..)
+            | SetClocksCommand::MaxExtraClock(..)
+            | SetClocksCommand::Reset => Ok(()),
+        }
+    }
+}
+
+impl ClocksInfo {
+    /// Checks that the values set by a command are within the allowed ranges, including those of the extra clocks
+    pub fn check(&self, command: &SetClocksCommand) -> anyhow::Result<()> {
+        match *command {
+            SetClocksCommand::MinExtraClock(clock, value)
+            | SetClocksCommand::MaxExtraClock(clock, value) => {
+                let Some(info) = self.extra_clocks.get(&clock) else {
+                    return Err(anyhow::anyhow!(
+                        "The {clock} clock can't be set on this GPU"
+                    ));
+                };
+                check_range(&format!("{clock} clock"), value, info.range)
+            }
+            _ => self.limits.check(command),
+        }
+    }
+}
+
+fn check_range(name: &str, value: i32, range: Option<(i32, i32)>) -> anyhow::Result<()> {
+    if let Some((min, max)) = range {
+        if !(min..=max).contains(&value) {
+            return Err(anyhow::anyhow!(
+                "Value {value} for {name} is outside of the allowed range {min}-{max}"
+            ));
+        }
+    }
+    Ok(())
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -139,6 +396,8 @@ pub struct ClocksInfo {
 pub enum ClocksTable {
     Amd(AmdClocksTableGen),
     Nvidia(NvidiaClocksTable),
+    Intel(IntelClocksTable),
+    AmdOffsets(AmdOffsetsTable),
 }
 
 #[skip_serializing_none]
@@ -155,6 +414,52 @@ pub struct NvidiaClockInfo {
     pub offset_range: (i32, i32),
 }
 
+/// GPU frequency limits of Intel GPUs, in MHz
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct IntelClocksTable {
+    /// Lowest and highest frequency supported by the hardware (RPn and RP0)
+    pub gt_freq_range: Option<(i32, i32)>,
+    pub gt_freq_min: Option<i32>,
+    pub gt_freq_max: Option<i32>,
+    /// Frequency used when the driver boosts the GPU, not available with the xe driver
+    pub gt_freq_boost: Option<i32>,
+}
+
+/// Clocks of AMD GPUs that are overclocked with offsets instead of min/max clocks (RDNA4).
+/// Read from the `OD_SCLK_OFFSET` format of `pp_od_clk_voltage`.
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct AmdOffsetsTable {
+    /// GPU clock offset in MHz
+    pub sclk_offset: Option<ClockOffset>,
+    /// GPU voltage offset in mV
+    pub voltage_offset: Option<ClockOffset>,
+    /// Current min and max VRAM clock, as memory still uses absolute clocks
+    pub mclk: Option<(i32, i32)>,
+    pub mclk_range: Option<(i32, i32)>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockOffset {
+    pub offset: i32,
+    pub range: Option<(i32, i32)>,
+}
+
+impl From<AmdOffsetsTable> for ClocksInfo {
+    fn from(table: AmdOffsetsTable) -> Self {
+        Self {
+            max_sclk: None,
+            max_mclk: table.mclk.map(|(_, max)| max),
+            max_voltage: None,
+            limits: ClocksLimits::from(&table),
+            table: Some(ClocksTable::AmdOffsets(table)),
+            extra_clocks: BTreeMap::new(),
+        }
+    }
+}
+
 impl From<AmdClocksTableGen> for ClocksInfo {
     fn from(table: AmdClocksTableGen) -> Self {
         let max_sclk = table.get_max_sclk();
@@ -164,8 +469,67 @@ impl From<AmdClocksTableGen> for ClocksInfo {
             max_sclk,
             max_mclk,
             max_voltage,
+            limits: ClocksLimits::from(&table),
             table: Some(ClocksTable::Amd(table)),
+            extra_clocks: BTreeMap::new(),
+        }
+    }
+}
+
+impl From<&AmdOffsetsTable> for ClocksLimits {
+    fn from(table: &AmdOffsetsTable) -> Self {
+        Self {
+            min_memory_clock: table.mclk_range,
+            max_memory_clock: table.mclk_range,
+            voltage_offset: table.voltage_offset.and_then(|offset| offset.range),
+            core_clock_offset: table.sclk_offset.and_then(|offset| offset.range),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&AmdClocksTableGen> for ClocksLimits {
+    fn from(table: &AmdClocksTableGen) -> Self {
+        let mut limits = Self {
+            min_core_clock: table
+                .get_min_sclk_range()
+                .and_then(|range| range.into_full()),
+            max_core_clock: table
+                .get_max_sclk_range()
+                .and_then(|range| range.into_full()),
+            min_memory_clock: table
+                .get_min_mclk_range()
+                .and_then(|range| range.into_full()),
+            max_memory_clock: table
+                .get_max_mclk_range()
+                .and_then(|range| range.into_full()),
+            min_voltage: table
+                .get_min_voltage_range()
+                .and_then(|range| range.into_full()),
+            max_voltage: table
+                .get_max_voltage_range()
+                .and_then(|range| range.into_full()),
+            ..Default::default()
+        };
+
+        if let AmdClocksTableGen::Vega20(table) = table {
+            let od_range = &table.od_range;
+            limits.voltage_offset = od_range.voltage_offset.and_then(|range| range.into_full());
+            limits.curve_points = (0..table.vddc_curve.len())
+                .map(|index| CurvePointLimits {
+                    clockspeed: od_range
+                        .curve_sclk_points
+                        .get(index)
+                        .and_then(|range| range.into_full()),
+                    voltage: od_range
+                        .curve_voltage_points
+                        .get(index)
+                        .and_then(|range| range.into_full()),
+                })
+                .collect();
         }
+
+        limits
     }
 }
 
@@ -177,6 +541,27 @@ pub struct LinkInfo {
     pub max_speed: Option<String>,
 }
 
+impl LinkInfo {
+    pub fn current_generation(&self) -> Option<u8> {
+        self.current_speed.as_deref().and_then(pcie_generation)
+    }
+
+    pub fn max_generation(&self) -> Option<u8> {
+        self.max_speed.as_deref().and_then(pcie_generation)
+    }
+}
+
+/// Gets the PCIe generation from a transfer rate such as `8.0 GT/s PCIe`
+pub fn pcie_generation(link_speed: &str) -> Option<u8> {
+    let (rate, _) = link_speed.split_once("GT/s")?;
+    let rate: f64 = rate.trim().parse().ok()?;
+    // Every generation doubles the transfer rate, except for the jump from 5 to 8 GT/s
+    [2.5, 5.0, 8.0, 16.0, 32.0, 64.0]
+        .iter()
+        .position(|gen_rate| (rate - gen_rate).abs() < 0.1)
+        .map(|index| index as u8 + 1)
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VulkanInfo {
     pub device_name: String,
@@ -213,10 +598,314 @@ pub struct DeviceStats {
     pub temps: HashMap<String, Temperature>,
     pub busy_percent: Option<u8>,
     pub performance_level: Option<PerformanceLevel>,
+    /// Index of the currently active core clock DPM level (the `*` entry in `pp_dpm_sclk`)
     pub core_power_state: Option<usize>,
+    /// Index of the currently active memory clock DPM level (the `*` entry in `pp_dpm_mclk`)
     pub memory_power_state: Option<usize>,
+    /// Index of the currently active PCIe DPM level (the `*` entry in `pp_dpm_pcie`)
     pub pcie_power_state: Option<usize>,
+    /// Current state of the PCIe link, which can change with power management
+    #[serde(default)]
+    pub link_info: Option<LinkInfo>,
     pub throttle_info: Option<BTreeMap<String, Vec<String>>>,
+    /// Kinds of throttling that are currently active
+    #[serde(default)]
+    pub throttle_status: Option<ThrottleStatus>,
+    /// How often each kind of throttling started since the daemon was started
+    #[serde(default)]
+    pub throttle_events: Option<ThrottleEvents>,
+    #[serde(default)]
+    pub benchmark_lock: Option<BenchmarkLockStats>,
+    /// Whether the low latency mode overrides the performance level and power states
+    #[serde(default)]
+    pub low_latency: bool,
+    /// Moving averages of noisy readings, present when smoothing is enabled in the daemon config
+    #[serde(default)]
+    pub smoothed: Option<SmoothedStats>,
+    /// Alerts whose temperature threshold is currently exceeded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<ActiveAlert>,
+    /// Busy percentage of each engine (e.g. `gfx`, `compute`, `enc`, `dec`) since the previous stats request,
+    /// summed over the processes using the GPU. Empty on the first request, or when the driver doesn't report engine usage in fdinfo.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub engine_usage: BTreeMap<String, f64>,
+    /// Present when the GPU supports runtime power management. While it is suspended,
+    /// the other stats are not read, as that would wake it up.
+    #[serde(default)]
+    pub runtime_pm: Option<RuntimePmStats>,
+}
+
+/// Runtime power management of the PCI device, which lets the dGPU of hybrid laptops power off while it's unused
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RuntimePmStats {
+    /// Contents of `power/runtime_status`, e.g. `active` or `suspended`
+    pub status: String,
+    /// Whether the GPU is allowed to suspend (`power/control` is `auto` rather than `on`)
+    pub auto: bool,
+    /// Total time spent suspended since boot, in milliseconds
+    pub suspended_time_ms: Option<u64>,
+    /// Total time spent active since boot, in milliseconds
+    pub active_time_ms: Option<u64>,
+    /// Names of the processes that have the GPU open, which keep it from suspending
+    #[serde(default)]
+    pub processes: Vec<String>,
+    #[serde(default)]
+    pub last_wake: Option<RuntimePmWake>,
+}
+
+impl RuntimePmStats {
+    pub fn is_suspended(&self) -> bool {
+        self.status == "suspended"
+    }
+}
+
+/// When the GPU last woke up from runtime suspend, and the processes that had it open right after
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RuntimePmWake {
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    /// Empty when something only touched the GPU briefly, such as `lspci` or reading a sensor
+    pub processes: Vec<String>,
+}
+
+/// Sensor of alerts on fans that don't spin. Its value is the speed in percent that a fan is driven at
+/// while it reports no RPM, so the threshold is the fan speed above which a stopped fan counts as failed.
+pub const FAN_STALL_SENSOR: &str = "fan_stall";
+
+/// Runs actions when a temperature stays above a threshold for a while
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Alert {
+    /// Temperature sensor to watch, e.g. `junction` or `edge`, or [`FAN_STALL_SENSOR`]
+    pub sensor: String,
+    /// Temperature in °C, or the fan speed in percent for [`FAN_STALL_SENSOR`]
+    pub threshold: f32,
+    /// Seconds the temperature has to stay above the threshold before the alert triggers
+    #[serde(default)]
+    pub duration: u64,
+    pub actions: Vec<AlertAction>,
+}
+
+impl Alert {
+    /// Shell commands of the alert's [`AlertAction::Command`] actions
+    pub fn commands(&self) -> impl Iterator<Item = &str> {
+        self.actions.iter().filter_map(|action| match action {
+            AlertAction::Command { command } => Some(command.as_str()),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertAction {
+    /// Shows a desktop notification in the GUI
+    Notify,
+    /// Runs a shell command as root when the alert triggers and clears,
+    /// with details about the alert in `LACT_ALERT_*` environment variables.
+    /// Can only be added by editing the config file, the API only accepts commands that are already in it
+    Command { command: String },
+    /// Runs the fans at full speed until the temperature goes down
+    MaxFans,
+    /// Lowers the power cap to the given amount of watts until the temperature goes down
+    PowerCap { watts: f64 },
+}
+
+impl fmt::Display for AlertAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Notify => write!(f, "notify"),
+            Self::Command { command } => write!(f, "run `{command}`"),
+            Self::MaxFans => write!(f, "max fans"),
+            Self::PowerCap { watts } => write!(f, "power cap {watts}W"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ActiveAlert {
+    pub sensor: String,
+    pub threshold: f32,
+    /// Current value of the sensor, which is the fan speed in percent for [`FAN_STALL_SENSOR`]
+    pub temperature: f32,
+    /// Whether a desktop notification should be shown
+    pub notify: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThrottleStatus {
+    /// Limited by the power cap or power delivery
+    pub power: bool,
+    pub thermal: bool,
+    /// Limited by the current the voltage regulators can supply
+    pub current: bool,
+    /// Any other reason, such as a clock limit of the firmware
+    pub other: bool,
+}
+
+impl ThrottleStatus {
+    pub fn is_active(&self) -> bool {
+        self.power || self.thermal || self.current || self.other
+    }
+
+    /// Descriptions of the active kinds of throttling, e.g. `power limit`
+    pub fn active_kinds(&self) -> Vec<&'static str> {
+        [
+            (self.power, "power limit"),
+            (self.thermal, "thermal"),
+            (self.current, "current limit"),
+            (self.other, "other"),
+        ]
+        .into_iter()
+        .filter_map(|(active, name)| active.then_some(name))
+        .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThrottleEvents {
+    pub power: u64,
+    pub thermal: u64,
+    pub current: u64,
+    pub other: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct SmoothedStats {
+    /// Amount of samples the values were averaged over
+    pub window: usize,
+    pub gpu_clockspeed: Option<f64>,
+    pub vram_clockspeed: Option<f64>,
+    pub power_average: Option<f64>,
+    pub power_current: Option<f64>,
+}
+
+/// Clocks the GPU is held at while the benchmark lock is active
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BenchmarkLockStats {
+    pub core_clock: Option<u64>,
+    pub memory_clock: Option<u64>,
+}
+
+/// Settings of the undervolting assistant, which lowers the voltage offset step by step while a stress test runs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UndervoltOptions {
+    /// Shell command that puts load on the GPU. It is stopped at the end of every step.
+    pub stress_command: String,
+    /// How much the offset is lowered per step, in mV
+    pub step: i32,
+    /// Lowest offset to try, in mV
+    pub min_offset: i32,
+    /// How long the stress command runs at every offset, in seconds
+    pub step_duration: u64,
+}
+
+impl Default for UndervoltOptions {
+    fn default() -> Self {
+        Self {
+            stress_command: String::new(),
+            step: 10,
+            min_offset: -200,
+            step_duration: 120,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum UndervoltState {
+    #[default]
+    Idle,
+    /// The stress test is running at the given offset
+    Running {
+        offset: i32,
+    },
+    /// The lowest stable offset was found and applied.
+    /// `None` means that no offset below the starting one was stable.
+    Finished {
+        stable_offset: Option<i32>,
+    },
+    Cancelled,
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct UndervoltStatus {
+    pub state: UndervoltState,
+    /// Lowest offset that passed the stress test so far
+    pub stable_offset: Option<i32>,
+    /// Results of the individual steps, oldest first
+    pub log: Vec<String>,
+}
+
+/// Settings of a comparison between two profiles. The settings of each profile are applied in turn
+/// and the stats are recorded while the same workload keeps running.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProfileComparisonOptions {
+    /// Profiles to compare, `None` is the default profile
+    pub profiles: [Option<String>; 2],
+    /// How long the stats are recorded with each profile, in seconds
+    pub duration: u64,
+    /// How long to wait after applying a profile before recording, in seconds, so that temperatures can settle
+    pub settle_time: u64,
+}
+
+impl Default for ProfileComparisonOptions {
+    fn default() -> Self {
+        Self {
+            profiles: [None, None],
+            duration: 120,
+            settle_time: 30,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ProfileComparisonState {
+    #[default]
+    Idle,
+    /// The settings of the profile at the given index of the options are applied
+    Running {
+        profile: usize,
+        /// Whether the stats are being recorded, as opposed to waiting for them to settle
+        recording: bool,
+    },
+    Finished,
+    Cancelled,
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ProfileComparisonStatus {
+    pub state: ProfileComparisonState,
+    /// Profiles being compared, `None` is the default profile
+    #[serde(default)]
+    pub profiles: [Option<String>; 2],
+    /// Averages of every profile that was recorded so far, in the order of the options
+    pub results: Vec<ProfileStatsSummary>,
+}
+
+/// Averages of the stats recorded with a profile
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ProfileStatsSummary {
+    pub profile: Option<String>,
+    pub samples: usize,
+    /// In watts
+    pub power: Option<f64>,
+    /// In °C, by sensor
+    #[serde(default)]
+    pub temperatures: BTreeMap<String, f64>,
+    /// In MHz
+    pub gpu_clockspeed: Option<f64>,
+    pub vram_clockspeed: Option<f64>,
+    /// In RPM
+    pub fan_speed: Option<f64>,
+    pub busy_percent: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -231,6 +920,22 @@ pub struct FanStats {
     pub speed_min: Option<u32>,
     pub spindown_delay_ms: Option<u64>,
     pub change_threshold: Option<u64>,
+    #[serde(default)]
+    pub max_ramp_rate: Option<u64>,
+    /// Temperature sensors that the curve follows
+    #[serde(default)]
+    pub temperature_sensors: Vec<String>,
+    /// Whether the fan appears to be stalled (high PWM with no measured RPM).
+    /// `None` when it cannot be determined.
+    #[serde(default)]
+    pub stall_detected: Option<bool>,
+    /// Individual fan channels, only reported for GPUs with more than one fan
+    #[serde(default)]
+    pub fans: Vec<FanChannelStats>,
+    #[serde(default)]
+    pub speed_unit: FanSpeedUnit,
+    #[serde(default)]
+    pub curve_input: FanCurveInput,
     // RDNA3+ params
     #[serde(default)]
     pub pmfw_info: PmfwInfo,
@@ -243,6 +948,9 @@ pub struct PmfwInfo {
     pub acoustic_target: Option<FanInfo>,
     pub target_temp: Option<FanInfo>,
     pub minimum_pwm: Option<FanInfo>,
+    pub zero_rpm_enable: Option<bool>,
+    /// Temperature below which the fans are stopped when zero RPM is enabled
+    pub zero_rpm_temperature: Option<FanInfo>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
@@ -262,6 +970,16 @@ pub struct VoltageStats {
 pub struct VramStats {
     pub total: Option<u64>,
     pub used: Option<u64>,
+    /// VRAM that the CPU can access directly, which is only part of it without resizable BAR
+    #[serde(default)]
+    pub visible_total: Option<u64>,
+    #[serde(default)]
+    pub visible_used: Option<u64>,
+    /// System memory that is mapped for use by the GPU, it gets used once the VRAM runs out
+    #[serde(default)]
+    pub gtt_total: Option<u64>,
+    #[serde(default)]
+    pub gtt_used: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
@@ -272,6 +990,12 @@ pub struct PowerStats {
     pub cap_max: Option<f64>,
     pub cap_min: Option<f64>,
     pub cap_default: Option<f64>,
+    /// Temperature that the power cap is adjusted to stay under
+    #[serde(default)]
+    pub thermal_target: Option<f32>,
+    /// Power cap of a running application that overrides the configured one
+    #[serde(default)]
+    pub app_power_cap: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -294,6 +1018,41 @@ pub struct PowerState {
     pub index: Option<u8>,
 }
 
+/// Memory power state of GPUs that set clocks and voltages per state (Polaris and Vega)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryState {
+    pub index: usize,
+    /// Clockspeed in MHz
+    pub clockspeed: i32,
+    /// Memory voltage (MVDD) in mV
+    pub voltage: i32,
+}
+
+/// GPU usage of a process, read from the DRM fdinfo of its open GPU file descriptors
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GpuProcess {
+    pub pid: u32,
+    pub name: String,
+    /// Percentage of time each engine (e.g. `gfx`, `compute`, `enc`) was busy with the process since the previous request.
+    /// Empty on the first request, as usage can only be calculated between two requests.
+    pub engine_usage: BTreeMap<String, f64>,
+    /// VRAM used by the process in bytes
+    pub vram: Option<u64>,
+    /// GTT (system memory mapped for the GPU) used by the process in bytes
+    pub gtt: Option<u64>,
+}
+
+/// Temperatures (in °C) and fan speeds (in RPM) of a hwmon chip that doesn't belong to a GPU,
+/// such as the CPU, motherboard or NVMe sensors
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SensorChip {
+    pub name: String,
+    /// Name of the hwmon directory, e.g. `hwmon3`, to tell apart chips with the same name
+    pub hwmon: String,
+    pub temperatures: BTreeMap<String, f32>,
+    pub fans: BTreeMap<String, u32>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InitramfsType {
     Debian,
@@ -308,12 +1067,35 @@ pub struct PmfwOptions {
     pub acoustic_target: Option<u32>,
     pub minimum_pwm: Option<u32>,
     pub target_temperature: Option<u32>,
+    pub zero_rpm: Option<bool>,
+    pub zero_rpm_threshold: Option<u32>,
 }
 
 impl PmfwOptions {
     pub fn is_empty(&self) -> bool {
         *self == Self::default()
     }
+
+    /// Takes over the settings that are set in `other`, keeping the rest
+    pub fn merge(&mut self, other: Self) {
+        self.acoustic_limit = other.acoustic_limit.or(self.acoustic_limit);
+        self.acoustic_target = other.acoustic_target.or(self.acoustic_target);
+        self.minimum_pwm = other.minimum_pwm.or(self.minimum_pwm);
+        self.target_temperature = other.target_temperature.or(self.target_temperature);
+        self.zero_rpm = other.zero_rpm.or(self.zero_rpm);
+        self.zero_rpm_threshold = other.zero_rpm_threshold.or(self.zero_rpm_threshold);
+    }
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct FanChannelStats {
+    /// Number of the fan channel starting at 1, e.g. 2 for `pwm2` on AMD or the second fan on Nvidia
+    pub channel: u32,
+    pub pwm_current: Option<u8>,
+    pub speed_current: Option<u32>,
+    /// Curve used only by this fan, when it does not follow the shared one
+    pub curve: Option<FanCurveMap>,
 }
 
 #[skip_serializing_none]
@@ -328,10 +1110,245 @@ pub struct FanOptions<'a> {
     pub pmfw: PmfwOptions,
     pub spindown_delay_ms: Option<u64>,
     pub change_threshold: Option<u64>,
+    /// Maximum fan speed change in percent per second, 0 means unlimited
+    pub max_ramp_rate: Option<u64>,
+    /// Temperature sensors that the curve follows (the hottest one is used), e.g. `edge`, `junction` or `mem`
+    #[serde(default)]
+    pub temperature_sensors: Option<Vec<String>>,
+    /// Independent curves for individual fan channels, which replace the existing ones for the given channels.
+    /// An empty map makes all fans follow `curve` again.
+    #[serde(default)]
+    pub fan_curves: Option<BTreeMap<u32, FanCurveMap>>,
+    /// Whether the fan speed is set as a PWM percentage or an RPM target
+    #[serde(default)]
+    pub speed_unit: Option<FanSpeedUnit>,
+    /// What the curve maps to fan speeds, the temperature by default
+    #[serde(default)]
+    pub curve_input: Option<FanCurveInput>,
+}
+
+/// Controls available on a given GPU, detected from what the driver actually exposes
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct DeviceCapabilities {
+    pub overdrive: bool,
+    pub clocks_control: Option<ClocksControlKind>,
+    pub voltage_offset: bool,
+    pub pmfw_fan_control: bool,
+    pub pwm_fan_control: bool,
+    /// Fans can be set to a target RPM through `fan[N]_target`
+    #[serde(default)]
+    pub rpm_fan_control: bool,
+    pub power_cap_range: Option<(f64, f64)>,
+    pub temperature_sensors: Vec<String>,
+    pub power_profile_modes: bool,
+    pub pcie_power_states: bool,
+    pub ecc: bool,
+    /// Limits that can be set on AMD APUs, empty on dedicated GPUs
+    #[serde(default)]
+    pub apu_limits: Vec<ApuLimitKind>,
+    /// The GPU can be held at its highest clocks without deep sleep for low latency
+    #[serde(default)]
+    pub low_latency_mode: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClocksControlKind {
+    /// Clocks and voltages are set per power state (pre-Vega20)
+    PowerStates,
+    /// Min/max clock ranges (Vega20 and newer, Intel)
+    Range,
+    /// Clock offsets relative to the stock boost clock (Nvidia, RDNA4)
+    Offset,
+}
+
+/// Power and temperature limits of AMD APUs, which are shared between the CPU and the integrated GPU
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ApuLimitKind {
+    /// Sustained power limit (STAPM)
+    Stapm,
+    /// Power limit for short boosts (fast PPT)
+    Fast,
+    /// Power limit for longer boosts (slow PPT)
+    Slow,
+    /// Temperature limit (Tctl)
+    Temperature,
+}
+
+impl ApuLimitKind {
+    pub fn unit(self) -> &'static str {
+        match self {
+            ApuLimitKind::Stapm | ApuLimitKind::Fast | ApuLimitKind::Slow => "W",
+            ApuLimitKind::Temperature => "°C",
+        }
+    }
+}
+
+/// Errors that occured while resetting settings, grouped by GPU id.
+/// GPUs that were reset successfully are not included.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ResetResult {
+    pub errors: BTreeMap<String, Vec<String>>,
+}
+
+impl ResetResult {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl fmt::Display for ResetResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (id, errors) in &self.errors {
+            writeln!(f, "{id}:")?;
+            for error in errors {
+                writeln!(f, "  {error}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How many applied changes of a GPU can be undone and redone
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConfigHistoryInfo {
+    pub undo_steps: usize,
+    pub redo_steps: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ImportStateResult {
+    /// GPUs from the imported state that are not present on this system, their settings were not imported
+    pub skipped_gpus: Vec<String>,
+    /// GPUs from the imported state that were matched to a GPU of the same model with a different id (e.g. in another PCI slot),
+    /// from the imported id to the one on this system
+    #[serde(default)]
+    pub matched_gpus: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EventLogEntry {
+    pub timestamp: String,
+    pub connection_id: u64,
+    pub event: ConnectionEvent,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ConnectionEvent {
+    Connected,
+    Request { command: String },
+    Ok,
+    Error(String),
+    Disconnected { error: Option<String> },
+}
+
+/// Change of the available GPUs, e.g. when an eGPU is plugged in or unplugged
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum DeviceEvent {
+    Added(String),
+    Removed(String),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct ProfilesInfo {
     pub profiles: Vec<String>,
     pub current_profile: Option<String>,
+    /// Whether profiles are switched automatically based on their rules
+    #[serde(default)]
+    pub auto_switch: bool,
+    /// Profile that is switched to when no rule or schedule matches anymore
+    #[serde(default)]
+    pub fallback_profile: Option<String>,
+    #[serde(default)]
+    pub rules: BTreeMap<String, ProfileRule>,
+    #[serde(default)]
+    pub schedules: BTreeMap<String, ProfileSchedule>,
+}
+
+/// Condition for activating a profile automatically
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProfileRule {
+    /// Name of the process, or of its executable
+    pub process: String,
+    /// Text that the process arguments have to contain
+    pub args: Option<String>,
+}
+
+/// Time of day during which a profile is activated automatically.
+/// Process rules take priority over schedules.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSchedule {
+    /// Start of the time window as `HH:MM`
+    pub start: String,
+    /// End of the time window as `HH:MM`. When it's before the start, the window ends on the next day
+    pub end: String,
+    /// Days of the week the window starts on, from 1 (Monday) to 7 (Sunday). Every day when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<u8>,
+}
+
+/// Fan speed that a proposed curve would have set at a point of the stats history
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct FanCurvePreviewPoint {
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    /// Temperature the curve followed
+    pub temperature: f32,
+    /// Fan speed between 0 and 1
+    pub speed: f32,
+}
+
+/// Energy used by a GPU, integrated by the daemon from its power usage
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct EnergyStats {
+    /// Used energy in Wh for every local date (`YYYY-MM-DD`) the GPU was tracked on
+    pub days: BTreeMap<String, f64>,
+    /// Configured electricity price, used for estimating costs
+    pub price: Option<EnergyPrice>,
+}
+
+impl EnergyStats {
+    /// Total energy in Wh used between the given dates, both included
+    pub fn total_between(&self, from: &str, to: &str) -> f64 {
+        self.days
+            .iter()
+            .filter(|(date, _)| date.as_str() >= from && date.as_str() <= to)
+            .map(|(_, energy)| energy)
+            .sum()
+    }
+
+    /// Estimated cost of the given amount of energy in Wh
+    pub fn cost(&self, energy: f64) -> Option<f64> {
+        self.price
+            .as_ref()
+            .map(|price| energy / 1000.0 * price.per_kwh)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EnergyPrice {
+    /// Price of one kWh
+    pub per_kwh: f64,
+    /// Shown after costs, e.g. `€`
+    #[serde(default)]
+    pub currency: String,
+}
+
+/// Stats sample recorded by the daemon's stats history
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct StatsHistorySample {
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    pub temperatures: BTreeMap<String, f32>,
+    pub power: Option<f32>,
+    pub gpu_clockspeed: Option<f32>,
+    pub vram_clockspeed: Option<f32>,
+    pub fan_speed: Option<f32>,
+    pub busy_percent: Option<f32>,
+    /// Used VRAM in MiB
+    pub vram_used: Option<f32>,
 }
@@ -1,6 +1,9 @@
 use std::fmt;
 
-use crate::FanOptions;
+use crate::{
+    Alert, ApuLimitKind, EnergyPrice, ExtraClock, FanCurveMap, FanOptions, PmfwOptions,
+    ProfileComparisonOptions, ProfileRule, ProfileSchedule, UndervoltOptions,
+};
 use amdgpu_sysfs::gpu_handle::{PerformanceLevel, PowerLevelKind};
 use serde::{Deserialize, Serialize};
 
@@ -8,32 +11,124 @@ use serde::{Deserialize, Serialize};
 #[serde(tag = "command", content = "args", rename_all = "snake_case")]
 pub enum Request<'a> {
     Ping,
+    /// Required as the first request on TCP connections when the daemon has an auth token configured
+    Authenticate {
+        token: String,
+    },
     ListDevices,
     SystemInfo,
     DeviceInfo {
         id: &'a str,
     },
+    SetGpuLabel {
+        id: &'a str,
+        label: Option<String>,
+    },
     DeviceStats {
         id: &'a str,
     },
+    /// Turns the connection into a stream of `DeviceStats` responses sent at the given interval,
+    /// after an initial empty response confirming the subscription
+    SubscribeStats {
+        id: &'a str,
+        interval_ms: u64,
+    },
+    /// Turns the connection into a stream of `DeviceEvent` responses sent when GPUs are added or removed,
+    /// after an initial empty response confirming the subscription
+    SubscribeDeviceEvents,
     DeviceClocksInfo {
         id: &'a str,
     },
     DevicePowerProfileModes {
         id: &'a str,
     },
+    DeviceCapabilities {
+        id: &'a str,
+    },
     SetFanControl(FanOptions<'a>),
+    /// Changes the given PMFW fan settings (RDNA3 and newer), keeping the ones that are not set
+    SetPmfwOptions {
+        id: &'a str,
+        options: PmfwOptions,
+    },
     ResetPmfw {
         id: &'a str,
     },
+    ResetFanControl {
+        id: &'a str,
+    },
+    /// Restores the stock clocks and voltages, keeping the other settings of the GPU
+    ResetClocks {
+        id: &'a str,
+    },
+    /// Restores the default power cap and removes the thermal target, keeping the other settings of the GPU
+    ResetPowerCap {
+        id: &'a str,
+    },
     SetPowerCap {
         id: &'a str,
         cap: Option<f64>,
     },
+    /// Adjust the power cap automatically to keep the GPU at or below the given temperature
+    SetThermalTarget {
+        id: &'a str,
+        temperature: Option<f32>,
+    },
+    /// Use a different power cap while a process with the given name is running, or remove the override when `None`
+    GetAlerts {
+        id: &'a str,
+    },
+    /// Replaces the temperature alerts of the GPU
+    SetAlerts {
+        id: &'a str,
+        alerts: Vec<Alert>,
+    },
+    SetAppPowerCap {
+        id: &'a str,
+        process: String,
+        power_cap: Option<f64>,
+    },
     SetPerformanceLevel {
         id: &'a str,
         performance_level: PerformanceLevel,
     },
+    SetBenchmarkLock {
+        id: &'a str,
+        enabled: bool,
+    },
+    SetLowLatencyMode {
+        id: &'a str,
+        enabled: bool,
+    },
+    /// Lets the GPU power off while unused (`power/control` set to `auto`) or keeps it on, `None` leaves the setting to the system
+    SetRuntimePm {
+        id: &'a str,
+        auto: Option<bool>,
+    },
+    ApplyIdleOptimization {
+        id: &'a str,
+    },
+    StartUndervolt {
+        id: &'a str,
+        options: UndervoltOptions,
+    },
+    GetUndervoltStatus {
+        id: &'a str,
+    },
+    CancelUndervolt {
+        id: &'a str,
+    },
+    /// Records the average stats with the settings of two profiles in turn, without switching the active profile
+    StartProfileComparison {
+        id: &'a str,
+        options: ProfileComparisonOptions,
+    },
+    GetProfileComparisonStatus {
+        id: &'a str,
+    },
+    CancelProfileComparison {
+        id: &'a str,
+    },
     SetClocksValue {
         id: &'a str,
         command: SetClocksCommand,
@@ -42,6 +137,9 @@ pub enum Request<'a> {
         id: &'a str,
         commands: Vec<SetClocksCommand>,
     },
+    /// Activates a power profile mode, or lets the driver choose it when `None`.
+    /// Setting a mode switches to the manual performance level, which goes back to automatic when it's reset
+    /// and no power states are configured.
     SetPowerProfileMode {
         id: &'a str,
         index: Option<u16>,
@@ -56,9 +154,66 @@ pub enum Request<'a> {
         kind: PowerLevelKind,
         states: Vec<u8>,
     },
+    /// Link speeds of the PCIe power states, from the slowest one
+    PciePowerStates {
+        id: &'a str,
+    },
+    /// Keeps the PCIe link at or above the given power state, or lets it use all states when `None`.
+    /// Avoids latency spikes caused by link speed changes, at the cost of higher idle power usage.
+    /// Switches to the manual performance level, which goes back to automatic once no power states are configured.
+    SetMinPcieState {
+        id: &'a str,
+        state: Option<u8>,
+    },
+    /// Keeps the VRAM clock at or above the given power state, or lets it use all states when `None`.
+    /// Commonly fixes flickering with multiple monitors, at the cost of higher idle power usage.
+    /// Switches to the manual performance level like `SetMinPcieState`.
+    SetMinVramState {
+        id: &'a str,
+        state: Option<u8>,
+    },
+    /// Sets a power or temperature limit of an APU, or restores its default when `None`
+    SetApuLimit {
+        id: &'a str,
+        kind: ApuLimitKind,
+        value: Option<f64>,
+    },
+    /// Memory power states with their clocks and voltages, empty when the GPU doesn't use per-state clocks
+    MemoryStates {
+        id: &'a str,
+    },
+    /// Locks the memory to a single power state, or lets it use all states when `None`
+    LockMemoryState {
+        id: &'a str,
+        state: Option<u8>,
+    },
+    /// Restores the settings of the GPU from before the last applied change.
+    /// Needs to be confirmed like other settings changes.
+    UndoLastChange {
+        id: &'a str,
+    },
+    /// Reapplies the last change that was undone
+    RedoLastChange {
+        id: &'a str,
+    },
+    ConfigHistory {
+        id: &'a str,
+    },
+    /// Processes that use the GPU, with their engine usage and memory
+    GetProcesses {
+        id: &'a str,
+    },
+    /// Non-GPU hwmon chips listed in the `sensor_chips` daemon setting
+    GetSensorChips,
     VbiosDump {
         id: &'a str,
     },
+    /// Resets a hung GPU, which interrupts all applications using it.
+    /// Only runs when `confirm` is set, and is only supported on AMD.
+    ResetGpu {
+        id: &'a str,
+        confirm: bool,
+    },
     ListProfiles,
     SetProfile {
         name: Option<String>,
@@ -70,11 +225,126 @@ pub enum Request<'a> {
     DeleteProfile {
         name: String,
     },
+    SetProfileRule {
+        name: String,
+        rule: Option<ProfileRule>,
+    },
+    /// Activates a profile automatically during a time window, or removes the schedule when `None`
+    SetProfileSchedule {
+        name: String,
+        schedule: Option<ProfileSchedule>,
+    },
+    SetAutoSwitchProfiles {
+        enabled: bool,
+    },
+    /// Profile to switch back to when an automatically activated profile stops matching,
+    /// the default settings are used when `None`
+    SetFallbackProfile {
+        name: Option<String>,
+    },
     EnableOverdrive,
     DisableOverdrive,
     GenerateSnapshot,
+    GetEventLog,
+    /// Energy used by the GPU on each day
+    GetEnergyStats {
+        id: &'a str,
+    },
+    /// Sets the electricity price used for estimating energy costs, or removes it when `None`
+    SetEnergyPrice {
+        price: Option<EnergyPrice>,
+    },
+    /// Samples recorded by the stats history, optionally only the ones since the given unix timestamp in milliseconds
+    GetStatsHistory {
+        id: &'a str,
+        since: Option<i64>,
+    },
+    /// Replays a fan curve on the stats history, returning the fan speeds it would have set.
+    /// Uses the GPU's current sensors, spindown delay, change threshold and ramp rate.
+    PreviewFanCurve {
+        id: &'a str,
+        curve: FanCurveMap,
+        since: Option<i64>,
+    },
+    ExportState,
+    ImportState {
+        state: String,
+    },
     ConfirmPendingConfig(ConfirmCommand),
     RestConfig,
+    /// Applies the changes of all the requests to a GPU at once, or none of them if any of the requests fails.
+    /// Only requests that change the settings of the GPU can be batched, see `Request::is_batchable`.
+    /// Returns the confirmation delay like a single change.
+    ApplyBatch {
+        id: &'a str,
+        requests: Vec<Request<'a>>,
+    },
+}
+
+impl Request<'_> {
+    /// Whether the request only reads data. Other requests change settings or the system,
+    /// so they need authorization from clients that don't have full access to the daemon.
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Request::Ping
+                | Request::Authenticate { .. }
+                | Request::ListDevices
+                | Request::SystemInfo
+                | Request::DeviceInfo { .. }
+                | Request::DeviceStats { .. }
+                | Request::SubscribeStats { .. }
+                | Request::SubscribeDeviceEvents
+                | Request::DeviceClocksInfo { .. }
+                | Request::DevicePowerProfileModes { .. }
+                | Request::DeviceCapabilities { .. }
+                | Request::GetAlerts { .. }
+                | Request::GetPowerStates { .. }
+                | Request::PciePowerStates { .. }
+                | Request::MemoryStates { .. }
+                | Request::ConfigHistory { .. }
+                | Request::GetProcesses { .. }
+                | Request::GetSensorChips
+                | Request::ListProfiles
+                | Request::GetEventLog
+                | Request::GetEnergyStats { .. }
+                | Request::GetStatsHistory { .. }
+                | Request::PreviewFanCurve { .. }
+                | Request::GetUndervoltStatus { .. }
+                | Request::GetProfileComparisonStatus { .. }
+        )
+    }
+
+    /// Whether the request only changes the stored settings of a GPU, which can be applied together with others.
+    /// Resets that write to the GPU right away are left out, including clocks commands that reset the clocks.
+    pub fn is_batchable(&self) -> bool {
+        match self {
+            Request::SetClocksValue { command, .. } => *command != SetClocksCommand::Reset,
+            Request::BatchSetClocksValue { commands, .. } => {
+                !commands.contains(&SetClocksCommand::Reset)
+            }
+            _ => matches!(
+                self,
+                Request::SetFanControl(_)
+                    | Request::SetPmfwOptions { .. }
+                    | Request::ResetPowerCap { .. }
+                    | Request::SetPowerCap { .. }
+                    | Request::SetThermalTarget { .. }
+                    | Request::SetAlerts { .. }
+                    | Request::SetAppPowerCap { .. }
+                    | Request::SetPerformanceLevel { .. }
+                    | Request::SetLowLatencyMode { .. }
+                    | Request::SetRuntimePm { .. }
+                    | Request::ApplyIdleOptimization { .. }
+                    | Request::SetPowerProfileMode { .. }
+                    | Request::SetEnabledPowerStates { .. }
+                    | Request::SetMinPcieState { .. }
+                    | Request::SetMinVramState { .. }
+                    | Request::SetApuLimit { .. }
+                    | Request::LockMemoryState { .. }
+            ),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -93,7 +363,25 @@ pub enum SetClocksCommand {
     MinCoreClock(i32),
     MinMemoryClock(i32),
     MinVoltage(i32),
+    /// Frequency the GPU is boosted to on demand (Intel)
+    BoostCoreClock(i32),
     VoltageOffset(i32),
+    /// Offset of the GPU clock in MHz, on GPUs that are overclocked with offsets (RDNA4)
+    CoreClockOffset(i32),
+    MinExtraClock(ExtraClock, i32),
+    MaxExtraClock(ExtraClock, i32),
+    /// Sets a single point of the GPU voltage/frequency curve
+    GpuCurvePoint {
+        index: usize,
+        clockspeed: i32,
+        voltage: i32,
+    },
+    /// Sets the clockspeed and memory voltage of a memory power state (pre-Vega20)
+    MemoryState {
+        index: usize,
+        clockspeed: i32,
+        voltage: i32,
+    },
     Reset,
 }
 
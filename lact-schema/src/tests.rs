@@ -1,4 +1,8 @@
-use crate::{FanControlMode, FanOptions, PmfwOptions, Pong, Request, Response};
+use crate::{
+    pcie_generation, request::SetClocksCommand, AmdOffsetsTable, ClockOffset, ClocksInfo,
+    ClocksLimits, CurvePointLimits, DeviceListEntry, ExtraClock, ExtraClockRange, FanControlMode,
+    FanOptions, PmfwOptions, Pong, Request, Response,
+};
 use anyhow::anyhow;
 use serde_json::json;
 use std::collections::BTreeMap;
@@ -83,6 +87,172 @@ fn set_fan_clocks() {
         pmfw: PmfwOptions::default(),
         spindown_delay_ms: None,
         change_threshold: None,
+        max_ramp_rate: None,
+        temperature_sensors: None,
+        fan_curves: None,
+        speed_unit: None,
+        curve_input: None,
     });
     assert_eq!(expected_request, request);
 }
+
+#[test]
+fn device_list_entry_label() {
+    let mut entry = DeviceListEntry {
+        id: "1002:73BF-1DA2:E438-0000:03:00.0".to_owned(),
+        name: Some("Navi 21".to_owned()),
+        label: None,
+    };
+    assert_eq!(entry.to_string(), "Navi 21");
+
+    entry.label = Some("Render".to_owned());
+    assert_eq!(entry.to_string(), "Render (Navi 21)");
+
+    entry.name = None;
+    assert_eq!(entry.to_string(), "Render");
+}
+
+#[test]
+fn link_speed_generation() {
+    assert_eq!(pcie_generation("2.5 GT/s PCIe"), Some(1));
+    assert_eq!(pcie_generation("8.0 GT/s PCIe"), Some(3));
+    assert_eq!(pcie_generation("16 GT/s PCIe gen 4"), Some(4));
+    assert_eq!(pcie_generation("Unknown"), None);
+}
+
+#[test]
+fn read_only_requests() {
+    assert!(Request::DeviceStats { id: "gpu" }.is_read_only());
+    assert!(Request::ListProfiles.is_read_only());
+    assert!(!Request::SetPowerCap {
+        id: "gpu",
+        cap: Some(150.0)
+    }
+    .is_read_only());
+    assert!(!Request::GenerateSnapshot.is_read_only());
+}
+
+#[test]
+fn batch_request() {
+    let value = r#"{
+        "command": "apply_batch",
+        "args": {
+            "id": "gpu",
+            "requests": [
+                { "command": "set_power_cap", "args": { "id": "gpu", "cap": 150.0 } },
+                { "command": "set_low_latency_mode", "args": { "id": "gpu", "enabled": true } }
+            ]
+        }
+    }"#;
+    let request: Request = serde_json::from_str(value).unwrap();
+    let Request::ApplyBatch { id, requests } = &request else {
+        panic!("not a batch: {request:?}");
+    };
+    assert_eq!(*id, "gpu");
+    assert_eq!(
+        requests[0],
+        Request::SetPowerCap {
+            id: "gpu",
+            cap: Some(150.0)
+        }
+    );
+    assert!(requests.iter().all(Request::is_batchable));
+    assert!(!request.is_batchable());
+    assert!(!request.is_read_only());
+    assert!(!Request::ResetClocks { id: "gpu" }.is_batchable());
+    assert!(Request::SetClocksValue {
+        id: "gpu",
+        command: SetClocksCommand::MaxCoreClock(2000),
+    }
+    .is_batchable());
+    assert!(!Request::SetClocksValue {
+        id: "gpu",
+        command: SetClocksCommand::Reset,
+    }
+    .is_batchable());
+    assert!(!Request::BatchSetClocksValue {
+        id: "gpu",
+        commands: vec![
+            SetClocksCommand::MaxCoreClock(2000),
+            SetClocksCommand::Reset
+        ],
+    }
+    .is_batchable());
+}
+
+#[test]
+fn clocks_limits_from_offsets_table() {
+    let table = AmdOffsetsTable {
+        sclk_offset: Some(ClockOffset {
+            offset: 0,
+            range: Some((-500, 1000)),
+        }),
+        voltage_offset: Some(ClockOffset {
+            offset: -50,
+            range: None,
+        }),
+        mclk: Some((97, 1258)),
+        mclk_range: Some((97, 1500)),
+    };
+    let limits = ClocksLimits::from(&table);
+
+    assert!(limits
+        .check(&SetClocksCommand::CoreClockOffset(1000))
+        .is_ok());
+    assert!(limits
+        .check(&SetClocksCommand::CoreClockOffset(1001))
+        .is_err());
+    assert!(limits
+        .check(&SetClocksCommand::MaxMemoryClock(1500))
+        .is_ok());
+    assert!(limits.check(&SetClocksCommand::MinMemoryClock(50)).is_err());
+    // No range was reported
+    assert!(limits.check(&SetClocksCommand::VoltageOffset(-500)).is_ok());
+    assert!(limits.check(&SetClocksCommand::Reset).is_ok());
+}
+
+#[test]
+fn extra_clocks_limits() {
+    let info = ClocksInfo {
+        extra_clocks: BTreeMap::from([(
+            ExtraClock::Fabric,
+            ExtraClockRange {
+                min: 1200,
+                max: 1800,
+                range: Some((1200, 2100)),
+            },
+        )]),
+        ..Default::default()
+    };
+
+    let max_fclk = |value| SetClocksCommand::MaxExtraClock(ExtraClock::Fabric, value);
+    assert!(info.check(&max_fclk(2100)).is_ok());
+    assert!(info.check(&max_fclk(2200)).is_err());
+    assert!(info
+        .check(&SetClocksCommand::MinExtraClock(ExtraClock::Soc, 800))
+        .is_err());
+    assert!(info.check(&SetClocksCommand::MinMemoryClock(50)).is_ok());
+}
+
+#[test]
+fn clocks_limits_curve_points() {
+    let limits = ClocksLimits {
+        curve_points: vec![CurvePointLimits {
+            clockspeed: Some((800, 2150)),
+            voltage: Some((750, 1200)),
+        }],
+        ..Default::default()
+    };
+
+    let command = |clockspeed, voltage| SetClocksCommand::GpuCurvePoint {
+        index: 0,
+        clockspeed,
+        voltage,
+    };
+    assert!(limits.check(&command(2000, 1100)).is_ok());
+    let err = limits.check(&command(2000, 1250)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Value 1250 for curve point 0 voltage is outside of the allowed range 750-1200"
+    );
+}
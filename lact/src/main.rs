@@ -7,7 +7,9 @@ fn main() -> anyhow::Result<()> {
         .unwrap_or_else(|| Command::Gui(GuiArgs::default()));
 
     match command {
-        Command::Daemon => lact_daemon::run(),
+        Command::Daemon(daemon_args) if daemon_args.apply_once => lact_daemon::apply_once(),
+        Command::Daemon(daemon_args) => lact_daemon::run(&daemon_args),
+        Command::Apply => lact_daemon::apply_once(),
         Command::Gui(gui_args) => run_gui(gui_args),
         Command::Cli(cli_args) => lact_cli::run(cli_args),
     }